@@ -1,13 +1,17 @@
+use std::iter::zip;
+
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 
 use reed_solomon_simd::{
-    engine::{DefaultEngine, Engine, Naive, NoSimd, ShardsRefMut},
+    engine::{self, DefaultEngine, Engine, EngineKind, Naive, NoSimd, ShardsRefMut},
     rate::{
         HighRateDecoder, HighRateEncoder, LowRateDecoder, LowRateEncoder, RateDecoder, RateEncoder,
     },
-    ReedSolomonDecoder, ReedSolomonEncoder,
+    recovery_only::RecoveryOnlyEncoder,
+    small::SmallCodec,
+    CodecParams, ReedSolomonDecoder, ReedSolomonEncoder,
 };
 
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
@@ -16,6 +20,9 @@ use reed_solomon_simd::engine::{Avx2, Ssse3};
 #[cfg(target_arch = "aarch64")]
 use reed_solomon_simd::engine::Neon;
 
+#[cfg(feature = "portable-simd")]
+use reed_solomon_simd::engine::PortableSimd;
+
 // ======================================================================
 // CONST
 
@@ -61,6 +68,7 @@ fn benchmarks_main(c: &mut Criterion) {
         (8192, 1024),
         (8192, 16384),
         (8192, 57344),
+        (10000, 100),
         (10000, 1000),
         (16384, 8192),
         (16385, 16385), // 2^n + 1
@@ -137,6 +145,372 @@ fn benchmarks_main(c: &mut Criterion) {
     group.finish();
 }
 
+// ======================================================================
+// BENCHMARKS - COMBINED
+
+fn benchmarks_combined(c: &mut Criterion) {
+    let mut group = c.benchmark_group("combined");
+    group.sample_size(10);
+
+    let (original_count, recovery_count) = (32768, 32768);
+    let original_bytes = original_count * SHARD_BYTES;
+
+    let original = generate_shards(original_count, SHARD_BYTES, 0);
+    let original_refs: Vec<&[u8]> = original.iter().map(|shard| shard.as_slice()).collect();
+    let mut out = vec![0u8; (original_count + recovery_count) * SHARD_BYTES];
+
+    let id = format!("{}:{}", original_count, recovery_count);
+
+    // Copying originals to the stripe, with and without non-temporal stores.
+
+    group.throughput(Throughput::Bytes(original_bytes as u64));
+
+    group.bench_function(BenchmarkId::new("copy_from_slice", &id), |b| {
+        b.iter(|| {
+            for (original, out) in zip(&original, out.chunks_exact_mut(SHARD_BYTES)) {
+                out.copy_from_slice(black_box(original));
+            }
+        });
+    });
+
+    group.bench_function(BenchmarkId::new("copy_nt", &id), |b| {
+        b.iter(|| {
+            for (original, out) in zip(&original, out.chunks_exact_mut(SHARD_BYTES)) {
+                DefaultEngine::copy_nt(out, black_box(original));
+            }
+        });
+    });
+
+    // Whole combined encode.
+
+    group.throughput(Throughput::Bytes(out.len() as u64));
+
+    let params = CodecParams::new(original_count, recovery_count, SHARD_BYTES);
+    group.bench_function(BenchmarkId::new("encode_combined", &id), |b| {
+        b.iter(|| params.encode_combined(&original_refs, &mut out).unwrap());
+    });
+
+    group.finish();
+}
+
+// ======================================================================
+// BENCHMARKS - DEDUPLICATION
+
+fn benchmarks_dedup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dedup");
+
+    let (original_count, recovery_count) = (1000, 100);
+
+    // 90% of shards are duplicates: 100 unique shards, each repeated 10 times.
+    let unique = generate_shards(original_count / 10, SHARD_BYTES, 0);
+    let original: Vec<_> = (0..original_count)
+        .map(|i| unique[i % unique.len()].clone())
+        .collect();
+
+    group.throughput(Throughput::Bytes(
+        ((original_count + recovery_count) * SHARD_BYTES) as u64,
+    ));
+
+    for dedup in [false, true] {
+        let mut encoder =
+            ReedSolomonEncoder::new(original_count, recovery_count, SHARD_BYTES).unwrap();
+        encoder.set_deduplication(dedup);
+
+        let id = format!(
+            "{}:{} (90% duplicates, dedup {})",
+            original_count,
+            recovery_count,
+            if dedup { "on" } else { "off" }
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("ReedSolomonEncoder", &id),
+            &original,
+            |b, original| {
+                b.iter(|| {
+                    for original in original {
+                        encoder.add_original_shard(original).unwrap();
+                    }
+                    black_box(encoder.encode().unwrap().duplicate_originals().len());
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+// ======================================================================
+// BENCHMARKS - SURPLUS SHARDS
+
+fn benchmarks_surplus(c: &mut Criterion) {
+    let mut group = c.benchmark_group("surplus");
+
+    // (original_count, recovery_count), 10% of original shards lost
+    // and all recovery shards given, i.e. heavy surplus.
+    // Recovery shards beyond those needed are not copied.
+    for (original_count, recovery_count) in [(1000, 1000), (100, 1000), (1000, 200), (1000, 2000)] {
+        let lost = original_count / 10;
+        let original = generate_shards(original_count, SHARD_BYTES, 0);
+        let recovery =
+            reed_solomon_simd::encode(original_count, recovery_count, &original).unwrap();
+
+        let mut decoder =
+            ReedSolomonDecoder::new(original_count, recovery_count, SHARD_BYTES).unwrap();
+
+        group.throughput(Throughput::Bytes((lost * SHARD_BYTES) as u64));
+
+        let id = format!(
+            "{}:{} ({} lost, {} recovery)",
+            original_count, recovery_count, lost, recovery_count
+        );
+
+        group.bench_function(BenchmarkId::new("ReedSolomonDecoder", &id), |b| {
+            b.iter(|| {
+                for (index, original) in original.iter().enumerate().skip(lost) {
+                    decoder.add_original_shard(index, original).unwrap();
+                }
+                for (index, recovery) in recovery.iter().enumerate() {
+                    decoder.add_recovery_shard(index, recovery).unwrap();
+                }
+                black_box(decoder.decode().unwrap());
+            });
+        });
+    }
+
+    group.finish();
+}
+
+// ======================================================================
+// BENCHMARKS - PEELING
+
+fn benchmarks_peeling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("peeling");
+
+    // 1% lost is above peeling limit so `decode_peeling` falls back to `decode`.
+    let original_count = 16384;
+    let recovery_count = 16384;
+    let original = generate_shards(original_count, SHARD_BYTES, 0);
+    let recovery = reed_solomon_simd::encode(original_count, recovery_count, &original).unwrap();
+    let mut decoder = ReedSolomonDecoder::new(original_count, recovery_count, SHARD_BYTES).unwrap();
+
+    for lost in [1, 2, original_count / 100] {
+        group.throughput(Throughput::Bytes((lost * SHARD_BYTES) as u64));
+
+        let id = if lost == original_count / 100 {
+            format!("{}:{} (1%)", original_count, recovery_count)
+        } else {
+            format!("{}:{} ({} lost)", original_count, recovery_count, lost)
+        };
+
+        for peeling in [false, true] {
+            let name = if peeling { "decode_peeling" } else { "decode" };
+            group.bench_function(BenchmarkId::new(name, &id), |b| {
+                b.iter(|| {
+                    for (index, original) in original.iter().enumerate().skip(lost) {
+                        decoder.add_original_shard(index, original).unwrap();
+                    }
+                    for (index, recovery) in recovery.iter().enumerate().take(lost) {
+                        decoder.add_recovery_shard(index, recovery).unwrap();
+                    }
+                    if peeling {
+                        black_box(decoder.decode_peeling().unwrap());
+                    } else {
+                        black_box(decoder.decode().unwrap());
+                    }
+                });
+            });
+        }
+    }
+
+    group.finish();
+}
+
+// ======================================================================
+// BENCHMARKS - SMALL CODEC
+
+fn benchmarks_small(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small");
+
+    // 480-byte audio frames padded to 512 bytes, see `small` module.
+    let shard_bytes = 512;
+    let original = generate_shards(4, shard_bytes, 0);
+    let original: [&[u8]; 4] = std::array::from_fn(|i| &original[i][..]);
+    let id = "4:2 (480 bytes padded to 512)";
+
+    group.throughput(Throughput::Bytes((4 * shard_bytes) as u64));
+
+    let codec = SmallCodec::<4, 2>::new().unwrap();
+    let mut recovery = [[0u8; 512]; 2];
+    group.bench_function(BenchmarkId::new("SmallCodec::encode", id), |b| {
+        let [r0, r1] = &mut recovery;
+        b.iter(|| codec.encode(&original, &mut [r0, r1]).unwrap());
+    });
+
+    let mut encoder = ReedSolomonEncoder::new(4, 2, shard_bytes).unwrap();
+    group.bench_function(BenchmarkId::new("ReedSolomonEncoder", id), |b| {
+        b.iter(|| black_box(encoder.encode_ref(&original).unwrap().recovery(0).unwrap()[0]));
+    });
+
+    // Original shards 1 and 3 lost.
+    let mut restored = [[0u8; 512]; 2];
+    group.bench_function(BenchmarkId::new("SmallCodec::decode", id), |b| {
+        let [a, b_] = &mut restored;
+        b.iter(|| {
+            codec
+                .decode(
+                    &[Some(original[0]), None, Some(original[2]), None],
+                    &[Some(&recovery[0]), Some(&recovery[1])],
+                    &mut [&mut [], a, &mut [], b_],
+                )
+                .unwrap()
+        });
+    });
+
+    let mut decoder = ReedSolomonDecoder::new(4, 2, shard_bytes).unwrap();
+    group.bench_function(BenchmarkId::new("ReedSolomonDecoder", id), |b| {
+        b.iter(|| {
+            let result = decoder
+                .decode_ref(
+                    &[(0, original[0]), (2, original[2])],
+                    &[(0, &recovery[0]), (1, &recovery[1])],
+                )
+                .unwrap();
+            black_box(result.restored_original(1).unwrap()[0]);
+        });
+    });
+
+    group.finish();
+}
+
+// ======================================================================
+// BENCHMARKS - RECOVERY ONLY
+
+fn benchmarks_recovery_only(c: &mut Criterion) {
+    let mut group = c.benchmark_group("recovery_only");
+
+    let (original_count, recovery_count) = (8192, 1024);
+    let original = generate_shards(original_count, SHARD_BYTES, 0);
+
+    group.throughput(Throughput::Bytes(
+        ((original_count + recovery_count) * SHARD_BYTES) as u64,
+    ));
+
+    // Peak memory is the working space, which is included in the id.
+    let id = |allocated_bytes: usize| {
+        format!(
+            "{}:{} ({} KiB work)",
+            original_count,
+            recovery_count,
+            allocated_bytes / 1024
+        )
+    };
+
+    let mut encoder = ReedSolomonEncoder::new(original_count, recovery_count, SHARD_BYTES).unwrap();
+    group.bench_function(
+        BenchmarkId::new("ReedSolomonEncoder", id(encoder.allocated_bytes())),
+        |b| {
+            b.iter(|| {
+                for original in &original {
+                    encoder.add_original_shard(original).unwrap();
+                }
+                black_box(encoder.encode().unwrap().recovery(0).unwrap()[0]);
+            });
+        },
+    );
+
+    let mut encoder =
+        RecoveryOnlyEncoder::new(original_count, recovery_count, SHARD_BYTES).unwrap();
+    group.bench_function(
+        BenchmarkId::new("RecoveryOnlyEncoder", id(encoder.allocated_bytes())),
+        |b| {
+            b.iter(|| {
+                for original in &original {
+                    encoder.add_original_shard(original).unwrap();
+                }
+                black_box(encoder.encode().unwrap().recovery(0).unwrap()[0]);
+            });
+        },
+    );
+
+    group.finish();
+}
+
+// ======================================================================
+// BENCHMARKS - ADDING SHARDS
+
+fn benchmarks_add(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add");
+
+    // Small shards so that per-shard overhead dominates copying.
+    let (original_count, recovery_count, shard_bytes) = (1000, 100, 64);
+
+    // Last 64 bytes are reserved and must be zero with integrity footer.
+    let original = vec![vec![0u8; shard_bytes]; original_count];
+
+    for footer in [false, true] {
+        let mut encoder =
+            ReedSolomonEncoder::new(original_count, recovery_count, shard_bytes).unwrap();
+        encoder.set_integrity_footer(if footer { Some(0) } else { None });
+
+        let id = format!(
+            "{}:{} ({} bytes, footer {})",
+            original_count,
+            recovery_count,
+            shard_bytes,
+            if footer { "on" } else { "off" }
+        );
+
+        group.throughput(Throughput::Elements(original_count as u64));
+        group.bench_with_input(
+            BenchmarkId::new("ReedSolomonEncoder", &id),
+            &original,
+            |b, original| {
+                b.iter(|| {
+                    for original in original {
+                        encoder.add_original_shard(original).unwrap();
+                    }
+                    encoder
+                        .reset(original_count, recovery_count, shard_bytes)
+                        .unwrap();
+                });
+            },
+        );
+
+        for original in &original {
+            encoder.add_original_shard(original).unwrap();
+        }
+        let recovery: Vec<_> = encoder
+            .encode()
+            .unwrap()
+            .recovery_iter()
+            .map(|shard| shard.to_vec())
+            .collect();
+
+        let mut decoder =
+            ReedSolomonDecoder::new(original_count, recovery_count, shard_bytes).unwrap();
+        decoder.set_integrity_footer(if footer { Some(0) } else { None });
+
+        group.throughput(Throughput::Elements(recovery_count as u64));
+        group.bench_with_input(
+            BenchmarkId::new("ReedSolomonDecoder", &id),
+            &recovery,
+            |b, recovery| {
+                b.iter(|| {
+                    for (index, recovery) in recovery.iter().enumerate() {
+                        decoder.add_recovery_shard(index, recovery).unwrap();
+                    }
+                    decoder
+                        .reset(original_count, recovery_count, shard_bytes)
+                        .unwrap();
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
 // ======================================================================
 // BENCHMARKS - RATE
 
@@ -292,29 +666,32 @@ fn benchmarks_rate_one<E: Engine>(c: &mut Criterion, name: &str, new_engine: fn(
 // BENCHMARKS - ENGINES
 
 fn benchmarks_engine(c: &mut Criterion) {
-    benchmarks_engine_one(c, "engine-Naive", Naive::new());
-    benchmarks_engine_one(c, "engine-NoSimd", NoSimd::new());
-
-    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-    {
-        if is_x86_feature_detected!("ssse3") {
-            benchmarks_engine_one(c, "engine-Ssse3", Ssse3::new());
-        }
-        if is_x86_feature_detected!("avx2") {
-            benchmarks_engine_one(c, "engine-Avx2", Avx2::new());
+    for kind in engine::available_engines().into_iter().rev() {
+        match kind {
+            EngineKind::Naive => benchmarks_engine_one(c, Naive::new()),
+            EngineKind::NoSimd => benchmarks_engine_one(c, NoSimd::new()),
+
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Ssse3 => benchmarks_engine_one(c, Ssse3::new()),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Avx2 => benchmarks_engine_one(c, Avx2::new()),
+
+            #[cfg(target_arch = "aarch64")]
+            EngineKind::Neon => benchmarks_engine_one(c, Neon::new()),
+
+            // Not available on this architecture.
+            #[allow(unreachable_patterns)]
+            _ => {}
         }
     }
 
-    #[cfg(target_arch = "aarch64")]
-    {
-        if std::arch::is_aarch64_feature_detected!("neon") {
-            benchmarks_engine_one(c, "engine-Neon", Neon::new());
-        }
-    }
+    // Gather-based alternative to scalar `mul` of `NoSimd`.
+    #[cfg(feature = "portable-simd")]
+    benchmarks_engine_one(c, PortableSimd::new());
 }
 
-fn benchmarks_engine_one<E: Engine>(c: &mut Criterion, name: &str, engine: E) {
-    let mut group = c.benchmark_group(name);
+fn benchmarks_engine_one<E: Engine>(c: &mut Criterion, engine: E) {
+    let mut group = c.benchmark_group(format!("engine-{}", engine.name()));
 
     // XOR MUL
 
@@ -387,6 +764,24 @@ fn benchmarks_engine_one<E: Engine>(c: &mut Criterion, name: &str, engine: E) {
 // MAIN
 
 criterion_group!(benches_main, benchmarks_main);
+criterion_group!(benches_combined, benchmarks_combined);
+criterion_group!(benches_dedup, benchmarks_dedup);
+criterion_group!(benches_surplus, benchmarks_surplus);
+criterion_group!(benches_peeling, benchmarks_peeling);
+criterion_group!(benches_small, benchmarks_small);
+criterion_group!(benches_recovery_only, benchmarks_recovery_only);
+criterion_group!(benches_add, benchmarks_add);
 criterion_group!(benches_rate, benchmarks_rate);
 criterion_group!(benches_engine, benchmarks_engine);
-criterion_main!(benches_main, benches_rate, benches_engine);
+criterion_main!(
+    benches_main,
+    benches_combined,
+    benches_dedup,
+    benches_surplus,
+    benches_peeling,
+    benches_small,
+    benches_recovery_only,
+    benches_add,
+    benches_rate,
+    benches_engine
+);
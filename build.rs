@@ -10,10 +10,20 @@ const CRATE_NAME: &str = "reed_solomon_simd";
 
 fn main() -> Result<(), Box<dyn Error>> {
     println!("cargo:rerun-if-changed=README.md");
+
+    let mut readme = fs::read_to_string("README.md")?;
+    // README examples both encode and decode,
+    // so they can't be compiled if either feature is disabled.
+    if env::var_os("CARGO_FEATURE_ENCODER").is_none()
+        || env::var_os("CARGO_FEATURE_DECODER").is_none()
+    {
+        readme = readme.replace("```rust\n", "```rust,ignore\n");
+    }
+
     fs::write(
         PathBuf::from(env::var("OUT_DIR")?).join("README-rustdocified.md"),
         readme_rustdocifier::rustdocify(
-            &fs::read_to_string("README.md")?,
+            &readme,
             &env::var("CARGO_PKG_NAME")?,
             Some(&env::var("CARGO_PKG_VERSION")?),
             Some(CRATE_NAME),
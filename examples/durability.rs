@@ -0,0 +1,392 @@
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use reed_solomon_simd::{ReedSolomonDecoder, ReedSolomonEncoder};
+use sha2::{Digest, Sha256};
+
+// ======================================================================
+// CONST
+
+const ORIGINAL_COUNT: usize = 10;
+const RECOVERY_COUNT: usize = 4;
+const SHARD_COUNT: usize = ORIGINAL_COUNT + RECOVERY_COUNT;
+
+// Shard files which are deleted unless others are given.
+const DEFAULT_LOST: [usize; RECOVERY_COUNT] = [0, 3, 10, 13];
+
+// ======================================================================
+// MAIN
+
+// Mini object store: stores given file as 10 original and 4 recovery
+// shard files, deletes some of them, scrubs the shard files to find
+// the lost ones, repairs them and reads the file back.
+//
+// cargo run --release --example durability -- <FILE> [INDEX ...]
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let input = match args.next() {
+        Some(input) => PathBuf::from(input),
+        None => {
+            eprintln!("usage: durability <FILE> [INDEX ...]");
+            std::process::exit(1);
+        }
+    };
+    let lost = args
+        .map(|index| index.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let lost = if lost.is_empty() {
+        DEFAULT_LOST.to_vec()
+    } else {
+        lost
+    };
+
+    let dir = input.with_extension("durability");
+    let data = fs::read(&input)?;
+
+    // STORE
+
+    store(&dir, &data)?;
+    println!(
+        "Stored {} bytes as {} shard files in {}",
+        data.len(),
+        SHARD_COUNT,
+        dir.display()
+    );
+
+    // LOSE SHARDS
+
+    for &index in &lost {
+        fs::remove_file(shard_path(&dir, index))?;
+        println!("Deleted shard file #{}", index);
+    }
+
+    // SCRUB, REPAIR, SCRUB AGAIN
+
+    let bad = scrub(&dir)?;
+    println!("Scrub found bad shard files {:?}", bad);
+
+    let repaired = repair(&dir)?;
+    println!("Repaired shard files {:?}", repaired);
+
+    let bad = scrub(&dir)?;
+    if !bad.is_empty() {
+        return Err(format!("shard files {:?} are still bad after repair", bad).into());
+    }
+    println!("Scrub found no bad shard files");
+
+    // READ BACK
+
+    if load(&dir)? == data {
+        println!("Stored file matches the original.");
+    } else {
+        return Err("stored file doesn't match the original".into());
+    }
+
+    Ok(())
+}
+
+// ======================================================================
+// Manifest
+
+// Object length, shard size and SHA-256 of each shard file.
+// - Hashes are what lets scrubbing tell which shard file is corrupted,
+//   as the codec itself doesn't detect errors within a shard.
+struct Manifest {
+    object_bytes: usize,
+    shard_bytes: usize,
+    hashes: Vec<String>,
+}
+
+impl Manifest {
+    fn read(dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(dir.join("manifest.txt"))?;
+        let mut lines = text.lines();
+        let mut number = || -> Result<usize, Box<dyn Error>> {
+            Ok(lines.next().ok_or("truncated manifest")?.parse()?)
+        };
+
+        let object_bytes = number()?;
+        let shard_bytes = number()?;
+        let hashes: Vec<_> = lines.map(str::to_owned).collect();
+        if hashes.len() != SHARD_COUNT {
+            return Err("wrong number of hashes in manifest".into());
+        }
+
+        Ok(Self {
+            object_bytes,
+            shard_bytes,
+            hashes,
+        })
+    }
+
+    fn write(&self, dir: &Path) -> Result<(), Box<dyn Error>> {
+        let mut text = format!("{}\n{}\n", self.object_bytes, self.shard_bytes);
+        for hash in &self.hashes {
+            text += hash;
+            text += "\n";
+        }
+        Ok(fs::write(dir.join("manifest.txt"), text)?)
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - STORE / LOAD
+
+// Encodes `data` into shard files and writes manifest.
+fn store(dir: &Path, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    // Shard size must be a non-zero multiple of 64 bytes,
+    // last original shard is padded with zeros.
+    let shard_bytes = std::cmp::max(data.len().div_ceil(ORIGINAL_COUNT).next_multiple_of(64), 64);
+
+    let mut original = vec![vec![0u8; shard_bytes]; ORIGINAL_COUNT];
+    for (shard, chunk) in original.iter_mut().zip(data.chunks(shard_bytes)) {
+        shard[..chunk.len()].copy_from_slice(chunk);
+    }
+
+    let mut encoder = ReedSolomonEncoder::new(ORIGINAL_COUNT, RECOVERY_COUNT, shard_bytes)?;
+    let result = encoder.encode_ref(&original)?;
+
+    fs::create_dir_all(dir)?;
+    let mut hashes = Vec::new();
+    for (index, shard) in original
+        .iter()
+        .map(Vec::as_slice)
+        .chain(result.recovery_iter())
+        .enumerate()
+    {
+        fs::write(shard_path(dir, index), shard)?;
+        hashes.push(hash(shard));
+    }
+
+    Manifest {
+        object_bytes: data.len(),
+        shard_bytes,
+        hashes,
+    }
+    .write(dir)
+}
+
+// Reads the object back from original shard files,
+// restoring lost ones from recovery shard files if needed.
+fn load(dir: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    let manifest = Manifest::read(dir)?;
+    let shards = read_shards(dir, &manifest);
+
+    let original: Vec<_> = shards[..ORIGINAL_COUNT]
+        .iter()
+        .enumerate()
+        .filter_map(|(index, shard)| Some((index, shard.as_ref()?)))
+        .collect();
+    let recovery: Vec<_> = shards[ORIGINAL_COUNT..]
+        .iter()
+        .enumerate()
+        .filter_map(|(index, shard)| Some((index, shard.as_ref()?)))
+        .collect();
+
+    Ok(reed_solomon_simd::reconstruct_data(
+        ORIGINAL_COUNT,
+        RECOVERY_COUNT,
+        original,
+        recovery,
+        manifest.object_bytes,
+    )?)
+}
+
+// ======================================================================
+// FUNCTIONS - SCRUB / REPAIR
+
+// Returns indexes of shard files which are missing or don't match
+// their hash. If there are none, also checks that recovery shards
+// are consistent with original shards.
+fn scrub(dir: &Path) -> Result<Vec<usize>, Box<dyn Error>> {
+    let manifest = Manifest::read(dir)?;
+    let shards = read_shards(dir, &manifest);
+
+    let bad: Vec<_> = (0..SHARD_COUNT).filter(|&i| shards[i].is_none()).collect();
+    if bad.is_empty() {
+        let shards: Vec<&[u8]> = shards.iter().flatten().map(Vec::as_slice).collect();
+        let mut encoder =
+            ReedSolomonEncoder::new(ORIGINAL_COUNT, RECOVERY_COUNT, manifest.shard_bytes)?;
+        if !encoder.verify(&shards[..ORIGINAL_COUNT], &shards[ORIGINAL_COUNT..])? {
+            return Err("recovery shards don't match original shards".into());
+        }
+    }
+
+    Ok(bad)
+}
+
+// Rewrites missing or corrupted shard files, returning their indexes.
+// - Lost original shards are restored by decoding.
+// - Lost recovery shards are regenerated by encoding
+//   the then complete set of original shards again.
+fn repair(dir: &Path) -> Result<Vec<usize>, Box<dyn Error>> {
+    let manifest = Manifest::read(dir)?;
+    let mut shards = read_shards(dir, &manifest);
+
+    let lost: Vec<_> = (0..SHARD_COUNT).filter(|&i| shards[i].is_none()).collect();
+    if lost.len() > RECOVERY_COUNT {
+        return Err(format!("too many shard files lost: {:?}", lost).into());
+    }
+
+    // ORIGINAL SHARDS
+
+    if lost.iter().any(|&index| index < ORIGINAL_COUNT) {
+        let (original, recovery) = shards.split_at(ORIGINAL_COUNT);
+        let original: Vec<_> = original
+            .iter()
+            .enumerate()
+            .filter_map(|(index, shard)| Some((index, shard.as_ref()?)))
+            .collect();
+        let recovery: Vec<_> = recovery
+            .iter()
+            .enumerate()
+            .filter_map(|(index, shard)| Some((index, shard.as_ref()?)))
+            .collect();
+
+        let mut decoder =
+            ReedSolomonDecoder::new(ORIGINAL_COUNT, RECOVERY_COUNT, manifest.shard_bytes)?;
+        let result = decoder.decode_ref(&original, &recovery)?;
+
+        let restored: Vec<_> = result
+            .restored_original_iter()
+            .map(|(index, shard)| (index, shard.to_vec()))
+            .collect();
+        for (index, shard) in restored {
+            shards[index] = Some(shard);
+        }
+    }
+
+    // RECOVERY SHARDS
+
+    if lost.iter().any(|&index| index >= ORIGINAL_COUNT) {
+        let original: Vec<_> = shards[..ORIGINAL_COUNT].iter().flatten().collect();
+
+        let mut encoder =
+            ReedSolomonEncoder::new(ORIGINAL_COUNT, RECOVERY_COUNT, manifest.shard_bytes)?;
+        let result = encoder.encode_ref(&original)?;
+
+        for (index, shard) in result.recovery_iter().enumerate() {
+            shards[ORIGINAL_COUNT + index] = Some(shard.to_vec());
+        }
+    }
+
+    // WRITE
+
+    for &index in &lost {
+        let shard = shards[index].as_ref().unwrap();
+        if hash(shard) != manifest.hashes[index] {
+            return Err(format!("repaired shard #{} doesn't match its hash", index).into());
+        }
+        fs::write(shard_path(dir, index), shard)?;
+    }
+
+    Ok(lost)
+}
+
+// ======================================================================
+// FUNCTIONS - UTIL
+
+fn hash(shard: &[u8]) -> String {
+    hex::encode(Sha256::digest(shard))
+}
+
+// Reads all shard files, giving `None` for missing ones
+// and ones which don't match their hash.
+fn read_shards(dir: &Path, manifest: &Manifest) -> Vec<Option<Vec<u8>>> {
+    (0..SHARD_COUNT)
+        .map(|index| {
+            fs::read(shard_path(dir, index))
+                .ok()
+                .filter(|shard| hash(shard) == manifest.hashes[index])
+        })
+        .collect()
+}
+
+fn shard_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("shard-{:03}.bin", index))
+}
+
+// ======================================================================
+// TESTS
+
+// Run with `cargo test --examples`.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("durability-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn object(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + i / 251) as u8).collect()
+    }
+
+    #[test]
+    fn lose_and_repair() {
+        let dir = temp_dir("lose");
+        let data = object(10_000);
+        store(&dir, &data).unwrap();
+
+        for lost in [&[][..], &[0], &[13], &[0, 3, 10, 13], &[6, 7, 8, 9]] {
+            for &index in lost {
+                fs::remove_file(shard_path(&dir, index)).unwrap();
+            }
+            assert_eq!(scrub(&dir).unwrap(), lost);
+            assert_eq!(repair(&dir).unwrap(), lost);
+            assert!(scrub(&dir).unwrap().is_empty());
+            assert_eq!(load(&dir).unwrap(), data);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn corrupted_shard_file() {
+        let dir = temp_dir("corrupted");
+        let data = object(1000);
+        store(&dir, &data).unwrap();
+
+        let path = shard_path(&dir, 4);
+        let mut shard = fs::read(&path).unwrap();
+        shard[0] ^= 1;
+        fs::write(&path, shard).unwrap();
+
+        assert_eq!(scrub(&dir).unwrap(), [4]);
+        assert_eq!(load(&dir).unwrap(), data);
+        assert_eq!(repair(&dir).unwrap(), [4]);
+        assert!(scrub(&dir).unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn too_many_lost() {
+        let dir = temp_dir("too-many");
+        store(&dir, &object(100)).unwrap();
+
+        for index in [0, 1, 2, 11, 12] {
+            fs::remove_file(shard_path(&dir, index)).unwrap();
+        }
+        assert_eq!(scrub(&dir).unwrap(), [0, 1, 2, 11, 12]);
+        assert!(repair(&dir).is_err());
+        assert!(load(&dir).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn empty_object() {
+        let dir = temp_dir("empty");
+        store(&dir, &[]).unwrap();
+        assert_eq!(load(&dir).unwrap(), []);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
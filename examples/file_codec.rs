@@ -0,0 +1,102 @@
+use std::{
+    env,
+    error::Error,
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use reed_solomon_simd::{stream, CodecParams};
+
+// ======================================================================
+// CONST
+
+const ORIGINAL_COUNT: usize = 4;
+const RECOVERY_COUNT: usize = 2;
+const SHARD_BYTES: usize = 64 * 1024;
+
+// ======================================================================
+// MAIN
+
+// Splits given file into shard files, deletes some of them
+// and reassembles the file from the remaining subset.
+//
+// cargo run --release --example file_codec -- <FILE>
+fn main() -> Result<(), Box<dyn Error>> {
+    let input = match env::args().nth(1) {
+        Some(input) => PathBuf::from(input),
+        None => {
+            eprintln!("usage: file_codec <FILE>");
+            std::process::exit(1);
+        }
+    };
+
+    let params = CodecParams::new(ORIGINAL_COUNT, RECOVERY_COUNT, SHARD_BYTES);
+    let shard_dir = input.with_extension("shards");
+    let restored = input.with_extension("restored");
+
+    // ENCODE
+
+    fs::create_dir_all(&shard_dir)?;
+
+    let mut writers = (0..ORIGINAL_COUNT + RECOVERY_COUNT)
+        .map(|index| File::create(shard_path(&shard_dir, index)).map(BufWriter::new))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let object_bytes =
+        stream::encode_stream(BufReader::new(File::open(&input)?), &mut writers, params)?;
+    drop(writers);
+
+    println!(
+        "Encoded {} bytes into {} shard files in {}",
+        object_bytes,
+        ORIGINAL_COUNT + RECOVERY_COUNT,
+        shard_dir.display()
+    );
+
+    // LOSE SHARDS
+
+    for index in [0, 2] {
+        fs::remove_file(shard_path(&shard_dir, index))?;
+        println!("Deleted shard file #{}", index);
+    }
+
+    // DECODE
+
+    let mut readers = Vec::new();
+    for index in 0..ORIGINAL_COUNT + RECOVERY_COUNT {
+        if let Ok(file) = File::open(shard_path(&shard_dir, index)) {
+            readers.push((index, BufReader::new(file)));
+        }
+    }
+
+    let restored_bytes = stream::decode_stream(
+        &mut readers,
+        BufWriter::new(File::create(&restored)?),
+        params,
+    )?;
+
+    println!(
+        "Restored {} bytes from {} shard files into {}",
+        restored_bytes,
+        readers.len(),
+        restored.display()
+    );
+
+    // VERIFY
+
+    if fs::read(&input)? == fs::read(&restored)? {
+        println!("Restored file matches the original.");
+    } else {
+        return Err("restored file doesn't match the original".into());
+    }
+
+    Ok(())
+}
+
+// ======================================================================
+// FUNCTIONS
+
+fn shard_path(shard_dir: &Path, index: usize) -> PathBuf {
+    shard_dir.join(format!("shard-{:03}.bin", index))
+}
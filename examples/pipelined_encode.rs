@@ -0,0 +1,90 @@
+use std::{error::Error, sync::mpsc, thread};
+
+use reed_solomon_simd::{
+    engine::DefaultEngine,
+    rate::{self, EncoderWork},
+    CodecParams, ReedSolomonEncoder,
+};
+
+// ======================================================================
+// CONST
+
+const ORIGINAL_COUNT: usize = 32;
+const RECOVERY_COUNT: usize = 8;
+const SHARD_BYTES: usize = 64 * 1024;
+const STRIPE_COUNT: usize = 16;
+
+// ======================================================================
+// MAIN
+
+// Encodes stripes in a compute thread while the main thread
+// reads original shards of the next stripe, using two working spaces
+// which are passed back and forth over channels.
+//
+// cargo run --release --example pipelined_encode
+fn main() -> Result<(), Box<dyn Error>> {
+    let params = CodecParams::new(ORIGINAL_COUNT, RECOVERY_COUNT, SHARD_BYTES);
+
+    // Filled working spaces to the compute thread.
+    let (work_tx, work_rx) = mpsc::channel::<(usize, EncoderWork)>();
+    // Encoded working spaces back to the main thread.
+    let (done_tx, done_rx) = mpsc::channel::<EncoderWork>();
+    // Recovery shards onwards, e.g. to network or disk.
+    let (recovery_tx, recovery_rx) = mpsc::channel::<(usize, Vec<Vec<u8>>)>();
+
+    let compute = thread::spawn(move || -> Result<(), reed_solomon_simd::Error> {
+        let engine = DefaultEngine::new();
+        for (stripe, mut work) in work_rx {
+            let result = rate::encode_work(&engine, params, &mut work)?;
+            let recovery = result.recovery_iter().map(<[u8]>::to_vec).collect();
+            drop(result);
+
+            // Receiver is gone only if the main thread panicked.
+            let _ = recovery_tx.send((stripe, recovery));
+            let _ = done_tx.send(work);
+        }
+        Ok(())
+    });
+
+    let mut encoder = ReedSolomonEncoder::from_params(params)?;
+    for stripe in 0..STRIPE_COUNT {
+        // Reading the next stripe overlaps with encoding of the previous one.
+        for original in read_stripe(stripe) {
+            encoder.add_original_shard(original)?;
+        }
+
+        // Working space of previous stripe is re-used once it's encoded,
+        // so only two working spaces are ever allocated.
+        if stripe > 0 {
+            encoder.restore_work(done_rx.recv()?);
+        }
+        work_tx.send((stripe, encoder.take_work()?))?;
+    }
+    drop(work_tx);
+
+    for (stripe, recovery) in recovery_rx {
+        println!(
+            "stripe {:2}: {} recovery shards, first bytes {:02x?}",
+            stripe,
+            recovery.len(),
+            &recovery[0][..4]
+        );
+    }
+
+    compute.join().unwrap()?;
+    Ok(())
+}
+
+// ======================================================================
+// FUNCTIONS
+
+// Stand-in for reading original shards of a stripe from IO.
+fn read_stripe(stripe: usize) -> Vec<Vec<u8>> {
+    (0..ORIGINAL_COUNT)
+        .map(|index| {
+            (0..SHARD_BYTES)
+                .map(|i| (stripe * 31 + index * 7 + i) as u8)
+                .collect()
+        })
+        .collect()
+}
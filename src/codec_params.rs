@@ -0,0 +1,284 @@
+use crate::{
+    engine::DefaultEngine,
+    rate::{DefaultRate, Rate},
+    Error,
+};
+#[cfg(feature = "encoder")]
+use crate::{engine::Engine, ReedSolomonEncoder};
+
+// ======================================================================
+// CodecParams - PUBLIC
+
+/// Configuration of an encoder/decoder as a single value.
+///
+/// This is convenient e.g. as a [`HashMap`] key when caching
+/// encoders/decoders by their configuration.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+/// use reed_solomon_simd::{CodecParams, ReedSolomonEncoder};
+/// use std::collections::HashMap;
+///
+/// let params = CodecParams::new(3, 5, 64);
+///
+/// let mut cache = HashMap::new();
+/// cache.insert(params, ReedSolomonEncoder::from_params(params)?);
+///
+/// assert!(cache.contains_key(&CodecParams::new(3, 5, 64)));
+/// # }
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+///
+/// [`HashMap`]: std::collections::HashMap
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CodecParams {
+    /// Number of original shards.
+    pub original_count: usize,
+    /// Number of recovery shards.
+    pub recovery_count: usize,
+    /// Size of each shard in bytes.
+    pub shard_bytes: usize,
+}
+
+impl CodecParams {
+    /// Creates new [`CodecParams`] with given configuration.
+    ///
+    /// Configuration is not validated here, use [`validate`] for that.
+    ///
+    /// [`validate`]: CodecParams::validate
+    pub fn new(original_count: usize, recovery_count: usize, shard_bytes: usize) -> Self {
+        Self {
+            original_count,
+            recovery_count,
+            shard_bytes,
+        }
+    }
+
+    /// Returns `Ok(())` if `original_count` / `recovery_count`
+    /// combination is supported and `shard_bytes` is valid.
+    ///
+    /// This is same as [`Rate::validate`] of [`DefaultRate`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::{CodecParams, Error};
+    ///
+    /// assert_eq!(CodecParams::new(3, 5, 64).validate(), Ok(()));
+    /// assert_eq!(
+    ///     CodecParams::new(3, 5, 100).validate(),
+    ///     Err(Error::InvalidShardSize { shard_bytes: 100 })
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        DefaultRate::<DefaultEngine>::validate(
+            self.original_count,
+            self.recovery_count,
+            self.shard_bytes,
+        )
+    }
+
+    /// Encodes in one go into a single combined buffer
+    /// laid out as `[original_0, ..., original_N, recovery_0, ..., recovery_M]`.
+    ///
+    /// - `out` must be exactly `(original_count + recovery_count) * shard_bytes`
+    ///   bytes, otherwise [`Error::InvalidOutputSize`] is returned.
+    /// - `originals` are copied to the front of `out`
+    ///   and generated recovery shards after them.
+    /// - Shards are copied to `out` with [`Engine::copy`],
+    ///   i.e. with non-temporal stores for large shards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::CodecParams;
+    ///
+    /// let original = [[1u8; 64], [2u8; 64]];
+    /// let params = CodecParams::new(2, 1, 64);
+    ///
+    /// let mut stripe = vec![0u8; 3 * 64];
+    /// params.encode_combined(&[&original[0], &original[1]], &mut stripe)?;
+    ///
+    /// assert_eq!(stripe[..64], original[0]);
+    /// assert_eq!(stripe[64..128], original[1]);
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// # Aliasing
+    ///
+    /// `originals` and `out` never overlap as `out` is a mutable borrow,
+    /// so this doesn't check for it. E.g. originals which are already
+    /// at the front of `out` are rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// use reed_solomon_simd::CodecParams;
+    ///
+    /// let mut stripe = vec![0u8; 3 * 64];
+    /// let (original_0, original_1) = stripe[..128].split_at(64);
+    ///
+    /// CodecParams::new(2, 1, 64).encode_combined(&[original_0, original_1], &mut stripe)?;
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// Callers which create these slices from raw pointers,
+    /// e.g. buffers from FFI, must ensure that they don't overlap.
+    /// To encode originals which are in the same buffer as recovery shards,
+    /// split it with [`slice::split_at_mut`] and use [`ReedSolomonEncoder`].
+    #[cfg(feature = "encoder")]
+    pub fn encode_combined(&self, originals: &[&[u8]], out: &mut [u8]) -> Result<(), Error> {
+        let mut encoder = ReedSolomonEncoder::from_params(*self)?;
+
+        let original_bytes = self.original_count * self.shard_bytes;
+        let expected = original_bytes + self.recovery_count * self.shard_bytes;
+        if out.len() != expected {
+            return Err(Error::InvalidOutputSize {
+                expected,
+                got: out.len(),
+            });
+        }
+
+        let (original_out, recovery_out) = out.split_at_mut(original_bytes);
+
+        for (original, out) in originals
+            .iter()
+            .zip(original_out.chunks_exact_mut(self.shard_bytes))
+        {
+            encoder.add_original_shard(original)?;
+            DefaultEngine::copy(out, original);
+        }
+
+        // Detects too many original shards.
+        for original in originals.iter().skip(self.original_count) {
+            encoder.add_original_shard(original)?;
+        }
+
+        let result = encoder.encode()?;
+
+        for (recovery, out) in result
+            .recovery_iter()
+            .zip(recovery_out.chunks_exact_mut(self.shard_bytes))
+        {
+            DefaultEngine::copy(out, recovery);
+        }
+
+        Ok(())
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{test_util, UnsupportedReason};
+
+    #[test]
+    fn hash_eq() {
+        let mut set = HashSet::new();
+        assert!(set.insert(CodecParams::new(2, 3, 64)));
+        assert!(!set.insert(CodecParams::new(2, 3, 64)));
+        assert!(set.insert(CodecParams::new(2, 3, 128)));
+        assert!(set.insert(CodecParams::new(3, 2, 64)));
+    }
+
+    // ============================================================
+    // encode_combined
+
+    #[test]
+    fn encode_combined_matches_encode() {
+        let original = test_util::generate_original(5, 1024, 155);
+        let original_refs: Vec<&[u8]> = original.iter().map(|s| s.as_slice()).collect();
+
+        let mut expected: Vec<u8> = original.concat();
+        for recovery in crate::encode(5, 3, &original).unwrap() {
+            expected.extend_from_slice(&recovery);
+        }
+
+        let mut out = vec![0u8; 8 * 1024];
+        CodecParams::new(5, 3, 1024)
+            .encode_combined(&original_refs, &mut out)
+            .unwrap();
+
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn encode_combined_errors() {
+        let params = CodecParams::new(2, 1, 64);
+        let shard = [0u8; 64];
+
+        assert_eq!(
+            params.encode_combined(&[&shard, &shard], &mut [0u8; 191]),
+            Err(Error::InvalidOutputSize {
+                expected: 192,
+                got: 191
+            })
+        );
+
+        assert_eq!(
+            params.encode_combined(&[&shard], &mut [0u8; 192]),
+            Err(Error::TooFewOriginalShards {
+                original_count: 2,
+                original_received_count: 1,
+            })
+        );
+
+        assert_eq!(
+            params.encode_combined(&[&shard, &shard, &shard], &mut [0u8; 192]),
+            Err(Error::TooManyOriginalShards { original_count: 2 })
+        );
+
+        assert_eq!(
+            params.encode_combined(&[&shard, &shard[..32]], &mut [0u8; 192]),
+            Err(Error::DifferentShardSize {
+                shard_bytes: 64,
+                got: 32
+            })
+        );
+    }
+
+    // ============================================================
+    // validate
+
+    #[test]
+    fn validate() {
+        assert_eq!(CodecParams::new(1, 1, 64).validate(), Ok(()));
+        assert_eq!(CodecParams::new(61440, 4096, 64).validate(), Ok(()));
+        assert_eq!(CodecParams::new(4096, 61440, 64).validate(), Ok(()));
+
+        assert_eq!(
+            CodecParams::new(1, 1, 0).validate(),
+            Err(Error::InvalidShardSize { shard_bytes: 0 })
+        );
+
+        assert_eq!(
+            CodecParams::new(1, 1, 123).validate(),
+            Err(Error::InvalidShardSize { shard_bytes: 123 })
+        );
+
+        assert_eq!(
+            CodecParams::new(0, 1, 64).validate(),
+            Err(Error::UnsupportedShardCount {
+                original_count: 0,
+                recovery_count: 1,
+                reason: UnsupportedReason::NoOriginalShards,
+                hint: None,
+            })
+        );
+
+        assert_eq!(
+            CodecParams::new(61440, 4097, 64).validate(),
+            Err(Error::UnsupportedShardCount {
+                original_count: 61440,
+                recovery_count: 4097,
+                reason: UnsupportedReason::TooManyShards,
+                hint: None,
+            })
+        );
+    }
+}
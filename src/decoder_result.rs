@@ -1,4 +1,6 @@
-use crate::rate::DecoderWork;
+use std::collections::BTreeMap;
+
+use crate::{rate::DecoderWork, repair::ShardId, Error};
 
 // ======================================================================
 // DecoderResult - PUBLIC
@@ -12,9 +14,125 @@ use crate::rate::DecoderWork;
 /// [`ReedSolomonDecoder::decode`]: crate::ReedSolomonDecoder::decode
 pub struct DecoderResult<'a> {
     work: &'a mut DecoderWork,
+    #[cfg(feature = "stripe-hash")]
+    integrity_hash: Option<[u8; 32]>,
+    untrusted_mismatches: Vec<ShardId>,
+    used_shards: Vec<(ShardId, Vec<u8>)>,
+    unused_shards: Vec<(ShardId, Vec<u8>)>,
 }
 
 impl<'a> DecoderResult<'a> {
+    /// Copies restored original shards into [`OwnedDecoderResult`]
+    /// which doesn't borrow the decoder.
+    ///
+    /// The decoder is reset when `self` is dropped here as usual,
+    /// so shards of the next round can be added while
+    /// the returned result is still in use.
+    ///
+    /// Owned shards which haven't been taken with [`take_used_shards`]
+    /// or [`take_unused_shards`] are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// let original = [[1u8; 64], [2u8; 64]];
+    /// let recovery = reed_solomon_simd::encode(2, 1, original)?;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(2, 1, 64)?;
+    /// decoder.add_original_shard(1, original[1])?;
+    /// decoder.add_recovery_shard(0, &recovery[0])?;
+    /// let result = decoder.decode()?.into_owned();
+    ///
+    /// // Decoder is already usable for the next round.
+    /// decoder.add_original_shard(0, original[0])?;
+    ///
+    /// assert_eq!(result.restored_original(0).unwrap(), original[0]);
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`take_unused_shards`]: DecoderResult::take_unused_shards
+    /// [`take_used_shards`]: DecoderResult::take_used_shards
+    pub fn into_owned(mut self) -> OwnedDecoderResult {
+        OwnedDecoderResult {
+            restored: self
+                .restored_original_iter()
+                .map(|(index, original)| (index, original.to_vec()))
+                .collect(),
+            untrusted_mismatches: std::mem::take(&mut self.untrusted_mismatches),
+        }
+    }
+
+    /// Copies restored original shards with given indexes,
+    /// in the order of `order`.
+    ///
+    /// Same index can be given more than once.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidOriginalShardIndex`] if an index is `>= original_count`.
+    /// - [`Error::OriginalShardNotRestored`] if an original shard
+    ///   was given to decoder, because those aren't kept after decoding.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+    /// let recovery = reed_solomon_simd::encode(3, 2, original)?;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(3, 2, 64)?;
+    /// decoder.add_original_shard(1, original[1])?;
+    /// decoder.add_recovery_shard(0, &recovery[0])?;
+    /// decoder.add_recovery_shard(1, &recovery[1])?;
+    /// let result = decoder.decode()?;
+    ///
+    /// assert_eq!(result.collect_ordered(&[2, 0])?, [original[2], original[0]]);
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    pub fn collect_ordered(&self, order: &[usize]) -> Result<Vec<Vec<u8>>, Error> {
+        order
+            .iter()
+            .map(|&index| {
+                if index >= self.work.original_count() {
+                    Err(Error::InvalidOriginalShardIndex {
+                        original_count: self.work.original_count(),
+                        index,
+                    })
+                } else {
+                    self.work
+                        .restored_original(index)
+                        .map(<[u8]>::to_vec)
+                        .ok_or(Error::OriginalShardNotRestored { index })
+                }
+            })
+            .collect()
+    }
+
+    /// Returns [stripe hash] of all original shards, both given and restored,
+    /// or `None` unless enabled with [`ReedSolomonDecoder::with_stripe_hash`].
+    ///
+    /// **This method is only available with `stripe-hash` feature.**
+    ///
+    /// This equals the hash returned by [`ReedSolomonEncoder::encode_with_stripe_hash`]
+    /// if the whole stripe survived encoding, shard loss and decoding.
+    /// See [`integrity::stripe_hash`] for an example.
+    ///
+    /// [stripe hash]: crate::integrity#stripe-hash
+    /// [`integrity::stripe_hash`]: crate::integrity::stripe_hash
+    /// [`ReedSolomonDecoder::with_stripe_hash`]: crate::ReedSolomonDecoder::with_stripe_hash
+    /// [`ReedSolomonEncoder::encode_with_stripe_hash`]: crate::ReedSolomonEncoder::encode_with_stripe_hash
+    #[cfg(feature = "stripe-hash")]
+    pub fn integrity_hash(&self) -> Option<[u8; 32]> {
+        self.integrity_hash
+    }
+
     /// Returns restored original shard with given `index`
     /// or `None` if given `index` doesn't correspond to
     /// a missing original shard.
@@ -27,6 +145,42 @@ impl<'a> DecoderResult<'a> {
     pub fn restored_original_iter(&self) -> RestoredOriginal {
         RestoredOriginal::new(self.work)
     }
+
+    /// Returns untrusted shards which were used only for cross-checking
+    /// and didn't match the decoded data, in increasing order.
+    ///
+    /// See [`ReedSolomonDecoder::add_untrusted_shard`].
+    ///
+    /// [`ReedSolomonDecoder::add_untrusted_shard`]: crate::ReedSolomonDecoder::add_untrusted_shard
+    pub fn untrusted_mismatches(&self) -> &[ShardId] {
+        &self.untrusted_mismatches
+    }
+
+    /// Takes owned shards which were copied into working space
+    /// and used for decoding, in increasing order.
+    ///
+    /// Content of the shards is unchanged.
+    /// Returns empty `Vec` if called again.
+    ///
+    /// See [`ReedSolomonDecoder::add_original_shard_owned`].
+    ///
+    /// [`ReedSolomonDecoder::add_original_shard_owned`]: crate::ReedSolomonDecoder::add_original_shard_owned
+    pub fn take_used_shards(&mut self) -> Vec<(ShardId, Vec<u8>)> {
+        std::mem::take(&mut self.used_shards)
+    }
+
+    /// Takes owned shards which weren't needed for decoding,
+    /// in increasing order.
+    ///
+    /// Content of the shards is unchanged.
+    /// Returns empty `Vec` if called again.
+    ///
+    /// See [`ReedSolomonDecoder::add_original_shard_owned`].
+    ///
+    /// [`ReedSolomonDecoder::add_original_shard_owned`]: crate::ReedSolomonDecoder::add_original_shard_owned
+    pub fn take_unused_shards(&mut self) -> Vec<(ShardId, Vec<u8>)> {
+        std::mem::take(&mut self.unused_shards)
+    }
 }
 
 // ======================================================================
@@ -34,7 +188,32 @@ impl<'a> DecoderResult<'a> {
 
 impl<'a> DecoderResult<'a> {
     pub(crate) fn new(work: &'a mut DecoderWork) -> Self {
-        Self { work }
+        Self {
+            work,
+            #[cfg(feature = "stripe-hash")]
+            integrity_hash: None,
+            untrusted_mismatches: Vec::new(),
+            used_shards: Vec::new(),
+            unused_shards: Vec::new(),
+        }
+    }
+
+    #[cfg(feature = "stripe-hash")]
+    pub(crate) fn set_integrity_hash(&mut self, integrity_hash: [u8; 32]) {
+        self.integrity_hash = Some(integrity_hash);
+    }
+
+    pub(crate) fn set_owned_shards(
+        &mut self,
+        used_shards: Vec<(ShardId, Vec<u8>)>,
+        unused_shards: Vec<(ShardId, Vec<u8>)>,
+    ) {
+        self.used_shards = used_shards;
+        self.unused_shards = unused_shards;
+    }
+
+    pub(crate) fn set_untrusted_mismatches(&mut self, untrusted_mismatches: Vec<ShardId>) {
+        self.untrusted_mismatches = untrusted_mismatches;
     }
 }
 
@@ -47,6 +226,48 @@ impl<'a> Drop for DecoderResult<'a> {
     }
 }
 
+// ======================================================================
+// OwnedDecoderResult - PUBLIC
+
+/// Result of decoding which owns the restored original shards.
+///
+/// This struct is created by [`DecoderResult::into_owned`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnedDecoderResult {
+    restored: BTreeMap<usize, Vec<u8>>,
+    untrusted_mismatches: Vec<ShardId>,
+}
+
+impl OwnedDecoderResult {
+    /// Returns restored original shards by their indexes.
+    pub fn into_restored(self) -> BTreeMap<usize, Vec<u8>> {
+        self.restored
+    }
+
+    /// Returns restored original shard with given `index`
+    /// or `None` if given `index` doesn't correspond to
+    /// a missing original shard.
+    pub fn restored_original(&self, index: usize) -> Option<&[u8]> {
+        self.restored.get(&index).map(Vec::as_slice)
+    }
+
+    /// Returns iterator over all restored original shards
+    /// and their indexes, ordered by indexes.
+    pub fn restored_original_iter(&self) -> impl Iterator<Item = (usize, &[u8])> {
+        self.restored
+            .iter()
+            .map(|(index, original)| (*index, original.as_slice()))
+    }
+
+    /// Returns untrusted shards which were used only for cross-checking
+    /// and didn't match the decoded data, in increasing order.
+    ///
+    /// See [`DecoderResult::untrusted_mismatches`].
+    pub fn untrusted_mismatches(&self) -> &[ShardId] {
+        &self.untrusted_mismatches
+    }
+}
+
 // ======================================================================
 // RestoredOriginal - PUBLIC
 
@@ -98,7 +319,7 @@ impl<'a> RestoredOriginal<'a> {
 // ======================================================================
 // TESTS
 
-#[cfg(test)]
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
 mod tests {
     use super::*;
     use crate::{test_util, ReedSolomonDecoder, ReedSolomonEncoder};
@@ -137,4 +358,90 @@ mod tests {
         assert_eq!(iter.next(), None);
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    // DecoderResult::collect_ordered
+    fn collect_ordered() {
+        let original = test_util::generate_original(8, 128, 0);
+        let recovery = crate::encode(8, 6, &original).unwrap();
+
+        let mut decoder = ReedSolomonDecoder::new(8, 6, 128).unwrap();
+        for index in [1, 4] {
+            decoder.add_original_shard(index, &original[index]).unwrap();
+        }
+        for (index, recovery) in recovery.iter().enumerate() {
+            decoder.add_recovery_shard(index, recovery).unwrap();
+        }
+        let result = decoder.decode().unwrap();
+
+        let order = [6, 0, 7, 3, 5, 2, 0];
+        let expected: Vec<_> = order.iter().map(|&index| original[index].clone()).collect();
+        assert_eq!(result.collect_ordered(&order).unwrap(), expected);
+        assert!(result.collect_ordered(&[]).unwrap().is_empty());
+
+        assert_eq!(
+            result.collect_ordered(&[0, 8]),
+            Err(Error::InvalidOriginalShardIndex {
+                original_count: 8,
+                index: 8
+            })
+        );
+        assert_eq!(
+            result.collect_ordered(&[0, 4]),
+            Err(Error::OriginalShardNotRestored { index: 4 })
+        );
+    }
+
+    #[test]
+    // DecoderResult::into_owned
+    // OwnedDecoderResult
+    fn into_owned_pipelined() {
+        let stripes: Vec<_> = (0..3)
+            .map(|seed| test_util::generate_original(3, 1024, seed))
+            .collect();
+        let recovery: Vec<_> = stripes
+            .iter()
+            .map(|original| crate::encode(3, 2, original).unwrap())
+            .collect();
+
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 1024).unwrap();
+
+        // Stripe 0 is added, later stripes are added while
+        // result of previous stripe is still being read.
+        decoder.add_original_shard(1, &stripes[0][1]).unwrap();
+        decoder.add_recovery_shard(0, &recovery[0][0]).unwrap();
+        decoder.add_recovery_shard(1, &recovery[0][1]).unwrap();
+        let mut previous = decoder.decode().unwrap().into_owned();
+
+        for stripe in 1..stripes.len() {
+            decoder.add_original_shard(1, &stripes[stripe][1]).unwrap();
+            assert_eq!(
+                previous.restored_original(0).unwrap(),
+                stripes[stripe - 1][0]
+            );
+            decoder.add_recovery_shard(0, &recovery[stripe][0]).unwrap();
+            assert_eq!(
+                previous.restored_original(2).unwrap(),
+                stripes[stripe - 1][2]
+            );
+            decoder.add_recovery_shard(1, &recovery[stripe][1]).unwrap();
+
+            assert!(previous.restored_original(1).is_none());
+            assert!(previous.untrusted_mismatches().is_empty());
+            assert_eq!(
+                previous
+                    .restored_original_iter()
+                    .map(|(index, _)| index)
+                    .collect::<Vec<_>>(),
+                [0, 2]
+            );
+
+            previous = decoder.decode().unwrap().into_owned();
+        }
+
+        let restored = previous.into_restored();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored[&0], stripes[2][0]);
+        assert_eq!(restored[&2], stripes[2][2]);
+    }
 }
@@ -15,6 +15,19 @@ pub struct EncoderResult<'a> {
 }
 
 impl<'a> EncoderResult<'a> {
+    /// Returns original shards which were identical to an earlier
+    /// original shard, as `(index, first_index)` pairs in increasing
+    /// order of `index`, where `first_index` is the earliest identical shard.
+    ///
+    /// This is always empty unless deduplication has been enabled with
+    /// [`ReedSolomonEncoder::with_deduplication`] or [`EncoderWork::set_deduplication`].
+    ///
+    /// [`EncoderWork::set_deduplication`]: crate::rate::EncoderWork::set_deduplication
+    /// [`ReedSolomonEncoder::with_deduplication`]: crate::ReedSolomonEncoder::with_deduplication
+    pub fn duplicate_originals(&self) -> &[(usize, usize)] {
+        self.work.duplicate_originals()
+    }
+
     /// Returns recovery shard with given `index`
     /// or `None` if `index >= recovery_count`.
     ///
@@ -24,6 +37,32 @@ impl<'a> EncoderResult<'a> {
         self.work.recovery(index)
     }
 
+    /// Returns all recovery shards as one contiguous slice
+    /// of `recovery_count * shard_bytes` bytes.
+    ///
+    /// Recovery shards are laid out in order of their indexes,
+    /// so recovery shard `i` is `recovery_flat()[i * shard_bytes..][..shard_bytes]`.
+    /// This is the same for all rates, and no copying is done.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonEncoder;
+    ///
+    /// let mut encoder = ReedSolomonEncoder::new(2, 3, 64)?;
+    /// encoder.add_original_shard([1u8; 64])?;
+    /// encoder.add_original_shard([2u8; 64])?;
+    /// let result = encoder.encode()?;
+    ///
+    /// let flat = result.recovery_flat();
+    /// assert_eq!(flat.len(), 3 * 64);
+    /// assert_eq!(&flat[64..128], result.recovery(1).unwrap());
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    pub fn recovery_flat(&self) -> &[u8] {
+        self.work.recovery_flat()
+    }
+
     /// Returns iterator over all recovery shards ordered by their indexes.
     ///
     /// Recovery shards have indexes `0..recovery_count`
@@ -38,8 +77,18 @@ impl<'a> EncoderResult<'a> {
 
 impl<'a> EncoderResult<'a> {
     pub(crate) fn new(work: &'a mut EncoderWork) -> Self {
+        // Otherwise `recovery_iter` would silently end early.
+        assert!(
+            work.recovery_count() <= work.work_count(),
+            "working space must contain all recovery shards"
+        );
+
         Self { work }
     }
+
+    pub(crate) fn recovery_mut(&mut self, index: usize) -> Option<&mut [u8]> {
+        self.work.recovery_mut(index)
+    }
 }
 
 // ======================================================================
@@ -97,7 +146,7 @@ impl<'a> Recovery<'a> {
 // ======================================================================
 // TESTS
 
-#[cfg(test)]
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
 mod tests {
     use super::*;
     use crate::{test_util, ReedSolomonEncoder};
@@ -131,4 +180,22 @@ mod tests {
         assert!(iter.next().is_none());
         test_util::assert_hash(all, test_util::LOW_2_3);
     }
+
+    #[test]
+    // EncoderResult::recovery_flat
+    fn recovery_flat() {
+        // Low rate, high rate and both with partial chunks.
+        for (original_count, recovery_count) in [(2, 3), (3, 2), (5, 37), (37, 5)] {
+            let original = test_util::generate_original(original_count, 128, 173);
+            let mut encoder = ReedSolomonEncoder::new(original_count, recovery_count, 128).unwrap();
+
+            for original in &original {
+                encoder.add_original_shard(original).unwrap();
+            }
+
+            let result = encoder.encode().unwrap();
+            let expected: Vec<u8> = result.recovery_iter().flatten().copied().collect();
+            assert_eq!(result.recovery_flat(), expected);
+        }
+    }
 }
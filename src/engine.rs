@@ -22,9 +22,34 @@
 //!     - Optimized engine that takes advantage of the x86(-64) SSSE3 SIMD instructions.
 //! - [`Neon`]
 //!     - Optimized engine that takes advantage of the AArch64 Neon SIMD instructions.
+//! - `PortableSimd`
+//!     - Engine using [`std::simd`] gathers for targets without byte shuffles.
+//!     - Only available with `portable-simd` feature, which requires nightly Rust.
 //! - [`DefaultEngine`]
 //!     - Default engine which is used when no specific engine is given.
 //!     - Automatically selects best engine at runtime.
+//! - [`Instrumented`]
+//!     - Wraps another engine, counting operations for algorithm analysis.
+//!
+//! # Additive FFT
+//!
+//! Reed-Solomon coding in this crate is built on the additive FFT
+//! over `GF(2^16)` of Lin, Chung and Han. It is also available
+//! as a standalone transform with [`additive_fft`] and [`additive_ifft`],
+//! e.g. for polynomial multiplication.
+//!
+//! Polynomials are represented by their coefficients in the
+//! *novel polynomial basis* `X_0, X_1, ...` where
+//!
+//! ```text
+//! W_i(x)  = product of (x - u) for u in 0 .. 2^i
+//! Ŵ_i(x)  = W_i(x) / W_i(2^i)
+//! X_j(x)  = product of Ŵ_i(x) for each bit i set in j
+//! ```
+//!
+//! Here integers stand for the [`GfElement`]:s with the same value.
+//! These are in [Cantor basis](CANTOR_BASIS), so e.g. `0 .. 2^i`
+//! is closed under addition (`XOR`).
 //!
 //! [simple usage]: crate#simple-usage
 //! [basic usage]: crate#basic-usage
@@ -37,7 +62,11 @@ use std::iter::zip;
 pub(crate) use self::shards::Shards;
 
 pub use self::{
-    engine_default::DefaultEngine, engine_naive::Naive, engine_nosimd::NoSimd, shards::ShardsRefMut,
+    engine_default::DefaultEngine,
+    engine_instrumented::{Instrumented, OpCount, OpCounts},
+    engine_naive::Naive,
+    engine_nosimd::NoSimd,
+    shards::ShardsRefMut,
 };
 
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
@@ -46,7 +75,11 @@ pub use self::{engine_avx2::Avx2, engine_ssse3::Ssse3};
 #[cfg(target_arch = "aarch64")]
 pub use self::engine_neon::Neon;
 
+#[cfg(feature = "portable-simd")]
+pub use self::engine_portable_simd::PortableSimd;
+
 mod engine_default;
+mod engine_instrumented;
 mod engine_naive;
 mod engine_nosimd;
 
@@ -58,11 +91,17 @@ mod engine_ssse3;
 #[cfg(target_arch = "aarch64")]
 mod engine_neon;
 
+#[cfg(feature = "portable-simd")]
+mod engine_portable_simd;
+
+#[cfg(feature = "decoder")]
 mod fwht;
 mod shards;
 
 pub mod tables;
 
+use crate::{engine::tables::Skew, Error};
+
 // ======================================================================
 // CONST - PUBLIC
 
@@ -78,12 +117,21 @@ pub const GF_MODULUS: GfElement = 65535;
 /// Galois field polynomial.
 pub const GF_POLYNOMIAL: usize = 0x1002D;
 
+/// Length in bytes from which [`Engine::copy`] uses [`Engine::copy_nt`].
+pub const COPY_NT_THRESHOLD: usize = 1 << 20;
+
 /// TODO
 pub const CANTOR_BASIS: [GfElement; GF_BITS] = [
     0x0001, 0xACCA, 0x3C0E, 0x163E, 0xC582, 0xED2E, 0x914C, 0x4012, 0x6C98, 0x10D8, 0x6A72, 0xB900,
     0xFDB8, 0xFB34, 0xFF38, 0x991E,
 ];
 
+// ======================================================================
+// CONST - PRIVATE
+
+// Maximum bytes per shard range of batched butterflies, see `butterfly_batch`.
+const BUTTERFLY_BATCH_BYTES: usize = 4096;
+
 // ======================================================================
 // TYPE ALIASES - PUBLIC
 
@@ -113,10 +161,20 @@ pub fn sub_mod(x: GfElement, y: GfElement) -> GfElement {
 // We have this function here instead of inside 'trait Engine' to allow
 // it to be included and compiled with SIMD features enabled within the
 // SIMD engines.
+#[cfg(feature = "decoder")]
 #[inline(always)]
 pub(crate) fn eval_poly(erasures: &mut [GfElement; GF_ORDER], truncated_size: usize) {
-    let log_walsh = tables::initialize_log_walsh();
+    eval_poly_with_log_walsh(erasures, truncated_size, tables::initialize_log_walsh())
+}
 
+// Like `eval_poly` but with given `LogWalsh` table.
+#[cfg(feature = "decoder")]
+#[inline(always)]
+pub(crate) fn eval_poly_with_log_walsh(
+    erasures: &mut [GfElement; GF_ORDER],
+    truncated_size: usize,
+    log_walsh: &tables::LogWalsh,
+) {
     fwht::fwht(erasures, truncated_size);
 
     for (e, factor) in std::iter::zip(erasures.iter_mut(), log_walsh.iter()) {
@@ -127,6 +185,147 @@ pub(crate) fn eval_poly(erasures: &mut [GfElement; GF_ORDER], truncated_size: us
     fwht::fwht(erasures, GF_ORDER);
 }
 
+// ======================================================================
+// FUNCTIONS - CRATE - Linear combination
+
+// Checks arguments of `Engine::linear_combination` and returns
+// inputs with logarithms of their coefficients, skipping zero coefficients.
+pub(crate) fn linear_combination_terms<'a>(
+    out: &[u8],
+    inputs: &[&'a [u8]],
+    coeffs: &[GfElement],
+) -> Vec<(&'a [u8], GfElement)> {
+    let (_, log) = tables::initialize_exp_log();
+    linear_combination_terms_with_log(out, inputs, coeffs, log)
+}
+
+// Like `linear_combination_terms` but with given `Log` table.
+pub(crate) fn linear_combination_terms_with_log<'a>(
+    out: &[u8],
+    inputs: &[&'a [u8]],
+    coeffs: &[GfElement],
+    log: &tables::Log,
+) -> Vec<(&'a [u8], GfElement)> {
+    assert_eq!(
+        inputs.len(),
+        coeffs.len(),
+        "inputs and coeffs must have same length"
+    );
+    assert!(out.len() & 63 == 0, "out length must be a multiple of 64");
+
+    zip(inputs, coeffs)
+        .filter(|(_, &coeff)| coeff != 0)
+        .map(|(&input, &coeff)| {
+            assert_eq!(
+                input.len(),
+                out.len(),
+                "inputs must have same length as out"
+            );
+            (input, log[usize::from(coeff)])
+        })
+        .collect()
+}
+
+// ======================================================================
+// FUNCTIONS - PUBLIC - Additive FFT
+
+/// Evaluates polynomials given in novel polynomial basis,
+/// see [Additive FFT](self#additive-fft).
+///
+/// Let `n = data.len()`.
+///
+/// - Before function call `data[j]` contains coefficient of `X_j`.
+/// - After function call `data[i]` contains value of the polynomial
+///   at point `offset + i`.
+/// - Each 64-byte block of a shard holds 32 independent polynomials,
+///   laid out as described in [`algorithm`] (`data[j]` low bytes
+///   at `0 .. 32` and high bytes at `32 .. 64`).
+///
+/// This is the inverse of [`additive_ifft`] with the same `offset`.
+///
+/// # Panics
+///
+/// If `n` is not a power of two, `offset` is not a multiple of `n`
+/// or `offset + n > GF_ORDER`.
+///
+/// # Examples
+///
+/// Multiplying two polynomials of degree `< 4`:
+///
+/// ```rust
+/// use reed_solomon_simd::engine::{self, DefaultEngine, ShardsRefMut};
+/// use reed_solomon_simd::gf::Gf16;
+///
+/// // Polynomials of degree `< 8` in the first lane of 64-byte shards.
+/// fn to_shards(coefs: &[u16]) -> Vec<u8> {
+///     let mut bytes = vec![0; 8 * 64];
+///     for (j, c) in coefs.iter().enumerate() {
+///         bytes[j * 64] = *c as u8;
+///         bytes[j * 64 + 32] = (*c >> 8) as u8;
+///     }
+///     bytes
+/// }
+///
+/// fn get(bytes: &[u8], i: usize) -> Gf16 {
+///     Gf16(u16::from(bytes[i * 64]) | u16::from(bytes[i * 64 + 32]) << 8)
+/// }
+///
+/// let engine = DefaultEngine::new();
+///
+/// let mut a = to_shards(&[1, 2, 3, 4]);
+/// let mut b = to_shards(&[5, 6, 7, 8]);
+///
+/// engine::additive_fft(&engine, &mut ShardsRefMut::new(8, 64, &mut a), 0);
+/// engine::additive_fft(&engine, &mut ShardsRefMut::new(8, 64, &mut b), 0);
+///
+/// // Pointwise product of values at points `0 .. 8`.
+/// let product: Vec<u16> = (0..8).map(|i| (get(&a, i) * get(&b, i)).0).collect();
+///
+/// // Product has degree `< 8` so 8 values determine it.
+/// let mut c = to_shards(&product);
+/// engine::additive_ifft(&engine, &mut ShardsRefMut::new(8, 64, &mut c), 0);
+///
+/// // `c` now contains coefficients of the product.
+/// // Check it at some other points.
+/// engine::additive_fft(&engine, &mut ShardsRefMut::new(8, 64, &mut c), 64);
+/// let mut a = to_shards(&[1, 2, 3, 4]);
+/// let mut b = to_shards(&[5, 6, 7, 8]);
+/// engine::additive_fft(&engine, &mut ShardsRefMut::new(8, 64, &mut a), 64);
+/// engine::additive_fft(&engine, &mut ShardsRefMut::new(8, 64, &mut b), 64);
+///
+/// for i in 0..8 {
+///     assert_eq!(get(&c, i), get(&a, i) * get(&b, i));
+/// }
+/// ```
+///
+/// [`algorithm`]: crate::algorithm
+pub fn additive_fft<E: Engine + ?Sized>(engine: &E, data: &mut ShardsRefMut, offset: usize) {
+    let size = data.len();
+    assert_additive_fft_args(size, offset);
+    engine.fft(data, 0, size, size, offset);
+}
+
+/// Interpolates polynomials in novel polynomial basis,
+/// see [Additive FFT](self#additive-fft).
+///
+/// Let `n = data.len()`.
+///
+/// - Before function call `data[i]` contains value of the polynomial
+///   at point `offset + i`.
+/// - After function call `data[j]` contains coefficient of `X_j`.
+///
+/// This is the inverse of [`additive_fft`] with the same `offset`.
+///
+/// # Panics
+///
+/// If `n` is not a power of two, `offset` is not a multiple of `n`
+/// or `offset + n > GF_ORDER`.
+pub fn additive_ifft<E: Engine + ?Sized>(engine: &E, data: &mut ShardsRefMut, offset: usize) {
+    let size = data.len();
+    assert_additive_fft_args(size, offset);
+    engine.ifft(data, 0, size, size, offset);
+}
+
 // ======================================================================
 // FUNCTIONS - PUBLIC - misc
 
@@ -155,6 +354,223 @@ pub fn checked_next_multiple_of(a: usize, b: usize) -> Option<usize> {
     }
 }
 
+/// Converts shard bytes to [`GfElement`]:s in the order
+/// the [`Engine`]:s process them.
+///
+/// Shard is processed in 64-byte blocks, each holding 32 elements:
+/// element `j` of a block has its low byte at `j` and
+/// its high byte at `32 + j`. See [`algorithm`] for details.
+///
+/// Bytes are combined explicitly, so result is the same
+/// regardless of endianness of the host.
+///
+/// Returns [`Error::InvalidShardSize`] if shard size is not
+/// non-zero multiple of 64 bytes.
+///
+/// # Examples
+///
+/// ```rust
+/// use reed_solomon_simd::engine;
+///
+/// let mut shard = [0u8; 64];
+/// shard[0] = 0x34;
+/// shard[32] = 0x12;
+///
+/// let elements = engine::shard_bytes_to_elements(&shard)?;
+/// assert_eq!(elements[0], 0x1234);
+/// assert_eq!(engine::elements_to_shard_bytes(&elements)?, shard);
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+///
+/// [`algorithm`]: crate::algorithm
+pub fn shard_bytes_to_elements(shard: &[u8]) -> Result<Vec<GfElement>, Error> {
+    if shard.is_empty() || shard.len() & 63 != 0 {
+        return Err(Error::InvalidShardSize {
+            shard_bytes: shard.len(),
+        });
+    }
+
+    Ok(shard
+        .chunks_exact(64)
+        .flat_map(|block| {
+            let (lo, hi) = block.split_at(32);
+            zip(lo, hi).map(|(&lo, &hi)| GfElement::from(lo) | GfElement::from(hi) << 8)
+        })
+        .collect())
+}
+
+/// Converts [`GfElement`]:s to shard bytes,
+/// i.e. inverse of [`shard_bytes_to_elements`].
+///
+/// Returns [`Error::InvalidShardSize`] if number of elements
+/// is not non-zero multiple of 32.
+pub fn elements_to_shard_bytes(elements: &[GfElement]) -> Result<Vec<u8>, Error> {
+    if elements.is_empty() || elements.len() & 31 != 0 {
+        return Err(Error::InvalidShardSize {
+            shard_bytes: elements.len() * 2,
+        });
+    }
+
+    let mut shard = vec![0; elements.len() * 2];
+    for (block, elements) in zip(shard.chunks_exact_mut(64), elements.chunks_exact(32)) {
+        let (lo, hi) = block.split_at_mut(32);
+        for (i, &element) in elements.iter().enumerate() {
+            lo[i] = element as u8;
+            hi[i] = (element >> 8) as u8;
+        }
+    }
+    Ok(shard)
+}
+
+// ======================================================================
+// FUNCTIONS - PUBLIC - Engine selection
+
+/// Returns kinds of engines which are supported on current CPU,
+/// in order of preference.
+///
+/// - [`DefaultEngine`] uses the first one which passes its self-test,
+///   see [`DefaultEngine::new`]. This is never [`Naive`].
+/// - [`Naive`] and [`NoSimd`] are always included.
+///
+/// # Examples
+///
+/// ```rust
+/// use reed_solomon_simd::engine::{self, EngineKind};
+///
+/// let engines = engine::available_engines();
+/// assert!(engines.contains(&EngineKind::NoSimd));
+/// assert_eq!(engines.last(), Some(&EngineKind::Naive));
+/// ```
+pub fn available_engines() -> Vec<EngineKind> {
+    let mut engines = Vec::new();
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if Avx2::supported() {
+            engines.push(EngineKind::Avx2);
+        }
+        if Ssse3::supported() {
+            engines.push(EngineKind::Ssse3);
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        if Neon::supported() {
+            engines.push(EngineKind::Neon);
+        }
+    }
+
+    engines.push(EngineKind::NoSimd);
+    engines.push(EngineKind::Naive);
+    engines
+}
+
+// ======================================================================
+// FUNCTIONS - CRATE - FFT / IFFT
+
+// Returns number of shard pairs per call of batched butterflies
+// in a two-layer step of FFT / IFFT with given `dist`.
+// - This is a power of two which is at most `dist`.
+// - Shard ranges are at most `BUTTERFLY_BATCH_BYTES`, so that all four
+//   ranges of a batch stay in L1 cache between the two layers.
+pub(crate) fn butterfly_batch(shard_bytes: usize, dist: usize) -> usize {
+    let batch = BUTTERFLY_BATCH_BYTES
+        .checked_div(shard_bytes)
+        .unwrap_or(dist)
+        .max(1);
+    std::cmp::min(1 << batch.ilog2(), dist)
+}
+
+// Returns steps of FFT of given `size` as `(dist, layers)` in order.
+// - `layers` is 1 or 2 and `dist` is the smallest butterfly distance
+//   of the step, so that the step works on blocks of `dist << layers`.
+// - A single layer is needed only if `log2(size)` is odd. It's done first,
+//   where `dist` is largest and its butterflies are done in one call per block,
+//   so that no separate pass over pairs of adjacent shards is needed.
+pub(crate) fn fft_steps(size: usize) -> impl Iterator<Item = (usize, u32)> {
+    let log_size = size.trailing_zeros();
+    let one_layer = (log_size % 2 == 1).then_some((size >> 1, 1));
+    let two_layers = (0..log_size / 2).rev().map(|k| (1 << (2 * k), 2));
+    one_layer.into_iter().chain(two_layers)
+}
+
+// Returns steps of IFFT of given `size` as `(dist, layers)` in order,
+// i.e. steps of `fft_steps` reversed.
+pub(crate) fn ifft_steps(size: usize) -> impl Iterator<Item = (usize, u32)> {
+    let log_size = size.trailing_zeros();
+    let one_layer = (log_size % 2 == 1).then_some((size >> 1, 1));
+    let two_layers = (0..log_size / 2).map(|k| (1 << (2 * k), 2));
+    two_layers.chain(one_layer)
+}
+
+// Asserts FFT / IFFT arguments which determine the `Skew` indexes
+// that engines read, see `Engine::fft`.
+// - All indexes are then at most `size + skew_delta - 2 < GF_MODULUS`,
+//   so engines can read them with `skew_at`.
+#[inline(always)]
+pub(crate) fn check_fft_params(size: usize, truncated_size: usize, skew_delta: usize) {
+    if !size.is_power_of_two()
+        || truncated_size > size
+        || skew_delta > GF_ORDER
+        || size > GF_ORDER - skew_delta
+    {
+        fft_params_panic(size, truncated_size, skew_delta);
+    }
+}
+
+// Returns `skew[index]`, checking the index only in debug builds.
+//
+// # Safety
+//
+// `index` must be below `GF_MODULUS`, which is the case for all
+// indexes read by FFT / IFFT after `check_fft_params`.
+#[inline(always)]
+pub(crate) unsafe fn skew_at(skew: &Skew, index: usize) -> GfElement {
+    debug_assert!(index < skew.len(), "skew index {} out of bounds", index);
+    unsafe { *skew.get_unchecked(index) }
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE - FFT / IFFT
+
+#[cold]
+#[inline(never)]
+fn fft_params_panic(size: usize, truncated_size: usize, skew_delta: usize) -> ! {
+    panic!(
+        "invalid FFT / IFFT arguments: size {}, truncated_size {}, skew_delta {} \
+         (size must be a power of two, truncated_size <= size \
+         and skew_delta + size <= {})",
+        size, truncated_size, skew_delta, GF_ORDER
+    )
+}
+
+// ======================================================================
+// FUNCTIONS - CRATE - XOR
+
+// Asserts arguments of `Engine::xor_into`.
+pub(crate) fn assert_xor_into_args(out: &[u8], a: &[u8], b: &[u8]) {
+    assert!(
+        out.len() == a.len() && out.len() == b.len(),
+        "out, a and b must have same length"
+    );
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+fn assert_additive_fft_args(size: usize, offset: usize) {
+    assert!(size.is_power_of_two(), "size must be a power of two");
+    assert!(
+        offset & (size - 1) == 0,
+        "offset must be a multiple of size"
+    );
+    assert!(
+        offset + size <= GF_ORDER,
+        "offset + size must be <= GF_ORDER"
+    );
+}
+
 // ======================================================================
 // Engine - PUBLIC
 
@@ -173,6 +589,8 @@ pub trait Engine {
     ///
     /// - FFT is done on chunk `data[pos .. pos + size]`
     /// - `size` must be `2^n`
+    /// - `truncated_size <= size` and `skew_delta + size <= GF_ORDER`,
+    ///   engines provided by this crate panic otherwise.
     /// - Before function call `data[pos .. pos + size]` must be valid.
     /// - After function call
     ///     - `data[pos .. pos + truncated_size]`
@@ -193,6 +611,8 @@ pub trait Engine {
     ///
     /// - IFFT is done on chunk `data[pos .. pos + size]`
     /// - `size` must be `2^n`
+    /// - `truncated_size <= size` and `skew_delta + size <= GF_ORDER`,
+    ///   engines provided by this crate panic otherwise.
     /// - Before function call `data[pos .. pos + size]` must be valid.
     /// - After function call
     ///     - `data[pos .. pos + truncated_size]`
@@ -215,6 +635,72 @@ pub trait Engine {
     // ============================================================
     // PROVIDED
 
+    /// Returns `true` if this engine can be used on current CPU.
+    ///
+    /// Engines using SIMD instructions detect them at runtime,
+    /// unless enabled at compile time, and are never supported under Miri
+    /// so that [`DefaultEngine`] falls back to [`NoSimd`] there.
+    /// Default implementation returns `true`.
+    ///
+    /// Creating an engine which isn't supported is allowed,
+    /// but using it is undefined behavior.
+    fn supported() -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
+
+    /// `dst[] = src[]` using non-temporal stores where available.
+    ///
+    /// This is meant for large copies which aren't read again soon,
+    /// so that they don't evict data which is still needed from cache.
+    /// Default implementation is [`slice::copy_from_slice`].
+    ///
+    /// # Panics
+    ///
+    /// If `dst` and `src` have different lengths.
+    fn copy_nt(dst: &mut [u8], src: &[u8])
+    where
+        Self: Sized,
+    {
+        dst.copy_from_slice(src);
+    }
+
+    /// `dst[] = src[]` for shard data which isn't read again soon.
+    ///
+    /// Copies of at least [`COPY_NT_THRESHOLD`] bytes use [`copy_nt`],
+    /// smaller ones [`slice::copy_from_slice`]. Non-temporal stores only
+    /// pay off when the copy is much larger than cache, and data copied
+    /// with them is slower to read back, so this shouldn't be used
+    /// for data which is processed right after copying.
+    ///
+    /// # Panics
+    ///
+    /// If `dst` and `src` have different lengths.
+    ///
+    /// [`copy_nt`]: Engine::copy_nt
+    fn copy(dst: &mut [u8], src: &[u8])
+    where
+        Self: Sized,
+    {
+        if src.len() >= COPY_NT_THRESHOLD {
+            Self::copy_nt(dst, src);
+        } else {
+            dst.copy_from_slice(src);
+        }
+    }
+
+    /// `x[] = byte`, e.g. zero-padding shards before transforms.
+    ///
+    /// Default implementation is [`slice::fill`].
+    fn fill(x: &mut [u8], byte: u8)
+    where
+        Self: Sized,
+    {
+        x.fill(byte);
+    }
+
     /// `x[] ^= y[]`
     #[inline(always)]
     fn xor(xs: &mut [u8], ys: &[u8])
@@ -231,7 +717,29 @@ pub trait Engine {
         }
     }
 
+    /// `out[] = a[] ^ b[]`, i.e. difference of `a` and `b` in `GF(2^16)`.
+    ///
+    /// This is same as copying `a` to `out` and then [`xor`] with `b`,
+    /// but in one pass. Default implementation XORs bytewise.
+    ///
+    /// # Panics
+    ///
+    /// If `out`, `a` and `b` don't all have same length.
+    ///
+    /// [`xor`]: Engine::xor
+    fn xor_into(out: &mut [u8], a: &[u8], b: &[u8])
+    where
+        Self: Sized,
+    {
+        assert_xor_into_args(out, a, b);
+
+        for (out, (a, b)) in zip(out.iter_mut(), zip(a, b)) {
+            *out = a ^ b;
+        }
+    }
+
     /// Evaluate polynomial.
+    #[cfg(feature = "decoder")]
     fn eval_poly(erasures: &mut [GfElement; GF_ORDER], truncated_size: usize)
     where
         Self: Sized,
@@ -239,6 +747,62 @@ pub trait Engine {
         eval_poly(erasures, truncated_size)
     }
 
+    /// Evaluate polynomial with tables of this engine instance.
+    ///
+    /// Decoders call this instead of [`eval_poly`], so that engines
+    /// with custom tables, like [`NoSimd::with_tables`], can use
+    /// their own [`LogWalsh`] table.
+    ///
+    /// Default implementation calls [`eval_poly`].
+    ///
+    /// Requires `unstable-basis` feature.
+    ///
+    /// [`eval_poly`]: Engine::eval_poly
+    /// [`LogWalsh`]: crate::engine::tables::LogWalsh
+    #[cfg(all(feature = "decoder", feature = "unstable-basis"))]
+    fn eval_poly_with_tables(&self, erasures: &mut [GfElement; GF_ORDER], truncated_size: usize)
+    where
+        Self: Sized,
+    {
+        Self::eval_poly(erasures, truncated_size)
+    }
+
+    /// FFT butterflies of `count` independent shard pairs
+    /// `data[x + i]`, `data[y + i]` which share the same `log_m`:
+    /// `x ^= y * log_m` and then `y ^= x`.
+    ///
+    /// - `log_m == GF_MODULUS` means multiplying with zero,
+    ///   so then only `y ^= x` is done.
+    /// - Ranges `x .. x + count` and `y .. y + count` must not overlap.
+    ///
+    /// Default implementation multiplies a copy of the `y` shards
+    /// with [`mul`], engines provided by this crate instead process
+    /// all pairs in one loop with the multiplication table loaded once.
+    /// Their [`fft`] uses this in two-layer steps with `dist >= 4`.
+    ///
+    /// [`fft`]: Engine::fft
+    /// [`mul`]: Engine::mul
+    fn fft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        let (xs, ys) = data.flat2_mut(x, y, count);
+        if log_m != GF_MODULUS {
+            let mut product = ys.to_vec();
+            self.mul(&mut product, log_m);
+            for (x, p) in zip(xs.iter_mut(), &product) {
+                *x ^= p;
+            }
+        }
+        for (y, x) in zip(ys.iter_mut(), xs.iter()) {
+            *y ^= x;
+        }
+    }
+
     /// FFT with `skew_delta = pos + size`.
     #[inline(always)]
     fn fft_skew_end(
@@ -262,6 +826,35 @@ pub trait Engine {
         }
     }
 
+    /// IFFT butterflies of `count` independent shard pairs
+    /// `data[x + i]`, `data[y + i]` which share the same `log_m`:
+    /// `y ^= x` and then `x ^= y * log_m`.
+    ///
+    /// This is the inverse of [`fft_butterflies`] with same arguments,
+    /// see it for details.
+    ///
+    /// [`fft_butterflies`]: Engine::fft_butterflies
+    fn ifft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        let (xs, ys) = data.flat2_mut(x, y, count);
+        for (y, x) in zip(ys.iter_mut(), xs.iter()) {
+            *y ^= x;
+        }
+        if log_m != GF_MODULUS {
+            let mut product = ys.to_vec();
+            self.mul(&mut product, log_m);
+            for (x, p) in zip(xs.iter_mut(), &product) {
+                *x ^= p;
+            }
+        }
+    }
+
     /// IFFT with `skew_delta = pos + size`.
     #[inline(always)]
     fn ifft_skew_end(
@@ -274,6 +867,76 @@ pub trait Engine {
         self.ifft(data, pos, size, truncated_size, pos + size)
     }
 
+    /// `out[] = inputs[0][] * coeffs[0] + inputs[1][] * coeffs[1] + ...`
+    ///
+    /// - Coefficients are [`GfElement`]:s, not logarithms like in [`mul`].
+    /// - `out.len()` must be a multiple of 64 and each input
+    ///   must have the same length as `out`.
+    ///
+    /// This is e.g. one recovery shard as a dot product of
+    /// the original shards with one row of a generator matrix.
+    ///
+    /// Default implementation multiplies each input separately with [`mul`],
+    /// engines provided by this crate instead accumulate all terms
+    /// into each 64-byte block of `out` in one pass.
+    ///
+    /// # Panics
+    ///
+    /// If lengths of the arguments don't match as described above.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::engine::{DefaultEngine, Engine};
+    /// use reed_solomon_simd::gf::Gf16;
+    ///
+    /// let engine = DefaultEngine::new();
+    /// let (a, b) = ([1u8; 64], [2u8; 64]);
+    ///
+    /// let mut out = [0u8; 64];
+    /// engine.linear_combination(&mut out, &[&a, &b], &[3, 0]);
+    ///
+    /// let expected = Gf16(0x0101) * Gf16(3);
+    /// assert_eq!(out[0], expected.0 as u8);
+    /// assert_eq!(out[32], (expected.0 >> 8) as u8);
+    /// ```
+    ///
+    /// [`mul`]: Engine::mul
+    fn linear_combination(&self, out: &mut [u8], inputs: &[&[u8]], coeffs: &[GfElement]) {
+        let terms = linear_combination_terms(out, inputs, coeffs);
+        out.fill(0);
+
+        let mut product = vec![0; out.len()];
+        for (input, log_m) in terms {
+            product.copy_from_slice(input);
+            self.mul(&mut product, log_m);
+            for (x, y) in zip(out.iter_mut(), &product) {
+                *x ^= y;
+            }
+        }
+    }
+
+    /// Returns name of this engine, e.g. for logging and metrics.
+    ///
+    /// Engines provided by this crate return their type name,
+    /// e.g. `"NoSimd"` or `"Avx2"`, and [`DefaultEngine`] returns
+    /// name of the engine it has chosen. Default implementation returns
+    /// last path segment of [`std::any::type_name`] without generic parameters,
+    /// which isn't guaranteed to be stable across compiler versions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::engine::{Engine, NoSimd};
+    ///
+    /// assert_eq!(NoSimd::new().name(), "NoSimd");
+    /// ```
+    fn name(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+        name.rsplit("::").next().unwrap_or(name)
+    }
+
     /// `data[x .. x + count] ^= data[y .. y + count]`
     ///
     /// Ranges must not overlap.
@@ -288,13 +951,1119 @@ pub trait Engine {
 }
 
 // ======================================================================
-// TESTS
+// Engine - IMPL Engine for &E
 
-// Engines are tested indirectly via roundtrip tests of HighRate and LowRate.
+/// Borrowed engine, so that one engine can be shared
+/// by several encoders/decoders without re-initializing it.
+impl<E: Engine> Engine for &E {
+    fn fft(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        size: usize,
+        truncated_size: usize,
+        skew_delta: usize,
+    ) {
+        (*self).fft(data, pos, size, truncated_size, skew_delta)
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn ifft(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        size: usize,
+        truncated_size: usize,
+        skew_delta: usize,
+    ) {
+        (*self).ifft(data, pos, size, truncated_size, skew_delta)
+    }
+
+    fn mul(&self, x: &mut [u8], log_m: GfElement) {
+        (*self).mul(x, log_m)
+    }
+
+    fn supported() -> bool {
+        E::supported()
+    }
+
+    fn copy_nt(dst: &mut [u8], src: &[u8]) {
+        E::copy_nt(dst, src)
+    }
+
+    fn copy(dst: &mut [u8], src: &[u8]) {
+        E::copy(dst, src)
+    }
+
+    fn fill(x: &mut [u8], byte: u8) {
+        E::fill(x, byte)
+    }
+
+    fn xor(xs: &mut [u8], ys: &[u8]) {
+        E::xor(xs, ys)
+    }
+
+    fn xor_into(out: &mut [u8], a: &[u8], b: &[u8]) {
+        E::xor_into(out, a, b)
+    }
+
+    #[cfg(feature = "decoder")]
+    fn eval_poly(erasures: &mut [GfElement; GF_ORDER], truncated_size: usize) {
+        E::eval_poly(erasures, truncated_size)
+    }
+
+    #[cfg(all(feature = "decoder", feature = "unstable-basis"))]
+    fn eval_poly_with_tables(&self, erasures: &mut [GfElement; GF_ORDER], truncated_size: usize) {
+        (*self).eval_poly_with_tables(erasures, truncated_size)
+    }
+
+    fn fft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        (*self).fft_butterflies(data, x, y, count, log_m)
+    }
+
+    #[inline(always)]
+    fn fft_skew_end(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        size: usize,
+        truncated_size: usize,
+    ) {
+        (*self).fft_skew_end(data, pos, size, truncated_size)
+    }
+
+    fn formal_derivative(data: &mut ShardsRefMut) {
+        E::formal_derivative(data)
+    }
+
+    fn ifft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        (*self).ifft_butterflies(data, x, y, count, log_m)
+    }
+
+    #[inline(always)]
+    fn ifft_skew_end(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        size: usize,
+        truncated_size: usize,
+    ) {
+        (*self).ifft_skew_end(data, pos, size, truncated_size)
+    }
+
+    fn linear_combination(&self, out: &mut [u8], inputs: &[&[u8]], coeffs: &[GfElement]) {
+        (*self).linear_combination(out, inputs, coeffs)
+    }
+
+    fn name(&self) -> &'static str {
+        (*self).name()
+    }
+
+    #[inline(always)]
+    fn xor_within(data: &mut ShardsRefMut, x: usize, y: usize, count: usize) {
+        E::xor_within(data, x, y, count)
+    }
+}
+
+// ======================================================================
+// EngineKind - PUBLIC
+
+/// Kind of an [`Engine`] provided by this crate.
+///
+/// See [`available_engines`] and [`DefaultEngine::kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EngineKind {
+    /// [`Avx2`] engine, only available on x86(-64).
+    Avx2,
+    /// [`Naive`] engine.
+    Naive,
+    /// [`Neon`] engine, only available on AArch64.
+    Neon,
+    /// [`NoSimd`] engine.
+    NoSimd,
+    /// [`Ssse3`] engine, only available on x86(-64).
+    Ssse3,
+}
+
+// ======================================================================
+// TESTS
+
+// Engines are tested indirectly via roundtrip tests of HighRate and LowRate.
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::gf::Gf16;
+
+    // Polynomials in the first lane of 64-byte shards.
+
+    fn to_shards(values: &[Gf16]) -> Vec<u8> {
+        let mut bytes = vec![0; values.len() * 64];
+        for (i, value) in values.iter().enumerate() {
+            bytes[i * 64] = value.0 as u8;
+            bytes[i * 64 + 32] = (value.0 >> 8) as u8;
+        }
+        bytes
+    }
+
+    fn from_shards(bytes: &[u8]) -> Vec<Gf16> {
+        bytes
+            .chunks_exact(64)
+            .map(|shard| Gf16(GfElement::from(shard[0]) | GfElement::from(shard[32]) << 8))
+            .collect()
+    }
+
+    fn fft(values: &[Gf16], offset: usize) -> Vec<Gf16> {
+        let mut bytes = to_shards(values);
+        let mut data = ShardsRefMut::new(values.len(), 64, &mut bytes);
+        additive_fft(&Naive::new(), &mut data, offset);
+        from_shards(&bytes)
+    }
+
+    fn ifft(values: &[Gf16], offset: usize) -> Vec<Gf16> {
+        let mut bytes = to_shards(values);
+        let mut data = ShardsRefMut::new(values.len(), 64, &mut bytes);
+        additive_ifft(&Naive::new(), &mut data, offset);
+        from_shards(&bytes)
+    }
+
+    // ============================================================
+    // additive_fft
+
+    #[test]
+    fn additive_fft_evaluates_basis() {
+        for (size, offset) in [(1, 0), (8, 0), (8, 8), (16, 48), (4, GF_ORDER - 4)] {
+            // X_0 = 1
+            let mut coefs = vec![Gf16::ZERO; size];
+            coefs[0] = Gf16(1234);
+            assert_eq!(fft(&coefs, offset), vec![Gf16(1234); size]);
+
+            // X_1 = x
+            if size > 1 {
+                let mut coefs = vec![Gf16::ZERO; size];
+                coefs[1] = Gf16::ONE;
+                let points: Vec<_> = (0..size).map(|i| Gf16((offset + i) as u16)).collect();
+                assert_eq!(fft(&coefs, offset), points);
+            }
+        }
+    }
+
+    #[test]
+    fn additive_fft_roundtrip() {
+        let mut rng = ChaCha8Rng::from_seed([157; 32]);
+        for (size, offset) in [(1, 5), (32, 0), (32, 1024)] {
+            let values: Vec<_> = (0..size).map(|_| Gf16(rng.gen())).collect();
+            assert_eq!(ifft(&fft(&values, offset), offset), values);
+            assert_eq!(fft(&ifft(&values, offset), offset), values);
+        }
+    }
+
+    #[test]
+    fn additive_fft_polynomial_multiplication() {
+        let mut rng = ChaCha8Rng::from_seed([157; 32]);
+        for n in [1, 2, 16, 64] {
+            let mut a: Vec<_> = (0..n).map(|_| Gf16(rng.gen())).collect();
+            let mut b: Vec<_> = (0..n).map(|_| Gf16(rng.gen())).collect();
+            a.resize(2 * n, Gf16::ZERO);
+            b.resize(2 * n, Gf16::ZERO);
+
+            let product: Vec<_> = zip(fft(&a, 0), fft(&b, 0)).map(|(x, y)| x * y).collect();
+            let c = ifft(&product, 0);
+
+            // Check product at other points.
+            for offset in [2 * n, 8 * n] {
+                let expected: Vec<_> = zip(fft(&a, offset), fft(&b, offset))
+                    .map(|(x, y)| x * y)
+                    .collect();
+                assert_eq!(fft(&c, offset), expected);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "offset must be a multiple of size")]
+    fn additive_fft_unaligned_offset() {
+        fft(&[Gf16::ZERO; 4], 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "size must be a power of two")]
+    fn additive_fft_size_not_power_of_two() {
+        fft(&[Gf16::ZERO; 3], 0);
+    }
+
+    // ============================================================
+    // shard_bytes_to_elements / elements_to_shard_bytes
+
+    #[test]
+    fn shard_layout_roundtrip() {
+        let mut rng = ChaCha8Rng::from_seed([163; 32]);
+        let mut shard = vec![0u8; 192];
+        rng.fill::<[u8]>(&mut shard);
+
+        let elements = shard_bytes_to_elements(&shard).unwrap();
+        assert_eq!(elements.len(), 96);
+        assert_eq!(elements_to_shard_bytes(&elements).unwrap(), shard);
+
+        // Element 33 is element 1 of the second block.
+        assert_eq!(
+            elements[33],
+            GfElement::from_le_bytes([shard[64 + 1], shard[64 + 32 + 1]])
+        );
+    }
+
+    #[test]
+    fn shard_layout_matches_engine() {
+        let mut rng = ChaCha8Rng::from_seed([163; 32]);
+        let mut shard = vec![0u8; 128];
+        rng.fill::<[u8]>(&mut shard);
+        let elements = shard_bytes_to_elements(&shard).unwrap();
+
+        let multiplier = Gf16(0x1234);
+        DefaultEngine::new().mul(&mut shard, multiplier.log().unwrap());
+
+        let expected: Vec<_> = elements.iter().map(|&x| (Gf16(x) * multiplier).0).collect();
+        assert_eq!(shard_bytes_to_elements(&shard).unwrap(), expected);
+    }
+
+    #[test]
+    fn shard_layout_invalid_size() {
+        assert_eq!(
+            shard_bytes_to_elements(&[0; 96]),
+            Err(Error::InvalidShardSize { shard_bytes: 96 })
+        );
+        assert_eq!(
+            shard_bytes_to_elements(&[]),
+            Err(Error::InvalidShardSize { shard_bytes: 0 })
+        );
+        assert_eq!(
+            elements_to_shard_bytes(&[0; 48]),
+            Err(Error::InvalidShardSize { shard_bytes: 96 })
+        );
+    }
+
+    // ============================================================
+    // linear_combination
+
+    // Uses provided `Engine::linear_combination`.
+    struct ProvidedOnly(Naive);
+
+    impl Engine for ProvidedOnly {
+        fn fft(
+            &self,
+            data: &mut ShardsRefMut,
+            pos: usize,
+            size: usize,
+            truncated: usize,
+            skew_delta: usize,
+        ) {
+            self.0.fft(data, pos, size, truncated, skew_delta)
+        }
+
+        fn ifft(
+            &self,
+            data: &mut ShardsRefMut,
+            pos: usize,
+            size: usize,
+            truncated: usize,
+            skew_delta: usize,
+        ) {
+            self.0.ifft(data, pos, size, truncated, skew_delta)
+        }
+
+        fn mul(&self, x: &mut [u8], log_m: GfElement) {
+            self.0.mul(x, log_m)
+        }
+    }
+
+    fn test_linear_combination<E: Engine>(engine: &E) {
+        let mut rng = ChaCha8Rng::from_seed([164; 32]);
+
+        let inputs: Vec<Vec<u8>> = (0..5)
+            .map(|_| {
+                let mut input = vec![0u8; 192];
+                rng.fill::<[u8]>(&mut input);
+                input
+            })
+            .collect();
+        let inputs: Vec<&[u8]> = inputs.iter().map(|input| input.as_slice()).collect();
+        let coeffs = [rng.gen(), 0, 1, rng.gen(), GF_MODULUS];
+
+        // Repeated multiply-and-add of each input separately.
+        let mut expected = vec![0u8; 192];
+        for (input, coeff) in zip(&inputs, coeffs) {
+            let mut product = input.to_vec();
+            if let Some(log_m) = Gf16(coeff).log() {
+                Naive::new().mul(&mut product, log_m);
+            } else {
+                product.fill(0);
+            }
+            Naive::xor(&mut expected, &product);
+        }
+
+        let mut out = vec![0xFFu8; 192];
+        engine.linear_combination(&mut out, &inputs, &coeffs);
+        assert_eq!(out, expected);
+
+        engine.linear_combination(&mut out, &[], &[]);
+        assert_eq!(out, vec![0; 192]);
+    }
+
+    #[test]
+    fn linear_combination_matches_mul_add() {
+        test_linear_combination(&ProvidedOnly(Naive::new()));
+        test_linear_combination(&Naive::new());
+        test_linear_combination(&NoSimd::new());
+        test_linear_combination(&DefaultEngine::new());
+
+        #[cfg(feature = "portable-simd")]
+        test_linear_combination(&PortableSimd::new());
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                test_linear_combination(&Avx2::new());
+            }
+            if is_x86_feature_detected!("ssse3") {
+                test_linear_combination(&Ssse3::new());
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                test_linear_combination(&Neon::new());
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "inputs must have same length as out")]
+    fn linear_combination_different_length() {
+        let mut out = [0u8; 128];
+        NoSimd::new().linear_combination(&mut out, &[&[0u8; 64]], &[1]);
+    }
+
+    // ============================================================
+    // fft / ifft - truncated_size
+
+    // Compares truncated transforms with odd `truncated_size`
+    // to full transforms of `Naive`, with zeros after `truncated_size`.
+    fn test_odd_truncated_size<E: Engine>(engine: &E) {
+        let mut rng = ChaCha8Rng::from_seed([169; 32]);
+        let naive = Naive::new();
+        let pos = 2;
+
+        for size in [2, 4, 8, 16, 32] {
+            for truncated_size in (1..size).step_by(2) {
+                for skew_delta in [size, 64 * size] {
+                    let mut data = vec![0u8; (pos + size) * 64];
+                    rng.fill::<[u8]>(&mut data[..(pos + truncated_size) * 64]);
+
+                    let mut expected = data.clone();
+                    let mut expected = ShardsRefMut::new(pos + size, 64, &mut expected);
+                    naive.fft(&mut expected, pos, size, size, skew_delta);
+
+                    let mut got = data.clone();
+                    let mut got = ShardsRefMut::new(pos + size, 64, &mut got);
+                    engine.fft(&mut got, pos, size, truncated_size, skew_delta);
+
+                    for i in pos..pos + truncated_size {
+                        assert_eq!(got[i], expected[i], "fft {} {}", size, truncated_size);
+                    }
+
+                    let mut expected = data.clone();
+                    let mut expected = ShardsRefMut::new(pos + size, 64, &mut expected);
+                    naive.ifft(&mut expected, pos, size, size, skew_delta);
+
+                    let mut got = data;
+                    let mut got = ShardsRefMut::new(pos + size, 64, &mut got);
+                    engine.ifft(&mut got, pos, size, truncated_size, skew_delta);
+
+                    for i in pos..pos + truncated_size {
+                        assert_eq!(got[i], expected[i], "ifft {} {}", size, truncated_size);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn odd_truncated_size() {
+        test_odd_truncated_size(&Naive::new());
+        test_odd_truncated_size(&NoSimd::new());
+        test_odd_truncated_size(&DefaultEngine::new());
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                test_odd_truncated_size(&Avx2::new());
+            }
+            if is_x86_feature_detected!("ssse3") {
+                test_odd_truncated_size(&Ssse3::new());
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                test_odd_truncated_size(&Neon::new());
+            }
+        }
+    }
+
+    // ============================================================
+    // fft / ifft - steps
+
+    #[test]
+    fn fft_steps_cover_all_layers() {
+        for log_size in 0..=16 {
+            let size = 1 << log_size;
+
+            let fft: Vec<_> = fft_steps(size).collect();
+            let mut ifft: Vec<_> = ifft_steps(size).collect();
+            ifft.reverse();
+            assert_eq!(fft, ifft);
+
+            // Layers from largest `dist` to smallest, each exactly once.
+            let dists: Vec<usize> = fft
+                .iter()
+                .flat_map(|&(dist, layers)| (0..layers).rev().map(move |k| dist << k))
+                .collect();
+            let expected: Vec<usize> = (0..log_size).rev().map(|k| 1 << k).collect();
+            assert_eq!(dists, expected);
+            assert!(fft.iter().filter(|&&(_, layers)| layers == 1).count() <= 1);
+        }
+    }
+
+    // Compares transforms with odd number of layers to `Naive`,
+    // also with shards large enough to be batched.
+    fn test_odd_layer_count<E: Engine>(engine: &E) {
+        let mut rng = ChaCha8Rng::from_seed([201; 32]);
+        let naive = Naive::new();
+
+        for shard_bytes in [64, 4096] {
+            for size in [2, 8, 32, 128] {
+                for truncated_size in [size, size / 2 + 1, 1] {
+                    let skew_delta = 4 * size;
+                    let mut data = vec![0u8; size * shard_bytes];
+                    rng.fill::<[u8]>(&mut data[..truncated_size * shard_bytes]);
+
+                    for fft in [true, false] {
+                        let mut expected = data.clone();
+                        let mut expected = ShardsRefMut::new(size, shard_bytes, &mut expected);
+                        let mut got = data.clone();
+                        let mut got = ShardsRefMut::new(size, shard_bytes, &mut got);
+                        if fft {
+                            naive.fft(&mut expected, 0, size, size, skew_delta);
+                            engine.fft(&mut got, 0, size, truncated_size, skew_delta);
+                        } else {
+                            naive.ifft(&mut expected, 0, size, size, skew_delta);
+                            engine.ifft(&mut got, 0, size, truncated_size, skew_delta);
+                        }
+
+                        for i in 0..truncated_size {
+                            assert_eq!(
+                                got[i], expected[i],
+                                "fft {} size {} truncated_size {} shard_bytes {}",
+                                fft, size, truncated_size, shard_bytes
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn odd_layer_count() {
+        test_odd_layer_count(&NoSimd::new());
+        test_odd_layer_count(&DefaultEngine::new());
+
+        #[cfg(feature = "portable-simd")]
+        test_odd_layer_count(&PortableSimd::new());
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                test_odd_layer_count(&Avx2::new());
+            }
+            if is_x86_feature_detected!("ssse3") {
+                test_odd_layer_count(&Ssse3::new());
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                test_odd_layer_count(&Neon::new());
+            }
+        }
+    }
+
+    // ============================================================
+    // fft / ifft - skew extremes
+
+    // Compares transforms to `Naive` at start of the field,
+    // where skew values are `GF_MODULUS`, and at end of the field.
+    fn test_skew_extremes<E: Engine>(engine: &E) {
+        let mut rng = ChaCha8Rng::from_seed([188; 32]);
+        let naive = Naive::new();
+
+        for size in [1, 2, 4, 8, 16, 32, 64, 128] {
+            for truncated_size in [size, size / 2 + 1] {
+                for skew_delta in [0, size, GF_ORDER - size] {
+                    let mut data = vec![0u8; size * 64];
+                    rng.fill::<[u8]>(&mut data[..truncated_size * 64]);
+
+                    for fft in [true, false] {
+                        let mut expected = data.clone();
+                        let mut expected = ShardsRefMut::new(size, 64, &mut expected);
+                        let mut got = data.clone();
+                        let mut got = ShardsRefMut::new(size, 64, &mut got);
+
+                        if fft {
+                            naive.fft(&mut expected, 0, size, size, skew_delta);
+                            engine.fft(&mut got, 0, size, truncated_size, skew_delta);
+                        } else {
+                            naive.ifft(&mut expected, 0, size, size, skew_delta);
+                            engine.ifft(&mut got, 0, size, truncated_size, skew_delta);
+                        }
+
+                        for i in 0..truncated_size {
+                            assert_eq!(
+                                got[i], expected[i],
+                                "fft {} size {} truncated {} skew_delta {}",
+                                fft, size, truncated_size, skew_delta
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn skew_extremes() {
+        test_skew_extremes(&NoSimd::new());
+        test_skew_extremes(&DefaultEngine::new());
+
+        #[cfg(feature = "portable-simd")]
+        test_skew_extremes(&PortableSimd::new());
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                test_skew_extremes(&Avx2::new());
+            }
+            if is_x86_feature_detected!("ssse3") {
+                test_skew_extremes(&Ssse3::new());
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                test_skew_extremes(&Neon::new());
+            }
+        }
+    }
+
+    // ============================================================
+    // fft_butterflies / ifft_butterflies
+
+    // Compares batched butterflies to provided implementation of `Naive`,
+    // and transforms with shard sizes giving batches of 1 - 64 shards.
+    fn test_butterflies<E: Engine>(engine: &E) {
+        let mut rng = ChaCha8Rng::from_seed([190; 32]);
+        let naive = Naive::new();
+
+        for log_m in [0, 1, rng.gen(), GF_MODULUS] {
+            let mut data = vec![0u8; 8 * 128];
+            rng.fill::<[u8]>(&mut data);
+
+            for (x, y) in [(0, 4), (5, 1)] {
+                let mut expected = data.clone();
+                let mut got = data.clone();
+
+                naive.fft_butterflies(
+                    &mut ShardsRefMut::new(8, 128, &mut expected),
+                    x,
+                    y,
+                    3,
+                    log_m,
+                );
+                engine.fft_butterflies(&mut ShardsRefMut::new(8, 128, &mut got), x, y, 3, log_m);
+                assert_eq!(got, expected, "fft log_m {}", log_m);
+
+                naive.ifft_butterflies(
+                    &mut ShardsRefMut::new(8, 128, &mut expected),
+                    x,
+                    y,
+                    3,
+                    log_m,
+                );
+                engine.ifft_butterflies(&mut ShardsRefMut::new(8, 128, &mut got), x, y, 3, log_m);
+                assert_eq!(got, expected, "ifft log_m {}", log_m);
+                assert_eq!(got, data, "roundtrip log_m {}", log_m);
+            }
+        }
+
+        let size = 256;
+        for shard_bytes in [64, 1024, 8192] {
+            let mut data = vec![0u8; size * shard_bytes];
+            rng.fill::<[u8]>(&mut data);
+
+            for fft in [true, false] {
+                let mut expected = data.clone();
+                let mut expected = ShardsRefMut::new(size, shard_bytes, &mut expected);
+                let mut got = data.clone();
+                let mut got = ShardsRefMut::new(size, shard_bytes, &mut got);
+
+                if fft {
+                    naive.fft(&mut expected, 0, size, size, size);
+                    engine.fft(&mut got, 0, size, size, size);
+                } else {
+                    naive.ifft(&mut expected, 0, size, size, size);
+                    engine.ifft(&mut got, 0, size, size, size);
+                }
+
+                for i in 0..size {
+                    assert_eq!(
+                        got[i], expected[i],
+                        "fft {} shard_bytes {}",
+                        fft, shard_bytes
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn butterflies() {
+        test_butterflies(&NoSimd::new());
+        test_butterflies(&DefaultEngine::new());
+
+        #[cfg(feature = "portable-simd")]
+        test_butterflies(&PortableSimd::new());
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                test_butterflies(&Avx2::new());
+            }
+            if is_x86_feature_detected!("ssse3") {
+                test_butterflies(&Ssse3::new());
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                test_butterflies(&Neon::new());
+            }
+        }
+    }
+
+    // ============================================================
+    // check_fft_params
+
+    // Invalid arguments panic with a message before `Skew` is read,
+    // largest valid `skew_delta` reads last element of `Skew`.
+    fn test_fft_params<E: Engine>(engine: &E) {
+        let mut bytes = vec![0u8; 4 * 64];
+
+        for (size, truncated_size, skew_delta) in [
+            (4, 4, GF_ORDER - 2),
+            (4, 4, GF_ORDER),
+            (4, 4, usize::MAX),
+            (4, 5, 0),
+            (3, 3, 0),
+        ] {
+            for fft in [true, false] {
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    let mut data = ShardsRefMut::new(4, 64, &mut bytes);
+                    if fft {
+                        engine.fft(&mut data, 0, size, truncated_size, skew_delta);
+                    } else {
+                        engine.ifft(&mut data, 0, size, truncated_size, skew_delta);
+                    }
+                }));
+                let message = result.unwrap_err().downcast::<String>().unwrap();
+                assert!(
+                    message.starts_with(&format!(
+                        "invalid FFT / IFFT arguments: size {}, truncated_size {}, skew_delta {}",
+                        size, truncated_size, skew_delta
+                    )),
+                    "{}",
+                    message
+                );
+            }
+        }
+
+        let mut data = ShardsRefMut::new(4, 64, &mut bytes);
+        engine.fft(&mut data, 0, 4, 4, GF_ORDER - 4);
+        engine.ifft(&mut data, 0, 4, 4, GF_ORDER - 4);
+    }
+
+    #[test]
+    fn fft_params() {
+        test_fft_params(&Naive::new());
+        test_fft_params(&NoSimd::new());
+        test_fft_params(&DefaultEngine::new());
+
+        #[cfg(feature = "portable-simd")]
+        test_fft_params(&PortableSimd::new());
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                test_fft_params(&Avx2::new());
+            }
+            if is_x86_feature_detected!("ssse3") {
+                test_fft_params(&Ssse3::new());
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                test_fft_params(&Neon::new());
+            }
+        }
+    }
+
+    #[test]
+    fn butterfly_batch_sizes() {
+        assert_eq!(butterfly_batch(64, 4), 4);
+        assert_eq!(butterfly_batch(64, 128), 64);
+        assert_eq!(butterfly_batch(1024, 32), 4);
+        assert_eq!(butterfly_batch(192, 32), 16);
+        assert_eq!(butterfly_batch(8192, 32), 1);
+    }
+
+    // ============================================================
+    // name
+
+    #[test]
+    fn engine_names() {
+        assert_eq!(Naive::new().name(), "Naive");
+        assert_eq!(NoSimd::new().name(), "NoSimd");
+        assert_eq!(<&NoSimd as Engine>::name(&&NoSimd::new()), "NoSimd");
+        assert_eq!(Instrumented::new(NoSimd::new()).name(), "Instrumented");
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            assert_eq!(Avx2::new().name(), "Avx2");
+            assert_eq!(Ssse3::new().name(), "Ssse3");
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(Neon::new().name(), "Neon");
+
+        #[cfg(feature = "portable-simd")]
+        assert_eq!(PortableSimd::new().name(), "PortableSimd");
+
+        let engine = DefaultEngine::new();
+        assert_eq!(engine.name(), format!("{:?}", engine.kind()));
+    }
+
+    // ============================================================
+    // available_engines
+
+    #[test]
+    fn available_engines_includes_default() {
+        let engines = available_engines();
+        assert_eq!(engines[0], DefaultEngine::new().kind());
+        assert_eq!(
+            &engines[engines.len() - 2..],
+            [EngineKind::NoSimd, EngineKind::Naive]
+        );
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            assert_eq!(
+                engines.contains(&EngineKind::Avx2),
+                is_x86_feature_detected!("avx2")
+            );
+            assert_eq!(
+                engines.contains(&EngineKind::Ssse3),
+                is_x86_feature_detected!("ssse3")
+            );
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(
+            engines.contains(&EngineKind::Neon),
+            std::arch::is_aarch64_feature_detected!("neon")
+        );
+    }
+
+    // ============================================================
+    // copy_nt
+
+    // Compares with `copy_from_slice` for all alignments of `dst` and `src`
+    // and lengths around the store width.
+    fn test_copy_nt<E: Engine>() {
+        let mut rng = ChaCha8Rng::from_seed([171; 32]);
+        let mut src = vec![0u8; 256];
+        rng.fill::<[u8]>(&mut src);
+
+        for dst_offset in 0..32 {
+            for src_offset in 0..32 {
+                for len in [0, 1, 15, 16, 17, 31, 32, 33, 64, 100, 192] {
+                    let src = &src[src_offset..src_offset + len];
+
+                    let mut expected = vec![0u8; 256];
+                    expected[dst_offset..dst_offset + len].copy_from_slice(src);
+
+                    let mut got = vec![0u8; 256];
+                    E::copy_nt(&mut got[dst_offset..dst_offset + len], src);
+
+                    assert_eq!(got, expected, "{} {} {}", dst_offset, src_offset, len);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn copy_nt() {
+        test_copy_nt::<Naive>();
+        test_copy_nt::<NoSimd>();
+        test_copy_nt::<DefaultEngine>();
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                test_copy_nt::<Avx2>();
+            }
+            if is_x86_feature_detected!("ssse3") {
+                test_copy_nt::<Ssse3>();
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                test_copy_nt::<Neon>();
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn copy_nt_different_lengths() {
+        DefaultEngine::copy_nt(&mut [0u8; 64], &[0u8; 32]);
+    }
+
+    // ============================================================
+    // copy
+
+    // Lengths around `COPY_NT_THRESHOLD` with unaligned `dst`.
+    fn test_copy<E: Engine>() {
+        let mut rng = ChaCha8Rng::from_seed([172; 32]);
+        let mut src = vec![0u8; COPY_NT_THRESHOLD + 64];
+        rng.fill::<[u8]>(&mut src);
+
+        for len in [
+            0,
+            64,
+            COPY_NT_THRESHOLD - 1,
+            COPY_NT_THRESHOLD,
+            COPY_NT_THRESHOLD + 33,
+        ] {
+            let src = &src[..len];
+            let mut got = vec![0u8; len + 1];
+            E::copy(&mut got[1..], src);
+            assert_eq!(got[0], 0);
+            assert!(got[1..] == *src, "{}", len);
+        }
+    }
+
+    #[test]
+    fn copy() {
+        test_copy::<Naive>();
+        test_copy::<NoSimd>();
+        test_copy::<DefaultEngine>();
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                test_copy::<Avx2>();
+            }
+            if is_x86_feature_detected!("ssse3") {
+                test_copy::<Ssse3>();
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                test_copy::<Neon>();
+            }
+        }
+    }
+
+    // ============================================================
+    // fill
+
+    fn test_fill<E: Engine>() {
+        for offset in 0..32 {
+            for len in [0, 1, 15, 16, 17, 31, 32, 33, 64, 100, 192] {
+                for byte in [0, 0x5a, 0xff] {
+                    let mut expected = vec![7u8; 256];
+                    expected[offset..offset + len].fill(byte);
+
+                    let mut got = vec![7u8; 256];
+                    E::fill(&mut got[offset..offset + len], byte);
+
+                    assert_eq!(got, expected, "{} {} {}", offset, len, byte);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fill() {
+        test_fill::<Naive>();
+        test_fill::<NoSimd>();
+        test_fill::<DefaultEngine>();
+        test_fill::<&NoSimd>();
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                test_fill::<Avx2>();
+            }
+            if is_x86_feature_detected!("ssse3") {
+                test_fill::<Ssse3>();
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                test_fill::<Neon>();
+            }
+        }
+    }
+
+    // ============================================================
+    // xor_into
+
+    fn test_xor_into<E: Engine>() {
+        let mut rng = ChaCha8Rng::from_seed([177; 32]);
+        let mut a = vec![0u8; 256];
+        let mut b = vec![0u8; 256];
+        rng.fill::<[u8]>(&mut a);
+        rng.fill::<[u8]>(&mut b);
+
+        for offset in 0..32 {
+            for len in [0, 1, 15, 16, 17, 31, 32, 33, 64, 100, 192] {
+                let a = &a[offset..offset + len];
+                let b = &b[32 - offset..32 - offset + len];
+
+                let expected: Vec<u8> = zip(a, b).map(|(a, b)| a ^ b).collect();
+
+                let mut got = vec![0u8; 256];
+                E::xor_into(&mut got[offset..offset + len], a, b);
+
+                assert_eq!(&got[offset..offset + len], expected, "{} {}", offset, len);
+                assert!(got[..offset].iter().all(|&x| x == 0));
+                assert!(got[offset + len..].iter().all(|&x| x == 0));
+            }
+        }
+    }
+
+    #[test]
+    fn xor_into() {
+        test_xor_into::<Naive>();
+        test_xor_into::<NoSimd>();
+        test_xor_into::<DefaultEngine>();
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                test_xor_into::<Avx2>();
+            }
+            if is_x86_feature_detected!("ssse3") {
+                test_xor_into::<Ssse3>();
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                test_xor_into::<Neon>();
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn xor_into_different_lengths() {
+        DefaultEngine::xor_into(&mut [0u8; 64], &[0u8; 64], &[0u8; 32]);
+    }
+
+    // ============================================================
+    // constant-time
+
+    #[cfg(feature = "constant-time")]
+    #[test]
+    fn nosimd_mul_matches_naive() {
+        let mut rng = ChaCha8Rng::from_seed([160; 32]);
+        let mut original = vec![0u8; 64];
+        rng.fill::<[u8]>(&mut original);
+
+        let naive = Naive::new();
+        let nosimd = NoSimd::new();
+
+        for log_m in 0..=GF_MODULUS {
+            let mut expected = original.clone();
+            let mut actual = original.clone();
+            naive.mul(&mut expected, log_m);
+            nosimd.mul(&mut actual, log_m);
+            assert_eq!(actual, expected, "log_m = {}", log_m);
+        }
+    }
+
+    // ============================================================
+    // portable-simd
+
+    #[cfg(feature = "portable-simd")]
+    #[test]
+    fn portable_simd_mul_matches_nosimd() {
+        let mut rng = ChaCha8Rng::from_seed([189; 32]);
+        let mut original = vec![0u8; 128];
+        rng.fill::<[u8]>(&mut original);
+
+        let nosimd = NoSimd::new();
+        let portable_simd = PortableSimd::new();
+
+        for log_m in 0..=GF_MODULUS {
+            let mut expected = original.clone();
+            let mut actual = original.clone();
+            nosimd.mul(&mut expected, log_m);
+            portable_simd.mul(&mut actual, log_m);
+            assert_eq!(actual, expected, "log_m = {}", log_m);
+        }
+    }
 
     // ============================================================
     // checked_next_multiple_of
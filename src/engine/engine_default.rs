@@ -1,4 +1,10 @@
-use crate::engine::{Engine, GfElement, NoSimd, ShardsRefMut, GF_ORDER};
+use std::iter::zip;
+
+use once_cell::sync::OnceCell;
+
+#[cfg(feature = "decoder")]
+use crate::engine::GF_ORDER;
+use crate::engine::{self, tables, Engine, EngineKind, GfElement, NoSimd, ShardsRefMut};
 
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 use crate::engine::{Avx2, Ssse3};
@@ -10,7 +16,7 @@ use crate::engine::Neon;
 // DefaultEngine - PUBLIC
 
 /// [`Engine`] that at runtime selects the best Engine.
-pub struct DefaultEngine(Box<dyn Engine + Send + Sync>);
+pub struct DefaultEngine(Box<dyn Engine + Send + Sync>, EngineKind);
 
 impl DefaultEngine {
     /// Creates new [`DefaultEngine`] by chosing and initializing the underlying engine.
@@ -23,26 +29,43 @@ impl DefaultEngine {
     /// On AArch64 the engine is chosen in the following order of preference:
     /// 1. [`Neon`]
     /// 2. [`NoSimd`]
+    ///
+    /// Support is checked with [`Engine::supported`].
+    /// If a feature is enabled at compile time, e.g. with
+    /// `RUSTFLAGS="-C target-feature=+avx2"`, that check is resolved
+    /// at compile time, but the self-test below is still run.
+    ///
+    /// A supported SIMD engine is also self-tested by multiplying
+    /// a known vector and comparing the result to scalar multiplication.
+    /// This guards against environments, e.g. some hypervisors,
+    /// which report a feature while the OS doesn't actually save
+    /// its registers. Only `mul` is self-tested, not `fft` or `ifft`.
+    /// An engine which fails the self-test is skipped,
+    /// with a warning logged if `tracing` feature is enabled.
+    ///
+    /// Selection including the self-test is done once per process
+    /// and stored in a `OnceCell`, later calls re-use the selected kind.
+    /// The chosen engine is always boxed as `Box<dyn Engine>`, so its methods
+    /// are called dynamically and aren't inlined into their callers.
+    /// The static methods `copy_nt`, `fill`, `xor_into` and `eval_poly`
+    /// read the selected kind on every call to dispatch to the chosen engine.
     pub fn new() -> Self {
-        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-        {
-            if is_x86_feature_detected!("avx2") {
-                return DefaultEngine(Box::new(Avx2::new()));
-            }
-
-            if is_x86_feature_detected!("ssse3") {
-                return DefaultEngine(Box::new(Ssse3::new()));
-            }
-        }
-
-        #[cfg(target_arch = "aarch64")]
-        {
-            if std::arch::is_aarch64_feature_detected!("neon") {
-                return DefaultEngine(Box::new(Neon::new()));
-            }
-        }
+        let kind = selected_kind();
+        DefaultEngine(new_engine(kind), kind)
+    }
 
-        DefaultEngine(Box::new(NoSimd::new()))
+    /// Returns kind of the underlying engine chosen by [`new`](Self::new).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::engine::{self, DefaultEngine};
+    ///
+    /// let kind = DefaultEngine::new().kind();
+    /// assert!(engine::available_engines().contains(&kind));
+    /// ```
+    pub fn kind(&self) -> EngineKind {
+        self.1
     }
 }
 
@@ -85,25 +108,231 @@ impl Engine for DefaultEngine {
         self.0.mul(x, log_m)
     }
 
+    fn fft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        self.0.fft_butterflies(data, x, y, count, log_m)
+    }
+
+    fn ifft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        self.0.ifft_butterflies(data, x, y, count, log_m)
+    }
+
+    fn linear_combination(&self, out: &mut [u8], inputs: &[&[u8]], coeffs: &[GfElement]) {
+        self.0.linear_combination(out, inputs, coeffs)
+    }
+
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn copy_nt(dst: &mut [u8], src: &[u8]) {
+        match selected_kind() {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Avx2 => Avx2::copy_nt(dst, src),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Ssse3 => Ssse3::copy_nt(dst, src),
+            #[cfg(target_arch = "aarch64")]
+            EngineKind::Neon => Neon::copy_nt(dst, src),
+            _ => NoSimd::copy_nt(dst, src),
+        }
+    }
+
+    fn fill(x: &mut [u8], byte: u8) {
+        match selected_kind() {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Avx2 => Avx2::fill(x, byte),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Ssse3 => Ssse3::fill(x, byte),
+            #[cfg(target_arch = "aarch64")]
+            EngineKind::Neon => Neon::fill(x, byte),
+            _ => NoSimd::fill(x, byte),
+        }
+    }
+
+    fn xor_into(out: &mut [u8], a: &[u8], b: &[u8]) {
+        match selected_kind() {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Avx2 => Avx2::xor_into(out, a, b),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Ssse3 => Ssse3::xor_into(out, a, b),
+            #[cfg(target_arch = "aarch64")]
+            EngineKind::Neon => Neon::xor_into(out, a, b),
+            _ => NoSimd::xor_into(out, a, b),
+        }
+    }
+
+    #[cfg(feature = "decoder")]
     fn eval_poly(erasures: &mut [GfElement; GF_ORDER], truncated_size: usize) {
-        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-        {
-            if is_x86_feature_detected!("avx2") {
-                return Avx2::eval_poly(erasures, truncated_size);
-            }
-
-            if is_x86_feature_detected!("ssse3") {
-                return Ssse3::eval_poly(erasures, truncated_size);
-            }
+        match selected_kind() {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Avx2 => Avx2::eval_poly(erasures, truncated_size),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Ssse3 => Ssse3::eval_poly(erasures, truncated_size),
+            #[cfg(target_arch = "aarch64")]
+            EngineKind::Neon => Neon::eval_poly(erasures, truncated_size),
+            _ => NoSimd::eval_poly(erasures, truncated_size),
         }
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+// Input of `self_test`, covering all nibbles of both bytes of `GfElement`.
+const SELF_TEST_BYTES: usize = 128;
+const SELF_TEST_LOG_M: GfElement = 12345;
+
+// Returns kind of engine selected by `select_kind`, selecting it on first call.
+fn selected_kind() -> EngineKind {
+    static SELECTED: OnceCell<EngineKind> = OnceCell::new();
+    *SELECTED.get_or_init(|| select_kind(engine::available_engines(), new_engine))
+}
+
+// Returns first of `kinds` whose engine passes `self_test`, or `NoSimd`.
+// - `kinds` are in order of preference, see `available_engines`.
+// - `NoSimd` is the last resort so it isn't tested.
+fn select_kind(
+    kinds: impl IntoIterator<Item = EngineKind>,
+    new_engine: impl Fn(EngineKind) -> Box<dyn Engine + Send + Sync>,
+) -> EngineKind {
+    kinds
+        .into_iter()
+        .find(|&kind| kind == EngineKind::NoSimd || self_test(kind, &*new_engine(kind)))
+        .unwrap_or(EngineKind::NoSimd)
+}
 
+// Creates engine of given kind, which must be supported.
+fn new_engine(kind: EngineKind) -> Box<dyn Engine + Send + Sync> {
+    match kind {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        EngineKind::Avx2 => Box::new(Avx2::new()),
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        EngineKind::Ssse3 => Box::new(Ssse3::new()),
         #[cfg(target_arch = "aarch64")]
-        {
-            if std::arch::is_aarch64_feature_detected!("neon") {
-                return Neon::eval_poly(erasures, truncated_size);
-            }
+        EngineKind::Neon => Box::new(Neon::new()),
+        _ => Box::new(NoSimd::new()),
+    }
+}
+
+// Returns `true` if `engine` multiplies a known vector like scalar
+// multiplication with `Exp` and `Log` tables, otherwise logs a warning
+// and returns `false`.
+// - `Engine::mul` of SIMD engines uses the widest registers of the engine,
+//   so this catches registers which aren't saved by the OS.
+// - `Exp` and `Log` tables are small and needed by SIMD engines anyway.
+fn self_test(kind: EngineKind, engine: &dyn Engine) -> bool {
+    let input: [u8; SELF_TEST_BYTES] = std::array::from_fn(|i| (i * 167 + 13) as u8);
+
+    let (exp, log) = tables::initialize_exp_log();
+    let mut expected = input;
+    for chunk in expected.chunks_exact_mut(64) {
+        let (lo, hi) = chunk.split_at_mut(32);
+        for (lo, hi) in zip(lo, hi) {
+            let x = GfElement::from(*lo) | GfElement::from(*hi) << 8;
+            let prod = tables::mul(x, SELF_TEST_LOG_M, exp, log);
+            *lo = prod as u8;
+            *hi = (prod >> 8) as u8;
+        }
+    }
+
+    let mut got = input;
+    engine.mul(&mut got, SELF_TEST_LOG_M);
+
+    if got == expected {
+        true
+    } else {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            ?kind,
+            "engine failed self-test, falling back to next engine"
+        );
+        #[cfg(not(feature = "tracing"))]
+        let _ = kind;
+        false
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use super::*;
+
+    // Engine which gives wrong results, like a SIMD engine
+    // whose registers aren't saved by the OS.
+    struct Broken(NoSimd);
+
+    impl Engine for Broken {
+        fn fft(
+            &self,
+            data: &mut ShardsRefMut,
+            pos: usize,
+            size: usize,
+            truncated_size: usize,
+            skew_delta: usize,
+        ) {
+            self.0.fft(data, pos, size, truncated_size, skew_delta)
+        }
+
+        fn ifft(
+            &self,
+            data: &mut ShardsRefMut,
+            pos: usize,
+            size: usize,
+            truncated_size: usize,
+            skew_delta: usize,
+        ) {
+            self.0.ifft(data, pos, size, truncated_size, skew_delta)
+        }
+
+        fn mul(&self, x: &mut [u8], log_m: GfElement) {
+            self.0.mul(x, log_m);
+            x[SELF_TEST_BYTES / 2] ^= 1;
         }
+    }
+
+    fn new_broken_avx2(kind: EngineKind) -> Box<dyn Engine + Send + Sync> {
+        if kind == EngineKind::Avx2 {
+            Box::new(Broken(NoSimd::new()))
+        } else {
+            Box::new(NoSimd::new())
+        }
+    }
+
+    #[test]
+    fn self_test_detects_broken_engine() {
+        assert!(self_test(EngineKind::NoSimd, &NoSimd::new()));
+        assert!(!self_test(EngineKind::Avx2, &Broken(NoSimd::new())));
+    }
+
+    #[test]
+    fn broken_engine_falls_back() {
+        use EngineKind::*;
+
+        assert_eq!(select_kind([Avx2, Ssse3, NoSimd], new_broken_avx2), Ssse3);
+        assert_eq!(select_kind([Avx2, NoSimd], new_broken_avx2), NoSimd);
+        assert_eq!(select_kind([Avx2], new_broken_avx2), NoSimd);
+        assert_eq!(select_kind([Ssse3, NoSimd], new_broken_avx2), Ssse3);
+    }
 
-        NoSimd::eval_poly(erasures, truncated_size)
+    #[test]
+    fn supported_engines_pass_self_test() {
+        let kind = DefaultEngine::new().kind();
+        assert_eq!(kind, engine::available_engines()[0]);
+        assert_eq!(select_kind(engine::available_engines(), new_engine), kind);
     }
 }
@@ -0,0 +1,516 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::engine::{Engine, GfElement, ShardsRefMut};
+#[cfg(feature = "decoder")]
+use crate::engine::{GF_BITS, GF_ORDER};
+
+// ======================================================================
+// Instrumented - PUBLIC
+
+/// [`Engine`] which counts operations done by another engine,
+/// e.g. for algorithm analysis.
+///
+/// All work is forwarded to the inner engine, so outputs are unchanged.
+/// Counts are collected per method into [`OpCounts`], see [`counters`].
+///
+/// Methods without `self`, i.e. [`xor`], [`xor_into`], [`xor_within`],
+/// [`formal_derivative`] and [`eval_poly`], have no engine instance
+/// to count in, so they are counted per thread and included in
+/// [`counters`] of every [`Instrumented`] engine on that thread.
+/// Encoders and decoders call these on the thread they are used from.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "encoder")] {
+/// use reed_solomon_simd::engine::{Instrumented, NoSimd};
+/// use reed_solomon_simd::rate::{HighRateEncoder, RateEncoder};
+///
+/// let engine = Instrumented::new(NoSimd::new());
+/// let mut encoder = HighRateEncoder::new(4, 4, 64, &engine, None)?;
+/// for original in [[1u8; 64], [2u8; 64], [3u8; 64], [4u8; 64]] {
+///     encoder.add_original_shard(original)?;
+/// }
+/// encoder.encode()?;
+///
+/// // IFFT and FFT of 4 shards of 64 bytes, 2 layers each.
+/// let counts = engine.counters();
+/// assert_eq!(counts.ifft.bytes + counts.fft.bytes, 2 * 4 * 64 * 2);
+/// # }
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+///
+/// [`counters`]: Instrumented::counters
+/// [`xor`]: Engine::xor
+/// [`xor_into`]: Engine::xor_into
+/// [`xor_within`]: Engine::xor_within
+/// [`formal_derivative`]: Engine::formal_derivative
+/// [`eval_poly`]: Engine::eval_poly
+pub struct Instrumented<E: Engine> {
+    inner: E,
+    counters: Counters,
+}
+
+impl<E: Engine> Instrumented<E> {
+    /// Returns operation counts since creation or last [`reset_counters`].
+    ///
+    /// [`reset_counters`]: Instrumented::reset_counters
+    pub fn counters(&self) -> OpCounts {
+        let thread = THREAD_COUNTERS.with(Counters::load);
+        self.counters.load().add(thread)
+    }
+
+    /// Returns the inner engine.
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Creates new [`Instrumented`] which forwards all work to `inner`.
+    pub fn new(inner: E) -> Self {
+        Self {
+            inner,
+            counters: Counters::new(),
+        }
+    }
+
+    /// Resets operation counts to zero, including
+    /// counts of methods without `self` on this thread.
+    pub fn reset_counters(&self) {
+        self.counters.reset();
+        THREAD_COUNTERS.with(Counters::reset);
+    }
+}
+
+// ======================================================================
+// Instrumented - IMPL Engine
+
+impl<E: Engine> Engine for Instrumented<E> {
+    fn fft(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        size: usize,
+        truncated_size: usize,
+        skew_delta: usize,
+    ) {
+        self.counters
+            .fft
+            .add(transform_bytes(data, size, truncated_size));
+        self.inner.fft(data, pos, size, truncated_size, skew_delta)
+    }
+
+    fn ifft(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        size: usize,
+        truncated_size: usize,
+        skew_delta: usize,
+    ) {
+        self.counters
+            .ifft
+            .add(transform_bytes(data, size, truncated_size));
+        self.inner.ifft(data, pos, size, truncated_size, skew_delta)
+    }
+
+    fn mul(&self, x: &mut [u8], log_m: GfElement) {
+        self.counters.mul.add(x.len());
+        self.inner.mul(x, log_m)
+    }
+
+    fn supported() -> bool {
+        E::supported()
+    }
+
+    fn copy_nt(dst: &mut [u8], src: &[u8]) {
+        E::copy_nt(dst, src)
+    }
+
+    fn copy(dst: &mut [u8], src: &[u8]) {
+        E::copy(dst, src)
+    }
+
+    fn fill(x: &mut [u8], byte: u8) {
+        E::fill(x, byte)
+    }
+
+    fn xor(xs: &mut [u8], ys: &[u8]) {
+        THREAD_COUNTERS.with(|counters| counters.xor.add(xs.len()));
+        E::xor(xs, ys)
+    }
+
+    fn xor_into(out: &mut [u8], a: &[u8], b: &[u8]) {
+        THREAD_COUNTERS.with(|counters| counters.xor.add(out.len()));
+        E::xor_into(out, a, b)
+    }
+
+    #[cfg(feature = "decoder")]
+    fn eval_poly(erasures: &mut [GfElement; GF_ORDER], truncated_size: usize) {
+        THREAD_COUNTERS.with(Counters::add_eval_poly);
+        E::eval_poly(erasures, truncated_size)
+    }
+
+    #[cfg(all(feature = "decoder", feature = "unstable-basis"))]
+    fn eval_poly_with_tables(&self, erasures: &mut [GfElement; GF_ORDER], truncated_size: usize) {
+        self.counters.add_eval_poly();
+        self.inner.eval_poly_with_tables(erasures, truncated_size)
+    }
+
+    fn fft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        self.counters.mul_add.add(count * data.shard_bytes());
+        self.inner.fft_butterflies(data, x, y, count, log_m)
+    }
+
+    fn fft_skew_end(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        size: usize,
+        truncated_size: usize,
+    ) {
+        self.counters
+            .fft
+            .add(transform_bytes(data, size, truncated_size));
+        self.inner.fft_skew_end(data, pos, size, truncated_size)
+    }
+
+    fn formal_derivative(data: &mut ShardsRefMut) {
+        let count: usize = (1..data.len()).map(|i| ((i ^ (i - 1)) + 1) >> 1).sum();
+        THREAD_COUNTERS.with(|counters| counters.xor.add(count * data.shard_bytes()));
+        E::formal_derivative(data)
+    }
+
+    fn ifft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        self.counters.mul_add.add(count * data.shard_bytes());
+        self.inner.ifft_butterflies(data, x, y, count, log_m)
+    }
+
+    fn ifft_skew_end(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        size: usize,
+        truncated_size: usize,
+    ) {
+        self.counters
+            .ifft
+            .add(transform_bytes(data, size, truncated_size));
+        self.inner.ifft_skew_end(data, pos, size, truncated_size)
+    }
+
+    fn linear_combination(&self, out: &mut [u8], inputs: &[&[u8]], coeffs: &[GfElement]) {
+        self.counters.mul_add.add(inputs.len() * out.len());
+        self.inner.linear_combination(out, inputs, coeffs)
+    }
+
+    fn xor_within(data: &mut ShardsRefMut, x: usize, y: usize, count: usize) {
+        THREAD_COUNTERS.with(|counters| counters.xor.add(count * data.shard_bytes()));
+        E::xor_within(data, x, y, count)
+    }
+}
+
+// ======================================================================
+// OpCount - PUBLIC
+
+/// Number of calls and bytes processed by one kind of operation,
+/// see [`OpCounts`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpCount {
+    /// Number of calls.
+    pub calls: u64,
+    /// Number of bytes processed by all calls.
+    pub bytes: u64,
+}
+
+// ======================================================================
+// OpCounts - PUBLIC
+
+/// Operation counts of an [`Instrumented`] engine.
+///
+/// Bytes are counted as follows, where `shard_bytes` is
+/// the size of one shard:
+///
+/// - `mul` - bytes multiplied.
+/// - `mul_add` - bytes multiplied and added to output, i.e.
+///   `count * shard_bytes` for FFT / IFFT butterflies and
+///   `inputs.len() * out.len()` for [`linear_combination`].
+/// - `xor` - bytes added to output, including [`formal_derivative`].
+/// - `fft`, `ifft` - `truncated_size * shard_bytes * log2(size)`,
+///   i.e. each of the `log2(size)` layers of butterflies counted
+///   as one pass over the shards within `truncated_size`.
+/// - `eval_poly` - bytes of the erasure array, i.e. `2 * GF_ORDER`.
+/// - `fwht` - each [`eval_poly`] does two Walsh-Hadamard transforms
+///   which are counted like `fft` with `size = GF_ORDER`
+///   and `shard_bytes = 2`.
+///
+/// Engines provided by this crate do butterflies inside [`fft`] and
+/// [`ifft`] without calling other [`Engine`] methods, so `mul_add`
+/// only counts butterflies and linear combinations called directly.
+///
+/// [`linear_combination`]: Engine::linear_combination
+/// [`formal_derivative`]: Engine::formal_derivative
+/// [`eval_poly`]: Engine::eval_poly
+/// [`fft`]: Engine::fft
+/// [`ifft`]: Engine::ifft
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpCounts {
+    /// [`Engine::mul`]
+    pub mul: OpCount,
+    /// [`Engine::fft_butterflies`], [`Engine::ifft_butterflies`]
+    /// and [`Engine::linear_combination`].
+    pub mul_add: OpCount,
+    /// [`Engine::xor`], [`Engine::xor_into`], [`Engine::xor_within`]
+    /// and [`Engine::formal_derivative`].
+    pub xor: OpCount,
+    /// [`Engine::fft`] and [`Engine::fft_skew_end`].
+    pub fft: OpCount,
+    /// [`Engine::ifft`] and [`Engine::ifft_skew_end`].
+    pub ifft: OpCount,
+    /// Walsh-Hadamard transforms of [`Engine::eval_poly`].
+    pub fwht: OpCount,
+    /// [`Engine::eval_poly`]
+    pub eval_poly: OpCount,
+}
+
+impl OpCounts {
+    fn add(self, other: OpCounts) -> OpCounts {
+        let add = |a: OpCount, b: OpCount| OpCount {
+            calls: a.calls + b.calls,
+            bytes: a.bytes + b.bytes,
+        };
+
+        OpCounts {
+            mul: add(self.mul, other.mul),
+            mul_add: add(self.mul_add, other.mul_add),
+            xor: add(self.xor, other.xor),
+            fft: add(self.fft, other.fft),
+            ifft: add(self.ifft, other.ifft),
+            fwht: add(self.fwht, other.fwht),
+            eval_poly: add(self.eval_poly, other.eval_poly),
+        }
+    }
+}
+
+// ======================================================================
+// THREAD LOCAL - PRIVATE
+
+thread_local! {
+    // Counts of `Engine` methods without `self`, see `Instrumented`.
+    static THREAD_COUNTERS: Counters = const { Counters::new() };
+}
+
+// ======================================================================
+// AtomicOpCount - PRIVATE
+
+struct AtomicOpCount {
+    calls: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl AtomicOpCount {
+    const fn new() -> Self {
+        Self {
+            calls: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+        }
+    }
+
+    fn add(&self, bytes: usize) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn load(&self) -> OpCount {
+        OpCount {
+            calls: self.calls.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        self.calls.store(0, Ordering::Relaxed);
+        self.bytes.store(0, Ordering::Relaxed);
+    }
+}
+
+// ======================================================================
+// Counters - PRIVATE
+
+struct Counters {
+    mul: AtomicOpCount,
+    mul_add: AtomicOpCount,
+    xor: AtomicOpCount,
+    fft: AtomicOpCount,
+    ifft: AtomicOpCount,
+    fwht: AtomicOpCount,
+    eval_poly: AtomicOpCount,
+}
+
+impl Counters {
+    const fn new() -> Self {
+        Self {
+            mul: AtomicOpCount::new(),
+            mul_add: AtomicOpCount::new(),
+            xor: AtomicOpCount::new(),
+            fft: AtomicOpCount::new(),
+            ifft: AtomicOpCount::new(),
+            fwht: AtomicOpCount::new(),
+            eval_poly: AtomicOpCount::new(),
+        }
+    }
+
+    // `eval_poly` and its two Walsh-Hadamard transforms.
+    #[cfg(feature = "decoder")]
+    fn add_eval_poly(&self) {
+        let fwht_bytes = 2 * GF_ORDER * GF_BITS;
+        self.eval_poly.add(2 * GF_ORDER);
+        self.fwht.add(fwht_bytes);
+        self.fwht.add(fwht_bytes);
+    }
+
+    fn load(&self) -> OpCounts {
+        OpCounts {
+            mul: self.mul.load(),
+            mul_add: self.mul_add.load(),
+            xor: self.xor.load(),
+            fft: self.fft.load(),
+            ifft: self.ifft.load(),
+            fwht: self.fwht.load(),
+            eval_poly: self.eval_poly.load(),
+        }
+    }
+
+    fn reset(&self) {
+        self.mul.reset();
+        self.mul_add.reset();
+        self.xor.reset();
+        self.fft.reset();
+        self.ifft.reset();
+        self.fwht.reset();
+        self.eval_poly.reset();
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+// Bytes counted for FFT / IFFT, see `OpCounts`.
+fn transform_bytes(data: &ShardsRefMut, size: usize, truncated_size: usize) -> usize {
+    truncated_size * data.shard_bytes() * size.trailing_zeros() as usize
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
+mod tests {
+    use super::*;
+    use crate::{
+        engine::NoSimd,
+        rate::{HighRateDecoder, HighRateEncoder, RateDecoder, RateEncoder},
+        test_util,
+    };
+
+    const SHARD_BYTES: usize = 64;
+
+    fn encode(engine: &Instrumented<NoSimd>, count: usize) -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+        let original = test_util::generate_original(count, SHARD_BYTES, 200);
+        let mut encoder = HighRateEncoder::new(count, count, SHARD_BYTES, engine, None).unwrap();
+        for original in &original {
+            encoder.add_original_shard(original).unwrap();
+        }
+        let result = encoder.encode().unwrap();
+        let recovery = result.recovery_iter().map(<[u8]>::to_vec).collect();
+        (original, recovery)
+    }
+
+    #[test]
+    fn outputs_unchanged() {
+        let engine = Instrumented::new(NoSimd::new());
+        let (original, recovery) = encode(&engine, 100);
+        assert_eq!(recovery, crate::encode(100, 100, &original).unwrap());
+
+        let mut decoder = HighRateDecoder::new(100, 100, SHARD_BYTES, &engine, None).unwrap();
+        for (index, original) in original.iter().enumerate().skip(50) {
+            decoder.add_original_shard(index, original).unwrap();
+        }
+        for (index, recovery) in recovery.iter().enumerate().take(50) {
+            decoder.add_recovery_shard(index, recovery).unwrap();
+        }
+        let result = decoder.decode().unwrap();
+        for (index, restored) in result.restored_original_iter() {
+            assert_eq!(restored, original[index]);
+        }
+
+        let counts = engine.counters();
+        assert_eq!(counts.eval_poly.calls, 1);
+        assert_eq!(counts.fwht.calls, 2);
+        assert!(counts.mul.calls > 0);
+        assert!(counts.xor.calls > 0);
+
+        engine.reset_counters();
+        assert_eq!(engine.counters(), OpCounts::default());
+    }
+
+    // Doubling `n` must grow counts like `n log n`,
+    // which also guards against accidentally quadratic code paths.
+    #[test]
+    fn n_log_n() {
+        let mut previous: Option<(u64, u64)> = None;
+        for log_n in 6..=12 {
+            let n = 1 << log_n;
+            let engine = Instrumented::new(NoSimd::new());
+
+            let (original, recovery) = encode(&engine, n);
+            let counts = engine.counters();
+            assert_eq!(counts.ifft.calls, 1);
+            assert_eq!(counts.fft.calls, 1);
+            assert_eq!(
+                counts.ifft.bytes + counts.fft.bytes,
+                (2 * n * SHARD_BYTES * log_n) as u64
+            );
+            assert_eq!(counts.mul, OpCount::default());
+
+            // Decode with half of original shards lost.
+            engine.reset_counters();
+            let mut decoder = HighRateDecoder::new(n, n, SHARD_BYTES, &engine, None).unwrap();
+            for (index, original) in original.iter().enumerate().skip(n / 2) {
+                decoder.add_original_shard(index, original).unwrap();
+            }
+            for (index, recovery) in recovery.iter().enumerate().take(n / 2) {
+                decoder.add_recovery_shard(index, recovery).unwrap();
+            }
+            drop(decoder.decode().unwrap());
+
+            let counts = engine.counters();
+            assert_eq!(counts.eval_poly.calls, 1);
+            // Formal derivative is `O(n log n)` XORs too.
+            let n_log_n_bytes = counts.ifft.bytes + counts.fft.bytes + counts.xor.bytes;
+            // Multiplication by erasure locations before and after transforms.
+            let linear_bytes = counts.mul.bytes;
+            assert!(linear_bytes <= (3 * n * SHARD_BYTES) as u64);
+
+            if let Some((previous_n_log_n, previous_linear)) = previous {
+                // `2n log 2n / (n log n) = 2 + 2 / log n`
+                let growth = 2.0 + 2.0 / (log_n - 1) as f64;
+                assert!(n_log_n_bytes as f64 <= previous_n_log_n as f64 * growth);
+                assert!(linear_bytes <= previous_linear * 2);
+            }
+            previous = Some((n_log_n_bytes, linear_bytes));
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::engine::{
+    self,
     tables::{self, Exp, Log, Skew},
     Engine, GfElement, ShardsRefMut, GF_MODULUS,
 };
@@ -44,8 +45,8 @@ impl Engine for Naive {
         truncated_size: usize,
         skew_delta: usize,
     ) {
-        debug_assert!(size.is_power_of_two());
-        debug_assert!(truncated_size <= size);
+        engine::check_fft_params(size, truncated_size, skew_delta);
+        debug_assert!(skew_delta & (size - 1) == 0);
 
         let mut dist = size / 2;
         while dist > 0 {
@@ -76,8 +77,8 @@ impl Engine for Naive {
         truncated_size: usize,
         skew_delta: usize,
     ) {
-        debug_assert!(size.is_power_of_two());
-        debug_assert!(truncated_size <= size);
+        engine::check_fft_params(size, truncated_size, skew_delta);
+        debug_assert!(skew_delta & (size - 1) == 0);
 
         let mut dist = 1;
         while dist < size {
@@ -116,6 +117,18 @@ impl Engine for Naive {
             pos += 64;
         }
     }
+
+    fn linear_combination(&self, out: &mut [u8], inputs: &[&[u8]], coeffs: &[GfElement]) {
+        let terms = engine::linear_combination_terms(out, inputs, coeffs);
+        out.fill(0);
+        for (input, log_m) in terms {
+            self.mul_add(out, input, log_m);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Naive"
+    }
 }
 
 // ======================================================================
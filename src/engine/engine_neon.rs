@@ -1,7 +1,9 @@
+#[cfg(feature = "decoder")]
+use crate::engine::GF_ORDER;
 use crate::engine::{
     self,
-    tables::{self, Mul128, Multiply128lutT, Skew},
-    Engine, GfElement, ShardsRefMut, GF_MODULUS, GF_ORDER,
+    tables::{self, Mul128Luts, Multiply128lutT, Skew},
+    Engine, GfElement, NoSimd, ShardsRefMut, GF_MODULUS,
 };
 use std::arch::aarch64::*;
 use std::iter::zip;
@@ -17,7 +19,7 @@ use std::iter::zip;
 /// [`NoSimd`]: crate::engine::NoSimd
 #[derive(Clone)]
 pub struct Neon {
-    mul128: &'static Mul128,
+    mul128: Mul128Luts,
     skew: &'static Skew,
 }
 
@@ -30,7 +32,7 @@ impl Neon {
     ///
     /// [`LogWalsh`]: crate::engine::tables::LogWalsh
     pub fn new() -> Self {
-        let mul128 = tables::initialize_mul128();
+        let mul128 = Mul128Luts::new();
         let skew = tables::initialize_skew();
 
         Self { mul128, skew }
@@ -70,6 +72,60 @@ impl Engine for Neon {
         }
     }
 
+    fn fft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        unsafe {
+            self.fft_butterflies_neon(data, x, y, count, log_m);
+        }
+    }
+
+    fn ifft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        unsafe {
+            self.ifft_butterflies_neon(data, x, y, count, log_m);
+        }
+    }
+
+    fn linear_combination(&self, out: &mut [u8], inputs: &[&[u8]], coeffs: &[GfElement]) {
+        unsafe {
+            self.linear_combination_neon(out, inputs, coeffs);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Neon"
+    }
+
+    fn supported() -> bool {
+        // Miri doesn't support SIMD intrinsics.
+        !cfg!(miri) && std::arch::is_aarch64_feature_detected!("neon")
+    }
+
+    fn copy_nt(dst: &mut [u8], src: &[u8]) {
+        unsafe { Self::copy_nt_neon(dst, src) }
+    }
+
+    fn fill(x: &mut [u8], byte: u8) {
+        unsafe { Self::fill_neon(x, byte) }
+    }
+
+    fn xor_into(out: &mut [u8], a: &[u8], b: &[u8]) {
+        unsafe { Self::xor_into_neon(out, a, b) }
+    }
+
+    #[cfg(feature = "decoder")]
     fn eval_poly(erasures: &mut [GfElement; GF_ORDER], truncated_size: usize) {
         unsafe { Self::eval_poly_neon(erasures, truncated_size) }
     }
@@ -90,9 +146,45 @@ impl Default for Neon {
 //
 
 impl Neon {
+    #[target_feature(enable = "neon")]
+    unsafe fn linear_combination_neon(
+        &self,
+        out: &mut [u8],
+        inputs: &[&[u8]],
+        coeffs: &[GfElement],
+    ) {
+        let terms: Vec<_> = engine::linear_combination_terms(out, inputs, coeffs)
+            .into_iter()
+            .map(|(input, log_m)| (input, self.mul128.get(log_m)))
+            .collect();
+
+        for (block, chunk) in out.chunks_exact_mut(64).enumerate() {
+            let out_ptr: *mut u8 = chunk.as_mut_ptr();
+            unsafe {
+                let mut acc0_lo = vdupq_n_u8(0);
+                let mut acc1_lo = vdupq_n_u8(0);
+                let mut acc0_hi = vdupq_n_u8(0);
+                let mut acc1_hi = vdupq_n_u8(0);
+                for (input, lut) in &terms {
+                    let y_ptr: *const u8 = input[block * 64..][..64].as_ptr();
+                    let y0_lo = vld1q_u8(y_ptr);
+                    let y1_lo = vld1q_u8(y_ptr.add(16));
+                    let y0_hi = vld1q_u8(y_ptr.add(16 * 2));
+                    let y1_hi = vld1q_u8(y_ptr.add(16 * 3));
+                    (acc0_lo, acc0_hi) = Self::muladd_128(acc0_lo, acc0_hi, y0_lo, y0_hi, lut);
+                    (acc1_lo, acc1_hi) = Self::muladd_128(acc1_lo, acc1_hi, y1_lo, y1_hi, lut);
+                }
+                vst1q_u8(out_ptr, acc0_lo);
+                vst1q_u8(out_ptr.add(16), acc1_lo);
+                vst1q_u8(out_ptr.add(16 * 2), acc0_hi);
+                vst1q_u8(out_ptr.add(16 * 3), acc1_hi);
+            }
+        }
+    }
+
     #[target_feature(enable = "neon")]
     unsafe fn mul_neon(&self, x: &mut [u8], log_m: GfElement) {
-        let lut = &self.mul128[log_m as usize];
+        let lut: &Multiply128lutT = &self.mul128.get(log_m);
 
         for chunk in x.chunks_exact_mut(64) {
             let x_ptr: *mut u8 = chunk.as_mut_ptr();
@@ -181,8 +273,7 @@ impl Neon {
 impl Neon {
     // Implementation of LEO_FFTB_128
     #[inline(always)]
-    fn fftb_128(&self, x: &mut [u8; 64], y: &mut [u8; 64], log_m: GfElement) {
-        let lut = &self.mul128[log_m as usize];
+    fn fftb_128(&self, x: &mut [u8; 64], y: &mut [u8; 64], lut: &Multiply128lutT) {
         let x_ptr: *mut u8 = x.as_mut_ptr();
         let y_ptr: *mut u8 = y.as_mut_ptr();
         unsafe {
@@ -219,17 +310,38 @@ impl Neon {
     // Partial butterfly, caller must do `GF_MODULUS` check with `xor`.
     #[inline(always)]
     fn fft_butterfly_partial(&self, x: &mut [u8], y: &mut [u8], log_m: GfElement) {
+        let lut: &Multiply128lutT = &self.mul128.get(log_m);
+
         // While we wait for array_chunks/slice_as_chunks (#74985) to become stable,
         // we have to try_into().unwrap() (which cannot fail in this case)
         for (x_chunk, y_chunk) in zip(x.chunks_exact_mut(64), y.chunks_exact_mut(64)) {
             self.fftb_128(
                 x_chunk.try_into().unwrap(),
                 y_chunk.try_into().unwrap(),
-                log_m,
+                lut,
             );
         }
     }
 
+    // Partial butterflies of shards `x .. x + count` with `y .. y + count`
+    // including `GF_MODULUS` check, see `Engine::fft_butterflies`.
+    #[inline(always)]
+    fn fft_butterflies_private(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        let (xs, ys) = data.flat2_mut(x, y, count);
+        if log_m == GF_MODULUS {
+            Self::xor(ys, xs);
+        } else {
+            self.fft_butterfly_partial(xs, ys, log_m);
+        }
+    }
+
     #[inline(always)]
     fn fft_butterfly_two_layers(
         &self,
@@ -267,6 +379,19 @@ impl Neon {
         }
     }
 
+    #[target_feature(enable = "neon")]
+    unsafe fn fft_butterflies_neon(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        // Drop unsafe privileges
+        self.fft_butterflies_private(data, x, y, count, log_m);
+    }
+
     #[target_feature(enable = "neon")]
     unsafe fn fft_private_neon(
         &self,
@@ -289,45 +414,73 @@ impl Neon {
         truncated_size: usize,
         skew_delta: usize,
     ) {
-        // TWO LAYERS AT TIME
-
-        let mut dist4 = size;
-        let mut dist = size >> 2;
-        while dist != 0 {
-            let mut r = 0;
-            while r < truncated_size {
-                let base = r + dist + skew_delta - 1;
-
-                let log_m01 = self.skew[base];
-                let log_m02 = self.skew[base + dist];
-                let log_m23 = self.skew[base + dist * 2];
-
-                for i in r..r + dist {
-                    self.fft_butterfly_two_layers(data, pos + i, dist, log_m01, log_m23, log_m02)
+        engine::check_fft_params(size, truncated_size, skew_delta);
+
+        for (dist, layers) in engine::fft_steps(size) {
+            if layers == 1 {
+                // ONE LAYER
+
+                let mut r = 0;
+                while r < truncated_size {
+                    // SAFETY: Index is checked by `check_fft_params` above.
+                    let log_m = unsafe { engine::skew_at(self.skew, r + dist + skew_delta - 1) };
+                    self.fft_butterflies_private(data, pos + r, pos + r + dist, dist, log_m);
+                    r += dist * 2;
                 }
-
-                r += dist4;
-            }
-            dist4 = dist;
-            dist >>= 2;
-        }
-
-        // FINAL ODD LAYER
-
-        if dist4 == 2 {
-            let mut r = 0;
-            while r < truncated_size {
-                let log_m = self.skew[r + skew_delta];
-
-                let (x, y) = data.dist2_mut(pos + r, 1);
-
-                if log_m == GF_MODULUS {
-                    Self::xor(y, x);
-                } else {
-                    self.fft_butterfly_partial(x, y, log_m)
+            } else {
+                // TWO LAYERS
+
+                let dist4 = dist * 4;
+                let batch = engine::butterfly_batch(data.shard_bytes(), dist);
+
+                let mut r = 0;
+                while r < truncated_size {
+                    let base = r + dist + skew_delta - 1;
+
+                    // SAFETY: Indexes are checked by `check_fft_params` above.
+                    let (log_m01, log_m02, log_m23) = unsafe {
+                        (
+                            engine::skew_at(self.skew, base),
+                            engine::skew_at(self.skew, base + dist),
+                            engine::skew_at(self.skew, base + dist * 2),
+                        )
+                    };
+
+                    if dist >= 4 {
+                        // Same as below, with `batch` consecutive `i` at a time.
+                        for i in (pos + r..pos + r + dist).step_by(batch) {
+                            self.fft_butterflies_private(data, i, i + dist * 2, batch, log_m02);
+                            self.fft_butterflies_private(
+                                data,
+                                i + dist,
+                                i + dist * 3,
+                                batch,
+                                log_m02,
+                            );
+                            self.fft_butterflies_private(data, i, i + dist, batch, log_m01);
+                            self.fft_butterflies_private(
+                                data,
+                                i + dist * 2,
+                                i + dist * 3,
+                                batch,
+                                log_m23,
+                            );
+                        }
+                    } else {
+                        for i in r..r + dist {
+                            self.fft_butterfly_two_layers(
+                                data,
+                                pos + i,
+                                dist,
+                                log_m01,
+                                log_m23,
+                                log_m02,
+                            )
+                        }
+                    }
+
+                    r += dist4;
                 }
-
-                r += 2;
             }
         }
     }
@@ -339,8 +492,7 @@ impl Neon {
 impl Neon {
     // Implementation of LEO_IFFTB_128
     #[inline(always)]
-    fn ifftb_128(&self, x: &mut [u8; 64], y: &mut [u8; 64], log_m: GfElement) {
-        let lut = &self.mul128[log_m as usize];
+    fn ifftb_128(&self, x: &mut [u8; 64], y: &mut [u8; 64], lut: &Multiply128lutT) {
         let x_ptr: *mut u8 = x.as_mut_ptr();
         let y_ptr: *mut u8 = y.as_mut_ptr();
 
@@ -377,17 +529,38 @@ impl Neon {
 
     #[inline(always)]
     fn ifft_butterfly_partial(&self, x: &mut [u8], y: &mut [u8], log_m: GfElement) {
+        let lut: &Multiply128lutT = &self.mul128.get(log_m);
+
         // While we wait for array_chunks/slice_as_chunks (#74985) to become stable,
         // we'll have to try_into() to array
         for (x_chunk, y_chunk) in zip(x.chunks_exact_mut(64), y.chunks_exact_mut(64)) {
             self.ifftb_128(
                 x_chunk.try_into().unwrap(),
                 y_chunk.try_into().unwrap(),
-                log_m,
+                lut,
             );
         }
     }
 
+    // Partial butterflies of shards `x .. x + count` with `y .. y + count`
+    // including `GF_MODULUS` check, see `Engine::ifft_butterflies`.
+    #[inline(always)]
+    fn ifft_butterflies_private(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        let (xs, ys) = data.flat2_mut(x, y, count);
+        if log_m == GF_MODULUS {
+            Self::xor(ys, xs);
+        } else {
+            self.ifft_butterfly_partial(xs, ys, log_m);
+        }
+    }
+
     #[inline(always)]
     fn ifft_butterfly_two_layers(
         &self,
@@ -425,6 +598,19 @@ impl Neon {
         }
     }
 
+    #[target_feature(enable = "neon")]
+    unsafe fn ifft_butterflies_neon(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        // Drop unsafe privileges
+        self.ifft_butterflies_private(data, x, y, count, log_m);
+    }
+
     #[target_feature(enable = "neon")]
     unsafe fn ifft_private_neon(
         &self,
@@ -447,52 +633,162 @@ impl Neon {
         truncated_size: usize,
         skew_delta: usize,
     ) {
-        // TWO LAYERS AT TIME
+        engine::check_fft_params(size, truncated_size, skew_delta);
+
+        for (dist, layers) in engine::ifft_steps(size) {
+            if layers == 1 {
+                // ONE LAYER
+
+                let mut r = 0;
+                while r < truncated_size {
+                    // SAFETY: Index is checked by `check_fft_params` above.
+                    let log_m = unsafe { engine::skew_at(self.skew, r + dist + skew_delta - 1) };
+                    self.ifft_butterflies_private(data, pos + r, pos + r + dist, dist, log_m);
+                    r += dist * 2;
+                }
+            } else {
+                // TWO LAYERS
+
+                let dist4 = dist * 4;
+                let batch = engine::butterfly_batch(data.shard_bytes(), dist);
+
+                let mut r = 0;
+                while r < truncated_size {
+                    let base = r + dist + skew_delta - 1;
+
+                    // SAFETY: Indexes are checked by `check_fft_params` above.
+                    let (log_m01, log_m02, log_m23) = unsafe {
+                        (
+                            engine::skew_at(self.skew, base),
+                            engine::skew_at(self.skew, base + dist),
+                            engine::skew_at(self.skew, base + dist * 2),
+                        )
+                    };
+
+                    if dist >= 4 {
+                        // Same as below, with `batch` consecutive `i` at a time.
+                        for i in (pos + r..pos + r + dist).step_by(batch) {
+                            self.ifft_butterflies_private(data, i, i + dist, batch, log_m01);
+                            self.ifft_butterflies_private(
+                                data,
+                                i + dist * 2,
+                                i + dist * 3,
+                                batch,
+                                log_m23,
+                            );
+                            self.ifft_butterflies_private(data, i, i + dist * 2, batch, log_m02);
+                            self.ifft_butterflies_private(
+                                data,
+                                i + dist,
+                                i + dist * 3,
+                                batch,
+                                log_m02,
+                            );
+                        }
+                    } else {
+                        for i in r..r + dist {
+                            self.ifft_butterfly_two_layers(
+                                data,
+                                pos + i,
+                                dist,
+                                log_m01,
+                                log_m23,
+                                log_m02,
+                            )
+                        }
+                    }
+
+                    r += dist4;
+                }
+            }
+        }
+    }
+}
 
-        let mut dist = 1;
-        let mut dist4 = 4;
-        while dist4 <= size {
-            let mut r = 0;
-            while r < truncated_size {
-                let base = r + dist + skew_delta - 1;
+// ======================================================================
+// Neon - PRIVATE - Non-temporal copy
 
-                let log_m01 = self.skew[base];
-                let log_m02 = self.skew[base + dist];
-                let log_m23 = self.skew[base + dist * 2];
+impl Neon {
+    #[target_feature(enable = "neon")]
+    unsafe fn copy_nt_neon(dst: &mut [u8], src: &[u8]) {
+        assert_eq!(dst.len(), src.len(), "dst and src must have same length");
+
+        // Pair stores must be aligned so unaligned head and tail are copied normally.
+        let head = dst.as_ptr().align_offset(32).min(dst.len());
+        let (dst_head, dst) = dst.split_at_mut(head);
+        let (src_head, src) = src.split_at(head);
+        dst_head.copy_from_slice(src_head);
+
+        let mut dst_chunks = dst.chunks_exact_mut(32);
+        let mut src_chunks = src.chunks_exact(32);
+        for (dst, src) in zip(&mut dst_chunks, &mut src_chunks) {
+            unsafe {
+                let lo = vld1q_u8(src.as_ptr());
+                let hi = vld1q_u8(src.as_ptr().add(16));
+                // There is no intrinsic for `STNP`.
+                std::arch::asm!(
+                    "stnp {lo:q}, {hi:q}, [{dst}]",
+                    lo = in(vreg) lo,
+                    hi = in(vreg) hi,
+                    dst = in(reg) dst.as_mut_ptr(),
+                    options(nostack, preserves_flags),
+                );
+            }
+        }
+        dst_chunks
+            .into_remainder()
+            .copy_from_slice(src_chunks.remainder());
+    }
+}
 
-                for i in r..r + dist {
-                    self.ifft_butterfly_two_layers(data, pos + i, dist, log_m01, log_m23, log_m02)
-                }
+// ======================================================================
+// Neon - PRIVATE - Fill
 
-                r += dist4;
+impl Neon {
+    #[target_feature(enable = "neon")]
+    unsafe fn fill_neon(x: &mut [u8], byte: u8) {
+        let value = vdupq_n_u8(byte);
+        let mut chunks = x.chunks_exact_mut(16);
+        for x in &mut chunks {
+            unsafe {
+                vst1q_u8(x.as_mut_ptr(), value);
             }
-            dist = dist4;
-            dist4 <<= 2;
         }
+        chunks.into_remainder().fill(byte);
+    }
+}
+
+// ======================================================================
+// Neon - PRIVATE - XOR
 
-        // FINAL ODD LAYER
+impl Neon {
+    #[target_feature(enable = "neon")]
+    unsafe fn xor_into_neon(out: &mut [u8], a: &[u8], b: &[u8]) {
+        engine::assert_xor_into_args(out, a, b);
 
-        if dist < size {
-            let log_m = self.skew[dist + skew_delta - 1];
-            if log_m == GF_MODULUS {
-                Self::xor_within(data, pos + dist, pos, dist);
-            } else {
-                let (mut a, mut b) = data.split_at_mut(pos + dist);
-                for i in 0..dist {
-                    self.ifft_butterfly_partial(
-                        &mut a[pos + i], // data[pos + i]
-                        &mut b[i],       // data[pos + i + dist]
-                        log_m,
-                    );
-                }
+        let mut out_chunks = out.chunks_exact_mut(16);
+        let mut a_chunks = a.chunks_exact(16);
+        let mut b_chunks = b.chunks_exact(16);
+        for (out, (a, b)) in zip(&mut out_chunks, zip(&mut a_chunks, &mut b_chunks)) {
+            unsafe {
+                let a = vld1q_u8(a.as_ptr());
+                let b = vld1q_u8(b.as_ptr());
+                vst1q_u8(out.as_mut_ptr(), veorq_u8(a, b));
             }
         }
+
+        NoSimd::xor_into(
+            out_chunks.into_remainder(),
+            a_chunks.remainder(),
+            b_chunks.remainder(),
+        );
     }
 }
 
 // ======================================================================
 // Neon - PRIVATE - Evaluate polynomial
 
+#[cfg(feature = "decoder")]
 impl Neon {
     #[target_feature(enable = "neon")]
     unsafe fn eval_poly_neon(erasures: &mut [GfElement; GF_ORDER], truncated_size: usize) {
@@ -1,9 +1,21 @@
 use std::iter::zip;
 
+#[cfg(feature = "unstable-basis")]
+use crate::engine::tables::BasisTables;
+#[cfg(not(feature = "constant-time"))]
+use crate::engine::tables::Mul16;
+#[cfg(all(feature = "decoder", feature = "unstable-basis"))]
+use crate::engine::GF_ORDER;
 use crate::engine::{
-    tables::{self, Mul16, Skew},
+    self,
+    tables::{self, Skew},
     Engine, GfElement, ShardsRefMut, GF_MODULUS,
 };
+#[cfg(feature = "constant-time")]
+use crate::engine::{
+    tables::{Exp, Log},
+    GF_BITS,
+};
 
 // ======================================================================
 // NoSimd - PUBLIC
@@ -11,10 +23,28 @@ use crate::engine::{
 /// Optimized [`Engine`] without SIMD.
 ///
 /// [`NoSimd`] is a basic optimized engine which works on all CPUs.
+///
+/// By default multiplications use [`Mul16`] lookup-table indexed by data,
+/// so their timing may leak data via cache. With `constant-time` feature
+/// multiplications use `tables::mul_ct` instead, which is slower
+/// but has no memory indexing or branches which depend on data.
+///
+/// With `unstable-basis` feature `NoSimd::with_tables` creates
+/// [`NoSimd`] which uses tables of another Cantor basis.
+///
+/// [`Mul16`]: crate::engine::tables::Mul16
 #[derive(Clone)]
 pub struct NoSimd {
+    #[cfg(not(feature = "constant-time"))]
     mul16: &'static Mul16,
+    #[cfg(feature = "constant-time")]
+    exp: &'static Exp,
+    #[cfg(feature = "constant-time")]
+    log: &'static Log,
     skew: &'static Skew,
+    // Custom tables given to `with_tables`, `None` for global tables.
+    #[cfg(feature = "unstable-basis")]
+    tables: Option<&'static BasisTables>,
 }
 
 impl NoSimd {
@@ -26,10 +56,66 @@ impl NoSimd {
     ///
     /// [`LogWalsh`]: crate::engine::tables::LogWalsh
     pub fn new() -> Self {
+        #[cfg(not(feature = "constant-time"))]
         let mul16 = tables::initialize_mul16();
+        #[cfg(feature = "constant-time")]
+        let (exp, log) = tables::initialize_exp_log();
         let skew = tables::initialize_skew();
 
-        Self { mul16, skew }
+        Self {
+            #[cfg(not(feature = "constant-time"))]
+            mul16,
+            #[cfg(feature = "constant-time")]
+            exp,
+            #[cfg(feature = "constant-time")]
+            log,
+            skew,
+            #[cfg(feature = "unstable-basis")]
+            tables: None,
+        }
+    }
+
+    /// Creates new [`NoSimd`] which uses given tables
+    /// of an alternative Cantor basis instead of global tables,
+    /// see [Alternative basis].
+    ///
+    /// **Shards encoded with this engine are incompatible with
+    /// the standard scheme** unless `tables` are for [`CANTOR_BASIS`].
+    /// They must be decoded with an engine using tables of the same basis.
+    ///
+    /// Engines keep `'static` references to tables,
+    /// so `tables` are usually leaked once and then shared.
+    ///
+    /// Requires `unstable-basis` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::engine::{tables::BasisTables, NoSimd, CANTOR_BASIS};
+    ///
+    /// let mut basis = CANTOR_BASIS;
+    /// for i in 3..16 {
+    ///     basis[i] ^= CANTOR_BASIS[i - 3];
+    /// }
+    ///
+    /// let tables: &'static BasisTables = Box::leak(Box::new(BasisTables::new(basis)));
+    /// let engine = NoSimd::with_tables(tables);
+    /// ```
+    ///
+    /// [Alternative basis]: crate::engine::tables#alternative-basis
+    /// [`CANTOR_BASIS`]: crate::engine::CANTOR_BASIS
+    #[cfg(feature = "unstable-basis")]
+    pub fn with_tables(tables: &'static BasisTables) -> Self {
+        Self {
+            #[cfg(not(feature = "constant-time"))]
+            mul16: tables.mul16(),
+            #[cfg(feature = "constant-time")]
+            exp: tables.exp(),
+            #[cfg(feature = "constant-time")]
+            log: tables.log(),
+            skew: tables.skew(),
+            tables: Some(tables),
+        }
     }
 }
 
@@ -57,23 +143,86 @@ impl Engine for NoSimd {
     }
 
     fn mul(&self, x: &mut [u8], log_m: GfElement) {
-        let lut = &self.mul16[log_m as usize];
+        let lut = self.mul_lut(log_m);
 
         for x_chunk in x.chunks_exact_mut(64) {
             let (x_lo, x_hi) = x_chunk.split_at_mut(32);
 
             for i in 0..32 {
-                let lo = x_lo[i];
-                let hi = x_hi[i];
-                let prod = lut[0][usize::from(lo & 15)]
-                    ^ lut[1][usize::from(lo >> 4)]
-                    ^ lut[2][usize::from(hi & 15)]
-                    ^ lut[3][usize::from(hi >> 4)];
+                let prod = Self::mul_one(lut, x_lo[i], x_hi[i]);
                 x_lo[i] = prod as u8;
                 x_hi[i] = (prod >> 8) as u8;
             }
         }
     }
+
+    #[cfg(all(feature = "decoder", feature = "unstable-basis"))]
+    fn eval_poly_with_tables(&self, erasures: &mut [GfElement; GF_ORDER], truncated_size: usize) {
+        match self.tables {
+            Some(tables) => {
+                engine::eval_poly_with_log_walsh(erasures, truncated_size, tables.log_walsh())
+            }
+            None => Self::eval_poly(erasures, truncated_size),
+        }
+    }
+
+    fn fft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        self.fft_butterflies_private(data, x, y, count, log_m);
+    }
+
+    fn ifft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        self.ifft_butterflies_private(data, x, y, count, log_m);
+    }
+
+    fn linear_combination(&self, out: &mut [u8], inputs: &[&[u8]], coeffs: &[GfElement]) {
+        #[cfg(feature = "unstable-basis")]
+        let terms = match self.tables {
+            Some(tables) => {
+                engine::linear_combination_terms_with_log(out, inputs, coeffs, tables.log())
+            }
+            None => engine::linear_combination_terms(out, inputs, coeffs),
+        };
+        #[cfg(not(feature = "unstable-basis"))]
+        let terms = engine::linear_combination_terms(out, inputs, coeffs);
+
+        let terms: Vec<_> = terms
+            .into_iter()
+            .map(|(input, log_m)| (input, self.mul_lut(log_m)))
+            .collect();
+
+        for (block, out_chunk) in out.chunks_exact_mut(64).enumerate() {
+            let (out_lo, out_hi) = out_chunk.split_at_mut(32);
+            out_lo.fill(0);
+            out_hi.fill(0);
+
+            for &(input, lut) in &terms {
+                let (y_lo, y_hi) = input[block * 64..][..64].split_at(32);
+                for i in 0..32 {
+                    let prod = Self::mul_one(lut, y_lo[i], y_hi[i]);
+                    out_lo[i] ^= prod as u8;
+                    out_hi[i] ^= (prod >> 8) as u8;
+                }
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "NoSimd"
+    }
 }
 
 // ======================================================================
@@ -91,19 +240,14 @@ impl Default for NoSimd {
 impl NoSimd {
     /// `x[] ^= y[] * log_m`
     fn mul_add(&self, x: &mut [u8], y: &[u8], log_m: GfElement) {
-        let lut = &self.mul16[log_m as usize];
+        let lut = self.mul_lut(log_m);
 
         for (x_chunk, y_chunk) in zip(x.chunks_exact_mut(64), y.chunks_exact(64)) {
             let (x_lo, x_hi) = x_chunk.split_at_mut(32);
             let (y_lo, y_hi) = y_chunk.split_at(32);
 
             for i in 0..32 {
-                let lo = y_lo[i];
-                let hi = y_hi[i];
-                let prod = lut[0][usize::from(lo & 15)]
-                    ^ lut[1][usize::from(lo >> 4)]
-                    ^ lut[2][usize::from(hi & 15)]
-                    ^ lut[3][usize::from(hi >> 4)];
+                let prod = Self::mul_one(lut, y_lo[i], y_hi[i]);
                 x_lo[i] ^= prod as u8;
                 x_hi[i] ^= (prod >> 8) as u8;
             }
@@ -111,6 +255,40 @@ impl NoSimd {
     }
 }
 
+// ======================================================================
+// NoSimd - PRIVATE - multiplication
+
+#[cfg(not(feature = "constant-time"))]
+impl NoSimd {
+    #[inline(always)]
+    fn mul_lut(&self, log_m: GfElement) -> &'static [[GfElement; 16]; 4] {
+        &self.mul16[log_m as usize]
+    }
+
+    /// `(lo | hi << 8) * log_m` given `lut` for `log_m`
+    #[inline(always)]
+    fn mul_one(lut: &[[GfElement; 16]; 4], lo: u8, hi: u8) -> GfElement {
+        lut[0][usize::from(lo & 15)]
+            ^ lut[1][usize::from(lo >> 4)]
+            ^ lut[2][usize::from(hi & 15)]
+            ^ lut[3][usize::from(hi >> 4)]
+    }
+}
+
+#[cfg(feature = "constant-time")]
+impl NoSimd {
+    #[inline(always)]
+    fn mul_lut(&self, log_m: GfElement) -> [GfElement; GF_BITS] {
+        tables::mul_bits(log_m, self.exp, self.log)
+    }
+
+    /// `(lo | hi << 8) * log_m` given `lut` for `log_m`
+    #[inline(always)]
+    fn mul_one(lut: [GfElement; GF_BITS], lo: u8, hi: u8) -> GfElement {
+        tables::mul_ct(GfElement::from(lo) | GfElement::from(hi) << 8, &lut)
+    }
+}
+
 // ======================================================================
 // NoSimd - PRIVATE - FFT (fast Fourier transform)
 
@@ -122,6 +300,25 @@ impl NoSimd {
         Self::xor(y, x);
     }
 
+    // Partial butterflies of shards `x .. x + count` with `y .. y + count`
+    // including `GF_MODULUS` check, see `Engine::fft_butterflies`.
+    #[inline(always)]
+    fn fft_butterflies_private(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        let (xs, ys) = data.flat2_mut(x, y, count);
+        if log_m == GF_MODULUS {
+            Self::xor(ys, xs);
+        } else {
+            self.fft_butterfly_partial(xs, ys, log_m);
+        }
+    }
+
     #[inline(always)]
     fn fft_butterfly_two_layers(
         &self,
@@ -168,45 +365,73 @@ impl NoSimd {
         truncated_size: usize,
         skew_delta: usize,
     ) {
-        // TWO LAYERS AT TIME
-
-        let mut dist4 = size;
-        let mut dist = size >> 2;
-        while dist != 0 {
-            let mut r = 0;
-            while r < truncated_size {
-                let base = r + dist + skew_delta - 1;
-
-                let log_m01 = self.skew[base];
-                let log_m02 = self.skew[base + dist];
-                let log_m23 = self.skew[base + dist * 2];
-
-                for i in r..r + dist {
-                    self.fft_butterfly_two_layers(data, pos + i, dist, log_m01, log_m23, log_m02)
+        engine::check_fft_params(size, truncated_size, skew_delta);
+
+        for (dist, layers) in engine::fft_steps(size) {
+            if layers == 1 {
+                // ONE LAYER
+
+                let mut r = 0;
+                while r < truncated_size {
+                    // SAFETY: Index is checked by `check_fft_params` above.
+                    let log_m = unsafe { engine::skew_at(self.skew, r + dist + skew_delta - 1) };
+                    self.fft_butterflies_private(data, pos + r, pos + r + dist, dist, log_m);
+                    r += dist * 2;
                 }
-
-                r += dist4;
-            }
-            dist4 = dist;
-            dist >>= 2;
-        }
-
-        // FINAL ODD LAYER
-
-        if dist4 == 2 {
-            let mut r = 0;
-            while r < truncated_size {
-                let log_m = self.skew[r + skew_delta];
-
-                let (x, y) = data.dist2_mut(pos + r, 1);
-
-                if log_m == GF_MODULUS {
-                    Self::xor(y, x);
-                } else {
-                    self.fft_butterfly_partial(x, y, log_m)
+            } else {
+                // TWO LAYERS
+
+                let dist4 = dist * 4;
+                let batch = engine::butterfly_batch(data.shard_bytes(), dist);
+
+                let mut r = 0;
+                while r < truncated_size {
+                    let base = r + dist + skew_delta - 1;
+
+                    // SAFETY: Indexes are checked by `check_fft_params` above.
+                    let (log_m01, log_m02, log_m23) = unsafe {
+                        (
+                            engine::skew_at(self.skew, base),
+                            engine::skew_at(self.skew, base + dist),
+                            engine::skew_at(self.skew, base + dist * 2),
+                        )
+                    };
+
+                    if dist >= 4 {
+                        // Same as below, with `batch` consecutive `i` at a time.
+                        for i in (pos + r..pos + r + dist).step_by(batch) {
+                            self.fft_butterflies_private(data, i, i + dist * 2, batch, log_m02);
+                            self.fft_butterflies_private(
+                                data,
+                                i + dist,
+                                i + dist * 3,
+                                batch,
+                                log_m02,
+                            );
+                            self.fft_butterflies_private(data, i, i + dist, batch, log_m01);
+                            self.fft_butterflies_private(
+                                data,
+                                i + dist * 2,
+                                i + dist * 3,
+                                batch,
+                                log_m23,
+                            );
+                        }
+                    } else {
+                        for i in r..r + dist {
+                            self.fft_butterfly_two_layers(
+                                data,
+                                pos + i,
+                                dist,
+                                log_m01,
+                                log_m23,
+                                log_m02,
+                            )
+                        }
+                    }
+
+                    r += dist4;
                 }
-
-                r += 2;
             }
         }
     }
@@ -223,6 +448,25 @@ impl NoSimd {
         self.mul_add(x, y, log_m);
     }
 
+    // Partial butterflies of shards `x .. x + count` with `y .. y + count`
+    // including `GF_MODULUS` check, see `Engine::ifft_butterflies`.
+    #[inline(always)]
+    fn ifft_butterflies_private(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        let (xs, ys) = data.flat2_mut(x, y, count);
+        if log_m == GF_MODULUS {
+            Self::xor(ys, xs);
+        } else {
+            self.ifft_butterfly_partial(xs, ys, log_m);
+        }
+    }
+
     #[inline(always)]
     fn ifft_butterfly_two_layers(
         &self,
@@ -269,43 +513,72 @@ impl NoSimd {
         truncated_size: usize,
         skew_delta: usize,
     ) {
-        // TWO LAYERS AT TIME
-
-        let mut dist = 1;
-        let mut dist4 = 4;
-        while dist4 <= size {
-            let mut r = 0;
-            while r < truncated_size {
-                let base = r + dist + skew_delta - 1;
-
-                let log_m01 = self.skew[base];
-                let log_m02 = self.skew[base + dist];
-                let log_m23 = self.skew[base + dist * 2];
-
-                for i in r..r + dist {
-                    self.ifft_butterfly_two_layers(data, pos + i, dist, log_m01, log_m23, log_m02)
+        engine::check_fft_params(size, truncated_size, skew_delta);
+
+        for (dist, layers) in engine::ifft_steps(size) {
+            if layers == 1 {
+                // ONE LAYER
+
+                let mut r = 0;
+                while r < truncated_size {
+                    // SAFETY: Index is checked by `check_fft_params` above.
+                    let log_m = unsafe { engine::skew_at(self.skew, r + dist + skew_delta - 1) };
+                    self.ifft_butterflies_private(data, pos + r, pos + r + dist, dist, log_m);
+                    r += dist * 2;
                 }
-
-                r += dist4;
-            }
-            dist = dist4;
-            dist4 <<= 2;
-        }
-
-        // FINAL ODD LAYER
-
-        if dist < size {
-            let log_m = self.skew[dist + skew_delta - 1];
-            if log_m == GF_MODULUS {
-                Self::xor_within(data, pos + dist, pos, dist);
             } else {
-                let (mut a, mut b) = data.split_at_mut(pos + dist);
-                for i in 0..dist {
-                    self.ifft_butterfly_partial(
-                        &mut a[pos + i], // data[pos + i]
-                        &mut b[i],       // data[pos + i + dist]
-                        log_m,
-                    );
+                // TWO LAYERS
+
+                let dist4 = dist * 4;
+                let batch = engine::butterfly_batch(data.shard_bytes(), dist);
+
+                let mut r = 0;
+                while r < truncated_size {
+                    let base = r + dist + skew_delta - 1;
+
+                    // SAFETY: Indexes are checked by `check_fft_params` above.
+                    let (log_m01, log_m02, log_m23) = unsafe {
+                        (
+                            engine::skew_at(self.skew, base),
+                            engine::skew_at(self.skew, base + dist),
+                            engine::skew_at(self.skew, base + dist * 2),
+                        )
+                    };
+
+                    if dist >= 4 {
+                        // Same as below, with `batch` consecutive `i` at a time.
+                        for i in (pos + r..pos + r + dist).step_by(batch) {
+                            self.ifft_butterflies_private(data, i, i + dist, batch, log_m01);
+                            self.ifft_butterflies_private(
+                                data,
+                                i + dist * 2,
+                                i + dist * 3,
+                                batch,
+                                log_m23,
+                            );
+                            self.ifft_butterflies_private(data, i, i + dist * 2, batch, log_m02);
+                            self.ifft_butterflies_private(
+                                data,
+                                i + dist,
+                                i + dist * 3,
+                                batch,
+                                log_m02,
+                            );
+                        }
+                    } else {
+                        for i in r..r + dist {
+                            self.ifft_butterfly_two_layers(
+                                data,
+                                pos + i,
+                                dist,
+                                log_m01,
+                                log_m23,
+                                log_m02,
+                            )
+                        }
+                    }
+
+                    r += dist4;
                 }
             }
         }
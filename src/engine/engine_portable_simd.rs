@@ -0,0 +1,498 @@
+use std::{
+    iter::zip,
+    simd::{num::SimdUint, u16x32, u8x32},
+};
+
+use crate::engine::{
+    self,
+    tables::{self, Mul16, Skew},
+    Engine, GfElement, ShardsRefMut, GF_MODULUS,
+};
+
+// ======================================================================
+// PortableSimd - PUBLIC
+
+/// [`Engine`] using portable SIMD with gathers from [`Mul16`].
+///
+/// [`Avx2`], [`Ssse3`] and [`Neon`] multiply with byte shuffles
+/// which look up 16 entries of a table at once. [`PortableSimd`] is
+/// meant for targets which have gather instructions but no byte shuffle:
+/// it multiplies 32 [`GfElement`]:s at a time by gathering their
+/// nibble products from the same [`Mul16`] table that [`NoSimd`] uses
+/// one element at a time.
+///
+/// Whether gathers beat scalar lookups depends on the target.
+/// On x86-64, even with AVX2 gathers enabled at compile time,
+/// [`PortableSimd`] is about half as fast as [`NoSimd`],
+/// see `engine-*` benchmarks.
+///
+/// Requires `portable-simd` feature and nightly Rust.
+/// This engine is never chosen by [`DefaultEngine`]
+/// and isn't included in [`available_engines`].
+///
+/// [`Avx2`]: crate::engine::Avx2
+/// [`DefaultEngine`]: crate::engine::DefaultEngine
+/// [`Mul16`]: crate::engine::tables::Mul16
+/// [`Neon`]: crate::engine::Neon
+/// [`NoSimd`]: crate::engine::NoSimd
+/// [`Ssse3`]: crate::engine::Ssse3
+/// [`available_engines`]: crate::engine::available_engines
+#[derive(Clone)]
+pub struct PortableSimd {
+    mul16: &'static Mul16,
+    skew: &'static Skew,
+}
+
+impl PortableSimd {
+    /// Creates new [`PortableSimd`], initializing all [tables]
+    /// needed for encoding or decoding.
+    ///
+    /// Currently only difference between encoding/decoding is
+    /// [`LogWalsh`] (128 kiB) which is only needed for decoding.
+    ///
+    /// [`LogWalsh`]: crate::engine::tables::LogWalsh
+    pub fn new() -> Self {
+        let mul16 = tables::initialize_mul16();
+        let skew = tables::initialize_skew();
+
+        Self { mul16, skew }
+    }
+}
+
+impl Engine for PortableSimd {
+    fn fft(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        size: usize,
+        truncated_size: usize,
+        skew_delta: usize,
+    ) {
+        self.fft_private(data, pos, size, truncated_size, skew_delta);
+    }
+
+    fn ifft(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        size: usize,
+        truncated_size: usize,
+        skew_delta: usize,
+    ) {
+        self.ifft_private(data, pos, size, truncated_size, skew_delta);
+    }
+
+    fn mul(&self, x: &mut [u8], log_m: GfElement) {
+        let lut = &self.mul16[log_m as usize];
+
+        for x_chunk in x.chunks_exact_mut(64) {
+            let (x_lo, x_hi) = x_chunk.split_at_mut(32);
+
+            let prod = Self::mul_32(lut, u8x32::from_slice(x_lo), u8x32::from_slice(x_hi));
+            Self::store_32(x_lo, x_hi, prod);
+        }
+    }
+
+    fn fft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        self.fft_butterflies_private(data, x, y, count, log_m);
+    }
+
+    fn ifft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        self.ifft_butterflies_private(data, x, y, count, log_m);
+    }
+
+    fn linear_combination(&self, out: &mut [u8], inputs: &[&[u8]], coeffs: &[GfElement]) {
+        let terms: Vec<_> = engine::linear_combination_terms(out, inputs, coeffs)
+            .into_iter()
+            .map(|(input, log_m)| (input, &self.mul16[log_m as usize]))
+            .collect();
+
+        for (block, out_chunk) in out.chunks_exact_mut(64).enumerate() {
+            let (out_lo, out_hi) = out_chunk.split_at_mut(32);
+
+            let mut sum = u16x32::splat(0);
+            for &(input, lut) in &terms {
+                let (y_lo, y_hi) = input[block * 64..][..64].split_at(32);
+                sum ^= Self::mul_32(lut, u8x32::from_slice(y_lo), u8x32::from_slice(y_hi));
+            }
+            Self::store_32(out_lo, out_hi, sum);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "PortableSimd"
+    }
+}
+
+// ======================================================================
+// PortableSimd - IMPL Default
+
+impl Default for PortableSimd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ======================================================================
+// PortableSimd - PRIVATE
+
+impl PortableSimd {
+    /// `x[] ^= y[] * log_m`
+    fn mul_add(&self, x: &mut [u8], y: &[u8], log_m: GfElement) {
+        let lut = &self.mul16[log_m as usize];
+
+        for (x_chunk, y_chunk) in zip(x.chunks_exact_mut(64), y.chunks_exact(64)) {
+            let (x_lo, x_hi) = x_chunk.split_at_mut(32);
+            let (y_lo, y_hi) = y_chunk.split_at(32);
+
+            let prod = Self::mul_32(lut, u8x32::from_slice(y_lo), u8x32::from_slice(y_hi));
+            let x =
+                u8x32::from_slice(x_lo).cast::<u16>() | u8x32::from_slice(x_hi).cast::<u16>() << 8;
+            Self::store_32(x_lo, x_hi, x ^ prod);
+        }
+    }
+}
+
+// ======================================================================
+// PortableSimd - PRIVATE - multiplication
+
+impl PortableSimd {
+    /// `(lo | hi << 8) * log_m` given `lut` for `log_m`
+    #[inline(always)]
+    fn mul_32(lut: &[[GfElement; 16]; 4], lo: u8x32, hi: u8x32) -> u16x32 {
+        let mask = u8x32::splat(15);
+
+        Self::gather(&lut[0], lo & mask)
+            ^ Self::gather(&lut[1], lo >> 4)
+            ^ Self::gather(&lut[2], hi & mask)
+            ^ Self::gather(&lut[3], hi >> 4)
+    }
+
+    // Indexes are nibbles so they are always within `table`.
+    #[inline(always)]
+    fn gather(table: &[GfElement; 16], index: u8x32) -> u16x32 {
+        u16x32::gather_or_default(table, index.cast::<usize>())
+    }
+
+    // Stores low and high bytes of `value` to `lo` and `hi`.
+    #[inline(always)]
+    fn store_32(lo: &mut [u8], hi: &mut [u8], value: u16x32) {
+        value.cast::<u8>().copy_to_slice(lo);
+        (value >> 8).cast::<u8>().copy_to_slice(hi);
+    }
+}
+
+// ======================================================================
+// PortableSimd - PRIVATE - FFT (fast Fourier transform)
+
+impl PortableSimd {
+    // Partial butterfly, caller must do `GF_MODULUS` check with `xor`.
+    #[inline(always)]
+    fn fft_butterfly_partial(&self, x: &mut [u8], y: &mut [u8], log_m: GfElement) {
+        self.mul_add(x, y, log_m);
+        Self::xor(y, x);
+    }
+
+    // Partial butterflies of shards `x .. x + count` with `y .. y + count`
+    // including `GF_MODULUS` check, see `Engine::fft_butterflies`.
+    #[inline(always)]
+    fn fft_butterflies_private(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        let (xs, ys) = data.flat2_mut(x, y, count);
+        if log_m == GF_MODULUS {
+            Self::xor(ys, xs);
+        } else {
+            self.fft_butterfly_partial(xs, ys, log_m);
+        }
+    }
+
+    #[inline(always)]
+    fn fft_butterfly_two_layers(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        dist: usize,
+        log_m01: GfElement,
+        log_m23: GfElement,
+        log_m02: GfElement,
+    ) {
+        let (s0, s1, s2, s3) = data.dist4_mut(pos, dist);
+
+        // FIRST LAYER
+
+        if log_m02 == GF_MODULUS {
+            Self::xor(s2, s0);
+            Self::xor(s3, s1);
+        } else {
+            self.fft_butterfly_partial(s0, s2, log_m02);
+            self.fft_butterfly_partial(s1, s3, log_m02);
+        }
+
+        // SECOND LAYER
+
+        if log_m01 == GF_MODULUS {
+            Self::xor(s1, s0);
+        } else {
+            self.fft_butterfly_partial(s0, s1, log_m01);
+        }
+
+        if log_m23 == GF_MODULUS {
+            Self::xor(s3, s2);
+        } else {
+            self.fft_butterfly_partial(s2, s3, log_m23);
+        }
+    }
+
+    #[inline(always)]
+    fn fft_private(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        size: usize,
+        truncated_size: usize,
+        skew_delta: usize,
+    ) {
+        engine::check_fft_params(size, truncated_size, skew_delta);
+
+        for (dist, layers) in engine::fft_steps(size) {
+            if layers == 1 {
+                // ONE LAYER
+
+                let mut r = 0;
+                while r < truncated_size {
+                    // SAFETY: Index is checked by `check_fft_params` above.
+                    let log_m = unsafe { engine::skew_at(self.skew, r + dist + skew_delta - 1) };
+                    self.fft_butterflies_private(data, pos + r, pos + r + dist, dist, log_m);
+                    r += dist * 2;
+                }
+            } else {
+                // TWO LAYERS
+
+                let dist4 = dist * 4;
+                let batch = engine::butterfly_batch(data.shard_bytes(), dist);
+
+                let mut r = 0;
+                while r < truncated_size {
+                    let base = r + dist + skew_delta - 1;
+
+                    // SAFETY: Indexes are checked by `check_fft_params` above.
+                    let (log_m01, log_m02, log_m23) = unsafe {
+                        (
+                            engine::skew_at(self.skew, base),
+                            engine::skew_at(self.skew, base + dist),
+                            engine::skew_at(self.skew, base + dist * 2),
+                        )
+                    };
+
+                    if dist >= 4 {
+                        // Same as below, with `batch` consecutive `i` at a time.
+                        for i in (pos + r..pos + r + dist).step_by(batch) {
+                            self.fft_butterflies_private(data, i, i + dist * 2, batch, log_m02);
+                            self.fft_butterflies_private(
+                                data,
+                                i + dist,
+                                i + dist * 3,
+                                batch,
+                                log_m02,
+                            );
+                            self.fft_butterflies_private(data, i, i + dist, batch, log_m01);
+                            self.fft_butterflies_private(
+                                data,
+                                i + dist * 2,
+                                i + dist * 3,
+                                batch,
+                                log_m23,
+                            );
+                        }
+                    } else {
+                        for i in r..r + dist {
+                            self.fft_butterfly_two_layers(
+                                data,
+                                pos + i,
+                                dist,
+                                log_m01,
+                                log_m23,
+                                log_m02,
+                            )
+                        }
+                    }
+
+                    r += dist4;
+                }
+            }
+        }
+    }
+}
+
+// ======================================================================
+// PortableSimd - PRIVATE - IFFT (inverse fast Fourier transform)
+
+impl PortableSimd {
+    // Partial butterfly, caller must do `GF_MODULUS` check with `xor`.
+    #[inline(always)]
+    fn ifft_butterfly_partial(&self, x: &mut [u8], y: &mut [u8], log_m: GfElement) {
+        Self::xor(y, x);
+        self.mul_add(x, y, log_m);
+    }
+
+    // Partial butterflies of shards `x .. x + count` with `y .. y + count`
+    // including `GF_MODULUS` check, see `Engine::ifft_butterflies`.
+    #[inline(always)]
+    fn ifft_butterflies_private(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        let (xs, ys) = data.flat2_mut(x, y, count);
+        if log_m == GF_MODULUS {
+            Self::xor(ys, xs);
+        } else {
+            self.ifft_butterfly_partial(xs, ys, log_m);
+        }
+    }
+
+    #[inline(always)]
+    fn ifft_butterfly_two_layers(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        dist: usize,
+        log_m01: GfElement,
+        log_m23: GfElement,
+        log_m02: GfElement,
+    ) {
+        let (s0, s1, s2, s3) = data.dist4_mut(pos, dist);
+
+        // FIRST LAYER
+
+        if log_m01 == GF_MODULUS {
+            Self::xor(s1, s0);
+        } else {
+            self.ifft_butterfly_partial(s0, s1, log_m01);
+        }
+
+        if log_m23 == GF_MODULUS {
+            Self::xor(s3, s2);
+        } else {
+            self.ifft_butterfly_partial(s2, s3, log_m23);
+        }
+
+        // SECOND LAYER
+
+        if log_m02 == GF_MODULUS {
+            Self::xor(s2, s0);
+            Self::xor(s3, s1);
+        } else {
+            self.ifft_butterfly_partial(s0, s2, log_m02);
+            self.ifft_butterfly_partial(s1, s3, log_m02);
+        }
+    }
+
+    #[inline(always)]
+    fn ifft_private(
+        &self,
+        data: &mut ShardsRefMut,
+        pos: usize,
+        size: usize,
+        truncated_size: usize,
+        skew_delta: usize,
+    ) {
+        engine::check_fft_params(size, truncated_size, skew_delta);
+
+        for (dist, layers) in engine::ifft_steps(size) {
+            if layers == 1 {
+                // ONE LAYER
+
+                let mut r = 0;
+                while r < truncated_size {
+                    // SAFETY: Index is checked by `check_fft_params` above.
+                    let log_m = unsafe { engine::skew_at(self.skew, r + dist + skew_delta - 1) };
+                    self.ifft_butterflies_private(data, pos + r, pos + r + dist, dist, log_m);
+                    r += dist * 2;
+                }
+            } else {
+                // TWO LAYERS
+
+                let dist4 = dist * 4;
+                let batch = engine::butterfly_batch(data.shard_bytes(), dist);
+
+                let mut r = 0;
+                while r < truncated_size {
+                    let base = r + dist + skew_delta - 1;
+
+                    // SAFETY: Indexes are checked by `check_fft_params` above.
+                    let (log_m01, log_m02, log_m23) = unsafe {
+                        (
+                            engine::skew_at(self.skew, base),
+                            engine::skew_at(self.skew, base + dist),
+                            engine::skew_at(self.skew, base + dist * 2),
+                        )
+                    };
+
+                    if dist >= 4 {
+                        // Same as below, with `batch` consecutive `i` at a time.
+                        for i in (pos + r..pos + r + dist).step_by(batch) {
+                            self.ifft_butterflies_private(data, i, i + dist, batch, log_m01);
+                            self.ifft_butterflies_private(
+                                data,
+                                i + dist * 2,
+                                i + dist * 3,
+                                batch,
+                                log_m23,
+                            );
+                            self.ifft_butterflies_private(data, i, i + dist * 2, batch, log_m02);
+                            self.ifft_butterflies_private(
+                                data,
+                                i + dist,
+                                i + dist * 3,
+                                batch,
+                                log_m02,
+                            );
+                        }
+                    } else {
+                        for i in r..r + dist {
+                            self.ifft_butterfly_two_layers(
+                                data,
+                                pos + i,
+                                dist,
+                                log_m01,
+                                log_m23,
+                                log_m02,
+                            )
+                        }
+                    }
+
+                    r += dist4;
+                }
+            }
+        }
+    }
+}
+
+// ======================================================================
+// TESTS
+
+// Engines are tested indirectly via roundtrip tests of HighRate and LowRate.
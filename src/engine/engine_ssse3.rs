@@ -5,10 +5,12 @@ use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
+#[cfg(feature = "decoder")]
+use crate::engine::GF_ORDER;
 use crate::engine::{
     self,
-    tables::{self, Mul128, Multiply128lutT, Skew},
-    Engine, GfElement, ShardsRefMut, GF_MODULUS, GF_ORDER,
+    tables::{self, Mul128Luts, Multiply128lutT, Skew},
+    Engine, GfElement, NoSimd, ShardsRefMut, GF_MODULUS,
 };
 
 // ======================================================================
@@ -22,7 +24,7 @@ use crate::engine::{
 /// [`NoSimd`]: crate::engine::NoSimd
 #[derive(Clone)]
 pub struct Ssse3 {
-    mul128: &'static Mul128,
+    mul128: Mul128Luts,
     skew: &'static Skew,
 }
 
@@ -35,7 +37,7 @@ impl Ssse3 {
     ///
     /// [`LogWalsh`]: crate::engine::tables::LogWalsh
     pub fn new() -> Self {
-        let mul128 = tables::initialize_mul128();
+        let mul128 = Mul128Luts::new();
         let skew = tables::initialize_skew();
 
         Self { mul128, skew }
@@ -75,6 +77,60 @@ impl Engine for Ssse3 {
         }
     }
 
+    fn fft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        unsafe {
+            self.fft_butterflies_ssse3(data, x, y, count, log_m);
+        }
+    }
+
+    fn ifft_butterflies(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        unsafe {
+            self.ifft_butterflies_ssse3(data, x, y, count, log_m);
+        }
+    }
+
+    fn linear_combination(&self, out: &mut [u8], inputs: &[&[u8]], coeffs: &[GfElement]) {
+        unsafe {
+            self.linear_combination_ssse3(out, inputs, coeffs);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Ssse3"
+    }
+
+    fn supported() -> bool {
+        // Miri doesn't support SIMD intrinsics.
+        !cfg!(miri) && is_x86_feature_detected!("ssse3")
+    }
+
+    fn copy_nt(dst: &mut [u8], src: &[u8]) {
+        unsafe { Self::copy_nt_ssse3(dst, src) }
+    }
+
+    fn fill(x: &mut [u8], byte: u8) {
+        unsafe { Self::fill_ssse3(x, byte) }
+    }
+
+    fn xor_into(out: &mut [u8], a: &[u8], b: &[u8]) {
+        unsafe { Self::xor_into_ssse3(out, a, b) }
+    }
+
+    #[cfg(feature = "decoder")]
     fn eval_poly(erasures: &mut [GfElement; GF_ORDER], truncated_size: usize) {
         unsafe { Self::eval_poly_ssse3(erasures, truncated_size) }
     }
@@ -95,9 +151,45 @@ impl Default for Ssse3 {
 //
 
 impl Ssse3 {
+    #[target_feature(enable = "ssse3")]
+    unsafe fn linear_combination_ssse3(
+        &self,
+        out: &mut [u8],
+        inputs: &[&[u8]],
+        coeffs: &[GfElement],
+    ) {
+        let terms: Vec<_> = engine::linear_combination_terms(out, inputs, coeffs)
+            .into_iter()
+            .map(|(input, log_m)| (input, self.mul128.get(log_m)))
+            .collect();
+
+        for (block, chunk) in out.chunks_exact_mut(64).enumerate() {
+            let out_ptr = chunk.as_mut_ptr() as *mut __m128i;
+            unsafe {
+                let mut acc0_lo = _mm_setzero_si128();
+                let mut acc1_lo = _mm_setzero_si128();
+                let mut acc0_hi = _mm_setzero_si128();
+                let mut acc1_hi = _mm_setzero_si128();
+                for (input, lut) in &terms {
+                    let y_ptr = input[block * 64..][..64].as_ptr() as *const __m128i;
+                    let y0_lo = _mm_loadu_si128(y_ptr);
+                    let y1_lo = _mm_loadu_si128(y_ptr.add(1));
+                    let y0_hi = _mm_loadu_si128(y_ptr.add(2));
+                    let y1_hi = _mm_loadu_si128(y_ptr.add(3));
+                    (acc0_lo, acc0_hi) = Self::muladd_128(acc0_lo, acc0_hi, y0_lo, y0_hi, lut);
+                    (acc1_lo, acc1_hi) = Self::muladd_128(acc1_lo, acc1_hi, y1_lo, y1_hi, lut);
+                }
+                _mm_storeu_si128(out_ptr, acc0_lo);
+                _mm_storeu_si128(out_ptr.add(1), acc1_lo);
+                _mm_storeu_si128(out_ptr.add(2), acc0_hi);
+                _mm_storeu_si128(out_ptr.add(3), acc1_hi);
+            }
+        }
+    }
+
     #[target_feature(enable = "ssse3")]
     unsafe fn mul_ssse3(&self, x: &mut [u8], log_m: GfElement) {
-        let lut = &self.mul128[log_m as usize];
+        let lut: &Multiply128lutT = &self.mul128.get(log_m);
 
         for chunk in x.chunks_exact_mut(64) {
             let x_ptr = chunk.as_mut_ptr() as *mut __m128i;
@@ -180,8 +272,7 @@ impl Ssse3 {
 impl Ssse3 {
     // Implementation of LEO_FFTB_128
     #[inline(always)]
-    fn fftb_128(&self, x: &mut [u8; 64], y: &mut [u8; 64], log_m: GfElement) {
-        let lut = &self.mul128[log_m as usize];
+    fn fftb_128(&self, x: &mut [u8; 64], y: &mut [u8; 64], lut: &Multiply128lutT) {
         let x_ptr = x.as_mut_ptr() as *mut __m128i;
         let y_ptr = y.as_mut_ptr() as *mut __m128i;
         unsafe {
@@ -218,17 +309,38 @@ impl Ssse3 {
     // Partial butterfly, caller must do `GF_MODULUS` check with `xor`.
     #[inline(always)]
     fn fft_butterfly_partial(&self, x: &mut [u8], y: &mut [u8], log_m: GfElement) {
+        let lut: &Multiply128lutT = &self.mul128.get(log_m);
+
         // While we wait for array_chunks/slice_as_chunks (#74985) to become stable,
         // we have to try_into().unwrap() (which cannot fail in this case)
         for (x_chunk, y_chunk) in zip(x.chunks_exact_mut(64), y.chunks_exact_mut(64)) {
             self.fftb_128(
                 x_chunk.try_into().unwrap(),
                 y_chunk.try_into().unwrap(),
-                log_m,
+                lut,
             );
         }
     }
 
+    // Partial butterflies of shards `x .. x + count` with `y .. y + count`
+    // including `GF_MODULUS` check, see `Engine::fft_butterflies`.
+    #[inline(always)]
+    fn fft_butterflies_private(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        let (xs, ys) = data.flat2_mut(x, y, count);
+        if log_m == GF_MODULUS {
+            Self::xor(ys, xs);
+        } else {
+            self.fft_butterfly_partial(xs, ys, log_m);
+        }
+    }
+
     #[inline(always)]
     fn fft_butterfly_two_layers(
         &self,
@@ -266,6 +378,19 @@ impl Ssse3 {
         }
     }
 
+    #[target_feature(enable = "ssse3")]
+    unsafe fn fft_butterflies_ssse3(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        // Drop unsafe privileges
+        self.fft_butterflies_private(data, x, y, count, log_m);
+    }
+
     #[target_feature(enable = "ssse3")]
     unsafe fn fft_private_ssse3(
         &self,
@@ -288,45 +413,73 @@ impl Ssse3 {
         truncated_size: usize,
         skew_delta: usize,
     ) {
-        // TWO LAYERS AT TIME
-
-        let mut dist4 = size;
-        let mut dist = size >> 2;
-        while dist != 0 {
-            let mut r = 0;
-            while r < truncated_size {
-                let base = r + dist + skew_delta - 1;
-
-                let log_m01 = self.skew[base];
-                let log_m02 = self.skew[base + dist];
-                let log_m23 = self.skew[base + dist * 2];
-
-                for i in r..r + dist {
-                    self.fft_butterfly_two_layers(data, pos + i, dist, log_m01, log_m23, log_m02)
+        engine::check_fft_params(size, truncated_size, skew_delta);
+
+        for (dist, layers) in engine::fft_steps(size) {
+            if layers == 1 {
+                // ONE LAYER
+
+                let mut r = 0;
+                while r < truncated_size {
+                    // SAFETY: Index is checked by `check_fft_params` above.
+                    let log_m = unsafe { engine::skew_at(self.skew, r + dist + skew_delta - 1) };
+                    self.fft_butterflies_private(data, pos + r, pos + r + dist, dist, log_m);
+                    r += dist * 2;
                 }
-
-                r += dist4;
-            }
-            dist4 = dist;
-            dist >>= 2;
-        }
-
-        // FINAL ODD LAYER
-
-        if dist4 == 2 {
-            let mut r = 0;
-            while r < truncated_size {
-                let log_m = self.skew[r + skew_delta];
-
-                let (x, y) = data.dist2_mut(pos + r, 1);
-
-                if log_m == GF_MODULUS {
-                    Self::xor(y, x);
-                } else {
-                    self.fft_butterfly_partial(x, y, log_m)
+            } else {
+                // TWO LAYERS
+
+                let dist4 = dist * 4;
+                let batch = engine::butterfly_batch(data.shard_bytes(), dist);
+
+                let mut r = 0;
+                while r < truncated_size {
+                    let base = r + dist + skew_delta - 1;
+
+                    // SAFETY: Indexes are checked by `check_fft_params` above.
+                    let (log_m01, log_m02, log_m23) = unsafe {
+                        (
+                            engine::skew_at(self.skew, base),
+                            engine::skew_at(self.skew, base + dist),
+                            engine::skew_at(self.skew, base + dist * 2),
+                        )
+                    };
+
+                    if dist >= 4 {
+                        // Same as below, with `batch` consecutive `i` at a time.
+                        for i in (pos + r..pos + r + dist).step_by(batch) {
+                            self.fft_butterflies_private(data, i, i + dist * 2, batch, log_m02);
+                            self.fft_butterflies_private(
+                                data,
+                                i + dist,
+                                i + dist * 3,
+                                batch,
+                                log_m02,
+                            );
+                            self.fft_butterflies_private(data, i, i + dist, batch, log_m01);
+                            self.fft_butterflies_private(
+                                data,
+                                i + dist * 2,
+                                i + dist * 3,
+                                batch,
+                                log_m23,
+                            );
+                        }
+                    } else {
+                        for i in r..r + dist {
+                            self.fft_butterfly_two_layers(
+                                data,
+                                pos + i,
+                                dist,
+                                log_m01,
+                                log_m23,
+                                log_m02,
+                            )
+                        }
+                    }
+
+                    r += dist4;
                 }
-
-                r += 2;
             }
         }
     }
@@ -338,8 +491,7 @@ impl Ssse3 {
 impl Ssse3 {
     // Implementation of LEO_IFFTB_128
     #[inline(always)]
-    fn ifftb_128(&self, x: &mut [u8; 64], y: &mut [u8; 64], log_m: GfElement) {
-        let lut = &self.mul128[log_m as usize];
+    fn ifftb_128(&self, x: &mut [u8; 64], y: &mut [u8; 64], lut: &Multiply128lutT) {
         let x_ptr = x.as_mut_ptr() as *mut __m128i;
         let y_ptr = y.as_mut_ptr() as *mut __m128i;
 
@@ -376,17 +528,38 @@ impl Ssse3 {
 
     #[inline(always)]
     fn ifft_butterfly_partial(&self, x: &mut [u8], y: &mut [u8], log_m: GfElement) {
+        let lut: &Multiply128lutT = &self.mul128.get(log_m);
+
         // While we wait for array_chunks/slice_as_chunks (#74985) to become stable,
         // we'll have to try_into() to array
         for (x_chunk, y_chunk) in zip(x.chunks_exact_mut(64), y.chunks_exact_mut(64)) {
             self.ifftb_128(
                 x_chunk.try_into().unwrap(),
                 y_chunk.try_into().unwrap(),
-                log_m,
+                lut,
             );
         }
     }
 
+    // Partial butterflies of shards `x .. x + count` with `y .. y + count`
+    // including `GF_MODULUS` check, see `Engine::ifft_butterflies`.
+    #[inline(always)]
+    fn ifft_butterflies_private(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        let (xs, ys) = data.flat2_mut(x, y, count);
+        if log_m == GF_MODULUS {
+            Self::xor(ys, xs);
+        } else {
+            self.ifft_butterfly_partial(xs, ys, log_m);
+        }
+    }
+
     #[inline(always)]
     fn ifft_butterfly_two_layers(
         &self,
@@ -424,6 +597,19 @@ impl Ssse3 {
         }
     }
 
+    #[target_feature(enable = "ssse3")]
+    unsafe fn ifft_butterflies_ssse3(
+        &self,
+        data: &mut ShardsRefMut,
+        x: usize,
+        y: usize,
+        count: usize,
+        log_m: GfElement,
+    ) {
+        // Drop unsafe privileges
+        self.ifft_butterflies_private(data, x, y, count, log_m);
+    }
+
     #[target_feature(enable = "ssse3")]
     unsafe fn ifft_private_ssse3(
         &self,
@@ -446,52 +632,157 @@ impl Ssse3 {
         truncated_size: usize,
         skew_delta: usize,
     ) {
-        // TWO LAYERS AT TIME
+        engine::check_fft_params(size, truncated_size, skew_delta);
+
+        for (dist, layers) in engine::ifft_steps(size) {
+            if layers == 1 {
+                // ONE LAYER
+
+                let mut r = 0;
+                while r < truncated_size {
+                    // SAFETY: Index is checked by `check_fft_params` above.
+                    let log_m = unsafe { engine::skew_at(self.skew, r + dist + skew_delta - 1) };
+                    self.ifft_butterflies_private(data, pos + r, pos + r + dist, dist, log_m);
+                    r += dist * 2;
+                }
+            } else {
+                // TWO LAYERS
+
+                let dist4 = dist * 4;
+                let batch = engine::butterfly_batch(data.shard_bytes(), dist);
+
+                let mut r = 0;
+                while r < truncated_size {
+                    let base = r + dist + skew_delta - 1;
+
+                    // SAFETY: Indexes are checked by `check_fft_params` above.
+                    let (log_m01, log_m02, log_m23) = unsafe {
+                        (
+                            engine::skew_at(self.skew, base),
+                            engine::skew_at(self.skew, base + dist),
+                            engine::skew_at(self.skew, base + dist * 2),
+                        )
+                    };
+
+                    if dist >= 4 {
+                        // Same as below, with `batch` consecutive `i` at a time.
+                        for i in (pos + r..pos + r + dist).step_by(batch) {
+                            self.ifft_butterflies_private(data, i, i + dist, batch, log_m01);
+                            self.ifft_butterflies_private(
+                                data,
+                                i + dist * 2,
+                                i + dist * 3,
+                                batch,
+                                log_m23,
+                            );
+                            self.ifft_butterflies_private(data, i, i + dist * 2, batch, log_m02);
+                            self.ifft_butterflies_private(
+                                data,
+                                i + dist,
+                                i + dist * 3,
+                                batch,
+                                log_m02,
+                            );
+                        }
+                    } else {
+                        for i in r..r + dist {
+                            self.ifft_butterfly_two_layers(
+                                data,
+                                pos + i,
+                                dist,
+                                log_m01,
+                                log_m23,
+                                log_m02,
+                            )
+                        }
+                    }
+
+                    r += dist4;
+                }
+            }
+        }
+    }
+}
 
-        let mut dist = 1;
-        let mut dist4 = 4;
-        while dist4 <= size {
-            let mut r = 0;
-            while r < truncated_size {
-                let base = r + dist + skew_delta - 1;
+// ======================================================================
+// Ssse3 - PRIVATE - Non-temporal copy
+
+impl Ssse3 {
+    #[target_feature(enable = "ssse3")]
+    unsafe fn copy_nt_ssse3(dst: &mut [u8], src: &[u8]) {
+        assert_eq!(dst.len(), src.len(), "dst and src must have same length");
+
+        // Stores must be aligned so unaligned head and tail are copied normally.
+        let head = dst.as_ptr().align_offset(16).min(dst.len());
+        let (dst_head, dst) = dst.split_at_mut(head);
+        let (src_head, src) = src.split_at(head);
+        dst_head.copy_from_slice(src_head);
+
+        let mut dst_chunks = dst.chunks_exact_mut(16);
+        let mut src_chunks = src.chunks_exact(16);
+        for (dst, src) in zip(&mut dst_chunks, &mut src_chunks) {
+            unsafe {
+                let value = _mm_loadu_si128(src.as_ptr() as *const __m128i);
+                _mm_stream_si128(dst.as_mut_ptr() as *mut __m128i, value);
+            }
+        }
+        dst_chunks
+            .into_remainder()
+            .copy_from_slice(src_chunks.remainder());
 
-                let log_m01 = self.skew[base];
-                let log_m02 = self.skew[base + dist];
-                let log_m23 = self.skew[base + dist * 2];
+        // Non-temporal stores are weakly ordered.
+        _mm_sfence();
+    }
+}
 
-                for i in r..r + dist {
-                    self.ifft_butterfly_two_layers(data, pos + i, dist, log_m01, log_m23, log_m02)
-                }
+// ======================================================================
+// Ssse3 - PRIVATE - Fill
 
-                r += dist4;
+impl Ssse3 {
+    #[target_feature(enable = "ssse3")]
+    unsafe fn fill_ssse3(x: &mut [u8], byte: u8) {
+        let value = _mm_set1_epi8(byte as i8);
+        let mut chunks = x.chunks_exact_mut(16);
+        for x in &mut chunks {
+            unsafe {
+                _mm_storeu_si128(x.as_mut_ptr() as *mut __m128i, value);
             }
-            dist = dist4;
-            dist4 <<= 2;
         }
+        chunks.into_remainder().fill(byte);
+    }
+}
+
+// ======================================================================
+// Ssse3 - PRIVATE - XOR
 
-        // FINAL ODD LAYER
+impl Ssse3 {
+    #[target_feature(enable = "ssse3")]
+    unsafe fn xor_into_ssse3(out: &mut [u8], a: &[u8], b: &[u8]) {
+        engine::assert_xor_into_args(out, a, b);
 
-        if dist < size {
-            let log_m = self.skew[dist + skew_delta - 1];
-            if log_m == GF_MODULUS {
-                Self::xor_within(data, pos + dist, pos, dist);
-            } else {
-                let (mut a, mut b) = data.split_at_mut(pos + dist);
-                for i in 0..dist {
-                    self.ifft_butterfly_partial(
-                        &mut a[pos + i], // data[pos + i]
-                        &mut b[i],       // data[pos + i + dist]
-                        log_m,
-                    );
-                }
+        let mut out_chunks = out.chunks_exact_mut(16);
+        let mut a_chunks = a.chunks_exact(16);
+        let mut b_chunks = b.chunks_exact(16);
+        for (out, (a, b)) in zip(&mut out_chunks, zip(&mut a_chunks, &mut b_chunks)) {
+            unsafe {
+                let a = _mm_loadu_si128(a.as_ptr() as *const __m128i);
+                let b = _mm_loadu_si128(b.as_ptr() as *const __m128i);
+                _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, _mm_xor_si128(a, b));
             }
         }
+
+        NoSimd::xor_into(
+            out_chunks.into_remainder(),
+            a_chunks.remainder(),
+            b_chunks.remainder(),
+        );
     }
 }
 
 // ======================================================================
 // Ssse3 - PRIVATE - Evaluate polynomial
 
+#[cfg(feature = "decoder")]
 impl Ssse3 {
     #[target_feature(enable = "ssse3")]
     unsafe fn eval_poly_ssse3(erasures: &mut [GfElement; GF_ORDER], truncated_size: usize) {
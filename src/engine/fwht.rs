@@ -57,7 +57,7 @@ fn fwht_4(data: &mut [GfElement; GF_ORDER], offset: u16, dist: u16) {
 // ======================================================================
 // FWHT - TESTS
 
-#[cfg(test)]
+#[cfg(all(test, not(miri)))]
 mod tests {
     use super::*;
     use rand::{Rng, SeedableRng};
@@ -1,5 +1,22 @@
+#[cfg(feature = "encoder")]
+use std::ops::Range;
 use std::ops::{Bound, Index, IndexMut, RangeBounds};
 
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use crate::{
+    engine::{Engine, NoSimd},
+    Error,
+};
+
+#[cfg(feature = "allocator-api")]
+use std::{
+    alloc::{AllocError, Allocator, Global, Layout},
+    ptr::NonNull,
+    sync::Arc,
+};
+
 // ======================================================================
 // Shards - CRATE
 
@@ -8,14 +25,29 @@ pub(crate) struct Shards {
     shard_bytes: usize,
 
     // Flat array of `shard_count * shard_bytes` bytes.
+    #[cfg(not(feature = "allocator-api"))]
     data: Vec<u8>,
+    #[cfg(feature = "allocator-api")]
+    data: Vec<u8, WorkAllocator>,
 }
 
 impl Shards {
+    // Number of bytes allocated, which may be more than currently used.
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.data.capacity()
+    }
+
     pub(crate) fn as_ref_mut(&mut self) -> ShardsRefMut {
         ShardsRefMut::new(self.shard_count, self.shard_bytes, self.data.as_mut())
     }
 
+    // Shards `range` as one flat slice.
+    #[cfg(feature = "encoder")]
+    pub(crate) fn flat(&self, range: Range<usize>) -> &[u8] {
+        &self.data[range.start * self.shard_bytes..range.end * self.shard_bytes]
+    }
+
+    #[cfg(not(feature = "allocator-api"))]
     pub(crate) fn new() -> Self {
         Self {
             shard_count: 0,
@@ -24,13 +56,81 @@ impl Shards {
         }
     }
 
-    pub(crate) fn resize(&mut self, shard_count: usize, shard_bytes: usize) {
+    #[cfg(feature = "allocator-api")]
+    pub(crate) fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    #[cfg(feature = "allocator-api")]
+    pub(crate) fn new_in<A: Allocator + Send + Sync + 'static>(alloc: A) -> Self {
+        Self {
+            shard_count: 0,
+            shard_bytes: 0,
+            data: Vec::new_in(WorkAllocator(Arc::new(alloc))),
+        }
+    }
+
+    #[cfg(feature = "encoder")]
+    pub(crate) fn shard_count(&self) -> usize {
+        self.shard_count
+    }
+
+    // Returns `Error::InvalidShardSize` if total size would overflow,
+    // in which case nothing is changed.
+    //
+    // With `zeroize` feature all previous content is zeroed,
+    // otherwise only bytes beyond previous size are.
+    pub(crate) fn resize(&mut self, shard_count: usize, shard_bytes: usize) -> Result<(), Error> {
         assert!(shard_bytes > 0 && shard_bytes & 63 == 0);
 
+        let bytes = shard_count
+            .checked_mul(shard_bytes)
+            .filter(|&bytes| bytes <= isize::MAX as usize)
+            .ok_or(Error::InvalidShardSize { shard_bytes })?;
+
         self.shard_count = shard_count;
         self.shard_bytes = shard_bytes;
 
-        self.data.resize(shard_count * shard_bytes, 0);
+        // Clearing first also means that a re-allocation
+        // doesn't copy old content to new allocation.
+        #[cfg(feature = "zeroize")]
+        self.zeroize_all();
+
+        self.data.resize(bytes, 0);
+        Ok(())
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        #[cfg(feature = "zeroize")]
+        if self.data.len() < self.data.capacity() {
+            // Copy to a new allocation instead of re-allocating in place,
+            // so that old allocation can be zeroed before it's freed.
+            let data = self.data.clone();
+            self.zeroize_all();
+            self.data = data;
+            return;
+        }
+
+        self.data.shrink_to_fit();
+    }
+
+    // Zeroes all allocated bytes, including spare capacity,
+    // and clears `data`.
+    #[cfg(feature = "zeroize")]
+    fn zeroize_all(&mut self) {
+        self.data.as_mut_slice().zeroize();
+        self.data.spare_capacity_mut().zeroize();
+        self.data.clear();
+    }
+}
+
+// ======================================================================
+// Shards - IMPL Drop
+
+#[cfg(feature = "zeroize")]
+impl Drop for Shards {
+    fn drop(&mut self) {
+        self.zeroize_all();
     }
 }
 
@@ -53,6 +153,57 @@ impl IndexMut<usize> for Shards {
     }
 }
 
+// ======================================================================
+// WorkAllocator - PRIVATE
+
+// Type-erased allocator so that working space types
+// don't need to be generic over the allocator.
+#[cfg(feature = "allocator-api")]
+#[derive(Clone)]
+struct WorkAllocator(Arc<dyn Allocator + Send + Sync>);
+
+#[cfg(feature = "allocator-api")]
+unsafe impl Allocator for WorkAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.0.allocate(layout)
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.0.allocate_zeroed(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { self.0.deallocate(ptr, layout) }
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe { self.0.grow(ptr, old_layout, new_layout) }
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe { self.0.grow_zeroed(ptr, old_layout, new_layout) }
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe { self.0.shrink(ptr, old_layout, new_layout) }
+    }
+}
+
 // ======================================================================
 // ShardsRefMut - PUBLIC
 
@@ -119,11 +270,26 @@ impl<'a> ShardsRefMut<'a> {
         self.shard_count == 0
     }
 
+    /// Returns iterator over mutable references to each shard in order,
+    /// e.g. to process shards one at a time without index arithmetic.
+    ///
+    /// # Panics
+    ///
+    /// If `shard_bytes` is `0`.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut [u8]> {
+        self.data.chunks_exact_mut(self.shard_bytes)
+    }
+
     /// Returns number of shards.
     pub fn len(&self) -> usize {
         self.shard_count
     }
 
+    /// Returns size of each shard in bytes.
+    pub(crate) fn shard_bytes(&self) -> usize {
+        self.shard_bytes
+    }
+
     /// Creates new [`ShardsRefMut`] that references given `data`.
     ///
     /// # Panics
@@ -149,6 +315,11 @@ impl<'a> ShardsRefMut<'a> {
 
     /// Fills the given shard-range with `0u8`:s.
     pub fn zero<R: RangeBounds<usize>>(&mut self, range: R) {
+        self.zero_with::<NoSimd>(range);
+    }
+
+    /// Fills the given shard-range with `0u8`:s using [`Engine::fill`] of `E`.
+    pub(crate) fn zero_with<E: Engine>(&mut self, range: impl RangeBounds<usize>) {
         let start = match range.start_bound() {
             Bound::Included(start) => start * self.shard_bytes,
             Bound::Excluded(start) => (start + 1) * self.shard_bytes,
@@ -161,7 +332,7 @@ impl<'a> ShardsRefMut<'a> {
             Bound::Unbounded => self.shard_count * self.shard_bytes,
         };
 
-        self.data[start..end].fill(0);
+        E::fill(&mut self.data[start..end], 0);
     }
 }
 
@@ -188,6 +359,7 @@ impl<'a> IndexMut<usize> for ShardsRefMut<'a> {
 // ShardsRefMut - CRATE
 
 impl<'a> ShardsRefMut<'a> {
+    #[cfg(feature = "encoder")]
     pub(crate) fn copy_within(&mut self, mut src: usize, mut dest: usize, mut count: usize) {
         src *= self.shard_bytes;
         dest *= self.shard_bytes;
@@ -219,3 +391,246 @@ impl<'a> ShardsRefMut<'a> {
         }
     }
 }
+
+// ======================================================================
+// TESTS
+
+// These only do pointer arithmetic of shard arrays
+// and are also run under Miri.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Shard `i` filled with `i + 1`.
+    fn numbered(shard_count: usize, shard_bytes: usize) -> Vec<u8> {
+        (0..shard_count)
+            .flat_map(|i| std::iter::repeat((i + 1) as u8).take(shard_bytes))
+            .collect()
+    }
+
+    fn first_bytes(data: &ShardsRefMut) -> Vec<u8> {
+        (0..data.len()).map(|i| data[i][0]).collect()
+    }
+
+    // ============================================================
+    // dist2_mut / dist4_mut
+
+    #[test]
+    fn dist2_mut() {
+        let mut bytes = numbered(8, 64);
+        let mut data = ShardsRefMut::new(8, 64, &mut bytes);
+
+        for (pos, dist) in [(0, 1), (0, 7), (3, 4), (6, 1)] {
+            let (a, b) = data.dist2_mut(pos, dist);
+            assert_eq!((a.len(), b.len()), (64, 64));
+            assert_eq!((a[0], b[63]), ((pos + 1) as u8, (pos + dist + 1) as u8));
+        }
+
+        // Last shard.
+        let (a, b) = data.dist2_mut(6, 1);
+        a.fill(0);
+        b.fill(0);
+        assert_eq!(first_bytes(&data), [1, 2, 3, 4, 5, 6, 0, 0]);
+    }
+
+    #[test]
+    fn dist4_mut() {
+        let mut bytes = numbered(8, 64);
+        let mut data = ShardsRefMut::new(8, 64, &mut bytes);
+
+        // Shards 4 .. 8, last one ending at end of data.
+        let (a, b, c, d) = data.dist4_mut(4, 1);
+        for (x, value) in [(a, 10), (b, 11), (c, 12), (d, 13)] {
+            assert_eq!(x.len(), 64);
+            x.fill(value);
+        }
+        assert_eq!(first_bytes(&data), [1, 2, 3, 4, 10, 11, 12, 13]);
+
+        let (a, b, c, d) = data.dist4_mut(1, 2);
+        assert_eq!([a[0], b[0], c[0], d[63]], [2, 4, 11, 13]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn dist2_mut_out_of_bounds() {
+        let mut bytes = numbered(4, 64);
+        ShardsRefMut::new(4, 64, &mut bytes).dist2_mut(2, 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn dist4_mut_out_of_bounds() {
+        let mut bytes = numbered(4, 64);
+        ShardsRefMut::new(4, 64, &mut bytes).dist4_mut(1, 1);
+    }
+
+    // ============================================================
+    // iter_mut
+
+    #[test]
+    fn iter_mut() {
+        let mut bytes = numbered(5, 64);
+        let mut data = ShardsRefMut::new(5, 64, &mut bytes);
+
+        let sums: Vec<usize> = data
+            .iter_mut()
+            .map(|shard| shard.iter().map(|&x| x as usize).sum())
+            .collect();
+        assert_eq!(sums, [64, 128, 192, 256, 320]);
+
+        for (i, shard) in data.iter_mut().enumerate() {
+            assert_eq!(shard.len(), 64);
+            shard[i] ^= 0xFF;
+        }
+        assert_eq!(first_bytes(&data), [!1, 2, 3, 4, 5]);
+
+        for i in 0..5 {
+            assert_eq!(bytes[i * 64 + i], !(i as u8 + 1));
+        }
+
+        // Only the referenced shards, even if given data is larger.
+        let mut bytes = numbered(4, 64);
+        let mut data = ShardsRefMut::new(3, 64, &mut bytes);
+        assert_eq!(data.iter_mut().count(), 3);
+        assert_eq!(ShardsRefMut::new(0, 64, &mut []).iter_mut().count(), 0);
+    }
+
+    // ============================================================
+    // split_at_mut / flat2_mut / copy_within / zero
+
+    #[test]
+    fn split_at_mut() {
+        let mut bytes = numbered(4, 64);
+        let mut data = ShardsRefMut::new(4, 64, &mut bytes);
+
+        for mid in 0..=4 {
+            let (mut a, mut b) = data.split_at_mut(mid);
+            assert_eq!((a.len(), b.len()), (mid, 4 - mid));
+            assert_eq!(first_bytes(&a), (1..=mid as u8).collect::<Vec<_>>());
+            assert_eq!(first_bytes(&b), (mid as u8 + 1..=4).collect::<Vec<_>>());
+
+            // Empty halves can be split further.
+            assert!(a.split_at_mut(0).0.is_empty());
+            assert!(b.split_at_mut(b.len()).1.is_empty());
+        }
+    }
+
+    #[test]
+    fn flat2_mut() {
+        let mut bytes = numbered(6, 64);
+        let mut data = ShardsRefMut::new(6, 64, &mut bytes);
+
+        // Both orders, adjacent ranges and ranges ending at end of data.
+        for (x, y, count) in [(0, 3, 3), (3, 0, 3), (1, 2, 1), (5, 0, 1), (2, 4, 0)] {
+            let (a, b) = data.flat2_mut(x, y, count);
+            assert_eq!((a.len(), b.len()), (count * 64, count * 64));
+            if count > 0 {
+                assert_eq!((a[0], b[b.len() - 1]), ((x + 1) as u8, (y + count) as u8));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "encoder")]
+    fn copy_within_and_zero() {
+        let mut bytes = numbered(6, 64);
+        let mut data = ShardsRefMut::new(6, 64, &mut bytes);
+
+        // Overlapping ranges.
+        data.copy_within(0, 2, 3);
+        assert_eq!(first_bytes(&data), [1, 2, 1, 2, 3, 6]);
+        data.copy_within(2, 1, 4);
+        assert_eq!(first_bytes(&data), [1, 1, 2, 3, 6, 6]);
+
+        data.zero(5..);
+        data.zero(..=0);
+        data.zero(2..2);
+        assert_eq!(first_bytes(&data), [0, 1, 2, 3, 6, 0]);
+        data.zero(..);
+        assert!(bytes.iter().all(|&x| x == 0));
+    }
+
+    // ============================================================
+    // Zero-length
+
+    #[test]
+    fn no_shards() {
+        let mut data = ShardsRefMut::new(0, 64, &mut []);
+        assert!(data.is_empty());
+
+        let (a, b) = data.split_at_mut(0);
+        assert!(a.is_empty() && b.is_empty());
+
+        let (a, b) = data.flat2_mut(0, 0, 0);
+        assert!(a.is_empty() && b.is_empty());
+
+        data.zero(..);
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_with_too_small_data() {
+        let mut bytes = numbered(3, 64);
+        ShardsRefMut::new(4, 64, &mut bytes);
+    }
+
+    // ============================================================
+    // Shards
+
+    #[test]
+    fn shards_resize_reuses_allocation() {
+        let mut shards = Shards::new();
+
+        shards.resize(4, 128).unwrap();
+        shards[3].fill(7);
+        let allocated_bytes = shards.allocated_bytes();
+
+        // Smaller and differently shaped configurations fit in same allocation.
+        for (shard_count, shard_bytes) in [(2, 64), (8, 64), (1, 512)] {
+            shards.resize(shard_count, shard_bytes).unwrap();
+            let mut data = shards.as_ref_mut();
+            assert_eq!(data.len(), shard_count);
+            data[shard_count - 1].fill(1);
+            assert_eq!(shards.allocated_bytes(), allocated_bytes);
+        }
+
+        shards.resize(0, 64).unwrap();
+        assert!(shards.as_ref_mut().is_empty());
+
+        shards.shrink_to_fit();
+        assert_eq!(shards.allocated_bytes(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn shards_resize_zeroizes() {
+        let mut shards = Shards::new();
+        shards.resize(4, 64).unwrap();
+
+        // Same size and smaller size re-use allocation as is.
+        for (shard_count, shard_bytes) in [(4, 64), (1, 128)] {
+            shards.as_ref_mut()[0].fill(7);
+            shards.resize(shard_count, shard_bytes).unwrap();
+            assert!((0..shard_count).all(|i| shards[i].iter().all(|&x| x == 0)));
+        }
+
+        // Content is kept when shrinking allocation.
+        shards.as_ref_mut()[0].fill(7);
+        shards.shrink_to_fit();
+        assert_eq!(shards.allocated_bytes(), 128);
+        assert!(shards[0].iter().all(|&x| x == 7));
+    }
+
+    #[test]
+    fn shards_resize_overflow() {
+        let mut shards = Shards::new();
+        shards.resize(2, 64).unwrap();
+
+        assert_eq!(
+            shards.resize(usize::MAX / 64, 128),
+            Err(Error::InvalidShardSize { shard_bytes: 128 })
+        );
+        assert_eq!(shards.as_ref_mut().len(), 2);
+    }
+}
@@ -4,26 +4,82 @@
 //!
 //! # Tables
 //!
-//! | Table        | Size    | Used in encoding | Used in decoding | By engines         |
-//! | ------------ | ------- | ---------------- | ---------------- | ------------------ |
-//! | [`Exp`]      | 128 kiB | yes              | yes              | all                |
-//! | [`Log`]      | 128 kiB | yes              | yes              | all                |
-//! | [`LogWalsh`] | 128 kiB | -                | yes              | all                |
-//! | [`Mul16`]    | 8 MiB   | yes              | yes              | [`NoSimd`]         |
-//! | [`Mul128`]   | 8 MiB   | yes              | yes              | [`Avx2`] [`Ssse3`] |
-//! | [`Skew`]     | 128 kiB | yes              | yes              | all                |
+//! | Table        | Size    | Used in encoding | Used in decoding | By engines                     |
+//! | ------------ | ------- | ---------------- | ---------------- | ------------------------------ |
+//! | [`Exp`]      | 128 kiB | yes              | yes              | all                            |
+//! | [`Log`]      | 128 kiB | yes              | yes              | all                            |
+//! | [`LogWalsh`] | 128 kiB | -                | yes (3)          | all                            |
+//! | [`Mul16`]    | 8 MiB   | yes              | yes              | [`NoSimd`] (1), `PortableSimd` |
+//! | [`Mul128`]   | 8 MiB   | yes              | yes              | [`Avx2`] [`Ssse3`] (2)         |
+//! | [`Skew`]     | 128 kiB | yes              | yes              | all                            |
 //!
+//! (1) Not used with `constant-time` feature, see [`NoSimd`].
+//!
+//! (2) Not used with `compact-tables` feature, which instead calculates
+//!     each [`Multiply128lutT`] when needed from [`Exp`] and [`Log`].
+//!     This is slower but saves 8 MiB.
+//!
+//! (3) Only available with `decoder` feature.
+//!     Other tables are shared by encoding and decoding.
+//!
+//! Tables are initialized when first needed, usually by engine constructors.
+//! [`is_initialized`] tells which tables have been initialized and
+//! [`reed_solomon_simd::warmup`] initializes tables ahead of time.
+//!
+//! Each table is initialized independently, so a thread needing one table
+//! doesn't wait for initialization of others. Threads which need [`Mul16`]
+//! or [`Mul128`] at the same time calculate different parts of it in parallel
+//! instead of waiting for one thread to calculate all of it.
+//! Threads waiting for a table to be ready sleep instead of spinning.
+//!
+//! # Fork
+//!
+//! A table is published only once it has been fully calculated,
+//! and no locks are held after that, so references returned by
+//! `initialize_*` functions and engines are plain `'static` data
+//! which stays valid and correct in a child process after `fork()`.
+//!
+//! However a table which another thread is initializing at the time of
+//! `fork()` can never be completed in the child, as that thread doesn't
+//! exist there, so a child needing that table blocks forever.
+//! Tables which were not being initialized are not affected.
+//!
+//! So when forking worker processes, call [`reed_solomon_simd::warmup`]
+//! and let it return before the first `fork()`.
+//!
+//! # Alternative basis
+//!
+//! With `unstable-basis` feature `BasisTables` calculates tables for
+//! another Cantor basis than [`CANTOR_BASIS`], which orders evaluation points
+//! of shards differently, e.g. to match shard indexes of another scheme.
+//! These are not global and are used only by engines created with
+//! `NoSimd::with_tables`.
+//!
+//! **Shards encoded with an alternative basis are incompatible with
+//! the standard scheme** and can only be decoded with the same basis.
+//! This is intended for research and has no stability guarantees.
+//!
+//! [`reed_solomon_simd::warmup`]: crate::warmup
 //! [`NoSimd`]: crate::engine::NoSimd
 //! [`Avx2`]: crate::engine::Avx2
 //! [`Ssse3`]: crate::engine::Ssse3
 //! [`Engine`]: crate::engine
+//! [`CANTOR_BASIS`]: crate::engine::CANTOR_BASIS
 //!
 
+use std::{
+    borrow::Cow,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
 use once_cell::sync::OnceCell;
 
-use crate::engine::{
-    self, fwht, GfElement, CANTOR_BASIS, GF_BITS, GF_MODULUS, GF_ORDER, GF_POLYNOMIAL,
-};
+#[cfg(feature = "decoder")]
+use crate::engine::fwht;
+use crate::engine::{self, GfElement, CANTOR_BASIS, GF_BITS, GF_MODULUS, GF_ORDER, GF_POLYNOMIAL};
 
 // ======================================================================
 // TYPE ALIASES - PUBLIC
@@ -61,6 +117,7 @@ pub struct Multiply128lutT {
 ///
 /// [`Engine`]: crate::engine
 /// [`Engine::eval_poly`]: crate::engine::Engine::eval_poly
+#[cfg(feature = "decoder")]
 pub type LogWalsh = [GfElement; GF_ORDER];
 
 /// Used by [`NoSimd`] engine for multiplications.
@@ -73,6 +130,156 @@ pub type Mul16 = [[[GfElement; 16]; 4]; GF_ORDER];
 /// [`Engine`]: crate::engine
 pub type Skew = [GfElement; GF_MODULUS as usize];
 
+// ======================================================================
+// Table - PUBLIC
+
+/// Global table, see [`is_initialized`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Table {
+    /// [`Exp`] and [`Log`], which are always initialized together.
+    ExpLog,
+    /// [`LogWalsh`]
+    #[cfg(feature = "decoder")]
+    LogWalsh,
+    /// [`Mul16`]
+    Mul16,
+    /// [`Mul128`]
+    Mul128,
+    /// [`Skew`]
+    Skew,
+}
+
+// ======================================================================
+// Mul128Luts - CRATE
+
+/// Source of [`Multiply128lutT`]:s for SIMD engines.
+///
+/// This is [`Mul128`] table, or [`Exp`] and [`Log`] tables
+/// with `compact-tables` feature.
+#[derive(Clone)]
+pub(crate) struct Mul128Luts {
+    #[cfg(not(feature = "compact-tables"))]
+    mul128: &'static Mul128,
+    #[cfg(feature = "compact-tables")]
+    exp: &'static Exp,
+    #[cfg(feature = "compact-tables")]
+    log: &'static Log,
+}
+
+impl Mul128Luts {
+    /// Initializes needed tables.
+    pub(crate) fn new() -> Self {
+        #[cfg(not(feature = "compact-tables"))]
+        {
+            Self {
+                mul128: initialize_mul128(),
+            }
+        }
+
+        #[cfg(feature = "compact-tables")]
+        {
+            let (exp, log) = initialize_exp_log();
+            Self { exp, log }
+        }
+    }
+
+    /// Returns [`Multiply128lutT`] for `log_m`.
+    ///
+    /// With `compact-tables` feature this is calculated on every call,
+    /// so callers should get it once for a whole shard, not per 64 bytes.
+    #[inline(always)]
+    pub(crate) fn get(&self, log_m: GfElement) -> Cow<'static, Multiply128lutT> {
+        #[cfg(not(feature = "compact-tables"))]
+        {
+            Cow::Borrowed(&self.mul128[log_m as usize])
+        }
+
+        #[cfg(feature = "compact-tables")]
+        {
+            Cow::Owned(mul128_lut(log_m, self.exp, self.log))
+        }
+    }
+}
+
+// ======================================================================
+// BasisTables - PUBLIC
+
+/// Tables for an alternative Cantor basis, see [Alternative basis].
+///
+/// This contains the tables which [`NoSimd`] needs,
+/// so 8 MiB [`Mul16`] unless `constant-time` feature is enabled.
+///
+/// Requires `unstable-basis` feature.
+///
+/// [Alternative basis]: self#alternative-basis
+/// [`NoSimd`]: crate::engine::NoSimd
+#[cfg(feature = "unstable-basis")]
+pub struct BasisTables {
+    basis: [GfElement; GF_BITS],
+    exp: Box<Exp>,
+    log: Box<Log>,
+    #[cfg(feature = "decoder")]
+    log_walsh: Box<LogWalsh>,
+    #[cfg(not(feature = "constant-time"))]
+    mul16: Box<Mul16>,
+    skew: Box<Skew>,
+}
+
+#[cfg(feature = "unstable-basis")]
+impl BasisTables {
+    /// Calculates tables for given Cantor basis.
+    ///
+    /// # Panics
+    ///
+    /// If `basis` is not a Cantor basis, see [`is_cantor_basis`].
+    pub fn new(basis: [GfElement; GF_BITS]) -> Self {
+        let (exp, log) = initialize_exp_log_with_basis(basis);
+
+        Self {
+            basis,
+            #[cfg(feature = "decoder")]
+            log_walsh: log_walsh(&log),
+            #[cfg(not(feature = "constant-time"))]
+            mul16: mul16(&exp, &log),
+            skew: skew(&exp, &log),
+            exp,
+            log,
+        }
+    }
+
+    /// Returns the basis these tables were calculated for.
+    pub fn basis(&self) -> [GfElement; GF_BITS] {
+        self.basis
+    }
+
+    /// Returns [`Exp`] table, e.g. for [`mul`].
+    pub fn exp(&self) -> &Exp {
+        &self.exp
+    }
+
+    /// Returns [`Log`] table, e.g. for [`mul`].
+    pub fn log(&self) -> &Log {
+        &self.log
+    }
+
+    /// Returns [`LogWalsh`] table.
+    #[cfg(feature = "decoder")]
+    pub fn log_walsh(&self) -> &LogWalsh {
+        &self.log_walsh
+    }
+
+    /// Returns [`Mul16`] table.
+    #[cfg(not(feature = "constant-time"))]
+    pub fn mul16(&self) -> &Mul16 {
+        &self.mul16
+    }
+
+    /// Returns [`Skew`] table.
+    pub fn skew(&self) -> &Skew {
+        &self.skew
+    }
+}
+
 // ======================================================================
 // ExpLog - PRIVATE
 
@@ -81,13 +288,136 @@ struct ExpLog {
     log: Box<Log>,
 }
 
+// ======================================================================
+// ChunkedTable - PRIVATE
+
+const CHUNK_COUNT: usize = 64;
+const CHUNK_LEN: usize = GF_ORDER / CHUNK_COUNT;
+
+// Global table of `GF_ORDER` elements which is calculated in chunks.
+// - Threads which need the table at the same time each calculate
+//   chunks not yet claimed by other threads, then wait for the rest.
+// - Chunks are written in place into the final table,
+//   which is published once all chunks have been written.
+// - Waiting threads block on `OnceCell`, which doesn't spin.
+struct ChunkedTable<T: 'static> {
+    table: OnceCell<Box<[T; GF_ORDER]>>,
+    chunks: Mutex<Option<Arc<Chunks<T>>>>,
+}
+
+struct Chunks<T> {
+    next: AtomicUsize,
+    // `done[i]` is set once chunk `i` has been written.
+    done: Vec<OnceCell<()>>,
+    // Final table with capacity of `GF_ORDER` elements,
+    // taken once all chunks have been written.
+    table: Mutex<Vec<T>>,
+    // Start of the buffer of `table`, see `write_chunk`.
+    ptr: *mut T,
+}
+
+// SAFETY: `ptr` is only written through by `write_chunk`
+// which writes each chunk from one thread only,
+// and `table` is read only after all chunks have been written.
+unsafe impl<T: Send> Send for Chunks<T> {}
+unsafe impl<T: Send> Sync for Chunks<T> {}
+
+impl<T> Chunks<T> {
+    fn new() -> Self {
+        let mut table = Vec::with_capacity(GF_ORDER);
+        let ptr = table.as_mut_ptr();
+        Self {
+            next: AtomicUsize::new(0),
+            done: (0..CHUNK_COUNT).map(|_| OnceCell::new()).collect(),
+            table: Mutex::new(table),
+            ptr,
+        }
+    }
+
+    // Writes element `i` of given chunk as `f(i)`
+    // unless the chunk has already been written.
+    fn write_chunk<F: Fn(usize) -> T>(&self, chunk: usize, f: &F) {
+        self.done[chunk].get_or_init(|| {
+            for i in chunk * CHUNK_LEN..(chunk + 1) * CHUNK_LEN {
+                // SAFETY: `i < GF_ORDER` is within capacity of `table`,
+                // and `OnceCell` runs only one initializer of a chunk at a time.
+                unsafe { self.ptr.add(i).write(f(i)) };
+            }
+        });
+    }
+}
+
+impl<T: Send> ChunkedTable<T> {
+    const fn new() -> Self {
+        Self {
+            table: OnceCell::new(),
+            chunks: Mutex::new(None),
+        }
+    }
+
+    fn get(&self) -> Option<&[T; GF_ORDER]> {
+        self.table.get().map(AsRef::as_ref)
+    }
+
+    // Returns the table, calculating its element `i` with `f(i)`
+    // if the table hasn't been initialized yet.
+    // - `name` is only used for tracing.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn get_or_init<F: Fn(usize) -> T>(
+        &'static self,
+        name: &'static str,
+        f: F,
+    ) -> &'static [T; GF_ORDER] {
+        if let Some(table) = self.get() {
+            return table;
+        }
+
+        let chunks = {
+            let mut chunks = self.chunks.lock().unwrap();
+            // Checked again as `chunks` is cleared after table is set.
+            if let Some(table) = self.get() {
+                return table;
+            }
+            chunks
+                .get_or_insert_with(|| Arc::new(Chunks::new()))
+                .clone()
+        };
+
+        trace_span!("init_table", table = name);
+
+        loop {
+            let chunk = chunks.next.fetch_add(1, Ordering::Relaxed);
+            if chunk >= CHUNK_COUNT {
+                break;
+            }
+            chunks.write_chunk(chunk, &f);
+        }
+
+        let table = self.table.get_or_init(|| {
+            // Waits for chunks claimed by other threads,
+            // or writes them if those threads panicked.
+            for chunk in 0..CHUNK_COUNT {
+                chunks.write_chunk(chunk, &f);
+            }
+            let mut table = std::mem::take(&mut *chunks.table.lock().unwrap());
+            // SAFETY: All `GF_ORDER` elements have been written above.
+            unsafe { table.set_len(GF_ORDER) };
+            table.into_boxed_slice().try_into().ok().unwrap()
+        });
+
+        *self.chunks.lock().unwrap() = None;
+        table
+    }
+}
+
 // ======================================================================
 // STATIC - PRIVATE
 
 static EXP_LOG: OnceCell<ExpLog> = OnceCell::new();
+#[cfg(feature = "decoder")]
 static LOG_WALSH: OnceCell<Box<LogWalsh>> = OnceCell::new();
-static MUL16: OnceCell<Box<Mul16>> = OnceCell::new();
-static MUL128: OnceCell<Box<Mul128>> = OnceCell::new();
+static MUL16: ChunkedTable<[[GfElement; 16]; 4]> = ChunkedTable::new();
+static MUL128: ChunkedTable<Multiply128lutT> = ChunkedTable::new();
 static SKEW: OnceCell<Box<Skew>> = OnceCell::new();
 
 // ======================================================================
@@ -103,164 +433,541 @@ pub fn mul(x: GfElement, log_m: GfElement, exp: &Exp, log: &Log) -> GfElement {
     }
 }
 
-// ======================================================================
-// FUNCTIONS - PUBLIC - initialize tables
-
-/// Initializes and returns [`Exp`] and [`Log`] tables.
-#[allow(clippy::needless_range_loop)]
-pub fn initialize_exp_log() -> (&'static Exp, &'static Log) {
-    let exp_log = EXP_LOG.get_or_init(|| {
-        let mut exp = Box::new([0; GF_ORDER]);
-        let mut log = Box::new([0; GF_ORDER]);
+/// Returns `2^i * log_m` for each bit `i` of [`GfElement`], for [`mul_ct`].
+///
+/// Only `log_m` is used for indexing, so this is not constant time
+/// with respect to the multiplier, which is never secret in this crate.
+#[cfg(feature = "constant-time")]
+pub fn mul_bits(log_m: GfElement, exp: &Exp, log: &Log) -> [GfElement; GF_BITS] {
+    let mut bits = [0; GF_BITS];
+    for (i, bit) in bits.iter_mut().enumerate() {
+        *bit = mul(1 << i, log_m, exp, log);
+    }
+    bits
+}
 
-        // GENERATE LFSR TABLE
+/// Calculates `x * log_m` given `bits` from [`mul_bits`] for `log_m`.
+///
+/// Multiplication by a constant is linear over `GF(2)`,
+/// so product is `XOR` of `bits[i]` for each bit `i` set in `x`.
+/// Unlike [`mul`] this has no memory indexing or branches
+/// which depend on `x`.
+#[cfg(feature = "constant-time")]
+#[inline(always)]
+pub fn mul_ct(x: GfElement, bits: &[GfElement; GF_BITS]) -> GfElement {
+    let mut prod = 0;
+    for (i, bit) in bits.iter().enumerate() {
+        let mask = 0u16.wrapping_sub((x >> i) & 1);
+        prod ^= bit & mask;
+    }
+    prod
+}
 
-        let mut state = 1;
-        for i in 0..GF_MODULUS {
-            exp[state] = i;
-            state <<= 1;
-            if state >= GF_ORDER {
-                state ^= GF_POLYNOMIAL;
-            }
+/// Calculates [`Multiply128lutT`] for `log_m` using [`Exp`] and [`Log`] tables.
+pub fn mul128_lut(log_m: GfElement, exp: &Exp, log: &Log) -> Multiply128lutT {
+    let mut lut = Multiply128lutT {
+        lo: [0; 4],
+        hi: [0; 4],
+    };
+
+    for i in 0..=3 {
+        // Multiplication is linear, so only products of
+        // single bits need lookups and rest are `XOR`:s of those.
+        let mut prod = [0 as GfElement; 16];
+        for bit in 0..4 {
+            prod[1 << bit] = mul(1 << (i * 4 + bit), log_m, exp, log);
         }
-        exp[0] = GF_MODULUS;
-
-        // CONVERT TO CANTOR BASIS
-
-        log[0] = 0;
-        for i in 0..GF_BITS {
-            let width = 1usize << i;
-            for j in 0..width {
-                log[j + width] = log[j] ^ CANTOR_BASIS[i];
-            }
+        for x in 1..16usize {
+            let low_bit = x & x.wrapping_neg();
+            prod[x] = prod[low_bit] ^ prod[x ^ low_bit];
         }
 
-        for i in 0..GF_ORDER {
-            log[i] = exp[log[i] as usize];
+        let mut prod_lo = [0u8; 16];
+        let mut prod_hi = [0u8; 16];
+        for x in 0..16 {
+            prod_lo[x] = prod[x] as u8;
+            prod_hi[x] = (prod[x] >> 8) as u8;
         }
+        lut.lo[i] = u128::from_le_bytes(prod_lo);
+        lut.hi[i] = u128::from_le_bytes(prod_hi);
+    }
 
-        for i in 0..GF_ORDER {
-            exp[log[i] as usize] = i as GfElement;
-        }
+    lut
+}
 
-        exp[GF_MODULUS as usize] = exp[0];
+// ======================================================================
+// FUNCTIONS - PUBLIC - initialize tables
 
-        ExpLog { exp, log }
+/// Returns `true` if given table has been initialized.
+///
+/// Tables are never released, so after this has returned `true`
+/// for a table it always does.
+///
+/// # Examples
+///
+/// ```rust
+/// use reed_solomon_simd::engine::tables::{self, Table};
+///
+/// tables::initialize_skew();
+/// assert!(tables::is_initialized(Table::Skew));
+/// ```
+pub fn is_initialized(table: Table) -> bool {
+    match table {
+        Table::ExpLog => EXP_LOG.get().is_some(),
+        #[cfg(feature = "decoder")]
+        Table::LogWalsh => LOG_WALSH.get().is_some(),
+        Table::Mul16 => MUL16.get().is_some(),
+        Table::Mul128 => MUL128.get().is_some(),
+        Table::Skew => SKEW.get().is_some(),
+    }
+}
+
+/// Initializes and returns [`Exp`] and [`Log`] tables.
+pub fn initialize_exp_log() -> (&'static Exp, &'static Log) {
+    let exp_log = EXP_LOG.get_or_init(|| {
+        trace_span!("init_table", table = "exp_log");
+        exp_log(&CANTOR_BASIS)
     });
 
     (&exp_log.exp, &exp_log.log)
 }
 
 /// Initializes and returns [`LogWalsh`] table.
+#[cfg(feature = "decoder")]
 pub fn initialize_log_walsh() -> &'static LogWalsh {
     LOG_WALSH.get_or_init(|| {
-        let (_, log) = initialize_exp_log();
-
-        let mut log_walsh: Box<LogWalsh> = Box::new([0; GF_ORDER]);
+        trace_span!("init_table", table = "log_walsh");
 
-        log_walsh.copy_from_slice(log.as_ref());
-        log_walsh[0] = 0;
-        fwht::fwht(log_walsh.as_mut(), GF_ORDER);
-
-        log_walsh
+        let (_, log) = initialize_exp_log();
+        log_walsh(log)
     })
 }
 
 /// Initializes and returns [`Mul16`] table.
 pub fn initialize_mul16() -> &'static Mul16 {
-    MUL16.get_or_init(|| {
-        let (exp, log) = initialize_exp_log();
-
-        let mut mul16 = vec![[[0; 16]; 4]; GF_ORDER];
+    let (exp, log) = initialize_exp_log();
 
-        for log_m in 0..=GF_MODULUS {
-            let lut = &mut mul16[log_m as usize];
-            for i in 0..16 {
-                lut[0][i] = mul(i as GfElement, log_m, exp, log);
-                lut[1][i] = mul((i << 4) as GfElement, log_m, exp, log);
-                lut[2][i] = mul((i << 8) as GfElement, log_m, exp, log);
-                lut[3][i] = mul((i << 12) as GfElement, log_m, exp, log);
-            }
-        }
-
-        mul16.into_boxed_slice().try_into().unwrap()
-    })
+    MUL16.get_or_init("mul16", |log_m| mul16_lut(log_m as GfElement, exp, log))
 }
 
 /// Initializes and returns [`Mul128`] table.
 pub fn initialize_mul128() -> &'static Mul128 {
     // Based on:
     // https://github.com/catid/leopard/blob/22ddc7804998d31c8f1a2617ee720e063b1fa6cd/LeopardFF16.cpp#L375
-    MUL128.get_or_init(|| {
-        let (exp, log) = initialize_exp_log();
-
-        let mut mul128 = vec![
-            Multiply128lutT {
-                lo: [0; 4],
-                hi: [0; 4],
-            };
-            GF_ORDER
-        ];
-
-        for log_m in 0..=GF_MODULUS {
-            for i in 0..=3 {
-                let mut prod_lo = [0u8; 16];
-                let mut prod_hi = [0u8; 16];
-                for x in 0..16 {
-                    let prod = mul((x << (i * 4)) as GfElement, log_m, exp, log);
-                    prod_lo[x] = prod as u8;
-                    prod_hi[x] = (prod >> 8) as u8;
-                }
-                mul128[log_m as usize].lo[i] = u128::from_le_bytes(prod_lo);
-                mul128[log_m as usize].hi[i] = u128::from_le_bytes(prod_hi);
-            }
-        }
+    let (exp, log) = initialize_exp_log();
 
-        mul128.into_boxed_slice().try_into().unwrap()
-    })
+    MUL128.get_or_init("mul128", |log_m| mul128_lut(log_m as GfElement, exp, log))
 }
 
 /// Initializes and returns [`Skew`] table.
-#[allow(clippy::needless_range_loop)]
+///
+/// Skew values are logarithms, so zero is stored as [`GF_MODULUS`].
+/// This is only the case at indexes `2^m - 1`, which FFT / IFFT
+/// only read when `skew_delta == 0`, i.e. for the first butterfly
+/// of each layer of a transform at the start of the field.
+/// Engines handle it with `xor` instead of multiplication.
 pub fn initialize_skew() -> &'static Skew {
     SKEW.get_or_init(|| {
+        trace_span!("init_table", table = "skew");
+
         let (exp, log) = initialize_exp_log();
+        skew(exp, log)
+    })
+}
 
-        let mut skew = Box::new([0; GF_MODULUS as usize]);
+// ======================================================================
+// FUNCTIONS - PUBLIC - alternative basis
 
-        let mut temp = [0; GF_BITS - 1];
+/// Returns `true` if `basis` is a Cantor basis of `GF(2^16)`.
+///
+/// Elements are in the polynomial basis of [`GF_POLYNOMIAL`] and
+/// must satisfy `basis[0] == 1` and `basis[i - 1] == basis[i]^2 + basis[i]`,
+/// which the additive FFT relies on. So e.g. a permutation of
+/// [`CANTOR_BASIS`] is not a Cantor basis. Instead other Cantor bases
+/// are obtained by adding elements of [`CANTOR_BASIS`] in lower positions:
+///
+/// ```rust
+/// use reed_solomon_simd::engine::{tables, CANTOR_BASIS};
+///
+/// let mut basis = CANTOR_BASIS;
+/// for i in 3..16 {
+///     basis[i] ^= CANTOR_BASIS[i - 3];
+/// }
+/// assert!(tables::is_cantor_basis(&basis));
+///
+/// basis.swap(1, 2);
+/// assert!(!tables::is_cantor_basis(&basis));
+/// ```
+///
+/// Adding `CANTOR_BASIS[i - d]` where `d` is a power of two
+/// only maps each evaluation point `x` to `x^(2^d)`,
+/// which is a field automorphism and gives the same shards as [`CANTOR_BASIS`].
+///
+/// Requires `unstable-basis` feature.
+///
+/// [`CANTOR_BASIS`]: crate::engine::CANTOR_BASIS
+#[cfg(feature = "unstable-basis")]
+pub fn is_cantor_basis(basis: &[GfElement; GF_BITS]) -> bool {
+    basis[0] == 1 && (1..GF_BITS).all(|i| basis[i - 1] == poly_mul(basis[i], basis[i]) ^ basis[i])
+}
 
-        for i in 1..GF_BITS {
-            temp[i - 1] = 1 << i;
+/// Calculates [`Exp`] and [`Log`] tables for given Cantor basis.
+///
+/// Unlike [`initialize_exp_log`] these are not global
+/// and are calculated again on every call.
+///
+/// Requires `unstable-basis` feature.
+///
+/// # Panics
+///
+/// If `basis` is not a Cantor basis, see [`is_cantor_basis`].
+#[cfg(feature = "unstable-basis")]
+pub fn initialize_exp_log_with_basis(basis: [GfElement; GF_BITS]) -> (Box<Exp>, Box<Log>) {
+    assert!(is_cantor_basis(&basis), "not a Cantor basis");
+    let ExpLog { exp, log } = exp_log(&basis);
+    (exp, log)
+}
+
+/// Calculates [`Skew`] table for given Cantor basis.
+///
+/// Unlike [`initialize_skew`] this is not global
+/// and is calculated again on every call.
+///
+/// Requires `unstable-basis` feature.
+///
+/// # Panics
+///
+/// If `basis` is not a Cantor basis, see [`is_cantor_basis`].
+#[cfg(feature = "unstable-basis")]
+pub fn initialize_skew_with_basis(basis: [GfElement; GF_BITS]) -> Box<Skew> {
+    let (exp, log) = initialize_exp_log_with_basis(basis);
+    skew(&exp, &log)
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE - calculate tables
+
+// Calculates `Exp` and `Log` tables for Cantor basis `basis`.
+#[allow(clippy::needless_range_loop)]
+fn exp_log(basis: &[GfElement; GF_BITS]) -> ExpLog {
+    let mut exp = Box::new([0; GF_ORDER]);
+    let mut log = Box::new([0; GF_ORDER]);
+
+    // GENERATE LFSR TABLE
+
+    let mut state = 1;
+    for i in 0..GF_MODULUS {
+        exp[state] = i;
+        state <<= 1;
+        if state >= GF_ORDER {
+            state ^= GF_POLYNOMIAL;
         }
+    }
+    exp[0] = GF_MODULUS;
+
+    // CONVERT TO CANTOR BASIS
+
+    log[0] = 0;
+    for i in 0..GF_BITS {
+        let width = 1usize << i;
+        for j in 0..width {
+            log[j + width] = log[j] ^ basis[i];
+        }
+    }
+
+    for i in 0..GF_ORDER {
+        log[i] = exp[log[i] as usize];
+    }
 
-        for m in 0..GF_BITS - 1 {
-            let step: usize = 1 << (m + 1);
+    for i in 0..GF_ORDER {
+        exp[log[i] as usize] = i as GfElement;
+    }
 
-            skew[(1 << m) - 1] = 0;
+    exp[GF_MODULUS as usize] = exp[0];
 
-            for i in m..GF_BITS - 1 {
-                let s: usize = 1 << (i + 1);
-                let mut j = (1 << m) - 1;
-                while j < s {
-                    skew[j + s] = skew[j] ^ temp[i];
-                    j += step;
-                }
+    ExpLog { exp, log }
+}
+
+#[cfg(feature = "decoder")]
+fn log_walsh(log: &Log) -> Box<LogWalsh> {
+    let mut log_walsh: Box<LogWalsh> = Box::new([0; GF_ORDER]);
+
+    log_walsh.copy_from_slice(log.as_ref());
+    log_walsh[0] = 0;
+    fwht::fwht(log_walsh.as_mut(), GF_ORDER);
+
+    log_walsh
+}
+
+#[cfg(all(feature = "unstable-basis", not(feature = "constant-time")))]
+fn mul16(exp: &Exp, log: &Log) -> Box<Mul16> {
+    // Built via `Vec` as 8 MiB doesn't fit on stack.
+    (0..GF_ORDER)
+        .map(|log_m| mul16_lut(log_m as GfElement, exp, log))
+        .collect::<Box<[_]>>()
+        .try_into()
+        .ok()
+        .unwrap()
+}
+
+fn mul16_lut(log_m: GfElement, exp: &Exp, log: &Log) -> [[GfElement; 16]; 4] {
+    let mut lut = [[0; 16]; 4];
+    for i in 0..16 {
+        lut[0][i] = mul(i as GfElement, log_m, exp, log);
+        lut[1][i] = mul((i << 4) as GfElement, log_m, exp, log);
+        lut[2][i] = mul((i << 8) as GfElement, log_m, exp, log);
+        lut[3][i] = mul((i << 12) as GfElement, log_m, exp, log);
+    }
+    lut
+}
+
+// Product of `a` and `b` in polynomial basis of `GF_POLYNOMIAL`.
+#[cfg(feature = "unstable-basis")]
+fn poly_mul(a: GfElement, b: GfElement) -> GfElement {
+    let mut prod = 0usize;
+    for i in 0..GF_BITS {
+        if (b >> i) & 1 == 1 {
+            prod ^= usize::from(a) << i;
+        }
+    }
+    for i in (GF_BITS..2 * GF_BITS - 1).rev() {
+        if (prod >> i) & 1 == 1 {
+            prod ^= GF_POLYNOMIAL << (i - GF_BITS);
+        }
+    }
+    prod as GfElement
+}
+
+#[allow(clippy::needless_range_loop)]
+fn skew(exp: &Exp, log: &Log) -> Box<Skew> {
+    let mut skew = Box::new([0; GF_MODULUS as usize]);
+
+    let mut temp = [0; GF_BITS - 1];
+
+    for i in 1..GF_BITS {
+        temp[i - 1] = 1 << i;
+    }
+
+    for m in 0..GF_BITS - 1 {
+        let step: usize = 1 << (m + 1);
+
+        skew[(1 << m) - 1] = 0;
+
+        for i in m..GF_BITS - 1 {
+            let s: usize = 1 << (i + 1);
+            let mut j = (1 << m) - 1;
+            while j < s {
+                skew[j + s] = skew[j] ^ temp[i];
+                j += step;
             }
+        }
+
+        temp[m] = GF_MODULUS - log[mul(temp[m], log[(temp[m] ^ 1) as usize], exp, log) as usize];
+
+        for i in m + 1..GF_BITS - 1 {
+            let sum = engine::add_mod(log[(temp[i] ^ 1) as usize], temp[m]);
+            temp[i] = mul(temp[i], sum, exp, log);
+        }
+    }
+
+    for i in 0..GF_MODULUS as usize {
+        skew[i] = log[skew[i] as usize];
+    }
+
+    skew
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::*;
 
-            temp[m] =
-                GF_MODULUS - log[mul(temp[m], log[(temp[m] ^ 1) as usize], exp, log) as usize];
+    const THREAD_COUNT: usize = 64;
 
-            for i in m + 1..GF_BITS - 1 {
-                let sum = engine::add_mod(log[(temp[i] ^ 1) as usize], temp[m]);
-                temp[i] = mul(temp[i], sum, exp, log);
+    // Slow enough that calculation dominates spawning of threads.
+    fn slow_element(i: usize) -> u64 {
+        (0..200).fold(i as u64, |x, _| {
+            x.wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407)
+        })
+    }
+
+    #[test]
+    fn chunked_table_concurrent_init() {
+        static SINGLE: ChunkedTable<u64> = ChunkedTable::new();
+        static CONCURRENT: ChunkedTable<u64> = ChunkedTable::new();
+
+        let expected = SINGLE.get_or_init("single", slow_element);
+        let tables = init_concurrently(&CONCURRENT);
+
+        for table in &tables {
+            assert!(std::ptr::eq(*table, tables[0]));
+        }
+        assert_eq!(tables[0], expected);
+        assert!(CONCURRENT.chunks.lock().unwrap().is_none());
+    }
+
+    // Timing depends on machine load, so this is run only on request.
+    #[test]
+    #[ignore]
+    fn chunked_table_concurrent_init_time() {
+        static SINGLE: ChunkedTable<u64> = ChunkedTable::new();
+        static CONCURRENT: ChunkedTable<u64> = ChunkedTable::new();
+
+        let start = Instant::now();
+        SINGLE.get_or_init("single", slow_element);
+        let single = start.elapsed();
+
+        let start = Instant::now();
+        init_concurrently(&CONCURRENT);
+        let concurrent = start.elapsed();
+
+        // Threads share the work, so this is far below
+        // `THREAD_COUNT` times single-threaded initialization
+        // even without any parallelism.
+        assert!(
+            concurrent < single * 4 + Duration::from_millis(100),
+            "concurrent {:?}, single {:?}",
+            concurrent,
+            single
+        );
+    }
+
+    fn init_concurrently(table: &'static ChunkedTable<u64>) -> Vec<&'static [u64; GF_ORDER]> {
+        thread::scope(|s| {
+            let handles: Vec<_> = (0..THREAD_COUNT)
+                .map(|_| s.spawn(|| table.get_or_init("concurrent", slow_element)))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        })
+    }
+
+    #[cfg(feature = "unstable-basis")]
+    #[test]
+    fn cantor_basis() {
+        assert!(is_cantor_basis(&CANTOR_BASIS));
+        assert_eq!(
+            *initialize_skew_with_basis(CANTOR_BASIS),
+            *initialize_skew()
+        );
+
+        let (exp, log) = initialize_exp_log_with_basis(CANTOR_BASIS);
+        assert_eq!((&*exp, &*log), initialize_exp_log());
+    }
+
+    #[test]
+    fn skew_modulus_positions() {
+        let skew = initialize_skew();
+        for (i, &log_m) in skew.iter().enumerate() {
+            assert_eq!(log_m == GF_MODULUS, (i + 1).is_power_of_two(), "{}", i);
+        }
+    }
+
+    #[cfg(all(feature = "encoder", feature = "decoder", feature = "unstable-basis"))]
+    #[test]
+    fn alternative_basis_roundtrip() {
+        use crate::{
+            engine::{Engine, NoSimd},
+            rate::{DefaultRate, Rate, RateDecoder, RateEncoder},
+            test_util,
+        };
+
+        fn roundtrip<E: Engine + Clone>(
+            engine: E,
+            original: &[Vec<u8>],
+            recovery_count: usize,
+        ) -> Vec<Vec<u8>> {
+            let original_count = original.len();
+
+            let mut encoder =
+                DefaultRate::encoder(original_count, recovery_count, 64, engine.clone(), None)
+                    .unwrap();
+            for shard in original {
+                encoder.add_original_shard(shard).unwrap();
+            }
+            let result = encoder.encode().unwrap();
+            let recovery: Vec<_> = result.recovery_iter().map(|shard| shard.to_vec()).collect();
+
+            // Lose as many original shards as there are recovery shards.
+            let lost = recovery_count.min(original_count);
+            let mut decoder =
+                DefaultRate::decoder(original_count, recovery_count, 64, engine, None).unwrap();
+            for (index, shard) in original.iter().enumerate().skip(lost) {
+                decoder.add_original_shard(index, shard).unwrap();
+            }
+            for (index, shard) in recovery.iter().enumerate().take(lost) {
+                decoder.add_recovery_shard(index, shard).unwrap();
+            }
+            let result = decoder.decode().unwrap();
+            for (index, shard) in original.iter().enumerate().take(lost) {
+                assert_eq!(result.restored_original(index).unwrap(), shard);
             }
+
+            recovery
         }
 
-        for i in 0..GF_MODULUS as usize {
-            skew[i] = log[skew[i] as usize];
+        // Not a power of two, see `is_cantor_basis`.
+        let mut basis = CANTOR_BASIS;
+        for i in 3..GF_BITS {
+            basis[i] ^= CANTOR_BASIS[i - 3];
         }
+        let tables: &'static BasisTables = Box::leak(Box::new(BasisTables::new(basis)));
 
-        skew
-    })
+        for (original_count, recovery_count) in [(20, 5), (5, 20), (100, 100)] {
+            let original = test_util::generate_original(original_count, 64, 197);
+
+            let standard = roundtrip(NoSimd::new(), &original, recovery_count);
+            let alternative = roundtrip(NoSimd::with_tables(tables), &original, recovery_count);
+            assert_ne!(standard, alternative);
+        }
+    }
+
+    #[cfg(all(feature = "encoder", feature = "decoder"))]
+    #[test]
+    fn concurrent_engines() {
+        use crate::{
+            engine::{DefaultEngine, Engine, Naive, NoSimd},
+            rate::{DefaultRate, Rate, RateEncoder},
+            test_util,
+        };
+
+        fn encode_with<E: Engine>(engine: E, original: &[Vec<u8>]) -> Vec<Vec<u8>> {
+            let mut encoder = DefaultRate::encoder(original.len(), 5, 64, engine, None).unwrap();
+            for shard in original {
+                encoder.add_original_shard(shard).unwrap();
+            }
+            let result = encoder.encode().unwrap();
+            result.recovery_iter().map(|shard| shard.to_vec()).collect()
+        }
+
+        let original = test_util::generate_original(3, 64, 178);
+
+        let results: Vec<_> = thread::scope(|s| {
+            let handles: Vec<_> = (0..THREAD_COUNT)
+                .map(|i| {
+                    let original = &original;
+                    s.spawn(move || {
+                        if i % 2 == 0 {
+                            encode_with(DefaultEngine::new(), original)
+                        } else {
+                            encode_with(NoSimd::new(), original)
+                        }
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let expected = encode_with(Naive::new(), &original);
+        for recovery in results {
+            assert_eq!(recovery, expected);
+        }
+    }
 }
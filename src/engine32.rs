@@ -0,0 +1,496 @@
+//! Experimental engine over `GF(2^32)` for codes with more than 65536 shards.
+//!
+//! **This is an unstable module which is only available with
+//! the `unstable-gf32` feature. It may change in any release.**
+//!
+//! Everything here mirrors the [`engine`] module with
+//! [`GfElement32`] instead of [`GfElement`]:
+//!
+//! - Elements are polynomials over `GF(2)` modulo [`GF32_POLYNOMIAL`],
+//!   i.e. in polynomial basis. Multiplication is carry-less
+//!   multiplication followed by reduction, as lookup-tables
+//!   for `GF(2^32)` would be far too large.
+//! - Evaluation points `0, 1, 2, ...` of the additive FFT stand for
+//!   combinations of [`cantor_basis`] elements selected by
+//!   the bits of the index, see [`point`]. The novel polynomial basis
+//!   is defined as in [Additive FFT](crate::engine#additive-fft)
+//!   with these points.
+//! - Shards are processed as little-endian 4-byte elements.
+//!
+//! Only the reference engine [`Naive32`] is available for now.
+//! See [`ReedSolomonEncoder32`] and [`ReedSolomonDecoder32`]
+//! for encoding and decoding.
+//!
+//! [`engine`]: crate::engine
+//! [`GfElement`]: crate::engine::GfElement
+//! [`ReedSolomonEncoder32`]: crate::ReedSolomonEncoder32
+//! [`ReedSolomonDecoder32`]: crate::ReedSolomonDecoder32
+
+use once_cell::sync::OnceCell;
+
+use crate::engine::ShardsRefMut;
+
+// ======================================================================
+// CONST - PUBLIC
+
+/// Size of Galois field element [`GfElement32`] in bits.
+pub const GF32_BITS: usize = 32;
+
+/// Galois field polynomial `x^32 + x^22 + x^2 + x + 1`.
+pub const GF32_POLYNOMIAL: u64 = 0x1_0040_0007;
+
+// ======================================================================
+// TYPE ALIASES - PUBLIC
+
+/// Galois field element of `GF(2^32)`.
+pub type GfElement32 = u32;
+
+// ======================================================================
+// FUNCTIONS - PUBLIC - Galois field operations
+
+/// Returns Cantor basis of `GF(2^32)` which defines the evaluation points.
+///
+/// `basis[0] = 1` and `basis[i]^2 + basis[i] = basis[i - 1]`.
+pub fn cantor_basis() -> &'static [GfElement32; GF32_BITS] {
+    &tables().cantor_basis
+}
+
+/// Returns multiplicative inverse of `x` or `None` if `x` is `0`.
+pub fn inv(x: GfElement32) -> Option<GfElement32> {
+    if x == 0 {
+        return None;
+    }
+
+    // x^-1 = x^(2^32 - 2) = x^2 * x^4 * ... * x^(2^31)
+    let mut result = 1;
+    let mut square = x;
+    for _ in 1..GF32_BITS {
+        square = mul(square, square);
+        result = mul(result, square);
+    }
+    Some(result)
+}
+
+/// Returns `x * y`.
+///
+/// This runs in constant time.
+pub fn mul(x: GfElement32, y: GfElement32) -> GfElement32 {
+    let x = u64::from(x);
+    let mut product = 0;
+    for i in 0..GF32_BITS {
+        let mask = 0u64.wrapping_sub(u64::from(y >> i & 1));
+        product ^= (x << i) & mask;
+    }
+
+    // x^32 = x^22 + x^2 + x + 1
+    while product >> GF32_BITS != 0 {
+        let high = product >> GF32_BITS;
+        product = (product & 0xFFFF_FFFF) ^ high ^ (high << 1) ^ (high << 2) ^ (high << 22);
+    }
+    product as GfElement32
+}
+
+/// Returns evaluation point with given `index`, i.e. sum of
+/// [`cantor_basis`] elements for each bit set in `index`.
+///
+/// Points with indexes `0 .. 2^i` are closed under addition.
+pub fn point(index: u64) -> GfElement32 {
+    let basis = cantor_basis();
+    (0..GF32_BITS)
+        .filter(|&i| index >> i & 1 != 0)
+        .fold(0, |sum, i| sum ^ basis[i])
+}
+
+// ======================================================================
+// Engine32 - PUBLIC
+
+/// Implementation of basic low-level algorithms over `GF(2^32)`.
+///
+/// This is the `GF(2^32)` counterpart of [`Engine`]:
+/// shard sizes must be multiples of 4 bytes and multipliers are
+/// [`GfElement32`]:s themselves instead of logarithms.
+///
+/// [`Engine`]: crate::engine::Engine
+pub trait Engine32 {
+    /// In-place decimation-in-time FFT (fast Fourier transform).
+    ///
+    /// - FFT is done on chunk `data[pos .. pos + size]`
+    /// - `size` must be a power of two.
+    /// - Coefficients of `X_j` become values at points `offset + i`,
+    ///   so `offset` must be a multiple of `size`.
+    fn fft(&self, data: &mut ShardsRefMut, pos: usize, size: usize, offset: usize);
+
+    /// In-place decimation-in-time IFFT (inverse fast Fourier transform).
+    ///
+    /// This is the inverse of [`fft`](Engine32::fft) with the same arguments.
+    fn ifft(&self, data: &mut ShardsRefMut, pos: usize, size: usize, offset: usize);
+
+    /// `x[] *= y`
+    fn mul(&self, x: &mut [u8], y: GfElement32);
+
+    /// Formal derivative of polynomial in novel polynomial basis.
+    fn formal_derivative(data: &mut ShardsRefMut)
+    where
+        Self: Sized,
+    {
+        for i in 1..data.len() {
+            let width: usize = ((i ^ (i - 1)) + 1) >> 1;
+            for j in i - width..i {
+                let (x, y) = data.dist2_mut(j, width);
+                Self::xor(x, y);
+            }
+        }
+    }
+
+    /// `x[] ^= y[]`
+    fn xor(x: &mut [u8], y: &[u8])
+    where
+        Self: Sized,
+    {
+        for (x, y) in x.iter_mut().zip(y) {
+            *x ^= y;
+        }
+    }
+}
+
+// ======================================================================
+// Naive32 - PUBLIC
+
+/// Simple reference implementation of [`Engine32`].
+#[derive(Clone)]
+pub struct Naive32 {
+    tables: &'static Tables32,
+}
+
+impl Naive32 {
+    /// Creates new [`Naive32`], initializing the small
+    /// tables of the Cantor basis.
+    pub fn new() -> Self {
+        Self { tables: tables() }
+    }
+}
+
+impl Engine32 for Naive32 {
+    fn fft(&self, data: &mut ShardsRefMut, pos: usize, size: usize, offset: usize) {
+        debug_assert!(size.is_power_of_two());
+        debug_assert!(offset & (size - 1) == 0);
+
+        let mut layer = size.trailing_zeros() as usize;
+        while layer > 0 {
+            layer -= 1;
+            let dist = 1 << layer;
+            for r in (0..size).step_by(dist * 2) {
+                let skew = self.tables.skew(layer, offset + r);
+                for i in r..r + dist {
+                    let (a, b) = data.dist2_mut(pos + i, dist);
+
+                    // FFT BUTTERFLY
+
+                    Self::mul_add(a, b, skew);
+                    Self::xor(b, a);
+                }
+            }
+        }
+    }
+
+    fn ifft(&self, data: &mut ShardsRefMut, pos: usize, size: usize, offset: usize) {
+        debug_assert!(size.is_power_of_two());
+        debug_assert!(offset & (size - 1) == 0);
+
+        for layer in 0..size.trailing_zeros() as usize {
+            let dist = 1 << layer;
+            for r in (0..size).step_by(dist * 2) {
+                let skew = self.tables.skew(layer, offset + r);
+                for i in r..r + dist {
+                    let (a, b) = data.dist2_mut(pos + i, dist);
+
+                    // IFFT BUTTERFLY
+
+                    Self::xor(b, a);
+                    Self::mul_add(a, b, skew);
+                }
+            }
+        }
+    }
+
+    fn mul(&self, x: &mut [u8], y: GfElement32) {
+        debug_assert!(x.len() & 3 == 0);
+
+        for x in x.chunks_exact_mut(4) {
+            let product = mul(element(x), y);
+            x.copy_from_slice(&product.to_le_bytes());
+        }
+    }
+}
+
+// ======================================================================
+// Naive32 - IMPL Default
+
+impl Default for Naive32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ======================================================================
+// Naive32 - PRIVATE
+
+impl Naive32 {
+    /// `x[] ^= y[] * m`
+    fn mul_add(x: &mut [u8], y: &[u8], m: GfElement32) {
+        debug_assert_eq!(x.len(), y.len());
+
+        if m == 0 {
+            return;
+        }
+
+        for (x, y) in x.chunks_exact_mut(4).zip(y.chunks_exact(4)) {
+            let sum = element(x) ^ mul(element(y), m);
+            x.copy_from_slice(&sum.to_le_bytes());
+        }
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - CRATE
+
+// Returns little-endian element at start of `bytes`.
+pub(crate) fn element(bytes: &[u8]) -> GfElement32 {
+    GfElement32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+// Returns `Ŵ_layer(point(index))`, see `Tables32::skew`.
+pub(crate) fn skew(layer: usize, index: usize) -> GfElement32 {
+    tables().skew(layer, index)
+}
+
+// ======================================================================
+// Tables32 - PRIVATE
+
+struct Tables32 {
+    cantor_basis: [GfElement32; GF32_BITS],
+    // `w_hat[i][j]` is `Ŵ_i(cantor_basis[j])`.
+    w_hat: [[GfElement32; GF32_BITS]; GF32_BITS],
+}
+
+static TABLES32: OnceCell<Tables32> = OnceCell::new();
+
+impl Tables32 {
+    // Returns `Ŵ_layer(point(index))` which is linear in the point,
+    // so only bits of `index` above `layer` contribute.
+    fn skew(&self, layer: usize, index: usize) -> GfElement32 {
+        let mut bits = index;
+        let mut skew = 0;
+        while bits != 0 {
+            skew ^= self.w_hat[layer][bits.trailing_zeros() as usize];
+            bits &= bits - 1;
+        }
+        skew
+    }
+}
+
+fn tables() -> &'static Tables32 {
+    TABLES32.get_or_init(|| {
+        let mut cantor_basis = [1; GF32_BITS];
+        for i in 1..GF32_BITS {
+            cantor_basis[i] = solve_square_plus_self(cantor_basis[i - 1])
+                .expect("Cantor basis exists in GF(2^32)");
+        }
+
+        // W_0(x) = x
+        // W_{i+1}(x) = W_i(x) * (W_i(x) + W_i(cantor_basis[i]))
+        let mut w = cantor_basis;
+        let mut w_hat = [[0; GF32_BITS]; GF32_BITS];
+        for i in 0..GF32_BITS {
+            let w_i = w[i];
+            let w_i_inv = inv(w_i).unwrap();
+            w_hat[i] = w.map(|x| mul(x, w_i_inv));
+            w = w.map(|x| mul(x, x ^ w_i));
+        }
+
+        Tables32 {
+            cantor_basis,
+            w_hat,
+        }
+    })
+}
+
+// Returns `y` such that `y^2 + y = c` or `None` if there is no such `y`.
+//
+// `y -> y^2 + y` is linear over `GF(2)`, so this is solved by
+// Gaussian elimination with images of single bits.
+fn solve_square_plus_self(c: GfElement32) -> Option<GfElement32> {
+    // `basis[h]` is `(image, preimage)` with highest bit of `image` at `h`.
+    let mut basis = [(0, 0); GF32_BITS];
+    for i in 0..GF32_BITS {
+        let mut preimage: GfElement32 = 1 << i;
+        let mut image = mul(preimage, preimage) ^ preimage;
+        while image != 0 {
+            let high = (GF32_BITS - 1) - image.leading_zeros() as usize;
+            if basis[high].0 == 0 {
+                basis[high] = (image, preimage);
+                break;
+            }
+            image ^= basis[high].0;
+            preimage ^= basis[high].1;
+        }
+    }
+
+    let mut rest = c;
+    let mut y = 0;
+    while rest != 0 {
+        let high = (GF32_BITS - 1) - rest.leading_zeros() as usize;
+        if basis[high].0 == 0 {
+            return None;
+        }
+        rest ^= basis[high].0;
+        y ^= basis[high].1;
+    }
+    Some(y)
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use super::*;
+
+    // Evaluates polynomial in novel polynomial basis at `x` directly.
+    fn eval_novel(coefs: &[GfElement32], x: GfElement32) -> GfElement32 {
+        let tables = tables();
+        coefs.iter().enumerate().fold(0, |sum, (j, &coef)| {
+            let basis = (0..GF32_BITS)
+                .filter(|&i| j >> i & 1 != 0)
+                .fold(1, |product, i| {
+                    // Ŵ_i(x) = W_i(x) / W_i(cantor_basis[i])
+                    let w = (0..1u64 << i).fold(1, |w, u| mul(w, x ^ point(u)));
+                    let norm =
+                        (0..1u64 << i).fold(1, |w, u| mul(w, tables.cantor_basis[i] ^ point(u)));
+                    mul(product, mul(w, inv(norm).unwrap()))
+                });
+            sum ^ mul(coef, basis)
+        })
+    }
+
+    fn to_bytes(elements: &[GfElement32]) -> Vec<u8> {
+        elements.iter().flat_map(|x| x.to_le_bytes()).collect()
+    }
+
+    fn to_elements(bytes: &[u8]) -> Vec<GfElement32> {
+        bytes.chunks_exact(4).map(element).collect()
+    }
+
+    // ============================================================
+    // FIELD
+
+    #[test]
+    fn polynomial_is_irreducible() {
+        // Polynomial of degree 32 without repeated factors is irreducible
+        // iff x^(2^32) = x and x^(2^16) != x, as every proper subfield
+        // of GF(2^32) is within GF(2^16).
+        let mut x = 2;
+        for i in 1..=GF32_BITS {
+            x = mul(x, x);
+            if i == 16 {
+                assert_ne!(x, 2);
+            }
+        }
+        assert_eq!(x, 2);
+    }
+
+    #[test]
+    fn inv_and_mul() {
+        assert_eq!(inv(0), None);
+        for x in [1, 2, 3, 0x1234_5678, 0xFFFF_FFFF, 0x8000_0000] {
+            assert_eq!(mul(x, inv(x).unwrap()), 1);
+            assert_eq!(mul(x, 1), x);
+            assert_eq!(mul(x, 0x9ABC_DEF0), mul(0x9ABC_DEF0, x));
+        }
+    }
+
+    #[test]
+    fn cantor_basis_is_basis() {
+        let basis = cantor_basis();
+        for i in 1..GF32_BITS {
+            assert_eq!(mul(basis[i], basis[i]) ^ basis[i], basis[i - 1]);
+        }
+
+        // Linearly independent iff all 2^32 points are distinct,
+        // which holds iff rank over GF(2) is 32.
+        let mut reduced: Vec<GfElement32> = Vec::new();
+        for &b in basis {
+            let x = reduced.iter().fold(b, |x, &r| x.min(x ^ r));
+            assert_ne!(x, 0);
+            reduced.push(x);
+        }
+    }
+
+    #[test]
+    fn derivative_of_w_hat_is_one() {
+        // Ŵ_i' is linear coefficient of W_i divided by W_i(cantor_basis[i]).
+        // `Engine32::formal_derivative` only uses `XOR`
+        // so it requires these to be `1`.
+        let mut w = *cantor_basis();
+        let mut linear = 1;
+        for i in 0..GF32_BITS {
+            assert_eq!(linear, w[i]);
+
+            // Linear coefficient of W_i(x)^2 is zero.
+            let w_i = w[i];
+            linear = mul(linear, w_i);
+            w = w.map(|x| mul(x, x ^ w_i));
+        }
+    }
+
+    // ============================================================
+    // Naive32
+
+    #[test]
+    fn fft_matches_direct_evaluation() {
+        let engine = Naive32::new();
+        let coefs: Vec<GfElement32> = (0..8u32).map(|i| i.wrapping_mul(0x9E37_79B9)).collect();
+
+        for offset in [0, 8, 1 << 20, (1 << 31) + 8] {
+            let mut bytes = to_bytes(&coefs);
+            engine.fft(&mut ShardsRefMut::new(8, 4, &mut bytes), 0, 8, offset);
+            let values = to_elements(&bytes);
+
+            for (i, &value) in values.iter().enumerate() {
+                let x = point((offset + i) as u64);
+                assert_eq!(value, eval_novel(&coefs, x));
+            }
+
+            engine.ifft(&mut ShardsRefMut::new(8, 4, &mut bytes), 0, 8, offset);
+            assert_eq!(to_elements(&bytes), coefs);
+        }
+    }
+
+    #[test]
+    fn formal_derivative() {
+        // Derivative of product `f * g` at roots of `g` is `f * g'`,
+        // so derivative of `f * (x + point(5))` at `point(5)` is `f(point(5))`.
+        let engine = Naive32::new();
+        let f = [7, 11, 13, 0];
+
+        // (x + point(5)) = X_1 + point(5) * X_0
+        let g = [point(5), 1, 0, 0];
+
+        let mut fb = to_bytes(&f);
+        let mut gb = to_bytes(&g);
+        engine.fft(&mut ShardsRefMut::new(4, 4, &mut fb), 0, 4, 4);
+        engine.fft(&mut ShardsRefMut::new(4, 4, &mut gb), 0, 4, 4);
+        let product: Vec<GfElement32> = to_elements(&fb)
+            .iter()
+            .zip(to_elements(&gb))
+            .map(|(&x, y)| mul(x, y))
+            .collect();
+
+        let mut pb = to_bytes(&product);
+        engine.ifft(&mut ShardsRefMut::new(4, 4, &mut pb), 0, 4, 4);
+        Naive32::formal_derivative(&mut ShardsRefMut::new(4, 4, &mut pb));
+        engine.fft(&mut ShardsRefMut::new(4, 4, &mut pb), 0, 4, 4);
+
+        assert_eq!(to_elements(&pb)[1], eval_novel(&f, point(5)));
+    }
+}
@@ -0,0 +1,235 @@
+//! Galois field `GF(2^16)` arithmetic as ordinary Rust operators.
+//!
+//! **This is an advanced module which is not needed for [simple usage] or [basic usage].**
+//!
+//! [`Gf16`] is meant for those who want to build custom codes on top of
+//! the same field this crate uses. Internally the [`Engine`]:s work on
+//! raw [`GfElement`]:s and lookup-tables instead.
+//!
+//! Elements are represented in the same Cantor basis
+//! which [`Engine`]:s use, so e.g. multiplication by [`Gf16`]
+//! matches [`Engine::mul`] with the logarithm of the multiplier.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use reed_solomon_simd::gf::Gf16;
+//!
+//! let a = Gf16(0x1234);
+//! let b = Gf16(0xABCD);
+//!
+//! assert_eq!(a + b - b, a);
+//! assert_eq!(a * b * b.inv().unwrap(), a);
+//! assert_eq!(a * Gf16::ONE, a);
+//! assert_eq!(a * Gf16::ZERO, Gf16::ZERO);
+//! ```
+//!
+//! [simple usage]: crate#simple-usage
+//! [basic usage]: crate#basic-usage
+//! [`Engine`]: crate::engine::Engine
+//! [`Engine::mul`]: crate::engine::Engine::mul
+//! [`GfElement`]: crate::engine::GfElement
+
+use std::ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign};
+
+use crate::engine::{tables, GfElement, GF_MODULUS};
+
+// ======================================================================
+// Gf16 - PUBLIC
+
+/// Element of Galois field `GF(2^16)`.
+///
+/// - Addition and subtraction are both `XOR`.
+/// - Multiplication uses [`Exp`] and [`Log`] tables.
+///
+/// [`Exp`]: crate::engine::tables::Exp
+/// [`Log`]: crate::engine::tables::Log
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Gf16(pub GfElement);
+
+impl Gf16 {
+    /// Additive identity.
+    pub const ZERO: Gf16 = Gf16(0);
+
+    /// Multiplicative identity.
+    pub const ONE: Gf16 = Gf16(1);
+
+    /// Returns multiplicative inverse of this element
+    /// or `None` if this is [`Gf16::ZERO`].
+    pub fn inv(self) -> Option<Gf16> {
+        if self.0 == 0 {
+            None
+        } else {
+            let (exp, log) = tables::initialize_exp_log();
+            Some(Gf16(exp[(GF_MODULUS - log[self.0 as usize]) as usize]))
+        }
+    }
+
+    /// Returns logarithm of this element
+    /// or `None` if this is [`Gf16::ZERO`].
+    ///
+    /// This is the form of multiplier taken by [`Engine::mul`].
+    ///
+    /// [`Engine::mul`]: crate::engine::Engine::mul
+    pub fn log(self) -> Option<GfElement> {
+        if self.0 == 0 {
+            None
+        } else {
+            let (_, log) = tables::initialize_exp_log();
+            Some(log[self.0 as usize])
+        }
+    }
+}
+
+// ======================================================================
+// Gf16 - IMPL From
+
+impl From<GfElement> for Gf16 {
+    fn from(x: GfElement) -> Self {
+        Gf16(x)
+    }
+}
+
+impl From<Gf16> for GfElement {
+    fn from(x: Gf16) -> Self {
+        x.0
+    }
+}
+
+// ======================================================================
+// Gf16 - IMPL Add
+
+impl Add for Gf16 {
+    type Output = Gf16;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: Gf16) -> Gf16 {
+        Gf16(self.0 ^ rhs.0)
+    }
+}
+
+impl AddAssign for Gf16 {
+    fn add_assign(&mut self, rhs: Gf16) {
+        *self = *self + rhs;
+    }
+}
+
+// ======================================================================
+// Gf16 - IMPL Sub
+
+impl Sub for Gf16 {
+    type Output = Gf16;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: Gf16) -> Gf16 {
+        Gf16(self.0 ^ rhs.0)
+    }
+}
+
+impl SubAssign for Gf16 {
+    fn sub_assign(&mut self, rhs: Gf16) {
+        *self = *self - rhs;
+    }
+}
+
+// ======================================================================
+// Gf16 - IMPL Mul
+
+impl Mul for Gf16 {
+    type Output = Gf16;
+    fn mul(self, rhs: Gf16) -> Gf16 {
+        match rhs.log() {
+            Some(log_m) => {
+                let (exp, log) = tables::initialize_exp_log();
+                Gf16(tables::mul(self.0, log_m, exp, log))
+            }
+            None => Gf16::ZERO,
+        }
+    }
+}
+
+impl MulAssign for Gf16 {
+    fn mul_assign(&mut self, rhs: Gf16) {
+        *self = *self * rhs;
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn random_elements(count: usize) -> Vec<Gf16> {
+        let mut rng = ChaCha8Rng::from_seed([154; 32]);
+        (0..count).map(|_| Gf16(rng.gen())).collect()
+    }
+
+    #[test]
+    fn identities() {
+        for a in random_elements(1000) {
+            assert_eq!(a + Gf16::ZERO, a);
+            assert_eq!(a - Gf16::ZERO, a);
+            assert_eq!(a * Gf16::ONE, a);
+            assert_eq!(a * Gf16::ZERO, Gf16::ZERO);
+            assert_eq!(Gf16::ZERO * a, Gf16::ZERO);
+        }
+    }
+
+    #[test]
+    fn inverses() {
+        assert_eq!(Gf16::ZERO.inv(), None);
+        assert_eq!(Gf16::ONE.inv(), Some(Gf16::ONE));
+
+        for a in random_elements(1000) {
+            assert_eq!(a + a, Gf16::ZERO);
+            assert_eq!(a - a, Gf16::ZERO);
+            if a != Gf16::ZERO {
+                assert_eq!(a * a.inv().unwrap(), Gf16::ONE);
+            }
+        }
+    }
+
+    #[test]
+    fn commutativity() {
+        let x = random_elements(2000);
+        for (a, b) in x[..1000].iter().zip(&x[1000..]) {
+            assert_eq!(*a + *b, *b + *a);
+            assert_eq!(*a * *b, *b * *a);
+        }
+    }
+
+    #[test]
+    fn associativity() {
+        let x = random_elements(3000);
+        for ((a, b), c) in x[..1000].iter().zip(&x[1000..2000]).zip(&x[2000..]) {
+            assert_eq!((*a + *b) + *c, *a + (*b + *c));
+            assert_eq!((*a * *b) * *c, *a * (*b * *c));
+        }
+    }
+
+    #[test]
+    fn distributivity() {
+        let x = random_elements(3000);
+        for ((a, b), c) in x[..1000].iter().zip(&x[1000..2000]).zip(&x[2000..]) {
+            assert_eq!(*a * (*b + *c), *a * *b + *a * *c);
+            assert_eq!((*a + *b) * *c, *a * *c + *b * *c);
+        }
+    }
+
+    #[test]
+    fn assign_ops() {
+        let x = random_elements(2);
+        let (a, b) = (x[0], x[1]);
+
+        let mut y = a;
+        y += b;
+        assert_eq!(y, a + b);
+        y -= b;
+        assert_eq!(y, a);
+        y *= b;
+        assert_eq!(y, a * b);
+    }
+}
@@ -0,0 +1,372 @@
+//! Optional integrity footer which catches encoder/decoder configuration mismatches.
+//!
+//! **This is an advanced module which is not needed for [simple usage] or [basic usage].**
+//!
+//! Decoder which is configured differently than the encoder was,
+//! e.g. with different `shard_bytes`, can't notice that by itself
+//! and restores garbage. With integrity footer enabled by
+//! [`ReedSolomonEncoder::with_integrity_footer`] and
+//! [`ReedSolomonDecoder::with_integrity_footer`]
+//! the encoder embeds an [`IntegrityFooter`] into each recovery shard
+//! and the decoder validates it when recovery shard is added,
+//! returning [`Error::ParameterMismatch`] if it doesn't match.
+//!
+//! # Stripe id
+//!
+//! The footer also contains caller-chosen `stripe_id`, e.g. a sequence number,
+//! which is checked against the one given to the decoder. Recovery shard with
+//! matching configuration but different `stripe_id` comes from another stripe,
+//! which decoder rejects with [`Error::StripeMismatch`] instead of restoring garbage
+//! from a mixture of stripes.
+//!
+//! # Reserved bytes
+//!
+//! The footer takes its space from the caller's shards:
+//!
+//! - Last [`RESERVED_BYTES`] bytes of each original shard are reserved
+//!   and must be zero, so encoder returns [`Error::NonZeroReservedBytes`]
+//!   otherwise. Restored original shards have these bytes zeroed.
+//! - Last [`FOOTER_BYTES`] bytes of each recovery shard contain the footer.
+//!
+//! Whole 64 bytes are reserved because shards are processed in blocks of 64 bytes
+//! in which each 2-byte element is split into two halves 32 bytes apart.
+//!
+//! # Footer format
+//!
+//! | Bytes    | Content                             |
+//! | -------- | ----------------------------------- |
+//! | `0..2`   | Magic `b"RS"`                       |
+//! | `2`      | Version, currently `2`              |
+//! | `3`      | Zero                                |
+//! | `4..6`   | `original_count` as `u16`           |
+//! | `6..8`   | `recovery_count` as `u16`           |
+//! | `8..12`  | `shard_bytes / 64` as `u32`         |
+//! | `12..20` | `stripe_id` as `u64`                |
+//! | `20..24` | Zero                                |
+//!
+//! All integers are little-endian.
+//!
+//! Version `1` was 16 bytes with `stripe_id` as `u32`. Widening `stripe_id`
+//! to `u64` doesn't fit in 16 bytes next to the configuration, so version `2`
+//! is 24 bytes. Decoder doesn't accept version `1` footers, so recovery shards
+//! must be encoded and decoded with the same footer version.
+//!
+//! # Stripe hash
+//!
+//! **This requires `stripe-hash` feature.**
+//!
+//! Independently of the footer, `stripe_hash` is a SHA-256 hash over
+//! all original shards of a stripe, in order of their indexes.
+//! It's computed by `ReedSolomonEncoder::encode_with_stripe_hash` and
+//! `DecoderResult::integrity_hash` of a decoder created with
+//! `ReedSolomonDecoder::with_stripe_hash`, so comparing the two confirms
+//! that the whole stripe survived encoding, shard loss and decoding.
+//!
+//! This is orthogonal to the mathematical guarantees of the codec, which
+//! restores original shards exactly only if all given shards are intact.
+//! Stripe hash detects that some given shard was corrupted, or came
+//! from another stripe, but not which one. It isn't keyed, so it doesn't
+//! authenticate the shards against deliberate tampering.
+//!
+//! The hash is `SHA-256(SHA-256(original_0) || SHA-256(original_1) || ...)`,
+//! so that the decoder can hash given original shards before decoding,
+//! which overwrites them, and restored original shards afterwards.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+//! use reed_solomon_simd::{integrity::IntegrityFooter, Error};
+//! use reed_solomon_simd::{CodecParams, ReedSolomonDecoder, ReedSolomonEncoder};
+//!
+//! // Payload in first 64 bytes, last 64 bytes reserved.
+//! let mut original = [[0u8; 128]; 2];
+//! original[0][..64].fill(1);
+//! original[1][..64].fill(2);
+//!
+//! let mut encoder = ReedSolomonEncoder::new(2, 1, 128)?.with_integrity_footer(7);
+//! for shard in original {
+//!     encoder.add_original_shard(shard)?;
+//! }
+//! let recovery: Vec<_> = encoder.encode()?.recovery_iter().map(|s| s.to_vec()).collect();
+//!
+//! // Decoder configured with wrong `shard_bytes`.
+//! let mut decoder = ReedSolomonDecoder::new(2, 1, 192)?.with_integrity_footer(7);
+//! assert_eq!(
+//!     decoder.add_recovery_shard(0, &recovery[0]),
+//!     Err(Error::ParameterMismatch {
+//!         index: 0,
+//!         expected: IntegrityFooter::new(CodecParams::new(2, 1, 192), 7),
+//!         got: Some(IntegrityFooter::new(CodecParams::new(2, 1, 128), 7)),
+//!     })
+//! );
+//!
+//! // Decoder configured with wrong `stripe_id`.
+//! let mut decoder = ReedSolomonDecoder::new(2, 1, 128)?.with_integrity_footer(8);
+//! assert_eq!(
+//!     decoder.add_recovery_shard(0, &recovery[0]),
+//!     Err(Error::StripeMismatch {
+//!         index: 0,
+//!         expected: 8,
+//!         got: 7,
+//!     })
+//! );
+//!
+//! let mut decoder = ReedSolomonDecoder::new(2, 1, 128)?.with_integrity_footer(7);
+//! decoder.add_original_shard(1, original[1])?;
+//! decoder.add_recovery_shard(0, &recovery[0])?;
+//! let result = decoder.decode()?;
+//! assert_eq!(result.restored_original(0).unwrap(), original[0]);
+//! # }
+//! # Ok::<(), reed_solomon_simd::Error>(())
+//! ```
+//!
+//! [simple usage]: crate#simple-usage
+//! [basic usage]: crate#basic-usage
+//! [`ReedSolomonEncoder::with_integrity_footer`]: crate::ReedSolomonEncoder::with_integrity_footer
+//! [`ReedSolomonDecoder::with_integrity_footer`]: crate::ReedSolomonDecoder::with_integrity_footer
+//! [`Error::ParameterMismatch`]: crate::Error::ParameterMismatch
+//! [`Error::StripeMismatch`]: crate::Error::StripeMismatch
+//! [`Error::NonZeroReservedBytes`]: crate::Error::NonZeroReservedBytes
+
+use std::fmt;
+
+#[cfg(feature = "stripe-hash")]
+use sha2::{Digest, Sha256};
+
+use crate::CodecParams;
+#[cfg(feature = "encoder")]
+use crate::EncoderResult;
+#[cfg(feature = "decoder")]
+use crate::Error;
+
+// ======================================================================
+// CONST - PUBLIC
+
+/// Size of the footer at the end of each recovery shard.
+pub const FOOTER_BYTES: usize = 24;
+
+/// Number of bytes reserved at the end of each shard.
+pub const RESERVED_BYTES: usize = 64;
+
+// ======================================================================
+// CONST - PRIVATE
+
+const MAGIC: [u8; 2] = *b"RS";
+const VERSION: u8 = 2;
+
+// ======================================================================
+// IntegrityFooter - PUBLIC
+
+/// Encoder configuration and stripe id embedded into recovery shards.
+///
+/// See [module documentation](self) for details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IntegrityFooter {
+    /// Configuration of the encoder.
+    pub params: CodecParams,
+    /// Caller-chosen id of the stripe, e.g. a sequence number.
+    pub stripe_id: u64,
+}
+
+impl IntegrityFooter {
+    /// Parses footer from given bytes.
+    ///
+    /// Returns `None` if bytes don't start with the magic and
+    /// supported version, e.g. because they are not a footer at all.
+    pub fn from_bytes(bytes: &[u8; FOOTER_BYTES]) -> Option<Self> {
+        if bytes[0..2] != MAGIC || bytes[2] != VERSION || bytes[3] != 0 || bytes[20..24] != [0; 4] {
+            return None;
+        }
+
+        let u16_at = |i: usize| usize::from(u16::from_le_bytes([bytes[i], bytes[i + 1]]));
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let u64_at = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+
+        Some(Self {
+            params: CodecParams::new(u16_at(4), u16_at(6), u32_at(8) as usize * 64),
+            stripe_id: u64_at(12),
+        })
+    }
+
+    /// Parses footer from the last [`FOOTER_BYTES`] bytes of given recovery shard.
+    ///
+    /// Returns `None` if shard is too short or doesn't contain a footer.
+    pub fn from_shard(shard: &[u8]) -> Option<Self> {
+        let start = shard.len().checked_sub(FOOTER_BYTES)?;
+        Self::from_bytes(shard[start..].try_into().unwrap())
+    }
+
+    /// Creates new [`IntegrityFooter`].
+    pub fn new(params: CodecParams, stripe_id: u64) -> Self {
+        Self { params, stripe_id }
+    }
+
+    /// Returns the footer as bytes.
+    ///
+    /// Counts and `shard_bytes` are truncated if they don't fit
+    /// the format, which never happens with supported configurations.
+    pub fn to_bytes(&self) -> [u8; FOOTER_BYTES] {
+        let mut bytes = [0u8; FOOTER_BYTES];
+        bytes[0..2].copy_from_slice(&MAGIC);
+        bytes[2] = VERSION;
+        bytes[4..6].copy_from_slice(&(self.params.original_count as u16).to_le_bytes());
+        bytes[6..8].copy_from_slice(&(self.params.recovery_count as u16).to_le_bytes());
+        bytes[8..12].copy_from_slice(&((self.params.shard_bytes / 64) as u32).to_le_bytes());
+        bytes[12..20].copy_from_slice(&self.stripe_id.to_le_bytes());
+        bytes
+    }
+}
+
+// ======================================================================
+// IntegrityFooter - IMPL DISPLAY
+
+impl fmt::Display for IntegrityFooter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} original + {} recovery shards of {} bytes in stripe {}",
+            self.params.original_count,
+            self.params.recovery_count,
+            self.params.shard_bytes,
+            self.stripe_id
+        )
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PUBLIC
+
+/// Returns SHA-256 hash over all original shards of a stripe,
+/// see [Stripe hash](self#stripe-hash).
+///
+/// **This function is only available with `stripe-hash` feature.**
+///
+/// # Examples
+///
+/// ```rust
+/// use reed_solomon_simd::{integrity, ReedSolomonDecoder, ReedSolomonEncoder};
+///
+/// let original = [[1u8; 64], [2u8; 64]];
+///
+/// let mut encoder = ReedSolomonEncoder::new(2, 1, 64)?;
+/// for shard in original {
+///     encoder.add_original_shard(shard)?;
+/// }
+/// let (result, hash) = encoder.encode_with_stripe_hash()?;
+/// let recovery = result.recovery(0).unwrap().to_vec();
+/// assert_eq!(hash, integrity::stripe_hash(original));
+///
+/// let mut decoder = ReedSolomonDecoder::new(2, 1, 64)?.with_stripe_hash();
+/// decoder.add_original_shard(1, original[1])?;
+/// decoder.add_recovery_shard(0, &recovery)?;
+/// let result = decoder.decode()?;
+/// assert_eq!(result.integrity_hash(), Some(hash));
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+#[cfg(feature = "stripe-hash")]
+pub fn stripe_hash<T: AsRef<[u8]>>(original: impl IntoIterator<Item = T>) -> [u8; 32] {
+    stripe_hash_from_digests(
+        original
+            .into_iter()
+            .map(|shard| shard_digest(shard.as_ref())),
+    )
+}
+
+// ======================================================================
+// FUNCTIONS - CRATE
+
+// Returns `true` if reserved bytes at the end of `shard` are all zero.
+// - Doesn't short-circuit so that this vectorizes.
+#[cfg(feature = "encoder")]
+pub(crate) fn is_reserved_zero(shard: &[u8]) -> bool {
+    let start = shard.len().saturating_sub(RESERVED_BYTES);
+    shard[start..].iter().fold(0, |acc, &byte| acc | byte) == 0
+}
+
+// Returns `Ok(())` if recovery shard `index` has footer equal to `expected`.
+// - Footer which differs only in `stripe_id` is reported as `StripeMismatch`.
+#[cfg(feature = "decoder")]
+pub(crate) fn check_footer(
+    expected: IntegrityFooter,
+    index: usize,
+    shard: &[u8],
+) -> Result<(), Error> {
+    let got = IntegrityFooter::from_shard(shard);
+    match got {
+        Some(got) if got == expected => Ok(()),
+        Some(got) if got.params == expected.params => Error::StripeMismatch {
+            index,
+            expected: expected.stripe_id,
+            got: got.stripe_id,
+        }
+        .into_cold_err(),
+        _ => Error::ParameterMismatch {
+            index,
+            expected,
+            got,
+        }
+        .into_cold_err(),
+    }
+}
+
+// Writes `footer` into the last `FOOTER_BYTES` bytes of each recovery shard.
+#[cfg(feature = "encoder")]
+pub(crate) fn write_footer(footer: IntegrityFooter, result: &mut EncoderResult) {
+    let bytes = footer.to_bytes();
+    let mut index = 0;
+    while let Some(recovery) = result.recovery_mut(index) {
+        let start = recovery.len() - FOOTER_BYTES;
+        recovery[start..].copy_from_slice(&bytes);
+        index += 1;
+    }
+}
+
+// SHA-256 of a single original shard.
+#[cfg(feature = "stripe-hash")]
+pub(crate) fn shard_digest(shard: &[u8]) -> [u8; 32] {
+    Sha256::digest(shard).into()
+}
+
+// Stripe hash from `shard_digest` of each original shard, in order.
+#[cfg(feature = "stripe-hash")]
+pub(crate) fn stripe_hash_from_digests(digests: impl Iterator<Item = [u8; 32]>) -> [u8; 32] {
+    let mut sha = Sha256::new();
+    for digest in digests {
+        sha.update(digest);
+    }
+    sha.finalize().into()
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let footer = IntegrityFooter::new(CodecParams::new(65535, 1, 1 << 30), u64::MAX - 1);
+        assert_eq!(
+            IntegrityFooter::from_bytes(&footer.to_bytes()),
+            Some(footer)
+        );
+    }
+
+    #[test]
+    fn from_shard_without_footer() {
+        assert_eq!(IntegrityFooter::from_shard(&[0u8; 64]), None);
+        assert_eq!(IntegrityFooter::from_shard(&[0u8; 8]), None);
+
+        let mut bytes = IntegrityFooter::new(CodecParams::new(2, 1, 64), 0).to_bytes();
+        bytes[2] = VERSION + 1;
+        assert_eq!(IntegrityFooter::from_bytes(&bytes), None);
+        bytes[2] = 1;
+        assert_eq!(IntegrityFooter::from_bytes(&bytes), None);
+
+        let mut bytes = IntegrityFooter::new(CodecParams::new(2, 1, 64), 0).to_bytes();
+        bytes[23] = 1;
+        assert_eq!(IntegrityFooter::from_bytes(&bytes), None);
+    }
+}
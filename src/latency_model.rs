@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use crate::CodecParams;
+
+// ======================================================================
+// LatencyModel - PUBLIC
+
+/// Predicts decoding time from earlier measurements.
+///
+/// Samples are recorded e.g. from `ReedSolomonDecoder::decode_timed`
+/// and predictions are interpolated linearly by
+/// `original_count * shard_bytes`, which decoding time mostly scales with.
+///
+/// - Samples with same `original_count * shard_bytes` are averaged.
+/// - Predictions never decrease as `original_count * shard_bytes` grows,
+///   so a noisy sample can't make larger decodes look cheaper.
+/// - Outside of recorded range predictions are proportional
+///   to the nearest sample.
+///
+/// This is only as good as the samples, which should come from
+/// the same machine and similar load as the predicted decodes.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use reed_solomon_simd::{CodecParams, LatencyModel};
+///
+/// let mut model = LatencyModel::new();
+///
+/// // E.g. `timings.total()` from `ReedSolomonDecoder::decode_timed`.
+/// let elapsed = Duration::from_micros(20);
+/// model.record(CodecParams::new(2, 1, 64), elapsed);
+///
+/// let budget = model.predict(CodecParams::new(20, 10, 64)).unwrap();
+/// assert!(budget >= elapsed);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LatencyModel {
+    // `(work, total elapsed, sample count)` ordered by `work`,
+    // where `work` is `original_count * shard_bytes`.
+    samples: Vec<(u64, Duration, u32)>,
+}
+
+impl LatencyModel {
+    /// Creates new [`LatencyModel`] without samples.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns predicted decoding time with given configuration
+    /// or `None` if no samples have been recorded.
+    pub fn predict(&self, params: CodecParams) -> Option<Duration> {
+        let work = Self::work(params);
+
+        let mut previous: Option<(u64, Duration)> = None;
+        for (sample_work, elapsed) in self.averages() {
+            if work <= sample_work {
+                return Some(match previous {
+                    None => scale(elapsed, work, sample_work),
+                    Some((previous_work, previous_elapsed)) => {
+                        let t =
+                            (work - previous_work) as f64 / (sample_work - previous_work) as f64;
+                        previous_elapsed + (elapsed - previous_elapsed).mul_f64(t)
+                    }
+                });
+            }
+            previous = Some((sample_work, elapsed));
+        }
+
+        previous.map(|(last_work, last_elapsed)| scale(last_elapsed, work, last_work))
+    }
+
+    /// Records that decoding with given configuration took `elapsed`.
+    pub fn record(&mut self, params: CodecParams, elapsed: Duration) {
+        let work = Self::work(params);
+        match self.samples.binary_search_by_key(&work, |sample| sample.0) {
+            Ok(i) => {
+                self.samples[i].1 += elapsed;
+                self.samples[i].2 += 1;
+            }
+            Err(i) => self.samples.insert(i, (work, elapsed, 1)),
+        }
+    }
+
+    /// Returns number of recorded samples.
+    pub fn sample_count(&self) -> usize {
+        self.samples.iter().map(|sample| sample.2 as usize).sum()
+    }
+}
+
+// ======================================================================
+// LatencyModel - PRIVATE
+
+impl LatencyModel {
+    // Averages of samples ordered by work,
+    // raised to running maximum so that these don't decrease.
+    fn averages(&self) -> impl Iterator<Item = (u64, Duration)> + '_ {
+        let mut max = Duration::ZERO;
+        self.samples.iter().map(move |&(work, elapsed, count)| {
+            max = max.max(elapsed / count);
+            (work, max)
+        })
+    }
+
+    fn work(params: CodecParams) -> u64 {
+        params.original_count as u64 * params.shard_bytes as u64
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+// `elapsed * work / sample_work`
+fn scale(elapsed: Duration, work: u64, sample_work: u64) -> Duration {
+    if sample_work == 0 {
+        elapsed
+    } else {
+        elapsed.mul_f64(work as f64 / sample_work as f64)
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use super::*;
+
+    fn params(original_count: usize) -> CodecParams {
+        CodecParams::new(original_count, 10, 1024)
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(LatencyModel::new().predict(params(100)), None);
+    }
+
+    #[test]
+    fn interpolation() {
+        let mut model = LatencyModel::new();
+        model.record(params(100), Duration::from_micros(100));
+        model.record(params(300), Duration::from_micros(250));
+        model.record(params(300), Duration::from_micros(350));
+        assert_eq!(model.sample_count(), 3);
+
+        let predict = |original_count| model.predict(params(original_count)).unwrap();
+
+        assert_eq!(predict(100), Duration::from_micros(100));
+        assert_eq!(predict(200), Duration::from_micros(200));
+        assert_eq!(predict(300), Duration::from_micros(300));
+        // proportional outside of recorded range
+        assert_eq!(predict(50), Duration::from_micros(50));
+        assert_eq!(predict(600), Duration::from_micros(600));
+    }
+
+    #[test]
+    fn monotonic() {
+        let mut model = LatencyModel::new();
+        // Synthetic samples, roughly `n log n` with noise.
+        for (original_count, micros) in [
+            (1000, 900),
+            (100, 60),
+            (2000, 2100),
+            (500, 450),
+            (700, 400),
+            (4000, 4000),
+            (3000, 4500),
+        ] {
+            model.record(params(original_count), Duration::from_micros(micros));
+        }
+
+        let mut previous = Duration::ZERO;
+        for original_count in (0..6000).step_by(10) {
+            let predicted = model.predict(params(original_count)).unwrap();
+            assert!(predicted >= previous, "{}", original_count);
+            previous = predicted;
+        }
+    }
+}
@@ -1,27 +1,111 @@
 #![doc = include_str!(concat!(env!("OUT_DIR"), "/README-rustdocified.md"))]
 #![deny(missing_docs)]
+#![cfg_attr(feature = "allocator-api", feature(allocator_api))]
+#![cfg_attr(feature = "portable-simd", feature(portable_simd))]
+
+#[cfg(not(any(feature = "encoder", feature = "decoder")))]
+compile_error!("at least one of features `encoder` and `decoder` must be enabled");
+
+#[cfg(feature = "decoder")]
+use std::collections::HashMap;
+use std::fmt;
+
+#[cfg(any(feature = "encoder", feature = "decoder"))]
+use crate::engine::Engine;
+#[cfg(feature = "decoder")]
+use crate::rate::{DefaultRateDecoder, RateDecoder};
+#[cfg(feature = "encoder")]
+use crate::rate::{DefaultRateEncoder, RateEncoder};
+use crate::{
+    engine::DefaultEngine,
+    integrity::IntegrityFooter,
+    rate::{DefaultRate, Rate, RateKind},
+};
 
-use std::{collections::HashMap, fmt};
-
 pub use crate::{
-    decoder_result::{DecoderResult, RestoredOriginal},
+    codec_params::CodecParams, latency_model::LatencyModel, phase_timings::PhaseTimings,
+};
+#[cfg(feature = "decoder")]
+pub use crate::{
+    decoder_result::{DecoderResult, OwnedDecoderResult, RestoredOriginal},
+    reed_solomon::ReedSolomonDecoder,
+    shard_writer::ShardWriter,
+};
+#[cfg(feature = "encoder")]
+pub use crate::{
     encoder_result::{EncoderResult, Recovery},
-    reed_solomon::{ReedSolomonDecoder, ReedSolomonEncoder},
+    reed_solomon::ReedSolomonEncoder,
 };
 
-#[cfg(test)]
+#[cfg(feature = "unstable-gf32")]
+pub use crate::reed_solomon32::{ReedSolomonDecoder32, ReedSolomonEncoder32};
+
+#[cfg(all(test, feature = "encoder", feature = "decoder"))]
 #[macro_use]
 mod test_util;
+#[macro_use]
+mod trace;
 
+mod codec_params;
+#[cfg(feature = "decoder")]
 mod decoder_result;
+#[cfg(feature = "encoder")]
 mod encoder_result;
+mod latency_model;
+mod phase_timings;
 mod reed_solomon;
+#[cfg(feature = "unstable-gf32")]
+mod reed_solomon32;
+#[cfg(feature = "decoder")]
+mod shard_writer;
 
 pub mod algorithm {
     #![doc = include_str!("algorithm.md")]
 }
 pub mod engine;
+#[cfg(feature = "unstable-gf32")]
+pub mod engine32;
+pub mod gf;
+pub mod integrity;
+#[cfg(feature = "encoder")]
+pub mod matrix;
 pub mod rate;
+#[cfg(feature = "encoder")]
+pub mod recovery_only;
+pub mod repair;
+#[cfg(feature = "encoder")]
+pub mod small;
+pub mod stream;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+
+// ======================================================================
+// CONST - PUBLIC
+
+/// Identifier of the coding scheme used by [`ReedSolomonEncoder`]
+/// and [`ReedSolomonDecoder`]:
+/// FFT-based Reed-Solomon over `GF(2^16)` in Cantor basis,
+/// with high or low rate chosen as in [`DefaultRate`].
+///
+/// This changes if and only if shards produced from the same input
+/// would change, so stored shards can be decoded by any version
+/// of this crate with the same identifier. Changing this is
+/// a semver-major change.
+///
+/// See also [`HighRate::SCHEME_ID`] and [`LowRate::SCHEME_ID`].
+///
+/// [`DefaultRate`]: crate::rate::DefaultRate
+/// [`HighRate::SCHEME_ID`]: crate::rate::HighRate::SCHEME_ID
+/// [`LowRate::SCHEME_ID`]: crate::rate::LowRate::SCHEME_ID
+pub const CODING_SCHEME_ID: &str = "rs-fft-gf16-cantor-v1";
+
+// ======================================================================
+// CONST - CRATE
+
+// Maximum `original_count + recovery_count` of `SmallCodec`,
+// public as `small::MAX_SHARDS`. This is here so that
+// `UnsupportedReason` can use it without `encoder` feature.
+pub(crate) const SMALL_MAX_SHARDS: usize = 16;
 
 // ======================================================================
 // Error - PUBLIC
@@ -29,12 +113,22 @@ pub mod rate;
 /// Represents all possible errors that can occur in this library.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Error {
+    /// Decoding was cancelled by the callback given to
+    /// [`ReedSolomonDecoder::decode_with_control`].
+    ///
+    /// Decoder has been reset and received shards must be added again.
+    Cancelled,
+
     /// Given shard has different size than given or inferred shard size.
     ///
     /// - Shard size is given explicitly to encoders/decoders
     ///   and inferred for [`reed_solomon_simd::encode`]
     ///   and [`reed_solomon_simd::decode`].
     ///
+    /// - All shards of a stripe must have the same size,
+    ///   so this usually means a shard is truncated or
+    ///   belongs to a different stripe.
+    ///
     /// [`reed_solomon_simd::encode`]: crate::encode
     /// [`reed_solomon_simd::decode`]: crate::decode
     DifferentShardSize {
@@ -45,19 +139,57 @@ pub enum Error {
     },
 
     /// Decoder was given two original shards with same index.
+    ///
+    /// - The second shard is not added, so decoding can continue
+    ///   with the first one. This usually means shard indexes
+    ///   are tracked incorrectly by the caller.
+    ///
+    /// - Also returned by [`ReedSolomonDecoder::decode_into_uninit`]
+    ///   when an output is given for an original shard
+    ///   which isn't missing, or twice for the same index.
     DuplicateOriginalShardIndex {
         /// Given duplicate index.
         index: usize,
     },
 
     /// Decoder was given two recovery shards with same index.
+    ///
+    /// - See [`Error::DuplicateOriginalShardIndex`].
     DuplicateRecoveryShardIndex {
         /// Given duplicate index.
         index: usize,
     },
 
+    /// [`reconstruct_data`] was given `original_len` which is larger
+    /// than original shards in total.
+    InvalidDataLength {
+        /// Total size of original shards in bytes.
+        max: usize,
+        /// Given `original_len`.
+        got: usize,
+    },
+
+    /// [`MatrixEncoder`] was given coefficient matrix which doesn't
+    /// have `original_count` rows of `recovery_count` elements each.
+    ///
+    /// [`MatrixEncoder`]: crate::matrix::MatrixEncoder
+    InvalidMatrixShape {
+        /// Configured number of original shards, i.e. expected number of rows.
+        original_count: usize,
+        /// Configured number of recovery shards, i.e. expected length of rows.
+        recovery_count: usize,
+        /// Number of rows in the given matrix.
+        rows: usize,
+        /// Length of the first row with wrong length,
+        /// or `recovery_count` if all rows have correct length.
+        columns: usize,
+    },
+
     /// Decoder was given original shard with invalid index,
     /// i.e. `index >= original_count`.
+    ///
+    /// - Decoder must be configured with the same `original_count`
+    ///   that was used in encoding.
     InvalidOriginalShardIndex {
         /// Configured number of original shards.
         original_count: usize,
@@ -65,8 +197,34 @@ pub enum Error {
         index: usize,
     },
 
+    /// Given output buffer has wrong size.
+    ///
+    /// - See [`CodecParams::encode_combined`],
+    ///   [`ReedSolomonEncoder::encode_flat`],
+    ///   [`ReedSolomonDecoder::decode_flat`]
+    ///   and [`ReedSolomonDecoder::decode_into_uninit`].
+    InvalidOutputSize {
+        /// Required size of the output buffer in bytes.
+        expected: usize,
+        /// Size of the given output buffer in bytes.
+        got: usize,
+    },
+
+    /// Given presence bitmap has wrong length.
+    ///
+    /// - See [`ReedSolomonDecoder::decode_flat`].
+    InvalidPresenceLength {
+        /// Required length, i.e. `original_count + recovery_count`.
+        expected: usize,
+        /// Length of the given presence bitmap.
+        got: usize,
+    },
+
     /// Decoder was given recovery shard with invalid index,
     /// i.e. `index >= recovery_count`.
+    ///
+    /// - Decoder must be configured with the same `recovery_count`
+    ///   that was used in encoding.
     InvalidRecoveryShardIndex {
         /// Configured number of recovery shards.
         recovery_count: usize,
@@ -88,10 +246,29 @@ pub enum Error {
         shard_bytes: usize,
     },
 
+    /// Encoder with integrity footer enabled was given original shard
+    /// whose reserved bytes are not zero.
+    ///
+    /// - See [`integrity`] module.
+    NonZeroReservedBytes {
+        /// Index of the original shard.
+        index: usize,
+    },
+
     /// Decoder was given too few shards.
     ///
     /// Decoding requires as many shards as there were original shards
     /// in total, in any combination of original shards and recovery shards.
+    /// In other words the number of missing original shards,
+    /// i.e. erasures, must be at most the number of recovery shards given.
+    ///
+    /// - [`ReedSolomonDecoder`] keeps the shards added so far,
+    ///   so decoding can be retried after adding more shards.
+    ///   [`ReedSolomonDecoder::can_recover`] checks this beforehand.
+    /// - [`Error::needed_additional_shards`] tells how many more are needed.
+    /// - Code is MDS, so any `original_count` distinct shards suffice.
+    ///   There are no erasure patterns or recovery shard indexes which
+    ///   can't be used, so with enough shards decoding never fails.
     NotEnoughShards {
         /// Configured number of original shards.
         original_count: usize,
@@ -101,6 +278,45 @@ pub enum Error {
         recovery_received_count: usize,
     },
 
+    /// Original shard was requested from decoding result,
+    /// but it was given to the decoder instead of being restored.
+    ///
+    /// - [`DecoderResult`] contains only restored original shards,
+    ///   see [`DecoderResult::collect_ordered`].
+    OriginalShardNotRestored {
+        /// Index of the original shard.
+        index: usize,
+    },
+
+    /// Decoder with integrity footer enabled was given recovery shard
+    /// whose footer doesn't match configuration of the decoder.
+    ///
+    /// - See [`integrity`] module.
+    /// - `got` tells the configuration and stripe id the shard
+    ///   was encoded with, or `None` if it was encoded without footer.
+    ParameterMismatch {
+        /// Index of the recovery shard.
+        index: usize,
+        /// Footer expected by the decoder.
+        expected: IntegrityFooter,
+        /// Footer of the given shard or `None` if it doesn't have one.
+        got: Option<IntegrityFooter>,
+    },
+
+    /// Decoder with integrity footer enabled was given recovery shard
+    /// from another stripe, i.e. with matching configuration
+    /// but different `stripe_id`.
+    ///
+    /// - See [`integrity`] module.
+    StripeMismatch {
+        /// Index of the recovery shard.
+        index: usize,
+        /// `stripe_id` expected by the decoder.
+        expected: u64,
+        /// `stripe_id` of the given shard.
+        got: u64,
+    },
+
     /// Encoder was given less than `original_count` original shards.
     TooFewOriginalShards {
         /// Configured number of original shards.
@@ -116,20 +332,98 @@ pub enum Error {
     },
 
     /// Given `original_count` / `recovery_count` combination is not supported.
+    ///
+    /// - See documentation of [`HighRate`], [`LowRate`] and [`DefaultRate`]
+    ///   for exact supported ranges.
+    ///
+    /// - `hint` is set when the combination was rejected by [`HighRate`]
+    ///   or [`LowRate`] but the other one supports it.
+    ///   [`rate::new_encoder`] picks the supporting rate automatically.
+    ///
+    /// [`HighRate`]: crate::rate::HighRate
+    /// [`LowRate`]: crate::rate::LowRate
+    /// [`DefaultRate`]: crate::rate::DefaultRate
+    /// [`rate::new_encoder`]: crate::rate::new_encoder
     UnsupportedShardCount {
         /// Given number of original shards.
         original_count: usize,
         /// Given number of recovery shards.
         recovery_count: usize,
+        /// Why the combination is not supported.
+        reason: UnsupportedReason,
+        /// Rate which supports the combination, if any.
+        hint: Option<RateKind>,
+    },
+
+    /// Working space given to `rate::encode_work` has different
+    /// configuration than given `params`.
+    WorkMismatch {
+        /// Configuration of the working space.
+        expected: CodecParams,
+        /// Given `params`.
+        got: CodecParams,
     },
 }
 
+// ======================================================================
+// Error - PUBLIC
+
+impl Error {
+    /// Returns how many more shards are needed for decoding
+    /// if this is [`Error::NotEnoughShards`], otherwise `None`.
+    ///
+    /// Any shards not given yet will do, original or recovery.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(3, 2, 64)?;
+    /// decoder.add_original_shard(0, [0u8; 64])?;
+    ///
+    /// let err = decoder.decode().err().unwrap();
+    /// assert_eq!(err.needed_additional_shards(), Some(2));
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    pub fn needed_additional_shards(&self) -> Option<usize> {
+        match self {
+            Error::NotEnoughShards {
+                original_count,
+                original_received_count,
+                recovery_received_count,
+            } => Some(
+                original_count.saturating_sub(original_received_count + recovery_received_count),
+            ),
+            _ => None,
+        }
+    }
+}
+
+// ======================================================================
+// Error - CRATE
+
+impl Error {
+    // Returns `Err(self)` from a `#[cold]` function,
+    // which hints the compiler that the calling branch is unlikely.
+    // This keeps error paths out of the happy path of per-shard functions.
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn into_cold_err<T>(self) -> Result<T, Error> {
+        Err(self)
+    }
+}
+
 // ======================================================================
 // Error - IMPL DISPLAY
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Error::Cancelled => write!(f, "decoding was cancelled"),
+
             Error::DifferentShardSize { shard_bytes, got } => {
                 write!(
                     f,
@@ -146,6 +440,27 @@ impl fmt::Display for Error {
                 write!(f, "duplicate recovery shard index: {}", index)
             }
 
+            Error::InvalidDataLength { max, got } => {
+                write!(
+                    f,
+                    "invalid data length: {} bytes is more than original shards in total ({} bytes)",
+                    got, max
+                )
+            }
+
+            Error::InvalidMatrixShape {
+                original_count,
+                recovery_count,
+                rows,
+                columns,
+            } => {
+                write!(
+                    f,
+                    "invalid matrix shape: expected {} rows of {} elements, got {} rows with a row of {} elements",
+                    original_count, recovery_count, rows, columns
+                )
+            }
+
             Error::InvalidOriginalShardIndex {
                 original_count,
                 index,
@@ -157,6 +472,22 @@ impl fmt::Display for Error {
                 )
             }
 
+            Error::InvalidOutputSize { expected, got } => {
+                write!(
+                    f,
+                    "invalid output size: expected {} bytes, got {} bytes",
+                    expected, got
+                )
+            }
+
+            Error::InvalidPresenceLength { expected, got } => {
+                write!(
+                    f,
+                    "invalid presence bitmap length: expected {}, got {}",
+                    expected, got
+                )
+            }
+
             Error::InvalidRecoveryShardIndex {
                 recovery_count,
                 index,
@@ -176,6 +507,14 @@ impl fmt::Display for Error {
                 )
             }
 
+            Error::NonZeroReservedBytes { index } => {
+                write!(
+                    f,
+                    "non-zero reserved bytes at the end of original shard {}",
+                    index
+                )
+            }
+
             Error::NotEnoughShards {
                 original_count,
                 original_received_count,
@@ -183,8 +522,48 @@ impl fmt::Display for Error {
             } => {
                 write!(
                     f,
-                    "not enough shards: {} original + {} recovery < {} original_count",
-                    original_received_count, recovery_received_count, original_count,
+                    "not enough shards: {} original + {} recovery < {} original_count, {} more needed",
+                    original_received_count,
+                    recovery_received_count,
+                    original_count,
+                    self.needed_additional_shards().unwrap(),
+                )
+            }
+
+            Error::OriginalShardNotRestored { index } => {
+                write!(
+                    f,
+                    "original shard {} was given to decoder, not restored",
+                    index
+                )
+            }
+
+            Error::ParameterMismatch {
+                index,
+                expected,
+                got,
+            } => match got {
+                Some(got) => write!(
+                    f,
+                    "parameter mismatch in recovery shard {}: decoder expects {}, shard was encoded with {}",
+                    index, expected, got
+                ),
+                None => write!(
+                    f,
+                    "parameter mismatch in recovery shard {}: decoder expects {}, shard has no integrity footer",
+                    index, expected
+                ),
+            },
+
+            Error::StripeMismatch {
+                index,
+                expected,
+                got,
+            } => {
+                write!(
+                    f,
+                    "stripe mismatch in recovery shard {}: decoder expects stripe {}, shard is from stripe {}",
+                    index, expected, got
                 )
             }
 
@@ -210,11 +589,31 @@ impl fmt::Display for Error {
             Error::UnsupportedShardCount {
                 original_count,
                 recovery_count,
+                reason,
+                hint,
             } => {
                 write!(
                     f,
-                    "unsupported shard count: {} original shards with {} recovery shards",
-                    original_count, recovery_count
+                    "unsupported shard count: {} original shards with {} recovery shards ({})",
+                    original_count, recovery_count, reason
+                )?;
+                match hint {
+                    Some(RateKind::High) => write!(f, ", use high rate instead"),
+                    Some(RateKind::Low) => write!(f, ", use low rate instead"),
+                    None => Ok(()),
+                }
+            }
+
+            Error::WorkMismatch { expected, got } => {
+                write!(
+                    f,
+                    "working space mismatch: working space has {} original + {} recovery shards of {} bytes, params have {} original + {} recovery shards of {} bytes",
+                    expected.original_count,
+                    expected.recovery_count,
+                    expected.shard_bytes,
+                    got.original_count,
+                    got.recovery_count,
+                    got.shard_bytes
                 )
             }
         }
@@ -226,6 +625,187 @@ impl fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+// ======================================================================
+// UnsupportedReason - PUBLIC
+
+/// Reason for [`Error::UnsupportedShardCount`].
+///
+/// Shards are padded to powers of two in the transforms,
+/// so limits of each rate depend on `next_power_of_two()` of one of the counts.
+/// See [algorithm > Rate](crate::algorithm#rate) for details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnsupportedReason {
+    /// `original_count` is `0`.
+    NoOriginalShards,
+
+    /// `recovery_count` is `0`.
+    NoRecoveryShards,
+
+    /// High rate requires
+    /// `recovery_count.next_power_of_two() + original_count <= 65536`.
+    HighRateLimit,
+
+    /// Low rate requires
+    /// `original_count.next_power_of_two() + recovery_count <= 65536`.
+    LowRateLimit,
+
+    /// Experimental `GF(2^32)` codes require
+    /// `original_count.next_power_of_two() + recovery_count <= 2^31`.
+    ///
+    /// Only returned with the `unstable-gf32` feature.
+    Gf32Limit,
+
+    /// [`SmallCodec`] requires `original_count + recovery_count`
+    /// to be at most [`small::MAX_SHARDS`].
+    ///
+    /// Only returned with the `encoder` feature.
+    ///
+    /// [`SmallCodec`]: crate::small::SmallCodec
+    /// [`small::MAX_SHARDS`]: crate::small::MAX_SHARDS
+    SmallCodecLimit,
+
+    /// Counts are too large for every rate available.
+    ///
+    /// [`DefaultRate`] requires
+    /// `min(original_count, recovery_count).next_power_of_two()`
+    /// ` + max(original_count, recovery_count) <= 65536`
+    /// (with the `next_power_of_two()` taken before `min`).
+    ///
+    /// [`DefaultRate`]: crate::rate::DefaultRate
+    TooManyShards,
+}
+
+// ======================================================================
+// UnsupportedReason - CRATE
+
+impl UnsupportedReason {
+    // Returns reason for zero counts, which no rate supports.
+    pub(crate) fn zero_count(original_count: usize, recovery_count: usize) -> Option<Self> {
+        if original_count == 0 {
+            Some(UnsupportedReason::NoOriginalShards)
+        } else if recovery_count == 0 {
+            Some(UnsupportedReason::NoRecoveryShards)
+        } else {
+            None
+        }
+    }
+}
+
+// ======================================================================
+// UnsupportedReason - IMPL DISPLAY
+
+impl fmt::Display for UnsupportedReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnsupportedReason::NoOriginalShards => write!(f, "original_count is 0"),
+            UnsupportedReason::NoRecoveryShards => write!(f, "recovery_count is 0"),
+            UnsupportedReason::HighRateLimit => write!(
+                f,
+                "high rate requires recovery_count.next_power_of_two() + original_count <= 65536"
+            ),
+            UnsupportedReason::LowRateLimit => write!(
+                f,
+                "low rate requires original_count.next_power_of_two() + recovery_count <= 65536"
+            ),
+            UnsupportedReason::Gf32Limit => write!(
+                f,
+                "GF(2^32) requires original_count.next_power_of_two() + recovery_count <= 2^31"
+            ),
+            UnsupportedReason::SmallCodecLimit => write!(
+                f,
+                "SmallCodec requires original_count + recovery_count <= {}",
+                SMALL_MAX_SHARDS
+            ),
+            UnsupportedReason::TooManyShards => {
+                write!(f, "too many shards for both high rate and low rate")
+            }
+        }
+    }
+}
+
+// ======================================================================
+// ShardKind - PUBLIC
+
+/// Kind of a shard, see [`ReedSolomonDecoder::add_shard`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShardKind {
+    /// Original shard.
+    Original,
+    /// Recovery shard.
+    Recovery,
+}
+
+// ======================================================================
+// OriginalShard - PUBLIC
+
+/// Original shard returned by [`reed_solomon_simd::decode_originals`],
+/// either borrowed from the caller or reconstructed by decoding.
+///
+/// [`reed_solomon_simd::decode_originals`]: crate::decode_originals
+#[derive(Clone, Debug, PartialEq)]
+pub enum OriginalShard<'a> {
+    /// Original shard which was given to the decoder,
+    /// borrowed from the caller without copying.
+    Provided(&'a [u8]),
+
+    /// Original shard which was missing and has been reconstructed.
+    Reconstructed(Vec<u8>),
+}
+
+impl<'a> OriginalShard<'a> {
+    /// Returns `true` if this shard was given to the decoder.
+    pub fn is_provided(&self) -> bool {
+        matches!(self, OriginalShard::Provided(_))
+    }
+}
+
+// ======================================================================
+// OriginalShard - IMPL AsRef
+
+impl<'a> AsRef<[u8]> for OriginalShard<'a> {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            OriginalShard::Provided(shard) => shard,
+            OriginalShard::Reconstructed(shard) => shard,
+        }
+    }
+}
+
+// ======================================================================
+// Want - PUBLIC
+
+/// Which missing shards [`reed_solomon_simd::recover`] should restore.
+///
+/// [`reed_solomon_simd::recover`]: crate::recover
+#[cfg(all(feature = "encoder", feature = "decoder"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Want {
+    /// Only missing original shards.
+    Original,
+    /// Only missing recovery shards.
+    Recovery,
+    /// Both missing original shards and missing recovery shards.
+    Both,
+}
+
+// ======================================================================
+// Recovered - PUBLIC
+
+/// Missing shards restored by [`reed_solomon_simd::recover`].
+///
+/// Given shards are not included and shards which
+/// weren't asked for with [`Want`] are left empty.
+///
+/// [`reed_solomon_simd::recover`]: crate::recover
+#[cfg(all(feature = "encoder", feature = "decoder"))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recovered {
+    /// Restored original shards with their indexes.
+    pub original: HashMap<usize, Vec<u8>>,
+    /// Restored recovery shards with their indexes.
+    pub recovery: HashMap<usize, Vec<u8>>,
+}
+
 // ======================================================================
 // FUNCTIONS - PUBLIC
 
@@ -239,6 +819,7 @@ impl std::error::Error for Error {}
 /// - These same indexes must be used when decoding.
 ///
 /// See [simple usage](crate#simple-usage) for an example.
+#[cfg(feature = "encoder")]
 pub fn encode<T>(
     original_count: usize,
     recovery_count: usize,
@@ -248,10 +829,51 @@ where
     T: IntoIterator,
     T::Item: AsRef<[u8]>,
 {
-    if !ReedSolomonEncoder::supports(original_count, recovery_count) {
+    encode_with(
+        &DefaultEngine::new(),
+        original_count,
+        recovery_count,
+        original,
+    )
+}
+
+/// Like [`encode`] but uses given [`Engine`].
+///
+/// This avoids selecting and initializing a new [`DefaultEngine`]
+/// on each call when caller already has an engine.
+///
+/// # Examples
+///
+/// ```rust
+/// use reed_solomon_simd::engine::DefaultEngine;
+///
+/// let engine = DefaultEngine::new();
+/// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+///
+/// let recovery = reed_solomon_simd::encode_with(&engine, 3, 2, original)?;
+/// assert_eq!(recovery, reed_solomon_simd::encode(3, 2, original)?);
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+///
+/// [`Engine`]: crate::engine::Engine
+#[cfg(feature = "encoder")]
+pub fn encode_with<E, T>(
+    engine: &E,
+    original_count: usize,
+    recovery_count: usize,
+    original: T,
+) -> Result<Vec<Vec<u8>>, Error>
+where
+    E: Engine,
+    T: IntoIterator,
+    T::Item: AsRef<[u8]>,
+{
+    if let Some(reason) = DefaultRate::<E>::unsupported_reason(original_count, recovery_count) {
         return Err(Error::UnsupportedShardCount {
             original_count,
             recovery_count,
+            reason,
+            hint: None,
         });
     }
 
@@ -266,7 +888,8 @@ where
         });
     };
 
-    let mut encoder = ReedSolomonEncoder::new(original_count, recovery_count, shard_bytes)?;
+    let mut encoder =
+        DefaultRateEncoder::new(original_count, recovery_count, shard_bytes, engine, None)?;
 
     encoder.add_original_shard(first)?;
     for original in original {
@@ -278,12 +901,47 @@ where
     Ok(result.recovery_iter().map(|s| s.to_vec()).collect())
 }
 
+/// Like [`encode`] but uses engine `E` which is chosen at compile time.
+///
+/// This is [`encode_with`] with an engine created with [`Default`],
+/// e.g. for builds where the engine is a type parameter of the caller.
+/// Whole encoding is then specialized for `E`, whereas [`DefaultEngine`]
+/// dispatches each [`Engine`] call to an engine selected at runtime.
+///
+/// # Examples
+///
+/// ```rust
+/// use reed_solomon_simd::engine::NoSimd;
+///
+/// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+///
+/// let recovery = reed_solomon_simd::encode_with_engine::<NoSimd, _>(3, 2, original)?;
+/// assert_eq!(recovery, reed_solomon_simd::encode(3, 2, original)?);
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+///
+/// [`Engine`]: crate::engine::Engine
+#[cfg(feature = "encoder")]
+pub fn encode_with_engine<E, T>(
+    original_count: usize,
+    recovery_count: usize,
+    original: T,
+) -> Result<Vec<Vec<u8>>, Error>
+where
+    E: Engine + Default,
+    T: IntoIterator,
+    T::Item: AsRef<[u8]>,
+{
+    encode_with(&E::default(), original_count, recovery_count, original)
+}
+
 /// Decodes in one go using [`ReedSolomonDecoder`],
 /// returning restored original shards with their indexes.
 ///
 /// - Given shard indexes must be the same that were used in encoding.
 ///
 /// See [simple usage](crate#simple-usage) for an example and more details.
+#[cfg(feature = "decoder")]
 pub fn decode<O, R, OT, RT>(
     original_count: usize,
     recovery_count: usize,
@@ -296,10 +954,42 @@ where
     OT: AsRef<[u8]>,
     RT: AsRef<[u8]>,
 {
-    if !ReedSolomonDecoder::supports(original_count, recovery_count) {
+    decode_with(
+        &DefaultEngine::new(),
+        original_count,
+        recovery_count,
+        original,
+        recovery,
+    )
+}
+
+/// Like [`decode`] but uses given [`Engine`].
+///
+/// This avoids selecting and initializing a new [`DefaultEngine`]
+/// on each call when caller already has an engine.
+///
+/// [`Engine`]: crate::engine::Engine
+#[cfg(feature = "decoder")]
+pub fn decode_with<E, O, R, OT, RT>(
+    engine: &E,
+    original_count: usize,
+    recovery_count: usize,
+    original: O,
+    recovery: R,
+) -> Result<HashMap<usize, Vec<u8>>, Error>
+where
+    E: Engine,
+    O: IntoIterator<Item = (usize, OT)>,
+    R: IntoIterator<Item = (usize, RT)>,
+    OT: AsRef<[u8]>,
+    RT: AsRef<[u8]>,
+{
+    if let Some(reason) = DefaultRate::<E>::unsupported_reason(original_count, recovery_count) {
         return Err(Error::UnsupportedShardCount {
             original_count,
             recovery_count,
+            reason,
+            hint: None,
         });
     }
 
@@ -324,7 +1014,8 @@ where
         }
     };
 
-    let mut decoder = ReedSolomonDecoder::new(original_count, recovery_count, shard_bytes)?;
+    let mut decoder =
+        DefaultRateDecoder::new(original_count, recovery_count, shard_bytes, engine, None)?;
 
     for (index, original) in original {
         decoder.add_original_shard(index, original)?;
@@ -343,13 +1034,456 @@ where
     Ok(result)
 }
 
-// ======================================================================
-// TESTS
+/// Like [`decode`] but uses engine `E` which is chosen at compile time.
+///
+/// This is [`decode_with`] with an engine created with [`Default`],
+/// so that whole decoding is specialized for `E`.
+#[cfg(feature = "decoder")]
+pub fn decode_with_engine<E, O, R, OT, RT>(
+    original_count: usize,
+    recovery_count: usize,
+    original: O,
+    recovery: R,
+) -> Result<HashMap<usize, Vec<u8>>, Error>
+where
+    E: Engine + Default,
+    O: IntoIterator<Item = (usize, OT)>,
+    R: IntoIterator<Item = (usize, RT)>,
+    OT: AsRef<[u8]>,
+    RT: AsRef<[u8]>,
+{
+    decode_with(
+        &E::default(),
+        original_count,
+        recovery_count,
+        original,
+        recovery,
+    )
+}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{engine::DefaultEngine, rate::DefaultRate};
+/// Decodes in one go using [`ReedSolomonDecoder`],
+/// returning all original shards ordered by their indexes.
+///
+/// - Given original shards are returned as [`OriginalShard::Provided`]
+///   which borrows them from the caller, so they are not copied into the result.
+/// - Missing original shards are returned as [`OriginalShard::Reconstructed`].
+/// - Given shard indexes must be the same that were used in encoding.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+/// use reed_solomon_simd::OriginalShard;
+///
+/// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+/// let recovery = reed_solomon_simd::encode(3, 2, original)?;
+///
+/// let all = reed_solomon_simd::decode_originals(
+///     3,
+///     2,
+///     [(0, &original[0]), (2, &original[2])],
+///     [(0, &recovery[0])],
+/// )?;
+///
+/// assert_eq!(all[0], OriginalShard::Provided(&original[0]));
+/// assert_eq!(all[1], OriginalShard::Reconstructed(original[1].to_vec()));
+/// assert_eq!(all[2], OriginalShard::Provided(&original[2]));
+/// # }
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+#[cfg(feature = "decoder")]
+pub fn decode_originals<'a, O, R, OT, RT>(
+    original_count: usize,
+    recovery_count: usize,
+    original: O,
+    recovery: R,
+) -> Result<Vec<OriginalShard<'a>>, Error>
+where
+    O: IntoIterator<Item = (usize, &'a OT)>,
+    R: IntoIterator<Item = (usize, RT)>,
+    OT: AsRef<[u8]> + ?Sized + 'a,
+    RT: AsRef<[u8]>,
+{
+    if let Some(reason) =
+        DefaultRate::<DefaultEngine>::unsupported_reason(original_count, recovery_count)
+    {
+        return Err(Error::UnsupportedShardCount {
+            original_count,
+            recovery_count,
+            reason,
+            hint: None,
+        });
+    }
+
+    let original: Vec<(usize, &'a [u8])> = original
+        .into_iter()
+        .map(|(index, shard)| (index, shard.as_ref()))
+        .collect();
+    let mut recovery = recovery.into_iter().peekable();
+
+    let shard_bytes = if let Some((_, first_recovery)) = recovery.peek() {
+        first_recovery.as_ref().len()
+    } else if let Some((_, first_original)) = original.first() {
+        first_original.len()
+    } else {
+        return Err(Error::NotEnoughShards {
+            original_count,
+            original_received_count: 0,
+            recovery_received_count: 0,
+        });
+    };
+
+    let mut decoder = ReedSolomonDecoder::new(original_count, recovery_count, shard_bytes)?;
+    let mut provided = vec![None; original_count];
+
+    for (index, original) in original {
+        decoder.add_original_shard(index, original)?;
+        provided[index] = Some(original);
+    }
+
+    for (index, recovery) in recovery {
+        decoder.add_recovery_shard(index, recovery)?;
+    }
+
+    let result = decoder.decode()?;
+
+    Ok(provided
+        .into_iter()
+        .enumerate()
+        .map(|(index, provided)| match provided {
+            Some(original) => OriginalShard::Provided(original),
+            None => OriginalShard::Reconstructed(result.restored_original(index).unwrap().to_vec()),
+        })
+        .collect())
+}
+
+/// Decodes in one go like [`decode_originals`], returning the original
+/// message as the first `original_len` bytes of the concatenated original shards.
+///
+/// This undoes splitting a message into zero-padded original shards,
+/// so that callers don't need to reassemble the shards themselves.
+///
+/// Returns [`Error::InvalidDataLength`] if `original_len` is larger
+/// than `original_count * shard_bytes`.
+///
+/// This is a function rather than a method of [`DecoderResult`],
+/// because [`DecoderResult`] only contains restored original shards.
+/// Provided original shards are overwritten in the working space
+/// during decoding, so they must be given here like to [`decode_originals`].
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+/// let message = b"Hello, world!";
+///
+/// // Split into two 64 byte shards, padding with zeros.
+/// let mut original = [[0u8; 64]; 2];
+/// original[0][..message.len()].copy_from_slice(message);
+///
+/// let recovery = reed_solomon_simd::encode(2, 1, original)?;
+///
+/// let data = reed_solomon_simd::reconstruct_data(
+///     2,
+///     1,
+///     [(1, &original[1])],
+///     [(0, &recovery[0])],
+///     message.len(),
+/// )?;
+///
+/// assert_eq!(data, message);
+/// # }
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+#[cfg(feature = "decoder")]
+pub fn reconstruct_data<'a, O, R, OT, RT>(
+    original_count: usize,
+    recovery_count: usize,
+    original: O,
+    recovery: R,
+    original_len: usize,
+) -> Result<Vec<u8>, Error>
+where
+    O: IntoIterator<Item = (usize, &'a OT)>,
+    R: IntoIterator<Item = (usize, RT)>,
+    OT: AsRef<[u8]> + ?Sized + 'a,
+    RT: AsRef<[u8]>,
+{
+    let all = decode_originals(original_count, recovery_count, original, recovery)?;
+
+    let total_len: usize = all.iter().map(|shard| shard.as_ref().len()).sum();
+    if original_len > total_len {
+        return Err(Error::InvalidDataLength {
+            max: total_len,
+            got: original_len,
+        });
+    }
+
+    let mut data = Vec::with_capacity(original_len);
+    for shard in &all {
+        let len = std::cmp::min(shard.as_ref().len(), original_len - data.len());
+        data.extend_from_slice(&shard.as_ref()[..len]);
+    }
+
+    Ok(data)
+}
+
+/// Restores missing original shards and/or missing recovery shards
+/// in one go, returning only the shards which were missing.
+///
+/// This does only the work which `want` needs:
+/// - Original shards are decoded only if some of them are missing.
+/// - Recovery shards are encoded only if some of them are missing,
+///   from given original shards together with decoded ones.
+///
+/// So e.g. regenerating lost recovery shards when all original shards
+/// are available is a plain [`encode`], and restoring lost original shards
+/// when all recovery shards are available is a plain [`decode`].
+///
+/// - Given shard indexes must be the same that were used in encoding.
+/// - If `want` includes recovery shards and original shards are missing,
+///   at least `original_count` shards must be given in total as with [`decode`].
+///
+/// # Examples
+///
+/// ```rust
+/// use reed_solomon_simd::Want;
+///
+/// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+/// let recovery = reed_solomon_simd::encode(3, 2, original)?;
+///
+/// // Original shard #1 and recovery shard #0 are lost.
+/// let recovered = reed_solomon_simd::recover(
+///     3,
+///     2,
+///     [(0, &original[0]), (2, &original[2])],
+///     [(1, &recovery[1])],
+///     Want::Both,
+/// )?;
+///
+/// assert_eq!(recovered.original[&1], original[1]);
+/// assert_eq!(recovered.recovery[&0], recovery[0]);
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+#[cfg(all(feature = "encoder", feature = "decoder"))]
+pub fn recover<'a, O, R, OT, RT>(
+    original_count: usize,
+    recovery_count: usize,
+    original: O,
+    recovery: R,
+    want: Want,
+) -> Result<Recovered, Error>
+where
+    O: IntoIterator<Item = (usize, &'a OT)>,
+    R: IntoIterator<Item = (usize, RT)>,
+    OT: AsRef<[u8]> + ?Sized + 'a,
+    RT: AsRef<[u8]>,
+{
+    let engine = DefaultEngine::new();
+
+    if let Some(reason) =
+        DefaultRate::<DefaultEngine>::unsupported_reason(original_count, recovery_count)
+    {
+        return Err(Error::UnsupportedShardCount {
+            original_count,
+            recovery_count,
+            reason,
+            hint: None,
+        });
+    }
+
+    // Indexes are validated here as decoding may be skipped.
+
+    let mut provided = vec![None; original_count];
+    for (index, original) in original {
+        match provided.get_mut(index) {
+            None => {
+                return Err(Error::InvalidOriginalShardIndex {
+                    original_count,
+                    index,
+                })
+            }
+            Some(Some(_)) => return Err(Error::DuplicateOriginalShardIndex { index }),
+            Some(slot) => *slot = Some(original.as_ref()),
+        }
+    }
+
+    let mut recovery: Vec<(usize, RT)> = recovery.into_iter().collect();
+    normalize_shards(ShardKind::Recovery, recovery_count, &mut recovery)?;
+    let mut received_recovery = vec![false; recovery_count];
+    for (index, _) in &recovery {
+        received_recovery[*index] = true;
+    }
+
+    let want_original = want != Want::Recovery;
+    let want_recovery = want != Want::Original && received_recovery.contains(&false);
+
+    let mut recovered = Recovered::default();
+
+    let restored = if provided.contains(&None) && (want_original || want_recovery) {
+        decode_with(
+            &engine,
+            original_count,
+            recovery_count,
+            provided
+                .iter()
+                .enumerate()
+                .filter_map(|(index, original)| original.map(|original| (index, original))),
+            recovery
+                .iter()
+                .map(|(index, recovery)| (*index, recovery.as_ref())),
+        )?
+    } else {
+        HashMap::new()
+    };
+
+    if want_recovery {
+        let all = provided
+            .iter()
+            .enumerate()
+            .map(|(index, original)| original.unwrap_or_else(|| &restored[&index]));
+
+        recovered.recovery = encode_with(&engine, original_count, recovery_count, all)?
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| !received_recovery[*index])
+            .collect();
+    }
+
+    if want_original {
+        recovered.original = restored;
+    }
+
+    Ok(recovered)
+}
+
+/// Sorts `shards` by index and checks that indexes are
+/// unique and valid for shards of given `kind`.
+///
+/// This is for shards which arrive in arbitrary order,
+/// e.g. from network, before giving them to a decoder.
+///
+/// - Index of a shard must be less than `count`, which is `original_count`
+///   or `recovery_count` depending on `kind`.
+/// - Shards are sorted even if an error is returned.
+///   Error is for the smallest offending index:
+///   [`Error::DuplicateOriginalShardIndex`] / [`Error::DuplicateRecoveryShardIndex`]
+///   or [`Error::InvalidOriginalShardIndex`] / [`Error::InvalidRecoveryShardIndex`].
+///
+/// # Examples
+///
+/// ```rust
+/// use reed_solomon_simd::{Error, ShardKind};
+///
+/// let mut shards = vec![(2, vec![2u8; 64]), (0, vec![0u8; 64])];
+/// reed_solomon_simd::normalize_shards(ShardKind::Original, 3, &mut shards)?;
+/// assert_eq!(shards[0].0, 0);
+/// assert_eq!(shards[1].0, 2);
+///
+/// shards.push((0, vec![0u8; 64]));
+/// assert_eq!(
+///     reed_solomon_simd::normalize_shards(ShardKind::Original, 3, &mut shards),
+///     Err(Error::DuplicateOriginalShardIndex { index: 0 })
+/// );
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+pub fn normalize_shards<T>(
+    kind: ShardKind,
+    count: usize,
+    shards: &mut [(usize, T)],
+) -> Result<(), Error> {
+    shards.sort_by_key(|(index, _)| *index);
+
+    let mut previous = None;
+    for &(index, _) in shards.iter() {
+        if index >= count {
+            return Err(match kind {
+                ShardKind::Original => Error::InvalidOriginalShardIndex {
+                    original_count: count,
+                    index,
+                },
+                ShardKind::Recovery => Error::InvalidRecoveryShardIndex {
+                    recovery_count: count,
+                    index,
+                },
+            });
+        } else if previous == Some(index) {
+            return Err(match kind {
+                ShardKind::Original => Error::DuplicateOriginalShardIndex { index },
+                ShardKind::Recovery => Error::DuplicateRecoveryShardIndex { index },
+            });
+        }
+        previous = Some(index);
+    }
+
+    Ok(())
+}
+
+/// Initializes all lookup-tables which [`DefaultEngine`]
+/// needs for encoding and decoding.
+///
+/// Tables are otherwise initialized by the first encoder or decoder,
+/// which takes tens of milliseconds. Calling this e.g. in `main()`
+/// before serving requests moves that cost out of the first request.
+///
+/// - This is idempotent: tables are initialized at most once
+///   and later calls return immediately.
+/// - This is thread-safe: if tables are being initialized concurrently,
+///   e.g. by an encoder in another thread, this waits for that
+///   instead of initializing them again.
+/// - This should be called before forking worker processes,
+///   see [fork](crate::engine::tables#fork).
+///
+/// See [`tables::is_initialized`] to check which tables have been initialized.
+///
+/// # Examples
+///
+/// ```rust
+/// reed_solomon_simd::warmup();
+///
+/// // Doesn't need to initialize any tables.
+/// # #[cfg(feature = "encoder")]
+/// let recovery = reed_solomon_simd::encode(2, 1, [[1u8; 64], [2u8; 64]])?;
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+///
+/// [`DefaultEngine`]: crate::engine::DefaultEngine
+/// [`tables::is_initialized`]: crate::engine::tables::is_initialized
+pub fn warmup() {
+    // Engine constructors initialize the tables the engine needs,
+    // except `LogWalsh` which is only used in decoding.
+    DefaultEngine::new();
+    #[cfg(feature = "decoder")]
+    engine::tables::initialize_log_walsh();
+}
+
+/// Like [`warmup`] but runs it with given `spawn` function,
+/// e.g. in a background thread or blocking task of an async runtime.
+///
+/// # Examples
+///
+/// ```rust
+/// reed_solomon_simd::warmup_async(|task| {
+///     std::thread::spawn(task);
+/// });
+/// ```
+pub fn warmup_async(spawn: impl FnOnce(Box<dyn FnOnce() + Send>)) {
+    spawn(Box::new(warmup))
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
+mod tests {
+    use super::*;
+    use crate::{
+        engine::{
+            tables::{self, Table},
+            DefaultEngine, EngineKind, NoSimd,
+        },
+        rate::{DefaultRate, HighRate, LowRate},
+    };
 
     // ============================================================
     // ROUNDTRIP
@@ -369,6 +1503,152 @@ mod tests {
         assert_eq!(restored[&1], original[1]);
     }
 
+    #[test]
+    fn roundtrip_with_engine() {
+        let engine = NoSimd::new();
+        let original = test_util::generate_original(3, 1024, 168);
+
+        for recovery_count in [2, 5] {
+            let recovery = encode_with(&engine, 3, recovery_count, &original).unwrap();
+            assert_eq!(recovery, encode(3, recovery_count, &original).unwrap());
+
+            let restored = decode_with(
+                &engine,
+                3,
+                recovery_count,
+                [(1, &original[1])],
+                [(0, &recovery[0]), (1, &recovery[1])],
+            )
+            .unwrap();
+
+            assert_eq!(restored.len(), 2);
+            assert_eq!(restored[&0], original[0]);
+            assert_eq!(restored[&2], original[2]);
+        }
+    }
+
+    #[test]
+    fn roundtrip_with_engine_type() {
+        let original = test_util::generate_original(3, 1024, 192);
+
+        for recovery_count in [2, 5] {
+            let recovery = encode_with_engine::<NoSimd, _>(3, recovery_count, &original).unwrap();
+            assert_eq!(recovery, encode(3, recovery_count, &original).unwrap());
+
+            let restored = decode_with_engine::<NoSimd, _, _, _, _>(
+                3,
+                recovery_count,
+                [(1, &original[1])],
+                [(0, &recovery[0]), (1, &recovery[1])],
+            )
+            .unwrap();
+
+            assert_eq!(
+                restored,
+                decode(
+                    3,
+                    recovery_count,
+                    [(1, &original[1])],
+                    [(0, &recovery[0]), (1, &recovery[1])],
+                )
+                .unwrap()
+            );
+            assert_eq!(restored[&0], original[0]);
+            assert_eq!(restored[&2], original[2]);
+        }
+    }
+
+    // ============================================================
+    // CODING_SCHEME_ID
+
+    #[test]
+    fn scheme_ids_match_recovery_hashes() {
+        fn check<R: Rate<NoSimd>>(
+            scheme_id: &str,
+            pinned_scheme_id: &str,
+            hashes: &[(usize, usize, u8, &str)],
+        ) {
+            assert_eq!(
+                scheme_id, pinned_scheme_id,
+                "scheme id changed without updating recovery hashes"
+            );
+
+            for &(original_count, recovery_count, seed, hash) in hashes {
+                let original = test_util::generate_original(original_count, 1024, seed);
+                let mut encoder =
+                    R::encoder(original_count, recovery_count, 1024, NoSimd::new(), None).unwrap();
+                for original in &original {
+                    encoder.add_original_shard(original).unwrap();
+                }
+                test_util::assert_hash(encoder.encode().unwrap().recovery_iter(), hash);
+            }
+        }
+
+        check::<DefaultRate<NoSimd>>(
+            DefaultRate::<NoSimd>::SCHEME_ID,
+            test_util::DEFAULT_SCHEME_ID,
+            test_util::DEFAULT_TINY,
+        );
+        check::<HighRate<NoSimd>>(
+            HighRate::<NoSimd>::SCHEME_ID,
+            test_util::HIGH_SCHEME_ID,
+            test_util::HIGH_TINY,
+        );
+        check::<LowRate<NoSimd>>(
+            LowRate::<NoSimd>::SCHEME_ID,
+            test_util::LOW_SCHEME_ID,
+            test_util::LOW_TINY,
+        );
+        assert_eq!(CODING_SCHEME_ID, DefaultRate::<NoSimd>::SCHEME_ID);
+    }
+
+    // ============================================================
+    // warmup
+
+    // Multiplication table used by the engine `DefaultEngine` selects.
+    fn default_engine_mul_table() -> Option<Table> {
+        // Not `DefaultEngine::new().kind()` which would initialize the table.
+        let simd = matches!(
+            engine::available_engines()[0],
+            EngineKind::Avx2 | EngineKind::Ssse3 | EngineKind::Neon
+        );
+
+        if simd {
+            (!cfg!(feature = "compact-tables")).then_some(Table::Mul128)
+        } else {
+            (!cfg!(feature = "constant-time")).then_some(Table::Mul16)
+        }
+    }
+
+    #[test]
+    fn warmup_concurrently() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        for _ in 0..4 {
+            let sender = sender.clone();
+            warmup_async(|task| {
+                std::thread::spawn(move || {
+                    task();
+                    sender.send(()).unwrap();
+                });
+            });
+        }
+        for _ in 0..4 {
+            receiver.recv().unwrap();
+        }
+
+        // Idempotent.
+        warmup();
+
+        // Constructors of the selected engine and decoding
+        // have nothing left to initialize.
+        for table in [Table::ExpLog, Table::LogWalsh, Table::Skew] {
+            assert!(tables::is_initialized(table), "{:?}", table);
+        }
+        if let Some(table) = default_engine_mul_table() {
+            assert!(tables::is_initialized(table), "{:?}", table);
+        }
+    }
+
     // ==================================================
     // trait Send
 
@@ -399,6 +1679,159 @@ mod tests {
         assert_sync::<Error>();
     }
 
+    // ============================================================
+    // NO PANICS
+
+    // Random sequences of public API calls with extreme parameters
+    // must return `Err` instead of panicking.
+    #[test]
+    fn public_api_does_not_panic() {
+        use rand::{seq::SliceRandom, Rng, SeedableRng};
+        use rand_chacha::ChaCha8Rng;
+
+        const COUNTS: &[usize] = &[
+            0,
+            1,
+            2,
+            3,
+            63,
+            64,
+            65,
+            4096,
+            32768,
+            61440,
+            65535,
+            65536,
+            65537,
+            usize::MAX,
+        ];
+        const SHARD_BYTES: &[usize] = &[
+            0,
+            1,
+            2,
+            62,
+            63,
+            64,
+            65,
+            128,
+            (isize::MAX as usize) & !63,
+            usize::MAX & !63,
+            usize::MAX,
+        ];
+
+        let mut rng = ChaCha8Rng::seed_from_u64(175);
+        let pick = |values: &[usize], rng: &mut ChaCha8Rng| *values.choose(rng).unwrap();
+
+        let mut encoder = ReedSolomonEncoder::new(2, 3, 64).unwrap();
+        let mut decoder = ReedSolomonDecoder::new(2, 3, 64).unwrap();
+        let mut params: (usize, usize, usize) = (2, 3, 64);
+        let mut recovery = Vec::new();
+
+        for _ in 0..3000 {
+            let (original_count, recovery_count, shard_bytes) = params;
+            let index = pick(
+                &[
+                    0,
+                    1,
+                    original_count.wrapping_sub(1),
+                    original_count,
+                    recovery_count.wrapping_sub(1),
+                    recovery_count,
+                    usize::MAX,
+                ],
+                &mut rng,
+            );
+            let len = pick(
+                &[
+                    0,
+                    1,
+                    64,
+                    shard_bytes.min(4096),
+                    shard_bytes.saturating_add(2).min(4098),
+                ],
+                &mut rng,
+            );
+            let shard = vec![rng.gen::<u8>(); len];
+
+            match rng.gen_range(0..12) {
+                0 => {
+                    let new_params = (
+                        pick(COUNTS, &mut rng),
+                        pick(COUNTS, &mut rng),
+                        pick(SHARD_BYTES, &mut rng),
+                    );
+                    if encoder
+                        .reset(new_params.0, new_params.1, new_params.2)
+                        .is_ok()
+                        && decoder
+                            .reset(new_params.0, new_params.1, new_params.2)
+                            .is_ok()
+                    {
+                        params = new_params;
+                    }
+                }
+                1 => {
+                    let (o, r, b) = (
+                        pick(COUNTS, &mut rng),
+                        pick(COUNTS, &mut rng),
+                        pick(SHARD_BYTES, &mut rng),
+                    );
+                    let _ = ReedSolomonEncoder::new(o, r, b);
+                    let _ = ReedSolomonDecoder::new(o, r, b);
+                }
+                2 => {
+                    // Small counts so that roundtrip is feasible.
+                    let (o, r) = (rng.gen_range(1..5), rng.gen_range(1..5));
+                    if encoder.reset(o, r, 64).is_ok() && decoder.reset(o, r, 64).is_ok() {
+                        params = (o, r, 64);
+                    }
+                }
+                3 | 4 => {
+                    let _ = encoder.add_original_shard(&shard);
+                }
+                5 => {
+                    if let Ok(result) = encoder.encode() {
+                        recovery = result.recovery_iter().map(<[u8]>::to_vec).collect();
+                    }
+                }
+                6 => {
+                    let _ = decoder.add_original_shard(index, &shard);
+                }
+                7 => {
+                    let shard = recovery.get(index).cloned().unwrap_or(shard);
+                    let _ = decoder.add_recovery_shard(index, shard);
+                }
+                8 => {
+                    let _ = decoder.can_recover();
+                    if let Ok(result) = decoder.decode() {
+                        let _ = result.restored_original(index);
+                        let _ = result.restored_original_iter().count();
+                    }
+                }
+                9 => {
+                    let _ = encode(original_count, recovery_count, [&shard, &shard]);
+                }
+                10 => {
+                    let _ = decode(
+                        original_count,
+                        recovery_count,
+                        [(index, &shard)],
+                        [(0, &shard), (index, &shard)],
+                    );
+                }
+                _ => {
+                    let _ = reconstruct_data(
+                        original_count,
+                        recovery_count,
+                        [(index, &shard)],
+                        [(0, &shard)],
+                        len,
+                    );
+                }
+            }
+        }
+    }
+
     // ============================================================
     // encode
 
@@ -453,6 +1886,8 @@ mod tests {
                 Err(Error::UnsupportedShardCount {
                     original_count: 0,
                     recovery_count: 1,
+                    reason: UnsupportedReason::NoOriginalShards,
+                    hint: None,
                 })
             );
         }
@@ -464,6 +1899,8 @@ mod tests {
                 Err(Error::UnsupportedShardCount {
                     original_count: 1,
                     recovery_count: 0,
+                    reason: UnsupportedReason::NoRecoveryShards,
+                    hint: None,
                 })
             );
         }
@@ -585,6 +2022,19 @@ mod tests {
             );
         }
 
+        #[test]
+        fn not_enough_shards_with_too_many_erasures() {
+            // Two original shards missing but only one recovery shard.
+            assert_eq!(
+                decode(3, 2, [(1, &[0u8; 64])], [(0, &[0u8; 64])]),
+                Err(Error::NotEnoughShards {
+                    original_count: 3,
+                    original_received_count: 1,
+                    recovery_received_count: 1,
+                })
+            );
+        }
+
         #[test]
         fn unsupported_shard_count_with_zero_original_count() {
             assert_eq!(
@@ -592,6 +2042,8 @@ mod tests {
                 Err(Error::UnsupportedShardCount {
                     original_count: 0,
                     recovery_count: 1,
+                    reason: UnsupportedReason::NoOriginalShards,
+                    hint: None,
                 })
             );
         }
@@ -603,8 +2055,601 @@ mod tests {
                 Err(Error::UnsupportedShardCount {
                     original_count: 1,
                     recovery_count: 0,
+                    reason: UnsupportedReason::NoRecoveryShards,
+                    hint: None,
+                })
+            );
+        }
+    }
+
+    // ============================================================
+    // decode_originals
+
+    mod decode_originals {
+        use super::super::*;
+        use crate::test_util;
+
+        #[test]
+        fn provided_are_borrowed_and_missing_are_reconstructed() {
+            let original = test_util::generate_original(4, 1024, 153);
+            let recovery = encode(4, 2, &original).unwrap();
+
+            let all = decode_originals(
+                4,
+                2,
+                [(0, &original[0]), (2, &original[2])],
+                [(0, &recovery[0]), (1, &recovery[1])],
+            )
+            .unwrap();
+
+            assert_eq!(all.len(), 4);
+            for (index, shard) in all.iter().enumerate() {
+                assert_eq!(shard.as_ref(), original[index]);
+            }
+
+            // Provided shards point into the caller's buffers, i.e. zero-copy.
+            match &all[0] {
+                OriginalShard::Provided(shard) => {
+                    assert!(std::ptr::eq(*shard, original[0].as_slice()))
+                }
+                _ => panic!("original shard 0 should be provided"),
+            }
+            match &all[2] {
+                OriginalShard::Provided(shard) => {
+                    assert!(std::ptr::eq(*shard, original[2].as_slice()))
+                }
+                _ => panic!("original shard 2 should be provided"),
+            }
+
+            assert!(!all[1].is_provided());
+            assert!(!all[3].is_provided());
+        }
+
+        #[test]
+        fn no_original_missing_with_no_recovery_given() {
+            let original = [[1u8; 64]];
+            let all = decode_originals(1, 1, [(0, &original[0])], [(0, ""); 0]).unwrap();
+            assert_eq!(all, vec![OriginalShard::Provided(&original[0])]);
+        }
+
+        // ==================================================
+        // ERRORS
+
+        #[test]
+        fn duplicate_original_shard_index() {
+            assert_eq!(
+                decode_originals(2, 1, [(0, &[0u8; 64]), (0, &[0u8; 64])], [(0, &[0u8; 64])]),
+                Err(Error::DuplicateOriginalShardIndex { index: 0 })
+            );
+        }
+
+        #[test]
+        fn not_enough_shards() {
+            assert_eq!(
+                decode_originals(2, 1, [(0, &[0u8; 64])], [(0, ""); 0]),
+                Err(Error::NotEnoughShards {
+                    original_count: 2,
+                    original_received_count: 1,
+                    recovery_received_count: 0,
+                })
+            );
+
+            assert_eq!(
+                decode_originals(1, 1, [(0, ""); 0], [(0, ""); 0]),
+                Err(Error::NotEnoughShards {
+                    original_count: 1,
+                    original_received_count: 0,
+                    recovery_received_count: 0,
+                })
+            );
+        }
+
+        #[test]
+        fn unsupported_shard_count_with_zero_original_count() {
+            assert_eq!(
+                decode_originals(0, 1, [(0, ""); 0], [(0, ""); 0]),
+                Err(Error::UnsupportedShardCount {
+                    original_count: 0,
+                    recovery_count: 1,
+                    reason: UnsupportedReason::NoOriginalShards,
+                    hint: None,
+                })
+            );
+        }
+    }
+
+    // ============================================================
+    // recover
+
+    mod recover {
+        use super::super::*;
+        use crate::test_util;
+
+        // Given shards are those of `have_original` / `have_recovery`,
+        // returned shards must be exactly the missing ones which are wanted.
+        fn check(have_original: &[usize], have_recovery: &[usize], want: Want) {
+            let original = test_util::generate_original(5, 1024, 191);
+            let recovery = encode(5, 3, &original).unwrap();
+
+            let recovered = recover(
+                5,
+                3,
+                have_original.iter().map(|&index| (index, &original[index])),
+                have_recovery.iter().map(|&index| (index, &recovery[index])),
+                want,
+            )
+            .unwrap();
+
+            let missing = |all: &[Vec<u8>], have: &[usize]| -> HashMap<usize, Vec<u8>> {
+                (0..all.len())
+                    .filter(|index| !have.contains(index))
+                    .map(|index| (index, all[index].clone()))
+                    .collect()
+            };
+
+            let expected = Recovered {
+                original: if want == Want::Recovery {
+                    HashMap::new()
+                } else {
+                    missing(&original, have_original)
+                },
+                recovery: if want == Want::Original {
+                    HashMap::new()
+                } else {
+                    missing(&recovery, have_recovery)
+                },
+            };
+            assert_eq!(
+                recovered, expected,
+                "{have_original:?} {have_recovery:?} {want:?}"
+            );
+        }
+
+        #[test]
+        fn all_modes() {
+            for want in [Want::Original, Want::Recovery, Want::Both] {
+                // All original shards, i.e. encoding only.
+                check(&[0, 1, 2, 3, 4], &[], want);
+                check(&[0, 1, 2, 3, 4], &[1], want);
+                // All recovery shards, i.e. decoding only.
+                check(&[0, 2], &[0, 1, 2], want);
+                check(&[1, 2, 4], &[0, 1, 2], want);
+                // Mixed, i.e. decoding and then encoding.
+                check(&[0, 1, 3], &[2, 0], want);
+                check(&[4, 0, 1, 2], &[1], want);
+                // Nothing missing.
+                check(&[0, 1, 2, 3, 4], &[0, 1, 2], want);
+            }
+        }
+
+        // ==================================================
+        // ERRORS
+
+        #[test]
+        fn errors() {
+            let shard = [0u8; 64];
+
+            assert_eq!(
+                recover(2, 1, [(0, &shard), (0, &shard)], [(0, ""); 0], Want::Both),
+                Err(Error::DuplicateOriginalShardIndex { index: 0 })
+            );
+            assert_eq!(
+                recover(2, 1, [(2, &shard)], [(0, ""); 0], Want::Recovery),
+                Err(Error::InvalidOriginalShardIndex {
+                    original_count: 2,
+                    index: 2,
+                })
+            );
+            assert_eq!(
+                recover(
+                    2,
+                    2,
+                    [(0, &shard)],
+                    [(1, &shard), (1, &shard)],
+                    Want::Original
+                ),
+                Err(Error::DuplicateRecoveryShardIndex { index: 1 })
+            );
+            assert_eq!(
+                recover(2, 1, [(0, &shard)], [(1, &shard)], Want::Original),
+                Err(Error::InvalidRecoveryShardIndex {
+                    recovery_count: 1,
+                    index: 1,
+                })
+            );
+
+            // Missing original shards are needed for recovery shards.
+            assert_eq!(
+                recover(2, 2, [(0, &shard)], [(0, ""); 0], Want::Recovery),
+                Err(Error::NotEnoughShards {
+                    original_count: 2,
+                    original_received_count: 1,
+                    recovery_received_count: 0,
+                })
+            );
+            // ... but not for original shards only.
+            assert_eq!(
+                recover(
+                    2,
+                    2,
+                    [(0, &shard), (1, &shard)],
+                    [(0, ""); 0],
+                    Want::Original
+                ),
+                Ok(Recovered::default())
+            );
+
+            assert_eq!(
+                recover(
+                    2,
+                    1,
+                    [(0, &shard[..]), (1, &[0u8; 128][..])],
+                    [(0, ""); 0],
+                    Want::Both
+                ),
+                Err(Error::DifferentShardSize {
+                    shard_bytes: 64,
+                    got: 128,
                 })
             );
         }
     }
+
+    // ============================================================
+    // normalize_shards
+
+    mod normalize_shards {
+        use super::super::*;
+
+        fn shards(indexes: &[usize]) -> Vec<(usize, Vec<u8>)> {
+            indexes
+                .iter()
+                .map(|&index| (index, vec![index as u8; 64]))
+                .collect()
+        }
+
+        #[test]
+        fn sorts_by_index() {
+            let mut shards = shards(&[3, 0, 4, 1]);
+            assert_eq!(
+                normalize_shards(ShardKind::Original, 5, &mut shards),
+                Ok(())
+            );
+            for (index, shard) in &shards {
+                assert_eq!(shard[0], *index as u8);
+            }
+            let indexes: Vec<_> = shards.iter().map(|(index, _)| *index).collect();
+            assert_eq!(indexes, [0, 1, 3, 4]);
+
+            assert_eq!(
+                normalize_shards::<Vec<u8>>(ShardKind::Recovery, 0, &mut []),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn duplicate_index() {
+            assert_eq!(
+                normalize_shards(ShardKind::Original, 5, &mut shards(&[4, 2, 1, 4, 2])),
+                Err(Error::DuplicateOriginalShardIndex { index: 2 })
+            );
+            assert_eq!(
+                normalize_shards(ShardKind::Recovery, 5, &mut shards(&[3, 0, 3])),
+                Err(Error::DuplicateRecoveryShardIndex { index: 3 })
+            );
+        }
+
+        #[test]
+        fn invalid_index() {
+            assert_eq!(
+                normalize_shards(ShardKind::Original, 3, &mut shards(&[7, 0, 3])),
+                Err(Error::InvalidOriginalShardIndex {
+                    original_count: 3,
+                    index: 3,
+                })
+            );
+            assert_eq!(
+                normalize_shards(ShardKind::Recovery, 2, &mut shards(&[1, 5, 0])),
+                Err(Error::InvalidRecoveryShardIndex {
+                    recovery_count: 2,
+                    index: 5,
+                })
+            );
+            // Smallest offending index is reported.
+            assert_eq!(
+                normalize_shards(ShardKind::Original, 3, &mut shards(&[9, 1, 1])),
+                Err(Error::DuplicateOriginalShardIndex { index: 1 })
+            );
+        }
+    }
+
+    // ============================================================
+    // reconstruct_data
+
+    mod reconstruct_data {
+        use super::super::*;
+        use crate::test_util;
+
+        #[test]
+        fn roundtrip_message() {
+            let message = &test_util::generate_original(1, 1024, 172)[0][..1000];
+
+            // Split 1000 bytes to 4 shards of 256 bytes, last one zero-padded.
+            let mut original = vec![vec![0u8; 256]; 4];
+            for (shard, chunk) in original.iter_mut().zip(message.chunks(256)) {
+                shard[..chunk.len()].copy_from_slice(chunk);
+            }
+
+            let recovery = encode(4, 3, &original).unwrap();
+
+            let data = reconstruct_data(
+                4,
+                3,
+                [(0, &original[0]), (2, &original[2])],
+                [(0, &recovery[0]), (2, &recovery[2])],
+                message.len(),
+            )
+            .unwrap();
+
+            assert_eq!(data, message);
+        }
+
+        #[test]
+        fn full_length_and_empty() {
+            let original = test_util::generate_original(2, 64, 0);
+            let recovery = encode(2, 1, &original).unwrap();
+
+            let data =
+                reconstruct_data(2, 1, [(0, &original[0])], [(0, &recovery[0])], 128).unwrap();
+            assert_eq!(data, original.concat());
+
+            let data = reconstruct_data(2, 1, [(0, &original[0])], [(0, &recovery[0])], 0).unwrap();
+            assert!(data.is_empty());
+        }
+
+        #[test]
+        fn original_len_too_large() {
+            let original = [[0u8; 64]];
+            assert_eq!(
+                reconstruct_data(1, 1, [(0, &original[0])], [(0, ""); 0], 65),
+                Err(Error::InvalidDataLength { max: 64, got: 65 })
+            );
+        }
+
+        #[test]
+        fn not_enough_shards() {
+            assert_eq!(
+                reconstruct_data(2, 1, [(0, &[0u8; 64])], [(0, ""); 0], 100),
+                Err(Error::NotEnoughShards {
+                    original_count: 2,
+                    original_received_count: 1,
+                    recovery_received_count: 0,
+                })
+            );
+        }
+    }
+}
+
+// Decoding of fixed shards which doesn't need `encoder` feature,
+// so that decoder-only builds are also tested.
+#[cfg(all(test, not(miri), feature = "decoder"))]
+mod decoder_only_tests {
+    use super::*;
+
+    // Shard `i` with 64 bytes `j * step + i * offset`.
+    fn original(count: u8, step: u8, offset: u8) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|i| {
+                (0..64)
+                    .map(|j: u8| j.wrapping_mul(step).wrapping_add(i.wrapping_mul(offset)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode_low_rate() {
+        let original = original(2, 7, 101);
+        let recovery = [
+            hex::decode(
+                "60bfcf994ad2038411c1b1f7a3a5a235b06f28094d2bfa14818fef77334555c2\
+                 37361b2f308689919bdbffdbedff262e343592bf99a2ad010b261b527d56656d",
+            )
+            .unwrap(),
+            hex::decode(
+                "53d87762b2f7729560c5b0b5a2a1c433d65dd0f2f54cc905f08936ef329bfc0b\
+                 202d6339cededdcdcf20e1abf304e8e8faf76ca9e1b9ba5d5f1846a4632b7272",
+            )
+            .unwrap(),
+        ];
+
+        let restored = decode(2, 3, [(0, ""); 0], [(0, &recovery[0]), (2, &recovery[1])]).unwrap();
+
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored[&0], original[0]);
+        assert_eq!(restored[&1], original[1]);
+    }
+
+    #[test]
+    fn decode_high_rate() {
+        let original = original(3, 13, 37);
+        let recovery = [
+            hex::decode(
+                "a9a9cd8f18f878609ab97a9e67b8ce6fba2f49a6716864fecd206918939190ff\
+                 6d133baeb41a62a14159196352d10a6b2ad835ec6520298c7e125ea865934451",
+            )
+            .unwrap(),
+            hex::decode(
+                "c6c18c950b54dd1ecde9937cdc2ca34985d7980c121451f02a80d06ad8b56d09\
+                 e29b5a9487d6a73f362910618965872d75c0c426e6bc7ca279d2873a0ed75947",
+            )
+            .unwrap(),
+        ];
+
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 64).unwrap();
+        decoder.add_original_shard(1, &original[1]).unwrap();
+        decoder.add_recovery_shard(0, &recovery[0]).unwrap();
+        decoder.add_recovery_shard(1, &recovery[1]).unwrap();
+        let result = decoder.decode().unwrap();
+
+        assert_eq!(result.restored_original(0).unwrap(), original[0]);
+        assert_eq!(result.restored_original(2).unwrap(), original[2]);
+    }
+}
+
+// Tests which are also run under Miri, see "Running tests" in README.
+//
+// Miri can't run SIMD engines and is too slow to initialize `Mul16` and
+// `Mul128` tables, so other test modules aren't run under it.
+// These use rate encoders and decoders directly with `Naive` engine,
+// and with `NoSimd` unless it would need `Mul16` table under Miri.
+#[cfg(all(test, feature = "encoder", feature = "decoder"))]
+mod miri_tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        engine::{Engine, Naive, NoSimd},
+        rate::{DefaultRate, DefaultRateDecoder, DefaultRateEncoder, RateDecoder, RateEncoder},
+        test_util, Error,
+    };
+
+    const NO_SIMD: bool = cfg!(any(not(miri), feature = "constant-time"));
+
+    fn for_each_engine(test: fn(fn() -> Naive), test_no_simd: fn(fn() -> NoSimd)) {
+        test(Naive::new);
+        if NO_SIMD {
+            test_no_simd(NoSimd::new);
+        }
+    }
+
+    fn encode<E: Engine>(
+        encoder: &mut DefaultRateEncoder<E>,
+        original: &[Vec<u8>],
+    ) -> Vec<Vec<u8>> {
+        for original in original {
+            encoder.add_original_shard(original).unwrap();
+        }
+        let result = encoder.encode().unwrap();
+        result.recovery_iter().map(|x| x.to_vec()).collect()
+    }
+
+    // ============================================================
+    // ROUNDTRIPS
+
+    // Includes odd shard counts with truncated FFT sizes 1, 3 and 5.
+    fn roundtrips<E: Engine>(new_engine: fn() -> E) {
+        for &(original_count, recovery_count, seed, recovery_hash) in test_util::DEFAULT_TINY {
+            if ![(1, 1), (2, 3), (3, 2), (5, 3)].contains(&(original_count, recovery_count)) {
+                continue;
+            }
+
+            test_util::roundtrip_single::<DefaultRate<E>, E, _>(
+                new_engine,
+                original_count,
+                recovery_count,
+                1024,
+                recovery_hash,
+                &[recovery_count..original_count],
+                &[0..std::cmp::min(original_count, recovery_count)],
+                seed,
+            );
+        }
+    }
+
+    #[test]
+    fn roundtrips_tiny() {
+        for_each_engine(roundtrips, roundtrips);
+    }
+
+    // ============================================================
+    // WORK REUSE
+
+    // High rate, low rate and back with growing and shrinking working space.
+    const CONFIGS: [(usize, usize, usize); 4] = [(3, 2, 128), (2, 5, 64), (5, 3, 192), (3, 2, 128)];
+
+    fn work_reuse<E: Engine>(new_engine: fn() -> E) {
+        let mut encoder = DefaultRateEncoder::new(1, 1, 64, new_engine(), None).unwrap();
+        let mut decoder = DefaultRateDecoder::new(1, 1, 64, new_engine(), None).unwrap();
+
+        for (i, (original_count, recovery_count, shard_bytes)) in CONFIGS.into_iter().enumerate() {
+            let original = test_util::generate_original(original_count, shard_bytes, i as u8);
+
+            encoder
+                .reset(original_count, recovery_count, shard_bytes)
+                .unwrap();
+            let recovery = encode(&mut encoder, &original);
+
+            let mut fresh_encoder = DefaultRateEncoder::new(
+                original_count,
+                recovery_count,
+                shard_bytes,
+                new_engine(),
+                None,
+            )
+            .unwrap();
+            assert_eq!(recovery, encode(&mut fresh_encoder, &original));
+
+            // Decode only on first and last round, decoding is slow under Miri.
+            if i == 0 || i == CONFIGS.len() - 1 {
+                decoder
+                    .reset(original_count, recovery_count, shard_bytes)
+                    .unwrap();
+                decoder.add_original_shard(1, &original[1]).unwrap();
+                decoder.add_recovery_shard(0, &recovery[0]).unwrap();
+                decoder.add_recovery_shard(1, &recovery[1]).unwrap();
+
+                let result = decoder.decode().unwrap();
+                let restored: HashMap<_, _> = result.restored_original_iter().collect();
+                assert_eq!(restored.len(), 2);
+                assert_eq!(restored[&0], original[0]);
+                assert_eq!(restored[&2], original[2]);
+            }
+        }
+    }
+
+    #[test]
+    fn work_reuse_across_resets() {
+        for_each_engine(work_reuse, work_reuse);
+    }
+
+    // ============================================================
+    // ZERO-LENGTH
+
+    fn zero_length<E: Engine>(new_engine: fn() -> E) {
+        let engine = new_engine();
+
+        engine.mul(&mut [], 1234);
+        E::xor(&mut [], &[]);
+        E::xor_into(&mut [], &[], &[]);
+        E::copy_nt(&mut [], &[]);
+        engine.linear_combination(&mut [], &[&[], &[]], &[0, 5]);
+
+        assert_eq!(
+            DefaultRateEncoder::new(2, 2, 0, new_engine(), None).err(),
+            Some(Error::InvalidShardSize { shard_bytes: 0 })
+        );
+
+        let mut encoder = DefaultRateEncoder::new(2, 2, 64, new_engine(), None).unwrap();
+        assert_eq!(
+            encoder.add_original_shard([]),
+            Err(Error::DifferentShardSize {
+                shard_bytes: 64,
+                got: 0
+            })
+        );
+
+        let mut decoder = DefaultRateDecoder::new(2, 2, 64, new_engine(), None).unwrap();
+        assert_eq!(
+            decoder.add_recovery_shard(0, []),
+            Err(Error::DifferentShardSize {
+                shard_bytes: 64,
+                got: 0
+            })
+        );
+    }
+
+    #[test]
+    fn zero_length_shards() {
+        for_each_engine(zero_length, zero_length);
+    }
 }
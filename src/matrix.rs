@@ -0,0 +1,259 @@
+//! Encoding with an explicit generator matrix.
+//!
+//! **This is an advanced module which is not needed for [simple usage] or [basic usage].**
+//!
+//! [`MatrixEncoder`] computes each recovery shard as a linear combination
+//! of the original shards with caller-given coefficients, bypassing
+//! the additive FFT. This is `O(original_count * recovery_count)`
+//! instead of `O(n log n)`, so it's only meant for interoperability
+//! with codes defined by a fixed matrix.
+//!
+//! Recovery shards of [`MatrixEncoder`] generally can't be decoded
+//! with [`ReedSolomonDecoder`], unless the matrix is the one implied
+//! by the FFT-based encoding.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use reed_solomon_simd::matrix::MatrixEncoder;
+//!
+//! // Recovery shard 0 is `original[0] + original[1]` (i.e. XOR)
+//! // and recovery shard 1 is `original[1]`.
+//! let matrix = [vec![1, 0], vec![1, 1]];
+//! let mut encoder = MatrixEncoder::new(2, 2, 64, &matrix)?;
+//!
+//! encoder.add_original_shard([0x0F; 64])?;
+//! encoder.add_original_shard([0xF0; 64])?;
+//! let result = encoder.encode()?;
+//!
+//! assert_eq!(result.recovery(0).unwrap(), [0xFF; 64]);
+//! assert_eq!(result.recovery(1).unwrap(), [0xF0; 64]);
+//! # Ok::<(), reed_solomon_simd::Error>(())
+//! ```
+//!
+//! [simple usage]: crate#simple-usage
+//! [basic usage]: crate#basic-usage
+//! [`ReedSolomonDecoder`]: crate::ReedSolomonDecoder
+
+use crate::{
+    engine::{DefaultEngine, Engine, GfElement},
+    rate::EncoderWork,
+    EncoderResult, Error, UnsupportedReason,
+};
+
+// ======================================================================
+// MatrixEncoder - PUBLIC
+
+/// Encoder which uses an explicit generator matrix.
+///
+/// See [module documentation](self) for details.
+pub struct MatrixEncoder<E: Engine = DefaultEngine> {
+    engine: E,
+    // Transposed matrix, i.e. coefficients of recovery shard `j`
+    // are at `j * original_count ..`.
+    coeffs: Vec<GfElement>,
+    work: EncoderWork,
+}
+
+impl MatrixEncoder {
+    /// Creates new encoder with [`DefaultEngine`].
+    ///
+    /// `matrix` has one row for each original shard and
+    /// one column for each recovery shard, so that
+    /// recovery shard `j` is the sum of `matrix[i][j] * original[i]`.
+    /// Every [`GfElement`] is a valid coefficient.
+    pub fn new(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        matrix: &[Vec<GfElement>],
+    ) -> Result<Self, Error> {
+        Self::with_engine(
+            original_count,
+            recovery_count,
+            shard_bytes,
+            matrix,
+            DefaultEngine::new(),
+        )
+    }
+}
+
+impl<E: Engine> MatrixEncoder<E> {
+    /// Adds one original shard to the encoder.
+    ///
+    /// Original shards have indexes `0..original_count` corresponding
+    /// to the order in which they are added, i.e. rows of the matrix.
+    pub fn add_original_shard<T: AsRef<[u8]>>(&mut self, original_shard: T) -> Result<(), Error> {
+        self.work.add_original_shard(original_shard)
+    }
+
+    /// Encodes the added original shards returning [`EncoderResult`]
+    /// which contains the generated recovery shards.
+    ///
+    /// When returned [`EncoderResult`] is dropped the encoder is
+    /// automatically reset and ready for new round of encoding.
+    pub fn encode(&mut self) -> Result<EncoderResult, Error> {
+        let (mut work, original_count, recovery_count) = self.work.encode_begin()?;
+
+        // work[               .. original_count] = original
+        // work[original_count ..               ] = recovery
+
+        let (original, mut recovery) = work.split_at_mut(original_count);
+        let inputs: Vec<&[u8]> = (0..original_count).map(|i| &original[i]).collect();
+        for (j, coeffs) in self.coeffs.chunks_exact(original_count).enumerate() {
+            self.engine
+                .linear_combination(&mut recovery[j], &inputs, coeffs);
+        }
+
+        work.copy_within(original_count, 0, recovery_count);
+
+        Ok(EncoderResult::new(&mut self.work))
+    }
+
+    /// Like [`new`](MatrixEncoder::new) with given [`Engine`].
+    pub fn with_engine(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        matrix: &[Vec<GfElement>],
+        engine: E,
+    ) -> Result<Self, Error> {
+        if let Some(reason) = UnsupportedReason::zero_count(original_count, recovery_count) {
+            return Err(Error::UnsupportedShardCount {
+                original_count,
+                recovery_count,
+                reason,
+                hint: None,
+            });
+        }
+
+        if shard_bytes == 0 || shard_bytes & 63 != 0 {
+            return Err(Error::InvalidShardSize { shard_bytes });
+        }
+
+        let columns = matrix
+            .iter()
+            .map(|row| row.len())
+            .find(|&len| len != recovery_count)
+            .unwrap_or(recovery_count);
+        if matrix.len() != original_count || columns != recovery_count {
+            return Err(Error::InvalidMatrixShape {
+                original_count,
+                recovery_count,
+                rows: matrix.len(),
+                columns,
+            });
+        }
+
+        let coeffs = (0..recovery_count)
+            .flat_map(|j| matrix.iter().map(move |row| row[j]))
+            .collect();
+
+        let mut work = EncoderWork::new();
+        work.reset(
+            original_count,
+            recovery_count,
+            shard_bytes,
+            original_count + recovery_count,
+        )?;
+
+        Ok(Self {
+            engine,
+            coeffs,
+            work,
+        })
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
+mod tests {
+    use super::*;
+    use crate::{engine::NoSimd, gf::Gf16, test_util};
+
+    // Generator matrix of low rate encoding, i.e. `V_r * V_o^-1`
+    // where `V_o` and `V_r` are Vandermonde matrices of the original
+    // points `0 .. chunk_size` and recovery points `chunk_size ..`.
+    // - Computed with Lagrange basis polynomials,
+    //   which are the columns of `V_o^-1`.
+    fn low_rate_matrix(original_count: usize, recovery_count: usize) -> Vec<Vec<GfElement>> {
+        let chunk_size = original_count.next_power_of_two();
+        let points = || (0..chunk_size).map(|p| Gf16(p as GfElement));
+
+        (0..original_count)
+            .map(|i| {
+                let i = Gf16(i as GfElement);
+                (chunk_size..chunk_size + recovery_count)
+                    .map(|x| {
+                        let x = Gf16(x as GfElement);
+                        points()
+                            .filter(|&p| p != i)
+                            .fold(Gf16::ONE, |l, p| l * (x - p) * (i - p).inv().unwrap())
+                            .0
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn vandermonde_matches_fft_encode() {
+        for (original_count, recovery_count) in [(2, 3), (3, 5), (4, 7)] {
+            let original = test_util::generate_original(original_count, 1024, 168);
+            // These counts use low rate.
+            let expected = crate::encode(original_count, recovery_count, &original).unwrap();
+
+            let matrix = low_rate_matrix(original_count, recovery_count);
+            let mut encoder = MatrixEncoder::with_engine(
+                original_count,
+                recovery_count,
+                1024,
+                &matrix,
+                NoSimd::new(),
+            )
+            .unwrap();
+            for shard in &original {
+                encoder.add_original_shard(shard).unwrap();
+            }
+
+            let result = encoder.encode().unwrap();
+            let recovery: Vec<_> = result.recovery_iter().map(|s| s.to_vec()).collect();
+            assert_eq!(recovery, expected);
+        }
+    }
+
+    #[test]
+    fn invalid_matrix_shape() {
+        assert_eq!(
+            MatrixEncoder::new(2, 3, 64, &[vec![1, 2, 3]]).err(),
+            Some(Error::InvalidMatrixShape {
+                original_count: 2,
+                recovery_count: 3,
+                rows: 1,
+                columns: 3,
+            })
+        );
+
+        assert_eq!(
+            MatrixEncoder::new(2, 3, 64, &[vec![1, 2, 3], vec![1, 2]]).err(),
+            Some(Error::InvalidMatrixShape {
+                original_count: 2,
+                recovery_count: 3,
+                rows: 2,
+                columns: 2,
+            })
+        );
+
+        assert_eq!(
+            MatrixEncoder::new(0, 3, 64, &[]).err(),
+            Some(Error::UnsupportedShardCount {
+                original_count: 0,
+                recovery_count: 3,
+                reason: UnsupportedReason::NoOriginalShards,
+                hint: None,
+            })
+        );
+    }
+}
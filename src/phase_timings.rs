@@ -0,0 +1,111 @@
+use std::time::{Duration, Instant};
+
+use crate::rate::{
+    PROGRESS_FFT, PROGRESS_FORMAL_DERIVATIVE, PROGRESS_IFFT, PROGRESS_MULTIPLY, PROGRESS_REVEAL,
+};
+
+// ======================================================================
+// PhaseTimings - PUBLIC
+
+/// Time taken by each phase of one encode/decode call.
+///
+/// This is returned by `ReedSolomonEncoder::encode_timed`
+/// and `ReedSolomonDecoder::decode_timed`, e.g. to see whether
+/// decoding time goes to fixed-cost steps (`eval_poly`)
+/// or to steps which scale with shard data (`ifft` / `fft`).
+/// See [algorithm](crate::algorithm) for what the phases do.
+///
+/// Phases are contiguous, so [`total`] is the duration of the whole call.
+/// Phases which aren't part of encoding are zero for encoders.
+/// There is no copy-out phase as both results borrow the working space.
+///
+/// [`total`]: PhaseTimings::total
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    /// Preparing the working space.
+    /// For decoder this includes copying owned shards into it.
+    pub copy_in: Duration,
+    /// Computing erasure locations and evaluating error locator polynomial.
+    /// Decoder only.
+    pub eval_poly: Duration,
+    /// Multiplying received shards with the polynomial. Decoder only.
+    pub multiply: Duration,
+    /// Inverse FFT.
+    pub ifft: Duration,
+    /// Formal derivative. Decoder only.
+    pub formal_derivative: Duration,
+    /// FFT. For encoder this includes writing integrity footers.
+    pub fft: Duration,
+    /// Revealing erased original shards.
+    /// This includes cross-checking untrusted shards. Decoder only.
+    pub reveal: Duration,
+}
+
+impl PhaseTimings {
+    /// Returns sum of all phases.
+    pub fn total(&self) -> Duration {
+        self.copy_in
+            + self.eval_poly
+            + self.multiply
+            + self.ifft
+            + self.formal_derivative
+            + self.fft
+            + self.reveal
+    }
+}
+
+// ======================================================================
+// PhaseTimer - CRATE
+
+// Splits time between progress checkpoints of encoders/decoders into phases.
+pub(crate) struct PhaseTimer {
+    last: Instant,
+    // Progress at the latest checkpoint, `None` before first one.
+    progress: Option<f32>,
+    timings: PhaseTimings,
+}
+
+impl PhaseTimer {
+    pub(crate) fn new() -> Self {
+        Self {
+            last: Instant::now(),
+            progress: None,
+            timings: PhaseTimings::default(),
+        }
+    }
+
+    // Ends current phase and begins the one starting at `progress`.
+    pub(crate) fn checkpoint(&mut self, progress: f32) {
+        self.end_phase();
+        self.progress = Some(progress);
+    }
+
+    // Ends current phase and returns the timings.
+    pub(crate) fn finish(mut self) -> PhaseTimings {
+        self.end_phase();
+        self.timings
+    }
+}
+
+// ======================================================================
+// PhaseTimer - PRIVATE
+
+impl PhaseTimer {
+    fn end_phase(&mut self) {
+        let now = Instant::now();
+        let elapsed = now - self.last;
+        self.last = now;
+
+        let timings = &mut self.timings;
+        let phase = match self.progress {
+            None => &mut timings.copy_in,
+            Some(progress) if progress < PROGRESS_MULTIPLY => &mut timings.eval_poly,
+            Some(progress) if progress < PROGRESS_IFFT => &mut timings.multiply,
+            Some(progress) if progress < PROGRESS_FORMAL_DERIVATIVE => &mut timings.ifft,
+            Some(progress) if progress < PROGRESS_FFT => &mut timings.formal_derivative,
+            Some(progress) if progress < PROGRESS_REVEAL => &mut timings.fft,
+            Some(_) => &mut timings.reveal,
+        };
+        *phase += elapsed;
+    }
+}
@@ -3,8 +3,11 @@
 //! **This is an advanced module which is not needed for [simple usage] or [basic usage].**
 //!
 //! This module is relevant if you want to
-//! - encode/decode using other [`Engine`] than [`DefaultEngine`].
+//! - encode/decode using other [`Engine`] than [`DefaultEngine`],
+//!   possibly shared by several encoders/decoders as `&engine`.
 //! - re-use working space of one encoder/decoder in another.
+//! - encode in another thread with [`encode_work`] while the encoder
+//!   receives the next stripe.
 //! - understand/benchmark/test high or low rate directly.
 //!
 //! # Rates
@@ -20,6 +23,44 @@
 //!     - Encoding/decoding using only high rate.
 //! - [`LowRate`], [`LowRateEncoder`], [`LowRateDecoder`]
 //!     - Encoding/decoding using only low rate.
+//! - [`encode_at_positions`], [`PositionDecoder`]
+//!     - Low rate encoding/decoding at arbitrary positions
+//!       instead of the standard recovery shards.
+//!
+//! # Generic code
+//!
+//! [`ReedSolomonEncoder`] and [`ReedSolomonDecoder`] also implement
+//! [`RateEncoder`] and [`RateDecoder`] with [`DefaultEngine`],
+//! so code generic over these traits works with all encoders/decoders.
+//!
+//! ```rust
+//! # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+//! use reed_solomon_simd::{
+//!     engine::{Engine, NoSimd},
+//!     rate::{HighRateEncoder, RateEncoder},
+//!     ReedSolomonEncoder,
+//! };
+//!
+//! fn recovery<E: Engine, R: RateEncoder<E>>(
+//!     encoder: &mut R,
+//!     original: &[[u8; 64]],
+//! ) -> Result<Vec<Vec<u8>>, reed_solomon_simd::Error> {
+//!     for shard in original {
+//!         encoder.add_original_shard(shard)?;
+//!     }
+//!     let result = encoder.encode()?;
+//!     Ok(result.recovery_iter().map(|shard| shard.to_vec()).collect())
+//! }
+//!
+//! let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+//!
+//! let mut simple = ReedSolomonEncoder::new(3, 2, 64)?;
+//! let mut high = HighRateEncoder::new(3, 2, 64, NoSimd::new(), None)?;
+//!
+//! assert_eq!(recovery(&mut simple, &original)?, recovery(&mut high, &original)?);
+//! # }
+//! # Ok::<(), reed_solomon_simd::Error>(())
+//! ```
 //!
 //! [simple usage]: crate#simple-usage
 //! [basic usage]: crate#basic-usage
@@ -28,18 +69,40 @@
 //! [`ReedSolomonDecoder`]: crate::ReedSolomonDecoder
 //! [`DefaultEngine`]: crate::engine::DefaultEngine
 
-use crate::{engine::Engine, DecoderResult, EncoderResult, Error};
+#[cfg(feature = "decoder")]
+use std::ops::ControlFlow;
+
+use crate::{engine::Engine, Error, UnsupportedReason};
+#[cfg(feature = "encoder")]
+use crate::{
+    integrity::{self, IntegrityFooter},
+    EncoderResult,
+};
+#[cfg(feature = "decoder")]
+use crate::{DecoderResult, ShardWriter};
 
+#[cfg(feature = "decoder")]
+pub use self::{
+    decoder_work::{DecoderWork, LoadError},
+    positions::PositionDecoder,
+    rate_default::DefaultRateDecoder,
+    rate_high::HighRateDecoder,
+    rate_low::LowRateDecoder,
+};
+#[cfg(feature = "encoder")]
 pub use self::{
-    decoder_work::DecoderWork,
-    encoder_work::EncoderWork,
-    rate_default::{DefaultRate, DefaultRateDecoder, DefaultRateEncoder},
-    rate_high::{HighRate, HighRateDecoder, HighRateEncoder},
-    rate_low::{LowRate, LowRateDecoder, LowRateEncoder},
+    encoder_work::EncoderWork, positions::encode_at_positions, rate_default::DefaultRateEncoder,
+    rate_high::HighRateEncoder, rate_low::LowRateEncoder,
 };
+pub use self::{rate_default::DefaultRate, rate_high::HighRate, rate_low::LowRate};
+
+pub(crate) use self::rate_default::{max_shard_count, use_high_rate};
 
+#[cfg(feature = "decoder")]
 mod decoder_work;
+#[cfg(feature = "encoder")]
 mod encoder_work;
+mod positions;
 mod rate_default;
 mod rate_high;
 mod rate_low;
@@ -53,8 +116,10 @@ pub trait Rate<E: Engine> {
     // REQUIRED
 
     /// Encoder of this rate.
+    #[cfg(feature = "encoder")]
     type RateEncoder: RateEncoder<E>;
     /// Decoder of this rate.
+    #[cfg(feature = "decoder")]
     type RateDecoder: RateDecoder<E>;
 
     /// Returns `true` if given `original_count` / `recovery_count`
@@ -65,6 +130,7 @@ pub trait Rate<E: Engine> {
     // PROVIDED
 
     /// Creates new encoder. This is same as [`RateEncoder::new`].
+    #[cfg(feature = "encoder")]
     fn encoder(
         original_count: usize,
         recovery_count: usize,
@@ -76,6 +142,7 @@ pub trait Rate<E: Engine> {
     }
 
     /// Creates new decoder. This is same as [`RateDecoder::new`].
+    #[cfg(feature = "decoder")]
     fn decoder(
         original_count: usize,
         recovery_count: usize,
@@ -86,6 +153,23 @@ pub trait Rate<E: Engine> {
         Self::RateDecoder::new(original_count, recovery_count, shard_bytes, engine, work)
     }
 
+    /// Returns reason why given `original_count` / `recovery_count`
+    /// combination is not supported, or `None` if it is supported.
+    ///
+    /// Default implementation returns [`UnsupportedReason::TooManyShards`]
+    /// for unsupported non-zero counts.
+    fn unsupported_reason(
+        original_count: usize,
+        recovery_count: usize,
+    ) -> Option<UnsupportedReason> {
+        if Self::supports(original_count, recovery_count) {
+            None
+        } else {
+            UnsupportedReason::zero_count(original_count, recovery_count)
+                .or(Some(UnsupportedReason::TooManyShards))
+        }
+    }
+
     /// Returns `Ok(())` if given `original_count` / `recovery_count`
     /// combination is supported and given `shard_bytes` is valid.
     fn validate(
@@ -93,13 +177,19 @@ pub trait Rate<E: Engine> {
         recovery_count: usize,
         shard_bytes: usize,
     ) -> Result<(), Error> {
-        if !Self::supports(original_count, recovery_count) {
-            Err(Error::UnsupportedShardCount {
+        if let Some(reason) = Self::unsupported_reason(original_count, recovery_count) {
+            Error::UnsupportedShardCount {
                 original_count,
                 recovery_count,
-            })
-        } else if shard_bytes == 0 || shard_bytes & 63 != 0 {
-            Err(Error::InvalidShardSize { shard_bytes })
+                reason,
+                hint: RateKind::supporting::<E>(original_count, recovery_count),
+            }
+            .into_cold_err()
+        } else if shard_bytes == 0
+            || shard_bytes & 63 != 0
+            || shard_bytes > max_shard_bytes(original_count, recovery_count)
+        {
+            Error::InvalidShardSize { shard_bytes }.into_cold_err()
         } else {
             Ok(())
         }
@@ -110,6 +200,7 @@ pub trait Rate<E: Engine> {
 // RateEncoder - PUBLIC
 
 /// Reed-Solomon encoder using specific rate.
+#[cfg(feature = "encoder")]
 pub trait RateEncoder<E: Engine>
 where
     Self: Sized,
@@ -172,10 +263,289 @@ where
     }
 }
 
+// ======================================================================
+// DynRateEncoder - PUBLIC
+
+/// Object-safe subset of [`RateEncoder`], returned by [`new_encoder`].
+///
+/// This allows holding [`HighRateEncoder`] or [`LowRateEncoder`]
+/// without knowing at compile time which one is used.
+///
+/// Methods are same as those of [`RateEncoder`] but with `&[u8]`
+/// instead of generic shard type.
+#[cfg(feature = "encoder")]
+pub trait DynRateEncoder {
+    /// Like [`RateEncoder::add_original_shard`].
+    fn add_original_shard(&mut self, original_shard: &[u8]) -> Result<(), Error>;
+
+    /// Like [`RateEncoder::encode`].
+    fn encode(&mut self) -> Result<EncoderResult, Error>;
+
+    /// Returns rate used by this encoder.
+    fn rate_kind(&self) -> RateKind;
+
+    /// Like [`RateEncoder::reset`].
+    ///
+    /// Returns [`Error::UnsupportedShardCount`] with `hint` set
+    /// if the new counts require the other rate.
+    fn reset(
+        &mut self,
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<(), Error>;
+}
+
+#[cfg(feature = "encoder")]
+impl<E: Engine> DynRateEncoder for HighRateEncoder<E> {
+    fn add_original_shard(&mut self, original_shard: &[u8]) -> Result<(), Error> {
+        RateEncoder::add_original_shard(self, original_shard)
+    }
+
+    fn encode(&mut self) -> Result<EncoderResult, Error> {
+        RateEncoder::encode(self)
+    }
+
+    fn rate_kind(&self) -> RateKind {
+        RateKind::High
+    }
+
+    fn reset(
+        &mut self,
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<(), Error> {
+        RateEncoder::reset(self, original_count, recovery_count, shard_bytes)
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl<E: Engine> DynRateEncoder for LowRateEncoder<E> {
+    fn add_original_shard(&mut self, original_shard: &[u8]) -> Result<(), Error> {
+        RateEncoder::add_original_shard(self, original_shard)
+    }
+
+    fn encode(&mut self) -> Result<EncoderResult, Error> {
+        RateEncoder::encode(self)
+    }
+
+    fn rate_kind(&self) -> RateKind {
+        RateKind::Low
+    }
+
+    fn reset(
+        &mut self,
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<(), Error> {
+        RateEncoder::reset(self, original_count, recovery_count, shard_bytes)
+    }
+}
+
+// ======================================================================
+// RateKind - PUBLIC
+
+/// Kind of a [`Rate`], see [algorithm > Rate].
+///
+/// Used in `hint` of [`Error::UnsupportedShardCount`].
+///
+/// [algorithm > Rate]: crate::algorithm#rate
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RateKind {
+    /// [`HighRate`].
+    High,
+    /// [`LowRate`].
+    Low,
+}
+
+// ======================================================================
+// RateKind - CRATE
+
+impl RateKind {
+    // Returns rate which supports given counts,
+    // preferring high rate if both do.
+    pub(crate) fn supporting<E: Engine>(
+        original_count: usize,
+        recovery_count: usize,
+    ) -> Option<Self> {
+        if HighRate::<E>::supports(original_count, recovery_count) {
+            Some(RateKind::High)
+        } else if LowRate::<E>::supports(original_count, recovery_count) {
+            Some(RateKind::Low)
+        } else {
+            None
+        }
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PUBLIC
+
+/// Encodes original shards in working space taken from an encoder
+/// with [`ReedSolomonEncoder::take_work`].
+///
+/// This allows encoding a stripe in another thread while
+/// the encoder receives original shards of the next stripe.
+/// Rate is chosen like [`DefaultRateEncoder`] does, so `work` must
+/// come from [`ReedSolomonEncoder`] or [`DefaultRateEncoder`].
+/// If `work` was taken from an encoder with integrity footer enabled,
+/// the footer is written into recovery shards like the encoder does.
+///
+/// When returned [`EncoderResult`] is dropped the working space
+/// is ready to be given back with [`ReedSolomonEncoder::restore_work`].
+///
+/// Returns [`Error::WorkMismatch`] if `params` isn't the configuration of `work`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::thread;
+/// use reed_solomon_simd::{engine::DefaultEngine, rate, CodecParams, ReedSolomonEncoder};
+///
+/// let params = CodecParams::new(2, 1, 64);
+/// let mut encoder = ReedSolomonEncoder::from_params(params)?;
+/// encoder.add_original_shard([1u8; 64])?;
+/// encoder.add_original_shard([2u8; 64])?;
+/// let mut work = encoder.take_work()?;
+///
+/// let worker = thread::spawn(move || {
+///     let result = rate::encode_work(&DefaultEngine::new(), params, &mut work)?;
+///     let recovery = result.recovery(0).unwrap().to_vec();
+///     drop(result);
+///     Ok::<_, reed_solomon_simd::Error>((recovery, work))
+/// });
+///
+/// // Encoder is already usable for the next stripe.
+/// encoder.add_original_shard([3u8; 64])?;
+///
+/// let (recovery, work) = worker.join().unwrap()?;
+/// encoder.restore_work(work);
+/// assert_eq!(recovery, reed_solomon_simd::encode(2, 1, [[1u8; 64], [2u8; 64]])?[0]);
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+///
+/// [`ReedSolomonEncoder`]: crate::ReedSolomonEncoder
+/// [`ReedSolomonEncoder::take_work`]: crate::ReedSolomonEncoder::take_work
+/// [`ReedSolomonEncoder::restore_work`]: crate::ReedSolomonEncoder::restore_work
+#[cfg(feature = "encoder")]
+pub fn encode_work<'a, E: Engine>(
+    engine: &E,
+    params: crate::CodecParams,
+    work: &'a mut EncoderWork,
+) -> Result<EncoderResult<'a>, Error> {
+    let expected = crate::CodecParams::new(
+        work.original_count(),
+        work.recovery_count(),
+        work.shard_bytes(),
+    );
+    if params != expected {
+        return Err(Error::WorkMismatch {
+            expected,
+            got: params,
+        });
+    }
+
+    let footer = work
+        .footer_stripe_id()
+        .map(|stripe_id| IntegrityFooter::new(params, stripe_id));
+
+    let mut result = if use_high_rate(params)? {
+        HighRateEncoder::encode_work(engine, work, |_| {})
+    } else {
+        LowRateEncoder::encode_work(engine, work, |_| {})
+    }?;
+
+    if let Some(footer) = footer {
+        integrity::write_footer(footer, &mut result);
+    }
+
+    Ok(result)
+}
+
+/// Creates new encoder using high or low rate as appropriate.
+///
+/// Rate is chosen like [`DefaultRateEncoder`] does,
+/// but the encoder is returned as [`DynRateEncoder`]
+/// so that [`DynRateEncoder::rate_kind`] tells which one was chosen.
+///
+/// ```rust
+/// use reed_solomon_simd::{
+///     engine::NoSimd,
+///     rate::{self, RateKind},
+/// };
+///
+/// let mut encoder = rate::new_encoder(3, 5, 64, NoSimd::new(), None)?;
+/// assert_eq!(encoder.rate_kind(), RateKind::Low);
+///
+/// for original in [[1u8; 64], [2u8; 64], [3u8; 64]] {
+///     encoder.add_original_shard(&original)?;
+/// }
+/// assert_eq!(encoder.encode()?.recovery_iter().count(), 5);
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+#[cfg(feature = "encoder")]
+pub fn new_encoder<'a, E: Engine + 'a>(
+    original_count: usize,
+    recovery_count: usize,
+    shard_bytes: usize,
+    engine: E,
+    work: Option<EncoderWork>,
+) -> Result<Box<dyn DynRateEncoder + 'a>, Error> {
+    let params = crate::CodecParams::new(original_count, recovery_count, shard_bytes);
+    if use_high_rate(params)? {
+        let encoder =
+            HighRateEncoder::new(original_count, recovery_count, shard_bytes, engine, work)?;
+        Ok(Box::new(encoder))
+    } else {
+        let encoder =
+            LowRateEncoder::new(original_count, recovery_count, shard_bytes, engine, work)?;
+        Ok(Box::new(encoder))
+    }
+}
+
+// ======================================================================
+// CONST - CRATE
+
+// Progress reported by decoders before each step of decoding,
+// roughly proportional to the time taken by the earlier steps.
+// - Encoders report `PROGRESS_IFFT` and `PROGRESS_FFT` before these steps,
+//   so that `PhaseTimer` can tell the phases apart.
+pub(crate) const PROGRESS_MULTIPLY: f32 = 0.05;
+pub(crate) const PROGRESS_IFFT: f32 = 0.15;
+pub(crate) const PROGRESS_FORMAL_DERIVATIVE: f32 = 0.5;
+pub(crate) const PROGRESS_FFT: f32 = 0.55;
+pub(crate) const PROGRESS_REVEAL: f32 = 0.9;
+
+// ======================================================================
+// FUNCTIONS - CRATE
+
+// Returns largest `shard_bytes` for which working space of any rate,
+// encoder or decoder, fits in `isize::MAX` bytes.
+// - Work count is at most `2 * (original_count + recovery_count).next_power_of_two()`.
+pub(crate) fn max_shard_bytes(original_count: usize, recovery_count: usize) -> usize {
+    (isize::MAX as usize) / (2 * (original_count + recovery_count).next_power_of_two())
+}
+
+// Reports `progress` to `control`,
+// returning `Error::Cancelled` if it asks to stop.
+#[cfg(feature = "decoder")]
+pub(crate) fn checkpoint<F: FnMut(f32) -> ControlFlow<()>>(
+    control: &mut F,
+    progress: f32,
+) -> Result<(), Error> {
+    match control(progress) {
+        ControlFlow::Continue(()) => Ok(()),
+        ControlFlow::Break(()) => Err(Error::Cancelled),
+    }
+}
+
 // ======================================================================
 // RateDecoder - PUBLIC
 
 /// Reed-Solomon decoder using specific rate.
+#[cfg(feature = "decoder")]
 pub trait RateDecoder<E: Engine>
 where
     Self: Sized,
@@ -200,9 +570,21 @@ where
         recovery_shard: T,
     ) -> Result<(), Error>;
 
+    /// Like [`ReedSolomonDecoder::begin_original_shard`](crate::ReedSolomonDecoder::begin_original_shard).
+    fn begin_original_shard(&mut self, index: usize) -> Result<ShardWriter, Error>;
+
+    /// Like [`ReedSolomonDecoder::begin_recovery_shard`](crate::ReedSolomonDecoder::begin_recovery_shard).
+    fn begin_recovery_shard(&mut self, index: usize) -> Result<ShardWriter, Error>;
+
     /// Like [`ReedSolomonDecoder::decode`](crate::ReedSolomonDecoder::decode).
     fn decode(&mut self) -> Result<DecoderResult, Error>;
 
+    /// Like [`ReedSolomonDecoder::decode_with_control`](crate::ReedSolomonDecoder::decode_with_control).
+    fn decode_with_control<F: FnMut(f32) -> ControlFlow<()>>(
+        &mut self,
+        control: F,
+    ) -> Result<DecoderResult, Error>;
+
     /// Consumes this decoder returning its [`Engine`] and [`DecoderWork`]
     /// so that they can be re-used by another decoder.
     fn into_parts(self) -> (E, DecoderWork);
@@ -248,3 +630,117 @@ where
         Self::Rate::validate(original_count, recovery_count, shard_bytes)
     }
 }
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
+mod tests {
+    use super::*;
+    use crate::{engine::NoSimd, test_util};
+
+    // ============================================================
+    // new_encoder
+
+    #[test]
+    fn new_encoder_crossover() {
+        for (original_count, recovery_count, expected) in [
+            (5, 3, RateKind::High),
+            (4, 3, RateKind::Low),
+            (3, 3, RateKind::High),
+            (3, 4, RateKind::High),
+            (3, 5, RateKind::Low),
+        ] {
+            let original = test_util::generate_original(original_count, 64, 0);
+
+            let mut encoder =
+                new_encoder(original_count, recovery_count, 64, NoSimd::new(), None).unwrap();
+            assert_eq!(encoder.rate_kind(), expected);
+
+            for original in &original {
+                encoder.add_original_shard(original).unwrap();
+            }
+            let recovery: Vec<_> = encoder
+                .encode()
+                .unwrap()
+                .recovery_iter()
+                .map(|shard| shard.to_vec())
+                .collect();
+
+            assert_eq!(
+                recovery,
+                crate::encode(original_count, recovery_count, &original).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn new_encoder_errors() {
+        assert_eq!(
+            new_encoder(4097, 61440, 64, NoSimd::new(), None).err(),
+            Some(Error::UnsupportedShardCount {
+                original_count: 4097,
+                recovery_count: 61440,
+                reason: UnsupportedReason::TooManyShards,
+                hint: None,
+            })
+        );
+
+        assert_eq!(
+            new_encoder(3, 5, 123, NoSimd::new(), None).err(),
+            Some(Error::InvalidShardSize { shard_bytes: 123 })
+        );
+    }
+
+    // ============================================================
+    // hint
+
+    #[test]
+    fn hint_crossover() {
+        // High rate rejects, low rate accepts.
+        let mut encoder = HighRateEncoder::new(3, 4, 64, NoSimd::new(), None).unwrap();
+        assert_eq!(
+            DynRateEncoder::reset(&mut encoder, 3, 65532, 64),
+            Err(Error::UnsupportedShardCount {
+                original_count: 3,
+                recovery_count: 65532,
+                reason: UnsupportedReason::HighRateLimit,
+                hint: Some(RateKind::Low),
+            })
+        );
+
+        // Low rate rejects, high rate accepts.
+        let mut encoder = LowRateEncoder::new(4, 3, 64, NoSimd::new(), None).unwrap();
+        assert_eq!(
+            DynRateEncoder::reset(&mut encoder, 65532, 3, 64),
+            Err(Error::UnsupportedShardCount {
+                original_count: 65532,
+                recovery_count: 3,
+                reason: UnsupportedReason::LowRateLimit,
+                hint: Some(RateKind::High),
+            })
+        );
+
+        // Neither rate accepts.
+        assert_eq!(
+            HighRate::<NoSimd>::validate(4097, 61440, 64),
+            Err(Error::UnsupportedShardCount {
+                original_count: 4097,
+                recovery_count: 61440,
+                reason: UnsupportedReason::HighRateLimit,
+                hint: None,
+            })
+        );
+    }
+
+    #[test]
+    fn hint_display() {
+        let err = LowRate::<NoSimd>::validate(61440, 4096, 64).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unsupported shard count: 61440 original shards with 4096 recovery shards \
+             (low rate requires original_count.next_power_of_two() + recovery_count <= 65536), \
+             use high rate instead"
+        );
+    }
+}
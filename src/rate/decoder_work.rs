@@ -1,15 +1,42 @@
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
 use fixedbitset::FixedBitSet;
 
+#[cfg(feature = "allocator-api")]
+use std::alloc::Allocator;
+
 use crate::{
-    engine::{Shards, ShardsRefMut},
-    Error,
+    engine::{Engine, GfElement, Shards, ShardsRefMut, GF_ORDER},
+    gf::Gf16,
+    repair::ShardId,
+    CodecParams, Error,
 };
 
+// ======================================================================
+// CONST - PRIVATE
+
+// First bytes of data written by `DecoderWork::save`.
+const SAVE_MAGIC: [u8; 4] = *b"RSDW";
+
+// Version of the format written by `DecoderWork::save`.
+const SAVE_VERSION: u32 = 1;
+
+// Most missing original shards which `DecoderWork::peel` restores,
+// see `ReedSolomonDecoder::decode_peeling`.
+const PEELING_MAX_MISSING: usize = 2;
+
 // ======================================================================
 // DecoderWork - PUBLIC
 
 /// Working space for [`RateDecoder`].
 ///
+/// Besides the shards this includes a 128 kiB buffer for erasure locations,
+/// which is allocated on heap by the first decoding and then re-used,
+/// so decoding doesn't need large stack frames.
+///
 /// [`RateDecoder`]: crate::rate::RateDecoder
 pub struct DecoderWork {
     original_count: usize,
@@ -23,10 +50,30 @@ pub struct DecoderWork {
     recovery_received_count: usize,
     // May contain extra zero bits.
     received: FixedBitSet,
+    // Recovery shards by index which were not copied
+    // as enough shards had been received, see `add_recovery_shard`.
+    skipped: FixedBitSet,
     shards: Shards,
+
+    // Erasure locations, allocated by first `decode_begin`.
+    erasures: Option<Box<[GfElement; GF_ORDER]>>,
 }
 
 impl DecoderWork {
+    /// Returns number of bytes currently allocated for working space.
+    ///
+    /// This may be more than current configuration needs,
+    /// see [`shrink_to_fit`](Self::shrink_to_fit).
+    pub fn allocated_bytes(&self) -> usize {
+        let erasures_bytes = if self.erasures.is_some() {
+            GF_ORDER * std::mem::size_of::<GfElement>()
+        } else {
+            0
+        };
+
+        self.shards.allocated_bytes() + erasures_bytes
+    }
+
     /// Creates new [`DecoderWork`] which initially
     /// has no working space allocated.
     pub fn new() -> Self {
@@ -41,8 +88,100 @@ impl DecoderWork {
             original_received_count: 0,
             recovery_received_count: 0,
             received: FixedBitSet::new(),
+            skipped: FixedBitSet::new(),
             shards: Shards::new(),
+
+            erasures: None,
+        }
+    }
+
+    /// Creates new [`DecoderWork`] which allocates
+    /// its working space with given allocator.
+    ///
+    /// Requires nightly Rust and `allocator-api` feature.
+    #[cfg(feature = "allocator-api")]
+    pub fn new_in<A: Allocator + Send + Sync + 'static>(alloc: A) -> Self {
+        Self {
+            shards: Shards::new_in(alloc),
+            ..Self::new()
+        }
+    }
+
+    /// Replaces received shards with those saved by [`save`].
+    ///
+    /// Saved configuration must be the same as current configuration
+    /// of this working space, i.e. the decoder it's attached to,
+    /// otherwise [`LoadError::ParameterMismatch`] is returned.
+    /// Saved shards may come from a decoder with different rate.
+    ///
+    /// On error all received shards are forgotten,
+    /// so they must be loaded or added again.
+    ///
+    /// [`save`]: DecoderWork::save
+    pub fn load<R: Read>(&mut self, mut reader: R) -> Result<(), LoadError> {
+        self.reset_received();
+        let result = self.load_shards(&mut reader);
+        if result.is_err() {
+            self.reset_received();
+        }
+        result
+    }
+
+    /// Writes received shards and configuration so that
+    /// they can be restored with [`load`], e.g. to continue
+    /// adding shards after the process has been restarted.
+    ///
+    /// Format is little-endian and versioned:
+    ///
+    /// - `b"RSDW"` and version `1` as `u32`.
+    /// - `original_count`, `recovery_count` and `shard_bytes` as `u64`.
+    /// - Number of received original shards as `u64`,
+    ///   followed by index as `u64` and content of each.
+    /// - Same for received recovery shards.
+    ///
+    /// Content of received shards is only valid before decoding,
+    /// so this must not be called while a [`DecoderResult`] exists
+    /// (which the borrow checker prevents anyway).
+    ///
+    /// [`load`]: DecoderWork::load
+    /// [`DecoderResult`]: crate::DecoderResult
+    pub fn save<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&SAVE_MAGIC)?;
+        writer.write_all(&SAVE_VERSION.to_le_bytes())?;
+        for value in [self.original_count, self.recovery_count, self.shard_bytes] {
+            writer.write_all(&(value as u64).to_le_bytes())?;
+        }
+
+        for (base_pos, count, received_count) in [
+            (
+                self.original_base_pos,
+                self.original_count,
+                self.original_received_count,
+            ),
+            (
+                self.recovery_base_pos,
+                self.recovery_count,
+                self.recovery_received_count,
+            ),
+        ] {
+            writer.write_all(&(received_count as u64).to_le_bytes())?;
+            for index in (0..count).filter(|index| self.received[base_pos + index]) {
+                writer.write_all(&(index as u64).to_le_bytes())?;
+                writer.write_all(&self.shards[base_pos + index])?;
+            }
         }
+
+        Ok(())
+    }
+
+    /// Releases working space which current configuration doesn't need.
+    ///
+    /// Working space is never shrunk automatically, so after handling
+    /// a large configuration it stays allocated for re-use.
+    /// This lowers memory usage at the cost of re-allocation
+    /// if a larger configuration is used again.
+    pub fn shrink_to_fit(&mut self) {
+        self.shards.shrink_to_fit();
     }
 }
 
@@ -64,84 +203,278 @@ impl DecoderWork {
         index: usize,
         original_shard: T,
     ) -> Result<(), Error> {
-        let pos = self.original_base_pos + index;
-        let original_shard = original_shard.as_ref();
-
-        if index >= self.original_count {
-            Err(Error::InvalidOriginalShardIndex {
-                original_count: self.original_count,
-                index,
-            })
-        } else if self.received[pos] {
-            Err(Error::DuplicateOriginalShardIndex { index })
-        } else if original_shard.len() != self.shard_bytes {
-            Err(Error::DifferentShardSize {
-                shard_bytes: self.shard_bytes,
-                got: original_shard.len(),
-            })
-        } else {
-            self.shards[pos].copy_from_slice(original_shard);
-            self.original_received_count += 1;
-            self.received.set(pos, true);
-            Ok(())
-        }
+        let pos = self.begin_original_shard(index)?;
+        self.copy_shard(pos, original_shard.as_ref())
     }
 
+    // Adds recovery shard, or only validates it if enough shards
+    // have been received already, as decoding wouldn't use it.
     pub(crate) fn add_recovery_shard<T: AsRef<[u8]>>(
         &mut self,
         index: usize,
         recovery_shard: T,
     ) -> Result<(), Error> {
-        let pos = self.recovery_base_pos + index;
         let recovery_shard = recovery_shard.as_ref();
+        let pos = self.begin_recovery_shard(index)?;
+        if self.has_enough_shards() {
+            self.check_shard_bytes(recovery_shard)?;
+            self.skip_recovery_shard(index);
+            Ok(())
+        } else {
+            self.copy_shard(pos, recovery_shard)
+        }
+    }
 
+    // Validates `index` of an original shard which is about to be added,
+    // returning its position in `shards`.
+    pub(crate) fn begin_original_shard(&self, index: usize) -> Result<usize, Error> {
+        // `index` must be validated before adding it to `original_base_pos`
+        // (or `recovery_base_pos`) as huge `index` would overflow.
+        if index >= self.original_count {
+            Error::InvalidOriginalShardIndex {
+                original_count: self.original_count,
+                index,
+            }
+            .into_cold_err()
+        } else if self.received[self.original_base_pos + index] {
+            Error::DuplicateOriginalShardIndex { index }.into_cold_err()
+        } else {
+            Ok(self.original_base_pos + index)
+        }
+    }
+
+    // Validates `index` of a recovery shard which is about to be added,
+    // returning its position in `shards`.
+    pub(crate) fn begin_recovery_shard(&self, index: usize) -> Result<usize, Error> {
         if index >= self.recovery_count {
-            Err(Error::InvalidRecoveryShardIndex {
+            Error::InvalidRecoveryShardIndex {
                 recovery_count: self.recovery_count,
                 index,
-            })
-        } else if self.received[pos] {
-            Err(Error::DuplicateRecoveryShardIndex { index })
-        } else if recovery_shard.len() != self.shard_bytes {
-            Err(Error::DifferentShardSize {
-                shard_bytes: self.shard_bytes,
-                got: recovery_shard.len(),
-            })
+            }
+            .into_cold_err()
+        } else if self.received[self.recovery_base_pos + index] || self.skipped[index] {
+            Error::DuplicateRecoveryShardIndex { index }.into_cold_err()
+        } else {
+            Ok(self.recovery_base_pos + index)
+        }
+    }
+
+    // Marks recovery shard `index` as added without copying its content,
+    // so that it's rejected as duplicate if added again.
+    // - `index` must have been validated by `begin_recovery_shard`.
+    // - Skipped shards are not received, e.g. they are not saved by `save`.
+    pub(crate) fn skip_recovery_shard(&mut self, index: usize) {
+        debug_assert!(self.has_enough_shards());
+        self.skipped.set(index, true);
+    }
+
+    // Marks shard at `pos` as received.
+    // - `pos` must have been returned by `begin_*_shard`.
+    pub(crate) fn finish_shard(&mut self, pos: usize) {
+        debug_assert!(!self.received[pos]);
+
+        if self.is_original_pos(pos) {
+            self.original_received_count += 1;
         } else {
-            self.shards[pos].copy_from_slice(recovery_shard);
             self.recovery_received_count += 1;
-            self.received.set(pos, true);
-            Ok(())
         }
+        self.received.set(pos, true);
+    }
+
+    pub(crate) fn shard_bytes(&self) -> usize {
+        self.shard_bytes
+    }
+
+    // Shard at `pos` for writing its content.
+    // - `pos` must have been returned by `begin_*_shard`.
+    pub(crate) fn shard_mut(&mut self, pos: usize) -> &mut [u8] {
+        &mut self.shards[pos]
     }
 
     // Begin decode.
     // - Returned `FixedBitSet` may contain extra zero bits.
+    // - Returned erasure locations are all zero.
+    #[allow(clippy::type_complexity)]
     pub(crate) fn decode_begin(
         &mut self,
-    ) -> Result<Option<(ShardsRefMut, usize, usize, &FixedBitSet)>, Error> {
+    ) -> Result<
+        Option<(
+            ShardsRefMut,
+            usize,
+            usize,
+            &FixedBitSet,
+            &mut [GfElement; GF_ORDER],
+        )>,
+        Error,
+    > {
         if self.original_received_count + self.recovery_received_count < self.original_count {
-            Err(Error::NotEnoughShards {
+            Error::NotEnoughShards {
                 original_count: self.original_count,
                 original_received_count: self.original_received_count,
                 recovery_received_count: self.recovery_received_count,
-            })
+            }
+            .into_cold_err()
         } else if self.original_received_count == self.original_count {
             Ok(None)
         } else {
+            self.drop_surplus_recovery();
+
+            let erasures = match &mut self.erasures {
+                Some(erasures) => {
+                    erasures.fill(0);
+                    erasures
+                }
+                // Allocated via `Vec` as `Box::new` may place array on stack first.
+                erasures @ None => {
+                    erasures.insert(vec![0; GF_ORDER].into_boxed_slice().try_into().unwrap())
+                }
+            };
+
             Ok(Some((
                 self.shards.as_ref_mut(),
                 self.original_count,
                 self.recovery_count,
                 &self.received,
+                erasures,
             )))
         }
     }
 
+    // Returns `true` if enough shards have been received for decoding,
+    // so further recovery shards are not needed.
+    pub(crate) fn has_enough_shards(&self) -> bool {
+        self.received_count() >= self.original_count
+    }
+
+    // Returns `true` if recovery shard `index` was added
+    // but not copied, see `skip_recovery_shard`.
+    pub(crate) fn is_skipped_recovery(&self, index: usize) -> bool {
+        index < self.recovery_count && self.skipped[index]
+    }
+
     pub(crate) fn original_count(&self) -> usize {
         self.original_count
     }
 
+    // Restores missing original shards in place by solving them directly
+    // from received shards, returning `false` without changing anything
+    // if none or more than `PEELING_MAX_MISSING` original shards are missing
+    // or there are not enough recovery shards.
+    // - With `m = 2^k` being the larger of `original_base_pos` and
+    //   `recovery_base_pos`, each recovery shard at position `y` is
+    //   `sum(original[x] * (d >> k) / d)` with `d = x ^ y` over
+    //   original positions `x`, as `d >> k` is the normalized subspace
+    //   polynomial vanishing on positions `0 .. m` in Cantor basis.
+    // - So each missing original shard is a linear combination of received
+    //   shards, which costs `O(original_count)` shard multiplications
+    //   instead of `O(m log m)` of IFFT / FFT.
+    pub(crate) fn peel<E: Engine>(&mut self, engine: &E) -> bool {
+        let missing_count = self.original_count - self.original_received_count;
+        if missing_count == 0
+            || missing_count > PEELING_MAX_MISSING
+            || self.received_count() < self.original_count
+        {
+            return false;
+        }
+
+        let original_pos = self.original_base_pos..self.original_base_pos + self.original_count;
+        let recovery_pos = self.recovery_base_pos..self.recovery_base_pos + self.recovery_count;
+
+        let mut missing = [0; PEELING_MAX_MISSING];
+        let mut used = [0; PEELING_MAX_MISSING];
+        let mut received = Vec::with_capacity(self.original_received_count);
+        let mut missing_iter = missing.iter_mut();
+        for pos in original_pos {
+            if self.received[pos] {
+                received.push(pos);
+            } else {
+                *missing_iter.next().unwrap() = pos;
+            }
+        }
+        for (used, pos) in std::iter::zip(
+            &mut used[..missing_count],
+            recovery_pos.filter(|&pos| self.received[pos]),
+        ) {
+            *used = pos;
+        }
+
+        // Original and recovery positions are in different chunks of `m`,
+        // so `d >> k` is never zero.
+        let k = std::cmp::max(self.original_base_pos, self.recovery_base_pos).trailing_zeros();
+        let coeff = |x: usize, y: usize| {
+            let d = (x ^ y) as GfElement;
+            Gf16(d >> k) * Gf16(d).inv().unwrap()
+        };
+
+        // Inverse of `a[s][t] = coeff(missing[t], used[s])`,
+        // which is invertible as the code is MDS.
+        let a = |s: usize, t: usize| coeff(missing[t], used[s]);
+        let inverse = if missing_count == 1 {
+            [[a(0, 0).inv().unwrap(), Gf16::ZERO], [Gf16::ZERO; 2]]
+        } else {
+            let det_inv = (a(0, 0) * a(1, 1) + a(0, 1) * a(1, 0)).inv().unwrap();
+            [
+                [a(1, 1) * det_inv, a(0, 1) * det_inv],
+                [a(1, 0) * det_inv, a(0, 0) * det_inv],
+            ]
+        };
+
+        let mut shards = self.shards.as_ref_mut();
+        let mut shards: Vec<&mut [u8]> = shards.iter_mut().collect();
+        let mut coeffs = Vec::with_capacity(missing_count + received.len());
+        for (t, &out_pos) in missing[..missing_count].iter().enumerate() {
+            let out = std::mem::take(&mut shards[out_pos]);
+            let used = &used[..missing_count];
+
+            coeffs.clear();
+            coeffs.extend(used.iter().enumerate().map(|(s, _)| inverse[t][s].0));
+            coeffs.extend(received.iter().map(|&x| {
+                let sum = used
+                    .iter()
+                    .enumerate()
+                    .fold(Gf16::ZERO, |sum, (s, &y)| sum + inverse[t][s] * coeff(x, y));
+                sum.0
+            }));
+
+            let inputs: Vec<&[u8]> = used
+                .iter()
+                .chain(&received)
+                .map(|&pos| &*shards[pos])
+                .collect();
+            engine.linear_combination(out, &inputs, &coeffs);
+        }
+
+        true
+    }
+
+    pub(crate) fn received_count(&self) -> usize {
+        self.original_received_count + self.recovery_received_count
+    }
+
+    // Content of received shard `id`, or `None` if it hasn't been received.
+    // - Content is valid only before decoding.
+    pub(crate) fn received_shard(&self, id: ShardId) -> Option<&[u8]> {
+        let pos = match id {
+            ShardId::Original(index) if index < self.original_count => {
+                self.original_base_pos + index
+            }
+            ShardId::Recovery(index) if index < self.recovery_count => {
+                self.recovery_base_pos + index
+            }
+            _ => return None,
+        };
+
+        if self.received[pos] {
+            Some(&self.shards[pos])
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn recovery_count(&self) -> usize {
+        self.recovery_count
+    }
+
     pub(crate) fn reset(
         &mut self,
         original_count: usize,
@@ -151,7 +484,9 @@ impl DecoderWork {
         original_base_pos: usize,
         recovery_base_pos: usize,
         work_count: usize,
-    ) {
+    ) -> Result<(), Error> {
+        self.shards.resize(work_count, shard_bytes)?;
+
         self.original_count = original_count;
         self.recovery_count = recovery_count;
         self.shard_bytes = shard_bytes;
@@ -172,23 +507,249 @@ impl DecoderWork {
             self.received.grow(max_received_pos);
         }
 
-        self.shards.resize(work_count, shard_bytes);
+        self.skipped.clear();
+        if self.skipped.len() < recovery_count {
+            self.skipped.grow(recovery_count);
+        }
+
+        Ok(())
     }
 
     pub(crate) fn reset_received(&mut self) {
         self.original_received_count = 0;
         self.recovery_received_count = 0;
         self.received.clear();
+        self.skipped.clear();
     }
 
     // This must only be called by `DecoderResult`.
     pub(crate) fn restored_original(&self, index: usize) -> Option<&[u8]> {
-        let pos = self.original_base_pos + index;
+        // See `begin_original_shard`.
+        if index >= self.original_count {
+            return None;
+        }
 
-        if index < self.original_count && !self.received[pos] {
+        let pos = self.original_base_pos + index;
+        if self.received[pos] {
+            None
+        } else {
             Some(&self.shards[pos])
+        }
+    }
+}
+
+// ======================================================================
+// DecoderWork - PRIVATE
+
+impl DecoderWork {
+    // Forgets received recovery shards beyond those needed to restore
+    // missing original shards, keeping the ones with lowest indexes.
+    // - Received original shards are always kept,
+    //   as each one dropped would be one more shard to restore.
+    // - Forgotten shards are zeroed instead of multiplied
+    //   by erasure locations, and low rate IFFT is truncated
+    //   after the last received recovery shard.
+    // - Recovery shards added after enough shards have been received
+    //   are not copied in the first place, see `add_recovery_shard`,
+    //   so this only forgets shards when original shards come last.
+    fn drop_surplus_recovery(&mut self) {
+        debug_assert!(self.original_received_count < self.original_count);
+
+        let mut surplus = self.received_count() - self.original_count;
+        let mut index = self.recovery_count;
+        while surplus > 0 {
+            index -= 1;
+            let pos = self.recovery_base_pos + index;
+            if self.received[pos] {
+                self.received.set(pos, false);
+                self.recovery_received_count -= 1;
+                surplus -= 1;
+            }
+        }
+    }
+
+    fn check_shard_bytes(&self, shard: &[u8]) -> Result<(), Error> {
+        if shard.len() != self.shard_bytes {
+            Error::DifferentShardSize {
+                shard_bytes: self.shard_bytes,
+                got: shard.len(),
+            }
+            .into_cold_err()
         } else {
-            None
+            Ok(())
+        }
+    }
+
+    fn copy_shard(&mut self, pos: usize, shard: &[u8]) -> Result<(), Error> {
+        self.check_shard_bytes(shard)?;
+
+        trace_span!("copy_in", pos, bytes = shard.len());
+        // Not `Engine::copy`, working space is read right away by FFT / IFFT
+        // which is slower after non-temporal stores.
+        self.shards[pos].copy_from_slice(shard);
+        self.finish_shard(pos);
+        Ok(())
+    }
+
+    fn is_original_pos(&self, pos: usize) -> bool {
+        pos >= self.original_base_pos && pos < self.original_base_pos + self.original_count
+    }
+
+    // Reads shards written by `save` into working space,
+    // which must not have any shards received.
+    fn load_shards<R: Read>(&mut self, reader: &mut R) -> Result<(), LoadError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != SAVE_MAGIC {
+            return Err(LoadError::InvalidFormat);
+        }
+
+        let version = u32::from_le_bytes(read_array(reader)?);
+        if version != SAVE_VERSION {
+            return Err(LoadError::UnsupportedVersion { version });
+        }
+
+        let expected = CodecParams::new(self.original_count, self.recovery_count, self.shard_bytes);
+        let got = CodecParams::new(
+            read_usize(reader)?,
+            read_usize(reader)?,
+            read_usize(reader)?,
+        );
+        if got != expected {
+            return Err(LoadError::ParameterMismatch { expected, got });
+        }
+
+        for original in [true, false] {
+            let count = read_usize(reader)?;
+            for _ in 0..count {
+                let index = read_usize(reader)?;
+                let pos = if original {
+                    self.begin_original_shard(index)?
+                } else {
+                    self.begin_recovery_shard(index)?
+                };
+                reader.read_exact(&mut self.shards[pos])?;
+                self.finish_shard(pos);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// ======================================================================
+// LoadError - PUBLIC
+
+/// Represents all possible errors of [`DecoderWork::load`].
+#[derive(Debug)]
+pub enum LoadError {
+    /// Saved shard is invalid for the decoder,
+    /// e.g. it has a duplicate index.
+    Codec(Error),
+
+    /// Data doesn't start with the bytes written by [`DecoderWork::save`].
+    InvalidFormat,
+
+    /// Reading failed.
+    Io(io::Error),
+
+    /// Data was saved with different configuration.
+    ParameterMismatch {
+        /// Configuration of the decoder.
+        expected: CodecParams,
+        /// Configuration in the saved data.
+        got: CodecParams,
+    },
+
+    /// Data ended before all saved shards were read.
+    Truncated,
+
+    /// Data was saved with an unknown version of the format.
+    UnsupportedVersion {
+        /// Version in the saved data.
+        version: u32,
+    },
+}
+
+// ======================================================================
+// LoadError - IMPL DISPLAY
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Codec(error) => write!(f, "invalid saved shard: {}", error),
+
+            LoadError::InvalidFormat => write!(f, "not a saved decoder working space"),
+
+            LoadError::Io(error) => write!(f, "io error: {}", error),
+
+            LoadError::ParameterMismatch { expected, got } => {
+                write!(
+                    f,
+                    "parameter mismatch: decoder is {}:{} with {} byte shards, \
+                     saved data is {}:{} with {} byte shards",
+                    expected.original_count,
+                    expected.recovery_count,
+                    expected.shard_bytes,
+                    got.original_count,
+                    got.recovery_count,
+                    got.shard_bytes,
+                )
+            }
+
+            LoadError::Truncated => write!(f, "saved data is truncated"),
+
+            LoadError::UnsupportedVersion { version } => {
+                write!(f, "unsupported format version {}", version)
+            }
         }
     }
 }
+
+// ======================================================================
+// LoadError - IMPL ERROR
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Codec(error) => Some(error),
+            LoadError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+// ======================================================================
+// LoadError - IMPL From
+
+impl From<Error> for LoadError {
+    fn from(error: Error) -> Self {
+        LoadError::Codec(error)
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        if error.kind() == io::ErrorKind::UnexpectedEof {
+            LoadError::Truncated
+        } else {
+            LoadError::Io(error)
+        }
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+// Values which don't fit into `usize` can't match the decoder,
+// so they are saturated instead of being rejected separately.
+fn read_usize<R: Read>(reader: &mut R) -> io::Result<usize> {
+    let value = u64::from_le_bytes(read_array(reader)?);
+    Ok(usize::try_from(value).unwrap_or(usize::MAX))
+}
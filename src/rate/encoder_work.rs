@@ -1,3 +1,7 @@
+#[cfg(feature = "allocator-api")]
+use std::alloc::Allocator;
+use std::{collections::HashMap, iter::zip};
+
 use crate::{
     engine::{Shards, ShardsRefMut},
     Error,
@@ -16,9 +20,28 @@ pub struct EncoderWork {
 
     original_received_count: usize,
     shards: Shards,
+
+    // Deduplication, see `set_deduplication`.
+    dedup: bool,
+    // Hash of shard content -> index of first original shard with that hash.
+    hashes: HashMap<u64, usize>,
+    // (index, index of first identical original shard)
+    duplicates: Vec<(usize, usize)>,
+
+    // Stripe id of integrity footer which `rate::encode_work` writes,
+    // see `ReedSolomonEncoder::take_work`.
+    footer_stripe_id: Option<u64>,
 }
 
 impl EncoderWork {
+    /// Returns number of bytes currently allocated for working space.
+    ///
+    /// This may be more than current configuration needs,
+    /// see [`shrink_to_fit`](Self::shrink_to_fit).
+    pub fn allocated_bytes(&self) -> usize {
+        self.shards.allocated_bytes()
+    }
+
     /// Creates new [`EncoderWork`] which initially
     /// has no working space allocated.
     pub fn new() -> Self {
@@ -29,8 +52,55 @@ impl EncoderWork {
 
             original_received_count: 0,
             shards: Shards::new(),
+
+            dedup: false,
+            hashes: HashMap::new(),
+            duplicates: Vec::new(),
+
+            footer_stripe_id: None,
+        }
+    }
+
+    /// Creates new [`EncoderWork`] which allocates
+    /// its working space with given allocator.
+    ///
+    /// Requires nightly Rust and `allocator-api` feature.
+    #[cfg(feature = "allocator-api")]
+    pub fn new_in<A: Allocator + Send + Sync + 'static>(alloc: A) -> Self {
+        Self {
+            shards: Shards::new_in(alloc),
+            ..Self::new()
         }
     }
+
+    /// Enables or disables detection of duplicate original shards.
+    ///
+    /// When enabled, each added original shard is hashed and compared
+    /// with earlier shards having the same hash. Shards identical to
+    /// an earlier shard are reported by [`EncoderResult::duplicate_originals`].
+    /// Encoding output is not affected. In the unlikely case
+    /// of a hash collision a duplicate may go unreported.
+    ///
+    /// This is disabled by default.
+    ///
+    /// [`EncoderResult::duplicate_originals`]: crate::EncoderResult::duplicate_originals
+    pub fn set_deduplication(&mut self, enabled: bool) {
+        self.dedup = enabled;
+        self.hashes.clear();
+        self.duplicates.clear();
+    }
+
+    /// Releases working space which current configuration doesn't need.
+    ///
+    /// Working space is never shrunk automatically, so after handling
+    /// a large configuration it stays allocated for re-use.
+    /// This lowers memory usage at the cost of re-allocation
+    /// if a larger configuration is used again.
+    pub fn shrink_to_fit(&mut self) {
+        self.shards.shrink_to_fit();
+        self.hashes.shrink_to_fit();
+        self.duplicates.shrink_to_fit();
+    }
 }
 
 // ======================================================================
@@ -51,29 +121,80 @@ impl EncoderWork {
         original_shard: T,
     ) -> Result<(), Error> {
         let original_shard = original_shard.as_ref();
+        let index = self.begin_original_shard(original_shard)?;
+        self.finish_original_shard(index, original_shard);
+        Ok(())
+    }
 
+    // Validates original shard which is about to be added,
+    // returning its index.
+    pub(crate) fn begin_original_shard(&self, original_shard: &[u8]) -> Result<usize, Error> {
         if self.original_received_count == self.original_count {
-            Err(Error::TooManyOriginalShards {
+            Error::TooManyOriginalShards {
                 original_count: self.original_count,
-            })
+            }
+            .into_cold_err()
         } else if original_shard.len() != self.shard_bytes {
-            Err(Error::DifferentShardSize {
+            Error::DifferentShardSize {
                 shard_bytes: self.shard_bytes,
                 got: original_shard.len(),
-            })
+            }
+            .into_cold_err()
         } else {
-            self.shards[self.original_received_count].copy_from_slice(original_shard);
-            self.original_received_count += 1;
-            Ok(())
+            Ok(self.original_received_count)
+        }
+    }
+
+    // Adds original shard without validating it again.
+    // - `index` must have been returned by `begin_original_shard`
+    //   with the same shard and nothing added since.
+    pub(crate) fn finish_original_shard(&mut self, index: usize, original_shard: &[u8]) {
+        self.finish_original_shard_at(index, index, original_shard);
+    }
+
+    // Like `finish_original_shard` but copies the shard
+    // to working space position `pos` instead of `index`.
+    // - Deduplication must be disabled unless `pos == index`,
+    //   as it compares against earlier shards at their index.
+    pub(crate) fn finish_original_shard_at(
+        &mut self,
+        index: usize,
+        pos: usize,
+        original_shard: &[u8],
+    ) {
+        debug_assert_eq!(index, self.original_received_count);
+        debug_assert!(index < self.original_count);
+        debug_assert_eq!(original_shard.len(), self.shard_bytes);
+        debug_assert!(!self.dedup || pos == index);
+
+        trace_span!("copy_in", index, bytes = original_shard.len());
+
+        if self.dedup {
+            self.find_duplicate(index, original_shard);
         }
+
+        // Not `Engine::copy`, working space is read right away by FFT / IFFT
+        // which is slower after non-temporal stores.
+        self.shards[pos].copy_from_slice(original_shard);
+        self.original_received_count += 1;
+    }
+
+    pub(crate) fn deduplication(&self) -> bool {
+        self.dedup
+    }
+
+    // This must only be called by `EncoderResult`.
+    pub(crate) fn duplicate_originals(&self) -> &[(usize, usize)] {
+        &self.duplicates
     }
 
     pub(crate) fn encode_begin(&mut self) -> Result<(ShardsRefMut, usize, usize), Error> {
         if self.original_received_count != self.original_count {
-            Err(Error::TooFewOriginalShards {
+            Error::TooFewOriginalShards {
                 original_count: self.original_count,
                 original_received_count: self.original_received_count,
-            })
+            }
+            .into_cold_err()
         } else {
             Ok((
                 self.shards.as_ref_mut(),
@@ -83,6 +204,26 @@ impl EncoderWork {
         }
     }
 
+    pub(crate) fn footer_stripe_id(&self) -> Option<u64> {
+        self.footer_stripe_id
+    }
+
+    // Content of added original shard `index`, or `None` if it hasn't been added.
+    // - Content is valid only before encoding.
+    // - Shard must have been added at its index, i.e. not with `finish_original_shard_at`.
+    #[cfg(feature = "stripe-hash")]
+    pub(crate) fn original(&self, index: usize) -> Option<&[u8]> {
+        if index < self.original_received_count {
+            Some(&self.shards[index])
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn original_count(&self) -> usize {
+        self.original_count
+    }
+
     // This must only be called by `EncoderResult`.
     pub(crate) fn recovery(&self, index: usize) -> Option<&[u8]> {
         if index < self.recovery_count {
@@ -92,22 +233,109 @@ impl EncoderWork {
         }
     }
 
+    // This must only be called by `EncoderResult`.
+    pub(crate) fn recovery_flat(&self) -> &[u8] {
+        self.shards.flat(0..self.recovery_count)
+    }
+
+    pub(crate) fn recovery_count(&self) -> usize {
+        self.recovery_count
+    }
+
+    // This must only be called by `EncoderResult`.
+    pub(crate) fn recovery_mut(&mut self, index: usize) -> Option<&mut [u8]> {
+        if index < self.recovery_count {
+            Some(&mut self.shards[index])
+        } else {
+            None
+        }
+    }
+
     pub(crate) fn reset(
         &mut self,
         original_count: usize,
         recovery_count: usize,
         shard_bytes: usize,
         work_count: usize,
-    ) {
+    ) -> Result<(), Error> {
+        self.shards.resize(work_count, shard_bytes)?;
+
         self.original_count = original_count;
         self.recovery_count = recovery_count;
         self.shard_bytes = shard_bytes;
 
         self.original_received_count = 0;
-        self.shards.resize(work_count, shard_bytes);
+        self.hashes.clear();
+        self.duplicates.clear();
+        self.footer_stripe_id = None;
+        Ok(())
     }
 
     pub(crate) fn reset_received(&mut self) {
         self.original_received_count = 0;
+        self.hashes.clear();
+        self.duplicates.clear();
+    }
+
+    // Cleared by `reset`.
+    pub(crate) fn set_footer_stripe_id(&mut self, stripe_id: Option<u64>) {
+        self.footer_stripe_id = stripe_id;
+    }
+
+    pub(crate) fn shard_bytes(&self) -> usize {
+        self.shard_bytes
     }
+
+    // Working space regardless of how many original shards have been added.
+    pub(crate) fn shards_mut(&mut self) -> ShardsRefMut {
+        self.shards.as_ref_mut()
+    }
+
+    // Number of shards in working space, recovery shards are at the start.
+    pub(crate) fn work_count(&self) -> usize {
+        self.shards.shard_count()
+    }
+}
+
+// ======================================================================
+// EncoderWork - PRIVATE
+
+impl EncoderWork {
+    // Records original shard `index` as duplicate if an identical shard
+    // has already been added, otherwise remembers its hash.
+    // - Shards are compared in full, so hash collisions are harmless.
+    fn find_duplicate(&mut self, index: usize, original_shard: &[u8]) {
+        let hash = shard_hash(original_shard);
+        match self.hashes.get(&hash) {
+            Some(&first) if self.shards[first] == *original_shard => {
+                self.duplicates.push((index, first));
+            }
+            Some(_) => {}
+            None => {
+                self.hashes.insert(hash, index);
+            }
+        }
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+// Cheap non-cryptographic 64-bit hash of shard content.
+// - `shard.len()` is a multiple of 64 so there is no remainder.
+// - Uses independent lanes so that multiplications can run in parallel.
+fn shard_hash(shard: &[u8]) -> u64 {
+    const K: u64 = 0x9E37_79B9_7F4A_7C15;
+
+    let mut lanes = [0u64; 8];
+    for chunk in shard.chunks_exact(64) {
+        for (lane, word) in zip(lanes.iter_mut(), chunk.chunks_exact(8)) {
+            let word = u64::from_le_bytes(word.try_into().unwrap());
+            *lane = (*lane ^ word).wrapping_mul(K).rotate_left(29);
+        }
+    }
+
+    lanes.iter().fold(0, |hash, lane| {
+        (hash ^ lane).wrapping_mul(K).rotate_left(29)
+    })
 }
@@ -0,0 +1,455 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "encoder")]
+use crate::engine::{self, ShardsRefMut};
+use crate::{
+    engine::{Engine, GfElement, GF_ORDER},
+    gf::Gf16,
+    Error, UnsupportedReason,
+};
+
+// ======================================================================
+// FUNCTIONS - PUBLIC
+
+/// Evaluates the codeword of [`LowRate`] encoding at given `positions`.
+///
+/// Low rate encoding defines a polynomial of degree `< chunk_size`,
+/// where `chunk_size = original_count.next_power_of_two()`,
+/// whose value at position
+/// - `i < original_count` is original shard `i`,
+/// - `original_count <= i < chunk_size` is zero,
+/// - `chunk_size + j` is recovery shard `j`.
+///
+/// This returns the value of that polynomial at each of `positions`,
+/// so any positions up to `GF_ORDER - 1` can be used as recovery shards
+/// instead of the standard `chunk_size .. chunk_size + recovery_count`.
+/// Such shards can be decoded with [`PositionDecoder`].
+///
+/// Positions which are dense within a block of `chunk_size` positions
+/// are evaluated together with an additive FFT, other positions
+/// are evaluated separately with [`Engine::linear_combination`].
+///
+/// # Examples
+///
+/// ```rust
+/// use reed_solomon_simd::{engine::NoSimd, rate};
+///
+/// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+/// let original: Vec<&[u8]> = original.iter().map(|shard| &shard[..]).collect();
+///
+/// // Positions `4 .. 9` are the standard recovery shards `0 .. 5`,
+/// // as 3 original and 5 recovery shards use low rate by default.
+/// let positions = [4, 5, 6, 7, 8, 1000];
+/// let shards = rate::encode_at_positions(&NoSimd::new(), &original, &positions)?;
+/// assert_eq!(shards[..5], reed_solomon_simd::encode(3, 5, &original)?[..]);
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+///
+/// [`LowRate`]: crate::rate::LowRate
+#[cfg(feature = "encoder")]
+pub fn encode_at_positions<E: Engine + ?Sized>(
+    engine: &E,
+    originals: &[&[u8]],
+    positions: &[u16],
+) -> Result<Vec<Vec<u8>>, Error> {
+    let original_count = originals.len();
+    let shard_bytes = originals.first().map_or(0, |shard| shard.len());
+    validate(original_count, positions.len(), shard_bytes)?;
+    for shard in originals {
+        if shard.len() != shard_bytes {
+            return Err(Error::DifferentShardSize {
+                shard_bytes,
+                got: shard.len(),
+            });
+        }
+    }
+
+    let chunk_size = original_count.next_power_of_two();
+    let fft_cost = chunk_size * chunk_size.trailing_zeros() as usize / 2;
+
+    // Number of requested positions in each block of `chunk_size` positions.
+    let mut block_counts = BTreeMap::new();
+    for &position in positions {
+        *block_counts
+            .entry(usize::from(position) / chunk_size)
+            .or_insert(0) += 1;
+    }
+
+    let mut coefs = Vec::new();
+    let mut fft_blocks = BTreeMap::new();
+    for (block, count) in block_counts {
+        if block > 0 && count * original_count >= fft_cost {
+            if coefs.is_empty() {
+                coefs = vec![0; chunk_size * shard_bytes];
+                for (i, shard) in originals.iter().enumerate() {
+                    coefs[i * shard_bytes..(i + 1) * shard_bytes].copy_from_slice(shard);
+                }
+                let mut data = ShardsRefMut::new(chunk_size, shard_bytes, &mut coefs);
+                engine::additive_ifft(engine, &mut data, 0);
+            }
+
+            let mut values = coefs.clone();
+            let mut data = ShardsRefMut::new(chunk_size, shard_bytes, &mut values);
+            engine::additive_fft(engine, &mut data, block * chunk_size);
+            fft_blocks.insert(block, values);
+        }
+    }
+
+    let subspace_product = subspace_product(chunk_size);
+    let mut coeffs = vec![0; original_count];
+
+    Ok(positions
+        .iter()
+        .map(|&position| {
+            let position = usize::from(position);
+            let (block, i) = (position / chunk_size, position % chunk_size);
+
+            if block == 0 {
+                originals
+                    .get(i)
+                    .map_or_else(|| vec![0; shard_bytes], |shard| shard.to_vec())
+            } else if let Some(values) = fft_blocks.get(&block) {
+                values[i * shard_bytes..(i + 1) * shard_bytes].to_vec()
+            } else {
+                // Lagrange interpolation from points `0 .. chunk_size`,
+                // which form an additive subgroup `V` so that
+                // L_j(x) = (prod of x - v for v in V) / (x - j) / subspace_product
+                let x = position as GfElement;
+                let numerator = (0..chunk_size)
+                    .fold(Gf16::ONE, |product, v| product * Gf16(x ^ v as GfElement))
+                    * subspace_product.inv().unwrap();
+                for (j, coeff) in coeffs.iter_mut().enumerate() {
+                    *coeff = (numerator * Gf16(x ^ j as GfElement).inv().unwrap()).0;
+                }
+
+                let mut shard = vec![0; shard_bytes];
+                engine.linear_combination(&mut shard, originals, &coeffs);
+                shard
+            }
+        })
+        .collect())
+}
+
+// ======================================================================
+// PositionDecoder - PUBLIC
+
+/// Decoder for shards evaluated at arbitrary positions
+/// with [`encode_at_positions`].
+///
+/// Any `original_count` distinct original or recovery positions are
+/// enough to restore the original shards. Recovery positions
+/// `chunk_size ..` correspond to recovery shards `0 ..` of [`LowRate`],
+/// which is also how errors refer to them.
+///
+/// Restoring uses Lagrange interpolation taking `O(original_count²)`
+/// field operations plus one [`Engine::linear_combination`]
+/// per restored shard, so this is meant for moderate `original_count`.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+/// use reed_solomon_simd::{engine::NoSimd, rate::{self, PositionDecoder}};
+///
+/// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+/// let original: Vec<&[u8]> = original.iter().map(|shard| &shard[..]).collect();
+///
+/// let shards = rate::encode_at_positions(&NoSimd::new(), &original, &[1000, 2000])?;
+///
+/// let mut decoder = PositionDecoder::new(3, 64, NoSimd::new())?;
+/// decoder.add_shard_at_position(1, original[1])?;
+/// decoder.add_shard_at_position(1000, &shards[0])?;
+/// decoder.add_shard_at_position(2000, &shards[1])?;
+/// assert_eq!(decoder.decode()?, original);
+/// # }
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+///
+/// [`LowRate`]: crate::rate::LowRate
+#[cfg(feature = "decoder")]
+pub struct PositionDecoder<E: Engine> {
+    engine: E,
+    original_count: usize,
+    shard_bytes: usize,
+    shards: BTreeMap<u16, Vec<u8>>,
+}
+
+#[cfg(feature = "decoder")]
+impl<E: Engine> PositionDecoder<E> {
+    /// Adds shard at given `position` to the decoder.
+    ///
+    /// Positions `original_count .. chunk_size` always have value zero,
+    /// so they are rejected with [`Error::InvalidOriginalShardIndex`].
+    pub fn add_shard_at_position<T: AsRef<[u8]>>(
+        &mut self,
+        position: u16,
+        shard: T,
+    ) -> Result<(), Error> {
+        let shard = shard.as_ref();
+        let chunk_size = self.original_count.next_power_of_two();
+        let index = usize::from(position);
+
+        if index >= self.original_count && index < chunk_size {
+            Err(Error::InvalidOriginalShardIndex {
+                original_count: self.original_count,
+                index,
+            })
+        } else if self.shards.contains_key(&position) {
+            if index < self.original_count {
+                Err(Error::DuplicateOriginalShardIndex { index })
+            } else {
+                Err(Error::DuplicateRecoveryShardIndex {
+                    index: index - chunk_size,
+                })
+            }
+        } else if shard.len() != self.shard_bytes {
+            Err(Error::DifferentShardSize {
+                shard_bytes: self.shard_bytes,
+                got: shard.len(),
+            })
+        } else {
+            self.shards.insert(position, shard.to_vec());
+            Ok(())
+        }
+    }
+
+    /// Restores all original shards from the added shards.
+    ///
+    /// Added shards are removed, so the decoder is then
+    /// ready for new round of decoding.
+    pub fn decode(&mut self) -> Result<Vec<Vec<u8>>, Error> {
+        let original_count = self.original_count;
+        let chunk_size = original_count.next_power_of_two();
+        let shards = std::mem::take(&mut self.shards);
+
+        if shards.len() < original_count {
+            return Err(Error::NotEnoughShards {
+                original_count,
+                original_received_count: shards.range(..original_count as u16).count(),
+                recovery_received_count: shards.range(chunk_size as u16..).count(),
+            });
+        }
+
+        // Points with known values: first `original_count` received
+        // positions, preferring originals, and the zero positions.
+        let received: Vec<(GfElement, &[u8])> = shards
+            .iter()
+            .take(original_count)
+            .map(|(&position, shard)| (position, &shard[..]))
+            .collect();
+        let points: Vec<GfElement> = received
+            .iter()
+            .map(|&(position, _)| position)
+            .chain((original_count..chunk_size).map(|i| i as GfElement))
+            .collect();
+
+        // Denominators of Lagrange basis polynomials of received points.
+        let denominators: Vec<Gf16> = received
+            .iter()
+            .map(|&(r, _)| {
+                let product = points
+                    .iter()
+                    .filter(|&&s| s != r)
+                    .fold(Gf16::ONE, |product, &s| product * Gf16(r ^ s));
+                product.inv().unwrap()
+            })
+            .collect();
+
+        let inputs: Vec<&[u8]> = received.iter().map(|&(_, shard)| shard).collect();
+        let mut coeffs = vec![0; received.len()];
+
+        Ok((0..original_count)
+            .map(|t| {
+                let t = t as GfElement;
+                if let Some(shard) = shards.get(&t) {
+                    return shard.clone();
+                }
+
+                let numerator = points
+                    .iter()
+                    .fold(Gf16::ONE, |product, &s| product * Gf16(t ^ s));
+                for ((coeff, &(r, _)), &denominator) in
+                    coeffs.iter_mut().zip(&received).zip(&denominators)
+                {
+                    *coeff = (numerator * Gf16(t ^ r).inv().unwrap() * denominator).0;
+                }
+
+                let mut shard = vec![0; self.shard_bytes];
+                self.engine.linear_combination(&mut shard, &inputs, &coeffs);
+                shard
+            })
+            .collect())
+    }
+
+    /// Creates new decoder for shards encoded from `original_count`
+    /// original shards of `shard_bytes` bytes each.
+    pub fn new(original_count: usize, shard_bytes: usize, engine: E) -> Result<Self, Error> {
+        validate(original_count, 0, shard_bytes)?;
+        Ok(Self {
+            engine,
+            original_count,
+            shard_bytes,
+            shards: BTreeMap::new(),
+        })
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+// Product of non-zero points of `0 .. chunk_size`.
+#[cfg(feature = "encoder")]
+fn subspace_product(chunk_size: usize) -> Gf16 {
+    (1..chunk_size).fold(Gf16::ONE, |product, v| product * Gf16(v as GfElement))
+}
+
+fn validate(original_count: usize, recovery_count: usize, shard_bytes: usize) -> Result<(), Error> {
+    if original_count == 0 || original_count > GF_ORDER / 2 {
+        Err(Error::UnsupportedShardCount {
+            original_count,
+            recovery_count,
+            reason: if original_count == 0 {
+                UnsupportedReason::NoOriginalShards
+            } else {
+                UnsupportedReason::TooManyShards
+            },
+            hint: None,
+        })
+    } else if shard_bytes == 0 || shard_bytes & 63 != 0 {
+        Err(Error::InvalidShardSize { shard_bytes })
+    } else {
+        Ok(())
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
+mod tests {
+    use super::*;
+    use crate::{
+        engine::{Naive, NoSimd},
+        rate::{LowRate, Rate, RateEncoder},
+        test_util,
+    };
+
+    fn as_refs(shards: &[Vec<u8>]) -> Vec<&[u8]> {
+        shards.iter().map(|shard| &shard[..]).collect()
+    }
+
+    #[test]
+    fn matches_low_rate_encoder() {
+        for (original_count, recovery_count) in [(1, 3), (3, 5), (5, 20), (8, 100), (33, 40)] {
+            let original = test_util::generate_original(original_count, 128, 166);
+            let mut encoder =
+                LowRate::encoder(original_count, recovery_count, 128, NoSimd::new(), None).unwrap();
+            for shard in &original {
+                encoder.add_original_shard(shard).unwrap();
+            }
+            let result = encoder.encode().unwrap();
+            let expected: Vec<Vec<u8>> = result.recovery_iter().map(|s| s.to_vec()).collect();
+
+            let chunk_size = original_count.next_power_of_two();
+            let positions: Vec<u16> = (chunk_size..chunk_size + recovery_count)
+                .map(|position| position as u16)
+                .collect();
+
+            // All positions together, mostly with FFT.
+            let got = encode_at_positions(&NoSimd::new(), &as_refs(&original), &positions);
+            assert_eq!(got.unwrap(), expected);
+
+            // Positions one at a time, with interpolation when `chunk_size > 1`.
+            for (&position, expected) in positions.iter().zip(&expected) {
+                let got = encode_at_positions(&Naive::new(), &as_refs(&original), &[position]);
+                assert_eq!(&got.unwrap()[0], expected);
+            }
+
+            // Positions within the first block.
+            let positions: Vec<u16> = (0..chunk_size as u16).rev().collect();
+            let got = encode_at_positions(&NoSimd::new(), &as_refs(&original), &positions);
+            for (&position, shard) in positions.iter().zip(got.unwrap()) {
+                let position = usize::from(position);
+                if position < original_count {
+                    assert_eq!(shard, original[position]);
+                } else {
+                    assert!(shard.iter().all(|&byte| byte == 0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decode_at_positions() {
+        let original_count = 10;
+        let original = test_util::generate_original(original_count, 64, 167);
+        let positions = [65535, 3, 200, 16, 4000, 17, 9, 60000, 1, 31, 5];
+        let shards = encode_at_positions(&NoSimd::new(), &as_refs(&original), &positions).unwrap();
+
+        let mut decoder = PositionDecoder::new(original_count, 64, NoSimd::new()).unwrap();
+        for (&position, shard) in positions.iter().zip(&shards) {
+            decoder.add_shard_at_position(position, shard).unwrap();
+        }
+        assert_eq!(decoder.decode().unwrap(), original);
+
+        // Decoder is ready for new round.
+        assert_eq!(
+            decoder.decode(),
+            Err(Error::NotEnoughShards {
+                original_count,
+                original_received_count: 0,
+                recovery_received_count: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn position_decoder_errors() {
+        let mut decoder = PositionDecoder::new(3, 64, NoSimd::new()).unwrap();
+
+        assert_eq!(
+            decoder.add_shard_at_position(3, [0; 64]),
+            Err(Error::InvalidOriginalShardIndex {
+                original_count: 3,
+                index: 3,
+            })
+        );
+
+        decoder.add_shard_at_position(1, [0; 64]).unwrap();
+        decoder.add_shard_at_position(6, [0; 64]).unwrap();
+        assert_eq!(
+            decoder.add_shard_at_position(1, [0; 64]),
+            Err(Error::DuplicateOriginalShardIndex { index: 1 })
+        );
+        assert_eq!(
+            decoder.add_shard_at_position(6, [0; 64]),
+            Err(Error::DuplicateRecoveryShardIndex { index: 2 })
+        );
+        assert_eq!(
+            decoder.add_shard_at_position(7, [0; 128]),
+            Err(Error::DifferentShardSize {
+                shard_bytes: 64,
+                got: 128,
+            })
+        );
+
+        assert_eq!(
+            decoder.decode(),
+            Err(Error::NotEnoughShards {
+                original_count: 3,
+                original_received_count: 1,
+                recovery_received_count: 1,
+            })
+        );
+
+        assert_eq!(
+            encode_at_positions(&NoSimd::new(), &[], &[1]),
+            Err(Error::UnsupportedShardCount {
+                original_count: 0,
+                recovery_count: 1,
+                reason: UnsupportedReason::NoOriginalShards,
+                hint: None,
+            })
+        );
+    }
+}
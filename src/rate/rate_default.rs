@@ -1,23 +1,49 @@
+#[cfg(feature = "decoder")]
+use std::ops::ControlFlow;
 use std::{cmp::Ordering, marker::PhantomData};
 
 use crate::{
     engine::{Engine, GF_ORDER},
-    rate::{
-        DecoderWork, EncoderWork, HighRateDecoder, HighRateEncoder, LowRateDecoder, LowRateEncoder,
-        Rate, RateDecoder, RateEncoder,
-    },
-    DecoderResult, EncoderResult, Error,
+    rate::Rate,
+    CodecParams, Error, UnsupportedReason,
+};
+#[cfg(feature = "decoder")]
+use crate::{
+    rate::{DecoderWork, HighRateDecoder, LowRateDecoder, RateDecoder},
+    DecoderResult, ShardWriter,
+};
+#[cfg(feature = "encoder")]
+use crate::{
+    rate::{EncoderWork, HighRateEncoder, LowRateEncoder, RateEncoder},
+    EncoderResult,
 };
 
 // ======================================================================
-// FUNCTIONS - PRIVATE
-
-fn use_high_rate(original_count: usize, recovery_count: usize) -> Result<bool, Error> {
-    if original_count > GF_ORDER || recovery_count > GF_ORDER {
-        return Err(Error::UnsupportedShardCount {
+// FUNCTIONS - CRATE
+
+// `shard_bytes` of `params` is not used in rate selection.
+pub(crate) fn use_high_rate(params: CodecParams) -> Result<bool, Error> {
+    let CodecParams {
+        original_count,
+        recovery_count,
+        ..
+    } = params;
+
+    let unsupported = |reason| {
+        Err(Error::UnsupportedShardCount {
             original_count,
             recovery_count,
-        });
+            reason,
+            hint: None,
+        })
+    };
+
+    if let Some(reason) = UnsupportedReason::zero_count(original_count, recovery_count) {
+        return unsupported(reason);
+    }
+
+    if original_count > GF_ORDER || recovery_count > GF_ORDER {
+        return unsupported(UnsupportedReason::TooManyShards);
     }
 
     let original_count_pow2 = original_count.next_power_of_two();
@@ -26,11 +52,8 @@ fn use_high_rate(original_count: usize, recovery_count: usize) -> Result<bool, E
     let smaller_pow2 = std::cmp::min(original_count_pow2, recovery_count_pow2);
     let larger = std::cmp::max(original_count, recovery_count);
 
-    if original_count == 0 || recovery_count == 0 || smaller_pow2 + larger > GF_ORDER {
-        return Err(Error::UnsupportedShardCount {
-            original_count,
-            recovery_count,
-        });
+    if smaller_pow2 + larger > GF_ORDER {
+        return unsupported(UnsupportedReason::TooManyShards);
     }
 
     match original_count_pow2.cmp(&recovery_count_pow2) {
@@ -63,24 +86,84 @@ fn use_high_rate(original_count: usize, recovery_count: usize) -> Result<bool, E
     }
 }
 
+// Largest shard count of one kind supported together with
+// `other_count` shards of the other kind, or `0` if there is none.
+// - Supported counts are exactly `1 ..= max_shard_count(other_count)`.
+// - Both kinds have same limits as the condition in `use_high_rate`
+//   is symmetric.
+pub(crate) fn max_shard_count(other_count: usize) -> usize {
+    if other_count == 0 || other_count >= GF_ORDER {
+        return 0;
+    }
+
+    let other_pow2 = other_count.next_power_of_two();
+    if other_pow2 + other_count <= GF_ORDER {
+        // Other count can be the smaller one,
+        // so this count can be up to `GF_ORDER - other_pow2`
+        // which is at least `other_count`.
+        GF_ORDER - other_pow2
+    } else {
+        // Other count must be the larger one,
+        // so this count is a power of two at most `GF_ORDER - other_count`.
+        let limit = GF_ORDER - other_count;
+        1 << (usize::BITS - 1 - limit.leading_zeros())
+    }
+}
+
 // ======================================================================
 // DefaultRate - PUBLIC
 
 /// Reed-Solomon encoder/decoder generator using high or low rate as appropriate.
+///
+/// Supported shard counts are
+/// - `original_count > 0`
+/// - `recovery_count > 0`
+/// - `smaller_pow2 + larger <= 65536` where
+///     - `smaller_pow2 = min(original_count.next_power_of_two(), recovery_count.next_power_of_two())`
+///     - `larger = max(original_count, recovery_count)`
+///
+/// So e.g. `1024 : 1025` and `1025 : 1024` are supported by both [`HighRate`]
+/// and [`LowRate`] while `61440 : 4096` is supported only by [`HighRate`].
+///
+/// [`HighRate`]: crate::rate::HighRate
+/// [`LowRate`]: crate::rate::LowRate
 pub struct DefaultRate<E: Engine>(PhantomData<E>);
 
+impl<E: Engine> DefaultRate<E> {
+    /// Identifier of the coding scheme, i.e. which rate is used
+    /// for which shard counts and the encoding of both rates.
+    ///
+    /// This is same as [`CODING_SCHEME_ID`].
+    ///
+    /// [`CODING_SCHEME_ID`]: crate::CODING_SCHEME_ID
+    pub const SCHEME_ID: &'static str = crate::CODING_SCHEME_ID;
+}
+
 impl<E: Engine> Rate<E> for DefaultRate<E> {
+    #[cfg(feature = "encoder")]
     type RateEncoder = DefaultRateEncoder<E>;
+    #[cfg(feature = "decoder")]
     type RateDecoder = DefaultRateDecoder<E>;
 
     fn supports(original_count: usize, recovery_count: usize) -> bool {
-        use_high_rate(original_count, recovery_count).is_ok()
+        use_high_rate(CodecParams::new(original_count, recovery_count, 0)).is_ok()
+    }
+
+    fn unsupported_reason(
+        original_count: usize,
+        recovery_count: usize,
+    ) -> Option<UnsupportedReason> {
+        match use_high_rate(CodecParams::new(original_count, recovery_count, 0)) {
+            Err(Error::UnsupportedShardCount { reason, .. }) => Some(reason),
+            _ => None,
+        }
     }
 }
 
 // ======================================================================
 // InnerEncoder - PRIVATE
 
+#[cfg(feature = "encoder")]
 #[derive(Default)]
 enum InnerEncoder<E: Engine> {
     High(HighRateEncoder<E>),
@@ -101,8 +184,10 @@ enum InnerEncoder<E: Engine> {
 /// specifying [`Engine`] and [`EncoderWork`].
 ///
 /// [`ReedSolomonEncoder`]: crate::ReedSolomonEncoder
+#[cfg(feature = "encoder")]
 pub struct DefaultRateEncoder<E: Engine>(InnerEncoder<E>);
 
+#[cfg(feature = "encoder")]
 impl<E: Engine> RateEncoder<E> for DefaultRateEncoder<E> {
     type Rate = DefaultRate<E>;
 
@@ -137,7 +222,8 @@ impl<E: Engine> RateEncoder<E> for DefaultRateEncoder<E> {
         engine: E,
         work: Option<EncoderWork>,
     ) -> Result<Self, Error> {
-        let inner = if use_high_rate(original_count, recovery_count)? {
+        let params = CodecParams::new(original_count, recovery_count, shard_bytes);
+        let inner = if use_high_rate(params)? {
             InnerEncoder::High(HighRateEncoder::new(
                 original_count,
                 recovery_count,
@@ -164,7 +250,12 @@ impl<E: Engine> RateEncoder<E> for DefaultRateEncoder<E> {
         recovery_count: usize,
         shard_bytes: usize,
     ) -> Result<(), Error> {
-        let new_rate_is_high = use_high_rate(original_count, recovery_count)?;
+        // Validate fully before taking inner value so that
+        // failed reset leaves this encoder/decoder usable.
+        Self::validate(original_count, recovery_count, shard_bytes)?;
+
+        let params = CodecParams::new(original_count, recovery_count, shard_bytes);
+        let new_rate_is_high = use_high_rate(params)?;
 
         self.0 = match std::mem::take(&mut self.0) {
             InnerEncoder::High(mut high) => {
@@ -206,9 +297,43 @@ impl<E: Engine> RateEncoder<E> for DefaultRateEncoder<E> {
     }
 }
 
+// ======================================================================
+// DefaultRateEncoder - CRATE
+
+#[cfg(feature = "encoder")]
+impl<E: Engine> DefaultRateEncoder<E> {
+    pub(crate) fn encode_with_checkpoints<F: FnMut(f32)>(
+        &mut self,
+        checkpoint: F,
+    ) -> Result<EncoderResult, Error> {
+        match &mut self.0 {
+            InnerEncoder::High(high) => high.encode_with_checkpoints(checkpoint),
+            InnerEncoder::Low(low) => low.encode_with_checkpoints(checkpoint),
+            InnerEncoder::None => unreachable!(),
+        }
+    }
+
+    pub(crate) fn work(&self) -> &EncoderWork {
+        match &self.0 {
+            InnerEncoder::High(high) => high.work(),
+            InnerEncoder::Low(low) => low.work(),
+            InnerEncoder::None => unreachable!(),
+        }
+    }
+
+    pub(crate) fn work_mut(&mut self) -> &mut EncoderWork {
+        match &mut self.0 {
+            InnerEncoder::High(high) => high.work_mut(),
+            InnerEncoder::Low(low) => low.work_mut(),
+            InnerEncoder::None => unreachable!(),
+        }
+    }
+}
+
 // ======================================================================
 // InnerDecoder - PRIVATE
 
+#[cfg(feature = "decoder")]
 #[derive(Default)]
 enum InnerDecoder<E: Engine> {
     High(HighRateDecoder<E>),
@@ -229,8 +354,10 @@ enum InnerDecoder<E: Engine> {
 /// specifying [`Engine`] and [`DecoderWork`].
 ///
 /// [`ReedSolomonDecoder`]: crate::ReedSolomonDecoder
+#[cfg(feature = "decoder")]
 pub struct DefaultRateDecoder<E: Engine>(InnerDecoder<E>);
 
+#[cfg(feature = "decoder")]
 impl<E: Engine> RateDecoder<E> for DefaultRateDecoder<E> {
     type Rate = DefaultRate<E>;
 
@@ -258,6 +385,22 @@ impl<E: Engine> RateDecoder<E> for DefaultRateDecoder<E> {
         }
     }
 
+    fn begin_original_shard(&mut self, index: usize) -> Result<ShardWriter, Error> {
+        match &mut self.0 {
+            InnerDecoder::High(high) => high.begin_original_shard(index),
+            InnerDecoder::Low(low) => low.begin_original_shard(index),
+            InnerDecoder::None => unreachable!(),
+        }
+    }
+
+    fn begin_recovery_shard(&mut self, index: usize) -> Result<ShardWriter, Error> {
+        match &mut self.0 {
+            InnerDecoder::High(high) => high.begin_recovery_shard(index),
+            InnerDecoder::Low(low) => low.begin_recovery_shard(index),
+            InnerDecoder::None => unreachable!(),
+        }
+    }
+
     fn decode(&mut self) -> Result<DecoderResult, Error> {
         match &mut self.0 {
             InnerDecoder::High(high) => high.decode(),
@@ -266,6 +409,17 @@ impl<E: Engine> RateDecoder<E> for DefaultRateDecoder<E> {
         }
     }
 
+    fn decode_with_control<F: FnMut(f32) -> ControlFlow<()>>(
+        &mut self,
+        control: F,
+    ) -> Result<DecoderResult, Error> {
+        match &mut self.0 {
+            InnerDecoder::High(high) => high.decode_with_control(control),
+            InnerDecoder::Low(low) => low.decode_with_control(control),
+            InnerDecoder::None => unreachable!(),
+        }
+    }
+
     fn into_parts(self) -> (E, DecoderWork) {
         match self.0 {
             InnerDecoder::High(high) => high.into_parts(),
@@ -281,7 +435,8 @@ impl<E: Engine> RateDecoder<E> for DefaultRateDecoder<E> {
         engine: E,
         work: Option<DecoderWork>,
     ) -> Result<Self, Error> {
-        let inner = if use_high_rate(original_count, recovery_count)? {
+        let params = CodecParams::new(original_count, recovery_count, shard_bytes);
+        let inner = if use_high_rate(params)? {
             InnerDecoder::High(HighRateDecoder::new(
                 original_count,
                 recovery_count,
@@ -308,7 +463,12 @@ impl<E: Engine> RateDecoder<E> for DefaultRateDecoder<E> {
         recovery_count: usize,
         shard_bytes: usize,
     ) -> Result<(), Error> {
-        let new_rate_is_high = use_high_rate(original_count, recovery_count)?;
+        // Validate fully before taking inner value so that
+        // failed reset leaves this encoder/decoder usable.
+        Self::validate(original_count, recovery_count, shard_bytes)?;
+
+        let params = CodecParams::new(original_count, recovery_count, shard_bytes);
+        let new_rate_is_high = use_high_rate(params)?;
 
         self.0 = match std::mem::take(&mut self.0) {
             InnerDecoder::High(mut high) => {
@@ -350,13 +510,61 @@ impl<E: Engine> RateDecoder<E> for DefaultRateDecoder<E> {
     }
 }
 
+// ======================================================================
+// DefaultRateDecoder - CRATE
+
+#[cfg(feature = "decoder")]
+impl<E: Engine> DefaultRateDecoder<E> {
+    // Restores few missing original shards directly if possible,
+    // otherwise decodes like `decode`, see `DecoderWork::peel`.
+    pub(crate) fn decode_peeling(&mut self) -> Result<DecoderResult<'_>, Error> {
+        let peeled = match &mut self.0 {
+            InnerDecoder::High(high) => {
+                let (engine, work) = high.parts_mut();
+                work.peel(engine)
+            }
+            InnerDecoder::Low(low) => {
+                let (engine, work) = low.parts_mut();
+                work.peel(engine)
+            }
+            InnerDecoder::None => unreachable!(),
+        };
+
+        if peeled {
+            Ok(DecoderResult::new(self.work_mut()))
+        } else {
+            self.decode()
+        }
+    }
+
+    pub(crate) fn work(&self) -> &DecoderWork {
+        match &self.0 {
+            InnerDecoder::High(high) => high.work(),
+            InnerDecoder::Low(low) => low.work(),
+            InnerDecoder::None => unreachable!(),
+        }
+    }
+
+    pub(crate) fn work_mut(&mut self) -> &mut DecoderWork {
+        match &mut self.0 {
+            InnerDecoder::High(high) => high.work_mut(),
+            InnerDecoder::Low(low) => low.work_mut(),
+            InnerDecoder::None => unreachable!(),
+        }
+    }
+}
+
 // ======================================================================
 // TESTS
 
-#[cfg(test)]
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
 mod tests {
     use super::*;
-    use crate::test_util;
+    use crate::{
+        engine::{DefaultEngine, Naive, NoSimd},
+        rate::{HighRate, LowRate},
+        test_util,
+    };
 
     // ============================================================
     // ROUNDTRIPS - SINGLE ROUND
@@ -430,6 +638,129 @@ mod tests {
         );
     }
 
+    #[test]
+    fn failed_reset_keeps_encoder_and_decoder_usable() {
+        let mut encoder = DefaultRateEncoder::new(2, 3, 1024, NoSimd::new(), None).unwrap();
+        let mut decoder = DefaultRateDecoder::new(2, 3, 1024, NoSimd::new(), None).unwrap();
+
+        for (original_count, recovery_count, shard_bytes) in [
+            (3, 2, 65),
+            (3, 2, usize::MAX & !63),
+            (0, 2, 64),
+            (61440, 4097, 64),
+        ] {
+            assert!(encoder
+                .reset(original_count, recovery_count, shard_bytes)
+                .is_err());
+            assert!(decoder
+                .reset(original_count, recovery_count, shard_bytes)
+                .is_err());
+        }
+
+        encoder.reset(3, 2, 1024).unwrap();
+        decoder.reset(3, 2, 1024).unwrap();
+        let original = test_util::generate_original(3, 1024, 132);
+        for original in &original {
+            encoder.add_original_shard(original).unwrap();
+        }
+        let result = encoder.encode().unwrap();
+        test_util::assert_hash(result.recovery_iter(), test_util::HIGH_3_2);
+
+        decoder.add_original_shard(0, &original[0]).unwrap();
+        decoder
+            .add_recovery_shard(0, result.recovery(0).unwrap())
+            .unwrap();
+        decoder
+            .add_recovery_shard(1, result.recovery(1).unwrap())
+            .unwrap();
+        let result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(1).unwrap(), original[1]);
+        assert_eq!(result.restored_original(2).unwrap(), original[2]);
+    }
+
+    // ============================================================
+    // ENCODING - NAIVE EQUIVALENCE
+
+    fn encode_with<E: Engine>(
+        engine: E,
+        original: &[Vec<u8>],
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Vec<Vec<u8>> {
+        let mut encoder =
+            DefaultRate::encoder(original.len(), recovery_count, shard_bytes, engine, None)
+                .unwrap();
+        for shard in original {
+            encoder.add_original_shard(shard).unwrap();
+        }
+        let result = encoder.encode().unwrap();
+        result.recovery_iter().map(|shard| shard.to_vec()).collect()
+    }
+
+    #[test]
+    // Covers both rates with non-power-of-two and `2^n + 1` counts,
+    // i.e. truncated transforms and odd number of layers.
+    fn encode_matches_naive() {
+        const COUNTS: [usize; 11] = [1, 2, 3, 4, 5, 7, 8, 9, 31, 33, 257];
+
+        for original_count in COUNTS {
+            for recovery_count in COUNTS {
+                for shard_bytes in [64, 192] {
+                    let seed = (original_count ^ recovery_count ^ shard_bytes) as u8;
+                    let original = test_util::generate_original(original_count, shard_bytes, seed);
+
+                    assert_eq!(
+                        encode_with(DefaultEngine::new(), &original, recovery_count, shard_bytes),
+                        encode_with(Naive::new(), &original, recovery_count, shard_bytes),
+                        "{}:{} with {} bytes",
+                        original_count,
+                        recovery_count,
+                        shard_bytes
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    // Every recovery shard must be produced, with same content as `Naive`.
+    // Counts around powers of two are where truncated transforms
+    // and chunking round, so all counts up to `300` are covered.
+    fn encode_recovery_count_sweep() {
+        for count in 1..=300 {
+            for (original_count, recovery_count) in [
+                (count, 1),
+                (1, count),
+                (count, count),
+                (count, 300 - count + 1),
+            ] {
+                for shard_bytes in [64, 128] {
+                    let seed = (original_count ^ recovery_count ^ shard_bytes) as u8;
+                    let original = test_util::generate_original(original_count, shard_bytes, seed);
+
+                    let got =
+                        encode_with(DefaultEngine::new(), &original, recovery_count, shard_bytes);
+                    assert_eq!(
+                        got.len(),
+                        recovery_count,
+                        "{}:{} with {} bytes",
+                        original_count,
+                        recovery_count,
+                        shard_bytes
+                    );
+                    assert_eq!(
+                        got,
+                        encode_with(Naive::new(), &original, recovery_count, shard_bytes),
+                        "{}:{} with {} bytes",
+                        original_count,
+                        recovery_count,
+                        shard_bytes
+                    );
+                }
+            }
+        }
+    }
+
     // ============================================================
     // use_high_rate
 
@@ -439,6 +770,9 @@ mod tests {
             Err(Error::UnsupportedShardCount {
                 original_count,
                 recovery_count,
+                reason: UnsupportedReason::zero_count(original_count, recovery_count)
+                    .unwrap_or(UnsupportedReason::TooManyShards),
+                hint: None,
             })
         }
 
@@ -463,9 +797,119 @@ mod tests {
             (usize::MAX, usize::MAX, err(usize::MAX, usize::MAX)),
         ] {
             assert_eq!(
-                super::use_high_rate(original_count, recovery_count),
+                super::use_high_rate(CodecParams::new(original_count, recovery_count, 64)),
                 expected
             );
         }
     }
+
+    // ============================================================
+    // max_shard_count
+
+    #[test]
+    fn max_shard_count() {
+        for (other_count, expected) in [
+            (0, 0),
+            (1, 65535),
+            (2, 65534),
+            (3, 65532),
+            (4096, 61440),
+            (4097, 57344),
+            (32768, 32768),
+            (32769, 16384),
+            (49152, 16384),
+            (49153, 8192),
+            (65535, 1),
+            (65536, 0),
+            (usize::MAX, 0),
+        ] {
+            assert_eq!(
+                super::max_shard_count(other_count),
+                expected,
+                "{}",
+                other_count
+            );
+        }
+
+        // Maximum is supported and one more isn't, in both directions.
+        for other_count in (1..GF_ORDER).step_by(97).chain([4096, 4097, 32768, 32769]) {
+            let max = super::max_shard_count(other_count);
+            assert!(DefaultRate::<NoSimd>::supports(max, other_count));
+            assert!(DefaultRate::<NoSimd>::supports(other_count, max));
+            assert!(!DefaultRate::<NoSimd>::supports(max + 1, other_count));
+            assert!(!DefaultRate::<NoSimd>::supports(other_count, max + 1));
+        }
+    }
+
+    // ============================================================
+    // unsupported_reason
+
+    #[test]
+    fn unsupported_reason() {
+        use UnsupportedReason::*;
+
+        // Expected reasons of `HighRate`, `LowRate` and `DefaultRate`.
+        const OK: [Option<UnsupportedReason>; 3] = [None, None, None];
+        const UNSUPPORTED: [Option<UnsupportedReason>; 3] =
+            [Some(HighRateLimit), Some(LowRateLimit), Some(TooManyShards)];
+
+        for (original_count, recovery_count, expected) in [
+            (0, 1, [Some(NoOriginalShards); 3]),
+            (1, 0, [Some(NoRecoveryShards); 3]),
+            // BOTH RATES
+            (1024, 1024, OK),
+            (1024, 1025, OK),
+            (1025, 1024, OK),
+            (32768, 32768, OK),
+            (32768, 32769, UNSUPPORTED),
+            (32769, 32768, UNSUPPORTED),
+            // HIGH RATE ONLY
+            (61440, 4096, [None, Some(LowRateLimit), None]),
+            (61440, 4097, UNSUPPORTED),
+            (61441, 4096, UNSUPPORTED),
+            // LOW RATE ONLY
+            (4096, 61440, [Some(HighRateLimit), None, None]),
+            (4096, 61441, UNSUPPORTED),
+            (4097, 61440, UNSUPPORTED),
+            // OVERFLOW CHECK
+            (usize::MAX, 1, UNSUPPORTED),
+        ] {
+            assert_eq!(
+                [
+                    HighRate::<NoSimd>::unsupported_reason(original_count, recovery_count),
+                    LowRate::<NoSimd>::unsupported_reason(original_count, recovery_count),
+                    DefaultRate::<NoSimd>::unsupported_reason(original_count, recovery_count),
+                ],
+                expected,
+                "{} : {}",
+                original_count,
+                recovery_count
+            );
+        }
+    }
+
+    #[test]
+    fn validate_reports_reason() {
+        assert_eq!(
+            DefaultRate::<NoSimd>::validate(61440, 4097, 64),
+            Err(Error::UnsupportedShardCount {
+                original_count: 61440,
+                recovery_count: 4097,
+                reason: UnsupportedReason::TooManyShards,
+                hint: None,
+            })
+        );
+
+        assert_eq!(
+            Error::UnsupportedShardCount {
+                original_count: 1024,
+                recovery_count: 0,
+                reason: UnsupportedReason::NoRecoveryShards,
+                hint: None,
+            }
+            .to_string(),
+            "unsupported shard count: 1024 original shards with 0 recovery shards \
+             (recovery_count is 0)"
+        );
+    }
 }
@@ -1,19 +1,51 @@
 use std::marker::PhantomData;
+#[cfg(feature = "decoder")]
+use std::ops::ControlFlow;
 
+#[cfg(feature = "encoder")]
 use crate::{
-    engine::{self, Engine, GF_MODULUS, GF_ORDER},
-    rate::{DecoderWork, EncoderWork, Rate, RateDecoder, RateEncoder},
-    DecoderResult, EncoderResult, Error,
+    engine,
+    rate::{EncoderWork, RateEncoder},
+    EncoderResult,
+};
+#[cfg(feature = "decoder")]
+use crate::{
+    engine::GF_MODULUS,
+    rate::{DecoderWork, RateDecoder},
+    DecoderResult, ShardWriter,
+};
+use crate::{
+    engine::{Engine, GF_ORDER},
+    rate::{self, Rate},
+    Error, UnsupportedReason,
 };
 
 // ======================================================================
 // HighRate - PUBLIC
 
 /// Reed-Solomon encoder/decoder generator using only high rate.
+///
+/// Supported shard counts are
+/// - `original_count > 0`
+/// - `recovery_count > 0`
+/// - `recovery_count.next_power_of_two() + original_count <= 65536`
 pub struct HighRate<E: Engine>(PhantomData<E>);
 
+impl<E: Engine> HighRate<E> {
+    /// Identifier of the coding scheme of high rate.
+    ///
+    /// This differs from [`LowRate::SCHEME_ID`] as recovery shards are different.
+    /// See [`CODING_SCHEME_ID`] for the stability policy.
+    ///
+    /// [`LowRate::SCHEME_ID`]: crate::rate::LowRate::SCHEME_ID
+    /// [`CODING_SCHEME_ID`]: crate::CODING_SCHEME_ID
+    pub const SCHEME_ID: &'static str = "rs-fft-gf16-cantor-high-v1";
+}
+
 impl<E: Engine> Rate<E> for HighRate<E> {
+    #[cfg(feature = "encoder")]
     type RateEncoder = HighRateEncoder<E>;
+    #[cfg(feature = "decoder")]
     type RateDecoder = HighRateDecoder<E>;
 
     fn supports(original_count: usize, recovery_count: usize) -> bool {
@@ -23,17 +55,31 @@ impl<E: Engine> Rate<E> for HighRate<E> {
             && recovery_count < GF_ORDER
             && recovery_count.next_power_of_two() + original_count <= GF_ORDER
     }
+
+    fn unsupported_reason(
+        original_count: usize,
+        recovery_count: usize,
+    ) -> Option<UnsupportedReason> {
+        if Self::supports(original_count, recovery_count) {
+            None
+        } else {
+            UnsupportedReason::zero_count(original_count, recovery_count)
+                .or(Some(UnsupportedReason::HighRateLimit))
+        }
+    }
 }
 
 // ======================================================================
 // HighRateEncoder - PUBLIC
 
 /// Reed-Solomon encoder using only high rate.
+#[cfg(feature = "encoder")]
 pub struct HighRateEncoder<E: Engine> {
     engine: E,
     work: EncoderWork,
 }
 
+#[cfg(feature = "encoder")]
 impl<E: Engine> RateEncoder<E> for HighRateEncoder<E> {
     type Rate = HighRate<E>;
 
@@ -42,44 +88,7 @@ impl<E: Engine> RateEncoder<E> for HighRateEncoder<E> {
     }
 
     fn encode(&mut self) -> Result<EncoderResult, Error> {
-        let (mut work, original_count, recovery_count) = self.work.encode_begin()?;
-        let chunk_size = recovery_count.next_power_of_two();
-        let engine = &self.engine;
-
-        // FIRST CHUNK
-
-        let first_count = std::cmp::min(original_count, chunk_size);
-
-        work.zero(first_count..chunk_size);
-        engine.ifft_skew_end(&mut work, 0, chunk_size, first_count);
-
-        if original_count > chunk_size {
-            // FULL CHUNKS
-
-            let mut chunk_start = chunk_size;
-            while chunk_start + chunk_size <= original_count {
-                engine.ifft_skew_end(&mut work, chunk_start, chunk_size, chunk_size);
-                E::xor_within(&mut work, 0, chunk_start, chunk_size);
-                chunk_start += chunk_size;
-            }
-
-            // FINAL PARTIAL CHUNK
-
-            let last_count = original_count % chunk_size;
-            if last_count > 0 {
-                work.zero(chunk_start + last_count..);
-                engine.ifft_skew_end(&mut work, chunk_start, chunk_size, last_count);
-                E::xor_within(&mut work, 0, chunk_start, chunk_size);
-            }
-        }
-
-        // FFT
-
-        engine.fft(&mut work, 0, chunk_size, recovery_count, 0);
-
-        // DONE
-
-        Ok(EncoderResult::new(&mut self.work))
+        self.encode_with_checkpoints(|_| {})
     }
 
     fn into_parts(self) -> (E, EncoderWork) {
@@ -108,9 +117,101 @@ impl<E: Engine> RateEncoder<E> for HighRateEncoder<E> {
     }
 }
 
+// ======================================================================
+// HighRateEncoder - CRATE
+
+#[cfg(feature = "encoder")]
+impl<E: Engine> HighRateEncoder<E> {
+    // Like `encode`, calling `checkpoint` with decoder progress
+    // of the same step before IFFT and FFT.
+    pub(crate) fn encode_with_checkpoints<F: FnMut(f32)>(
+        &mut self,
+        checkpoint: F,
+    ) -> Result<EncoderResult, Error> {
+        Self::encode_work(&self.engine, &mut self.work, checkpoint)
+    }
+
+    // Like `encode_with_checkpoints` but with given engine and
+    // working space which has been reset by this rate.
+    pub(crate) fn encode_work<'a, F: FnMut(f32)>(
+        engine: &E,
+        encoder_work: &'a mut EncoderWork,
+        mut checkpoint: F,
+    ) -> Result<EncoderResult<'a>, Error> {
+        trace_span!(
+            "encode",
+            rate = "high",
+            original_count = encoder_work.original_count(),
+            recovery_count = encoder_work.recovery_count(),
+            shard_bytes = encoder_work.shard_bytes(),
+        );
+
+        let (mut work, original_count, recovery_count) = encoder_work.encode_begin()?;
+        let chunk_size = recovery_count.next_power_of_two();
+
+        checkpoint(rate::PROGRESS_IFFT);
+        {
+            trace_span!("ifft", chunk_size);
+
+            // FIRST CHUNK
+
+            let first_count = std::cmp::min(original_count, chunk_size);
+
+            work.zero_with::<E>(first_count..chunk_size);
+            engine.ifft_skew_end(&mut work, 0, chunk_size, first_count);
+
+            if original_count > chunk_size {
+                // FULL CHUNKS
+
+                let mut chunk_start = chunk_size;
+                while chunk_start + chunk_size <= original_count {
+                    engine.ifft_skew_end(&mut work, chunk_start, chunk_size, chunk_size);
+                    E::xor_within(&mut work, 0, chunk_start, chunk_size);
+                    chunk_start += chunk_size;
+                }
+
+                // FINAL PARTIAL CHUNK
+
+                let last_count = original_count % chunk_size;
+                if last_count > 0 {
+                    work.zero_with::<E>(chunk_start + last_count..);
+                    engine.ifft_skew_end(&mut work, chunk_start, chunk_size, last_count);
+                    E::xor_within(&mut work, 0, chunk_start, chunk_size);
+                }
+            }
+        }
+
+        // FFT
+
+        // Only the first chunk, which chunks above were xor:ed into,
+        // is transformed back, so this is independent of `original_count`.
+        // `truncated_size` skips butterflies whose outputs are
+        // all beyond `recovery_count`.
+
+        checkpoint(rate::PROGRESS_FFT);
+        {
+            trace_span!("fft", chunk_size);
+            engine.fft(&mut work, 0, chunk_size, recovery_count, 0);
+        }
+
+        // DONE
+
+        Ok(EncoderResult::new(encoder_work))
+    }
+
+    pub(crate) fn work(&self) -> &EncoderWork {
+        &self.work
+    }
+
+    pub(crate) fn work_mut(&mut self) -> &mut EncoderWork {
+        &mut self.work
+    }
+}
+
 // ======================================================================
 // HighRateEncoder - PRIVATE
 
+#[cfg(feature = "encoder")]
 impl<E: Engine> HighRateEncoder<E> {
     fn reset_work(
         original_count: usize,
@@ -124,7 +225,7 @@ impl<E: Engine> HighRateEncoder<E> {
             recovery_count,
             shard_bytes,
             Self::work_count(original_count, recovery_count),
-        );
+        )?;
         Ok(())
     }
 
@@ -141,11 +242,13 @@ impl<E: Engine> HighRateEncoder<E> {
 // HighRateDecoder - PUBLIC
 
 /// Reed-Solomon decoder using only high rate.
+#[cfg(feature = "decoder")]
 pub struct HighRateDecoder<E: Engine> {
     engine: E,
     work: DecoderWork,
 }
 
+#[cfg(feature = "decoder")]
 impl<E: Engine> RateDecoder<E> for HighRateDecoder<E> {
     type Rate = HighRate<E>;
 
@@ -165,13 +268,106 @@ impl<E: Engine> RateDecoder<E> for HighRateDecoder<E> {
         self.work.add_recovery_shard(index, recovery_shard)
     }
 
+    fn begin_original_shard(&mut self, index: usize) -> Result<ShardWriter, Error> {
+        let pos = self.work.begin_original_shard(index)?;
+        Ok(ShardWriter::new(&mut self.work, pos))
+    }
+
+    fn begin_recovery_shard(&mut self, index: usize) -> Result<ShardWriter, Error> {
+        let pos = self.work.begin_recovery_shard(index)?;
+        Ok(ShardWriter::new(&mut self.work, pos))
+    }
+
     fn decode(&mut self) -> Result<DecoderResult, Error> {
-        let (mut work, original_count, recovery_count, received) =
+        self.decode_with_control(|_| ControlFlow::Continue(()))
+    }
+
+    fn decode_with_control<F: FnMut(f32) -> ControlFlow<()>>(
+        &mut self,
+        mut control: F,
+    ) -> Result<DecoderResult, Error> {
+        match self.decode_steps(&mut control) {
+            Ok(()) => Ok(DecoderResult::new(&mut self.work)),
+            Err(Error::Cancelled) => {
+                self.work.reset_received();
+                Err(Error::Cancelled)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn into_parts(self) -> (E, DecoderWork) {
+        (self.engine, self.work)
+    }
+
+    fn new(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        engine: E,
+        work: Option<DecoderWork>,
+    ) -> Result<Self, Error> {
+        let mut work = work.unwrap_or_default();
+        Self::reset_work(original_count, recovery_count, shard_bytes, &mut work)?;
+        Ok(Self { work, engine })
+    }
+
+    fn reset(
+        &mut self,
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<(), Error> {
+        Self::reset_work(original_count, recovery_count, shard_bytes, &mut self.work)
+    }
+}
+
+// ======================================================================
+// HighRateDecoder - CRATE
+
+#[cfg(feature = "decoder")]
+impl<E: Engine> HighRateDecoder<E> {
+    pub(crate) fn work(&self) -> &DecoderWork {
+        &self.work
+    }
+
+    // Engine together with working space, e.g. for `DecoderWork::peel`.
+    pub(crate) fn parts_mut(&mut self) -> (&E, &mut DecoderWork) {
+        (&self.engine, &mut self.work)
+    }
+
+    pub(crate) fn work_mut(&mut self) -> &mut DecoderWork {
+        &mut self.work
+    }
+}
+
+// ======================================================================
+// HighRateDecoder - PRIVATE
+
+#[cfg(feature = "decoder")]
+impl<E: Engine> HighRateDecoder<E> {
+    // Decodes in place, calling `control` before each step.
+    // - Received shards are overwritten, so after `Error::Cancelled`
+    //   the caller must reset received shards.
+    fn decode_steps<F: FnMut(f32) -> ControlFlow<()>>(
+        &mut self,
+        control: &mut F,
+    ) -> Result<(), Error> {
+        trace_span!(
+            "decode",
+            rate = "high",
+            original_count = self.work.original_count(),
+            recovery_count = self.work.recovery_count(),
+            shard_bytes = self.work.shard_bytes(),
+            received_count = self.work.received_count(),
+        );
+
+        let (mut work, original_count, recovery_count, received, erasures) =
             if let Some(stuff) = self.work.decode_begin()? {
                 stuff
             } else {
                 // Nothing to do, original data is complete.
-                return Ok(DecoderResult::new(&mut self.work));
+                return Ok(());
             };
 
         let chunk_size = recovery_count.next_power_of_two();
@@ -180,102 +376,106 @@ impl<E: Engine> RateDecoder<E> for HighRateDecoder<E> {
 
         // ERASURE LOCATIONS
 
-        let mut erasures = [0; GF_ORDER];
+        rate::checkpoint(control, 0.0)?;
+
+        {
+            trace_span!("erasures");
 
-        for i in 0..recovery_count {
-            if !received[i] {
-                erasures[i] = 1;
+            for i in 0..recovery_count {
+                if !received[i] {
+                    erasures[i] = 1;
+                }
             }
-        }
 
-        erasures[recovery_count..chunk_size].fill(1);
+            erasures[recovery_count..chunk_size].fill(1);
 
-        for i in chunk_size..original_end {
-            if !received[i] {
-                erasures[i] = 1;
+            for i in chunk_size..original_end {
+                if !received[i] {
+                    erasures[i] = 1;
+                }
             }
         }
 
         // EVALUATE POLYNOMIAL
 
-        E::eval_poly(&mut erasures, original_end);
+        {
+            trace_span!("eval_poly");
+            #[cfg(feature = "unstable-basis")]
+            self.engine.eval_poly_with_tables(erasures, original_end);
+            #[cfg(not(feature = "unstable-basis"))]
+            E::eval_poly(erasures, original_end);
+        }
 
         // MULTIPLY SHARDS
 
-        // work[               .. recovery_count] = recovery * erasures
-        // work[recovery_count .. chunk_size    ] = 0
-        // work[chunk_size     .. original_end  ] = original * erasures
-        // work[original_end   ..               ] = 0
+        rate::checkpoint(control, rate::PROGRESS_MULTIPLY)?;
 
-        for i in 0..recovery_count {
-            if received[i] {
-                self.engine.mul(&mut work[i], erasures[i]);
-            } else {
-                work[i].fill(0);
+        {
+            trace_span!("multiply");
+
+            // work[               .. recovery_count] = recovery * erasures
+            // work[recovery_count .. chunk_size    ] = 0
+            // work[chunk_size     .. original_end  ] = original * erasures
+            // work[original_end   ..               ] = 0
+
+            for i in 0..recovery_count {
+                if received[i] {
+                    self.engine.mul(&mut work[i], erasures[i]);
+                } else {
+                    E::fill(&mut work[i], 0);
+                }
             }
-        }
 
-        work.zero(recovery_count..chunk_size);
+            work.zero_with::<E>(recovery_count..chunk_size);
 
-        for i in chunk_size..original_end {
-            if received[i] {
-                self.engine.mul(&mut work[i], erasures[i]);
-            } else {
-                work[i].fill(0);
+            for i in chunk_size..original_end {
+                if received[i] {
+                    self.engine.mul(&mut work[i], erasures[i]);
+                } else {
+                    E::fill(&mut work[i], 0);
+                }
             }
-        }
 
-        work.zero(original_end..);
+            work.zero_with::<E>(original_end..);
+        }
 
         // IFFT / FORMAL DERIVATIVE / FFT
 
-        self.engine.ifft(&mut work, 0, work_count, original_end, 0);
-        E::formal_derivative(&mut work);
-        self.engine.fft(&mut work, 0, work_count, original_end, 0);
+        rate::checkpoint(control, rate::PROGRESS_IFFT)?;
+        {
+            trace_span!("ifft", work_count);
+            self.engine.ifft(&mut work, 0, work_count, original_end, 0);
+        }
+        rate::checkpoint(control, rate::PROGRESS_FORMAL_DERIVATIVE)?;
+        {
+            trace_span!("formal_derivative", work_count);
+            E::formal_derivative(&mut work);
+        }
+        rate::checkpoint(control, rate::PROGRESS_FFT)?;
+        {
+            trace_span!("fft", work_count);
+            self.engine.fft(&mut work, 0, work_count, original_end, 0);
+        }
 
         // REVEAL ERASURES
 
-        for i in chunk_size..original_end {
-            if !received[i] {
-                self.engine.mul(&mut work[i], GF_MODULUS - erasures[i]);
+        rate::checkpoint(control, rate::PROGRESS_REVEAL)?;
+
+        {
+            trace_span!("reveal");
+
+            for i in chunk_size..original_end {
+                if !received[i] {
+                    self.engine.mul(&mut work[i], GF_MODULUS - erasures[i]);
+                }
             }
         }
 
         // DONE
 
-        Ok(DecoderResult::new(&mut self.work))
-    }
-
-    fn into_parts(self) -> (E, DecoderWork) {
-        (self.engine, self.work)
-    }
-
-    fn new(
-        original_count: usize,
-        recovery_count: usize,
-        shard_bytes: usize,
-        engine: E,
-        work: Option<DecoderWork>,
-    ) -> Result<Self, Error> {
-        let mut work = work.unwrap_or_default();
-        Self::reset_work(original_count, recovery_count, shard_bytes, &mut work)?;
-        Ok(Self { work, engine })
+        Ok(())
     }
 
-    fn reset(
-        &mut self,
-        original_count: usize,
-        recovery_count: usize,
-        shard_bytes: usize,
-    ) -> Result<(), Error> {
-        Self::reset_work(original_count, recovery_count, shard_bytes, &mut self.work)
-    }
-}
-
-// ======================================================================
-// HighRateDecoder - PRIVATE
-
-impl<E: Engine> HighRateDecoder<E> {
     fn reset_work(
         original_count: usize,
         recovery_count: usize,
@@ -293,7 +493,7 @@ impl<E: Engine> HighRateDecoder<E> {
             recovery_count.next_power_of_two(),
             0,
             Self::work_count(original_count, recovery_count),
-        );
+        )?;
 
         Ok(())
     }
@@ -308,7 +508,7 @@ impl<E: Engine> HighRateDecoder<E> {
 // ======================================================================
 // TESTS
 
-#[cfg(test)]
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
 mod tests {
     use super::*;
     use crate::test_util;
@@ -335,6 +535,21 @@ mod tests {
         roundtrip_single!(HighRate, 3, 2, 1024, test_util::HIGH_3_2, &[0..3], &[], 132);
     }
 
+    // Surplus recovery shards are forgotten before decoding.
+    #[test]
+    fn roundtrip_surplus_recovery() {
+        roundtrip_single!(
+            HighRate,
+            3,
+            2,
+            1024,
+            test_util::HIGH_3_2,
+            &[0, 2],
+            &[0, 1],
+            132
+        );
+    }
+
     #[test]
     fn roundtrips_tiny() {
         for (original_count, recovery_count, seed, recovery_hash) in test_util::HIGH_TINY {
@@ -425,8 +640,8 @@ mod tests {
     mod high_rate {
         use crate::{
             engine::NoSimd,
-            rate::{HighRate, Rate},
-            Error,
+            rate::{HighRate, Rate, RateKind},
+            Error, UnsupportedReason,
         };
 
         #[test]
@@ -436,6 +651,8 @@ mod tests {
                 Some(Error::UnsupportedShardCount {
                     original_count: 4096,
                     recovery_count: 61440,
+                    reason: UnsupportedReason::HighRateLimit,
+                    hint: Some(RateKind::Low),
                 })
             );
 
@@ -449,6 +666,8 @@ mod tests {
                 Some(Error::UnsupportedShardCount {
                     original_count: 4096,
                     recovery_count: 61440,
+                    reason: UnsupportedReason::HighRateLimit,
+                    hint: Some(RateKind::Low),
                 })
             );
 
@@ -481,6 +700,8 @@ mod tests {
                 Some(Error::UnsupportedShardCount {
                     original_count: 4096,
                     recovery_count: 61440,
+                    reason: UnsupportedReason::HighRateLimit,
+                    hint: Some(RateKind::Low),
                 })
             );
 
@@ -494,8 +715,8 @@ mod tests {
     mod high_rate_encoder {
         use crate::{
             engine::NoSimd,
-            rate::{HighRateEncoder, RateEncoder},
-            Error,
+            rate::{HighRateEncoder, RateEncoder, RateKind},
+            Error, UnsupportedReason,
         };
 
         // ==================================================
@@ -527,6 +748,8 @@ mod tests {
                 Some(Error::UnsupportedShardCount {
                     original_count: 4096,
                     recovery_count: 61440,
+                    reason: UnsupportedReason::HighRateLimit,
+                    hint: Some(RateKind::Low),
                 })
             );
 
@@ -544,6 +767,33 @@ mod tests {
             assert_eq!(HighRateEncoder::<NoSimd>::work_count(4097, 1025), 6144);
             assert_eq!(HighRateEncoder::<NoSimd>::work_count(32768, 32768), 32768);
         }
+
+        // ==================================================
+        // borrowed engine
+
+        #[test]
+        fn borrowed_engine() {
+            let engine = NoSimd::new();
+            let original = crate::test_util::generate_original(3, 64, 183);
+
+            let mut first = HighRateEncoder::new(3, 2, 64, &engine, None).unwrap();
+            let mut second = HighRateEncoder::new(3, 2, 64, &engine, None).unwrap();
+            let mut owned = HighRateEncoder::new(3, 2, 64, NoSimd::new(), None).unwrap();
+
+            for original in &original {
+                first.add_original_shard(original).unwrap();
+                second.add_original_shard(original).unwrap();
+                owned.add_original_shard(original).unwrap();
+            }
+
+            let first = first.encode().unwrap();
+            let second = second.encode().unwrap();
+            let owned = owned.encode().unwrap();
+
+            let expected: Vec<_> = owned.recovery_iter().collect();
+            assert_eq!(first.recovery_iter().collect::<Vec<_>>(), expected);
+            assert_eq!(second.recovery_iter().collect::<Vec<_>>(), expected);
+        }
     }
 
     // ============================================================
@@ -552,8 +802,8 @@ mod tests {
     mod high_rate_decoder {
         use crate::{
             engine::NoSimd,
-            rate::{HighRateDecoder, RateDecoder},
-            Error,
+            rate::{HighRateDecoder, RateDecoder, RateKind},
+            Error, UnsupportedReason,
         };
 
         // ==================================================
@@ -585,6 +835,8 @@ mod tests {
                 Some(Error::UnsupportedShardCount {
                     original_count: 4096,
                     recovery_count: 61440,
+                    reason: UnsupportedReason::HighRateLimit,
+                    hint: Some(RateKind::Low),
                 })
             );
 
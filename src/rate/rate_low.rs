@@ -1,19 +1,51 @@
 use std::marker::PhantomData;
+#[cfg(feature = "decoder")]
+use std::ops::ControlFlow;
 
+#[cfg(feature = "encoder")]
 use crate::{
-    engine::{self, Engine, GF_MODULUS, GF_ORDER},
-    rate::{DecoderWork, EncoderWork, Rate, RateDecoder, RateEncoder},
-    DecoderResult, EncoderResult, Error,
+    engine,
+    rate::{EncoderWork, RateEncoder},
+    EncoderResult,
+};
+#[cfg(feature = "decoder")]
+use crate::{
+    engine::GF_MODULUS,
+    rate::{DecoderWork, RateDecoder},
+    DecoderResult, ShardWriter,
+};
+use crate::{
+    engine::{Engine, GF_ORDER},
+    rate::{self, Rate},
+    Error, UnsupportedReason,
 };
 
 // ======================================================================
 // LowRate - PUBLIC
 
 /// Reed-Solomon encoder/decoder generator using only low rate.
+///
+/// Supported shard counts are
+/// - `original_count > 0`
+/// - `recovery_count > 0`
+/// - `original_count.next_power_of_two() + recovery_count <= 65536`
 pub struct LowRate<E: Engine>(PhantomData<E>);
 
+impl<E: Engine> LowRate<E> {
+    /// Identifier of the coding scheme of low rate.
+    ///
+    /// This differs from [`HighRate::SCHEME_ID`] as recovery shards are different.
+    /// See [`CODING_SCHEME_ID`] for the stability policy.
+    ///
+    /// [`HighRate::SCHEME_ID`]: crate::rate::HighRate::SCHEME_ID
+    /// [`CODING_SCHEME_ID`]: crate::CODING_SCHEME_ID
+    pub const SCHEME_ID: &'static str = "rs-fft-gf16-cantor-low-v1";
+}
+
 impl<E: Engine> Rate<E> for LowRate<E> {
+    #[cfg(feature = "encoder")]
     type RateEncoder = LowRateEncoder<E>;
+    #[cfg(feature = "decoder")]
     type RateDecoder = LowRateDecoder<E>;
 
     fn supports(original_count: usize, recovery_count: usize) -> bool {
@@ -23,17 +55,31 @@ impl<E: Engine> Rate<E> for LowRate<E> {
             && recovery_count < GF_ORDER
             && original_count.next_power_of_two() + recovery_count <= GF_ORDER
     }
+
+    fn unsupported_reason(
+        original_count: usize,
+        recovery_count: usize,
+    ) -> Option<UnsupportedReason> {
+        if Self::supports(original_count, recovery_count) {
+            None
+        } else {
+            UnsupportedReason::zero_count(original_count, recovery_count)
+                .or(Some(UnsupportedReason::LowRateLimit))
+        }
+    }
 }
 
 // ======================================================================
 // LowRateEncoder - PUBLIC
 
 /// Reed-Solomon encoder using only low rate.
+#[cfg(feature = "encoder")]
 pub struct LowRateEncoder<E: Engine> {
     engine: E,
     work: EncoderWork,
 }
 
+#[cfg(feature = "encoder")]
 impl<E: Engine> RateEncoder<E> for LowRateEncoder<E> {
     type Rate = LowRate<E>;
 
@@ -42,44 +88,7 @@ impl<E: Engine> RateEncoder<E> for LowRateEncoder<E> {
     }
 
     fn encode(&mut self) -> Result<EncoderResult, Error> {
-        let (mut work, original_count, recovery_count) = self.work.encode_begin()?;
-        let chunk_size = original_count.next_power_of_two();
-        let engine = &self.engine;
-
-        // ZEROPAD ORIGINAL
-
-        work.zero(original_count..chunk_size);
-
-        // IFFT - ORIGINAL
-
-        engine.ifft(&mut work, 0, chunk_size, original_count, 0);
-
-        // COPY IFFT RESULT TO OTHER CHUNKS
-
-        let mut chunk_start = chunk_size;
-        while chunk_start < recovery_count {
-            work.copy_within(0, chunk_start, chunk_size);
-            chunk_start += chunk_size;
-        }
-
-        // FFT - FULL CHUNKS
-
-        let mut chunk_start = 0;
-        while chunk_start + chunk_size <= recovery_count {
-            engine.fft_skew_end(&mut work, chunk_start, chunk_size, chunk_size);
-            chunk_start += chunk_size;
-        }
-
-        // FFT - FINAL PARTIAL CHUNK
-
-        let last_count = recovery_count % chunk_size;
-        if last_count > 0 {
-            engine.fft_skew_end(&mut work, chunk_start, chunk_size, last_count);
-        }
-
-        // DONE
-
-        Ok(EncoderResult::new(&mut self.work))
+        self.encode_with_checkpoints(|_| {})
     }
 
     fn into_parts(self) -> (E, EncoderWork) {
@@ -108,9 +117,108 @@ impl<E: Engine> RateEncoder<E> for LowRateEncoder<E> {
     }
 }
 
+// ======================================================================
+// LowRateEncoder - CRATE
+
+#[cfg(feature = "encoder")]
+impl<E: Engine> LowRateEncoder<E> {
+    // Like `encode`, calling `checkpoint` with decoder progress
+    // of the same step before IFFT and FFT.
+    pub(crate) fn encode_with_checkpoints<F: FnMut(f32)>(
+        &mut self,
+        checkpoint: F,
+    ) -> Result<EncoderResult, Error> {
+        Self::encode_work(&self.engine, &mut self.work, checkpoint)
+    }
+
+    // Like `encode_with_checkpoints` but with given engine and
+    // working space which has been reset by this rate.
+    pub(crate) fn encode_work<'a, F: FnMut(f32)>(
+        engine: &E,
+        encoder_work: &'a mut EncoderWork,
+        mut checkpoint: F,
+    ) -> Result<EncoderResult<'a>, Error> {
+        trace_span!(
+            "encode",
+            rate = "low",
+            original_count = encoder_work.original_count(),
+            recovery_count = encoder_work.recovery_count(),
+            shard_bytes = encoder_work.shard_bytes(),
+        );
+
+        let (mut work, original_count, recovery_count) = encoder_work.encode_begin()?;
+        let chunk_size = original_count.next_power_of_two();
+
+        // Recovery shard `i` is value of the polynomial at point `chunk_size + i`,
+        // so chunk at `chunk_start` is FFT with `skew_delta = chunk_start + chunk_size`.
+        // Both are multiples of `chunk_size` and `Rate::supports` guarantees
+        // that last chunk ends at or before `GF_ORDER`.
+        debug_assert!(chunk_size.is_power_of_two());
+        debug_assert!(chunk_size + recovery_count <= GF_ORDER);
+
+        // ZEROPAD ORIGINAL
+
+        checkpoint(rate::PROGRESS_IFFT);
+        work.zero_with::<E>(original_count..chunk_size);
+
+        // IFFT - ORIGINAL
+
+        {
+            trace_span!("ifft", chunk_size);
+            engine.ifft(&mut work, 0, chunk_size, original_count, 0);
+        }
+
+        // COPY IFFT RESULT TO OTHER CHUNKS
+
+        checkpoint(rate::PROGRESS_FFT);
+        {
+            trace_span!("copy_chunks", chunk_size);
+            let mut chunk_start = chunk_size;
+            while chunk_start < recovery_count {
+                work.copy_within(0, chunk_start, chunk_size);
+                chunk_start += chunk_size;
+            }
+        }
+
+        {
+            trace_span!("fft", chunk_size);
+
+            // FFT - FULL CHUNKS
+
+            let mut chunk_start = 0;
+            while chunk_start + chunk_size <= recovery_count {
+                debug_assert!(chunk_start + 2 * chunk_size <= GF_ORDER);
+                engine.fft_skew_end(&mut work, chunk_start, chunk_size, chunk_size);
+                chunk_start += chunk_size;
+            }
+
+            // FFT - FINAL PARTIAL CHUNK
+
+            let last_count = recovery_count % chunk_size;
+            if last_count > 0 {
+                debug_assert!(chunk_start + 2 * chunk_size <= GF_ORDER);
+                engine.fft_skew_end(&mut work, chunk_start, chunk_size, last_count);
+            }
+        }
+
+        // DONE
+
+        Ok(EncoderResult::new(encoder_work))
+    }
+
+    pub(crate) fn work(&self) -> &EncoderWork {
+        &self.work
+    }
+
+    pub(crate) fn work_mut(&mut self) -> &mut EncoderWork {
+        &mut self.work
+    }
+}
+
 // ======================================================================
 // LowRateEncoder - PRIVATE
 
+#[cfg(feature = "encoder")]
 impl<E: Engine> LowRateEncoder<E> {
     fn reset_work(
         original_count: usize,
@@ -124,7 +232,7 @@ impl<E: Engine> LowRateEncoder<E> {
             recovery_count,
             shard_bytes,
             Self::work_count(original_count, recovery_count),
-        );
+        )?;
         Ok(())
     }
 
@@ -141,11 +249,13 @@ impl<E: Engine> LowRateEncoder<E> {
 // LowRateDecoder - PUBLIC
 
 /// Reed-Solomon decoder using only low rate.
+#[cfg(feature = "decoder")]
 pub struct LowRateDecoder<E: Engine> {
     engine: E,
     work: DecoderWork,
 }
 
+#[cfg(feature = "decoder")]
 impl<E: Engine> RateDecoder<E> for LowRateDecoder<E> {
     type Rate = LowRate<E>;
 
@@ -165,117 +275,221 @@ impl<E: Engine> RateDecoder<E> for LowRateDecoder<E> {
         self.work.add_recovery_shard(index, recovery_shard)
     }
 
+    fn begin_original_shard(&mut self, index: usize) -> Result<ShardWriter, Error> {
+        let pos = self.work.begin_original_shard(index)?;
+        Ok(ShardWriter::new(&mut self.work, pos))
+    }
+
+    fn begin_recovery_shard(&mut self, index: usize) -> Result<ShardWriter, Error> {
+        let pos = self.work.begin_recovery_shard(index)?;
+        Ok(ShardWriter::new(&mut self.work, pos))
+    }
+
     fn decode(&mut self) -> Result<DecoderResult, Error> {
-        let (mut work, original_count, recovery_count, received) =
+        self.decode_with_control(|_| ControlFlow::Continue(()))
+    }
+
+    fn decode_with_control<F: FnMut(f32) -> ControlFlow<()>>(
+        &mut self,
+        mut control: F,
+    ) -> Result<DecoderResult, Error> {
+        match self.decode_steps(&mut control) {
+            Ok(()) => Ok(DecoderResult::new(&mut self.work)),
+            Err(Error::Cancelled) => {
+                self.work.reset_received();
+                Err(Error::Cancelled)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn into_parts(self) -> (E, DecoderWork) {
+        (self.engine, self.work)
+    }
+
+    fn new(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        engine: E,
+        work: Option<DecoderWork>,
+    ) -> Result<Self, Error> {
+        let mut work = work.unwrap_or_default();
+        Self::reset_work(original_count, recovery_count, shard_bytes, &mut work)?;
+        Ok(Self { work, engine })
+    }
+
+    fn reset(
+        &mut self,
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<(), Error> {
+        Self::reset_work(original_count, recovery_count, shard_bytes, &mut self.work)
+    }
+}
+
+// ======================================================================
+// LowRateDecoder - CRATE
+
+#[cfg(feature = "decoder")]
+impl<E: Engine> LowRateDecoder<E> {
+    pub(crate) fn work(&self) -> &DecoderWork {
+        &self.work
+    }
+
+    // Engine together with working space, e.g. for `DecoderWork::peel`.
+    pub(crate) fn parts_mut(&mut self) -> (&E, &mut DecoderWork) {
+        (&self.engine, &mut self.work)
+    }
+
+    pub(crate) fn work_mut(&mut self) -> &mut DecoderWork {
+        &mut self.work
+    }
+}
+
+// ======================================================================
+// LowRateDecoder - PRIVATE
+
+#[cfg(feature = "decoder")]
+impl<E: Engine> LowRateDecoder<E> {
+    // Decodes in place, calling `control` before each step.
+    // - Received shards are overwritten, so after `Error::Cancelled`
+    //   the caller must reset received shards.
+    fn decode_steps<F: FnMut(f32) -> ControlFlow<()>>(
+        &mut self,
+        control: &mut F,
+    ) -> Result<(), Error> {
+        trace_span!(
+            "decode",
+            rate = "low",
+            original_count = self.work.original_count(),
+            recovery_count = self.work.recovery_count(),
+            shard_bytes = self.work.shard_bytes(),
+            received_count = self.work.received_count(),
+        );
+
+        let (mut work, original_count, recovery_count, received, erasures) =
             if let Some(stuff) = self.work.decode_begin()? {
                 stuff
             } else {
                 // Nothing to do, original data is complete.
-                return Ok(DecoderResult::new(&mut self.work));
+                return Ok(());
             };
 
         let chunk_size = original_count.next_power_of_two();
         let recovery_end = chunk_size + recovery_count;
         let work_count = work.len();
 
+        // Surplus recovery shards have been forgotten by `decode_begin`,
+        // so `work` is zero after the last received recovery shard.
+        let received_end = (chunk_size..recovery_end)
+            .rev()
+            .find(|i| received[*i])
+            .map_or(chunk_size, |i| i + 1);
+
         // ERASURE LOCATIONS
 
-        let mut erasures = [0; GF_ORDER];
+        rate::checkpoint(control, 0.0)?;
+
+        {
+            trace_span!("erasures");
 
-        for i in 0..original_count {
-            if !received[i] {
-                erasures[i] = 1;
+            for i in 0..original_count {
+                if !received[i] {
+                    erasures[i] = 1;
+                }
             }
-        }
 
-        for i in chunk_size..recovery_end {
-            if !received[i] {
-                erasures[i] = 1;
+            for i in chunk_size..recovery_end {
+                if !received[i] {
+                    erasures[i] = 1;
+                }
             }
-        }
 
-        erasures[recovery_end..].fill(1);
+            erasures[recovery_end..].fill(1);
+        }
 
         // EVALUATE POLYNOMIAL
 
-        E::eval_poly(&mut erasures, GF_ORDER);
+        {
+            trace_span!("eval_poly");
+            #[cfg(feature = "unstable-basis")]
+            self.engine.eval_poly_with_tables(erasures, GF_ORDER);
+            #[cfg(not(feature = "unstable-basis"))]
+            E::eval_poly(erasures, GF_ORDER);
+        }
 
         // MULTIPLY SHARDS
 
-        // work[               .. original_count] = original * erasures
-        // work[original_count .. chunk_size    ] = 0
-        // work[chunk_size     .. original_end  ] = recovery * erasures
-        // work[recovery_end   ..               ] = 0
+        rate::checkpoint(control, rate::PROGRESS_MULTIPLY)?;
 
-        for i in 0..original_count {
-            if received[i] {
-                self.engine.mul(&mut work[i], erasures[i]);
-            } else {
-                work[i].fill(0);
+        {
+            trace_span!("multiply");
+
+            // work[               .. original_count] = original * erasures
+            // work[original_count .. chunk_size    ] = 0
+            // work[chunk_size     .. original_end  ] = recovery * erasures
+            // work[recovery_end   ..               ] = 0
+
+            for i in 0..original_count {
+                if received[i] {
+                    self.engine.mul(&mut work[i], erasures[i]);
+                } else {
+                    E::fill(&mut work[i], 0);
+                }
             }
-        }
 
-        work.zero(original_count..chunk_size);
+            work.zero_with::<E>(original_count..chunk_size);
 
-        for i in chunk_size..recovery_end {
-            if received[i] {
-                self.engine.mul(&mut work[i], erasures[i]);
-            } else {
-                work[i].fill(0);
+            for i in chunk_size..recovery_end {
+                if received[i] {
+                    self.engine.mul(&mut work[i], erasures[i]);
+                } else {
+                    E::fill(&mut work[i], 0);
+                }
             }
-        }
 
-        work.zero(recovery_end..);
+            work.zero_with::<E>(recovery_end..);
+        }
 
         // IFFT / FORMAL DERIVATIVE / FFT
 
-        self.engine.ifft(&mut work, 0, work_count, recovery_end, 0);
-        E::formal_derivative(&mut work);
-        self.engine.fft(&mut work, 0, work_count, recovery_end, 0);
+        rate::checkpoint(control, rate::PROGRESS_IFFT)?;
+        {
+            trace_span!("ifft", work_count);
+            self.engine.ifft(&mut work, 0, work_count, received_end, 0);
+        }
+        rate::checkpoint(control, rate::PROGRESS_FORMAL_DERIVATIVE)?;
+        {
+            trace_span!("formal_derivative", work_count);
+            E::formal_derivative(&mut work);
+        }
+        rate::checkpoint(control, rate::PROGRESS_FFT)?;
+        {
+            trace_span!("fft", work_count);
+            self.engine.fft(&mut work, 0, work_count, recovery_end, 0);
+        }
 
         // REVEAL ERASURES
 
-        for i in 0..original_count {
-            if !received[i] {
-                self.engine.mul(&mut work[i], GF_MODULUS - erasures[i]);
+        rate::checkpoint(control, rate::PROGRESS_REVEAL)?;
+
+        {
+            trace_span!("reveal");
+
+            for i in 0..original_count {
+                if !received[i] {
+                    self.engine.mul(&mut work[i], GF_MODULUS - erasures[i]);
+                }
             }
         }
 
         // DONE
 
-        Ok(DecoderResult::new(&mut self.work))
-    }
-
-    fn into_parts(self) -> (E, DecoderWork) {
-        (self.engine, self.work)
-    }
-
-    fn new(
-        original_count: usize,
-        recovery_count: usize,
-        shard_bytes: usize,
-        engine: E,
-        work: Option<DecoderWork>,
-    ) -> Result<Self, Error> {
-        let mut work = work.unwrap_or_default();
-        Self::reset_work(original_count, recovery_count, shard_bytes, &mut work)?;
-        Ok(Self { work, engine })
-    }
-
-    fn reset(
-        &mut self,
-        original_count: usize,
-        recovery_count: usize,
-        shard_bytes: usize,
-    ) -> Result<(), Error> {
-        Self::reset_work(original_count, recovery_count, shard_bytes, &mut self.work)
+        Ok(())
     }
-}
 
-// ======================================================================
-// LowRateDecoder - PRIVATE
-
-impl<E: Engine> LowRateDecoder<E> {
     fn reset_work(
         original_count: usize,
         recovery_count: usize,
@@ -293,7 +507,7 @@ impl<E: Engine> LowRateDecoder<E> {
             0,
             original_count.next_power_of_two(),
             Self::work_count(original_count, recovery_count),
-        );
+        )?;
 
         Ok(())
     }
@@ -308,7 +522,7 @@ impl<E: Engine> LowRateDecoder<E> {
 // ======================================================================
 // TESTS
 
-#[cfg(test)]
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
 mod tests {
     use super::*;
     use crate::test_util;
@@ -335,6 +549,31 @@ mod tests {
         roundtrip_single!(LowRate, 2, 3, 1024, test_util::LOW_2_3, &[0, 1], &[], 123);
     }
 
+    // Surplus recovery shards are forgotten before decoding.
+    #[test]
+    fn roundtrip_surplus_recovery() {
+        roundtrip_single!(
+            LowRate,
+            2,
+            3,
+            1024,
+            test_util::LOW_2_3,
+            &[1],
+            &[0, 1, 2],
+            123
+        );
+        roundtrip_single!(
+            LowRate,
+            2,
+            3,
+            1024,
+            test_util::LOW_2_3,
+            &[],
+            &[0, 1, 2],
+            123
+        );
+    }
+
     #[test]
     fn roundtrips_tiny() {
         for (original_count, recovery_count, seed, recovery_hash) in test_util::LOW_TINY {
@@ -419,14 +658,146 @@ mod tests {
         );
     }
 
+    // ============================================================
+    // ENCODING - CHUNKED
+
+    mod chunked {
+        #[cfg(target_arch = "aarch64")]
+        use crate::engine::Neon;
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        use crate::engine::{Avx2, Ssse3};
+        use crate::{
+            engine::{self, Engine, Naive, NoSimd, ShardsRefMut},
+            rate::{LowRate, Rate, RateEncoder},
+            test_util,
+        };
+
+        // Recovery shards as values of the interpolated polynomial
+        // at points `chunk_size ..` with a single full-size FFT.
+        fn reference_recovery(original: &[Vec<u8>], recovery_count: usize) -> Vec<Vec<u8>> {
+            let chunk_size = original.len().next_power_of_two();
+            let size = (chunk_size + recovery_count).next_power_of_two();
+
+            let mut bytes = vec![0; size * 64];
+            for (i, shard) in original.iter().enumerate() {
+                bytes[i * 64..(i + 1) * 64].copy_from_slice(shard);
+            }
+
+            let naive = Naive::new();
+            let mut coefs = ShardsRefMut::new(chunk_size, 64, &mut bytes[..chunk_size * 64]);
+            engine::additive_ifft(&naive, &mut coefs, 0);
+            engine::additive_fft(&naive, &mut ShardsRefMut::new(size, 64, &mut bytes), 0);
+
+            bytes[chunk_size * 64..(chunk_size + recovery_count) * 64]
+                .chunks_exact(64)
+                .map(|shard| shard.to_vec())
+                .collect()
+        }
+
+        fn assert_chunks_match_reference(original_count: usize, recovery_count: usize) {
+            let chunk_size = original_count.next_power_of_two();
+            let original = test_util::generate_original(original_count, 64, 160);
+            let expected = reference_recovery(&original, recovery_count);
+
+            let mut encoder =
+                LowRate::encoder(original_count, recovery_count, 64, NoSimd::new(), None).unwrap();
+            for shard in &original {
+                encoder.add_original_shard(shard).unwrap();
+            }
+            let result = encoder.encode().unwrap();
+
+            for (i, shard) in expected.iter().enumerate() {
+                assert_eq!(
+                    result.recovery(i).unwrap(),
+                    shard,
+                    "{} original, {} recovery: chunk {} shard {}",
+                    original_count,
+                    recovery_count,
+                    i / chunk_size,
+                    i,
+                );
+            }
+        }
+
+        #[test]
+        fn spans_one_to_four_chunks() {
+            // chunk_size = 4
+            for recovery_count in [3, 4, 5, 8, 9, 12, 13, 16] {
+                assert_chunks_match_reference(3, recovery_count);
+            }
+        }
+
+        #[test]
+        fn spans_chunks_up_to_end_of_field() {
+            // chunk_size = 4096, last chunk ends at 65536
+            assert_chunks_match_reference(4000, 61440);
+            assert_chunks_match_reference(4000, 61439);
+        }
+
+        fn encode<E: Engine>(
+            engine: E,
+            original: &[Vec<u8>],
+            recovery_count: usize,
+        ) -> Vec<Vec<u8>> {
+            let mut encoder =
+                LowRate::encoder(original.len(), recovery_count, 64, engine, None).unwrap();
+            for shard in original {
+                encoder.add_original_shard(shard).unwrap();
+            }
+            let result = encoder.encode().unwrap();
+            result.recovery_iter().map(|shard| shard.to_vec()).collect()
+        }
+
+        // IFFT of original chunk is at skew 0 where skew values
+        // are `GF_MODULUS`, followed by many small recovery chunks
+        // up to end of the field.
+        #[test]
+        fn small_chunks_all_engines() {
+            assert_chunks_match_reference(2, 65534);
+
+            for (original_count, recovery_count) in [(2, 65534), (3, 65532), (5, 65528)] {
+                let original = test_util::generate_original(original_count, 64, 188);
+                let expected = encode(Naive::new(), &original, recovery_count);
+
+                assert!(encode(NoSimd::new(), &original, recovery_count) == expected);
+
+                #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+                {
+                    if is_x86_feature_detected!("avx2") {
+                        assert!(encode(Avx2::new(), &original, recovery_count) == expected);
+                    }
+                    if is_x86_feature_detected!("ssse3") {
+                        assert!(encode(Ssse3::new(), &original, recovery_count) == expected);
+                    }
+                }
+
+                #[cfg(target_arch = "aarch64")]
+                {
+                    if std::arch::is_aarch64_feature_detected!("neon") {
+                        assert!(encode(Neon::new(), &original, recovery_count) == expected);
+                    }
+                }
+            }
+        }
+
+        #[test]
+        #[ignore]
+        fn spans_chunks_of_16384() {
+            // chunk boundaries at 16384, 32768 and 49152
+            assert_chunks_match_reference(10000, 16385);
+            assert_chunks_match_reference(10000, 32769);
+            assert_chunks_match_reference(10000, 49152);
+        }
+    }
+
     // ============================================================
     // LowRate
 
     mod low_rate {
         use crate::{
             engine::NoSimd,
-            rate::{LowRate, Rate},
-            Error,
+            rate::{LowRate, Rate, RateKind},
+            Error, UnsupportedReason,
         };
 
         #[test]
@@ -438,6 +809,8 @@ mod tests {
                 Some(Error::UnsupportedShardCount {
                     original_count: 61440,
                     recovery_count: 4096,
+                    reason: UnsupportedReason::LowRateLimit,
+                    hint: Some(RateKind::High),
                 })
             );
         }
@@ -451,6 +824,8 @@ mod tests {
                 Some(Error::UnsupportedShardCount {
                     original_count: 61440,
                     recovery_count: 4096,
+                    reason: UnsupportedReason::LowRateLimit,
+                    hint: Some(RateKind::High),
                 })
             );
         }
@@ -483,6 +858,8 @@ mod tests {
                 Some(Error::UnsupportedShardCount {
                     original_count: 61440,
                     recovery_count: 4096,
+                    reason: UnsupportedReason::LowRateLimit,
+                    hint: Some(RateKind::High),
                 })
             );
         }
@@ -494,8 +871,8 @@ mod tests {
     mod low_rate_encoder {
         use crate::{
             engine::NoSimd,
-            rate::{LowRateEncoder, RateEncoder},
-            Error,
+            rate::{LowRateEncoder, RateEncoder, RateKind},
+            Error, UnsupportedReason,
         };
 
         // ==================================================
@@ -529,6 +906,8 @@ mod tests {
                 Some(Error::UnsupportedShardCount {
                     original_count: 61440,
                     recovery_count: 4096,
+                    reason: UnsupportedReason::LowRateLimit,
+                    hint: Some(RateKind::High),
                 })
             );
         }
@@ -552,8 +931,8 @@ mod tests {
     mod low_rate_decoder {
         use crate::{
             engine::NoSimd,
-            rate::{LowRateDecoder, RateDecoder},
-            Error,
+            rate::{LowRateDecoder, RateDecoder, RateKind},
+            Error, UnsupportedReason,
         };
 
         // ==================================================
@@ -587,6 +966,8 @@ mod tests {
                 Some(Error::UnsupportedShardCount {
                     original_count: 61440,
                     recovery_count: 4096,
+                    reason: UnsupportedReason::LowRateLimit,
+                    hint: Some(RateKind::High),
                 })
             );
         }
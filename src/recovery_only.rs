@@ -0,0 +1,326 @@
+//! Encoding without keeping original shards.
+//!
+//! **This is an advanced module which is not needed for [simple usage] or [basic usage].**
+//!
+//! [`ReedSolomonEncoder`] keeps every original shard in its working space
+//! until [`encode`] is called, so with many original shards
+//! most of its memory is spent on data which the caller already has.
+//!
+//! [`RecoveryOnlyEncoder`] instead folds original shards into running
+//! recovery buffers as they are added, so its working space is
+//! `O(recovery_count * shard_bytes)` regardless of `original_count`.
+//! Recovery shards are identical to those of [`ReedSolomonEncoder`].
+//!
+//! # Memory usage
+//!
+//! When [high rate] is used, original shards are folded in chunks of
+//! `recovery_count.next_power_of_two()` shards: added shards are held
+//! until their chunk is complete and then merged into the first chunk.
+//! So working space is at most `2 * recovery_count.next_power_of_two()`
+//! shards, e.g. 2048 shards instead of 8192 with 8192 original shards
+//! and 1024 recovery shards.
+//!
+//! When [low rate] is used there are at most
+//! `recovery_count.next_power_of_two()` original shards,
+//! so they are simply kept as with [`ReedSolomonEncoder`].
+//!
+//! # Original shards
+//!
+//! Original shards can't be retrieved from [`RecoveryOnlyEncoder`],
+//! as their content is overwritten when their chunk is folded.
+//! Original shards are also not deduplicated.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use reed_solomon_simd::recovery_only::RecoveryOnlyEncoder;
+//!
+//! let original = [[1u8; 64], [2u8; 64], [3u8; 64], [4u8; 64], [5u8; 64]];
+//!
+//! let mut encoder = RecoveryOnlyEncoder::new(5, 2, 64)?;
+//! for original in &original {
+//!     encoder.add_original_shard(original)?;
+//! }
+//! let result = encoder.encode()?;
+//! let recovery: Vec<_> = result.recovery_iter().collect();
+//!
+//! assert_eq!(recovery, reed_solomon_simd::encode(5, 2, original)?);
+//! # Ok::<(), reed_solomon_simd::Error>(())
+//! ```
+//!
+//! [simple usage]: crate#simple-usage
+//! [basic usage]: crate#basic-usage
+//! [`ReedSolomonEncoder`]: crate::ReedSolomonEncoder
+//! [`encode`]: crate::ReedSolomonEncoder::encode
+//! [high rate]: crate::rate::HighRate
+//! [low rate]: crate::rate::LowRate
+
+use crate::{
+    engine::{DefaultEngine, Engine},
+    rate::{self, EncoderWork, HighRate, LowRateEncoder, Rate, RateEncoder},
+    CodecParams, EncoderResult, Error,
+};
+
+// ======================================================================
+// RecoveryOnlyEncoder - PUBLIC
+
+/// Encoder which doesn't keep original shards.
+///
+/// See [module documentation](self) for details.
+pub struct RecoveryOnlyEncoder<E: Engine = DefaultEngine>(InnerEncoder<E>);
+
+impl RecoveryOnlyEncoder {
+    /// Creates new encoder with [`DefaultEngine`].
+    ///
+    /// Supported shard counts and shard sizes are same as with
+    /// [`ReedSolomonEncoder::new`](crate::ReedSolomonEncoder::new).
+    pub fn new(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<Self, Error> {
+        Self::with_engine(
+            original_count,
+            recovery_count,
+            shard_bytes,
+            DefaultEngine::new(),
+        )
+    }
+}
+
+impl<E: Engine> RecoveryOnlyEncoder<E> {
+    /// Adds one original shard to the encoder.
+    ///
+    /// Original shards have indexes `0..original_count` corresponding
+    /// to the order in which they are added.
+    ///
+    /// With [high rate] this folds the shard into recovery buffers
+    /// if it completes a chunk, see [module documentation](self).
+    ///
+    /// [high rate]: crate::rate::HighRate
+    pub fn add_original_shard<T: AsRef<[u8]>>(&mut self, original_shard: T) -> Result<(), Error> {
+        match &mut self.0 {
+            InnerEncoder::High { engine, work } => {
+                let original_shard = original_shard.as_ref();
+                let index = work.begin_original_shard(original_shard)?;
+                let chunk_size = work.recovery_count().next_power_of_two();
+
+                // work[          .. chunk_size    ] = first chunk, i.e. accumulator
+                // work[chunk_size .. 2 * chunk_size] = current chunk
+
+                let chunk_start = index - index % chunk_size;
+                let offset = index % chunk_size;
+                let pos = if chunk_start == 0 {
+                    offset
+                } else {
+                    chunk_size + offset
+                };
+                work.finish_original_shard_at(index, pos, original_shard);
+
+                if offset + 1 == chunk_size || index + 1 == work.original_count() {
+                    fold_chunk(engine, work, chunk_start, offset + 1);
+                }
+                Ok(())
+            }
+
+            InnerEncoder::Low(encoder) => encoder.add_original_shard(original_shard),
+        }
+    }
+
+    /// Returns number of bytes currently allocated for working space.
+    pub fn allocated_bytes(&self) -> usize {
+        match &self.0 {
+            InnerEncoder::High { work, .. } => work.allocated_bytes(),
+            InnerEncoder::Low(encoder) => encoder.work().allocated_bytes(),
+        }
+    }
+
+    /// Encodes the added original shards returning [`EncoderResult`]
+    /// which contains the generated recovery shards.
+    ///
+    /// When returned [`EncoderResult`] is dropped the encoder is
+    /// automatically reset and ready for new round of encoding.
+    pub fn encode(&mut self) -> Result<EncoderResult, Error> {
+        match &mut self.0 {
+            InnerEncoder::High { engine, work } => {
+                let (mut shards, _, recovery_count) = work.encode_begin()?;
+                let chunk_size = recovery_count.next_power_of_two();
+
+                {
+                    trace_span!("fft", chunk_size);
+                    engine.fft(&mut shards, 0, chunk_size, recovery_count, 0);
+                }
+
+                Ok(EncoderResult::new(work))
+            }
+
+            InnerEncoder::Low(encoder) => encoder.encode(),
+        }
+    }
+
+    /// Like [`new`](RecoveryOnlyEncoder::new) with given [`Engine`].
+    pub fn with_engine(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        engine: E,
+    ) -> Result<Self, Error> {
+        let params = CodecParams::new(original_count, recovery_count, shard_bytes);
+
+        if rate::use_high_rate(params)? {
+            HighRate::<E>::validate(original_count, recovery_count, shard_bytes)?;
+
+            let chunk_size = recovery_count.next_power_of_two();
+            let work_count = if original_count > chunk_size {
+                2 * chunk_size
+            } else {
+                chunk_size
+            };
+
+            let mut work = EncoderWork::new();
+            work.reset(original_count, recovery_count, shard_bytes, work_count)?;
+
+            Ok(Self(InnerEncoder::High { engine, work }))
+        } else {
+            Ok(Self(InnerEncoder::Low(LowRateEncoder::new(
+                original_count,
+                recovery_count,
+                shard_bytes,
+                engine,
+                None,
+            )?)))
+        }
+    }
+}
+
+// ======================================================================
+// InnerEncoder - PRIVATE
+
+enum InnerEncoder<E: Engine> {
+    High { engine: E, work: EncoderWork },
+    Low(LowRateEncoder<E>),
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+// Folds chunk starting at original shard `chunk_start`,
+// of which `count` shards have been added, into the first chunk.
+// - This is the IFFT step of high rate encoding,
+//   see `HighRateEncoder::encode_with_checkpoints`.
+fn fold_chunk<E: Engine>(engine: &E, work: &mut EncoderWork, chunk_start: usize, count: usize) {
+    let chunk_size = work.recovery_count().next_power_of_two();
+    let mut shards = work.shards_mut();
+
+    trace_span!("ifft", chunk_start, chunk_size);
+
+    if chunk_start == 0 {
+        shards.zero_with::<E>(count..chunk_size);
+        engine.ifft_skew_end(&mut shards, 0, chunk_size, count);
+    } else {
+        // Chunk is at `chunk_size` in working space,
+        // so skew is given explicitly instead of `ifft_skew_end`.
+        shards.zero_with::<E>(chunk_size + count..);
+        engine.ifft(
+            &mut shards,
+            chunk_size,
+            chunk_size,
+            count,
+            chunk_start + chunk_size,
+        );
+        E::xor_within(&mut shards, 0, chunk_size, chunk_size);
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
+mod tests {
+    use super::*;
+    use crate::{engine::NoSimd, test_util, ReedSolomonEncoder};
+
+    #[test]
+    fn matches_reed_solomon_encoder() {
+        for (original_count, recovery_count) in [
+            // high rate: single chunk, full chunks, partial final chunk
+            (3, 2),
+            (8, 2),
+            (100, 30),
+            (1000, 100),
+            // low rate
+            (2, 3),
+            (100, 300),
+        ] {
+            let original = test_util::generate_original(original_count, 1024, 186);
+            let expected = crate::encode(original_count, recovery_count, &original).unwrap();
+
+            let mut encoder = RecoveryOnlyEncoder::with_engine(
+                original_count,
+                recovery_count,
+                1024,
+                NoSimd::new(),
+            )
+            .unwrap();
+
+            // Twice to check that encoder is reset after first round.
+            for _ in 0..2 {
+                for shard in &original {
+                    encoder.add_original_shard(shard).unwrap();
+                }
+                let result = encoder.encode().unwrap();
+                let recovery: Vec<_> = result.recovery_iter().map(|s| s.to_vec()).collect();
+                assert_eq!(recovery, expected, "{original_count}:{recovery_count}");
+            }
+        }
+    }
+
+    #[test]
+    fn working_space() {
+        let shard_bytes = 1024;
+
+        let encoder = RecoveryOnlyEncoder::new(8192, 1024, shard_bytes).unwrap();
+        assert_eq!(encoder.allocated_bytes(), 2 * 1024 * shard_bytes);
+
+        let encoder = ReedSolomonEncoder::new(8192, 1024, shard_bytes).unwrap();
+        assert_eq!(encoder.allocated_bytes(), 8192 * shard_bytes);
+    }
+
+    #[test]
+    fn errors() {
+        let mut encoder = RecoveryOnlyEncoder::new(3, 2, 64).unwrap();
+
+        assert_eq!(
+            encoder.add_original_shard([0; 128]),
+            Err(Error::DifferentShardSize {
+                shard_bytes: 64,
+                got: 128
+            })
+        );
+
+        encoder.add_original_shard([0; 64]).unwrap();
+        assert_eq!(
+            encoder.encode().err(),
+            Some(Error::TooFewOriginalShards {
+                original_count: 3,
+                original_received_count: 1,
+            })
+        );
+
+        encoder.add_original_shard([0; 64]).unwrap();
+        encoder.add_original_shard([0; 64]).unwrap();
+        assert_eq!(
+            encoder.add_original_shard([0; 64]),
+            Err(Error::TooManyOriginalShards { original_count: 3 })
+        );
+
+        assert_eq!(
+            RecoveryOnlyEncoder::new(3, 2, 63).err(),
+            Some(Error::InvalidShardSize { shard_bytes: 63 })
+        );
+        assert_eq!(
+            RecoveryOnlyEncoder::new(2, 3, 63).err(),
+            Some(Error::InvalidShardSize { shard_bytes: 63 })
+        );
+    }
+}
@@ -1,7 +1,31 @@
+#[cfg(feature = "allocator-api")]
+use std::alloc::Allocator;
+#[cfg(feature = "decoder")]
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Read, Write},
+    mem::MaybeUninit,
+    ops::ControlFlow,
+};
+
+use crate::{
+    engine::{DefaultEngine, Engine},
+    integrity::{self, IntegrityFooter},
+    phase_timings::PhaseTimer,
+    rate::{self, DefaultRate, Rate},
+    CodecParams, Error, PhaseTimings,
+};
+#[cfg(feature = "decoder")]
 use crate::{
-    engine::DefaultEngine,
-    rate::{DefaultRate, DefaultRateDecoder, DefaultRateEncoder, Rate, RateDecoder, RateEncoder},
-    DecoderResult, EncoderResult, Error,
+    integrity::FOOTER_BYTES,
+    rate::{DecoderWork, DefaultRateDecoder, LoadError, RateDecoder},
+    repair::{RepairPlan, ShardId},
+    DecoderResult, ShardKind, ShardWriter,
+};
+#[cfg(feature = "encoder")]
+use crate::{
+    rate::{DefaultRateEncoder, EncoderWork, RateEncoder},
+    EncoderResult,
 };
 
 // ======================================================================
@@ -9,9 +33,30 @@ use crate::{
 
 /// Reed-Solomon encoder using [`DefaultEngine`] and [`DefaultRate`].
 ///
+/// # Working space
+///
+/// Encoder owns its working space, see [`allocated_bytes`],
+/// while the engine only refers to `'static` tables.
+/// Dropping the encoder frees the working space, which with large
+/// configurations may stall latency-sensitive threads for a while
+/// (and with `zeroize` feature includes zeroing it first).
+/// [`into_recycled`] takes the working space instead, so that it can be
+/// re-used by [`from_recycled`] or dropped elsewhere at a better time.
+///
 /// [`DefaultEngine`]: crate::engine::DefaultEngine
-pub struct ReedSolomonEncoder(DefaultRateEncoder<DefaultEngine>);
+/// [`allocated_bytes`]: ReedSolomonEncoder::allocated_bytes
+/// [`into_recycled`]: ReedSolomonEncoder::into_recycled
+/// [`from_recycled`]: ReedSolomonEncoder::from_recycled
+#[cfg(feature = "encoder")]
+pub struct ReedSolomonEncoder {
+    rate: DefaultRateEncoder<DefaultEngine>,
+    // Stripe id if integrity footer is enabled, see `with_integrity_footer`.
+    footer_stripe_id: Option<u64>,
+    // Working space given back with `restore_work`, see `take_work`.
+    work: Option<EncoderWork>,
+}
 
+#[cfg(feature = "encoder")]
 impl ReedSolomonEncoder {
     /// Adds one original shard to the encoder.
     ///
@@ -20,7 +65,19 @@ impl ReedSolomonEncoder {
     ///
     /// See [basic usage](crate#basic-usage) for an example.
     pub fn add_original_shard<T: AsRef<[u8]>>(&mut self, original_shard: T) -> Result<(), Error> {
-        self.0.add_original_shard(original_shard)
+        if self.footer_stripe_id.is_none() {
+            return self.rate.add_original_shard(original_shard);
+        }
+
+        let original_shard = original_shard.as_ref();
+        let work = self.rate.work_mut();
+        let index = work.begin_original_shard(original_shard)?;
+        if !integrity::is_reserved_zero(original_shard) {
+            return Err(Error::NonZeroReservedBytes { index });
+        }
+
+        work.finish_original_shard(index, original_shard);
+        Ok(())
     }
 
     /// Encodes the added original shards returning [`EncoderResult`]
@@ -33,7 +90,198 @@ impl ReedSolomonEncoder {
     ///
     /// [`reset`]: ReedSolomonEncoder::reset
     pub fn encode(&mut self) -> Result<EncoderResult, Error> {
-        self.0.encode()
+        self.encode_with_checkpoints(|_| {})
+    }
+
+    /// Like [`encode`] but also returns time taken by each phase of encoding.
+    ///
+    /// This is meant for tuning without external profilers.
+    /// [`encode`] doesn't measure time at all.
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonEncoder;
+    ///
+    /// let mut encoder = ReedSolomonEncoder::new(2, 1, 64)?;
+    /// encoder.add_original_shard([1u8; 64])?;
+    /// encoder.add_original_shard([2u8; 64])?;
+    /// let (result, timings) = encoder.encode_timed()?;
+    /// assert_eq!(result.recovery_iter().count(), 1);
+    /// println!("IFFT {:?}, FFT {:?}", timings.ifft, timings.fft);
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`encode`]: ReedSolomonEncoder::encode
+    pub fn encode_timed(&mut self) -> Result<(EncoderResult, PhaseTimings), Error> {
+        let mut timer = PhaseTimer::new();
+        let result = self.encode_with_checkpoints(|progress| timer.checkpoint(progress))?;
+        Ok((result, timer.finish()))
+    }
+
+    /// Like [`encode`] but also returns [stripe hash] of the added original shards,
+    /// to be compared with [`DecoderResult::integrity_hash`] after decoding.
+    ///
+    /// **This method is only available with `stripe-hash` feature.**
+    ///
+    /// See [`integrity::stripe_hash`] for an example.
+    ///
+    /// [`encode`]: ReedSolomonEncoder::encode
+    /// [stripe hash]: crate::integrity#stripe-hash
+    /// [`DecoderResult::integrity_hash`]: crate::DecoderResult::integrity_hash
+    #[cfg(feature = "stripe-hash")]
+    pub fn encode_with_stripe_hash(&mut self) -> Result<(EncoderResult<'_>, [u8; 32]), Error> {
+        // Content of original shards is only available before encoding.
+        let work = self.rate.work();
+        let hash = integrity::stripe_hash_from_digests(
+            (0..work.original_count())
+                .filter_map(|index| work.original(index))
+                .map(integrity::shard_digest),
+        );
+        Ok((self.encode()?, hash))
+    }
+
+    /// Returns `true` if `recovery` are exactly the recovery shards
+    /// which [`encode`] generates from `originals`.
+    ///
+    /// This is meant for scrubbing stored stripes:
+    /// nothing is restored, `originals` are just encoded again
+    /// and the result is compared to given `recovery`.
+    ///
+    /// - Original shards added before this call are forgotten.
+    /// - Errors with `originals` are same as with [`add_original_shard`]
+    ///   and [`encode`].
+    /// - Wrong number of recovery shards, or recovery shards of wrong size,
+    ///   result in `Ok(false)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonEncoder;
+    ///
+    /// let original = [[1u8; 64], [2u8; 64]];
+    /// let originals = [&original[0][..], &original[1][..]];
+    /// let mut recovery = reed_solomon_simd::encode(2, 1, &original)?;
+    ///
+    /// let mut encoder = ReedSolomonEncoder::new(2, 1, 64)?;
+    /// assert!(encoder.verify(&originals, &[&recovery[0]])?);
+    ///
+    /// recovery[0][10] ^= 1;
+    /// assert!(!encoder.verify(&originals, &[&recovery[0]])?);
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`encode`]: ReedSolomonEncoder::encode
+    /// [`add_original_shard`]: ReedSolomonEncoder::add_original_shard
+    pub fn verify(&mut self, originals: &[&[u8]], recovery: &[&[u8]]) -> Result<bool, Error> {
+        let result = self.encode_ref(originals)?;
+        Ok(result.recovery_iter().eq(recovery.iter().copied()))
+    }
+
+    /// Encodes given original shards in one go, returning [`EncoderResult`]
+    /// which contains the generated recovery shards.
+    ///
+    /// This is same as adding `originals` with [`add_original_shard`]
+    /// and then calling [`encode`], except that:
+    ///
+    /// - Original shards added before this call are forgotten,
+    ///   so calling this again with same `originals` gives same result.
+    /// - Number of `originals` is checked before any of them is added,
+    ///   returning [`Error::TooFewOriginalShards`]
+    ///   or [`Error::TooManyOriginalShards`] if it's not `original_count`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonEncoder;
+    ///
+    /// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+    ///
+    /// let mut encoder = ReedSolomonEncoder::new(3, 2, 64)?;
+    /// let result = encoder.encode_ref(&original)?;
+    /// assert_eq!(result.recovery_iter().count(), 2);
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`encode`]: ReedSolomonEncoder::encode
+    /// [`add_original_shard`]: ReedSolomonEncoder::add_original_shard
+    pub fn encode_ref<T: AsRef<[u8]>>(&mut self, originals: &[T]) -> Result<EncoderResult, Error> {
+        let original_count = self.rate.work().original_count();
+        if originals.len() < original_count {
+            return Err(Error::TooFewOriginalShards {
+                original_count,
+                original_received_count: originals.len(),
+            });
+        } else if originals.len() > original_count {
+            return Err(Error::TooManyOriginalShards { original_count });
+        }
+
+        self.rate.work_mut().reset_received();
+        for original in originals {
+            self.add_original_shard(original)?;
+        }
+        self.encode()
+    }
+
+    /// Encodes original shards of a flat `stripe` buffer in one go,
+    /// writing generated recovery shards into the same buffer.
+    ///
+    /// `stripe` is laid out as `[original_0, ..., original_N, recovery_0, ..., recovery_M]`,
+    /// i.e. shards of `shard_bytes` each back-to-back with original shards
+    /// at the front and recovery shards after them.
+    ///
+    /// - `stripe` must be exactly `(original_count + recovery_count) * shard_bytes`
+    ///   bytes, otherwise [`Error::InvalidOutputSize`] is returned.
+    /// - Original shards added before this call are forgotten.
+    /// - Original shards are only read and previous content
+    ///   of recovery shards is overwritten.
+    /// - Other errors are same as with [`add_original_shard`].
+    ///
+    /// Transforms work on a power-of-two number of shards,
+    /// so they can't be done within `stripe` itself. Instead original
+    /// shards are copied into working space once, like with
+    /// [`add_original_shard`], and recovery shards are copied back once,
+    /// with [`Engine::copy`], i.e. with non-temporal stores for large shards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonEncoder;
+    ///
+    /// let mut stripe = vec![0u8; 3 * 64];
+    /// stripe[..64].fill(1);
+    /// stripe[64..128].fill(2);
+    ///
+    /// let mut encoder = ReedSolomonEncoder::new(2, 1, 64)?;
+    /// encoder.encode_flat(&mut stripe)?;
+    ///
+    /// let recovery = reed_solomon_simd::encode(2, 1, [[1u8; 64], [2u8; 64]])?;
+    /// assert_eq!(stripe[128..], recovery[0]);
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`add_original_shard`]: ReedSolomonEncoder::add_original_shard
+    /// [`Engine::copy`]: crate::engine::Engine::copy
+    pub fn encode_flat(&mut self, stripe: &mut [u8]) -> Result<(), Error> {
+        let work = self.rate.work();
+        let original_bytes = work.original_count() * work.shard_bytes();
+        let expected = original_bytes + work.recovery_count() * work.shard_bytes();
+        if stripe.len() != expected {
+            return Err(Error::InvalidOutputSize {
+                expected,
+                got: stripe.len(),
+            });
+        }
+
+        let shard_bytes = work.shard_bytes();
+        let (originals, recovery) = stripe.split_at_mut(original_bytes);
+
+        self.rate.work_mut().reset_received();
+        for original in originals.chunks_exact(shard_bytes) {
+            self.add_original_shard(original)?;
+        }
+
+        let result = self.encode()?;
+        DefaultEngine::copy(recovery, result.recovery_flat());
+        Ok(())
     }
 
     /// Creates new encoder with given configuration
@@ -45,13 +293,63 @@ impl ReedSolomonEncoder {
         recovery_count: usize,
         shard_bytes: usize,
     ) -> Result<Self, Error> {
-        Ok(Self(DefaultRateEncoder::new(
-            original_count,
-            recovery_count,
-            shard_bytes,
-            DefaultEngine::new(),
-            None,
-        )?))
+        Ok(Self {
+            rate: DefaultRateEncoder::new(
+                original_count,
+                recovery_count,
+                shard_bytes,
+                DefaultEngine::new(),
+                None,
+            )?,
+            footer_stripe_id: None,
+            work: None,
+        })
+    }
+
+    /// Like [`new`] but allocates working space with given allocator.
+    ///
+    /// Requires nightly Rust and `allocator-api` feature.
+    ///
+    /// [`new`]: ReedSolomonEncoder::new
+    #[cfg(feature = "allocator-api")]
+    pub fn new_in<A: Allocator + Send + Sync + 'static>(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        alloc: A,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            rate: DefaultRateEncoder::new(
+                original_count,
+                recovery_count,
+                shard_bytes,
+                DefaultEngine::new(),
+                Some(EncoderWork::new_in(alloc)),
+            )?,
+            footer_stripe_id: None,
+            work: None,
+        })
+    }
+
+    /// Creates new encoder with configuration given as [`CodecParams`]
+    /// and allocates required working space.
+    ///
+    /// This is same as [`ReedSolomonEncoder::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::{CodecParams, ReedSolomonEncoder};
+    ///
+    /// let encoder = ReedSolomonEncoder::from_params(CodecParams::new(3, 5, 64))?;
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    pub fn from_params(params: CodecParams) -> Result<Self, Error> {
+        Self::new(
+            params.original_count,
+            params.recovery_count,
+            params.shard_bytes,
+        )
     }
 
     /// Resets encoder to given configuration.
@@ -59,13 +357,293 @@ impl ReedSolomonEncoder {
     /// - Added original shards are forgotten.
     /// - Existing working space is re-used if it's large enough
     ///   or re-allocated otherwise.
+    ///   With `zeroize` feature it's zeroed first,
+    ///   see [zeroizing working space](crate#zeroizing-working-space).
     pub fn reset(
         &mut self,
         original_count: usize,
         recovery_count: usize,
         shard_bytes: usize,
     ) -> Result<(), Error> {
-        self.0.reset(original_count, recovery_count, shard_bytes)
+        self.rate.reset(original_count, recovery_count, shard_bytes)
+    }
+
+    /// Resets encoder to given `shard_bytes`, keeping shard counts.
+    ///
+    /// This is same as [`reset`] with current counts. Rate and transforms
+    /// depend only on shard counts, so this only resizes working space,
+    /// which is re-used if it's large enough.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonEncoder;
+    ///
+    /// let mut encoder = ReedSolomonEncoder::new(2, 1, 2048)?;
+    ///
+    /// encoder.set_shard_bytes(512)?;
+    /// encoder.add_original_shard([1u8; 512])?;
+    /// encoder.add_original_shard([2u8; 512])?;
+    /// assert_eq!(encoder.encode()?.recovery(0).unwrap().len(), 512);
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`reset`]: ReedSolomonEncoder::reset
+    pub fn set_shard_bytes(&mut self, shard_bytes: usize) -> Result<(), Error> {
+        let work = self.rate.work();
+        let (original_count, recovery_count) = (work.original_count(), work.recovery_count());
+        self.rate.reset(original_count, recovery_count, shard_bytes)
+    }
+
+    /// Creates new encoder with working space for every configuration
+    /// with at most given counts and shard size, so that [`reset`]
+    /// to such configuration doesn't allocate.
+    ///
+    /// - Encoder is initially configured with the maximums,
+    ///   which must be a supported configuration.
+    /// - Working space is `max_shard_bytes` times the smaller of
+    ///   `max_original_count` rounded up to a multiple of
+    ///   `max_recovery_count.next_power_of_two()` and
+    ///   `max_recovery_count` rounded up to a multiple of
+    ///   `max_original_count.next_power_of_two()`.
+    ///   This is held until [`shrink_to_fit`], see [`allocated_bytes`].
+    /// - With [`with_deduplication`] adding original shards
+    ///   may still allocate.
+    ///
+    /// This is same as [`new`] with the maximums, as the rate chosen
+    /// by [`DefaultRate`] never needs more working space for smaller
+    /// counts, but unlike [`new`] this guarantee is part of the API.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonEncoder;
+    ///
+    /// let mut encoder = ReedSolomonEncoder::with_capacity(1000, 100, 1024)?;
+    /// let allocated = encoder.allocated_bytes();
+    ///
+    /// encoder.reset(10, 100, 64)?;
+    /// encoder.reset(1000, 1, 1024)?;
+    /// assert_eq!(encoder.allocated_bytes(), allocated);
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`reset`]: ReedSolomonEncoder::reset
+    /// [`new`]: ReedSolomonEncoder::new
+    /// [`shrink_to_fit`]: ReedSolomonEncoder::shrink_to_fit
+    /// [`allocated_bytes`]: ReedSolomonEncoder::allocated_bytes
+    /// [`with_deduplication`]: ReedSolomonEncoder::with_deduplication
+    pub fn with_capacity(
+        max_original_count: usize,
+        max_recovery_count: usize,
+        max_shard_bytes: usize,
+    ) -> Result<Self, Error> {
+        // Work count of both rates is monotonic in both counts
+        // and `DefaultRate` chooses the rate with smaller work count.
+        Self::new(max_original_count, max_recovery_count, max_shard_bytes)
+    }
+
+    /// Creates new encoder with given configuration, re-using
+    /// working space taken earlier with [`into_recycled`].
+    ///
+    /// This is same as [`new`] except that `work` is used instead of
+    /// allocating, so working space isn't re-allocated if it's large enough,
+    /// e.g. when it comes from an encoder created with [`with_capacity`]
+    /// with at least given counts and shard size.
+    /// Deduplication is disabled like with [`new`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonEncoder;
+    ///
+    /// let encoder = ReedSolomonEncoder::with_capacity(1000, 100, 1024)?;
+    /// let allocated = encoder.allocated_bytes();
+    ///
+    /// let work = encoder.into_recycled();
+    /// let encoder = ReedSolomonEncoder::from_recycled(500, 50, 1024, work)?;
+    /// assert_eq!(encoder.allocated_bytes(), allocated);
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`into_recycled`]: ReedSolomonEncoder::into_recycled
+    /// [`new`]: ReedSolomonEncoder::new
+    /// [`with_capacity`]: ReedSolomonEncoder::with_capacity
+    pub fn from_recycled(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        mut work: EncoderWork,
+    ) -> Result<Self, Error> {
+        work.set_deduplication(false);
+        Ok(Self {
+            rate: DefaultRateEncoder::new(
+                original_count,
+                recovery_count,
+                shard_bytes,
+                DefaultEngine::new(),
+                Some(work),
+            )?,
+            footer_stripe_id: None,
+            work: None,
+        })
+    }
+
+    /// Enables integrity footer with given `stripe_id`.
+    ///
+    /// Last 64 bytes of each original shard are then reserved and
+    /// [`encode`] embeds [`IntegrityFooter`] into the last 24 bytes
+    /// of each recovery shard. See [`integrity`] module for details.
+    ///
+    /// [`encode`]: ReedSolomonEncoder::encode
+    /// [`integrity`]: crate::integrity
+    pub fn with_integrity_footer(mut self, stripe_id: u64) -> Self {
+        self.footer_stripe_id = Some(stripe_id);
+        self
+    }
+
+    /// Enables integrity footer with given `stripe_id`
+    /// or disables it with `None`.
+    ///
+    /// This is same as [`with_integrity_footer`] but e.g. allows
+    /// changing `stripe_id` between stripes without creating new encoder.
+    ///
+    /// [`with_integrity_footer`]: ReedSolomonEncoder::with_integrity_footer
+    pub fn set_integrity_footer(&mut self, stripe_id: Option<u64>) {
+        self.footer_stripe_id = stripe_id;
+    }
+
+    /// Enables detection of duplicate original shards.
+    ///
+    /// Original shards identical to an earlier original shard
+    /// are then reported by [`EncoderResult::duplicate_originals`],
+    /// e.g. to see how much redundancy the data has.
+    /// Recovery shards are not affected.
+    ///
+    /// See [`EncoderWork::set_deduplication`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonEncoder;
+    ///
+    /// let mut encoder = ReedSolomonEncoder::new(3, 2, 64)?.with_deduplication();
+    /// encoder.add_original_shard([0u8; 64])?;
+    /// encoder.add_original_shard([1u8; 64])?;
+    /// encoder.add_original_shard([0u8; 64])?;
+    ///
+    /// let result = encoder.encode()?;
+    /// assert_eq!(result.duplicate_originals(), [(2, 0)]);
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    pub fn with_deduplication(mut self) -> Self {
+        self.set_deduplication(true);
+        self
+    }
+
+    /// Enables or disables detection of duplicate original shards,
+    /// see [`with_deduplication`].
+    ///
+    /// [`with_deduplication`]: ReedSolomonEncoder::with_deduplication
+    pub fn set_deduplication(&mut self, enabled: bool) {
+        self.rate.work_mut().set_deduplication(enabled);
+    }
+
+    /// Returns number of bytes currently allocated for working space.
+    ///
+    /// See [`shrink_to_fit`](Self::shrink_to_fit).
+    pub fn allocated_bytes(&self) -> usize {
+        self.rate.work().allocated_bytes()
+    }
+
+    /// Releases working space which current configuration doesn't need.
+    ///
+    /// Working space is re-used by [`reset`](Self::reset) and never shrunk
+    /// automatically, so after handling a large configuration it stays allocated.
+    /// For long-lived encoders which now handle smaller configurations
+    /// this lowers memory usage, at the cost of re-allocation
+    /// if a larger configuration is used again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonEncoder;
+    ///
+    /// let mut encoder = ReedSolomonEncoder::new(1000, 1000, 1024)?;
+    /// encoder.reset(2, 2, 64)?;
+    ///
+    /// let before = encoder.allocated_bytes();
+    /// encoder.shrink_to_fit();
+    /// assert!(encoder.allocated_bytes() < before);
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.rate.work_mut().shrink_to_fit();
+    }
+
+    /// Takes working space containing the added original shards,
+    /// e.g. to encode them in another thread with [`rate::encode_work`].
+    ///
+    /// The encoder continues with working space given back earlier with
+    /// [`restore_work`], or newly allocated if there is none, with same
+    /// configuration and deduplication setting, so original shards of the
+    /// next stripe can be added while the previous one is being encoded.
+    /// Alternating between two working spaces this way doesn't allocate
+    /// after the first round.
+    ///
+    /// If integrity footer is enabled, the taken working space
+    /// remembers current `stripe_id` so that [`rate::encode_work`]
+    /// writes the footer like [`encode`](Self::encode) does.
+    ///
+    /// See [`rate::encode_work`] for an example.
+    ///
+    /// [`rate::encode_work`]: crate::rate::encode_work
+    /// [`restore_work`]: ReedSolomonEncoder::restore_work
+    pub fn take_work(&mut self) -> Result<EncoderWork, Error> {
+        let work = self.rate.work();
+        let (original_count, recovery_count, shard_bytes) = (
+            work.original_count(),
+            work.recovery_count(),
+            work.shard_bytes(),
+        );
+        let dedup = work.deduplication();
+
+        let mut taken = self.work.take().unwrap_or_default();
+        std::mem::swap(self.rate.work_mut(), &mut taken);
+        if let Err(err) = self.rate.reset(original_count, recovery_count, shard_bytes) {
+            std::mem::swap(self.rate.work_mut(), &mut taken);
+            return Err(err);
+        }
+        self.set_deduplication(dedup);
+        taken.set_footer_stripe_id(self.footer_stripe_id);
+
+        Ok(taken)
+    }
+
+    /// Gives working space back to be re-used by next [`take_work`].
+    ///
+    /// Working space given earlier and not yet re-used is dropped.
+    /// Any original shards in `work` are forgotten.
+    ///
+    /// [`take_work`]: ReedSolomonEncoder::take_work
+    pub fn restore_work(&mut self, work: EncoderWork) {
+        self.work = Some(work);
+    }
+
+    /// Consumes the encoder returning its working space,
+    /// to be re-used by [`from_recycled`] instead of being freed.
+    ///
+    /// Added original shards are forgotten. Working space given with
+    /// [`restore_work`] and not yet re-used is dropped, so e.g. take it
+    /// back first with [`take_work`] to recycle it too.
+    ///
+    /// See [`from_recycled`] for an example.
+    ///
+    /// [`from_recycled`]: ReedSolomonEncoder::from_recycled
+    /// [`restore_work`]: ReedSolomonEncoder::restore_work
+    /// [`take_work`]: ReedSolomonEncoder::take_work
+    pub fn into_recycled(self) -> EncoderWork {
+        self.rate.into_parts().1
     }
 
     /// Returns `true` if given `original_count` / `recovery_count`
@@ -82,6 +660,120 @@ impl ReedSolomonEncoder {
     pub fn supports(original_count: usize, recovery_count: usize) -> bool {
         DefaultRate::<DefaultEngine>::supports(original_count, recovery_count)
     }
+
+    /// Returns the largest supported `original_count`
+    /// with given `recovery_count`, or `0` if there is none.
+    ///
+    /// Every `original_count` in `1 ..= max_original_count(recovery_count)`
+    /// is supported, see [`supports`]. With `r = recovery_count`
+    /// and `r_pow2 = r.next_power_of_two()` this is
+    ///
+    /// - `65536 - r_pow2` if `r_pow2 + r <= 65536`
+    /// - otherwise largest power of two which is at most `65536 - r`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonEncoder;
+    ///
+    /// assert_eq!(ReedSolomonEncoder::max_original_count(4_000), 61_440);
+    /// assert_eq!(ReedSolomonEncoder::max_original_count(40_000), 16_384);
+    /// ```
+    ///
+    /// [`supports`]: ReedSolomonEncoder::supports
+    pub fn max_original_count(recovery_count: usize) -> usize {
+        rate::max_shard_count(recovery_count)
+    }
+
+    /// Returns the largest supported `recovery_count`
+    /// with given `original_count`, or `0` if there is none.
+    ///
+    /// This is same as [`max_original_count`] with roles swapped.
+    ///
+    /// [`max_original_count`]: ReedSolomonEncoder::max_original_count
+    pub fn max_recovery_count(original_count: usize) -> usize {
+        rate::max_shard_count(original_count)
+    }
+}
+
+// ======================================================================
+// ReedSolomonEncoder - PRIVATE
+
+#[cfg(feature = "encoder")]
+impl ReedSolomonEncoder {
+    fn encode_with_checkpoints<F: FnMut(f32)>(
+        &mut self,
+        checkpoint: F,
+    ) -> Result<EncoderResult, Error> {
+        let footer = self.integrity_footer();
+        let mut result = self.rate.encode_with_checkpoints(checkpoint)?;
+
+        if let Some(footer) = footer {
+            integrity::write_footer(footer, &mut result);
+        }
+
+        Ok(result)
+    }
+
+    fn integrity_footer(&self) -> Option<IntegrityFooter> {
+        let work = self.rate.work();
+        let params = CodecParams::new(
+            work.original_count(),
+            work.recovery_count(),
+            work.shard_bytes(),
+        );
+        self.footer_stripe_id
+            .map(|stripe_id| IntegrityFooter::new(params, stripe_id))
+    }
+}
+
+// ======================================================================
+// ReedSolomonEncoder - IMPL RateEncoder
+
+#[cfg(feature = "encoder")]
+impl RateEncoder<DefaultEngine> for ReedSolomonEncoder {
+    type Rate = DefaultRate<DefaultEngine>;
+
+    fn add_original_shard<T: AsRef<[u8]>>(&mut self, original_shard: T) -> Result<(), Error> {
+        ReedSolomonEncoder::add_original_shard(self, original_shard)
+    }
+
+    fn encode(&mut self) -> Result<EncoderResult, Error> {
+        ReedSolomonEncoder::encode(self)
+    }
+
+    fn into_parts(self) -> (DefaultEngine, EncoderWork) {
+        self.rate.into_parts()
+    }
+
+    fn new(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        engine: DefaultEngine,
+        work: Option<EncoderWork>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            rate: DefaultRateEncoder::new(
+                original_count,
+                recovery_count,
+                shard_bytes,
+                engine,
+                work,
+            )?,
+            footer_stripe_id: None,
+            work: None,
+        })
+    }
+
+    fn reset(
+        &mut self,
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<(), Error> {
+        self.rate.reset(original_count, recovery_count, shard_bytes)
+    }
 }
 
 // ======================================================================
@@ -89,14 +781,46 @@ impl ReedSolomonEncoder {
 
 /// Reed-Solomon decoder using [`DefaultEngine`] and [`DefaultRate`].
 ///
+/// # Working space
+///
+/// Decoder owns its working space, see [`allocated_bytes`],
+/// as well as any added owned or untrusted shards,
+/// while the engine only refers to `'static` tables.
+/// Dropping the decoder frees all of these, which with large
+/// configurations may stall latency-sensitive threads for a while
+/// (and with `zeroize` feature includes zeroing working space first).
+/// [`into_recycled`] takes the working space instead, so that it can be
+/// re-used by [`from_recycled`] or dropped elsewhere at a better time.
+///
 /// [`DefaultEngine`]: crate::engine::DefaultEngine
-pub struct ReedSolomonDecoder(DefaultRateDecoder<DefaultEngine>);
+/// [`allocated_bytes`]: ReedSolomonDecoder::allocated_bytes
+/// [`into_recycled`]: ReedSolomonDecoder::into_recycled
+/// [`from_recycled`]: ReedSolomonDecoder::from_recycled
+#[cfg(feature = "decoder")]
+pub struct ReedSolomonDecoder {
+    rate: DefaultRateDecoder<DefaultEngine>,
+    // Untrusted shards, see `add_untrusted_shard`.
+    untrusted: BTreeMap<ShardId, Vec<u8>>,
+    // Stripe id if integrity footer is enabled, see `with_integrity_footer`.
+    footer_stripe_id: Option<u64>,
+    // Owned shards not yet copied to working space,
+    // see `add_original_shard_owned`.
+    owned: BTreeMap<ShardId, Vec<u8>>,
+    // Whether stripe hash is computed, see `with_stripe_hash`.
+    #[cfg_attr(not(feature = "stripe-hash"), allow(dead_code))]
+    stripe_hash: bool,
+}
 
+#[cfg(feature = "decoder")]
 impl ReedSolomonDecoder {
     /// Adds one original shard to the decoder.
     ///
     /// - Shards can be added in any order.
     /// - Index must be the same that was used in encoding.
+    /// - Index out of range is rejected with [`Error::InvalidOriginalShardIndex`]
+    ///   before anything is written to the decoder.
+    ///   This includes indexes `original_count ..` which the transform
+    ///   pads with zero shards when `original_count` isn't a power of two.
     ///
     /// See [basic usage](crate#basic-usage) for an example.
     pub fn add_original_shard<T: AsRef<[u8]>>(
@@ -104,13 +828,23 @@ impl ReedSolomonDecoder {
         index: usize,
         original_shard: T,
     ) -> Result<(), Error> {
-        self.0.add_original_shard(index, original_shard)
+        self.rate.add_original_shard(index, original_shard)
     }
 
     /// Adds one recovery shard to the decoder.
     ///
     /// - Shards can be added in any order.
     /// - Index must be the same that was used in encoding.
+    /// - Index out of range is rejected with [`Error::InvalidRecoveryShardIndex`]
+    ///   before anything is written to the decoder.
+    ///   As with original shards this includes padded indexes.
+    /// - If integrity footer is enabled, footer of the shard is validated
+    ///   and [`Error::ParameterMismatch`] is returned if it doesn't match.
+    /// - Once the decoder has `original_count` shards, i.e. enough for decoding,
+    ///   further recovery shards are validated but not copied to the decoder,
+    ///   which makes adding surplus shards cheap e.g. when shards are
+    ///   received with redundancy. Such shards are still rejected
+    ///   with [`Error::DuplicateRecoveryShardIndex`] if added again.
     ///
     /// See [basic usage](crate#basic-usage) for an example.
     pub fn add_recovery_shard<T: AsRef<[u8]>>(
@@ -118,7 +852,571 @@ impl ReedSolomonDecoder {
         index: usize,
         recovery_shard: T,
     ) -> Result<(), Error> {
-        self.0.add_recovery_shard(index, recovery_shard)
+        let recovery_shard = recovery_shard.as_ref();
+
+        let expected = match self.integrity_footer() {
+            Some(expected) => expected,
+            None => return self.rate.add_recovery_shard(index, recovery_shard),
+        };
+
+        let work = self.rate.work_mut();
+        let pos = work.begin_recovery_shard(index)?;
+        integrity::check_footer(expected, index, recovery_shard)?;
+        if recovery_shard.len() != expected.params.shard_bytes {
+            return Err(Error::DifferentShardSize {
+                shard_bytes: expected.params.shard_bytes,
+                got: recovery_shard.len(),
+            });
+        } else if work.has_enough_shards() {
+            work.skip_recovery_shard(index);
+            return Ok(());
+        }
+
+        let (content, footer) = work
+            .shard_mut(pos)
+            .split_at_mut(recovery_shard.len() - FOOTER_BYTES);
+        content.copy_from_slice(strip_footer(recovery_shard));
+        footer.fill(0);
+        work.finish_shard(pos);
+        Ok(())
+    }
+
+    /// Adds one original or recovery shard to the decoder.
+    ///
+    /// This is same as [`add_original_shard`] or [`add_recovery_shard`]
+    /// depending on `kind`, which is convenient when shards come from
+    /// a collection tagged with their kind.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::{ReedSolomonDecoder, ShardKind};
+    ///
+    /// let original = [[1u8; 64], [2u8; 64]];
+    /// let recovery = reed_solomon_simd::encode(2, 1, original)?;
+    ///
+    /// let shards = [
+    ///     (ShardKind::Recovery, 0, recovery[0].as_slice()),
+    ///     (ShardKind::Original, 1, original[1].as_slice()),
+    /// ];
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(2, 1, 64)?;
+    /// for (kind, index, shard) in shards {
+    ///     decoder.add_shard(kind, index, shard)?;
+    /// }
+    ///
+    /// let result = decoder.decode()?;
+    /// assert_eq!(result.restored_original(0).unwrap(), original[0]);
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`add_original_shard`]: ReedSolomonDecoder::add_original_shard
+    /// [`add_recovery_shard`]: ReedSolomonDecoder::add_recovery_shard
+    pub fn add_shard<T: AsRef<[u8]>>(
+        &mut self,
+        kind: ShardKind,
+        index: usize,
+        shard: T,
+    ) -> Result<(), Error> {
+        match kind {
+            ShardKind::Original => self.add_original_shard(index, shard),
+            ShardKind::Recovery => self.add_recovery_shard(index, shard),
+        }
+    }
+
+    /// Adds one trusted original or recovery shard to the decoder.
+    ///
+    /// This is same as [`add_shard`]. All shards added with other
+    /// methods than [`add_untrusted_shard`] are trusted.
+    ///
+    /// [`add_shard`]: ReedSolomonDecoder::add_shard
+    /// [`add_untrusted_shard`]: ReedSolomonDecoder::add_untrusted_shard
+    pub fn add_trusted_shard<T: AsRef<[u8]>>(
+        &mut self,
+        kind: ShardKind,
+        index: usize,
+        shard: T,
+    ) -> Result<(), Error> {
+        self.add_shard(kind, index, shard)
+    }
+
+    /// Adds one untrusted original or recovery shard to the decoder.
+    ///
+    /// Untrusted shards are copied aside and handled by [`decode`]:
+    ///
+    /// - If trusted shards alone are not enough for decoding,
+    ///   as few untrusted shards as needed are used for decoding,
+    ///   original shards first and then recovery shards,
+    ///   both in increasing order of index.
+    /// - Rest of the untrusted shards are used only for cross-checking
+    ///   and those which don't match are listed by
+    ///   [`DecoderResult::untrusted_mismatches`]. Untrusted shard is
+    ///   cross-checked if same shard has been added as trusted
+    ///   or if it's an original shard which is restored.
+    ///   Other untrusted recovery shards are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::{repair::ShardId, ReedSolomonDecoder, ShardKind};
+    ///
+    /// let original = [[1u8; 64], [2u8; 64]];
+    /// let recovery = reed_solomon_simd::encode(2, 2, original)?;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(2, 2, 64)?;
+    /// decoder.add_trusted_shard(ShardKind::Original, 1, original[1])?;
+    /// decoder.add_trusted_shard(ShardKind::Recovery, 1, &recovery[1])?;
+    /// // Corrupted copy of original shard #0.
+    /// decoder.add_untrusted_shard(ShardKind::Original, 0, [0u8; 64])?;
+    ///
+    /// let result = decoder.decode()?;
+    /// assert_eq!(result.restored_original(0).unwrap(), original[0]);
+    /// assert_eq!(result.untrusted_mismatches(), [ShardId::Original(0)]);
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`decode`]: ReedSolomonDecoder::decode
+    pub fn add_untrusted_shard<T: AsRef<[u8]>>(
+        &mut self,
+        kind: ShardKind,
+        index: usize,
+        shard: T,
+    ) -> Result<(), Error> {
+        let work = self.rate.work();
+        let shard = shard.as_ref();
+
+        let id = match kind {
+            ShardKind::Original => {
+                if index >= work.original_count() {
+                    return Err(Error::InvalidOriginalShardIndex {
+                        original_count: work.original_count(),
+                        index,
+                    });
+                } else if self.untrusted.contains_key(&ShardId::Original(index)) {
+                    return Err(Error::DuplicateOriginalShardIndex { index });
+                }
+                ShardId::Original(index)
+            }
+
+            ShardKind::Recovery => {
+                if index >= work.recovery_count() {
+                    return Err(Error::InvalidRecoveryShardIndex {
+                        recovery_count: work.recovery_count(),
+                        index,
+                    });
+                } else if self.untrusted.contains_key(&ShardId::Recovery(index)) {
+                    return Err(Error::DuplicateRecoveryShardIndex { index });
+                }
+                ShardId::Recovery(index)
+            }
+        };
+
+        let mut shard = shard.to_vec();
+        if let (ShardId::Recovery(index), Some(expected)) = (id, self.integrity_footer()) {
+            integrity::check_footer(expected, index, &shard)?;
+            let start = shard.len() - FOOTER_BYTES;
+            shard[start..].fill(0);
+        }
+
+        if shard.len() != work.shard_bytes() {
+            return Err(Error::DifferentShardSize {
+                shard_bytes: work.shard_bytes(),
+                got: shard.len(),
+            });
+        }
+
+        self.untrusted.insert(id, shard);
+        Ok(())
+    }
+
+    /// Adds one original shard to the decoder, taking ownership of it.
+    ///
+    /// Owned shards are not copied when added. Instead [`decode`] copies
+    /// as few of them as needed into working space, original shards first
+    /// and then recovery shards, both in increasing order of index.
+    /// All owned shards are then given back by [`DecoderResult`]:
+    ///
+    /// - [`DecoderResult::take_used_shards`] returns the ones used
+    ///   for decoding, which are no longer needed as they were copied.
+    /// - [`DecoderResult::take_unused_shards`] returns the rest.
+    ///
+    /// If there are not enough shards for decoding, [`decode`] returns
+    /// [`Error::NotEnoughShards`] and owned shards are kept for next attempt.
+    /// If [`decode`] fails otherwise, owned shards are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::{repair::ShardId, ReedSolomonDecoder};
+    ///
+    /// let original = [[1u8; 64], [2u8; 64]];
+    /// let recovery = reed_solomon_simd::encode(2, 2, original)?;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(2, 2, 64)?;
+    /// decoder.add_original_shard(1, original[1])?;
+    /// decoder.add_recovery_shard_owned(0, recovery[0].clone())?;
+    /// decoder.add_recovery_shard_owned(1, recovery[1].clone())?;
+    ///
+    /// let mut result = decoder.decode()?;
+    /// assert_eq!(result.restored_original(0).unwrap(), original[0]);
+    /// assert_eq!(result.take_used_shards(), [(ShardId::Recovery(0), recovery[0].clone())]);
+    /// assert_eq!(result.take_unused_shards(), [(ShardId::Recovery(1), recovery[1].clone())]);
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`decode`]: ReedSolomonDecoder::decode
+    pub fn add_original_shard_owned(
+        &mut self,
+        index: usize,
+        original_shard: Vec<u8>,
+    ) -> Result<(), Error> {
+        self.add_owned_shard(ShardId::Original(index), original_shard)
+    }
+
+    /// Adds one recovery shard to the decoder, taking ownership of it.
+    ///
+    /// See [`add_original_shard_owned`] for details.
+    ///
+    /// [`add_original_shard_owned`]: ReedSolomonDecoder::add_original_shard_owned
+    pub fn add_recovery_shard_owned(
+        &mut self,
+        index: usize,
+        recovery_shard: Vec<u8>,
+    ) -> Result<(), Error> {
+        self.add_owned_shard(ShardId::Recovery(index), recovery_shard)
+    }
+
+    /// Begins adding one original shard to the decoder,
+    /// returning [`ShardWriter`] which streams the shard content
+    /// directly into the working space of the decoder.
+    ///
+    /// - Shards can be added in any order.
+    /// - Index must be the same that was used in encoding.
+    /// - Shard is added only when [`ShardWriter::finish`] is called.
+    pub fn begin_original_shard(&mut self, index: usize) -> Result<ShardWriter, Error> {
+        self.rate.begin_original_shard(index)
+    }
+
+    /// Begins adding one recovery shard to the decoder,
+    /// returning [`ShardWriter`] which streams the shard content
+    /// directly into the working space of the decoder.
+    ///
+    /// - Shards can be added in any order.
+    /// - Index must be the same that was used in encoding.
+    /// - Shard is added only when [`ShardWriter::finish`] is called.
+    /// - If integrity footer is enabled, whole shard including the footer
+    ///   is written and [`ShardWriter::finish`] validates the footer
+    ///   like [`add_recovery_shard`] does.
+    ///
+    /// See [`ShardWriter`] for an example.
+    ///
+    /// [`add_recovery_shard`]: ReedSolomonDecoder::add_recovery_shard
+    pub fn begin_recovery_shard(&mut self, index: usize) -> Result<ShardWriter, Error> {
+        let expected = self.integrity_footer();
+        let writer = self.rate.begin_recovery_shard(index)?;
+        Ok(match expected {
+            Some(expected) => writer.with_footer(expected, index),
+            None => writer,
+        })
+    }
+
+    /// Returns `true` if enough shards have been added for [`decode`],
+    /// i.e. number of missing original shards is at most
+    /// number of added recovery shards.
+    ///
+    /// - Untrusted and owned shards which can be used for decoding
+    ///   are counted, see [`add_untrusted_shard`]
+    ///   and [`add_original_shard_owned`].
+    /// - This is cheap, only counts of added shards are compared.
+    ///   [`decode`] does the same check before any transforms and returns
+    ///   [`Error::NotEnoughShards`] if this returns `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+    /// let recovery = reed_solomon_simd::encode(3, 2, original)?;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(3, 2, 64)?;
+    /// decoder.add_original_shard(2, original[2])?;
+    /// decoder.add_recovery_shard(0, &recovery[0])?;
+    /// assert!(!decoder.can_recover());
+    ///
+    /// decoder.add_recovery_shard(1, &recovery[1])?;
+    /// assert!(decoder.can_recover());
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`add_original_shard_owned`]: ReedSolomonDecoder::add_original_shard_owned
+    /// [`add_untrusted_shard`]: ReedSolomonDecoder::add_untrusted_shard
+    /// [`decode`]: ReedSolomonDecoder::decode
+    pub fn can_recover(&self) -> bool {
+        let work = self.rate.work();
+        let owned = self
+            .owned
+            .keys()
+            .filter(|id| work.received_shard(**id).is_none())
+            .count();
+        let untrusted = self
+            .untrusted
+            .keys()
+            .filter(|id| work.received_shard(**id).is_none() && !self.owned.contains_key(id))
+            .count();
+        work.received_count() + owned + untrusted >= work.original_count()
+    }
+
+    /// Decodes given shards in one go, returning [`DecoderResult`]
+    /// which contains the restored original shards.
+    ///
+    /// This is same as adding `originals` with [`add_original_shard`]
+    /// and `recovery` with [`add_recovery_shard`] and then calling [`decode`],
+    /// except that shards added before this call are forgotten,
+    /// so calling this again with same shards gives same result.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+    /// let recovery = reed_solomon_simd::encode(3, 2, original)?;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(3, 2, 64)?;
+    /// let result = decoder.decode_ref(
+    ///     &[(1, original[1])],
+    ///     &[(0, &recovery[0]), (1, &recovery[1])],
+    /// )?;
+    /// assert_eq!(result.restored_original(0).unwrap(), original[0]);
+    /// assert_eq!(result.restored_original(2).unwrap(), original[2]);
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`add_original_shard`]: ReedSolomonDecoder::add_original_shard
+    /// [`add_recovery_shard`]: ReedSolomonDecoder::add_recovery_shard
+    /// [`decode`]: ReedSolomonDecoder::decode
+    pub fn decode_ref<T: AsRef<[u8]>, U: AsRef<[u8]>>(
+        &mut self,
+        originals: &[(usize, T)],
+        recovery: &[(usize, U)],
+    ) -> Result<DecoderResult, Error> {
+        self.untrusted.clear();
+        self.owned.clear();
+        self.rate.work_mut().reset_received();
+
+        for (index, original) in originals {
+            self.add_original_shard(*index, original)?;
+        }
+        for (index, recovery) in recovery {
+            self.add_recovery_shard(*index, recovery)?;
+        }
+        self.decode()
+    }
+
+    /// Decodes shards of a flat `stripe` buffer in one go,
+    /// writing restored original shards into the same buffer.
+    ///
+    /// `stripe` is laid out like with `ReedSolomonEncoder::encode_flat`,
+    /// i.e. as `[original_0, ..., original_N, recovery_0, ..., recovery_M]`.
+    /// `present[i]` tells whether shard `i` of this layout has been received,
+    /// so `present[original_count + index]` is for recovery shard `index`.
+    ///
+    /// - `stripe` must be exactly `(original_count + recovery_count) * shard_bytes`
+    ///   bytes, otherwise [`Error::InvalidOutputSize`] is returned.
+    /// - `present` must be exactly `original_count + recovery_count` long,
+    ///   otherwise [`Error::InvalidPresenceLength`] is returned.
+    /// - Shards added before this call are forgotten.
+    /// - Only missing original shards are written, i.e. on success
+    ///   all original shards in `stripe` are valid.
+    ///   Missing recovery shards are left as they are.
+    /// - On error nothing is written.
+    /// - Other errors are same as with [`decode_ref`].
+    ///
+    /// Like with `ReedSolomonEncoder::encode_flat`, received shards are
+    /// copied into working space once and restored shards are copied back once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::{ReedSolomonDecoder, ReedSolomonEncoder};
+    ///
+    /// let mut stripe = vec![0u8; 3 * 64];
+    /// stripe[..64].fill(1);
+    /// stripe[64..128].fill(2);
+    /// ReedSolomonEncoder::new(2, 1, 64)?.encode_flat(&mut stripe)?;
+    ///
+    /// // Original shard 0 is lost.
+    /// stripe[..64].fill(0);
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(2, 1, 64)?;
+    /// decoder.decode_flat(&mut stripe, &[false, true, true])?;
+    /// assert_eq!(stripe[..64], [1u8; 64]);
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`decode_ref`]: ReedSolomonDecoder::decode_ref
+    pub fn decode_flat(&mut self, stripe: &mut [u8], present: &[bool]) -> Result<(), Error> {
+        let work = self.rate.work();
+        let original_count = work.original_count();
+        let shard_count = original_count + work.recovery_count();
+        let shard_bytes = work.shard_bytes();
+
+        if stripe.len() != shard_count * shard_bytes {
+            return Err(Error::InvalidOutputSize {
+                expected: shard_count * shard_bytes,
+                got: stripe.len(),
+            });
+        } else if present.len() != shard_count {
+            return Err(Error::InvalidPresenceLength {
+                expected: shard_count,
+                got: present.len(),
+            });
+        }
+
+        self.untrusted.clear();
+        self.owned.clear();
+        self.rate.work_mut().reset_received();
+
+        for (index, shard) in stripe.chunks_exact(shard_bytes).enumerate() {
+            if !present[index] {
+                continue;
+            } else if index < original_count {
+                self.add_original_shard(index, shard)?;
+            } else {
+                self.add_recovery_shard(index - original_count, shard)?;
+            }
+        }
+
+        let result = self.decode()?;
+        for (index, restored) in result.restored_original_iter() {
+            DefaultEngine::copy(
+                &mut stripe[index * shard_bytes..(index + 1) * shard_bytes],
+                restored,
+            );
+        }
+        Ok(())
+    }
+
+    /// Decodes the added shards and writes restored original shards
+    /// into given possibly uninitialized buffers.
+    ///
+    /// This avoids zero-initializing output buffers, e.g. ones allocated
+    /// with [`Box::new_uninit_slice`], before the restored shards are copied
+    /// into them, which is significant when restoring large amounts of data.
+    ///
+    /// - `outputs` are `(index, buffer)` pairs where `index` is the index of
+    ///   a missing original shard to restore into `buffer`,
+    ///   which must be exactly `shard_bytes` long.
+    /// - On success every byte of every buffer in `outputs` has been written,
+    ///   so they can be assumed initialized, e.g. with
+    ///   [`MaybeUninit::assume_init`] on a `Box<[MaybeUninit<u8>]>`.
+    /// - On error nothing is written, so buffers which
+    ///   were uninitialized remain uninitialized.
+    ///
+    /// The decoder is then reset like after dropping [`DecoderResult`]
+    /// returned by [`decode`], except on errors detected before decoding:
+    ///
+    /// - [`Error::InvalidOutputSize`] if a buffer isn't `shard_bytes` long.
+    /// - [`Error::InvalidOriginalShardIndex`] if an `index` is invalid.
+    /// - [`Error::DuplicateOriginalShardIndex`] if an `index`
+    ///   is given twice or the shard itself has been added.
+    /// - [`Error::NotEnoughShards`].
+    ///
+    /// Owned and untrusted shards are handled like in [`decode`],
+    /// but aren't returned. [`Error::DuplicateOriginalShardIndex`] is also
+    /// returned after decoding if an untrusted shard was needed
+    /// in place of a missing original shard given in `outputs`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+    /// let recovery = reed_solomon_simd::encode(3, 2, original)?;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(3, 2, 64)?;
+    /// decoder.add_original_shard(1, original[1])?;
+    /// decoder.add_recovery_shard(0, &recovery[0])?;
+    /// decoder.add_recovery_shard(1, &recovery[1])?;
+    ///
+    /// let mut restored_0 = Box::new_uninit_slice(64);
+    /// let mut restored_2 = Box::new_uninit_slice(64);
+    /// decoder.decode_into_uninit(&mut [(0, &mut restored_0), (2, &mut restored_2)])?;
+    ///
+    /// // SAFETY: All bytes have been written by `decode_into_uninit`.
+    /// let (restored_0, restored_2) = unsafe { (restored_0.assume_init(), restored_2.assume_init()) };
+    /// assert_eq!(*restored_0, original[0]);
+    /// assert_eq!(*restored_2, original[2]);
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`decode`]: ReedSolomonDecoder::decode
+    pub fn decode_into_uninit(
+        &mut self,
+        outputs: &mut [(usize, &mut [MaybeUninit<u8>])],
+    ) -> Result<(), Error> {
+        let work = self.rate.work();
+        let original_count = work.original_count();
+        let shard_bytes = work.shard_bytes();
+
+        let mut indexes = BTreeSet::new();
+        for (index, output) in outputs.iter() {
+            let index = *index;
+            if output.len() != shard_bytes {
+                return Err(Error::InvalidOutputSize {
+                    expected: shard_bytes,
+                    got: output.len(),
+                });
+            } else if index >= original_count {
+                return Err(Error::InvalidOriginalShardIndex {
+                    original_count,
+                    index,
+                });
+            }
+
+            let id = ShardId::Original(index);
+            if !indexes.insert(index)
+                || work.received_shard(id).is_some()
+                || self.owned.contains_key(&id)
+            {
+                return Err(Error::DuplicateOriginalShardIndex { index });
+            }
+        }
+
+        let result = self.decode()?;
+
+        // Nothing is written unless all outputs can be written.
+        if let Some((index, _)) = outputs
+            .iter()
+            .find(|(index, _)| result.restored_original(*index).is_none())
+        {
+            return Err(Error::DuplicateOriginalShardIndex { index: *index });
+        }
+
+        for (index, output) in outputs.iter_mut() {
+            let restored = result.restored_original(*index).unwrap();
+            for (output, byte) in output.iter_mut().zip(restored) {
+                output.write(*byte);
+            }
+        }
+
+        Ok(())
     }
 
     /// Decodes the added shards returning [`DecoderResult`]
@@ -127,11 +1425,163 @@ impl ReedSolomonDecoder {
     /// When returned [`DecoderResult`] is dropped the decoder is
     /// automatically [`reset`] and ready for new round of decoding.
     ///
+    /// Invalid shards are already rejected when added, so this only fails
+    /// with [`Error::NotEnoughShards`], which keeps added shards
+    /// so that more can be added before retrying.
+    ///
+    /// Working space, including a 128 kiB buffer for erasure locations,
+    /// is on heap and re-used by later decodings,
+    /// so decoding works with small thread stacks too.
+    ///
+    /// If more shards have been added than are needed, all original shards
+    /// are used and only as many recovery shards as there are missing
+    /// original shards, preferring ones with lowest indexes.
+    /// This gives same result with less work.
+    ///
     /// See [basic usage](crate#basic-usage) for an example.
     ///
     /// [`reset`]: ReedSolomonDecoder::reset
     pub fn decode(&mut self) -> Result<DecoderResult, Error> {
-        self.0.decode()
+        self.decode_with_control(|_| ControlFlow::Continue(()))
+    }
+
+    /// Like [`decode`] but reports progress to `control`
+    /// which can also cancel decoding.
+    ///
+    /// - `control` is called before each step of decoding with
+    ///   the approximate fraction of work done so far, from `0.0` upwards.
+    /// - Returning [`ControlFlow::Break`] cancels decoding and returns
+    ///   [`Error::Cancelled`]. Received shards have already been partly
+    ///   overwritten at that point, so the decoder is [`reset`]
+    ///   and shards must be added again before retrying.
+    /// - Steps are not interrupted, so cancellation takes effect
+    ///   within at most one IFFT or FFT over the working space.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use std::ops::ControlFlow;
+    /// use reed_solomon_simd::{Error, ReedSolomonDecoder};
+    ///
+    /// let original = [[1u8; 64], [2u8; 64]];
+    /// let recovery = reed_solomon_simd::encode(2, 1, original)?;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(2, 1, 64)?;
+    /// decoder.add_original_shard(1, original[1])?;
+    /// decoder.add_recovery_shard(0, &recovery[0])?;
+    /// let cancelled = decoder.decode_with_control(|progress| {
+    ///     if progress < 0.5 {
+    ///         ControlFlow::Continue(())
+    ///     } else {
+    ///         ControlFlow::Break(())
+    ///     }
+    /// });
+    /// assert_eq!(cancelled.err(), Some(Error::Cancelled));
+    ///
+    /// // Retry with the shards added again.
+    /// decoder.add_original_shard(1, original[1])?;
+    /// decoder.add_recovery_shard(0, &recovery[0])?;
+    /// let result = decoder.decode_with_control(|_| ControlFlow::Continue(()))?;
+    /// assert_eq!(result.restored_original(0).unwrap(), original[0]);
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`decode`]: ReedSolomonDecoder::decode
+    /// [`reset`]: ReedSolomonDecoder::reset
+    pub fn decode_with_control<F: FnMut(f32) -> ControlFlow<()>>(
+        &mut self,
+        control: F,
+    ) -> Result<DecoderResult, Error> {
+        let (used, unused) = self.add_owned_shards()?;
+        let mut result = self.decode_untrusted(control)?;
+        result.set_owned_shards(used, unused);
+        Ok(result)
+    }
+
+    /// Like [`decode`] but also returns time taken by each phase of decoding.
+    ///
+    /// This is meant for tuning without external profilers,
+    /// e.g. to see how decoding time splits between `eval_poly`,
+    /// which has fixed cost, and `ifft` / `fft`, which scale with shard data.
+    /// [`decode`] doesn't measure time at all.
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// let original = [[1u8; 64], [2u8; 64]];
+    /// let recovery = reed_solomon_simd::encode(2, 1, original)?;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(2, 1, 64)?;
+    /// decoder.add_original_shard(1, original[1])?;
+    /// decoder.add_recovery_shard(0, &recovery[0])?;
+    /// let (result, timings) = decoder.decode_timed()?;
+    /// assert_eq!(result.restored_original(0).unwrap(), original[0]);
+    /// println!("eval_poly {:?}, total {:?}", timings.eval_poly, timings.total());
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`decode`]: ReedSolomonDecoder::decode
+    pub fn decode_timed(&mut self) -> Result<(DecoderResult, PhaseTimings), Error> {
+        let mut timer = PhaseTimer::new();
+        let result = self.decode_with_control(|progress| {
+            timer.checkpoint(progress);
+            ControlFlow::Continue(())
+        })?;
+        Ok((result, timer.finish()))
+    }
+
+    /// Like [`decode`] but restores one or two missing original shards
+    /// directly from the received shards, falling back to [`decode`]
+    /// when more original shards are missing.
+    ///
+    /// Each missing original shard is then a linear combination of
+    /// all received original shards and as many recovery shards
+    /// as there are missing original shards. This takes time linear in
+    /// `original_count` instead of the `O(n log n)` transforms of [`decode`],
+    /// e.g. with `16384:16384` and 1 kiB shards restoring one shard is
+    /// about 8x and two shards about 5x faster, including adding the shards.
+    ///
+    /// Each further missing shard would cost another pass over all
+    /// received shards, so with `16384:16384` peeling would break even
+    /// with [`decode`] at around 13 missing shards and with smaller
+    /// `original_count` even sooner. Limit of two keeps the solving
+    /// in closed form and well below that crossover.
+    ///
+    /// Restored shards are identical to those of [`decode`].
+    /// Untrusted or owned shards and `with_stripe_hash` also
+    /// always use [`decode`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+    /// let recovery = reed_solomon_simd::encode(3, 2, original)?;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(3, 2, 64)?;
+    /// decoder.add_original_shard(1, original[1])?;
+    /// decoder.add_recovery_shard(0, &recovery[0])?;
+    /// decoder.add_recovery_shard(1, &recovery[1])?;
+    /// let result = decoder.decode_peeling()?;
+    /// assert_eq!(result.restored_original(0).unwrap(), original[0]);
+    /// assert_eq!(result.restored_original(2).unwrap(), original[2]);
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`decode`]: ReedSolomonDecoder::decode
+    pub fn decode_peeling(&mut self) -> Result<DecoderResult<'_>, Error> {
+        if self.untrusted.is_empty() && self.owned.is_empty() && !self.stripe_hash {
+            self.rate.decode_peeling()
+        } else {
+            self.decode()
+        }
     }
 
     /// Creates new decoder with given configuration
@@ -143,109 +1593,2552 @@ impl ReedSolomonDecoder {
         recovery_count: usize,
         shard_bytes: usize,
     ) -> Result<Self, Error> {
-        Ok(Self(DefaultRateDecoder::new(
-            original_count,
-            recovery_count,
-            shard_bytes,
-            DefaultEngine::new(),
-            None,
-        )?))
+        Ok(Self {
+            rate: DefaultRateDecoder::new(
+                original_count,
+                recovery_count,
+                shard_bytes,
+                DefaultEngine::new(),
+                None,
+            )?,
+            untrusted: BTreeMap::new(),
+            footer_stripe_id: None,
+            owned: BTreeMap::new(),
+            stripe_hash: false,
+        })
+    }
+
+    /// Like [`new`] but allocates working space with given allocator.
+    ///
+    /// Requires nightly Rust and `allocator-api` feature.
+    ///
+    /// [`new`]: ReedSolomonDecoder::new
+    #[cfg(feature = "allocator-api")]
+    pub fn new_in<A: Allocator + Send + Sync + 'static>(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        alloc: A,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            rate: DefaultRateDecoder::new(
+                original_count,
+                recovery_count,
+                shard_bytes,
+                DefaultEngine::new(),
+                Some(DecoderWork::new_in(alloc)),
+            )?,
+            untrusted: BTreeMap::new(),
+            footer_stripe_id: None,
+            owned: BTreeMap::new(),
+            stripe_hash: false,
+        })
+    }
+
+    /// Creates new decoder with configuration given as [`CodecParams`]
+    /// and allocates required working space.
+    ///
+    /// This is same as [`ReedSolomonDecoder::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::{CodecParams, ReedSolomonDecoder};
+    ///
+    /// let decoder = ReedSolomonDecoder::from_params(CodecParams::new(3, 5, 64))?;
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    pub fn from_params(params: CodecParams) -> Result<Self, Error> {
+        Self::new(
+            params.original_count,
+            params.recovery_count,
+            params.shard_bytes,
+        )
+    }
+
+    /// Creates new decoder with given configuration, re-using
+    /// working space taken earlier with [`into_recycled`].
+    ///
+    /// This is same as [`new`] except that `work` is used instead of
+    /// allocating, so working space isn't re-allocated if it's large enough.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// let decoder = ReedSolomonDecoder::new(1000, 100, 1024)?;
+    /// let allocated = decoder.allocated_bytes();
+    ///
+    /// let work = decoder.into_recycled();
+    /// let decoder = ReedSolomonDecoder::from_recycled(1000, 100, 1024, work)?;
+    /// assert_eq!(decoder.allocated_bytes(), allocated);
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`into_recycled`]: ReedSolomonDecoder::into_recycled
+    /// [`new`]: ReedSolomonDecoder::new
+    pub fn from_recycled(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        work: DecoderWork,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            rate: DefaultRateDecoder::new(
+                original_count,
+                recovery_count,
+                shard_bytes,
+                DefaultEngine::new(),
+                Some(work),
+            )?,
+            untrusted: BTreeMap::new(),
+            footer_stripe_id: None,
+            owned: BTreeMap::new(),
+            stripe_hash: false,
+        })
+    }
+
+    /// Creates new decoder configured for given [`RepairPlan`].
+    ///
+    /// Shards listed in [`RepairPlan::fetch`] should then be added
+    /// and [`RepairPlan::missing`] shards will be restored by [`decode`].
+    ///
+    /// See [`repair`] module for an example.
+    ///
+    /// [`decode`]: ReedSolomonDecoder::decode
+    /// [`repair`]: crate::repair
+    pub fn from_plan(plan: &RepairPlan) -> Result<Self, Error> {
+        Self::from_params(plan.params())
+    }
+
+    /// Resets decoder to given configuration.
+    ///
+    /// - Added shards are forgotten.
+    /// - Existing working space is re-used if it's large enough
+    ///   or re-allocated otherwise.
+    ///   With `zeroize` feature it's zeroed first,
+    ///   see [zeroizing working space](crate#zeroizing-working-space).
+    pub fn reset(
+        &mut self,
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<(), Error> {
+        self.untrusted.clear();
+        self.owned.clear();
+        self.rate.reset(original_count, recovery_count, shard_bytes)
+    }
+
+    /// Enables integrity footer with given `stripe_id`.
+    ///
+    /// Recovery shards must then have been encoded by an encoder
+    /// with integrity footer enabled with same configuration,
+    /// otherwise [`add_recovery_shard`] returns [`Error::ParameterMismatch`],
+    /// and same `stripe_id`, otherwise it returns [`Error::StripeMismatch`].
+    /// See [`integrity`] module for details.
+    ///
+    /// [`add_recovery_shard`]: ReedSolomonDecoder::add_recovery_shard
+    /// [`integrity`]: crate::integrity
+    pub fn with_integrity_footer(mut self, stripe_id: u64) -> Self {
+        self.footer_stripe_id = Some(stripe_id);
+        self
+    }
+
+    /// Enables integrity footer with given `stripe_id`
+    /// or disables it with `None`.
+    ///
+    /// This is same as [`with_integrity_footer`] but e.g. allows
+    /// changing `stripe_id` between stripes without creating new decoder.
+    ///
+    /// [`with_integrity_footer`]: ReedSolomonDecoder::with_integrity_footer
+    pub fn set_integrity_footer(&mut self, stripe_id: Option<u64>) {
+        self.footer_stripe_id = stripe_id;
+    }
+
+    /// Enables computing [stripe hash] of original shards when decoding,
+    /// available from [`DecoderResult::integrity_hash`].
+    ///
+    /// **This method is only available with `stripe-hash` feature.**
+    ///
+    /// This hashes all original shards, both given and restored,
+    /// which takes time comparable to decoding itself.
+    /// See [`integrity::stripe_hash`] for an example.
+    ///
+    /// [stripe hash]: crate::integrity#stripe-hash
+    /// [`integrity::stripe_hash`]: crate::integrity::stripe_hash
+    #[cfg(feature = "stripe-hash")]
+    pub fn with_stripe_hash(mut self) -> Self {
+        self.stripe_hash = true;
+        self
+    }
+
+    /// Returns number of bytes currently allocated for working space.
+    ///
+    /// See [`shrink_to_fit`](Self::shrink_to_fit).
+    pub fn allocated_bytes(&self) -> usize {
+        self.rate.work().allocated_bytes()
+    }
+
+    /// Replaces added shards with those saved by [`save_work`],
+    /// see [`DecoderWork::load`].
+    ///
+    /// Decoder must have the same configuration that was saved.
+    /// On error all added shards are forgotten.
+    ///
+    /// [`save_work`]: ReedSolomonDecoder::save_work
+    pub fn load_work<R: Read>(&mut self, reader: R) -> Result<(), LoadError> {
+        self.untrusted.clear();
+        self.owned.clear();
+        self.rate.work_mut().load(reader)
+    }
+
+    /// Writes added shards so that they can be restored with [`load_work`],
+    /// see [`DecoderWork::save`].
+    ///
+    /// Only shards in working space are saved, i.e. not shards given to
+    /// [`add_untrusted_shard`] or the `*_owned` methods which
+    /// haven't been used by [`decode`] yet.
+    /// Integrity footer setting isn't saved either.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// let original = [[1u8; 64], [2u8; 64]];
+    /// let recovery = reed_solomon_simd::encode(2, 2, original)?;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(2, 2, 64)?;
+    /// decoder.add_recovery_shard(0, &recovery[0])?;
+    ///
+    /// let mut saved = Vec::new();
+    /// decoder.save_work(&mut saved).unwrap();
+    ///
+    /// // E.g. after restart.
+    /// let mut decoder = ReedSolomonDecoder::new(2, 2, 64)?;
+    /// decoder.load_work(saved.as_slice()).unwrap();
+    /// decoder.add_recovery_shard(1, &recovery[1])?;
+    ///
+    /// let result = decoder.decode()?;
+    /// assert_eq!(result.restored_original(0).unwrap(), original[0]);
+    /// # }
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    ///
+    /// [`load_work`]: ReedSolomonDecoder::load_work
+    /// [`add_untrusted_shard`]: ReedSolomonDecoder::add_untrusted_shard
+    /// [`decode`]: ReedSolomonDecoder::decode
+    pub fn save_work<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.rate.work().save(writer)
+    }
+
+    /// Releases working space which current configuration doesn't need.
+    ///
+    /// Working space is re-used by [`reset`](Self::reset) and never shrunk
+    /// automatically, so after handling a large configuration it stays allocated.
+    /// For long-lived decoders which now handle smaller configurations
+    /// this lowers memory usage, at the cost of re-allocation
+    /// if a larger configuration is used again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// let mut decoder = ReedSolomonDecoder::new(1000, 1000, 1024)?;
+    /// decoder.reset(2, 2, 64)?;
+    ///
+    /// let before = decoder.allocated_bytes();
+    /// decoder.shrink_to_fit();
+    /// assert!(decoder.allocated_bytes() < before);
+    /// # Ok::<(), reed_solomon_simd::Error>(())
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.rate.work_mut().shrink_to_fit();
+    }
+
+    /// Consumes the decoder returning its working space,
+    /// to be re-used by [`from_recycled`] instead of being freed.
+    ///
+    /// Added shards are forgotten, including owned and untrusted shards
+    /// which are dropped.
+    ///
+    /// See [`from_recycled`] for an example.
+    ///
+    /// [`from_recycled`]: ReedSolomonDecoder::from_recycled
+    pub fn into_recycled(self) -> DecoderWork {
+        self.rate.into_parts().1
+    }
+
+    /// Returns `true` if given `original_count` / `recovery_count`
+    /// combination is supported.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// assert_eq!(ReedSolomonDecoder::supports(60_000, 4_000), true);
+    /// assert_eq!(ReedSolomonDecoder::supports(60_000, 5_000), false);
+    /// ```
+    pub fn supports(original_count: usize, recovery_count: usize) -> bool {
+        DefaultRate::<DefaultEngine>::supports(original_count, recovery_count)
+    }
+
+    /// Returns the largest supported `original_count`
+    /// with given `recovery_count`, or `0` if there is none.
+    ///
+    /// Every `original_count` in `1 ..= max_original_count(recovery_count)`
+    /// is supported, see [`supports`]. With `r = recovery_count`
+    /// and `r_pow2 = r.next_power_of_two()` this is
+    ///
+    /// - `65536 - r_pow2` if `r_pow2 + r <= 65536`
+    /// - otherwise largest power of two which is at most `65536 - r`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use reed_solomon_simd::ReedSolomonDecoder;
+    ///
+    /// assert_eq!(ReedSolomonDecoder::max_original_count(4_000), 61_440);
+    /// assert_eq!(ReedSolomonDecoder::max_original_count(40_000), 16_384);
+    /// ```
+    ///
+    /// [`supports`]: ReedSolomonDecoder::supports
+    pub fn max_original_count(recovery_count: usize) -> usize {
+        rate::max_shard_count(recovery_count)
+    }
+
+    /// Returns the largest supported `recovery_count`
+    /// with given `original_count`, or `0` if there is none.
+    ///
+    /// This is same as [`max_original_count`] with roles swapped.
+    ///
+    /// [`max_original_count`]: ReedSolomonDecoder::max_original_count
+    pub fn max_recovery_count(original_count: usize) -> usize {
+        rate::max_shard_count(original_count)
+    }
+}
+
+// ======================================================================
+// ReedSolomonDecoder - PRIVATE
+
+#[cfg(feature = "decoder")]
+impl ReedSolomonDecoder {
+    fn integrity_footer(&self) -> Option<IntegrityFooter> {
+        let work = self.rate.work();
+        let params = CodecParams::new(
+            work.original_count(),
+            work.recovery_count(),
+            work.shard_bytes(),
+        );
+        self.footer_stripe_id
+            .map(|stripe_id| IntegrityFooter::new(params, stripe_id))
+    }
+
+    fn add_owned_shard(&mut self, id: ShardId, shard: Vec<u8>) -> Result<(), Error> {
+        let work = self.rate.work();
+
+        let duplicate = work.received_shard(id).is_some()
+            || self.owned.contains_key(&id)
+            || matches!(id, ShardId::Recovery(index) if work.is_skipped_recovery(index));
+        match id {
+            ShardId::Original(index) => {
+                if index >= work.original_count() {
+                    return Err(Error::InvalidOriginalShardIndex {
+                        original_count: work.original_count(),
+                        index,
+                    });
+                } else if duplicate {
+                    return Err(Error::DuplicateOriginalShardIndex { index });
+                }
+            }
+
+            ShardId::Recovery(index) => {
+                if index >= work.recovery_count() {
+                    return Err(Error::InvalidRecoveryShardIndex {
+                        recovery_count: work.recovery_count(),
+                        index,
+                    });
+                } else if duplicate {
+                    return Err(Error::DuplicateRecoveryShardIndex { index });
+                }
+                if let Some(expected) = self.integrity_footer() {
+                    integrity::check_footer(expected, index, &shard)?;
+                }
+            }
+        }
+
+        if shard.len() != work.shard_bytes() {
+            return Err(Error::DifferentShardSize {
+                shard_bytes: work.shard_bytes(),
+                got: shard.len(),
+            });
+        }
+
+        self.owned.insert(id, shard);
+        Ok(())
+    }
+
+    // Copies as few owned shards as needed for decoding into working space,
+    // returning used and unused owned shards.
+    // - Owned shards are kept if there are not enough shards for decoding.
+    #[allow(clippy::type_complexity)]
+    fn add_owned_shards(
+        &mut self,
+    ) -> Result<(Vec<(ShardId, Vec<u8>)>, Vec<(ShardId, Vec<u8>)>), Error> {
+        let mut used = Vec::new();
+        let mut unused = Vec::new();
+        if self.owned.is_empty() || !self.can_recover() {
+            return Ok((used, unused));
+        }
+
+        let work = self.rate.work();
+        let mut needed = work.original_count().saturating_sub(work.received_count());
+        for (id, shard) in std::mem::take(&mut self.owned) {
+            if needed > 0 && self.rate.work().received_shard(id).is_none() {
+                match id {
+                    ShardId::Original(index) => self.rate.add_original_shard(index, &shard)?,
+                    ShardId::Recovery(index) => self.add_recovery_shard(index, &shard)?,
+                }
+                needed -= 1;
+                used.push((id, shard));
+            } else {
+                unused.push((id, shard));
+            }
+        }
+
+        Ok((used, unused))
+    }
+
+    // Decodes like `RateDecoder::decode_with_control`,
+    // also computing stripe hash if enabled, see `with_stripe_hash`.
+    fn decode_hashed<F: FnMut(f32) -> ControlFlow<()>>(
+        &mut self,
+        control: F,
+    ) -> Result<DecoderResult<'_>, Error> {
+        #[cfg(feature = "stripe-hash")]
+        if self.stripe_hash && self.can_recover() {
+            // Content of received shards is only available before decoding.
+            let work = self.rate.work();
+            let digests: Vec<_> = (0..work.original_count())
+                .map(|index| {
+                    work.received_shard(ShardId::Original(index))
+                        .map(crate::integrity::shard_digest)
+                })
+                .collect();
+
+            let mut result = self.rate.decode_with_control(control)?;
+            let hash = crate::integrity::stripe_hash_from_digests(
+                digests.into_iter().enumerate().map(|(index, digest)| {
+                    digest.unwrap_or_else(|| {
+                        crate::integrity::shard_digest(result.restored_original(index).unwrap())
+                    })
+                }),
+            );
+            result.set_integrity_hash(hash);
+            return Ok(result);
+        }
+
+        self.rate.decode_with_control(control)
+    }
+
+    // Decodes like `RateDecoder::decode_with_control`,
+    // first using untrusted shards as needed, see `add_untrusted_shard`.
+    fn decode_untrusted<F: FnMut(f32) -> ControlFlow<()>>(
+        &mut self,
+        mut control: F,
+    ) -> Result<DecoderResult, Error> {
+        // Untrusted shards are kept if there are not enough shards.
+        if self.untrusted.is_empty() || !self.can_recover() {
+            return self.decode_hashed(control);
+        }
+
+        let untrusted = std::mem::take(&mut self.untrusted);
+        let mut mismatches = Vec::new();
+        let mut surplus = Vec::new();
+
+        // Content of trusted shards is only available before decoding.
+        let work = self.rate.work();
+        let mut needed = work.original_count().saturating_sub(work.received_count());
+        let mut candidates = Vec::new();
+        for (id, shard) in untrusted {
+            match work.received_shard(id) {
+                Some(trusted) if trusted != shard => mismatches.push(id),
+                Some(_) => {}
+                None => candidates.push((id, shard)),
+            }
+        }
+
+        for (id, shard) in candidates {
+            if needed > 0 {
+                match id {
+                    ShardId::Original(index) => self.rate.add_original_shard(index, &shard)?,
+                    ShardId::Recovery(index) => self.rate.add_recovery_shard(index, &shard)?,
+                }
+                needed -= 1;
+            } else {
+                surplus.push((id, shard));
+            }
+        }
+
+        let mut result = self.decode_hashed(&mut control)?;
+
+        for (id, shard) in surplus {
+            if let ShardId::Original(index) = id {
+                if result.restored_original(index) != Some(&shard) {
+                    mismatches.push(id);
+                }
+            }
+        }
+
+        mismatches.sort_unstable();
+        result.set_untrusted_mismatches(mismatches);
+        Ok(result)
+    }
+}
+
+// ======================================================================
+// ReedSolomonDecoder - IMPL RateDecoder
+
+#[cfg(feature = "decoder")]
+impl RateDecoder<DefaultEngine> for ReedSolomonDecoder {
+    type Rate = DefaultRate<DefaultEngine>;
+
+    fn add_original_shard<T: AsRef<[u8]>>(
+        &mut self,
+        index: usize,
+        original_shard: T,
+    ) -> Result<(), Error> {
+        self.rate.add_original_shard(index, original_shard)
+    }
+
+    fn add_recovery_shard<T: AsRef<[u8]>>(
+        &mut self,
+        index: usize,
+        recovery_shard: T,
+    ) -> Result<(), Error> {
+        ReedSolomonDecoder::add_recovery_shard(self, index, recovery_shard)
+    }
+
+    fn begin_original_shard(&mut self, index: usize) -> Result<ShardWriter, Error> {
+        self.rate.begin_original_shard(index)
+    }
+
+    fn begin_recovery_shard(&mut self, index: usize) -> Result<ShardWriter, Error> {
+        ReedSolomonDecoder::begin_recovery_shard(self, index)
+    }
+
+    fn decode(&mut self) -> Result<DecoderResult, Error> {
+        ReedSolomonDecoder::decode(self)
+    }
+
+    fn decode_with_control<F: FnMut(f32) -> ControlFlow<()>>(
+        &mut self,
+        control: F,
+    ) -> Result<DecoderResult, Error> {
+        ReedSolomonDecoder::decode_with_control(self, control)
+    }
+
+    fn into_parts(self) -> (DefaultEngine, DecoderWork) {
+        self.rate.into_parts()
+    }
+
+    fn new(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        engine: DefaultEngine,
+        work: Option<DecoderWork>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            rate: DefaultRateDecoder::new(
+                original_count,
+                recovery_count,
+                shard_bytes,
+                engine,
+                work,
+            )?,
+            untrusted: BTreeMap::new(),
+            footer_stripe_id: None,
+            owned: BTreeMap::new(),
+            stripe_hash: false,
+        })
+    }
+
+    fn reset(
+        &mut self,
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<(), Error> {
+        ReedSolomonDecoder::reset(self, original_count, recovery_count, shard_bytes)
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+// Returns content of a recovery shard before its footer.
+// - Footer must have been checked with `check_footer`.
+#[cfg(feature = "decoder")]
+fn strip_footer(shard: &[u8]) -> &[u8] {
+    &shard[..shard.len() - FOOTER_BYTES]
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
+mod tests {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+        collections::HashMap,
+    };
+
+    use fixedbitset::FixedBitSet;
+
+    use super::*;
+    use crate::{
+        engine::{Engine, NoSimd, GF_ORDER},
+        rate::{HighRateDecoder, LowRateDecoder},
+        test_util, UnsupportedReason,
+    };
+
+    // ============================================================
+    // HELPERS
+
+    fn roundtrip(
+        encoder: &mut ReedSolomonEncoder,
+        decoder: &mut ReedSolomonDecoder,
+        original_count: usize,
+        recovery_hash: &str,
+        decoder_original: &[usize],
+        decoder_recovery: &[usize],
+        seed: u8,
+    ) {
+        let original = test_util::generate_original(original_count, 1024, seed);
+
+        for original in &original {
+            encoder.add_original_shard(original).unwrap();
+        }
+
+        let result = encoder.encode().unwrap();
+        let recovery: Vec<_> = result.recovery_iter().collect();
+
+        test_util::assert_hash(&recovery, recovery_hash);
+
+        let mut original_received = FixedBitSet::with_capacity(original_count);
+
+        for i in decoder_original {
+            decoder.add_original_shard(*i, &original[*i]).unwrap();
+            original_received.set(*i, true);
+        }
+
+        for i in decoder_recovery {
+            decoder.add_recovery_shard(*i, recovery[*i]).unwrap();
+        }
+
+        let result = decoder.decode().unwrap();
+        let restored: HashMap<_, _> = result.restored_original_iter().collect();
+
+        for i in 0..original_count {
+            if !original_received[i] {
+                assert_eq!(restored[&i], original[i]);
+            }
+        }
+    }
+
+    // ============================================================
+    // ROUNDTRIP - TWO ROUNDS
+
+    #[test]
+    fn roundtrip_two_rounds_reset_low_to_high() {
+        let mut encoder = ReedSolomonEncoder::new(2, 3, 1024).unwrap();
+        let mut decoder = ReedSolomonDecoder::new(2, 3, 1024).unwrap();
+
+        roundtrip(
+            &mut encoder,
+            &mut decoder,
+            2,
+            test_util::LOW_2_3,
+            &[],
+            &[0, 1],
+            123,
+        );
+
+        encoder.reset(3, 2, 1024).unwrap();
+        decoder.reset(3, 2, 1024).unwrap();
+
+        roundtrip(
+            &mut encoder,
+            &mut decoder,
+            3,
+            test_util::HIGH_3_2,
+            &[1],
+            &[0, 1],
+            132,
+        );
+    }
+
+    // ============================================================
+    // set_shard_bytes
+
+    #[test]
+    fn set_shard_bytes_alternating() {
+        let mut encoder = ReedSolomonEncoder::new(3, 5, 2048).unwrap();
+        let allocated = encoder.allocated_bytes();
+
+        for (round, shard_bytes) in [512, 2048, 512, 2048, 512].into_iter().enumerate() {
+            encoder.set_shard_bytes(shard_bytes).unwrap();
+
+            let original = test_util::generate_original(3, shard_bytes, round as u8);
+            for original in &original {
+                encoder.add_original_shard(original).unwrap();
+            }
+            let recovery: Vec<_> = encoder
+                .encode()
+                .unwrap()
+                .recovery_iter()
+                .map(<[u8]>::to_vec)
+                .collect();
+
+            assert_eq!(recovery, crate::encode(3, 5, &original).unwrap());
+            assert_eq!(encoder.allocated_bytes(), allocated);
+        }
+
+        assert_eq!(
+            encoder.set_shard_bytes(63),
+            Err(Error::InvalidShardSize { shard_bytes: 63 })
+        );
+        assert_eq!(encoder.set_shard_bytes(64), Ok(()));
+    }
+
+    // ============================================================
+    // verify
+
+    fn refs(shards: &[Vec<u8>]) -> Vec<&[u8]> {
+        shards.iter().map(Vec::as_slice).collect()
+    }
+
+    #[test]
+    fn verify_detects_tampered_recovery() {
+        let original = test_util::generate_original(3, 1024, 176);
+        let originals = refs(&original);
+        let mut recovery = crate::encode(3, 2, &original).unwrap();
+
+        let mut encoder = ReedSolomonEncoder::new(3, 2, 1024).unwrap();
+        // Pending original shard is forgotten.
+        encoder.add_original_shard(&original[0]).unwrap();
+
+        assert!(encoder.verify(&originals, &refs(&recovery)).unwrap());
+
+        recovery[1][500] ^= 0x40;
+        assert!(!encoder.verify(&originals, &refs(&recovery)).unwrap());
+        assert!(!encoder.verify(&originals, &refs(&recovery[..1])).unwrap());
+
+        assert_eq!(
+            encoder.verify(&originals[..2], &refs(&recovery)),
+            Err(Error::TooFewOriginalShards {
+                original_count: 3,
+                original_received_count: 2,
+            })
+        );
+    }
+
+    // ============================================================
+    // encode_ref
+
+    #[test]
+    fn encode_ref_matches_add_and_encode() {
+        let original = test_util::generate_original(3, 1024, 179);
+        let expected = crate::encode(3, 2, &original).unwrap();
+
+        let mut encoder = ReedSolomonEncoder::new(3, 2, 1024).unwrap();
+        // Pending original shard is forgotten.
+        encoder.add_original_shard(&original[2]).unwrap();
+
+        for _ in 0..2 {
+            let result = encoder.encode_ref(&original).unwrap();
+            assert!(result.recovery_iter().eq(refs(&expected)));
+        }
+    }
+
+    #[test]
+    fn encode_ref_errors() {
+        let original = test_util::generate_original(3, 64, 179);
+        let mut encoder = ReedSolomonEncoder::new(2, 2, 64).unwrap();
+
+        assert_eq!(
+            encoder.encode_ref(&original[..1]).err(),
+            Some(Error::TooFewOriginalShards {
+                original_count: 2,
+                original_received_count: 1,
+            })
+        );
+        assert_eq!(
+            encoder.encode_ref(&original).err(),
+            Some(Error::TooManyOriginalShards { original_count: 2 })
+        );
+        assert_eq!(
+            encoder.encode_ref(&[&[0u8; 64][..], &[0u8; 128]]).err(),
+            Some(Error::DifferentShardSize {
+                shard_bytes: 64,
+                got: 128,
+            })
+        );
+        assert!(encoder.encode_ref(&original[..2]).is_ok());
+    }
+
+    // ============================================================
+    // encode_flat
+
+    #[test]
+    fn encode_flat_matches_encode() {
+        let original = test_util::generate_original(5, 1024, 202);
+        let recovery = crate::encode(5, 3, &original).unwrap();
+
+        let mut encoder = ReedSolomonEncoder::new(5, 3, 1024).unwrap();
+        // Pending original shard is forgotten.
+        encoder.add_original_shard(&original[4]).unwrap();
+
+        let mut stripe = original.concat();
+        stripe.resize(8 * 1024, 0xFF);
+        for _ in 0..2 {
+            encoder.encode_flat(&mut stripe).unwrap();
+            assert_eq!(stripe[..5 * 1024], original.concat());
+            assert_eq!(stripe[5 * 1024..], recovery.concat());
+        }
+    }
+
+    #[test]
+    fn encode_flat_errors() {
+        let mut encoder = ReedSolomonEncoder::new(2, 1, 64).unwrap();
+
+        assert_eq!(
+            encoder.encode_flat(&mut [0u8; 128]),
+            Err(Error::InvalidOutputSize {
+                expected: 192,
+                got: 128,
+            })
+        );
+
+        let mut encoder = ReedSolomonEncoder::new(2, 1, 64)
+            .unwrap()
+            .with_integrity_footer(1);
+        assert_eq!(
+            encoder.encode_flat(&mut [1u8; 192]),
+            Err(Error::NonZeroReservedBytes { index: 0 })
+        );
+    }
+
+    // ============================================================
+    // decode_ref
+
+    #[test]
+    fn decode_ref_forgets_added_shards() {
+        let original = test_util::generate_original(3, 1024, 179);
+        let recovery = crate::encode(3, 2, &original).unwrap();
+
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 1024).unwrap();
+        // Pending shards are forgotten.
+        decoder.add_original_shard(1, &original[1]).unwrap();
+        decoder
+            .add_untrusted_shard(ShardKind::Recovery, 0, &recovery[1])
+            .unwrap();
+        decoder
+            .add_recovery_shard_owned(1, recovery[1].clone())
+            .unwrap();
+
+        for _ in 0..2 {
+            let result = decoder
+                .decode_ref(
+                    &[(1, &original[1])],
+                    &[(0, &recovery[0]), (1, &recovery[1])],
+                )
+                .unwrap();
+            assert_eq!(result.restored_original(0).unwrap(), original[0]);
+            assert_eq!(result.restored_original(2).unwrap(), original[2]);
+            assert!(result.untrusted_mismatches().is_empty());
+        }
+    }
+
+    #[test]
+    fn decode_ref_errors() {
+        let mut decoder = ReedSolomonDecoder::new(2, 2, 64).unwrap();
+        let no_shards: &[(usize, [u8; 64])] = &[];
+
+        assert_eq!(
+            decoder.decode_ref(&[(0, [0u8; 64])], no_shards).err(),
+            Some(Error::NotEnoughShards {
+                original_count: 2,
+                original_received_count: 1,
+                recovery_received_count: 0,
+            })
+        );
+        assert_eq!(
+            decoder.decode_ref(no_shards, &[(2, [0u8; 64])]).err(),
+            Some(Error::InvalidRecoveryShardIndex {
+                recovery_count: 2,
+                index: 2,
+            })
+        );
+        assert_eq!(
+            decoder
+                .decode_ref(&[(0, [0u8; 64]), (0, [0u8; 64])], no_shards)
+                .err(),
+            Some(Error::DuplicateOriginalShardIndex { index: 0 })
+        );
+        assert!(decoder
+            .decode_ref(&[(0, [0u8; 64]), (1, [0u8; 64])], no_shards)
+            .is_ok());
+    }
+
+    // ============================================================
+    // decode_flat
+
+    #[test]
+    fn decode_flat_matches_decode() {
+        use rand::{seq::index, SeedableRng};
+        use rand_chacha::ChaCha8Rng;
+
+        let mut rng = ChaCha8Rng::from_seed([202; 32]);
+        let original = test_util::generate_original(20, 1024, 202);
+        let recovery = crate::encode(20, 10, &original).unwrap();
+        let mut expected = original.concat();
+        expected.extend(recovery.concat());
+
+        let mut decoder = ReedSolomonDecoder::new(20, 10, 1024).unwrap();
+        for lost_count in [0, 1, 5, 10] {
+            let mut present = vec![true; 30];
+            for index in index::sample(&mut rng, 30, lost_count) {
+                present[index] = false;
+            }
+
+            let mut stripe = expected.clone();
+            for (shard, _) in stripe
+                .chunks_exact_mut(1024)
+                .zip(&present)
+                .filter(|(_, present)| !**present)
+            {
+                shard.fill(0xFF);
+            }
+
+            let mut originals = Vec::new();
+            let mut recoveries = Vec::new();
+            for (index, shard) in expected.chunks_exact(1024).enumerate() {
+                if present[index] && index < 20 {
+                    originals.push((index, shard));
+                } else if present[index] {
+                    recoveries.push((index - 20, shard));
+                }
+            }
+            let result = decoder.decode_ref(&originals, &recoveries).unwrap();
+            let mut restored = stripe.clone();
+            for (index, shard) in result.restored_original_iter() {
+                restored[index * 1024..(index + 1) * 1024].copy_from_slice(shard);
+            }
+            drop(result);
+
+            decoder.decode_flat(&mut stripe, &present).unwrap();
+            assert_eq!(stripe, restored);
+            assert_eq!(stripe[..20 * 1024], expected[..20 * 1024]);
+        }
+    }
+
+    #[test]
+    fn decode_flat_errors() {
+        let mut decoder = ReedSolomonDecoder::new(2, 1, 64).unwrap();
+        let mut stripe = [0xFFu8; 192];
+
+        assert_eq!(
+            decoder.decode_flat(&mut stripe[..128], &[true; 3]),
+            Err(Error::InvalidOutputSize {
+                expected: 192,
+                got: 128,
+            })
+        );
+        assert_eq!(
+            decoder.decode_flat(&mut stripe, &[true; 2]),
+            Err(Error::InvalidPresenceLength {
+                expected: 3,
+                got: 2,
+            })
+        );
+        assert_eq!(
+            decoder.decode_flat(&mut stripe, &[false, false, true]),
+            Err(Error::NotEnoughShards {
+                original_count: 2,
+                original_received_count: 0,
+                recovery_received_count: 1,
+            })
+        );
+        // Nothing is written on error.
+        assert_eq!(stripe, [0xFFu8; 192]);
+    }
+
+    // ============================================================
+    // decode_into_uninit
+
+    fn uninit_outputs(
+        indexes: &[usize],
+        shard_bytes: usize,
+    ) -> Vec<(usize, Box<[MaybeUninit<u8>]>)> {
+        indexes
+            .iter()
+            .map(|index| (*index, Box::new_uninit_slice(shard_bytes)))
+            .collect()
+    }
+
+    fn decode_into_uninit<T: AsRef<[u8]>, U: AsRef<[u8]>>(
+        decoder: &mut ReedSolomonDecoder,
+        originals: &[(usize, T)],
+        recovery: &[(usize, U)],
+        outputs: &mut [(usize, Box<[MaybeUninit<u8>]>)],
+    ) -> Result<(), Error> {
+        for (index, original) in originals {
+            decoder.add_original_shard(*index, original)?;
+        }
+        for (index, recovery) in recovery {
+            decoder.add_recovery_shard(*index, recovery)?;
+        }
+        let mut outputs: Vec<_> = outputs
+            .iter_mut()
+            .map(|(index, output)| (*index, &mut output[..]))
+            .collect();
+        decoder.decode_into_uninit(&mut outputs)
+    }
+
+    #[test]
+    fn decode_into_uninit_matches_decode() {
+        for (original_count, recovery_count) in [(3, 2), (2, 3), (5, 5)] {
+            let original = test_util::generate_original(original_count, 1024, 182);
+            let recovery = crate::encode(original_count, recovery_count, &original).unwrap();
+
+            let lost: Vec<_> = (0..original_count.min(recovery_count)).collect();
+            let originals: Vec<_> = (lost.len()..original_count)
+                .map(|index| (index, &original[index]))
+                .collect();
+            let recovery: Vec<_> = recovery.iter().take(lost.len()).enumerate().collect();
+
+            let mut decoder =
+                ReedSolomonDecoder::new(original_count, recovery_count, 1024).unwrap();
+            let expected = decoder
+                .decode_ref(&originals, &recovery)
+                .unwrap()
+                .into_owned();
+
+            let mut outputs = uninit_outputs(&lost, 1024);
+            decode_into_uninit(&mut decoder, &originals, &recovery, &mut outputs).unwrap();
+
+            for (index, output) in outputs {
+                // SAFETY: All bytes have been written by `decode_into_uninit`.
+                let output = unsafe { output.assume_init() };
+                assert_eq!(*output, *expected.restored_original(index).unwrap());
+                assert_eq!(*output, original[index]);
+            }
+        }
+    }
+
+    #[test]
+    fn decode_into_uninit_subset_of_missing() {
+        let original = test_util::generate_original(3, 64, 183);
+        let recovery = crate::encode(3, 2, &original).unwrap();
+
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 64).unwrap();
+        let mut outputs = uninit_outputs(&[2], 64);
+        decode_into_uninit(
+            &mut decoder,
+            &[(1, &original[1])],
+            &[(0, &recovery[0]), (1, &recovery[1])],
+            &mut outputs,
+        )
+        .unwrap();
+
+        // SAFETY: All bytes have been written by `decode_into_uninit`.
+        let output = unsafe { outputs.pop().unwrap().1.assume_init() };
+        assert_eq!(*output, original[2]);
+    }
+
+    #[test]
+    fn decode_into_uninit_errors() {
+        let original = test_util::generate_original(2, 64, 184);
+        let recovery = crate::encode(2, 2, &original).unwrap();
+        let mut decoder = ReedSolomonDecoder::new(2, 2, 64).unwrap();
+        let no_shards: &[(usize, [u8; 64])] = &[];
+
+        let mut outputs = vec![(0, Box::new_uninit_slice(128))];
+        assert_eq!(
+            decode_into_uninit(&mut decoder, &[(1, &original[1])], no_shards, &mut outputs),
+            Err(Error::InvalidOutputSize {
+                expected: 64,
+                got: 128
+            })
+        );
+
+        // Shards are kept on errors detected before decoding.
+        let mut outputs = uninit_outputs(&[2], 64);
+        assert_eq!(
+            decode_into_uninit(&mut decoder, no_shards, no_shards, &mut outputs),
+            Err(Error::InvalidOriginalShardIndex {
+                original_count: 2,
+                index: 2
+            })
+        );
+
+        for indexes in [&[1][..], &[0, 0]] {
+            let mut outputs = uninit_outputs(indexes, 64);
+            assert_eq!(
+                decode_into_uninit(&mut decoder, no_shards, no_shards, &mut outputs),
+                Err(Error::DuplicateOriginalShardIndex { index: indexes[0] })
+            );
+        }
+
+        let mut outputs = uninit_outputs(&[0], 64);
+        assert_eq!(
+            decode_into_uninit(&mut decoder, no_shards, no_shards, &mut outputs),
+            Err(Error::NotEnoughShards {
+                original_count: 2,
+                original_received_count: 1,
+                recovery_received_count: 0,
+            })
+        );
+
+        decode_into_uninit(&mut decoder, no_shards, &[(0, &recovery[0])], &mut outputs).unwrap();
+        // SAFETY: All bytes have been written by `decode_into_uninit`.
+        let output = unsafe { outputs.pop().unwrap().1.assume_init() };
+        assert_eq!(*output, original[0]);
+    }
+
+    // Untrusted shard used in place of requested missing original shard.
+    #[test]
+    fn decode_into_uninit_untrusted_original() {
+        let original = test_util::generate_original(2, 64, 185);
+        let recovery = crate::encode(2, 2, &original).unwrap();
+
+        let mut decoder = ReedSolomonDecoder::new(2, 2, 64).unwrap();
+        let no_shards: &[(usize, [u8; 64])] = &[];
+        decoder
+            .add_untrusted_shard(ShardKind::Original, 0, &original[0])
+            .unwrap();
+        let mut outputs = vec![(0, Box::new([MaybeUninit::new(7u8); 64]) as Box<[_]>)];
+        assert_eq!(
+            decode_into_uninit(&mut decoder, no_shards, &[(0, &recovery[0])], &mut outputs),
+            Err(Error::DuplicateOriginalShardIndex { index: 0 })
+        );
+
+        // Nothing is written on error.
+        // SAFETY: Buffer was initialized above.
+        let output = unsafe { outputs.pop().unwrap().1.assume_init() };
+        assert_eq!(*output, [7u8; 64]);
+    }
+
+    // ============================================================
+    // add_shard
+
+    #[test]
+    fn add_shard_mixed() {
+        let original = test_util::generate_original(4, 1024, 159);
+        let recovery = crate::encode(4, 3, &original).unwrap();
+
+        let shards = [
+            (ShardKind::Recovery, 2, &recovery[2]),
+            (ShardKind::Original, 1, &original[1]),
+            (ShardKind::Recovery, 0, &recovery[0]),
+            (ShardKind::Original, 3, &original[3]),
+        ];
+
+        let mut decoder = ReedSolomonDecoder::new(4, 3, 1024).unwrap();
+        for (kind, index, shard) in shards {
+            decoder.add_shard(kind, index, shard).unwrap();
+        }
+
+        assert_eq!(
+            decoder.add_shard(ShardKind::Recovery, 0, &recovery[0]),
+            Err(Error::DuplicateRecoveryShardIndex { index: 0 })
+        );
+
+        let result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(0).unwrap(), original[0]);
+        assert_eq!(result.restored_original(2).unwrap(), original[2]);
+        assert_eq!(result.restored_original(1), None);
+        assert_eq!(result.restored_original(3), None);
+    }
+
+    // ============================================================
+    // can_recover
+
+    #[test]
+    fn can_recover_at_tolerance() {
+        let original = test_util::generate_original(5, 64, 173);
+        let recovery = crate::encode(5, 3, &original).unwrap();
+
+        // 3 missing originals, exactly tolerated by 3 recovery shards.
+        let mut decoder = ReedSolomonDecoder::new(5, 3, 64).unwrap();
+        decoder.add_original_shard(1, &original[1]).unwrap();
+        decoder.add_original_shard(3, &original[3]).unwrap();
+        for (index, recovery) in recovery.iter().enumerate() {
+            assert!(!decoder.can_recover());
+            decoder.add_recovery_shard(index, recovery).unwrap();
+        }
+        assert!(decoder.can_recover());
+
+        let result = decoder.decode().unwrap();
+        for index in [0, 2, 4] {
+            assert_eq!(result.restored_original(index).unwrap(), original[index]);
+        }
+    }
+
+    #[test]
+    fn can_recover_over_tolerance() {
+        let original = test_util::generate_original(5, 64, 173);
+        let recovery = crate::encode(5, 3, &original).unwrap();
+
+        // 4 missing originals, one more than 3 recovery shards tolerate.
+        let mut decoder = ReedSolomonDecoder::new(5, 3, 64).unwrap();
+        decoder.add_original_shard(3, &original[3]).unwrap();
+        for (index, recovery) in recovery.iter().enumerate() {
+            decoder.add_recovery_shard(index, recovery).unwrap();
+        }
+        assert!(!decoder.can_recover());
+
+        assert_eq!(
+            decoder.decode().err(),
+            Some(Error::NotEnoughShards {
+                original_count: 5,
+                original_received_count: 1,
+                recovery_received_count: 3,
+            })
+        );
+    }
+
+    // ============================================================
+    // surplus recovery shards
+
+    #[test]
+    fn surplus_recovery_shards_not_copied() {
+        let original = test_util::generate_original(10, 64, 198);
+        let recovery = crate::encode(10, 30, &original).unwrap();
+
+        let mut decoder = ReedSolomonDecoder::new(10, 30, 64).unwrap();
+        // Two rounds to check that skipped shards are forgotten by decoding.
+        for _ in 0..2 {
+            // 5 originals and 30 recovery shards, i.e. 3.5x redundancy.
+            for (index, original) in original.iter().enumerate().skip(5) {
+                decoder.add_original_shard(index, original).unwrap();
+            }
+            for index in (0..30).rev() {
+                decoder.add_recovery_shard(index, &recovery[index]).unwrap();
+            }
+
+            // Only the first 5 recovery shards were copied.
+            let work = decoder.rate.work();
+            for index in 0..30 {
+                let copied = work.received_shard(ShardId::Recovery(index)).is_some();
+                assert_eq!(copied, index >= 25, "{}", index);
+            }
+
+            assert_eq!(
+                decoder.add_recovery_shard(3, &recovery[3]),
+                Err(Error::DuplicateRecoveryShardIndex { index: 3 })
+            );
+            assert_eq!(
+                decoder.add_recovery_shard_owned(3, recovery[3].clone()),
+                Err(Error::DuplicateRecoveryShardIndex { index: 3 })
+            );
+
+            let result = decoder.decode().unwrap();
+            assert_eq!(result.restored_original_iter().count(), 5);
+            for (index, original) in original.iter().enumerate().take(5) {
+                assert_eq!(result.restored_original(index).unwrap(), original);
+            }
+        }
+    }
+
+    #[test]
+    fn surplus_recovery_shard_is_validated() {
+        let original = test_util::generate_original(2, 64, 198);
+        let recovery = crate::encode(2, 3, &original).unwrap();
+
+        let mut decoder = ReedSolomonDecoder::new(2, 3, 64).unwrap();
+        decoder.add_original_shard(0, &original[0]).unwrap();
+        decoder.add_recovery_shard(0, &recovery[0]).unwrap();
+
+        assert_eq!(
+            decoder.add_recovery_shard(1, [0u8; 128]),
+            Err(Error::DifferentShardSize {
+                shard_bytes: 64,
+                got: 128,
+            })
+        );
+        assert_eq!(
+            decoder.add_recovery_shard(3, &recovery[0]),
+            Err(Error::InvalidRecoveryShardIndex {
+                recovery_count: 3,
+                index: 3,
+            })
+        );
+
+        // Rejected shard wasn't marked as added.
+        decoder.add_recovery_shard(1, &recovery[1]).unwrap();
+        let result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(1).unwrap(), original[1]);
+    }
+
+    // ============================================================
+    // NotEnoughShards
+
+    #[test]
+    fn decode_fails_only_with_not_enough_shards() {
+        use rand::{seq::index, Rng, SeedableRng};
+        use rand_chacha::ChaCha8Rng;
+
+        let mut rng = ChaCha8Rng::from_seed([200; 32]);
+        // both rates, partial and full chunks
+        for (original_count, recovery_count) in [
+            (1, 1),
+            (3, 5),
+            (100, 30),
+            (30, 100),
+            (1000, 1000),
+            (4096, 100),
+            (100, 4096),
+        ] {
+            let original = test_util::generate_original(original_count, 64, 201);
+            let recovery = crate::encode(original_count, recovery_count, &original).unwrap();
+            let mut decoder = ReedSolomonDecoder::new(original_count, recovery_count, 64).unwrap();
+
+            for _ in 0..20 {
+                // Around the boundary of just enough shards.
+                let original_received_count = rng
+                    .gen_range(original_count.saturating_sub(recovery_count + 1)..=original_count);
+                let missing = original_count - original_received_count;
+                let recovery_received_count = rng.gen_range(
+                    missing.saturating_sub(2)..=std::cmp::min(missing + 1, recovery_count),
+                );
+
+                for index in index::sample(&mut rng, original_count, original_received_count) {
+                    decoder.add_original_shard(index, &original[index]).unwrap();
+                }
+                for index in index::sample(&mut rng, recovery_count, recovery_received_count) {
+                    decoder.add_recovery_shard(index, &recovery[index]).unwrap();
+                }
+
+                let failed = match decoder.decode() {
+                    Ok(result) => {
+                        assert!(recovery_received_count >= missing);
+                        assert_eq!(result.restored_original_iter().count(), missing);
+                        for (index, restored) in result.restored_original_iter() {
+                            assert_eq!(restored, original[index]);
+                        }
+                        false
+                    }
+                    Err(err) => {
+                        assert_eq!(
+                            err,
+                            Error::NotEnoughShards {
+                                original_count,
+                                original_received_count,
+                                recovery_received_count,
+                            }
+                        );
+                        assert_eq!(
+                            err.needed_additional_shards(),
+                            Some(missing - recovery_received_count)
+                        );
+                        true
+                    }
+                };
+
+                // Shards are kept after failure.
+                if failed {
+                    decoder.reset(original_count, recovery_count, 64).unwrap();
+                }
+            }
+        }
+    }
+
+    // ============================================================
+    // decode_peeling
+
+    #[test]
+    fn decode_peeling_matches_decode() {
+        use rand::{seq::index, Rng, SeedableRng};
+        use rand_chacha::ChaCha8Rng;
+
+        let mut rng = ChaCha8Rng::from_seed([198; 32]);
+        // high rate, low rate, non-power-of-two and partial chunks
+        for (original_count, recovery_count) in [(100, 10), (10, 100), (3000, 40), (5, 5), (1, 1)] {
+            let original = test_util::generate_original(original_count, 128, 199);
+            let recovery = crate::encode(original_count, recovery_count, &original).unwrap();
+
+            let mut decoder = ReedSolomonDecoder::new(original_count, recovery_count, 128).unwrap();
+            for _ in 0..10 {
+                let missing_count = rng.gen_range(1..=std::cmp::min(3, recovery_count));
+                let missing = index::sample(&mut rng, original_count, missing_count).into_vec();
+                let recovery_used_count = rng.gen_range(missing_count..=recovery_count);
+                let recovery_used =
+                    index::sample(&mut rng, recovery_count, recovery_used_count).into_vec();
+
+                let mut restored = Vec::new();
+                for peeling in [false, true] {
+                    for (index, original) in original.iter().enumerate() {
+                        if !missing.contains(&index) {
+                            decoder.add_original_shard(index, original).unwrap();
+                        }
+                    }
+                    for &index in &recovery_used {
+                        decoder.add_recovery_shard(index, &recovery[index]).unwrap();
+                    }
+
+                    let result = if peeling {
+                        decoder.decode_peeling().unwrap()
+                    } else {
+                        decoder.decode().unwrap()
+                    };
+                    let shards: Vec<_> = result
+                        .restored_original_iter()
+                        .map(|(index, shard)| (index, shard.to_vec()))
+                        .collect();
+                    restored.push(shards);
+                }
+
+                assert_eq!(
+                    restored[0], restored[1],
+                    "{:?} {:?}",
+                    missing, recovery_used
+                );
+                assert_eq!(restored[1].len(), missing_count);
+                for (index, shard) in &restored[1] {
+                    assert_eq!(shard, &original[*index]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decode_peeling_not_enough_shards() {
+        let original = test_util::generate_original(3, 64, 200);
+        let recovery = crate::encode(3, 2, &original).unwrap();
+
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 64).unwrap();
+        decoder.add_original_shard(0, &original[0]).unwrap();
+        assert_eq!(
+            decoder.decode_peeling().err(),
+            Some(Error::NotEnoughShards {
+                original_count: 3,
+                original_received_count: 1,
+                recovery_received_count: 0,
+            })
+        );
+
+        // Added shards are kept.
+        decoder.add_recovery_shard(1, &recovery[1]).unwrap();
+        decoder.add_recovery_shard(0, &recovery[0]).unwrap();
+        let result = decoder.decode_peeling().unwrap();
+        assert_eq!(result.restored_original(1).unwrap(), original[1]);
+        assert_eq!(result.restored_original(2).unwrap(), original[2]);
+    }
+
+    #[test]
+    fn can_recover_counts_untrusted() {
+        let original = test_util::generate_original(2, 64, 173);
+        let recovery = crate::encode(2, 1, &original).unwrap();
+
+        let mut decoder = ReedSolomonDecoder::new(2, 1, 64).unwrap();
+        decoder.add_original_shard(0, &original[0]).unwrap();
+        // Already added as trusted, so doesn't count.
+        decoder
+            .add_untrusted_shard(ShardKind::Original, 0, &original[0])
+            .unwrap();
+        assert!(!decoder.can_recover());
+
+        decoder
+            .add_untrusted_shard(ShardKind::Recovery, 0, &recovery[0])
+            .unwrap();
+        assert!(decoder.can_recover());
+
+        let result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(1).unwrap(), original[1]);
+    }
+
+    // ============================================================
+    // decode_with_control
+
+    #[test]
+    fn decode_with_control_cancel_and_retry() {
+        for (original_count, recovery_count) in [(4, 3), (3, 5)] {
+            let original = test_util::generate_original(original_count, 1024, 165);
+            let recovery = crate::encode(original_count, recovery_count, &original).unwrap();
+
+            let mut decoder =
+                ReedSolomonDecoder::new(original_count, recovery_count, 1024).unwrap();
+            let add_shards = |decoder: &mut ReedSolomonDecoder| {
+                decoder.add_original_shard(1, &original[1]).unwrap();
+                for (index, shard) in recovery.iter().enumerate().take(3) {
+                    decoder.add_recovery_shard(index, shard).unwrap();
+                }
+            };
+
+            add_shards(&mut decoder);
+            let mut progress = Vec::new();
+            decoder
+                .decode_with_control(|p| {
+                    progress.push(p);
+                    ControlFlow::Continue(())
+                })
+                .unwrap();
+            assert_eq!(progress[0], 0.0);
+            assert!(progress.windows(2).all(|w| w[0] < w[1] && w[1] < 1.0));
+
+            for cancel_at in 0..progress.len() {
+                add_shards(&mut decoder);
+                let mut calls = 0;
+                let cancelled = decoder.decode_with_control(|_| {
+                    calls += 1;
+                    if calls > cancel_at {
+                        ControlFlow::Break(())
+                    } else {
+                        ControlFlow::Continue(())
+                    }
+                });
+                assert_eq!(cancelled.err(), Some(Error::Cancelled));
+
+                // Decoder was reset, so retrying needs the shards again.
+                assert_eq!(
+                    decoder.decode().err(),
+                    Some(Error::NotEnoughShards {
+                        original_count,
+                        original_received_count: 0,
+                        recovery_received_count: 0,
+                    })
+                );
+
+                add_shards(&mut decoder);
+                let result = decoder.decode().unwrap();
+                assert_eq!(result.restored_original(0).unwrap(), original[0]);
+                assert_eq!(result.restored_original(2).unwrap(), original[2]);
+            }
+        }
+    }
+
+    // ============================================================
+    // add_untrusted_shard
+
+    #[test]
+    fn corrupt_untrusted_shard_is_ignored() {
+        let original = test_util::generate_original(3, 1024, 161);
+        let recovery = crate::encode(3, 3, &original).unwrap();
+
+        let mut corrupt = recovery[2].clone();
+        corrupt[100] ^= 1;
+
+        let mut decoder = ReedSolomonDecoder::new(3, 3, 1024).unwrap();
+        decoder
+            .add_trusted_shard(ShardKind::Original, 0, &original[0])
+            .unwrap();
+        decoder
+            .add_trusted_shard(ShardKind::Recovery, 0, &recovery[0])
+            .unwrap();
+        decoder
+            .add_trusted_shard(ShardKind::Recovery, 1, &recovery[1])
+            .unwrap();
+        decoder
+            .add_untrusted_shard(ShardKind::Recovery, 2, &corrupt)
+            .unwrap();
+        decoder
+            .add_untrusted_shard(ShardKind::Original, 1, &original[1])
+            .unwrap();
+
+        let result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(1).unwrap(), original[1]);
+        assert_eq!(result.restored_original(2).unwrap(), original[2]);
+        assert!(result.untrusted_mismatches().is_empty());
+    }
+
+    #[test]
+    fn untrusted_shards_used_only_as_needed() {
+        let original = test_util::generate_original(3, 1024, 161);
+        let recovery = crate::encode(3, 3, &original).unwrap();
+
+        let mut corrupt = original[2].clone();
+        corrupt[0] ^= 1;
+
+        let mut decoder = ReedSolomonDecoder::new(3, 3, 1024).unwrap();
+        decoder
+            .add_trusted_shard(ShardKind::Recovery, 0, &recovery[0])
+            .unwrap();
+        decoder
+            .add_trusted_shard(ShardKind::Recovery, 1, &recovery[1])
+            .unwrap();
+        decoder
+            .add_untrusted_shard(ShardKind::Recovery, 0, &original[0])
+            .unwrap();
+        decoder
+            .add_untrusted_shard(ShardKind::Original, 2, &corrupt)
+            .unwrap();
+        decoder
+            .add_untrusted_shard(ShardKind::Original, 0, &original[0])
+            .unwrap();
+
+        // Original #0 is used, original #2 and recovery #0 are cross-checked.
+        let result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(0), None);
+        assert_eq!(result.restored_original(1).unwrap(), original[1]);
+        assert_eq!(result.restored_original(2).unwrap(), original[2]);
+        assert_eq!(
+            result.untrusted_mismatches(),
+            [ShardId::Original(2), ShardId::Recovery(0)]
+        );
+        drop(result);
+
+        // Untrusted shards are forgotten after decoding.
+        decoder
+            .add_trusted_shard(ShardKind::Original, 0, &original[0])
+            .unwrap();
+        decoder
+            .add_trusted_shard(ShardKind::Original, 1, &original[1])
+            .unwrap();
+        decoder
+            .add_trusted_shard(ShardKind::Recovery, 0, &recovery[0])
+            .unwrap();
+        let result = decoder.decode().unwrap();
+        assert!(result.untrusted_mismatches().is_empty());
+    }
+
+    #[test]
+    fn untrusted_shards_kept_if_not_enough_shards() {
+        let original = test_util::generate_original(3, 64, 179);
+        let recovery = crate::encode(3, 2, &original).unwrap();
+
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 64).unwrap();
+        decoder
+            .add_untrusted_shard(ShardKind::Recovery, 0, &recovery[0])
+            .unwrap();
+        assert_eq!(
+            decoder.decode().err(),
+            Some(Error::NotEnoughShards {
+                original_count: 3,
+                original_received_count: 0,
+                recovery_received_count: 0,
+            })
+        );
+
+        decoder.add_original_shard(0, &original[0]).unwrap();
+        decoder.add_recovery_shard(1, &recovery[1]).unwrap();
+        let result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(1).unwrap(), original[1]);
+        assert_eq!(result.restored_original(2).unwrap(), original[2]);
+    }
+
+    #[test]
+    fn add_untrusted_shard_errors() {
+        let mut decoder = ReedSolomonDecoder::new(2, 3, 64).unwrap();
+
+        assert_eq!(
+            decoder.add_untrusted_shard(ShardKind::Original, 2, [0; 64]),
+            Err(Error::InvalidOriginalShardIndex {
+                original_count: 2,
+                index: 2,
+            })
+        );
+        assert_eq!(
+            decoder.add_untrusted_shard(ShardKind::Recovery, 3, [0; 64]),
+            Err(Error::InvalidRecoveryShardIndex {
+                recovery_count: 3,
+                index: 3,
+            })
+        );
+        assert_eq!(
+            decoder.add_untrusted_shard(ShardKind::Recovery, 0, [0; 128]),
+            Err(Error::DifferentShardSize {
+                shard_bytes: 64,
+                got: 128,
+            })
+        );
+
+        decoder
+            .add_untrusted_shard(ShardKind::Original, 1, [0; 64])
+            .unwrap();
+        assert_eq!(
+            decoder.add_untrusted_shard(ShardKind::Original, 1, [0; 64]),
+            Err(Error::DuplicateOriginalShardIndex { index: 1 })
+        );
+
+        decoder
+            .add_untrusted_shard(ShardKind::Recovery, 2, [0; 64])
+            .unwrap();
+        assert_eq!(
+            decoder.add_untrusted_shard(ShardKind::Recovery, 2, [0; 64]),
+            Err(Error::DuplicateRecoveryShardIndex { index: 2 })
+        );
+    }
+
+    // ============================================================
+    // add_*_shard_owned
+
+    #[test]
+    fn owned_shards_used_only_as_needed() {
+        let original = test_util::generate_original(4, 1024, 167);
+        let recovery = crate::encode(4, 4, &original).unwrap();
+
+        let added = [
+            (ShardId::Original(1), original[1].clone()),
+            (ShardId::Original(3), original[3].clone()),
+            (ShardId::Recovery(0), recovery[0].clone()),
+            (ShardId::Recovery(1), recovery[1].clone()),
+            (ShardId::Recovery(3), recovery[3].clone()),
+        ];
+
+        let mut decoder = ReedSolomonDecoder::new(4, 4, 1024).unwrap();
+        decoder.add_original_shard(0, &original[0]).unwrap();
+        // Added in reverse order, which doesn't affect which are used.
+        for (id, shard) in added.iter().rev() {
+            match *id {
+                ShardId::Original(index) => decoder.add_original_shard_owned(index, shard.clone()),
+                ShardId::Recovery(index) => decoder.add_recovery_shard_owned(index, shard.clone()),
+            }
+            .unwrap();
+        }
+        assert!(decoder.can_recover());
+
+        let mut result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(2).unwrap(), original[2]);
+        assert_eq!(result.restored_original_iter().count(), 1);
+
+        let used = result.take_used_shards();
+        let unused = result.take_unused_shards();
+        assert_eq!(used, added[..3]);
+        assert_eq!(unused, added[3..]);
+        assert!(result.take_used_shards().is_empty());
+        assert!(result.take_unused_shards().is_empty());
+        drop(result);
+
+        // Owned shards are forgotten after decoding.
+        decoder.add_original_shard(0, &original[0]).unwrap();
+        assert!(!decoder.can_recover());
+    }
+
+    #[test]
+    fn owned_shards_unused_if_all_original_received() {
+        let original = test_util::generate_original(2, 64, 167);
+        let recovery = crate::encode(2, 2, &original).unwrap();
+
+        let mut decoder = ReedSolomonDecoder::new(2, 2, 64).unwrap();
+        decoder.add_original_shard(0, &original[0]).unwrap();
+        decoder.add_original_shard(1, &original[1]).unwrap();
+        decoder
+            .add_recovery_shard_owned(1, recovery[1].clone())
+            .unwrap();
+
+        let mut result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original_iter().count(), 0);
+        assert!(result.take_used_shards().is_empty());
+        assert_eq!(
+            result.take_unused_shards(),
+            [(ShardId::Recovery(1), recovery[1].clone())]
+        );
+    }
+
+    #[test]
+    fn owned_shards_kept_if_not_enough_shards() {
+        let original = test_util::generate_original(3, 64, 167);
+        let recovery = crate::encode(3, 2, &original).unwrap();
+
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 64).unwrap();
+        decoder.add_original_shard(0, &original[0]).unwrap();
+        decoder
+            .add_recovery_shard_owned(0, recovery[0].clone())
+            .unwrap();
+        assert!(matches!(
+            decoder.decode().err(),
+            Some(Error::NotEnoughShards { .. })
+        ));
+
+        decoder
+            .add_recovery_shard_owned(1, recovery[1].clone())
+            .unwrap();
+        let mut result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(1).unwrap(), original[1]);
+        assert_eq!(result.restored_original(2).unwrap(), original[2]);
+        assert_eq!(
+            result.take_used_shards(),
+            [
+                (ShardId::Recovery(0), recovery[0].clone()),
+                (ShardId::Recovery(1), recovery[1].clone()),
+            ]
+        );
+    }
+
+    #[test]
+    fn add_shard_owned_errors() {
+        let mut decoder = ReedSolomonDecoder::new(2, 3, 64).unwrap();
+
+        assert_eq!(
+            decoder.add_original_shard_owned(2, vec![0; 64]),
+            Err(Error::InvalidOriginalShardIndex {
+                original_count: 2,
+                index: 2,
+            })
+        );
+        assert_eq!(
+            decoder.add_recovery_shard_owned(3, vec![0; 64]),
+            Err(Error::InvalidRecoveryShardIndex {
+                recovery_count: 3,
+                index: 3,
+            })
+        );
+        assert_eq!(
+            decoder.add_recovery_shard_owned(0, vec![0; 128]),
+            Err(Error::DifferentShardSize {
+                shard_bytes: 64,
+                got: 128,
+            })
+        );
+
+        decoder.add_original_shard(0, [0; 64]).unwrap();
+        assert_eq!(
+            decoder.add_original_shard_owned(0, vec![0; 64]),
+            Err(Error::DuplicateOriginalShardIndex { index: 0 })
+        );
+
+        decoder.add_recovery_shard_owned(2, vec![0; 64]).unwrap();
+        assert_eq!(
+            decoder.add_recovery_shard_owned(2, vec![0; 64]),
+            Err(Error::DuplicateRecoveryShardIndex { index: 2 })
+        );
+    }
+
+    // ============================================================
+    // integrity footer
+
+    // Original shards with reserved bytes zeroed.
+    fn generate_original_reserved(count: usize, shard_bytes: usize, seed: u8) -> Vec<Vec<u8>> {
+        let mut original = test_util::generate_original(count, shard_bytes, seed);
+        for shard in &mut original {
+            shard[shard_bytes - integrity::RESERVED_BYTES..].fill(0);
+        }
+        original
+    }
+
+    fn encode_with_footer(
+        original: &[Vec<u8>],
+        recovery_count: usize,
+        stripe_id: Option<u64>,
+    ) -> Vec<Vec<u8>> {
+        let mut encoder =
+            ReedSolomonEncoder::new(original.len(), recovery_count, original[0].len()).unwrap();
+        encoder.set_integrity_footer(stripe_id);
+        for shard in original {
+            encoder.add_original_shard(shard).unwrap();
+        }
+        let result = encoder.encode().unwrap();
+        result.recovery_iter().map(|s| s.to_vec()).collect()
+    }
+
+    #[test]
+    fn integrity_footer_roundtrip_mixed() {
+        let original = generate_original_reserved(4, 1024, 163);
+        let recovery = encode_with_footer(&original, 3, Some(5));
+
+        let expected = IntegrityFooter::new(CodecParams::new(4, 3, 1024), 5);
+        for shard in &recovery {
+            assert_eq!(IntegrityFooter::from_shard(shard), Some(expected));
+        }
+
+        // Non-headered original shards together with headered recovery shards.
+        let mut decoder = ReedSolomonDecoder::new(4, 3, 1024)
+            .unwrap()
+            .with_integrity_footer(5);
+        decoder.add_original_shard(1, &original[1]).unwrap();
+        decoder.add_recovery_shard(0, &recovery[0]).unwrap();
+        decoder.add_recovery_shard(2, &recovery[2]).unwrap();
+        decoder
+            .add_untrusted_shard(ShardKind::Recovery, 1, &recovery[1])
+            .unwrap();
+
+        let result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(0).unwrap(), original[0]);
+        assert_eq!(result.restored_original(2).unwrap(), original[2]);
+        assert_eq!(result.restored_original(3).unwrap(), original[3]);
+        assert!(result.untrusted_mismatches().is_empty());
+    }
+
+    #[test]
+    fn integrity_footer_owned_shards_unchanged() {
+        let original = generate_original_reserved(2, 1024, 163);
+        let recovery = encode_with_footer(&original, 2, Some(5));
+
+        let mut decoder = ReedSolomonDecoder::new(2, 2, 1024)
+            .unwrap()
+            .with_integrity_footer(5);
+        decoder
+            .add_recovery_shard_owned(0, recovery[0].clone())
+            .unwrap();
+        decoder
+            .add_recovery_shard_owned(1, recovery[1].clone())
+            .unwrap();
+
+        let mut result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(0).unwrap(), original[0]);
+        assert_eq!(result.restored_original(1).unwrap(), original[1]);
+        // Footers are kept in returned shards.
+        assert_eq!(
+            result.take_used_shards(),
+            [
+                (ShardId::Recovery(0), recovery[0].clone()),
+                (ShardId::Recovery(1), recovery[1].clone()),
+            ]
+        );
+    }
+
+    #[test]
+    fn integrity_footer_mismatch() {
+        let original = generate_original_reserved(3, 1024, 163);
+        let headered = encode_with_footer(&original, 2, Some(5));
+        let plain = encode_with_footer(&original, 2, None);
+
+        let got = Some(IntegrityFooter::new(CodecParams::new(3, 2, 1024), 5));
+
+        // Different `shard_bytes`.
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 960)
+            .unwrap()
+            .with_integrity_footer(5);
+        assert_eq!(
+            decoder.add_recovery_shard(0, &headered[0]),
+            Err(Error::ParameterMismatch {
+                index: 0,
+                expected: IntegrityFooter::new(CodecParams::new(3, 2, 960), 5),
+                got,
+            })
+        );
+
+        // Different `stripe_id`.
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 1024)
+            .unwrap()
+            .with_integrity_footer(6);
+        let expected = IntegrityFooter::new(CodecParams::new(3, 2, 1024), 6);
+        assert_eq!(
+            decoder.add_recovery_shard(1, &headered[1]),
+            Err(Error::StripeMismatch {
+                index: 1,
+                expected: 6,
+                got: 5,
+            })
+        );
+
+        // Non-headered recovery shard.
+        assert_eq!(
+            decoder.add_recovery_shard(0, &plain[0]),
+            Err(Error::ParameterMismatch {
+                index: 0,
+                expected,
+                got: None,
+            })
+        );
+        assert_eq!(
+            decoder.add_untrusted_shard(ShardKind::Recovery, 0, &plain[0]),
+            Err(Error::ParameterMismatch {
+                index: 0,
+                expected,
+                got: None,
+            })
+        );
+
+        // Rejected shards are not added.
+        assert_eq!(
+            decoder.decode().err(),
+            Some(Error::NotEnoughShards {
+                original_count: 3,
+                original_received_count: 0,
+                recovery_received_count: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn integrity_footer_mixed_stripes() {
+        let original_5 = generate_original_reserved(3, 1024, 5);
+        let original_6 = generate_original_reserved(3, 1024, 6);
+        let recovery_5 = encode_with_footer(&original_5, 3, Some(5));
+        let recovery_6 = encode_with_footer(&original_6, 3, Some(u64::MAX));
+
+        let mut decoder = ReedSolomonDecoder::new(3, 3, 1024)
+            .unwrap()
+            .with_integrity_footer(5);
+        let mismatch = |index| {
+            Err(Error::StripeMismatch {
+                index,
+                expected: 5,
+                got: u64::MAX,
+            })
+        };
+
+        decoder.add_recovery_shard(0, &recovery_5[0]).unwrap();
+        assert_eq!(decoder.add_recovery_shard(1, &recovery_6[1]), mismatch(1));
+        assert_eq!(
+            decoder.add_recovery_shard_owned(1, recovery_6[1].clone()),
+            mismatch(1)
+        );
+        assert_eq!(
+            decoder.add_untrusted_shard(ShardKind::Recovery, 2, &recovery_6[2]),
+            mismatch(2)
+        );
+
+        // Rejected shards are not added, so decoding uses only stripe 5.
+        decoder.add_recovery_shard(1, &recovery_5[1]).unwrap();
+        decoder.add_original_shard(2, &original_5[2]).unwrap();
+        {
+            let result = decoder.decode().unwrap();
+            assert_eq!(result.restored_original(0).unwrap(), original_5[0]);
+            assert_eq!(result.restored_original(1).unwrap(), original_5[1]);
+        }
+
+        // Consistent stripe with id which doesn't fit in `u32`.
+        decoder.set_integrity_footer(Some(u64::MAX));
+        let received: Vec<_> = recovery_6.iter().enumerate().collect();
+        let result = decoder
+            .decode_ref(&[] as &[(usize, &[u8])], &received)
+            .unwrap();
+        for (index, original) in original_6.iter().enumerate() {
+            assert_eq!(result.restored_original(index).unwrap(), original);
+        }
+    }
+
+    #[test]
+    fn integrity_footer_shard_writer() {
+        use std::io::Write;
+
+        let original = generate_original_reserved(3, 1024, 163);
+        let recovery = encode_with_footer(&original, 2, Some(5));
+        let other_stripe = encode_with_footer(&original, 2, Some(6));
+        let plain = encode_with_footer(&original, 2, None);
+
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 1024)
+            .unwrap()
+            .with_integrity_footer(5);
+        let mut write = |index, shard: &[u8]| {
+            let mut writer = decoder.begin_recovery_shard(index)?;
+            writer.write_all(shard).unwrap();
+            writer.finish()
+        };
+
+        assert_eq!(
+            write(0, &other_stripe[0]),
+            Err(Error::StripeMismatch {
+                index: 0,
+                expected: 5,
+                got: 6,
+            })
+        );
+        assert!(matches!(
+            write(1, &plain[1]),
+            Err(Error::ParameterMismatch {
+                index: 1,
+                got: None,
+                ..
+            })
+        ));
+
+        // Rejected shards aren't added.
+        assert_eq!(write(0, &recovery[0]), Ok(()));
+        assert_eq!(write(1, &recovery[1]), Ok(()));
+        decoder.add_original_shard(2, &original[2]).unwrap();
+
+        let result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(0).unwrap(), original[0]);
+        assert_eq!(result.restored_original(1).unwrap(), original[1]);
+    }
+
+    #[test]
+    fn integrity_footer_ignored_by_plain_decoder() {
+        let original = generate_original_reserved(3, 1024, 163);
+        let headered = encode_with_footer(&original, 2, Some(5));
+
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 1024).unwrap();
+        decoder.add_original_shard(0, &original[0]).unwrap();
+        decoder.add_recovery_shard(0, &headered[0]).unwrap();
+        decoder.add_recovery_shard(1, &headered[1]).unwrap();
+
+        // Only reserved bytes are restored incorrectly.
+        let payload = 1024 - integrity::RESERVED_BYTES;
+        let result = decoder.decode().unwrap();
+        assert_eq!(
+            result.restored_original(1).unwrap()[..payload],
+            original[1][..payload]
+        );
+        assert_eq!(
+            result.restored_original(2).unwrap()[..payload],
+            original[2][..payload]
+        );
+    }
+
+    #[test]
+    fn integrity_footer_requires_zero_reserved_bytes() {
+        let mut original = generate_original_reserved(2, 128, 163);
+        original[1][127] = 1;
+
+        let mut encoder = ReedSolomonEncoder::new(2, 1, 128)
+            .unwrap()
+            .with_integrity_footer(0);
+        encoder.add_original_shard(&original[0]).unwrap();
+        assert_eq!(
+            encoder.add_original_shard(&original[1]),
+            Err(Error::NonZeroReservedBytes { index: 1 })
+        );
+
+        encoder.set_integrity_footer(None);
+        encoder.add_original_shard(&original[1]).unwrap();
+        encoder.encode().unwrap();
+    }
+
+    // ============================================================
+    // stripe hash
+
+    #[test]
+    #[cfg(feature = "stripe-hash")]
+    fn stripe_hash_roundtrip() {
+        // high rate, low rate
+        for (original_count, recovery_count) in [(5, 3), (3, 5)] {
+            let original = test_util::generate_original(original_count, 128, 167);
+
+            let mut encoder = ReedSolomonEncoder::new(original_count, recovery_count, 128).unwrap();
+            for shard in &original {
+                encoder.add_original_shard(shard).unwrap();
+            }
+            let (result, hash) = encoder.encode_with_stripe_hash().unwrap();
+            let recovery: Vec<_> = result.recovery_iter().map(|s| s.to_vec()).collect();
+            drop(result);
+            assert_eq!(hash, integrity::stripe_hash(&original));
+
+            let mut decoder = ReedSolomonDecoder::new(original_count, recovery_count, 128)
+                .unwrap()
+                .with_stripe_hash();
+
+            // Originals 0 and 2 lost, with and without untrusted shard.
+            for untrusted in [false, true] {
+                for index in [1, 3, 4].into_iter().filter(|&i| i < original_count) {
+                    decoder.add_original_shard(index, &original[index]).unwrap();
+                }
+                for (index, shard) in recovery.iter().enumerate() {
+                    decoder.add_recovery_shard(index, shard).unwrap();
+                }
+                if untrusted {
+                    decoder
+                        .add_untrusted_shard(ShardKind::Original, 1, &original[1])
+                        .unwrap();
+                }
+                assert_eq!(decoder.decode().unwrap().integrity_hash(), Some(hash));
+            }
+
+            // Corrupted recovery shard
+            let mut corrupted = recovery.clone();
+            corrupted[0][0] ^= 1;
+            for index in [1, 3, 4].into_iter().filter(|&i| i < original_count) {
+                decoder.add_original_shard(index, &original[index]).unwrap();
+            }
+            for (index, shard) in corrupted.iter().enumerate() {
+                decoder.add_recovery_shard(index, shard).unwrap();
+            }
+            let result = decoder.decode().unwrap();
+            assert!(result.integrity_hash().is_some());
+            assert_ne!(result.integrity_hash(), Some(hash));
+            drop(result);
+
+            // Not enabled
+            let mut decoder = ReedSolomonDecoder::new(original_count, recovery_count, 128).unwrap();
+            for index in [1, 3, 4].into_iter().filter(|&i| i < original_count) {
+                decoder.add_original_shard(index, &original[index]).unwrap();
+            }
+            for (index, shard) in recovery.iter().enumerate() {
+                decoder.add_recovery_shard(index, shard).unwrap();
+            }
+            assert_eq!(decoder.decode().unwrap().integrity_hash(), None);
+        }
+    }
+
+    // ============================================================
+    // deduplication
+
+    fn encode_with(encoder: &mut ReedSolomonEncoder, original: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        for shard in original {
+            encoder.add_original_shard(shard).unwrap();
+        }
+        let result = encoder.encode().unwrap();
+        result.recovery_iter().map(|s| s.to_vec()).collect()
+    }
+
+    #[test]
+    fn deduplication_doesnt_change_output() {
+        let mut original = test_util::generate_original(10, 1024, 165);
+        original[3] = original[1].clone();
+        original[5] = vec![0; 1024];
+        original[7] = vec![0; 1024];
+        original[9] = original[1].clone();
+
+        let mut encoder = ReedSolomonEncoder::new(10, 4, 1024).unwrap();
+        let expected = encode_with(&mut encoder, &original);
+
+        encoder.set_deduplication(true);
+        for _ in 0..2 {
+            for shard in &original {
+                encoder.add_original_shard(shard).unwrap();
+            }
+            let result = encoder.encode().unwrap();
+            assert_eq!(result.duplicate_originals(), [(3, 1), (7, 5), (9, 1)]);
+            let recovery: Vec<_> = result.recovery_iter().map(|s| s.to_vec()).collect();
+            assert_eq!(recovery, expected);
+        }
+
+        encoder.set_deduplication(false);
+        for shard in &original {
+            encoder.add_original_shard(shard).unwrap();
+        }
+        assert!(encoder.encode().unwrap().duplicate_originals().is_empty());
+    }
+
+    // ============================================================
+    // take_work / restore_work
+
+    #[test]
+    fn take_work_and_encode_work() {
+        // high rate, low rate
+        for (original_count, recovery_count) in [(5, 3), (3, 5)] {
+            let params = CodecParams::new(original_count, recovery_count, 128);
+            let engine = NoSimd::new();
+            let stripes: Vec<_> = (0..3)
+                .map(|seed| test_util::generate_original(original_count, 128, 177 + seed))
+                .collect();
+
+            let mut encoder = ReedSolomonEncoder::from_params(params)
+                .unwrap()
+                .with_deduplication();
+            let mut works = Vec::new();
+            for original in &stripes {
+                for shard in original {
+                    encoder.add_original_shard(shard).unwrap();
+                }
+                works.push(encoder.take_work().unwrap());
+
+                // Encoder continues with empty working space.
+                assert_eq!(
+                    encoder.encode().err(),
+                    Some(Error::TooFewOriginalShards {
+                        original_count,
+                        original_received_count: 0,
+                    })
+                );
+            }
+
+            for (original, work) in stripes.iter().zip(&mut works) {
+                let result = rate::encode_work(&engine, params, work).unwrap();
+                let recovery: Vec<_> = result.recovery_iter().map(|s| s.to_vec()).collect();
+                assert_eq!(
+                    recovery,
+                    crate::encode(original_count, recovery_count, original).unwrap()
+                );
+                assert!(result.duplicate_originals().is_empty());
+            }
+
+            // Restored working space is re-used with same configuration
+            // and deduplication, even if it was already encoded.
+            encoder.restore_work(works.pop().unwrap());
+            encoder.add_original_shard(&stripes[0][0]).unwrap();
+            let mut work = encoder.take_work().unwrap();
+            assert_eq!(
+                rate::encode_work(&engine, params, &mut work).err(),
+                Some(Error::TooFewOriginalShards {
+                    original_count,
+                    original_received_count: 1,
+                })
+            );
+            for _ in 0..original_count {
+                encoder.add_original_shard(&stripes[0][0]).unwrap();
+            }
+            let result = encoder.encode().unwrap();
+            assert_eq!(result.duplicate_originals().len(), original_count - 1);
+        }
     }
 
-    /// Resets decoder to given configuration.
-    ///
-    /// - Added shards are forgotten.
-    /// - Existing working space is re-used if it's large enough
-    ///   or re-allocated otherwise.
-    pub fn reset(
-        &mut self,
-        original_count: usize,
-        recovery_count: usize,
-        shard_bytes: usize,
-    ) -> Result<(), Error> {
-        self.0.reset(original_count, recovery_count, shard_bytes)
+    #[test]
+    fn take_work_with_integrity_footer() {
+        let params = CodecParams::new(4, 3, 1024);
+        let original = generate_original_reserved(4, 1024, 196);
+
+        let mut encoder = ReedSolomonEncoder::from_params(params)
+            .unwrap()
+            .with_integrity_footer(7);
+        for shard in &original {
+            encoder.add_original_shard(shard).unwrap();
+        }
+        let mut work = encoder.take_work().unwrap();
+        let result = rate::encode_work(&NoSimd::new(), params, &mut work).unwrap();
+        let recovery: Vec<_> = result.recovery_iter().map(|s| s.to_vec()).collect();
+        drop(result);
+        assert_eq!(recovery, encode_with_footer(&original, 3, Some(7)));
+
+        // Footer is written only to working space taken with footer enabled.
+        encoder.restore_work(work);
+        encoder.set_integrity_footer(None);
+        for shard in &original {
+            encoder.add_original_shard(shard).unwrap();
+        }
+        let mut work = encoder.take_work().unwrap();
+        let result = rate::encode_work(&NoSimd::new(), params, &mut work).unwrap();
+        let recovery: Vec<_> = result.recovery_iter().map(|s| s.to_vec()).collect();
+        assert_eq!(recovery, crate::encode(4, 3, &original).unwrap());
     }
 
-    /// Returns `true` if given `original_count` / `recovery_count`
-    /// combination is supported.
-    ///
-    /// # Examples
-    ///
-    /// ```rust
-    /// use reed_solomon_simd::ReedSolomonDecoder;
-    ///
-    /// assert_eq!(ReedSolomonDecoder::supports(60_000, 4_000), true);
-    /// assert_eq!(ReedSolomonDecoder::supports(60_000, 5_000), false);
-    /// ```
-    pub fn supports(original_count: usize, recovery_count: usize) -> bool {
-        DefaultRate::<DefaultEngine>::supports(original_count, recovery_count)
+    #[test]
+    fn encode_work_with_wrong_params() {
+        let mut encoder = ReedSolomonEncoder::new(2, 1, 64).unwrap();
+        encoder.add_original_shard([1u8; 64]).unwrap();
+        encoder.add_original_shard([2u8; 64]).unwrap();
+        let mut work = encoder.take_work().unwrap();
+        assert_eq!(
+            rate::encode_work(&NoSimd::new(), CodecParams::new(2, 1, 128), &mut work).err(),
+            Some(Error::WorkMismatch {
+                expected: CodecParams::new(2, 1, 64),
+                got: CodecParams::new(2, 1, 128),
+            })
+        );
     }
-}
 
-// ======================================================================
-// TESTS
+    // ============================================================
+    // encode_timed / decode_timed
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
+    #[test]
+    fn timed_phases() {
+        use std::time::{Duration, Instant};
 
-    use fixedbitset::FixedBitSet;
+        // High rate and low rate.
+        for (original_count, recovery_count) in [(1000, 100), (100, 1000)] {
+            let original = test_util::generate_original(original_count, 1024, 186);
 
-    use super::*;
-    use crate::test_util;
+            let mut encoder =
+                ReedSolomonEncoder::new(original_count, recovery_count, 1024).unwrap();
+            let expected = encode_with(&mut encoder, &original);
+            for shard in &original {
+                encoder.add_original_shard(shard).unwrap();
+            }
 
-    // ============================================================
-    // HELPERS
+            let start = Instant::now();
+            let (result, timings) = encoder.encode_timed().unwrap();
+            let elapsed = start.elapsed();
 
-    fn roundtrip(
-        encoder: &mut ReedSolomonEncoder,
-        decoder: &mut ReedSolomonDecoder,
-        original_count: usize,
-        recovery_hash: &str,
-        decoder_original: &[usize],
-        decoder_recovery: &[usize],
-        seed: u8,
-    ) {
-        let original = test_util::generate_original(original_count, 1024, seed);
+            let recovery: Vec<_> = result.recovery_iter().map(|s| s.to_vec()).collect();
+            assert_eq!(recovery, expected);
+            assert!(timings.ifft > Duration::ZERO);
+            assert!(timings.fft > Duration::ZERO);
+            assert_eq!(timings.eval_poly, Duration::ZERO);
+            assert_eq!(timings.multiply, Duration::ZERO);
+            assert_eq!(timings.formal_derivative, Duration::ZERO);
+            assert_eq!(timings.reveal, Duration::ZERO);
+            assert!(timings.total() <= elapsed);
 
-        for original in &original {
-            encoder.add_original_shard(original).unwrap();
+            let lost = std::cmp::min(original_count, recovery_count);
+            let mut decoder =
+                ReedSolomonDecoder::new(original_count, recovery_count, 1024).unwrap();
+            for index in lost..original_count {
+                decoder.add_original_shard(index, &original[index]).unwrap();
+            }
+            for (index, shard) in recovery.iter().enumerate().take(lost) {
+                decoder
+                    .add_recovery_shard_owned(index, shard.clone())
+                    .unwrap();
+            }
+
+            let start = Instant::now();
+            let (result, timings) = decoder.decode_timed().unwrap();
+            let elapsed = start.elapsed();
+
+            for (index, original) in original.iter().enumerate().take(lost) {
+                assert_eq!(result.restored_original(index).unwrap(), original);
+            }
+            for phase in [
+                timings.copy_in,
+                timings.eval_poly,
+                timings.multiply,
+                timings.ifft,
+                timings.formal_derivative,
+                timings.fft,
+                timings.reveal,
+            ] {
+                assert!(phase > Duration::ZERO, "{:?}", timings);
+            }
+            assert!(timings.total() <= elapsed);
         }
+    }
 
-        let result = encoder.encode().unwrap();
-        let recovery: Vec<_> = result.recovery_iter().collect();
+    // ============================================================
+    // save_work / load_work
 
-        test_util::assert_hash(&recovery, recovery_hash);
+    #[test]
+    fn save_load_roundtrip() {
+        // High rate and low rate.
+        for (original_count, recovery_count) in [(100, 30), (30, 100)] {
+            let original = test_util::generate_original(original_count, 1024, 187);
+            let recovery = crate::encode(original_count, recovery_count, &original).unwrap();
+            let lost = std::cmp::min(original_count, recovery_count);
 
-        let mut original_received = FixedBitSet::with_capacity(original_count);
+            // Half of the needed shards before saving.
+            let mut decoder =
+                ReedSolomonDecoder::new(original_count, recovery_count, 1024).unwrap();
+            for index in lost..original_count {
+                decoder.add_original_shard(index, &original[index]).unwrap();
+            }
+            for (index, recovery) in recovery.iter().enumerate().take(lost / 2) {
+                decoder.add_recovery_shard(index, recovery).unwrap();
+            }
+            let mut saved = Vec::new();
+            decoder.save_work(&mut saved).unwrap();
 
-        for i in decoder_original {
-            decoder.add_original_shard(*i, &original[*i]).unwrap();
-            original_received.set(*i, true);
-        }
+            // Loading into a decoder which has other shards replaces them.
+            let mut decoder =
+                ReedSolomonDecoder::new(original_count, recovery_count, 1024).unwrap();
+            decoder
+                .add_recovery_shard(lost / 2, &recovery[lost / 2])
+                .unwrap();
+            decoder.load_work(saved.as_slice()).unwrap();
+            for (index, recovery) in recovery.iter().enumerate().take(lost).skip(lost / 2) {
+                decoder.add_recovery_shard(index, recovery).unwrap();
+            }
 
-        for i in decoder_recovery {
-            decoder.add_recovery_shard(*i, recovery[*i]).unwrap();
+            let result = decoder.decode().unwrap();
+            let restored: HashMap<_, _> = result.restored_original_iter().collect();
+            assert_eq!(restored.len(), lost);
+            for (index, original) in original.iter().enumerate().take(lost) {
+                assert_eq!(restored[&index], original);
+            }
         }
+    }
 
-        let result = decoder.decode().unwrap();
-        let restored: HashMap<_, _> = result.restored_original_iter().collect();
+    #[test]
+    fn save_load_across_rates() {
+        let original = test_util::generate_original(3, 1024, 187);
+        let recovery = crate::encode(3, 3, &original).unwrap();
 
-        for i in 0..original_count {
-            if !original_received[i] {
-                assert_eq!(restored[&i], original[i]);
-            }
+        let mut decoder = HighRateDecoder::new(3, 3, 1024, NoSimd::new(), None).unwrap();
+        decoder.add_original_shard(1, &original[1]).unwrap();
+        decoder.add_recovery_shard(2, &recovery[2]).unwrap();
+        let mut saved = Vec::new();
+        decoder.work().save(&mut saved).unwrap();
+
+        let mut decoder = LowRateDecoder::new(3, 3, 1024, NoSimd::new(), None).unwrap();
+        decoder.work_mut().load(saved.as_slice()).unwrap();
+
+        let mut saved_again = Vec::new();
+        decoder.work().save(&mut saved_again).unwrap();
+        assert_eq!(saved_again, saved);
+    }
+
+    #[test]
+    fn load_errors() {
+        let original = test_util::generate_original(3, 64, 187);
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 64).unwrap();
+        decoder.add_original_shard(0, &original[0]).unwrap();
+        decoder.add_original_shard(2, &original[2]).unwrap();
+        let mut saved = Vec::new();
+        decoder.save_work(&mut saved).unwrap();
+
+        // 4 + 4 bytes header, 3 * 8 bytes configuration, then
+        // original count and two original shards, and recovery count.
+        assert_eq!(saved.len(), 8 + 24 + 8 + 2 * (8 + 64) + 8);
+
+        let not_enough_shards = Err(Error::NotEnoughShards {
+            original_count: 3,
+            original_received_count: 0,
+            recovery_received_count: 0,
+        });
+
+        // Truncated
+        for len in [0, 3, 8, 31, 40, 100, saved.len() - 1] {
+            decoder.add_original_shard(1, &original[1]).unwrap();
+            assert!(
+                matches!(decoder.load_work(&saved[..len]), Err(LoadError::Truncated)),
+                "{len}"
+            );
+            assert_eq!(decoder.decode().map(|_| ()), not_enough_shards);
         }
+
+        // InvalidFormat
+        let mut corrupted = saved.clone();
+        corrupted[0] = b'X';
+        assert!(matches!(
+            decoder.load_work(corrupted.as_slice()),
+            Err(LoadError::InvalidFormat)
+        ));
+
+        // UnsupportedVersion
+        let mut corrupted = saved.clone();
+        corrupted[4] = 2;
+        assert!(matches!(
+            decoder.load_work(corrupted.as_slice()),
+            Err(LoadError::UnsupportedVersion { version: 2 })
+        ));
+
+        // ParameterMismatch
+        let mut other = ReedSolomonDecoder::new(3, 3, 64).unwrap();
+        assert!(matches!(
+            other.load_work(saved.as_slice()),
+            Err(LoadError::ParameterMismatch { expected, got })
+                if expected == CodecParams::new(3, 3, 64) && got == CodecParams::new(3, 2, 64)
+        ));
+
+        // Codec, i.e. both original shards with index 0.
+        let mut corrupted = saved.clone();
+        corrupted[48 + 64..48 + 64 + 8].fill(0);
+        assert!(matches!(
+            decoder.load_work(corrupted.as_slice()),
+            Err(LoadError::Codec(Error::DuplicateOriginalShardIndex {
+                index: 0
+            }))
+        ));
+        assert_eq!(decoder.decode().map(|_| ()), not_enough_shards);
+
+        // Decoder is still usable.
+        decoder.load_work(saved.as_slice()).unwrap();
+        decoder.add_original_shard(1, &original[1]).unwrap();
+        assert!(decoder
+            .decode()
+            .unwrap()
+            .restored_original_iter()
+            .next()
+            .is_none());
     }
 
     // ============================================================
-    // ROUNDTRIP - TWO ROUNDS
+    // shrink_to_fit
 
     #[test]
-    fn roundtrip_two_rounds_reset_low_to_high() {
-        let mut encoder = ReedSolomonEncoder::new(2, 3, 1024).unwrap();
-        let mut decoder = ReedSolomonDecoder::new(2, 3, 1024).unwrap();
+    fn shrink_to_fit_after_large_stripe() {
+        let original = test_util::generate_original(1000, 1024, 162);
+
+        let mut encoder = ReedSolomonEncoder::new(1000, 100, 1024).unwrap();
+        for original in &original {
+            encoder.add_original_shard(original).unwrap();
+        }
+        encoder.encode().unwrap();
 
+        let mut decoder = ReedSolomonDecoder::new(1000, 100, 1024).unwrap();
+        let large = (encoder.allocated_bytes(), decoder.allocated_bytes());
+
+        encoder.reset(2, 3, 1024).unwrap();
+        decoder.reset(2, 3, 1024).unwrap();
+        assert_eq!(
+            (encoder.allocated_bytes(), decoder.allocated_bytes()),
+            large
+        );
+
+        encoder.shrink_to_fit();
+        decoder.shrink_to_fit();
+        assert!(encoder.allocated_bytes() < large.0);
+        assert!(decoder.allocated_bytes() < large.1);
+
+        // Still works after shrinking, and grows back when needed.
         roundtrip(
             &mut encoder,
             &mut decoder,
@@ -256,18 +4149,394 @@ mod tests {
             123,
         );
 
-        encoder.reset(3, 2, 1024).unwrap();
-        decoder.reset(3, 2, 1024).unwrap();
+        encoder.reset(1000, 100, 1024).unwrap();
+        assert_eq!(encoder.allocated_bytes(), large.0);
+    }
 
-        roundtrip(
-            &mut encoder,
-            &mut decoder,
-            3,
-            test_util::HIGH_3_2,
-            &[1],
-            &[0, 1],
-            132,
+    // ============================================================
+    // with_capacity
+
+    // Counts allocations of each thread separately,
+    // so that tests running in parallel don't interfere.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static COUNTING_ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    #[test]
+    fn reset_within_capacity_does_not_allocate() {
+        let mut encoder = ReedSolomonEncoder::with_capacity(1000, 100, 1024).unwrap();
+        let allocated = encoder.allocated_bytes();
+        let allocations = ALLOCATIONS.with(Cell::get);
+
+        // Both rates, switching between them.
+        for (original_count, recovery_count, shard_bytes) in [
+            (1000, 100, 1024),
+            (10, 100, 1024),
+            (1, 100, 1024),
+            (1000, 1, 1024),
+            (3, 5, 64),
+            (600, 90, 512),
+            (64, 65, 1024),
+        ] {
+            encoder
+                .reset(original_count, recovery_count, shard_bytes)
+                .unwrap();
+        }
+
+        assert_eq!(ALLOCATIONS.with(Cell::get), allocations);
+        assert_eq!(encoder.allocated_bytes(), allocated);
+
+        // Still works.
+        let original = test_util::generate_original(2, 1024, 123);
+        encoder.reset(2, 3, 1024).unwrap();
+        assert_eq!(encode_with(&mut encoder, &original), {
+            let mut plain = ReedSolomonEncoder::new(2, 3, 1024).unwrap();
+            encode_with(&mut plain, &original)
+        });
+    }
+
+    #[test]
+    fn repeated_decode_does_not_allocate() {
+        let original = test_util::generate_original(3, 1024, 175);
+        let recovery = crate::encode(3, 2, &original).unwrap();
+
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 1024).unwrap();
+        let decode = |decoder: &mut ReedSolomonDecoder| {
+            decoder.add_original_shard(1, &original[1]).unwrap();
+            decoder.add_recovery_shard(0, &recovery[0]).unwrap();
+            decoder.add_recovery_shard(1, &recovery[1]).unwrap();
+            let result = decoder.decode().unwrap();
+            assert_eq!(result.restored_original(0).unwrap(), original[0]);
+            assert_eq!(result.restored_original(2).unwrap(), original[2]);
+        };
+
+        // First decoding allocates erasure locations and initializes tables.
+        let allocated = decoder.allocated_bytes();
+        decode(&mut decoder);
+        assert_eq!(decoder.allocated_bytes(), allocated + 2 * GF_ORDER);
+
+        let allocations = ALLOCATIONS.with(Cell::get);
+        for _ in 0..3 {
+            decode(&mut decoder);
+        }
+        assert_eq!(ALLOCATIONS.with(Cell::get), allocations);
+    }
+
+    #[test]
+    fn recycled_work_is_not_reallocated() {
+        let original = test_util::generate_original(3, 1024, 199);
+        let expected = crate::encode(3, 2, &original).unwrap();
+
+        let mut encoder = ReedSolomonEncoder::with_capacity(1000, 100, 1024)
+            .unwrap()
+            .with_deduplication();
+        encoder.add_original_shard(&original[0]).unwrap();
+        let allocated = encoder.allocated_bytes();
+
+        let allocations = ALLOCATIONS.with(Cell::get);
+        let work = encoder.into_recycled();
+        let mut encoder = ReedSolomonEncoder::from_recycled(3, 2, 1024, work).unwrap();
+        // Only `DefaultEngine` is boxed.
+        assert!(ALLOCATIONS.with(Cell::get) <= allocations + 1);
+        assert_eq!(encoder.allocated_bytes(), allocated);
+
+        // Earlier shard is forgotten and deduplication is disabled.
+        for original in [&original[0], &original[1], &original[2]] {
+            encoder.add_original_shard(original).unwrap();
+        }
+        let result = encoder.encode().unwrap();
+        assert_eq!(result.recovery_iter().collect::<Vec<_>>(), refs(&expected));
+        assert!(result.duplicate_originals().is_empty());
+        drop(result);
+
+        let mut decoder = ReedSolomonDecoder::new(3, 2, 1024).unwrap();
+        decoder.add_original_shard(1, &original[1]).unwrap();
+        let allocated = decoder.allocated_bytes();
+
+        let allocations = ALLOCATIONS.with(Cell::get);
+        let work = decoder.into_recycled();
+        let mut decoder = ReedSolomonDecoder::from_recycled(3, 2, 1024, work).unwrap();
+        assert!(ALLOCATIONS.with(Cell::get) <= allocations + 1);
+        assert_eq!(decoder.allocated_bytes(), allocated);
+
+        decoder.add_original_shard(1, &original[1]).unwrap();
+        decoder.add_recovery_shard(0, &expected[0]).unwrap();
+        decoder.add_recovery_shard(1, &expected[1]).unwrap();
+        let result = decoder.decode().unwrap();
+        assert_eq!(result.restored_original(0).unwrap(), original[0]);
+        assert_eq!(result.restored_original(2).unwrap(), original[2]);
+    }
+
+    #[test]
+    fn alternating_take_work_does_not_allocate() {
+        let original = test_util::generate_original(3, 1024, 181);
+        let params = CodecParams::new(3, 2, 1024);
+        let engine = NoSimd::new();
+        let mut encoder = ReedSolomonEncoder::from_params(params).unwrap();
+
+        let round = |encoder: &mut ReedSolomonEncoder| {
+            for shard in &original {
+                encoder.add_original_shard(shard).unwrap();
+            }
+            let mut work = encoder.take_work().unwrap();
+            rate::encode_work(&engine, params, &mut work).unwrap();
+            encoder.restore_work(work);
+        };
+
+        // First round allocates the second working space.
+        round(&mut encoder);
+
+        let allocations = ALLOCATIONS.with(Cell::get);
+        for _ in 0..3 {
+            round(&mut encoder);
+        }
+        assert_eq!(ALLOCATIONS.with(Cell::get), allocations);
+    }
+
+    // ============================================================
+    // RateEncoder / RateDecoder
+
+    fn generic_roundtrip<E: Engine, RE: RateEncoder<E>, RD: RateDecoder<E>>(
+        encoder: &mut RE,
+        decoder: &mut RD,
+        original: &[Vec<u8>],
+    ) -> Vec<Vec<u8>> {
+        for shard in original {
+            encoder.add_original_shard(shard).unwrap();
+        }
+        let result = encoder.encode().unwrap();
+
+        decoder.add_original_shard(1, &original[1]).unwrap();
+        decoder
+            .add_recovery_shard(0, result.recovery(0).unwrap())
+            .unwrap();
+        decoder
+            .add_recovery_shard(2, result.recovery(2).unwrap())
+            .unwrap();
+
+        let result = decoder.decode().unwrap();
+        result
+            .restored_original_iter()
+            .map(|(_, shard)| shard.to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn generic_over_rate_traits() {
+        let original = test_util::generate_original(3, 1024, 162);
+
+        let mut encoder = ReedSolomonEncoder::new(3, 3, 1024).unwrap();
+        let mut decoder = ReedSolomonDecoder::new(3, 3, 1024).unwrap();
+        let simple = generic_roundtrip(&mut encoder, &mut decoder, &original);
+
+        let (engine, work) = RateEncoder::into_parts(encoder);
+        let mut encoder = DefaultRateEncoder::new(3, 3, 1024, engine, Some(work)).unwrap();
+        let (engine, work) = RateDecoder::into_parts(decoder);
+        let mut decoder = DefaultRateDecoder::new(3, 3, 1024, engine, Some(work)).unwrap();
+        let rate = generic_roundtrip(&mut encoder, &mut decoder, &original);
+
+        assert_eq!(simple, [original[0].clone(), original[2].clone()]);
+        assert_eq!(simple, rate);
+    }
+
+    // ==================================================
+    // from_params
+
+    #[test]
+    fn from_params() {
+        assert!(ReedSolomonEncoder::from_params(CodecParams::new(2, 3, 1024)).is_ok());
+        assert!(ReedSolomonDecoder::from_params(CodecParams::new(2, 3, 1024)).is_ok());
+
+        assert_eq!(
+            ReedSolomonEncoder::from_params(CodecParams::new(2, 3, 100)).err(),
+            Some(Error::InvalidShardSize { shard_bytes: 100 })
         );
+        assert_eq!(
+            ReedSolomonDecoder::from_params(CodecParams::new(0, 3, 1024)).err(),
+            Some(Error::UnsupportedShardCount {
+                original_count: 0,
+                recovery_count: 3,
+                reason: UnsupportedReason::NoOriginalShards,
+                hint: None,
+            })
+        );
+    }
+
+    // ==================================================
+    // indexes beyond GF_ORDER
+
+    // Indexes are validated against counts before any arithmetic,
+    // so they are never truncated to `GfElement` or overflow.
+    #[test]
+    fn large_indexes() {
+        let original = test_util::generate_original(3, 64, 174);
+        let recovery = crate::encode(3, 2, &original).unwrap();
+
+        for index in [65535, 65536, 65536 + 1, u32::MAX as usize + 1, usize::MAX] {
+            let mut decoder = ReedSolomonDecoder::new(3, 2, 64).unwrap();
+
+            assert_eq!(
+                decoder.add_original_shard(index, &original[0]),
+                Err(Error::InvalidOriginalShardIndex {
+                    original_count: 3,
+                    index
+                })
+            );
+            assert_eq!(
+                decoder.add_recovery_shard(index, &recovery[0]),
+                Err(Error::InvalidRecoveryShardIndex {
+                    recovery_count: 2,
+                    index
+                })
+            );
+            assert_eq!(
+                decoder
+                    .add_untrusted_shard(ShardKind::Original, index, &original[0])
+                    .err(),
+                Some(Error::InvalidOriginalShardIndex {
+                    original_count: 3,
+                    index
+                })
+            );
+            assert_eq!(
+                decoder
+                    .add_untrusted_shard(ShardKind::Recovery, index, &recovery[0])
+                    .err(),
+                Some(Error::InvalidRecoveryShardIndex {
+                    recovery_count: 2,
+                    index
+                })
+            );
+            assert_eq!(
+                decoder.add_shard(ShardKind::Recovery, index, &recovery[0]),
+                Err(Error::InvalidRecoveryShardIndex {
+                    recovery_count: 2,
+                    index
+                })
+            );
+            assert_eq!(
+                decoder.add_original_shard_owned(index, original[0].clone()),
+                Err(Error::InvalidOriginalShardIndex {
+                    original_count: 3,
+                    index
+                })
+            );
+            assert_eq!(
+                decoder.add_recovery_shard_owned(index, recovery[0].clone()),
+                Err(Error::InvalidRecoveryShardIndex {
+                    recovery_count: 2,
+                    index
+                })
+            );
+            assert!(decoder.begin_original_shard(index).is_err());
+            assert!(decoder.begin_recovery_shard(index).is_err());
+
+            decoder.add_original_shard(1, &original[1]).unwrap();
+            decoder.add_recovery_shard(0, &recovery[0]).unwrap();
+            decoder.add_recovery_shard(1, &recovery[1]).unwrap();
+            let result = decoder.decode().unwrap();
+            assert_eq!(result.restored_original(index), None);
+            assert_eq!(result.restored_original(0).unwrap(), original[0]);
+        }
+
+        let mut encoder = ReedSolomonEncoder::new(3, 2, 64).unwrap();
+        for original in &original {
+            encoder.add_original_shard(original).unwrap();
+        }
+        let result = encoder.encode().unwrap();
+        assert_eq!(result.recovery(65536), None);
+        assert_eq!(result.recovery(usize::MAX), None);
+    }
+
+    // ==================================================
+    // indexes in padding
+
+    // Indexes between shard count and next power of two are zero padding
+    // of the transform, so they are rejected like any other index
+    // and don't affect decoding of the real shards.
+    #[test]
+    fn padding_indexes() {
+        // high rate, low rate
+        for (original_count, recovery_count) in [(5, 3), (3, 5)] {
+            let counts = format!("{original_count}:{recovery_count}");
+            let original = test_util::generate_original(original_count, 64, 191);
+
+            let mut encoder = ReedSolomonEncoder::new(original_count, recovery_count, 64).unwrap();
+            for original in &original {
+                encoder.add_original_shard(original).unwrap();
+            }
+            assert_eq!(
+                encoder.add_original_shard(&original[0]),
+                Err(Error::TooManyOriginalShards { original_count }),
+                "{counts}"
+            );
+            let recovery: Vec<_> = encoder
+                .encode()
+                .unwrap()
+                .recovery_iter()
+                .map(|shard| shard.to_vec())
+                .collect();
+            assert_eq!(
+                recovery,
+                crate::encode(original_count, recovery_count, &original).unwrap(),
+                "{counts}"
+            );
+
+            let mut decoder = ReedSolomonDecoder::new(original_count, recovery_count, 64).unwrap();
+            for index in original_count..original_count.next_power_of_two() {
+                assert_eq!(
+                    decoder.add_original_shard(index, &original[0]),
+                    Err(Error::InvalidOriginalShardIndex {
+                        original_count,
+                        index
+                    }),
+                    "{counts}"
+                );
+            }
+            for index in recovery_count..recovery_count.next_power_of_two() {
+                assert_eq!(
+                    decoder.add_recovery_shard(index, &recovery[0]),
+                    Err(Error::InvalidRecoveryShardIndex {
+                        recovery_count,
+                        index
+                    }),
+                    "{counts}"
+                );
+            }
+
+            // Last real indexes, losing first original shards.
+            let lost = original_count.min(recovery_count);
+            for (index, original) in original.iter().enumerate().skip(lost) {
+                decoder.add_original_shard(index, original).unwrap();
+            }
+            for (index, recovery) in recovery.iter().enumerate().skip(recovery_count - lost) {
+                decoder.add_recovery_shard(index, recovery).unwrap();
+            }
+            let result = decoder.decode().unwrap();
+            for (index, original) in original.iter().enumerate().take(lost) {
+                assert_eq!(
+                    result.restored_original(index).unwrap(),
+                    original,
+                    "{counts}"
+                );
+            }
+        }
     }
 
     // ==================================================
@@ -281,4 +4550,126 @@ mod tests {
         assert!(ReedSolomonDecoder::supports(4096, 61440));
         assert!(ReedSolomonDecoder::supports(61440, 4096));
     }
+
+    // ==================================================
+    // max_original_count / max_recovery_count
+
+    #[test]
+    fn max_counts() {
+        for count in [1, 3, 1000, 4096, 4097, 32769, 65535] {
+            let max = ReedSolomonEncoder::max_original_count(count);
+            assert_eq!(ReedSolomonEncoder::max_recovery_count(count), max);
+            assert_eq!(ReedSolomonDecoder::max_original_count(count), max);
+            assert_eq!(ReedSolomonDecoder::max_recovery_count(count), max);
+
+            assert!(ReedSolomonEncoder::new(max, count, 64).is_ok());
+            assert!(ReedSolomonEncoder::new(count, max, 64).is_ok());
+            assert!(ReedSolomonDecoder::new(max, count, 64).is_ok());
+            assert!(ReedSolomonDecoder::new(count, max, 64).is_ok());
+
+            assert!(matches!(
+                ReedSolomonEncoder::new(max + 1, count, 64),
+                Err(Error::UnsupportedShardCount { .. })
+            ));
+            assert!(matches!(
+                ReedSolomonEncoder::new(count, max + 1, 64),
+                Err(Error::UnsupportedShardCount { .. })
+            ));
+            assert!(matches!(
+                ReedSolomonDecoder::new(max + 1, count, 64),
+                Err(Error::UnsupportedShardCount { .. })
+            ));
+            assert!(matches!(
+                ReedSolomonDecoder::new(count, max + 1, 64),
+                Err(Error::UnsupportedShardCount { .. })
+            ));
+        }
+
+        assert_eq!(ReedSolomonEncoder::max_original_count(0), 0);
+        assert_eq!(ReedSolomonEncoder::max_original_count(65536), 0);
+    }
+
+    // ============================================================
+    // new_in
+
+    #[cfg(feature = "allocator-api")]
+    mod new_in {
+        use std::{
+            alloc::{AllocError, Allocator, Global, Layout},
+            ptr::NonNull,
+            sync::{
+                atomic::{AtomicUsize, Ordering},
+                Arc,
+            },
+        };
+
+        use super::*;
+
+        #[derive(Clone, Default)]
+        struct TrackingAllocator {
+            allocated: Arc<AtomicUsize>,
+            in_use: Arc<AtomicUsize>,
+        }
+
+        unsafe impl Allocator for TrackingAllocator {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                self.allocated.fetch_add(layout.size(), Ordering::SeqCst);
+                self.in_use.fetch_add(layout.size(), Ordering::SeqCst);
+                Global.allocate(layout)
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                self.in_use.fetch_sub(layout.size(), Ordering::SeqCst);
+                unsafe { Global.deallocate(ptr, layout) }
+            }
+        }
+
+        #[test]
+        fn encoder_uses_given_allocator() {
+            let alloc = TrackingAllocator::default();
+            let original = test_util::generate_original(1000, 1024, 159);
+
+            let mut encoder = ReedSolomonEncoder::new_in(1000, 100, 1024, alloc.clone()).unwrap();
+            for original in &original {
+                encoder.add_original_shard(original).unwrap();
+            }
+            let recovery: Vec<_> = encoder
+                .encode()
+                .unwrap()
+                .recovery_iter()
+                .map(|s| s.to_vec())
+                .collect();
+            assert_eq!(recovery, crate::encode(1000, 100, &original).unwrap());
+
+            // Working space is at least all original shards.
+            assert!(alloc.in_use.load(Ordering::SeqCst) >= 1000 * 1024);
+
+            // Growing the working space also goes through the allocator.
+            encoder.reset(2000, 100, 1024).unwrap();
+            assert!(alloc.allocated.load(Ordering::SeqCst) >= 3000 * 1024);
+
+            drop(encoder);
+            assert_eq!(alloc.in_use.load(Ordering::SeqCst), 0);
+        }
+
+        #[test]
+        fn decoder_uses_given_allocator() {
+            let alloc = TrackingAllocator::default();
+            let original = test_util::generate_original(100, 1024, 159);
+            let recovery = crate::encode(100, 1000, &original).unwrap();
+
+            let mut decoder = ReedSolomonDecoder::new_in(100, 1000, 1024, alloc.clone()).unwrap();
+            for (index, recovery) in recovery.iter().enumerate().take(100) {
+                decoder.add_recovery_shard(index, recovery).unwrap();
+            }
+            let result = decoder.decode().unwrap();
+            assert_eq!(result.restored_original(0).unwrap(), original[0]);
+            drop(result);
+
+            assert!(alloc.in_use.load(Ordering::SeqCst) >= 1100 * 1024);
+
+            drop(decoder);
+            assert_eq!(alloc.in_use.load(Ordering::SeqCst), 0);
+        }
+    }
 }
@@ -0,0 +1,609 @@
+use std::collections::HashMap;
+
+use fixedbitset::FixedBitSet;
+
+use crate::{
+    engine::ShardsRefMut,
+    engine32::{self, Engine32, GfElement32, Naive32},
+    Error, UnsupportedReason,
+};
+
+// ======================================================================
+// CONST - PRIVATE
+
+// Limit of `original_count.next_power_of_two() + recovery_count`.
+const MAX_WORK_COUNT32: usize = 1 << 31;
+
+// ======================================================================
+// ReedSolomonEncoder32 - PUBLIC
+
+/// Experimental Reed-Solomon encoder over `GF(2^32)`.
+///
+/// **Only available with the `unstable-gf32` feature.**
+///
+/// This supports up to `2^31` shards in total instead of `65536`,
+/// see [`UnsupportedReason::Gf32Limit`]. Shards are encoded as with
+/// [`LowRate`] using points of [`engine32`], so they are **not**
+/// compatible with [`ReedSolomonEncoder`].
+///
+/// # Examples
+///
+/// ```rust
+/// use reed_solomon_simd::{ReedSolomonDecoder32, ReedSolomonEncoder32};
+///
+/// let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+///
+/// let mut encoder = ReedSolomonEncoder32::new(3, 2, 64)?;
+/// for shard in original {
+///     encoder.add_original_shard(shard)?;
+/// }
+/// let recovery = encoder.encode()?;
+///
+/// let mut decoder = ReedSolomonDecoder32::new(3, 2, 64)?;
+/// decoder.add_original_shard(1, original[1])?;
+/// decoder.add_recovery_shard(0, &recovery[0])?;
+/// decoder.add_recovery_shard(1, &recovery[1])?;
+/// let restored = decoder.decode()?;
+///
+/// assert_eq!(restored[&0], original[0]);
+/// assert_eq!(restored[&2], original[2]);
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+///
+/// [`LowRate`]: crate::rate::LowRate
+/// [`ReedSolomonEncoder`]: crate::ReedSolomonEncoder
+pub struct ReedSolomonEncoder32<E: Engine32 = Naive32> {
+    engine: E,
+    original_count: usize,
+    recovery_count: usize,
+    shard_bytes: usize,
+    original_received_count: usize,
+    // Original shards at `0 .. original_count`,
+    // zero-padded to `original_count.next_power_of_two()`.
+    work: Vec<u8>,
+}
+
+impl ReedSolomonEncoder32 {
+    /// Creates new encoder with [`Naive32`] engine.
+    pub fn new(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<Self, Error> {
+        Self::with_engine(original_count, recovery_count, shard_bytes, Naive32::new())
+    }
+}
+
+impl<E: Engine32> ReedSolomonEncoder32<E> {
+    /// Adds one original shard to the encoder.
+    ///
+    /// Original shards have indexes `0..original_count` corresponding
+    /// to the order in which they are added.
+    pub fn add_original_shard<T: AsRef<[u8]>>(&mut self, original_shard: T) -> Result<(), Error> {
+        let original_shard = original_shard.as_ref();
+
+        if self.original_received_count == self.original_count {
+            Err(Error::TooManyOriginalShards {
+                original_count: self.original_count,
+            })
+        } else if original_shard.len() != self.shard_bytes {
+            Err(Error::DifferentShardSize {
+                shard_bytes: self.shard_bytes,
+                got: original_shard.len(),
+            })
+        } else {
+            let start = self.original_received_count * self.shard_bytes;
+            self.work[start..start + self.shard_bytes].copy_from_slice(original_shard);
+            self.original_received_count += 1;
+            Ok(())
+        }
+    }
+
+    /// Encodes the added original shards returning the recovery shards.
+    ///
+    /// The encoder is then ready for new round of encoding.
+    pub fn encode(&mut self) -> Result<Vec<Vec<u8>>, Error> {
+        if self.original_received_count != self.original_count {
+            return Err(Error::TooFewOriginalShards {
+                original_count: self.original_count,
+                original_received_count: self.original_received_count,
+            });
+        }
+
+        let chunk_size = self.original_count.next_power_of_two();
+        let shard_bytes = self.shard_bytes;
+
+        let mut work = ShardsRefMut::new(chunk_size, shard_bytes, &mut self.work);
+        self.engine.ifft(&mut work, 0, chunk_size, 0);
+
+        let mut recovery = Vec::with_capacity(self.recovery_count);
+        let mut chunk = vec![0; chunk_size * shard_bytes];
+        let mut offset = chunk_size;
+        while recovery.len() < self.recovery_count {
+            chunk.copy_from_slice(&self.work);
+            let mut shards = ShardsRefMut::new(chunk_size, shard_bytes, &mut chunk);
+            self.engine.fft(&mut shards, 0, chunk_size, offset);
+
+            let needed = self.recovery_count - recovery.len();
+            recovery.extend(
+                chunk
+                    .chunks_exact(shard_bytes)
+                    .take(needed)
+                    .map(<[u8]>::to_vec),
+            );
+            offset += chunk_size;
+        }
+
+        self.original_received_count = 0;
+        self.work.fill(0);
+
+        Ok(recovery)
+    }
+
+    /// Like [`new`](ReedSolomonEncoder32::new) with given [`Engine32`].
+    pub fn with_engine(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        engine: E,
+    ) -> Result<Self, Error> {
+        validate(original_count, recovery_count, shard_bytes)?;
+
+        Ok(Self {
+            engine,
+            original_count,
+            recovery_count,
+            shard_bytes,
+            original_received_count: 0,
+            work: vec![0; original_count.next_power_of_two() * shard_bytes],
+        })
+    }
+}
+
+// ======================================================================
+// ReedSolomonDecoder32 - PUBLIC
+
+/// Experimental Reed-Solomon decoder over `GF(2^32)`.
+///
+/// **Only available with the `unstable-gf32` feature.**
+///
+/// Decodes shards of [`ReedSolomonEncoder32`], see it for an example.
+pub struct ReedSolomonDecoder32<E: Engine32 = Naive32> {
+    engine: E,
+    original_count: usize,
+    recovery_count: usize,
+    shard_bytes: usize,
+    original_received_count: usize,
+    recovery_received_count: usize,
+    // Shards at their points, i.e. original shard `i` at `i` and
+    // recovery shard `j` at `original_count.next_power_of_two() + j`.
+    work: Vec<u8>,
+    received: FixedBitSet,
+}
+
+impl ReedSolomonDecoder32 {
+    /// Creates new decoder with [`Naive32`] engine.
+    pub fn new(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<Self, Error> {
+        Self::with_engine(original_count, recovery_count, shard_bytes, Naive32::new())
+    }
+}
+
+impl<E: Engine32> ReedSolomonDecoder32<E> {
+    /// Adds original shard with given `index` to the decoder.
+    pub fn add_original_shard<T: AsRef<[u8]>>(
+        &mut self,
+        index: usize,
+        original_shard: T,
+    ) -> Result<(), Error> {
+        if index >= self.original_count {
+            return Err(Error::InvalidOriginalShardIndex {
+                original_count: self.original_count,
+                index,
+            });
+        }
+
+        if self.received[index] {
+            return Err(Error::DuplicateOriginalShardIndex { index });
+        }
+
+        self.add_shard(index, original_shard.as_ref())?;
+        self.original_received_count += 1;
+        Ok(())
+    }
+
+    /// Adds recovery shard with given `index` to the decoder.
+    pub fn add_recovery_shard<T: AsRef<[u8]>>(
+        &mut self,
+        index: usize,
+        recovery_shard: T,
+    ) -> Result<(), Error> {
+        if index >= self.recovery_count {
+            return Err(Error::InvalidRecoveryShardIndex {
+                recovery_count: self.recovery_count,
+                index,
+            });
+        }
+
+        let pos = self.original_count.next_power_of_two() + index;
+        if self.received[pos] {
+            return Err(Error::DuplicateRecoveryShardIndex { index });
+        }
+
+        self.add_shard(pos, recovery_shard.as_ref())?;
+        self.recovery_received_count += 1;
+        Ok(())
+    }
+
+    /// Decodes the added shards returning restored original shards
+    /// which weren't added, indexed by original shard index.
+    ///
+    /// On success the decoder is ready for new round of decoding.
+    pub fn decode(&mut self) -> Result<HashMap<usize, Vec<u8>>, Error> {
+        let original_count = self.original_count;
+        let recovery_count = self.recovery_count;
+        let shard_bytes = self.shard_bytes;
+
+        if self.original_received_count + self.recovery_received_count < original_count {
+            return Err(Error::NotEnoughShards {
+                original_count,
+                original_received_count: self.original_received_count,
+                recovery_received_count: self.recovery_received_count,
+            });
+        }
+
+        if self.original_received_count == original_count {
+            self.reset();
+            return Ok(HashMap::new());
+        }
+
+        let chunk_size = original_count.next_power_of_two();
+        let work_count = (chunk_size + recovery_count).next_power_of_two();
+
+        // ERASURE LOCATOR
+        //
+        // Product of `x + point(e)` for every point `e` without known value,
+        // i.e. missing shards and the tail `chunk_size + recovery_count ..`.
+
+        let mut factors: Vec<Vec<GfElement32>> = (0..original_count)
+            .chain(chunk_size..chunk_size + recovery_count)
+            .filter(|&pos| !self.received[pos])
+            .map(|pos| subspace_factor(0, pos))
+            .collect();
+
+        let mut pos = chunk_size + recovery_count;
+        while pos < work_count {
+            let layer = pos.trailing_zeros() as usize;
+            factors.push(subspace_factor(layer, pos));
+            pos += 1 << layer;
+        }
+
+        let locator = product(&self.engine, factors);
+
+        // Values of locator and its derivative at points `0 .. work_count`.
+
+        let mut locator_values = to_bytes(&locator, work_count);
+        let mut derivative_values = locator_values.clone();
+        let mut values = ShardsRefMut::new(work_count, 4, &mut locator_values);
+        self.engine.fft(&mut values, 0, work_count, 0);
+        let mut values = ShardsRefMut::new(work_count, 4, &mut derivative_values);
+        E::formal_derivative(&mut values);
+        self.engine.fft(&mut values, 0, work_count, 0);
+
+        // MULTIPLY SHARDS
+        //
+        // Values of `original * locator` which are zero at unknown points.
+
+        let mut work = ShardsRefMut::new(work_count, shard_bytes, &mut self.work);
+        for pos in 0..work_count {
+            if self.received[pos] {
+                let value = engine32::element(&locator_values[pos * 4..]);
+                self.engine.mul(&mut work[pos], value);
+            } else {
+                work[pos].fill(0);
+            }
+        }
+
+        // FORMAL DERIVATIVE
+        //
+        // At roots of locator derivative of `original * locator`
+        // is `original * locator'`.
+
+        self.engine.ifft(&mut work, 0, work_count, 0);
+        E::formal_derivative(&mut work);
+        self.engine.fft(&mut work, 0, work_count, 0);
+
+        // REVEAL ERASURES
+
+        let mut restored = HashMap::new();
+        for pos in (0..original_count).filter(|&pos| !self.received[pos]) {
+            let derivative = engine32::element(&derivative_values[pos * 4..]);
+            let mut shard = work[pos].to_vec();
+            self.engine
+                .mul(&mut shard, engine32::inv(derivative).unwrap());
+            restored.insert(pos, shard);
+        }
+
+        self.reset();
+        Ok(restored)
+    }
+
+    /// Like [`new`](ReedSolomonDecoder32::new) with given [`Engine32`].
+    pub fn with_engine(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+        engine: E,
+    ) -> Result<Self, Error> {
+        validate(original_count, recovery_count, shard_bytes)?;
+
+        let work_count = (original_count.next_power_of_two() + recovery_count).next_power_of_two();
+
+        Ok(Self {
+            engine,
+            original_count,
+            recovery_count,
+            shard_bytes,
+            original_received_count: 0,
+            recovery_received_count: 0,
+            work: vec![0; work_count * shard_bytes],
+            received: FixedBitSet::with_capacity(work_count),
+        })
+    }
+}
+
+// ======================================================================
+// ReedSolomonDecoder32 - PRIVATE
+
+impl<E: Engine32> ReedSolomonDecoder32<E> {
+    fn add_shard(&mut self, pos: usize, shard: &[u8]) -> Result<(), Error> {
+        if shard.len() != self.shard_bytes {
+            return Err(Error::DifferentShardSize {
+                shard_bytes: self.shard_bytes,
+                got: shard.len(),
+            });
+        }
+
+        let start = pos * self.shard_bytes;
+        self.work[start..start + self.shard_bytes].copy_from_slice(shard);
+        self.received.insert(pos);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.original_received_count = 0;
+        self.recovery_received_count = 0;
+        self.received.clear();
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+// Multiplies polynomials in novel polynomial basis.
+fn mul_poly<E: Engine32>(engine: &E, a: &[GfElement32], b: &[GfElement32]) -> Vec<GfElement32> {
+    let len = a.len() + b.len() - 1;
+    let size = len.next_power_of_two();
+
+    let mut a = to_bytes(a, size);
+    let mut b = to_bytes(b, size);
+    engine.fft(&mut ShardsRefMut::new(size, 4, &mut a), 0, size, 0);
+    engine.fft(&mut ShardsRefMut::new(size, 4, &mut b), 0, size, 0);
+
+    let product: Vec<GfElement32> = a
+        .chunks_exact(4)
+        .zip(b.chunks_exact(4))
+        .map(|(x, y)| engine32::mul(engine32::element(x), engine32::element(y)))
+        .collect();
+
+    let mut product = to_bytes(&product, size);
+    engine.ifft(&mut ShardsRefMut::new(size, 4, &mut product), 0, size, 0);
+    product
+        .chunks_exact(4)
+        .take(len)
+        .map(engine32::element)
+        .collect()
+}
+
+// Multiplies polynomials pairwise until one remains.
+fn product<E: Engine32>(engine: &E, mut factors: Vec<Vec<GfElement32>>) -> Vec<GfElement32> {
+    while factors.len() > 1 {
+        factors = factors
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => mul_poly(engine, a, b),
+                [a] => a.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    factors.pop().unwrap()
+}
+
+// Returns product of `x + point(p)` for `p` in `pos .. pos + 2^layer`
+// divided by a constant, in novel polynomial basis.
+//
+// This is `W_layer(x + point(pos))` which is linear,
+// so dividing by `W_layer(cantor_basis[layer])`
+// gives `X_{2^layer} + Ŵ_layer(point(pos))`.
+fn subspace_factor(layer: usize, pos: usize) -> Vec<GfElement32> {
+    let mut factor = vec![0; (1 << layer) + 1];
+    factor[0] = engine32::skew(layer, pos);
+    factor[1 << layer] = 1;
+    factor
+}
+
+// Returns little-endian bytes of `elements` zero-padded to `count` elements.
+fn to_bytes(elements: &[GfElement32], count: usize) -> Vec<u8> {
+    let mut bytes = vec![0; count * 4];
+    for (chunk, element) in bytes.chunks_exact_mut(4).zip(elements) {
+        chunk.copy_from_slice(&element.to_le_bytes());
+    }
+    bytes
+}
+
+fn validate(original_count: usize, recovery_count: usize, shard_bytes: usize) -> Result<(), Error> {
+    let reason = UnsupportedReason::zero_count(original_count, recovery_count).or_else(|| {
+        let work_count = original_count
+            .checked_next_power_of_two()
+            .and_then(|chunk_size| chunk_size.checked_add(recovery_count));
+        match work_count {
+            Some(count) if count <= MAX_WORK_COUNT32 => None,
+            _ => Some(UnsupportedReason::Gf32Limit),
+        }
+    });
+
+    if let Some(reason) = reason {
+        Err(Error::UnsupportedShardCount {
+            original_count,
+            recovery_count,
+            reason,
+            hint: None,
+        })
+    } else if shard_bytes == 0 || shard_bytes & 63 != 0 {
+        Err(Error::InvalidShardSize { shard_bytes })
+    } else {
+        Ok(())
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    // Encodes and decodes, losing originals `0, step, 2 * step, ...`
+    // and using recovery shards only as needed.
+    fn roundtrip(original_count: usize, recovery_count: usize, shard_bytes: usize, step: usize) {
+        let original = test_util::generate_original(original_count, shard_bytes, 169);
+
+        let mut encoder =
+            ReedSolomonEncoder32::new(original_count, recovery_count, shard_bytes).unwrap();
+        for shard in &original {
+            encoder.add_original_shard(shard).unwrap();
+        }
+        let recovery = encoder.encode().unwrap();
+        assert_eq!(recovery.len(), recovery_count);
+
+        let mut decoder =
+            ReedSolomonDecoder32::new(original_count, recovery_count, shard_bytes).unwrap();
+        let lost: Vec<usize> = (0..original_count).step_by(step).collect();
+        for (index, shard) in original.iter().enumerate() {
+            if index % step != 0 {
+                decoder.add_original_shard(index, shard).unwrap();
+            }
+        }
+        // Skip first recovery shards when there are extra.
+        let skip = recovery_count - lost.len();
+        for (index, shard) in recovery.iter().enumerate().skip(skip) {
+            decoder.add_recovery_shard(index, shard).unwrap();
+        }
+
+        let restored = decoder.decode().unwrap();
+        assert_eq!(restored.len(), lost.len());
+        for index in lost {
+            assert_eq!(restored[&index], original[index]);
+        }
+    }
+
+    // ============================================================
+    // ROUNDTRIP
+
+    #[test]
+    fn roundtrip_small() {
+        roundtrip(1, 1, 64, 1);
+        roundtrip(3, 2, 64, 2);
+        roundtrip(2, 3, 128, 1);
+        roundtrip(5, 5, 64, 1);
+        roundtrip(100, 10, 64, 10);
+        roundtrip(1000, 100, 64, 13);
+    }
+
+    #[test]
+    #[ignore]
+    fn roundtrip_100000_10000() {
+        roundtrip(100_000, 10_000, 64, 10);
+    }
+
+    // ============================================================
+    // DECODER
+
+    #[test]
+    fn decoder_reuse_and_errors() {
+        let original = test_util::generate_original(3, 64, 0);
+        let mut encoder = ReedSolomonEncoder32::new(3, 2, 64).unwrap();
+        for shard in &original {
+            encoder.add_original_shard(shard).unwrap();
+        }
+        let recovery = encoder.encode().unwrap();
+
+        let mut decoder = ReedSolomonDecoder32::new(3, 2, 64).unwrap();
+        decoder.add_recovery_shard(0, &recovery[0]).unwrap();
+        assert_eq!(
+            decoder.add_recovery_shard(0, &recovery[0]),
+            Err(Error::DuplicateRecoveryShardIndex { index: 0 })
+        );
+        for index in [3, 65536, usize::MAX] {
+            assert_eq!(
+                decoder.add_original_shard(index, &original[0]),
+                Err(Error::InvalidOriginalShardIndex {
+                    original_count: 3,
+                    index,
+                })
+            );
+        }
+        for index in [2, 65536, usize::MAX] {
+            assert_eq!(
+                decoder.add_recovery_shard(index, &recovery[0]),
+                Err(Error::InvalidRecoveryShardIndex {
+                    recovery_count: 2,
+                    index,
+                })
+            );
+        }
+        assert_eq!(
+            decoder.decode(),
+            Err(Error::NotEnoughShards {
+                original_count: 3,
+                original_received_count: 0,
+                recovery_received_count: 1,
+            })
+        );
+
+        decoder.add_original_shard(2, &original[2]).unwrap();
+        decoder.add_recovery_shard(1, &recovery[1]).unwrap();
+        let restored = decoder.decode().unwrap();
+        assert_eq!(restored[&0], original[0]);
+        assert_eq!(restored[&1], original[1]);
+
+        // Decoder was reset.
+        for (index, shard) in original.iter().enumerate() {
+            decoder.add_original_shard(index, shard).unwrap();
+        }
+        assert!(decoder.decode().unwrap().is_empty());
+    }
+
+    #[test]
+    fn limits() {
+        assert!(ReedSolomonEncoder32::new(100_000, 10_000, 64).is_ok());
+        assert_eq!(
+            ReedSolomonEncoder32::new((1 << 30) + 1, 1, 64).err(),
+            Some(Error::UnsupportedShardCount {
+                original_count: (1 << 30) + 1,
+                recovery_count: 1,
+                reason: UnsupportedReason::Gf32Limit,
+                hint: None,
+            })
+        );
+        assert_eq!(
+            ReedSolomonDecoder32::new(1, 1, 4).err(),
+            Some(Error::InvalidShardSize { shard_bytes: 4 })
+        );
+    }
+}
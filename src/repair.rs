@@ -0,0 +1,402 @@
+//! Planning which shards to fetch for restoring missing original shards.
+//!
+//! **This is an advanced module which is not needed for [simple usage] or [basic usage].**
+//!
+//! Reed-Solomon is an MDS code: any `original_count` shards,
+//! in any combination of original shards and recovery shards,
+//! are enough to restore all original shards.
+//! [`plan`] makes the choice of those shards explicit, e.g. for
+//! a repair scheduler which needs to know what to fetch before fetching it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+//! use reed_solomon_simd::{
+//!     repair::{self, ShardId},
+//!     CodecParams, ReedSolomonDecoder,
+//! };
+//!
+//! let params = CodecParams::new(3, 2, 64);
+//! let original = [[1u8; 64], [2u8; 64], [3u8; 64]];
+//! let recovery = reed_solomon_simd::encode(3, 2, original)?;
+//!
+//! // Original shard #1 is lost.
+//! let available = [
+//!     ShardId::Original(0),
+//!     ShardId::Original(2),
+//!     ShardId::Recovery(0),
+//!     ShardId::Recovery(1),
+//! ];
+//!
+//! let plan = repair::plan(params, &available, &[1])?;
+//! assert_eq!(
+//!     plan.fetch(),
+//!     [ShardId::Original(0), ShardId::Original(2), ShardId::Recovery(0)]
+//! );
+//!
+//! let mut decoder = ReedSolomonDecoder::from_plan(&plan)?;
+//! for id in plan.fetch() {
+//!     match *id {
+//!         ShardId::Original(index) => decoder.add_original_shard(index, original[index])?,
+//!         ShardId::Recovery(index) => decoder.add_recovery_shard(index, &recovery[index])?,
+//!     }
+//! }
+//!
+//! let result = decoder.decode()?;
+//! assert_eq!(result.restored_original(1).unwrap(), original[1]);
+//! # }
+//! # Ok::<(), reed_solomon_simd::Error>(())
+//! ```
+//!
+//! [simple usage]: crate#simple-usage
+//! [basic usage]: crate#basic-usage
+
+use crate::{rate, CodecParams, Error};
+
+// ======================================================================
+// ShardId - PUBLIC
+
+/// Identifies a single original shard or recovery shard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ShardId {
+    /// Original shard with given index.
+    Original(usize),
+    /// Recovery shard with given index.
+    Recovery(usize),
+}
+
+// ======================================================================
+// RepairPlan - PUBLIC
+
+/// Shards to fetch for restoring missing original shards,
+/// with estimated cost of decoding.
+///
+/// This struct is created by [`plan`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepairPlan {
+    params: CodecParams,
+    fetch: Vec<ShardId>,
+    missing: Vec<usize>,
+    fft_truncated_size: usize,
+}
+
+impl RepairPlan {
+    /// Returns number of bytes to read, i.e. `fetch().len() * shard_bytes`.
+    pub fn bytes_to_read(&self) -> usize {
+        self.fetch.len() * self.params.shard_bytes
+    }
+
+    /// Returns shards to fetch, original shards first
+    /// and then recovery shards, both in increasing order of index.
+    ///
+    /// This is empty if there are no missing original shards
+    /// and otherwise contains exactly `original_count` shards.
+    pub fn fetch(&self) -> &[ShardId] {
+        &self.fetch
+    }
+
+    /// Returns truncated size of the FFT/IFFT which decoding
+    /// will do, or `0` if no decoding is needed.
+    ///
+    /// Decoding time is roughly proportional to this.
+    pub fn fft_truncated_size(&self) -> usize {
+        self.fft_truncated_size
+    }
+
+    /// Returns indexes of the missing original shards
+    /// which will be restored, in increasing order.
+    pub fn missing(&self) -> &[usize] {
+        &self.missing
+    }
+
+    /// Returns configuration this plan was made for.
+    pub fn params(&self) -> CodecParams {
+        self.params
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PUBLIC
+
+/// Plans which of the `available` shards to fetch
+/// for restoring the `missing` original shards.
+///
+/// - Original shards are preferred over recovery shards
+///   and recovery shards with low index over those with high index.
+/// - Original shards listed in `missing` are never fetched,
+///   even if they are also listed in `available`.
+/// - Returns [`Error::NotEnoughShards`] if `available` shards
+///   are not enough for decoding.
+///
+/// See [module documentation](self) for an example.
+pub fn plan(
+    params: CodecParams,
+    available: &[ShardId],
+    missing: &[usize],
+) -> Result<RepairPlan, Error> {
+    params.validate()?;
+
+    let CodecParams {
+        original_count,
+        recovery_count,
+        ..
+    } = params;
+
+    let mut is_missing = vec![false; original_count];
+    for &index in missing {
+        if index >= original_count {
+            return Err(Error::InvalidOriginalShardIndex {
+                original_count,
+                index,
+            });
+        } else if is_missing[index] {
+            return Err(Error::DuplicateOriginalShardIndex { index });
+        }
+        is_missing[index] = true;
+    }
+
+    let mut original_available = vec![false; original_count];
+    let mut recovery_available = vec![false; recovery_count];
+    for &id in available {
+        match id {
+            ShardId::Original(index) => {
+                if index >= original_count {
+                    return Err(Error::InvalidOriginalShardIndex {
+                        original_count,
+                        index,
+                    });
+                } else if original_available[index] {
+                    return Err(Error::DuplicateOriginalShardIndex { index });
+                }
+                original_available[index] = true;
+            }
+
+            ShardId::Recovery(index) => {
+                if index >= recovery_count {
+                    return Err(Error::InvalidRecoveryShardIndex {
+                        recovery_count,
+                        index,
+                    });
+                } else if recovery_available[index] {
+                    return Err(Error::DuplicateRecoveryShardIndex { index });
+                }
+                recovery_available[index] = true;
+            }
+        }
+    }
+
+    let missing: Vec<usize> = (0..original_count).filter(|&i| is_missing[i]).collect();
+
+    if missing.is_empty() {
+        return Ok(RepairPlan {
+            params,
+            fetch: Vec::new(),
+            missing,
+            fft_truncated_size: 0,
+        });
+    }
+
+    let originals = (0..original_count).filter(|&i| original_available[i] && !is_missing[i]);
+    let recoveries = (0..recovery_count).filter(|&i| recovery_available[i]);
+
+    let fetch: Vec<ShardId> = originals
+        .clone()
+        .map(ShardId::Original)
+        .chain(recoveries.clone().map(ShardId::Recovery))
+        .take(original_count)
+        .collect();
+
+    if fetch.len() < original_count {
+        return Err(Error::NotEnoughShards {
+            original_count,
+            original_received_count: originals.count(),
+            recovery_received_count: recoveries.count(),
+        });
+    }
+
+    Ok(RepairPlan {
+        params,
+        fetch,
+        missing,
+        fft_truncated_size: fft_truncated_size(params)?,
+    })
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+// Same as `original_end` / `recovery_end` in decoding of `DefaultRate`.
+fn fft_truncated_size(params: CodecParams) -> Result<usize, Error> {
+    if rate::use_high_rate(params)? {
+        Ok(params.recovery_count.next_power_of_two() + params.original_count)
+    } else {
+        Ok(params.original_count.next_power_of_two() + params.recovery_count)
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
+mod tests {
+    use super::*;
+    use crate::{test_util, ReedSolomonDecoder};
+
+    fn all_shards(params: CodecParams) -> Vec<ShardId> {
+        (0..params.original_count)
+            .map(ShardId::Original)
+            .chain((0..params.recovery_count).map(ShardId::Recovery))
+            .collect()
+    }
+
+    // ============================================================
+    // plan
+
+    #[test]
+    fn nothing_missing() {
+        let params = CodecParams::new(3, 2, 64);
+        let repair_plan = plan(params, &all_shards(params), &[]).unwrap();
+
+        assert!(repair_plan.fetch().is_empty());
+        assert!(repair_plan.missing().is_empty());
+        assert_eq!(repair_plan.bytes_to_read(), 0);
+        assert_eq!(repair_plan.fft_truncated_size(), 0);
+    }
+
+    #[test]
+    fn prefers_originals_then_low_recovery() {
+        let params = CodecParams::new(4, 4, 1024);
+        let available = [
+            ShardId::Recovery(3),
+            ShardId::Original(3),
+            ShardId::Recovery(1),
+            ShardId::Original(0),
+            ShardId::Recovery(2),
+        ];
+
+        let repair_plan = plan(params, &available, &[2, 1]).unwrap();
+
+        assert_eq!(
+            repair_plan.fetch(),
+            [
+                ShardId::Original(0),
+                ShardId::Original(3),
+                ShardId::Recovery(1),
+                ShardId::Recovery(2),
+            ]
+        );
+        assert_eq!(repair_plan.missing(), [1, 2]);
+        assert_eq!(repair_plan.bytes_to_read(), 4 * 1024);
+        assert_eq!(repair_plan.params(), params);
+    }
+
+    #[test]
+    fn missing_original_is_not_fetched() {
+        let params = CodecParams::new(2, 2, 64);
+        let repair_plan = plan(params, &all_shards(params), &[0]).unwrap();
+
+        assert_eq!(
+            repair_plan.fetch(),
+            [ShardId::Original(1), ShardId::Recovery(0)]
+        );
+    }
+
+    #[test]
+    fn fft_truncated_size_by_rate() {
+        // high rate
+        let params = CodecParams::new(1025, 1024, 64);
+        let repair_plan = plan(params, &all_shards(params), &[0]).unwrap();
+        assert_eq!(repair_plan.fft_truncated_size(), 1024 + 1025);
+
+        // low rate
+        let params = CodecParams::new(1024, 1025, 64);
+        let repair_plan = plan(params, &all_shards(params), &[0]).unwrap();
+        assert_eq!(repair_plan.fft_truncated_size(), 1024 + 1025);
+
+        let params = CodecParams::new(3, 1000, 64);
+        let repair_plan = plan(params, &all_shards(params), &[0]).unwrap();
+        assert_eq!(repair_plan.fft_truncated_size(), 4 + 1000);
+    }
+
+    #[test]
+    fn decode_with_planned_shards_only() {
+        let original = test_util::generate_original(5, 1024, 157);
+        let recovery = crate::encode(5, 3, &original).unwrap();
+
+        let params = CodecParams::new(5, 3, 1024);
+        let repair_plan = plan(params, &all_shards(params), &[0, 2, 4]).unwrap();
+        assert_eq!(repair_plan.fetch().len(), 5);
+
+        let mut decoder = ReedSolomonDecoder::from_plan(&repair_plan).unwrap();
+        for id in repair_plan.fetch() {
+            match *id {
+                ShardId::Original(index) => {
+                    decoder.add_original_shard(index, &original[index]).unwrap()
+                }
+                ShardId::Recovery(index) => {
+                    decoder.add_recovery_shard(index, &recovery[index]).unwrap()
+                }
+            }
+        }
+
+        let result = decoder.decode().unwrap();
+        for &index in repair_plan.missing() {
+            assert_eq!(result.restored_original(index).unwrap(), original[index]);
+        }
+    }
+
+    // ============================================================
+    // ERRORS
+
+    #[test]
+    fn not_enough_shards() {
+        let params = CodecParams::new(3, 2, 64);
+        let available = [ShardId::Original(0), ShardId::Recovery(1)];
+
+        assert_eq!(
+            plan(params, &available, &[1, 2]),
+            Err(Error::NotEnoughShards {
+                original_count: 3,
+                original_received_count: 1,
+                recovery_received_count: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_and_duplicate_index() {
+        let params = CodecParams::new(3, 2, 64);
+
+        assert_eq!(
+            plan(params, &[], &[3]),
+            Err(Error::InvalidOriginalShardIndex {
+                original_count: 3,
+                index: 3,
+            })
+        );
+
+        assert_eq!(
+            plan(params, &[], &[1, 1]),
+            Err(Error::DuplicateOriginalShardIndex { index: 1 })
+        );
+
+        assert_eq!(
+            plan(params, &[ShardId::Recovery(2)], &[0]),
+            Err(Error::InvalidRecoveryShardIndex {
+                recovery_count: 2,
+                index: 2,
+            })
+        );
+
+        assert_eq!(
+            plan(params, &[ShardId::Recovery(0), ShardId::Recovery(0)], &[0]),
+            Err(Error::DuplicateRecoveryShardIndex { index: 0 })
+        );
+
+        assert_eq!(
+            plan(CodecParams::new(3, 2, 100), &[], &[0]),
+            Err(Error::InvalidShardSize { shard_bytes: 100 })
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use std::io;
+
+use crate::{
+    integrity::{self, IntegrityFooter, FOOTER_BYTES},
+    rate::DecoderWork,
+    Error,
+};
+
+// ======================================================================
+// ShardWriter - PUBLIC
+
+/// Writer which streams content of one shard
+/// directly into the working space of a decoder.
+///
+/// This struct is created by [`ReedSolomonDecoder::begin_original_shard`],
+/// [`ReedSolomonDecoder::begin_recovery_shard`]
+/// and the corresponding methods of [`RateDecoder`].
+///
+/// - At most `shard_bytes` bytes can be written, after that
+///   [`write`] returns `Ok(0)`.
+/// - Shard is added to the decoder only when [`finish`] is called.
+///   If [`ShardWriter`] is dropped without calling [`finish`],
+///   the shard is not added.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+/// use reed_solomon_simd::{ReedSolomonDecoder, ReedSolomonEncoder};
+/// use std::io::Write;
+///
+/// let original = [[1u8; 64], [2u8; 64]];
+/// let recovery = reed_solomon_simd::encode(2, 1, original)?;
+///
+/// let mut decoder = ReedSolomonDecoder::new(2, 1, 64)?;
+/// decoder.add_original_shard(0, original[0])?;
+///
+/// let mut writer = decoder.begin_recovery_shard(0)?;
+/// for frame in recovery[0].chunks(16) {
+///     writer.write_all(frame).unwrap();
+/// }
+/// writer.finish()?;
+///
+/// let result = decoder.decode()?;
+/// assert_eq!(result.restored_original(1).unwrap(), original[1]);
+/// # }
+/// # Ok::<(), reed_solomon_simd::Error>(())
+/// ```
+///
+/// [`ReedSolomonDecoder::begin_original_shard`]: crate::ReedSolomonDecoder::begin_original_shard
+/// [`ReedSolomonDecoder::begin_recovery_shard`]: crate::ReedSolomonDecoder::begin_recovery_shard
+/// [`RateDecoder`]: crate::rate::RateDecoder
+/// [`write`]: ShardWriter#method.write
+/// [`finish`]: ShardWriter::finish
+pub struct ShardWriter<'a> {
+    work: &'a mut DecoderWork,
+    pos: usize,
+    written: usize,
+    // Expected footer and index of recovery shard, see `with_footer`.
+    footer: Option<(IntegrityFooter, usize)>,
+}
+
+impl<'a> ShardWriter<'a> {
+    /// Adds the written shard to the decoder.
+    ///
+    /// Returns [`Error::DifferentShardSize`] if less than
+    /// `shard_bytes` bytes have been written,
+    /// in which case the shard is not added.
+    ///
+    /// For recovery shards of a decoder with integrity footer enabled
+    /// the footer is validated, returning [`Error::ParameterMismatch`]
+    /// or [`Error::StripeMismatch`] like [`add_recovery_shard`] does,
+    /// in which case the shard is not added either.
+    ///
+    /// [`add_recovery_shard`]: crate::ReedSolomonDecoder::add_recovery_shard
+    pub fn finish(self) -> Result<(), Error> {
+        let shard_bytes = self.work.shard_bytes();
+
+        if self.written != shard_bytes {
+            return Error::DifferentShardSize {
+                shard_bytes,
+                got: self.written,
+            }
+            .into_cold_err();
+        }
+
+        if let Some((expected, index)) = self.footer {
+            let shard = self.work.shard_mut(self.pos);
+            integrity::check_footer(expected, index, shard)?;
+            shard[shard_bytes - FOOTER_BYTES..].fill(0);
+        }
+
+        self.work.finish_shard(self.pos);
+        Ok(())
+    }
+
+    /// Returns number of bytes written so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+}
+
+// ======================================================================
+// ShardWriter - IMPL Write
+
+impl<'a> io::Write for ShardWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let shard = &mut self.work.shard_mut(self.pos)[self.written..];
+        let count = std::cmp::min(shard.len(), buf.len());
+        shard[..count].copy_from_slice(&buf[..count]);
+        self.written += count;
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// ======================================================================
+// ShardWriter - CRATE
+
+impl<'a> ShardWriter<'a> {
+    // `pos` must have been returned by `DecoderWork::begin_*_shard`.
+    pub(crate) fn new(work: &'a mut DecoderWork, pos: usize) -> Self {
+        Self {
+            work,
+            pos,
+            written: 0,
+            footer: None,
+        }
+    }
+
+    // Validates integrity footer of recovery shard `index` in `finish`
+    // and zeroes it, like `ReedSolomonDecoder::add_recovery_shard`.
+    pub(crate) fn with_footer(mut self, expected: IntegrityFooter, index: usize) -> Self {
+        self.footer = Some((expected, index));
+        self
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
+mod tests {
+    use std::io::{ErrorKind, Write};
+
+    use super::*;
+    use crate::{test_util, ReedSolomonDecoder, ReedSolomonEncoder};
+
+    // Writes `shard` in irregular chunk sizes.
+    fn write_irregular(writer: &mut ShardWriter, shard: &[u8]) {
+        let mut pos = 0;
+        let mut chunk = 1;
+        while pos < shard.len() {
+            let end = std::cmp::min(pos + chunk, shard.len());
+            writer.write_all(&shard[pos..end]).unwrap();
+            pos = end;
+            chunk = chunk * 3 + 1;
+        }
+    }
+
+    #[test]
+    fn roundtrip_matches_slice_api() {
+        let original = test_util::generate_original(5, 1024, 153);
+
+        let mut encoder = ReedSolomonEncoder::new(5, 3, 1024).unwrap();
+        for original in &original {
+            encoder.add_original_shard(original).unwrap();
+        }
+        let result = encoder.encode().unwrap();
+        let recovery: Vec<_> = result.recovery_iter().map(|s| s.to_vec()).collect();
+        drop(result);
+
+        let mut decoder = ReedSolomonDecoder::new(5, 3, 1024).unwrap();
+        decoder.add_original_shard(1, &original[1]).unwrap();
+        decoder.add_original_shard(3, &original[3]).unwrap();
+        decoder.add_recovery_shard(0, &recovery[0]).unwrap();
+        decoder.add_recovery_shard(1, &recovery[1]).unwrap();
+        decoder.add_recovery_shard(2, &recovery[2]).unwrap();
+        let expected: Vec<_> = decoder
+            .decode()
+            .unwrap()
+            .restored_original_iter()
+            .map(|(index, shard)| (index, shard.to_vec()))
+            .collect();
+
+        let mut writer = decoder.begin_original_shard(1).unwrap();
+        write_irregular(&mut writer, &original[1]);
+        writer.finish().unwrap();
+
+        let mut writer = decoder.begin_original_shard(3).unwrap();
+        write_irregular(&mut writer, &original[3]);
+        writer.finish().unwrap();
+
+        for (index, recovery) in recovery.iter().enumerate() {
+            let mut writer = decoder.begin_recovery_shard(index).unwrap();
+            write_irregular(&mut writer, recovery);
+            assert_eq!(writer.written(), 1024);
+            writer.finish().unwrap();
+        }
+
+        let got: Vec<_> = decoder
+            .decode()
+            .unwrap()
+            .restored_original_iter()
+            .map(|(index, shard)| (index, shard.to_vec()))
+            .collect();
+
+        assert_eq!(got, expected);
+        assert_eq!(got.len(), 3);
+        for (index, shard) in got {
+            assert_eq!(shard, original[index]);
+        }
+    }
+
+    #[test]
+    fn write_at_most_shard_bytes() {
+        let mut decoder = ReedSolomonDecoder::new(1, 1, 64).unwrap();
+        let mut writer = decoder.begin_recovery_shard(0).unwrap();
+
+        assert_eq!(writer.write(&[0; 100]).unwrap(), 64);
+        assert_eq!(writer.write(&[0; 1]).unwrap(), 0);
+        assert_eq!(
+            writer.write_all(&[0; 1]).unwrap_err().kind(),
+            ErrorKind::WriteZero
+        );
+        assert_eq!(writer.finish(), Ok(()));
+    }
+
+    // ============================================================
+    // ERRORS
+
+    #[test]
+    fn abandoned_shard_is_not_added() {
+        let mut decoder = ReedSolomonDecoder::new(1, 1, 64).unwrap();
+
+        {
+            let mut writer = decoder.begin_recovery_shard(0).unwrap();
+            writer.write_all(&[0; 64]).unwrap();
+        }
+
+        assert_eq!(
+            decoder.decode().err(),
+            Some(Error::NotEnoughShards {
+                original_count: 1,
+                original_received_count: 0,
+                recovery_received_count: 0,
+            })
+        );
+
+        // Same index can be started again.
+        let mut writer = decoder.begin_recovery_shard(0).unwrap();
+        writer.write_all(&[0; 64]).unwrap();
+        writer.finish().unwrap();
+        assert!(decoder.decode().is_ok());
+    }
+
+    #[test]
+    fn different_shard_size_in_finish() {
+        let mut decoder = ReedSolomonDecoder::new(1, 1, 64).unwrap();
+
+        let mut writer = decoder.begin_original_shard(0).unwrap();
+        writer.write_all(&[0; 63]).unwrap();
+        assert_eq!(
+            writer.finish(),
+            Err(Error::DifferentShardSize {
+                shard_bytes: 64,
+                got: 63
+            })
+        );
+
+        assert_eq!(
+            decoder.decode().err(),
+            Some(Error::NotEnoughShards {
+                original_count: 1,
+                original_received_count: 0,
+                recovery_received_count: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn duplicate_and_invalid_index_in_begin() {
+        let mut decoder = ReedSolomonDecoder::new(1, 1, 64).unwrap();
+        decoder.add_original_shard(0, [0; 64]).unwrap();
+        decoder.add_recovery_shard(0, [0; 64]).unwrap();
+
+        assert_eq!(
+            decoder.begin_original_shard(0).err(),
+            Some(Error::DuplicateOriginalShardIndex { index: 0 })
+        );
+        assert_eq!(
+            decoder.begin_recovery_shard(0).err(),
+            Some(Error::DuplicateRecoveryShardIndex { index: 0 })
+        );
+        assert_eq!(
+            decoder.begin_original_shard(1).err(),
+            Some(Error::InvalidOriginalShardIndex {
+                original_count: 1,
+                index: 1,
+            })
+        );
+        assert_eq!(
+            decoder.begin_recovery_shard(1).err(),
+            Some(Error::InvalidRecoveryShardIndex {
+                recovery_count: 1,
+                index: 1,
+            })
+        );
+    }
+}
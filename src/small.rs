@@ -0,0 +1,552 @@
+//! Encoding/decoding of small stripes without working space.
+//!
+//! **This is an advanced module which is not needed for [simple usage] or [basic usage].**
+//!
+//! [`SmallCodec`] is meant for many independent small stripes,
+//! e.g. forward error correction of real-time audio with a few
+//! short frames per stripe.
+//!
+//! - Coefficients of the recovery shards are computed once in
+//!   [`SmallCodec::new`], which is the only place that allocates.
+//! - [`SmallCodec::encode`] and [`SmallCodec::decode`] compute each
+//!   output shard as a linear combination of the input shards
+//!   with [`Engine::mul`] and [`Engine::xor`], without FFT and
+//!   without working space. This is `O(original_count * recovery_count)`
+//!   multiplications, so shard counts are limited to [`MAX_SHARDS`].
+//! - Decoding is much faster than with [`ReedSolomonDecoder`],
+//!   which always evaluates erasure locations over the whole field.
+//! - Encoding is not faster than with a re-used [`ReedSolomonEncoder`],
+//!   as FFT needs fewer multiplications even for small stripes.
+//!
+//! Recovery shards are the same as those of [`ReedSolomonEncoder`],
+//! so stripes can be encoded with one and decoded with the other.
+//!
+//! # Shard size
+//!
+//! Like elsewhere in this crate, shard size must be non-zero and
+//! multiple of 64 bytes, otherwise [`Error::InvalidShardSize`] is returned.
+//! Frames of other sizes, e.g. 480-byte audio frames, must be padded by
+//! the caller, e.g. with zeros to 512 bytes. Only the original frame needs
+//! to be sent as original shard, as the receiver can pad it the same way
+//! before decoding and truncate restored shards back to frame size.
+//! Recovery shards must be sent whole.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use reed_solomon_simd::small::SmallCodec;
+//!
+//! let codec = SmallCodec::<4, 2>::new()?;
+//!
+//! let original = [[1u8; 64], [2u8; 64], [3u8; 64], [4u8; 64]];
+//! let mut recovery = [[0u8; 64]; 2];
+//! let [r0, r1] = &mut recovery;
+//! codec.encode(&[&original[0], &original[1], &original[2], &original[3]], &mut [r0, r1])?;
+//!
+//! // Original shards 1 and 3 are lost.
+//! let mut restored = [[0u8; 64]; 2];
+//! let [a, b] = &mut restored;
+//! codec.decode(
+//!     &[Some(&original[0]), None, Some(&original[2]), None],
+//!     &[Some(&recovery[0]), Some(&recovery[1])],
+//!     &mut [&mut [], a, &mut [], b],
+//! )?;
+//!
+//! assert_eq!(restored, [original[1], original[3]]);
+//! # Ok::<(), reed_solomon_simd::Error>(())
+//! ```
+//!
+//! [simple usage]: crate#simple-usage
+//! [basic usage]: crate#basic-usage
+//! [`ReedSolomonDecoder`]: crate::ReedSolomonDecoder
+//! [`ReedSolomonEncoder`]: crate::ReedSolomonEncoder
+
+use crate::{
+    engine::{DefaultEngine, Engine, GfElement},
+    gf::Gf16,
+    rate::{DefaultRateEncoder, RateEncoder},
+    Error, UnsupportedReason,
+};
+
+// ======================================================================
+// CONST - PUBLIC
+
+/// Maximum `original_count + recovery_count` of [`SmallCodec`].
+pub const MAX_SHARDS: usize = crate::SMALL_MAX_SHARDS;
+
+// ======================================================================
+// CONST - PRIVATE
+
+// Size of the stack buffer for products, multiple of 64.
+const SCRATCH_BYTES: usize = 1024;
+
+// ======================================================================
+// SmallCodec - PUBLIC
+
+/// Encoder/decoder for stripes of `N` original shards
+/// and `K` recovery shards, where `N + K <= MAX_SHARDS`.
+///
+/// See [module documentation](self) for details.
+#[derive(Clone)]
+pub struct SmallCodec<const N: usize, const K: usize, E: Engine = DefaultEngine> {
+    engine: E,
+    // Recovery shard `j` is the sum of `coeffs[j][i] * original[i]`.
+    coeffs: [[GfElement; N]; K],
+}
+
+impl<const N: usize, const K: usize> SmallCodec<N, K> {
+    /// Creates new codec with [`DefaultEngine`].
+    ///
+    /// Returns [`Error::UnsupportedShardCount`] unless `N > 0`,
+    /// `K > 0` and `N + K <= MAX_SHARDS`.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_engine(DefaultEngine::new())
+    }
+}
+
+impl<const N: usize, const K: usize, E: Engine> SmallCodec<N, K, E> {
+    /// Restores missing original shards.
+    ///
+    /// - `original[i]` and `recovery[j]` are the received shards,
+    ///   `None` for lost ones. At least `N` shards must be given.
+    /// - `restored[i]` receives original shard `i` if it's lost
+    ///   and must then be as long as the other shards,
+    ///   otherwise it's ignored and can be empty.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::NotEnoughShards`] if less than `N` shards are given.
+    /// - [`Error::InvalidShardSize`] if shard size isn't
+    ///   a non-zero multiple of 64 bytes.
+    /// - [`Error::DifferentShardSize`] if given shards have different sizes.
+    /// - [`Error::InvalidOutputSize`] if a buffer in `restored`
+    ///   which receives a lost shard has wrong size.
+    pub fn decode(
+        &self,
+        original: &[Option<&[u8]>; N],
+        recovery: &[Option<&[u8]>; K],
+        restored: &mut [&mut [u8]; N],
+    ) -> Result<(), Error> {
+        let original_received_count = original.iter().flatten().count();
+        let recovery_received_count = recovery.iter().flatten().count();
+        if original_received_count + recovery_received_count < N {
+            return Err(Error::NotEnoughShards {
+                original_count: N,
+                original_received_count,
+                recovery_received_count,
+            });
+        }
+
+        let shard_bytes = shard_bytes(original.iter().chain(recovery).flatten().copied())?;
+        for (shard, restored) in original.iter().zip(restored.iter()) {
+            if shard.is_none() && restored.len() != shard_bytes {
+                return Err(Error::InvalidOutputSize {
+                    expected: shard_bytes,
+                    got: restored.len(),
+                });
+            }
+        }
+
+        let m = N - original_received_count;
+        if m == 0 {
+            return Ok(());
+        }
+
+        // Lost original shards `missing[..m]` are solved from
+        // received recovery shards `used[..m]`, which are
+        // `sum(coeffs[used[s]][i] * original[i])` over all `i`.
+        let mut missing = [0; K];
+        let mut used = [0; K];
+
+        for (slot, index) in missing
+            .iter_mut()
+            .zip((0..N).filter(|&i| original[i].is_none()))
+        {
+            *slot = index;
+        }
+        for (slot, index) in used
+            .iter_mut()
+            .zip((0..K).filter(|&j| recovery[j].is_some()))
+        {
+            *slot = index;
+        }
+
+        // `inverse[t][s]` is coefficient of `recovery[used[s]]`
+        // in `original[missing[t]]`.
+        let mut matrix = [[Gf16::ZERO; K]; K];
+        for (s, row) in matrix.iter_mut().enumerate().take(m) {
+            for (t, x) in row.iter_mut().enumerate().take(m) {
+                *x = Gf16(self.coeffs[used[s]][missing[t]]);
+            }
+        }
+        let inverse = invert(matrix, m);
+
+        for t in 0..m {
+            let mut terms = [(&[][..], Gf16::ZERO); N];
+            let mut term_count = 0;
+
+            for s in 0..m {
+                terms[term_count] = (recovery[used[s]].unwrap(), inverse[t][s]);
+                term_count += 1;
+            }
+
+            // Received original shards are subtracted from recovery shards,
+            // which is same as adding them as subtraction is XOR.
+            for (i, shard) in original.iter().enumerate() {
+                if let Some(shard) = shard {
+                    let coeff = (0..m).fold(Gf16::ZERO, |sum, s| {
+                        sum + inverse[t][s] * Gf16(self.coeffs[used[s]][i])
+                    });
+                    terms[term_count] = (shard, coeff);
+                    term_count += 1;
+                }
+            }
+
+            self.mul_add(restored[missing[t]], &terms[..term_count]);
+        }
+
+        Ok(())
+    }
+
+    /// Generates recovery shards from original shards into `recovery`.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidShardSize`] if shard size isn't
+    ///   a non-zero multiple of 64 bytes.
+    /// - [`Error::DifferentShardSize`] if original shards have different sizes.
+    /// - [`Error::InvalidOutputSize`] if a buffer in `recovery`
+    ///   isn't as long as the original shards.
+    pub fn encode(
+        &self,
+        original: &[&[u8]; N],
+        recovery: &mut [&mut [u8]; K],
+    ) -> Result<(), Error> {
+        let shard_bytes = shard_bytes(original.iter().copied())?;
+        if let Some(recovery) = recovery.iter().find(|r| r.len() != shard_bytes) {
+            return Err(Error::InvalidOutputSize {
+                expected: shard_bytes,
+                got: recovery.len(),
+            });
+        }
+
+        for (recovery, coeffs) in recovery.iter_mut().zip(&self.coeffs) {
+            let mut terms = [(&[][..], Gf16::ZERO); N];
+            for (term, (original, coeff)) in terms.iter_mut().zip(original.iter().zip(coeffs)) {
+                *term = (*original, Gf16(*coeff));
+            }
+            self.mul_add(recovery, &terms);
+        }
+
+        Ok(())
+    }
+
+    /// Like [`new`](SmallCodec::new) with given [`Engine`].
+    pub fn with_engine(engine: E) -> Result<Self, Error> {
+        if let Some(reason) = UnsupportedReason::zero_count(N, K) {
+            return Err(Error::UnsupportedShardCount {
+                original_count: N,
+                recovery_count: K,
+                reason,
+                hint: None,
+            });
+        } else if N + K > MAX_SHARDS {
+            return Err(Error::UnsupportedShardCount {
+                original_count: N,
+                recovery_count: K,
+                reason: UnsupportedReason::SmallCodecLimit,
+                hint: None,
+            });
+        }
+
+        // Encoding is linear separately for each of the 32 `GfElement`:s
+        // of a 64-byte shard, so setting element `i` of original shard `i`
+        // to one gives coefficients of original shard `i` as element `i`
+        // of each recovery shard.
+        let mut coeffs = [[0; N]; K];
+        {
+            let mut encoder = DefaultRateEncoder::new(N, K, 64, &engine, None)?;
+            for i in 0..N {
+                let mut shard = [0u8; 64];
+                shard[i] = 1;
+                encoder.add_original_shard(shard)?;
+            }
+
+            let result = encoder.encode()?;
+            for (coeffs, recovery) in coeffs.iter_mut().zip(result.recovery_iter()) {
+                for (i, coeff) in coeffs.iter_mut().enumerate() {
+                    *coeff = GfElement::from(recovery[i]) | GfElement::from(recovery[32 + i]) << 8;
+                }
+            }
+        }
+
+        Ok(Self { engine, coeffs })
+    }
+}
+
+// ======================================================================
+// SmallCodec - PRIVATE
+
+impl<const N: usize, const K: usize, E: Engine> SmallCodec<N, K, E> {
+    // `out[] = sum(terms[i].1 * terms[i].0[])`
+    fn mul_add(&self, out: &mut [u8], terms: &[(&[u8], Gf16)]) {
+        let mut scratch = [0u8; SCRATCH_BYTES];
+
+        for (chunk_start, out) in (0..)
+            .step_by(SCRATCH_BYTES)
+            .zip(out.chunks_mut(SCRATCH_BYTES))
+        {
+            let chunk_end = chunk_start + out.len();
+            let mut first = true;
+
+            for (input, coeff) in terms {
+                let Some(log_m) = coeff.log() else {
+                    continue;
+                };
+                let input = &input[chunk_start..chunk_end];
+
+                // First product is computed directly into `out`.
+                if first {
+                    out.copy_from_slice(input);
+                    if log_m != 0 {
+                        self.engine.mul(out, log_m);
+                    }
+                    first = false;
+                } else if log_m == 0 {
+                    E::xor(out, input);
+                } else {
+                    let product = &mut scratch[..out.len()];
+                    product.copy_from_slice(input);
+                    self.engine.mul(product, log_m);
+                    E::xor(out, product);
+                }
+            }
+
+            if first {
+                out.fill(0);
+            }
+        }
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+// Returns common size of given shards, which must be valid.
+fn shard_bytes<'a>(mut shards: impl Iterator<Item = &'a [u8]>) -> Result<usize, Error> {
+    let shard_bytes = shards.next().map_or(0, <[u8]>::len);
+    if shard_bytes == 0 || shard_bytes & 63 != 0 {
+        return Err(Error::InvalidShardSize { shard_bytes });
+    }
+
+    match shards.find(|shard| shard.len() != shard_bytes) {
+        Some(shard) => Err(Error::DifferentShardSize {
+            shard_bytes,
+            got: shard.len(),
+        }),
+        None => Ok(shard_bytes),
+    }
+}
+
+// Inverts top-left `size x size` submatrix with Gauss-Jordan elimination.
+// - Submatrix of an MDS code is always invertible.
+fn invert<const K: usize>(mut matrix: [[Gf16; K]; K], size: usize) -> [[Gf16; K]; K] {
+    let mut inverse = [[Gf16::ZERO; K]; K];
+    for (i, row) in inverse.iter_mut().enumerate().take(size) {
+        row[i] = Gf16::ONE;
+    }
+
+    for col in 0..size {
+        let pivot = (col..size)
+            .find(|&row| matrix[row][col] != Gf16::ZERO)
+            .expect("matrix is invertible");
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+
+        let inv = matrix[col][col].inv().unwrap();
+        for x in 0..size {
+            matrix[col][x] *= inv;
+            inverse[col][x] *= inv;
+        }
+
+        for row in 0..size {
+            let factor = matrix[row][col];
+            if row != col && factor != Gf16::ZERO {
+                for x in 0..size {
+                    let (m, i) = (matrix[col][x], inverse[col][x]);
+                    matrix[row][x] -= factor * m;
+                    inverse[row][x] -= factor * i;
+                }
+            }
+        }
+    }
+
+    inverse
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
+mod tests {
+    use super::*;
+    use crate::{engine::NoSimd, test_util};
+
+    // Compares encoding with `ReedSolomonEncoder` and decodes
+    // with every combination of at most `K` lost shards.
+    fn roundtrip_all_erasures<const N: usize, const K: usize, E: Engine>(
+        codec: &SmallCodec<N, K, E>,
+        shard_bytes: usize,
+    ) {
+        let original = test_util::generate_original(N, shard_bytes, (N * K) as u8);
+        let expected = crate::encode(N, K, &original).unwrap();
+
+        let mut recovery = vec![vec![0u8; shard_bytes]; K];
+        let original_refs: [&[u8]; N] = std::array::from_fn(|i| &original[i][..]);
+        let mut recovery_refs: [&mut [u8]; K] = {
+            let mut iter = recovery.iter_mut();
+            std::array::from_fn(|_| &mut iter.next().unwrap()[..])
+        };
+        codec.encode(&original_refs, &mut recovery_refs).unwrap();
+        assert_eq!(recovery, expected);
+
+        for lost in 0u32..1 << (N + K) {
+            if lost.count_ones() as usize > K {
+                continue;
+            }
+
+            let is_lost = |pos: usize| lost & (1 << pos) != 0;
+            let original_received: [Option<&[u8]>; N] =
+                std::array::from_fn(|i| (!is_lost(i)).then_some(&original[i][..]));
+            let recovery_received: [Option<&[u8]>; K] =
+                std::array::from_fn(|j| (!is_lost(N + j)).then_some(&recovery[j][..]));
+
+            let mut restored = vec![vec![0u8; shard_bytes]; N];
+            let mut restored_refs: [&mut [u8]; N] = {
+                let mut iter = restored.iter_mut();
+                std::array::from_fn(|_| &mut iter.next().unwrap()[..])
+            };
+            codec
+                .decode(&original_received, &recovery_received, &mut restored_refs)
+                .unwrap();
+
+            for i in (0..N).filter(|&i| is_lost(i)) {
+                assert_eq!(restored[i], original[i], "lost = {:b}", lost);
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_4_2() {
+        // Larger than stack buffer, which is used in chunks.
+        let shard_bytes = SCRATCH_BYTES + 64;
+        roundtrip_all_erasures(&SmallCodec::<4, 2>::new().unwrap(), shard_bytes);
+        roundtrip_all_erasures(
+            &SmallCodec::<4, 2, _>::with_engine(NoSimd::new()).unwrap(),
+            shard_bytes,
+        );
+    }
+
+    #[test]
+    fn roundtrip_6_3() {
+        let shard_bytes = SCRATCH_BYTES + 64;
+        roundtrip_all_erasures(&SmallCodec::<6, 3>::new().unwrap(), shard_bytes);
+        roundtrip_all_erasures(
+            &SmallCodec::<6, 3, _>::with_engine(NoSimd::new()).unwrap(),
+            shard_bytes,
+        );
+    }
+
+    #[test]
+    fn roundtrip_misc() {
+        roundtrip_all_erasures(&SmallCodec::<1, 1>::new().unwrap(), 64);
+        roundtrip_all_erasures(&SmallCodec::<2, 5>::new().unwrap(), 64);
+        roundtrip_all_erasures(&SmallCodec::<13, 3>::new().unwrap(), 64);
+    }
+
+    #[test]
+    fn unsupported_shard_count() {
+        for (result, original_count, recovery_count, reason) in [
+            (
+                SmallCodec::<0, 1>::new().err(),
+                0,
+                1,
+                UnsupportedReason::NoOriginalShards,
+            ),
+            (
+                SmallCodec::<1, 0>::new().err(),
+                1,
+                0,
+                UnsupportedReason::NoRecoveryShards,
+            ),
+            (
+                SmallCodec::<9, 8>::new().err(),
+                9,
+                8,
+                UnsupportedReason::SmallCodecLimit,
+            ),
+        ] {
+            assert_eq!(
+                result,
+                Some(Error::UnsupportedShardCount {
+                    original_count,
+                    recovery_count,
+                    reason,
+                    hint: None,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let codec = SmallCodec::<2, 1>::new().unwrap();
+        let (a, b) = ([0u8; 64], [0u8; 128]);
+        let mut out = [0u8; 64];
+
+        assert_eq!(
+            codec.encode(&[&a[..10], &a[..10]], &mut [&mut out]),
+            Err(Error::InvalidShardSize { shard_bytes: 10 })
+        );
+        assert_eq!(
+            codec.encode(&[&a, &b], &mut [&mut out]),
+            Err(Error::DifferentShardSize {
+                shard_bytes: 64,
+                got: 128
+            })
+        );
+        assert_eq!(
+            codec.encode(&[&b, &b], &mut [&mut out]),
+            Err(Error::InvalidOutputSize {
+                expected: 128,
+                got: 64
+            })
+        );
+
+        assert_eq!(
+            codec.decode(&[Some(&a), None], &[None], &mut [&mut [], &mut out]),
+            Err(Error::NotEnoughShards {
+                original_count: 2,
+                original_received_count: 1,
+                recovery_received_count: 0,
+            })
+        );
+        assert_eq!(
+            codec.decode(&[Some(&a), None], &[Some(&b)], &mut [&mut [], &mut out]),
+            Err(Error::DifferentShardSize {
+                shard_bytes: 64,
+                got: 128
+            })
+        );
+        assert_eq!(
+            codec.decode(&[Some(&a), None], &[Some(&a)], &mut [&mut out, &mut []]),
+            Err(Error::InvalidOutputSize {
+                expected: 64,
+                got: 0
+            })
+        );
+        assert!(codec
+            .decode(&[Some(&a), Some(&a)], &[None], &mut [&mut [], &mut []])
+            .is_ok());
+    }
+}
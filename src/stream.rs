@@ -0,0 +1,487 @@
+//! Whole-object encoding/decoding over [`io::Read`] and [`io::Write`].
+//!
+//! **This is an advanced module which is not needed for [simple usage] or [basic usage].**
+//!
+//! - [`encode_stream`] reads an object and writes each original shard
+//!   and recovery shard to its own writer.
+//! - [`decode_stream`] reads shards from any sufficient subset
+//!   of those writers and writes the object back.
+//!
+//! # Stripes
+//!
+//! An object can be larger than what fits into one set of shards,
+//! so it is processed in stripes. Each stripe holds
+//! `original_count * shard_bytes` bytes of which the last 8 bytes
+//! contain the number of object bytes in that stripe
+//! as little-endian `u64`. Rest of the stripe is zero-padded.
+//!
+//! Only one stripe is held in memory at a time, so memory
+//! usage doesn't depend on size of the object.
+//!
+//! Each shard stream is concatenation of that shard from every stripe.
+//!
+//! # Shard indexes
+//!
+//! Shard streams are indexed `0..original_count + recovery_count`
+//! with original shards first and recovery shards after them.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(all(feature = "encoder", feature = "decoder"))] {
+//! use reed_solomon_simd::{stream, CodecParams};
+//!
+//! let params = CodecParams::new(3, 2, 64);
+//! let object: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+//!
+//! let mut shards = vec![Vec::new(); 5];
+//! stream::encode_stream(object.as_slice(), &mut shards, params)?;
+//!
+//! // Lose original shards #0 and #2.
+//! let mut readers = [
+//!     (1, shards[1].as_slice()),
+//!     (3, shards[3].as_slice()),
+//!     (4, shards[4].as_slice()),
+//! ];
+//!
+//! let mut restored = Vec::new();
+//! stream::decode_stream(&mut readers, &mut restored, params)?;
+//!
+//! assert_eq!(restored, object);
+//! # }
+//! # Ok::<(), reed_solomon_simd::stream::StreamError>(())
+//! ```
+//!
+//! [simple usage]: crate#simple-usage
+//! [basic usage]: crate#basic-usage
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+#[cfg(feature = "decoder")]
+use crate::ReedSolomonDecoder;
+#[cfg(feature = "encoder")]
+use crate::ReedSolomonEncoder;
+use crate::{CodecParams, Error};
+
+// ======================================================================
+// CONST - PRIVATE
+
+// Size of the object length stored at the end of each stripe.
+const LENGTH_BYTES: usize = 8;
+
+// ======================================================================
+// StreamError - PUBLIC
+
+/// Represents all possible errors of [`encode_stream`] and [`decode_stream`].
+#[derive(Debug)]
+pub enum StreamError {
+    /// Encoding or decoding failed.
+    Codec(Error),
+
+    /// Reading or writing failed.
+    Io(io::Error),
+
+    /// Stripe claims to contain more bytes than fit into it,
+    /// i.e. shard streams are corrupted or were created
+    /// with different [`CodecParams`].
+    InvalidStripeLength {
+        /// Object bytes this stripe can contain.
+        capacity: usize,
+        /// Object bytes stored in the stripe.
+        length: u64,
+    },
+
+    /// [`encode_stream`] was given wrong number of writers.
+    WrongWriterCount {
+        /// `original_count + recovery_count`
+        expected: usize,
+        /// Number of given writers.
+        got: usize,
+    },
+}
+
+// ======================================================================
+// StreamError - IMPL DISPLAY
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Codec(error) => write!(f, "coding error: {}", error),
+
+            StreamError::Io(error) => write!(f, "io error: {}", error),
+
+            StreamError::InvalidStripeLength { capacity, length } => {
+                write!(
+                    f,
+                    "invalid stripe length: {} bytes > capacity {} bytes",
+                    length, capacity
+                )
+            }
+
+            StreamError::WrongWriterCount { expected, got } => {
+                write!(
+                    f,
+                    "wrong writer count: expected {} writers, got {}",
+                    expected, got
+                )
+            }
+        }
+    }
+}
+
+// ======================================================================
+// StreamError - IMPL ERROR
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StreamError::Codec(error) => Some(error),
+            StreamError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+// ======================================================================
+// StreamError - IMPL From
+
+impl From<Error> for StreamError {
+    fn from(error: Error) -> Self {
+        StreamError::Codec(error)
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(error: io::Error) -> Self {
+        StreamError::Io(error)
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PUBLIC
+
+/// Reads whole object from `reader`, encodes it stripe by stripe
+/// and writes each shard to its own writer.
+///
+/// - `writers` must contain `original_count + recovery_count` writers,
+///   original shards first and recovery shards after them.
+/// - Returns number of object bytes read.
+///
+/// See [module documentation](self) for details and an example.
+#[cfg(feature = "encoder")]
+pub fn encode_stream<R: Read, W: Write>(
+    mut reader: R,
+    writers: &mut [W],
+    params: CodecParams,
+) -> Result<u64, StreamError> {
+    params.validate()?;
+
+    let CodecParams {
+        original_count,
+        recovery_count,
+        shard_bytes,
+    } = params;
+
+    if writers.len() != original_count + recovery_count {
+        return Err(StreamError::WrongWriterCount {
+            expected: original_count + recovery_count,
+            got: writers.len(),
+        });
+    }
+
+    let mut encoder = ReedSolomonEncoder::from_params(params)?;
+    let mut stripe = vec![0u8; original_count * shard_bytes];
+    let capacity = stripe.len() - LENGTH_BYTES;
+    let mut total = 0;
+
+    loop {
+        let (payload, length) = stripe.split_at_mut(capacity);
+        let count = read_full(&mut reader, payload)?;
+
+        // Empty object still gets one stripe, otherwise
+        // there's no need for a stripe without object bytes.
+        if count == 0 && total > 0 {
+            break;
+        }
+
+        payload[count..].fill(0);
+        length.copy_from_slice(&(count as u64).to_le_bytes());
+        total += count as u64;
+
+        for original in stripe.chunks_exact(shard_bytes) {
+            encoder.add_original_shard(original)?;
+        }
+
+        let result = encoder.encode()?;
+
+        for (writer, original) in writers.iter_mut().zip(stripe.chunks_exact(shard_bytes)) {
+            writer.write_all(original)?;
+        }
+
+        for (writer, recovery) in writers[original_count..]
+            .iter_mut()
+            .zip(result.recovery_iter())
+        {
+            writer.write_all(recovery)?;
+        }
+
+        if count < capacity {
+            break;
+        }
+    }
+
+    for writer in writers.iter_mut() {
+        writer.flush()?;
+    }
+
+    Ok(total)
+}
+
+/// Reads shards from given readers, decodes them stripe by stripe
+/// and writes the restored object to `writer`.
+///
+/// - Each reader is given with its shard index
+///   in range `0..original_count + recovery_count`,
+///   original shards first and recovery shards after them.
+/// - At least `original_count` readers must be given,
+///   in any combination of original shards and recovery shards.
+/// - Returns number of object bytes written.
+///
+/// See [module documentation](self) for details and an example.
+#[cfg(feature = "decoder")]
+pub fn decode_stream<R: Read, W: Write>(
+    readers: &mut [(usize, R)],
+    mut writer: W,
+    params: CodecParams,
+) -> Result<u64, StreamError> {
+    params.validate()?;
+
+    let CodecParams {
+        original_count,
+        shard_bytes,
+        ..
+    } = params;
+
+    let mut decoder = ReedSolomonDecoder::from_params(params)?;
+    let mut stripe = vec![0u8; original_count * shard_bytes];
+    let capacity = stripe.len() - LENGTH_BYTES;
+    let mut total = 0;
+    let mut first_stripe = true;
+
+    'stripes: loop {
+        for (reader_index, (index, reader)) in readers.iter_mut().enumerate() {
+            let index = *index;
+
+            let count = if index < original_count {
+                let original = &mut stripe[index * shard_bytes..(index + 1) * shard_bytes];
+                let count = read_full(reader, original)?;
+                if count == shard_bytes {
+                    decoder.add_original_shard(index, original)?;
+                }
+                count
+            } else {
+                let mut shard_writer = decoder.begin_recovery_shard(index - original_count)?;
+                let count = io::copy(&mut reader.take(shard_bytes as u64), &mut shard_writer)?;
+                if count == shard_bytes as u64 {
+                    shard_writer.finish()?;
+                }
+                count as usize
+            };
+
+            if count == 0 && reader_index == 0 && !first_stripe {
+                // All stripes have been decoded.
+                break 'stripes;
+            } else if count != shard_bytes {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+        }
+
+        let result = decoder.decode()?;
+        for (index, restored) in result.restored_original_iter() {
+            stripe[index * shard_bytes..(index + 1) * shard_bytes].copy_from_slice(restored);
+        }
+        drop(result);
+
+        let (payload, length) = stripe.split_at(capacity);
+        let length = u64::from_le_bytes(length.try_into().unwrap());
+        if length > capacity as u64 {
+            return Err(StreamError::InvalidStripeLength { capacity, length });
+        }
+
+        writer.write_all(&payload[..length as usize])?;
+        total += length;
+        first_stripe = false;
+    }
+
+    writer.flush()?;
+
+    Ok(total)
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+// Reads until `buf` is full or end of stream is reached,
+// returning number of bytes read.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut count = 0;
+    while count < buf.len() {
+        match reader.read(&mut buf[count..]) {
+            Ok(0) => break,
+            Ok(n) => count += n,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(count)
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(test, not(miri), feature = "encoder", feature = "decoder"))]
+mod tests {
+    use super::*;
+
+    fn generate_object(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + i / 251) as u8).collect()
+    }
+
+    fn encode_to_vecs(object: &[u8], params: CodecParams) -> Vec<Vec<u8>> {
+        let mut shards = vec![Vec::new(); params.original_count + params.recovery_count];
+        let count = encode_stream(object, &mut shards, params).unwrap();
+        assert_eq!(count, object.len() as u64);
+        shards
+    }
+
+    fn decode_from(shards: &[Vec<u8>], indexes: &[usize], params: CodecParams) -> Vec<u8> {
+        let mut readers: Vec<_> = indexes
+            .iter()
+            .map(|i| (*i, shards[*i].as_slice()))
+            .collect();
+        let mut restored = Vec::new();
+        let count = decode_stream(&mut readers, &mut restored, params).unwrap();
+        assert_eq!(count, restored.len() as u64);
+        restored
+    }
+
+    // ============================================================
+    // ROUNDTRIP
+
+    #[test]
+    fn roundtrip_various_lengths() {
+        let params = CodecParams::new(3, 2, 64);
+        let capacity = 3 * 64 - LENGTH_BYTES;
+
+        for len in [
+            0,
+            1,
+            capacity - 1,
+            capacity,
+            capacity + 1,
+            capacity * 5 + 17,
+        ] {
+            let object = generate_object(len);
+            let shards = encode_to_vecs(&object, params);
+
+            for shard in &shards {
+                assert_eq!(shard.len() % 64, 0);
+                assert!(!shard.is_empty());
+            }
+
+            assert_eq!(decode_from(&shards, &[0, 1, 2], params), object);
+            assert_eq!(decode_from(&shards, &[4, 1, 3], params), object);
+            assert_eq!(decode_from(&shards, &[3, 4, 2], params), object);
+        }
+    }
+
+    #[test]
+    fn roundtrip_low_rate() {
+        let params = CodecParams::new(2, 5, 128);
+        let object = generate_object(10000);
+        let shards = encode_to_vecs(&object, params);
+
+        assert_eq!(decode_from(&shards, &[6, 2], params), object);
+        assert_eq!(decode_from(&shards, &[0, 3, 5], params), object);
+    }
+
+    // ============================================================
+    // ERRORS
+
+    #[test]
+    fn not_enough_shards() {
+        let params = CodecParams::new(3, 2, 64);
+        let shards = encode_to_vecs(&generate_object(500), params);
+
+        let mut readers = [(0, shards[0].as_slice()), (4, shards[4].as_slice())];
+        assert!(matches!(
+            decode_stream(&mut readers, Vec::new(), params),
+            Err(StreamError::Codec(Error::NotEnoughShards { .. }))
+        ));
+    }
+
+    #[test]
+    fn truncated_shard_stream() {
+        let params = CodecParams::new(3, 2, 64);
+        let shards = encode_to_vecs(&generate_object(500), params);
+
+        let mut readers = [
+            (0, shards[0].as_slice()),
+            (1, &shards[1][..shards[1].len() - 1]),
+            (3, shards[3].as_slice()),
+        ];
+        match decode_stream(&mut readers, Vec::new(), params) {
+            Err(StreamError::Io(error)) => {
+                assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof)
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn wrong_writer_count() {
+        let mut shards = vec![Vec::new(); 4];
+        assert!(matches!(
+            encode_stream(&[0u8; 10][..], &mut shards, CodecParams::new(3, 2, 64)),
+            Err(StreamError::WrongWriterCount {
+                expected: 5,
+                got: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn invalid_params() {
+        let mut shards = vec![Vec::new(); 5];
+        assert!(matches!(
+            encode_stream(&[0u8; 10][..], &mut shards, CodecParams::new(3, 2, 100)),
+            Err(StreamError::Codec(Error::InvalidShardSize {
+                shard_bytes: 100
+            }))
+        ));
+    }
+
+    #[test]
+    fn io_error_is_distinguishable() {
+        struct FailingWriter;
+
+        impl Write for FailingWriter {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "disk full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writers = [FailingWriter, FailingWriter];
+        assert!(matches!(
+            encode_stream(&[0u8; 10][..], &mut writers, CodecParams::new(1, 1, 64)),
+            Err(StreamError::Io(_))
+        ));
+    }
+}
@@ -418,6 +418,8 @@ macro_rules! test_rate_encoder_errors {
                 Some(Error::UnsupportedShardCount {
                     original_count: 0,
                     recovery_count: 1,
+                    reason: UnsupportedReason::NoOriginalShards,
+                    hint: None,
                 }),
             );
         }
@@ -430,6 +432,8 @@ macro_rules! test_rate_encoder_errors {
                 Err(Error::UnsupportedShardCount {
                     original_count: 0,
                     recovery_count: 1,
+                    reason: UnsupportedReason::NoOriginalShards,
+                    hint: None,
                 }),
             );
         }
@@ -487,26 +491,36 @@ macro_rules! test_rate_decoder_errors {
 
         #[test]
         fn invalid_original_shard_index() {
-            let mut decoder = $Decoder::new(1, 1, 64, NoSimd::new(), None).unwrap();
-            assert_eq!(
-                decoder.add_original_shard(1, [0; 64]),
-                Err(Error::InvalidOriginalShardIndex {
-                    original_count: 1,
-                    index: 1,
-                }),
-            );
+            let mut decoder = $Decoder::new(2, 3, 64, NoSimd::new(), None).unwrap();
+
+            for index in [2, 2 + 1, usize::MAX] {
+                assert_eq!(
+                    decoder.add_original_shard(index, [0; 64]),
+                    Err(Error::InvalidOriginalShardIndex {
+                        original_count: 2,
+                        index,
+                    }),
+                );
+            }
+
+            assert_eq!(decoder.add_original_shard(2 - 1, [0; 64]), Ok(()));
         }
 
         #[test]
         fn invalid_recovery_shard_index() {
-            let mut decoder = $Decoder::new(1, 1, 64, NoSimd::new(), None).unwrap();
-            assert_eq!(
-                decoder.add_recovery_shard(1, [0; 64]),
-                Err(Error::InvalidRecoveryShardIndex {
-                    recovery_count: 1,
-                    index: 1,
-                }),
-            );
+            let mut decoder = $Decoder::new(2, 3, 64, NoSimd::new(), None).unwrap();
+
+            for index in [3, 3 + 1, usize::MAX] {
+                assert_eq!(
+                    decoder.add_recovery_shard(index, [0; 64]),
+                    Err(Error::InvalidRecoveryShardIndex {
+                        recovery_count: 3,
+                        index,
+                    }),
+                );
+            }
+
+            assert_eq!(decoder.add_recovery_shard(3 - 1, [0; 64]), Ok(()));
         }
 
         #[test]
@@ -546,6 +560,8 @@ macro_rules! test_rate_decoder_errors {
                 Some(Error::UnsupportedShardCount {
                     original_count: 0,
                     recovery_count: 1,
+                    reason: UnsupportedReason::NoOriginalShards,
+                    hint: None,
                 }),
             );
         }
@@ -558,6 +574,8 @@ macro_rules! test_rate_decoder_errors {
                 Err(Error::UnsupportedShardCount {
                     original_count: 0,
                     recovery_count: 1,
+                    reason: UnsupportedReason::NoOriginalShards,
+                    hint: None,
                 }),
             );
         }
@@ -571,6 +589,17 @@ macro_rules! test_rate_decoder_errors {
 // - shard_bytes = 1024 (or 64 if mentioned explicitly)
 // - Original shards are from `generate_original`.
 
+// ==================================================
+// SCHEME IDS
+
+// Scheme identifiers which these hashes were produced with.
+// - If a hash changes, the corresponding `SCHEME_ID` must be bumped
+//   (semver-major) and the identifier here updated with the hash.
+
+pub(crate) const DEFAULT_SCHEME_ID: &str = "rs-fft-gf16-cantor-v1";
+pub(crate) const HIGH_SCHEME_ID: &str = "rs-fft-gf16-cantor-high-v1";
+pub(crate) const LOW_SCHEME_ID: &str = "rs-fft-gf16-cantor-low-v1";
+
 // ==================================================
 // TINY
 
@@ -0,0 +1,131 @@
+// ======================================================================
+// MACROS - CRATE
+
+// Enters `tracing` span at TRACE level for the rest of the enclosing block,
+// recording given fields (`name = value` or local variable `name`),
+// if `tracing` feature is enabled.
+// - Without `tracing` feature this expands to nothing,
+//   so field expressions aren't evaluated.
+macro_rules! trace_span {
+    ($name:literal $(, $field:ident $(= $value:expr)?)* $(,)?) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!($name $(, $field $(= $value)?)*).entered();
+    };
+}
+
+// ======================================================================
+// TESTS
+
+#[cfg(all(
+    test,
+    not(miri),
+    feature = "tracing",
+    feature = "encoder",
+    feature = "decoder"
+))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing::{
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    use crate::{test_util, ReedSolomonDecoder, ReedSolomonEncoder};
+
+    // Records name and parent name of each created span.
+    #[derive(Default)]
+    struct Capture {
+        // (name, parent index)
+        spans: Mutex<Vec<(&'static str, Option<usize>)>>,
+        // Indexes of currently entered spans.
+        stack: Mutex<Vec<usize>>,
+    }
+
+    impl Capture {
+        // Returns `(name, parent name)` of spans other than table initialization,
+        // which happens only once per process.
+        fn hierarchy(&self) -> Vec<(&'static str, Option<&'static str>)> {
+            let spans = self.spans.lock().unwrap();
+            spans
+                .iter()
+                .filter(|(name, _)| *name != "init_table")
+                .map(|&(name, parent)| (name, parent.map(|parent| spans[parent].0)))
+                .collect()
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes) -> Id {
+            let mut spans = self.spans.lock().unwrap();
+            let parent = self.stack.lock().unwrap().last().copied();
+            spans.push((span.metadata().name(), parent));
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, _event: &Event) {}
+
+        fn enter(&self, span: &Id) {
+            let index = span.into_u64() as usize - 1;
+            self.stack.lock().unwrap().push(index);
+        }
+
+        fn exit(&self, _span: &Id) {
+            self.stack.lock().unwrap().pop();
+        }
+    }
+
+    #[test]
+    fn span_hierarchy() {
+        let capture = Arc::new(Capture::default());
+
+        tracing::subscriber::with_default(capture.clone(), || {
+            let original = test_util::generate_original(2, 64, 0);
+
+            // 2:3 uses low rate
+            let mut encoder = ReedSolomonEncoder::new(2, 3, 64).unwrap();
+            for original in &original {
+                encoder.add_original_shard(original).unwrap();
+            }
+            let result = encoder.encode().unwrap();
+
+            let mut decoder = ReedSolomonDecoder::new(2, 3, 64).unwrap();
+            decoder.add_original_shard(1, &original[1]).unwrap();
+            decoder
+                .add_recovery_shard(0, result.recovery(0).unwrap())
+                .unwrap();
+            let result = decoder.decode().unwrap();
+            assert_eq!(result.restored_original(0).unwrap(), original[0]);
+        });
+
+        assert_eq!(
+            capture.hierarchy(),
+            [
+                ("copy_in", None),
+                ("copy_in", None),
+                ("encode", None),
+                ("ifft", Some("encode")),
+                ("copy_chunks", Some("encode")),
+                ("fft", Some("encode")),
+                ("copy_in", None),
+                ("copy_in", None),
+                ("decode", None),
+                ("erasures", Some("decode")),
+                ("eval_poly", Some("decode")),
+                ("multiply", Some("decode")),
+                ("ifft", Some("decode")),
+                ("formal_derivative", Some("decode")),
+                ("fft", Some("decode")),
+                ("reveal", Some("decode")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! JavaScript bindings with [`wasm-bindgen`], e.g. for repairing shards in a browser.
+//!
+//! **This module is only available with `wasm-bindgen` feature.**
+//!
+//! [`WasmEncoder`] and [`WasmDecoder`] wrap [`ReedSolomonEncoder`] and
+//! [`ReedSolomonDecoder`] with methods which take and return `Uint8Array`:s.
+//!
+//! - Shards given to these are copied once, from JavaScript memory to
+//!   the working space of encoder/decoder.
+//! - Shards returned by these are copied once, from the working space
+//!   to a new `Uint8Array`.
+//! - [`Error`] is thrown as a JavaScript `Error` whose message is
+//!   the [`Display`] of the error followed by its [`Debug`],
+//!   e.g. `invalid shard size: 100 bytes (InvalidShardSize { shard_bytes: 100 })`,
+//!   so that all fields of the error are preserved.
+//!
+//! There is no `simd128` engine yet, so on `wasm32` the [`DefaultEngine`]
+//! of these is always [`NoSimd`].
+//!
+//! # Examples
+//!
+//! ```js
+//! import { WasmEncoder, WasmDecoder } from "reed-solomon-simd";
+//!
+//! const original = [new Uint8Array(64).fill(1), new Uint8Array(64).fill(2)];
+//!
+//! const encoder = new WasmEncoder(2, 1, 64);
+//! for (const shard of original) {
+//!     encoder.addOriginalShard(shard);
+//! }
+//! const recovery = encoder.encode();
+//!
+//! const decoder = new WasmDecoder(2, 1, 64);
+//! decoder.addOriginalShard(1, original[1]);
+//! decoder.addRecoveryShard(0, recovery[0]);
+//! const restored = decoder.decode(); // Map { 0 => Uint8Array }
+//! ```
+//!
+//! [`wasm-bindgen`]: https://crates.io/crates/wasm-bindgen
+//! [`Display`]: std::fmt::Display
+//! [`Debug`]: std::fmt::Debug
+//! [`DefaultEngine`]: crate::engine::DefaultEngine
+//! [`NoSimd`]: crate::engine::NoSimd
+
+use js_sys::{Array, Map, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+use crate::{Error, ReedSolomonDecoder, ReedSolomonEncoder};
+
+// ======================================================================
+// WasmEncoder - PUBLIC
+
+/// [`ReedSolomonEncoder`] for JavaScript.
+///
+/// See [module documentation](self) for details.
+#[wasm_bindgen]
+pub struct WasmEncoder(ReedSolomonEncoder);
+
+#[wasm_bindgen]
+impl WasmEncoder {
+    /// Like [`ReedSolomonEncoder::new`].
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<WasmEncoder, JsError> {
+        ReedSolomonEncoder::new(original_count, recovery_count, shard_bytes)
+            .map(Self)
+            .map_err(js_error)
+    }
+
+    /// Like [`ReedSolomonEncoder::add_original_shard`].
+    #[wasm_bindgen(js_name = addOriginalShard)]
+    pub fn add_original_shard(&mut self, original_shard: &[u8]) -> Result<(), JsError> {
+        self.0.add_original_shard(original_shard).map_err(js_error)
+    }
+
+    /// Like [`ReedSolomonEncoder::encode`], returning
+    /// an `Array` of recovery shards as `Uint8Array`:s.
+    pub fn encode(&mut self) -> Result<Array, JsError> {
+        let result = self.0.encode().map_err(js_error)?;
+        Ok(result.recovery_iter().map(Uint8Array::from).collect())
+    }
+}
+
+// ======================================================================
+// WasmDecoder - PUBLIC
+
+/// [`ReedSolomonDecoder`] for JavaScript.
+///
+/// See [module documentation](self) for details.
+#[wasm_bindgen]
+pub struct WasmDecoder(ReedSolomonDecoder);
+
+#[wasm_bindgen]
+impl WasmDecoder {
+    /// Like [`ReedSolomonDecoder::new`].
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        original_count: usize,
+        recovery_count: usize,
+        shard_bytes: usize,
+    ) -> Result<WasmDecoder, JsError> {
+        ReedSolomonDecoder::new(original_count, recovery_count, shard_bytes)
+            .map(Self)
+            .map_err(js_error)
+    }
+
+    /// Like [`ReedSolomonDecoder::add_original_shard`].
+    #[wasm_bindgen(js_name = addOriginalShard)]
+    pub fn add_original_shard(
+        &mut self,
+        index: usize,
+        original_shard: &[u8],
+    ) -> Result<(), JsError> {
+        self.0
+            .add_original_shard(index, original_shard)
+            .map_err(js_error)
+    }
+
+    /// Like [`ReedSolomonDecoder::add_recovery_shard`].
+    #[wasm_bindgen(js_name = addRecoveryShard)]
+    pub fn add_recovery_shard(
+        &mut self,
+        index: usize,
+        recovery_shard: &[u8],
+    ) -> Result<(), JsError> {
+        self.0
+            .add_recovery_shard(index, recovery_shard)
+            .map_err(js_error)
+    }
+
+    /// Like [`ReedSolomonDecoder::decode`], returning a `Map`
+    /// from index to restored original shard as `Uint8Array`.
+    pub fn decode(&mut self) -> Result<Map, JsError> {
+        let result = self.0.decode().map_err(js_error)?;
+        let restored = Map::new();
+        for (index, original) in result.restored_original_iter() {
+            restored.set(&JsValue::from(index), &Uint8Array::from(original));
+        }
+        Ok(restored)
+    }
+}
+
+// ======================================================================
+// FUNCTIONS - PRIVATE
+
+fn js_error(error: Error) -> JsError {
+    JsError::new(&format!("{} ({:?})", error, error))
+}
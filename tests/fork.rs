@@ -0,0 +1,121 @@
+//! Encoding in child processes forked after and during
+//! initialization of lookup-tables, see "Fork" in `engine::tables`.
+//!
+//! This is a separate test binary with a single test,
+//! so that no other test initializes tables in parallel.
+
+#![cfg(unix)]
+
+use std::{
+    ffi::c_int,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use sha2::{Digest, Sha256};
+
+use reed_solomon_simd::engine::{
+    tables::{self, Table},
+    DefaultEngine, Engine, Naive,
+};
+
+// ======================================================================
+// CONST
+
+// 2 original ; 3 recovery ; 123 seed, same as `LOW_2_3` of lib tests.
+const LOW_2_3: &str = "f682a6c87c2bcd3e0feddbeff5c34f9d14026b78c44e5fdb5cf3cf71ec15e1f4";
+
+const CHILD_TIMEOUT: Duration = Duration::from_secs(60);
+
+// ======================================================================
+// LIBC
+
+extern "C" {
+    fn fork() -> c_int;
+    fn waitpid(pid: c_int, status: *mut c_int, options: c_int) -> c_int;
+    fn kill(pid: c_int, sig: c_int) -> c_int;
+    fn _exit(status: c_int) -> !;
+}
+
+const WNOHANG: c_int = 1;
+const SIGKILL: c_int = 9;
+
+// ======================================================================
+// UTIL
+
+fn generate_original(original_count: usize, shard_bytes: usize, seed: u8) -> Vec<Vec<u8>> {
+    let mut rng = ChaCha8Rng::from_seed([seed; 32]);
+    let mut original = vec![vec![0u8; shard_bytes]; original_count];
+    for original in &mut original {
+        rng.fill::<[u8]>(original);
+    }
+    original
+}
+
+// Returns `true` if recovery shards generated with `engine` match `LOW_2_3`.
+fn encode_matches<E: Engine>(engine: E) -> bool {
+    let original = generate_original(2, 1024, 123);
+    let recovery = reed_solomon_simd::encode_with(&engine, 2, 3, &original).unwrap();
+
+    let mut sha = Sha256::new();
+    for shard in &recovery {
+        sha.update(shard);
+    }
+    sha.finalize()[..] == hex::decode(LOW_2_3).unwrap()
+}
+
+// Runs `f` in a forked child process, returning `true` if it returned `true`.
+// Child which doesn't exit within `CHILD_TIMEOUT` is killed.
+fn in_child(f: impl FnOnce() -> bool) -> bool {
+    let pid = unsafe { fork() };
+    assert!(pid >= 0, "fork failed");
+
+    if pid == 0 {
+        let ok = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_or(false);
+        unsafe { _exit(if ok { 0 } else { 1 }) }
+    }
+
+    let start = Instant::now();
+    let mut status = 0;
+    loop {
+        let ret = unsafe { waitpid(pid, &mut status, WNOHANG) };
+        assert!(ret >= 0, "waitpid failed");
+        if ret == pid {
+            // Exited normally with status 0.
+            return status == 0;
+        }
+        if start.elapsed() > CHILD_TIMEOUT {
+            unsafe { kill(pid, SIGKILL) };
+            unsafe { waitpid(pid, &mut status, 0) };
+            panic!("child didn't exit within {:?}", CHILD_TIMEOUT);
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[test]
+fn fork_after_and_during_initialization() {
+    // DURING - fork while another thread initializes `Mul16`,
+    // which `Naive` doesn't use.
+
+    Naive::new();
+    assert!(!tables::is_initialized(Table::Mul16));
+
+    let initializer = thread::spawn(tables::initialize_mul16);
+    assert!(in_child(|| encode_matches(Naive::new())));
+    initializer.join().unwrap();
+
+    // AFTER - fork after `warmup`.
+
+    reed_solomon_simd::warmup();
+
+    for _ in 0..4 {
+        assert!(in_child(|| encode_matches(DefaultEngine::new())));
+    }
+    assert!(encode_matches(DefaultEngine::new()));
+}
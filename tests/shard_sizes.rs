@@ -0,0 +1,206 @@
+//! Roundtrips over shard sizes 2 ..= 256 (in steps of 2)
+//! with every engine available on current CPU.
+//!
+//! Engines handle data in 64-byte blocks, so shard sizes which
+//! aren't a multiple of 64 must be rejected with [`Error::InvalidShardSize`]
+//! and all other sizes must roundtrip with byte-exact recovery
+//! which is identical between engines.
+
+use std::collections::HashMap;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use reed_solomon_simd::{
+    engine::{self, Engine, EngineKind, Naive, NoSimd},
+    rate::{DefaultRateDecoder, DefaultRateEncoder, RateDecoder, RateEncoder},
+    Error, ReedSolomonDecoder, ReedSolomonEncoder,
+};
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use reed_solomon_simd::engine::{Avx2, Ssse3};
+
+#[cfg(target_arch = "aarch64")]
+use reed_solomon_simd::engine::Neon;
+
+// ======================================================================
+// CONST
+
+const SHARD_BYTES: std::ops::RangeInclusive<usize> = 2..=256;
+
+// (original_count, recovery_count), high rate and low rate.
+const SHARD_COUNTS: [(usize, usize); 2] = [(5, 3), (3, 5)];
+
+// ======================================================================
+// UTIL
+
+fn generate_shards(shard_count: usize, shard_bytes: usize, seed: u8) -> Vec<Vec<u8>> {
+    let mut rng = ChaCha8Rng::from_seed([seed; 32]);
+    let mut shards = vec![vec![0u8; shard_bytes]; shard_count];
+    for shard in &mut shards {
+        rng.fill::<[u8]>(shard);
+    }
+    shards
+}
+
+// Recovery shards for each valid shard size and shard count,
+// encoded with `Naive` engine.
+fn expected_recovery() -> HashMap<(usize, usize), Vec<Vec<u8>>> {
+    let mut expected = HashMap::new();
+    for shard_bytes in SHARD_BYTES.filter(|shard_bytes| shard_bytes % 64 == 0) {
+        for (original_count, recovery_count) in SHARD_COUNTS {
+            let original = generate_shards(original_count, shard_bytes, shard_bytes as u8);
+            let recovery = reed_solomon_simd::encode_with(
+                &Naive::new(),
+                original_count,
+                recovery_count,
+                &original,
+            )
+            .unwrap();
+            expected.insert((shard_bytes, original_count), recovery);
+        }
+    }
+    expected
+}
+
+// Number of original shards lost, which are the first ones.
+fn lost_count(original_count: usize, recovery_count: usize) -> usize {
+    std::cmp::min(original_count, recovery_count)
+}
+
+// Encodes and decodes each shard size with given engine.
+fn roundtrip_all_sizes<E: Engine + Clone>(
+    engine: E,
+    expected: &HashMap<(usize, usize), Vec<Vec<u8>>>,
+) {
+    for shard_bytes in SHARD_BYTES.step_by(2) {
+        for (original_count, recovery_count) in SHARD_COUNTS {
+            let encoder = DefaultRateEncoder::new(
+                original_count,
+                recovery_count,
+                shard_bytes,
+                engine.clone(),
+                None,
+            );
+            let decoder = DefaultRateDecoder::new(
+                original_count,
+                recovery_count,
+                shard_bytes,
+                engine.clone(),
+                None,
+            );
+
+            if shard_bytes % 64 != 0 {
+                assert_eq!(encoder.err(), Some(Error::InvalidShardSize { shard_bytes }));
+                assert_eq!(decoder.err(), Some(Error::InvalidShardSize { shard_bytes }));
+                continue;
+            }
+
+            let (mut encoder, mut decoder) = (encoder.unwrap(), decoder.unwrap());
+            let original = generate_shards(original_count, shard_bytes, shard_bytes as u8);
+
+            for original in &original {
+                encoder.add_original_shard(original).unwrap();
+            }
+            let result = encoder.encode().unwrap();
+            let recovery: Vec<_> = result.recovery_iter().collect();
+            assert_eq!(
+                recovery,
+                expected[&(shard_bytes, original_count)],
+                "shard_bytes = {shard_bytes}, original_count = {original_count}"
+            );
+
+            let lost = lost_count(original_count, recovery_count);
+            for index in lost..original_count {
+                decoder.add_original_shard(index, &original[index]).unwrap();
+            }
+            for (index, recovery) in recovery.iter().enumerate().take(lost) {
+                decoder.add_recovery_shard(index, recovery).unwrap();
+            }
+
+            let result = decoder.decode().unwrap();
+            for (index, original) in original.iter().enumerate().take(lost) {
+                assert_eq!(
+                    result.restored_original(index).unwrap(),
+                    original,
+                    "shard_bytes = {shard_bytes}, original_count = {original_count}"
+                );
+            }
+        }
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[test]
+fn all_engines() {
+    let expected = expected_recovery();
+
+    for kind in engine::available_engines() {
+        match kind {
+            EngineKind::Naive => roundtrip_all_sizes(Naive::new(), &expected),
+            EngineKind::NoSimd => roundtrip_all_sizes(NoSimd::new(), &expected),
+
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Ssse3 => roundtrip_all_sizes(Ssse3::new(), &expected),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Avx2 => roundtrip_all_sizes(Avx2::new(), &expected),
+
+            #[cfg(target_arch = "aarch64")]
+            EngineKind::Neon => roundtrip_all_sizes(Neon::new(), &expected),
+
+            // Not available on this architecture.
+            #[allow(unreachable_patterns)]
+            _ => {}
+        }
+    }
+}
+
+// Same sweep with `ReedSolomonEncoder` and `ReedSolomonDecoder`,
+// re-using them for each shard size.
+#[test]
+fn reed_solomon_reset() {
+    let expected = expected_recovery();
+
+    for (original_count, recovery_count) in SHARD_COUNTS {
+        let mut encoder = ReedSolomonEncoder::new(original_count, recovery_count, 64).unwrap();
+        let mut decoder = ReedSolomonDecoder::new(original_count, recovery_count, 64).unwrap();
+
+        for shard_bytes in SHARD_BYTES.step_by(2) {
+            let encoder_reset = encoder.reset(original_count, recovery_count, shard_bytes);
+            let decoder_reset = decoder.reset(original_count, recovery_count, shard_bytes);
+
+            if shard_bytes % 64 != 0 {
+                assert_eq!(encoder_reset, Err(Error::InvalidShardSize { shard_bytes }));
+                assert_eq!(decoder_reset, Err(Error::InvalidShardSize { shard_bytes }));
+                continue;
+            }
+
+            encoder_reset.unwrap();
+            decoder_reset.unwrap();
+            let original = generate_shards(original_count, shard_bytes, shard_bytes as u8);
+
+            let recovery: Vec<_> = encoder
+                .encode_ref(&original)
+                .unwrap()
+                .recovery_iter()
+                .map(|shard| shard.to_vec())
+                .collect();
+            assert_eq!(recovery, expected[&(shard_bytes, original_count)]);
+
+            let lost = lost_count(original_count, recovery_count);
+            let original_received: Vec<_> = (lost..original_count)
+                .map(|index| (index, &original[index]))
+                .collect();
+            let recovery_received: Vec<_> = recovery.iter().take(lost).enumerate().collect();
+
+            let result = decoder
+                .decode_ref(&original_received, &recovery_received)
+                .unwrap();
+            for (index, original) in original.iter().enumerate().take(lost) {
+                assert_eq!(result.restored_original(index).unwrap(), original);
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Roundtrips with a single original or recovery shard
+//! with every engine available on current CPU.
+//!
+//! These have transform sizes 1 and 2, so IFFT / FFT have
+//! no two-layer steps at all. Every combination of lost shards
+//! which still leaves `original_count` shards must roundtrip,
+//! and recovery shards must be copies of the original shard (`1:n`)
+//! or `xor` of all original shards (`n:1`).
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use reed_solomon_simd::{
+    engine::{self, Engine, EngineKind, Naive, NoSimd},
+    rate::{DefaultRateDecoder, DefaultRateEncoder, RateDecoder, RateEncoder},
+    ReedSolomonDecoder, ReedSolomonEncoder,
+};
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use reed_solomon_simd::engine::{Avx2, Ssse3};
+
+#[cfg(target_arch = "aarch64")]
+use reed_solomon_simd::engine::Neon;
+
+// ======================================================================
+// CONST
+
+const SHARD_BYTES: usize = 128;
+
+// (original_count, recovery_count)
+const SHARD_COUNTS: [(usize, usize); 5] = [(1, 1), (1, 2), (2, 1), (1, 3), (3, 1)];
+
+// ======================================================================
+// UTIL
+
+fn generate_shards(shard_count: usize, shard_bytes: usize, seed: u8) -> Vec<Vec<u8>> {
+    let mut rng = ChaCha8Rng::from_seed([seed; 32]);
+    let mut shards = vec![vec![0u8; shard_bytes]; shard_count];
+    for shard in &mut shards {
+        rng.fill::<[u8]>(shard);
+    }
+    shards
+}
+
+// Expected recovery shards, i.e. copies of the only original shard
+// or a single parity shard.
+fn expected_recovery(original: &[Vec<u8>], recovery_count: usize) -> Vec<Vec<u8>> {
+    if original.len() == 1 {
+        vec![original[0].clone(); recovery_count]
+    } else {
+        assert_eq!(recovery_count, 1);
+        let mut parity = vec![0u8; SHARD_BYTES];
+        for original in original {
+            for (p, x) in parity.iter_mut().zip(original) {
+                *p ^= x;
+            }
+        }
+        vec![parity]
+    }
+}
+
+// Each subset of shard indexes `0 .. original_count + recovery_count`
+// which has exactly `original_count` shards, as bitmasks.
+fn received_sets(original_count: usize, recovery_count: usize) -> Vec<u32> {
+    let total = original_count + recovery_count;
+    (0..1u32 << total)
+        .filter(|set| set.count_ones() as usize == original_count)
+        .collect()
+}
+
+fn roundtrip_all<E: Engine + Clone>(engine: E) {
+    for (original_count, recovery_count) in SHARD_COUNTS {
+        let counts = format!("{original_count}:{recovery_count}");
+        let original = generate_shards(original_count, SHARD_BYTES, original_count as u8);
+
+        let mut encoder = DefaultRateEncoder::new(
+            original_count,
+            recovery_count,
+            SHARD_BYTES,
+            engine.clone(),
+            None,
+        )
+        .unwrap();
+        for original in &original {
+            encoder.add_original_shard(original).unwrap();
+        }
+        let recovery: Vec<_> = encoder
+            .encode()
+            .unwrap()
+            .recovery_iter()
+            .map(|shard| shard.to_vec())
+            .collect();
+        assert_eq!(
+            recovery,
+            expected_recovery(&original, recovery_count),
+            "{counts}"
+        );
+
+        let mut decoder = DefaultRateDecoder::new(
+            original_count,
+            recovery_count,
+            SHARD_BYTES,
+            engine.clone(),
+            None,
+        )
+        .unwrap();
+
+        for set in received_sets(original_count, recovery_count) {
+            let received = |index: usize| set & (1 << index) != 0;
+
+            for (index, original) in original.iter().enumerate() {
+                if received(index) {
+                    decoder.add_original_shard(index, original).unwrap();
+                }
+            }
+            for (index, recovery) in recovery.iter().enumerate() {
+                if received(original_count + index) {
+                    decoder.add_recovery_shard(index, recovery).unwrap();
+                }
+            }
+
+            let result = decoder.decode().unwrap();
+            for (index, original) in original.iter().enumerate() {
+                assert_eq!(
+                    result.restored_original(index),
+                    if received(index) {
+                        None
+                    } else {
+                        Some(&original[..])
+                    },
+                    "{counts}, received {set:b}"
+                );
+            }
+        }
+    }
+}
+
+// ======================================================================
+// TESTS
+
+#[test]
+fn all_engines() {
+    for kind in engine::available_engines() {
+        match kind {
+            EngineKind::Naive => roundtrip_all(Naive::new()),
+            EngineKind::NoSimd => roundtrip_all(NoSimd::new()),
+
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Ssse3 => roundtrip_all(Ssse3::new()),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            EngineKind::Avx2 => roundtrip_all(Avx2::new()),
+
+            #[cfg(target_arch = "aarch64")]
+            EngineKind::Neon => roundtrip_all(Neon::new()),
+
+            // Not available on this architecture.
+            #[allow(unreachable_patterns)]
+            _ => {}
+        }
+    }
+}
+
+// Mirroring, i.e. `1:1` with `ReedSolomonEncoder` and `ReedSolomonDecoder`
+// re-used from a larger configuration.
+#[test]
+fn reed_solomon_mirror() {
+    let original = generate_shards(1, SHARD_BYTES, 11);
+
+    let mut encoder = ReedSolomonEncoder::new(100, 30, SHARD_BYTES).unwrap();
+    let mut decoder = ReedSolomonDecoder::new(100, 30, SHARD_BYTES).unwrap();
+    encoder.reset(1, 1, SHARD_BYTES).unwrap();
+    decoder.reset(1, 1, SHARD_BYTES).unwrap();
+
+    let recovery = encoder
+        .encode_ref(&original)
+        .unwrap()
+        .recovery(0)
+        .unwrap()
+        .to_vec();
+    assert_eq!(recovery, original[0]);
+
+    let result = decoder
+        .decode_ref(&[] as &[(usize, &[u8])], &[(0, &recovery)])
+        .unwrap();
+    assert_eq!(result.restored_original(0).unwrap(), original[0]);
+
+    assert_eq!(
+        reed_solomon_simd::decode(1, 1, [(0, ""); 0], [(0, &recovery)]).unwrap()[&0],
+        original[0]
+    );
+}
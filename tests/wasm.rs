@@ -0,0 +1,77 @@
+//! `WasmEncoder` and `WasmDecoder` on `wasm32`, run with e.g.
+//! `wasm-pack test --node --features wasm-bindgen -- --test wasm`.
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm-bindgen"))]
+
+use js_sys::{Array, Uint8Array};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use reed_solomon_simd::wasm::{WasmDecoder, WasmEncoder};
+
+// ======================================================================
+// UTIL
+
+fn generate_original(original_count: usize, shard_bytes: usize, seed: u8) -> Vec<Vec<u8>> {
+    let mut rng = ChaCha8Rng::from_seed([seed; 32]);
+    let mut original = vec![vec![0u8; shard_bytes]; original_count];
+    for original in &mut original {
+        rng.fill::<[u8]>(original);
+    }
+    original
+}
+
+fn to_vecs(array: &Array) -> Vec<Vec<u8>> {
+    array
+        .iter()
+        .map(|shard| shard.dyn_into::<Uint8Array>().unwrap().to_vec())
+        .collect()
+}
+
+// ======================================================================
+// TESTS
+
+#[wasm_bindgen_test]
+fn roundtrip() {
+    let original = generate_original(3, 64, 123);
+
+    let mut encoder = WasmEncoder::new(3, 2, 64).unwrap();
+    for original in &original {
+        encoder.add_original_shard(original).unwrap();
+    }
+    let recovery = to_vecs(&encoder.encode().unwrap());
+    assert_eq!(
+        recovery,
+        reed_solomon_simd::encode(3, 2, &original).unwrap()
+    );
+
+    let mut decoder = WasmDecoder::new(3, 2, 64).unwrap();
+    decoder.add_original_shard(1, &original[1]).unwrap();
+    decoder.add_recovery_shard(0, &recovery[0]).unwrap();
+    decoder.add_recovery_shard(1, &recovery[1]).unwrap();
+
+    let restored = decoder.decode().unwrap();
+    assert_eq!(restored.size(), 2);
+    for index in [0, 2] {
+        let shard: Uint8Array = restored.get(&index.into()).dyn_into().unwrap();
+        assert_eq!(shard.to_vec(), original[index as usize]);
+    }
+}
+
+#[wasm_bindgen_test]
+fn errors() {
+    assert!(WasmEncoder::new(0, 1, 64).is_err());
+    assert!(WasmDecoder::new(1, 1, 63).is_err());
+
+    let mut encoder = WasmEncoder::new(2, 1, 64).unwrap();
+    assert!(encoder.add_original_shard(&[0; 32]).is_err());
+    assert!(encoder.encode().is_err());
+
+    let mut decoder = WasmDecoder::new(2, 1, 64).unwrap();
+    decoder.add_original_shard(0, &[0; 64]).unwrap();
+    assert!(decoder.add_original_shard(0, &[0; 64]).is_err());
+    assert!(decoder.add_recovery_shard(1, &[0; 64]).is_err());
+    assert!(decoder.decode().is_err());
+}